@@ -1,6 +1,7 @@
 info = Info
 toggle_side_panel = Toggle the side panel visibility
 launch_new_process = Launch in a new process
+failed_to_launch = Failed to launch
 
 # SidePanel
 ## Device Info
@@ -8,6 +9,10 @@ device_info = Device Info
 device_name = Device Name
 pci_bus = PCI (domain:bus:dev.func)
 did_rid = DeviceID:RevID
+numa_node = NUMA Node
+local_cpus = Local CPUs
+iommu_group = IOMMU Group
+devices = devices
 opengl_driver_ver = OpenGL Driver Version
 apu = APU
 dgpu = dGPU
@@ -46,6 +51,7 @@ kib = KiB
 mib = MiB
 mib_s = MiB/s
 mhz = MHz
+ms = ms
 mw = mW
 w = W
 mv = mV
@@ -59,9 +65,97 @@ power_cap = Power Cap.
 power_cap_default = Power Cap. (Default)
 
 pcie_link_speed = PCIe Link Speed
+
+# VCN state from debugfs (amdgpu_pm_info, amdgpu_fence_info)
+vcn_power_gated = Power Gated
+vcn_idle = Idle
+vcn_busy = Busy
+vcn_busy_rings = Busy Rings
+smu_msg_failures = SMU Message Failures
+fw_errors = Firmware Errors
+last_error = Last Error
+
+# amdgpu_pm_info (debugfs)
+clock_gating = Clock Gating
+on = On
+off = Off
 # Dynamic Power Management (DPM)
 dpm = DPM
+dpm_clocks = DPM Clocks
+kernel_messages = Kernel Messages
+queue_priority = Queue Priority
+activity_sources = Activity Sources
+source = Source
+ring = Ring
+priority = Priority
+submissions = Submissions (10s)
+no_submissions = No submissions
+crash_dumps = Crash Dumps
+seconds_ago = { $secs }s ago
+stale = Not updated for { $secs }s
+session_stats = Session min/avg/max: { $stats }
+save = Save
+save_gzip = Save (gzip)
+crash_dump_saved = Saved: { $path }
+crash_dump_dir = Saved to { $path } (reading a dump requires root)
+failed_to_save = Failed to save
+sqtt_capture = SQTT Capture (RGP)
+sqtt_trigger = Trigger a capture
+sqtt_trigger_file = Trigger file: { $path } (RADV captures the next frame)
+sqtt_triggered = Triggered, the last { $samples } samples are saved to { $path }
+failed_to_trigger = Failed to trigger
+deep_inspect = Deep Inspect (umr)
+umr_path = { $path } (requires root)
+umr_hang_detected = Ring timeout of { $ring } at { $sec }s
+umr_inspect = Inspect { $ring }
+umr_running = Running umr...
+umr_waves = Waves
+umr_failed = umr failed
+xgmi = XGMI
+xgmi_hive_id = Hive ID
+xgmi_physical_id = Physical ID
+xgmi_link = Link
+xgmi_errors = Errors
+xgmi_peer = Peer
+xgmi_hops = Hops
+xgmi_links = Links
+anomaly = Anomaly
+resident = Resident
+purgeable = Purgeable
+shared = Shared
+buffer_objects = Buffer Objects
+bo_list_note = Click to list the buffer objects of the process (requires root)
+refresh = Refresh
+requires_root = requires root
+bo_breakdown = Buffer Objects (all processes)
+bo_system = System (evicted, may be swapped)
+bo_pinned = Pinned
+bo_shared = Shared (dma-buf)
+bo_duplicated = Imported dma-buf (not counted)
+bo_kernel_vram = VRAM not in BOs (kernel/driver)
+bo_kernel_gtt = GTT not in BOs (kernel/driver)
+media_sessions = Media Sessions
+leak_suspects = Leak Suspects
+no_leak_suspects = No process has grown monotonically faster than the threshold
+growth = Growth
+duration = Duration
+mib_per_min = MiB/min
+sessions = Sessions
+resolution = Resolution
+frame_times = Frame Times
+frame_time = Frame time
+median = Median
+sclk = SCLK
+stutter = Stutter
+vr = VR
+vr_compositor = VR compositor
+vr_apps = Other processes
+vr_budget = { $hz } Hz budget ({ $ms } ms)
+vr_throttles = Throttle events in the range: { $count }
+vr_over_budget = Frames over the { $hz } Hz budget: { $count } / { $total } ({ $pct }%)
+vr_frame_source_hint = The frame times and the 90/120 Hz budgets require "--frame-source".
 max = Max
+min = Min
 gpu = GPU
 system = System
 
@@ -71,7 +165,14 @@ ip_discovery_table = IP Discovery table
 gpu_die = Die
 ip_hw = IP HW
 version = version
-num = num
+instance = instance
+harvest = harvest
+harvested = harvested
+
+capabilities = Capabilities
+
+p2p_access = P2P Access
+p2p_access_desc = row: from, column: to the VRAM of
 
 video_caps_info = Video Caps Info
 codec = Codec
@@ -123,6 +224,9 @@ gtt = GTT
 
 fdinfo = fdinfo
 fdinfo_plot = fdinfo plot
+fdinfo_plot_engines = Engines
+fdinfo_plot_stacked = GFX by process (stacked)
+fdinfo_plot_top = GFX of the top processes
 cpu = CPU
 gfx = GFX
 compute = Compute
@@ -132,12 +236,62 @@ vcn = VCN
 # Process Name
 name = Name
 pid = PID
+smoothing = Smoothing
+copy_csv = Copy as CSV
+plot_options = Plot Options
+pcie_bw_log_scale = PCIe Bandwidth: Log scale
+vram_log_scale = VRAM/GTT (Timeline): Log scale
+peak_hold = Peak hold
+peak_hold_desc = The hold time of the peak markers of the VRAM bars and the power/temperature plots (0: off)
+temp_unit = Temperature
+power_unit = Power
+language = Language
+system_language = System default
+
+accessibility = Accessibility
+high_contrast = High contrast
+keyboard_shortcuts = Keyboard shortcuts
+sort_by = Sort by
+reverse_sort = Reverse the sort order
+move_focus = Move the focus to the next/previous widget
+save_csv = Save as CSV
+csv_saved = Saved: { $path }
+tuning_profiles = Tuning Profiles
+no_tuning_profiles = No profiles in ~/.config/amdgpu_top/profiles.conf
+apply_tuning_profile = Apply Tuning Profile
+apply = Apply
+cancel = Cancel
+revert = Revert
+revert_on_exit = Revert on exit
+tuning_profile_applied = Applied: { $name }
+failed_to_apply = Failed to apply
+failed_to_revert = Failed to revert
+auth_may_be_required = Authentication may be required to change the settings.
+read_only_mode = Read-only mode
+no_writable_controls = No writable controls (requires root or amdgpu_top_helper)
+profile_not_writable = Some settings of this profile are not writable on this device
+fdinfo_raw = Raw values (engine time delta per interval, memory in bytes)
+fdinfo_shared = Include shared buffers
+fdinfo_shared_note = The buffers shared with the other processes (dma-buf), e.g. the windows shared with the compositor, are counted in each process
+fdinfo_group_by = Group by
+display_server_note = Display server/compositor, includes rendering for its clients
 
 sensor = Sensors
+apu_power_limits = APU Power Limits
+power_limit_type = Limit
+power_limit_current = Current
+power_limit_cap = Cap.
+
+timeline = Timeline
+timeline_all = All
+timeline_resolution = Resolution: { $resolution }s, { $points } points
+timeline_zoomed = Zoomed, select a span to follow the latest sample again.
 
 pcie_bw = PCIe Bandwidth
+pcie_bw_unsupported = Unsupported ({ $reason })
 sent = Sent
 received = Received
+total = Total
 
 gpu_metrics = GPU Metrics
 avg = Avg.
@@ -155,4 +309,4 @@ socket_power = Socket Power
 avg_activity = Average Activity
 throttle_status = Throttle Status
 
-failed_to_set_up_gui = Failed to set up a graphics context (OpenGL).
+failed_to_set_up_gui = Failed to set up a graphics context (OpenGL or wgpu).
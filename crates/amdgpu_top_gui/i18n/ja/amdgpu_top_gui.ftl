@@ -1,4 +1,7 @@
 toggle_side_panel = サイドパネルの表示を切り替える
 launch_new_process = 新しいプロセスで起動する
+failed_to_launch = 起動に失敗しました
+high_contrast = ハイコントラスト
+keyboard_shortcuts = キーボードショートカット
 
-failed_to_set_up_gui = グラフィクスコンテキスト (OpenGL) のセットアップに失敗しました
+failed_to_set_up_gui = グラフィクスコンテキスト (OpenGL または wgpu) のセットアップに失敗しました
@@ -1,9 +1,10 @@
 use std::sync::{Arc, Mutex};
 use std::ops::RangeInclusive;
 use std::path::PathBuf;
+use std::time::Instant;
 use eframe::egui;
 use egui::{RichText, util::History};
-use egui_plot::{Corner, Legend, Line, Plot, PlotPoint, PlotPoints};
+use egui_plot::{Corner, HLine, Legend, Line, LineStyle, MarkerShape, Plot, PlotBounds, PlotPoint, PlotPoints, Points, Polygon, VLine};
 use crate::{BASE, MEDIUM, HISTORY_LENGTH};
 
 use libamdgpu_top::AMDGPU::{
@@ -11,21 +12,92 @@ use libamdgpu_top::AMDGPU::{
     GPU_INFO,
     IpDieEntry,
 };
-use libamdgpu_top::stat::{self, gpu_metrics_util::*, FdInfoSortType, PerfCounter};
+use libamdgpu_top::stat::{self, gpu_metrics_util::*, FdInfoGroupBy, FdInfoSortType, FdInfoUsage, PcieBwSource, PeakHold, PerfCounter, ProcBoList, Smoothing, VcnPowerState};
+use libamdgpu_top::{crash_dump_dir, is_harvested, Capability, LeakSuspect, NumberFormat, P2pMatrix, SessionOptions, TempUnit, TuningBackup, TuningProfile};
+use libamdgpu_top::priv_helper::ControlCapabilities;
+use libamdgpu_top::sqtt::{SqttTrigger, WINDOW_LEN};
+use libamdgpu_top::ring_trace::QueuePriority;
+use libamdgpu_top::umr::{parse_ring_timeout, Umr, UmrReport, DEFAULT_RING};
 
-use crate::{AppDeviceInfo, CentralData, GpuMetrics, util::*, fl};
+use crate::{AppDeviceInfo, CentralData, GpuMetrics, LANGUAGE_LOADER, util::*, fl};
 
 const PLOT_HEIGHT: f32 = 32.0;
 const PLOT_WIDTH: f32 = 240.0;
+// All plots share the time axis (elapsed seconds), link x-axis and hover cursor
+const PLOT_LINK_ID: &str = "time axis";
+const PEAK_HOLD_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 190, 0);
+// The same keys as the TUI, with Alt
+pub const FDINFO_SORT_SHORTCUTS: &[(egui::KeyboardShortcut, FdInfoSortType)] = &[
+    (alt(egui::Key::P), FdInfoSortType::PID),
+    (alt(egui::Key::V), FdInfoSortType::VRAM),
+    (alt(egui::Key::T), FdInfoSortType::GTT),
+    (alt(egui::Key::C), FdInfoSortType::CPU),
+    (alt(egui::Key::G), FdInfoSortType::GFX),
+    (alt(egui::Key::O), FdInfoSortType::Compute),
+    (alt(egui::Key::D), FdInfoSortType::DMA),
+    (alt(egui::Key::M), FdInfoSortType::MediaEngine),
+];
+pub const REVERSE_SORT_SHORTCUT: egui::KeyboardShortcut = alt(egui::Key::R);
+pub const SIDE_PANEL_SHORTCUT: egui::KeyboardShortcut = alt(egui::Key::I);
+pub const SQTT_TRIGGER_SHORTCUT: egui::KeyboardShortcut = alt(egui::Key::S);
+
+const fn alt(key: egui::Key) -> egui::KeyboardShortcut {
+    egui::KeyboardShortcut::new(egui::Modifiers::ALT, key)
+}
 
 pub struct MyApp {
     pub command_path: PathBuf,
     pub app_device_info: AppDeviceInfo,
     pub device_list: Vec<DeviceListMenu>,
+    /// With multiple devices
+    pub p2p_matrix: Option<P2pMatrix>,
     pub has_vcn_unified: bool,
-    pub support_pcie_bw: bool,
+    /// `Err` with the reason if not supported
+    pub pcie_bw_source: Result<PcieBwSource, &'static str>,
     pub fdinfo_sort: FdInfoSortType,
     pub reverse_sort: bool,
+    pub fdinfo_raw: bool,
+    /// Include the shared buffers in the memory usage of each process
+    pub fdinfo_shared: bool,
+    pub fdinfo_group: FdInfoGroupBy,
+    pub fdinfo_plot: FdInfoPlot,
+    /// N of [`FdInfoPlot::TopProcesses`]
+    pub top_procs: usize,
+    /// The DRI instance for debugfs
+    pub instance: Option<u32>,
+    /// The buffer objects of the selected process (pid, list or error)
+    pub bo_list: Option<(i32, Result<ProcBoList, String>)>,
+    pub smoothing: Smoothing,
+    pub arc_smoothing: Arc<Mutex<Smoothing>>,
+    pub plot_options: PlotOptions,
+    pub peaks: PeakHolds,
+    pub timeline_view: TimelineView,
+    /// `--baseline`
+    pub baseline: Option<crate::Baseline>,
+    pub number_format: NumberFormat,
+    pub control_caps: ControlCapabilities,
+    pub capabilities: Vec<Capability>,
+    pub tuning_profiles: Vec<TuningProfile>,
+    pub pending_profile: Option<TuningProfile>,
+    pub tuning_backup: Option<TuningBackup>,
+    pub tuning_status: Option<String>,
+    pub crash_dump_status: Option<String>,
+    /// `--sqtt-trigger`, shared with the sampling thread
+    pub sqtt: Option<Arc<Mutex<SqttTrigger>>>,
+    pub sqtt_status: Option<String>,
+    /// The error of "Launch in a new process"
+    pub launch_status: Option<String>,
+    /// `umr` in `PATH`, for the deep inspect panel
+    pub umr: Option<Umr>,
+    pub umr_report: Arc<Mutex<Option<UmrReport>>>,
+    /// (sec, ring) of the last ring timeout in the kernel messages
+    pub umr_hang: Option<(f64, String)>,
+    pub umr_inspecting: bool,
+    pub revert_on_exit: bool,
+    pub lang: Option<String>, // None: the desktop languages
+    pub high_contrast: bool,
+    pub session: SessionOptions,
+    pub start: std::time::Instant,
     pub buf_data: CentralData,
     pub arc_data: Arc<Mutex<CentralData>>,
     pub show_sidepanel: bool,
@@ -46,7 +118,7 @@ trait GuiInfo {
     fn memory_info(&self, ui: &mut egui::Ui);
     fn cache_info(&self, ui: &mut egui::Ui);
     fn power_cap_info(&self, ui: &mut egui::Ui);
-    fn temp_info(&self, ui: &mut egui::Ui);
+    fn temp_info(&self, ui: &mut egui::Ui, temp_unit: TempUnit);
     fn fan_info(&self, ui: &mut egui::Ui);
     fn link_info(&self, ui: &mut egui::Ui);
 }
@@ -61,6 +133,25 @@ impl GuiInfo for AppDeviceInfo {
             (&fl!("did_rid"), &dev_id),
         ]);
 
+        if let Some(numa_node) = self.placement.numa_node {
+            grid(ui, &[(&fl!("numa_node"), &numa_node.to_string())]);
+        }
+
+        if let Some(cpulist) = &self.placement.local_cpulist {
+            grid(ui, &[(&fl!("local_cpus"), cpulist)]);
+        }
+
+        if let Some(group) = &self.placement.iommu_group {
+            let devices: Vec<String> = group.devices.iter().map(|dev| {
+                format!("{} ({})", dev.pci, dev.driver.as_deref().unwrap_or("-"))
+            }).collect();
+
+            ui.label(fl!("iommu_group"));
+            ui.label(format!("{} ({} {})", group.id, group.devices.len(), fl!("devices")))
+                .on_hover_text(devices.join("\n"));
+            ui.end_row();
+        }
+
         if let Some(gl) = gl_vendor_info {
             ui.label(&fl!("opengl_driver_ver"));
             ui.label(gl);
@@ -169,7 +260,7 @@ impl GuiInfo for AppDeviceInfo {
         ui.end_row();
     }
 
-    fn temp_info(&self, ui: &mut egui::Ui) {
+    fn temp_info(&self, ui: &mut egui::Ui, temp_unit: TempUnit) {
         for temp in [
             &self.edge_temp,
             &self.junction_temp,
@@ -179,12 +270,12 @@ impl GuiInfo for AppDeviceInfo {
             let name = temp.type_.to_string();
             if let Some(crit) = temp.critical {
                 ui.label(format!("{name} Temp. (Critical)"));
-                ui.label(format!("{crit:4} C"));
+                ui.label(format!("{:4} {temp_unit}", temp_unit.convert_int(crit)));
                 ui.end_row();
             }
             if let Some(e) = temp.emergency {
                 ui.label(format!("{name} Temp. (Emergency)"));
-                ui.label(format!("{e:4} C"));
+                ui.label(format!("{:4} {temp_unit}", temp_unit.convert_int(e)));
                 ui.end_row();
             }
         }
@@ -234,7 +325,7 @@ impl MyApp {
             self.app_device_info.memory_info(ui);
             self.app_device_info.cache_info(ui);
             self.app_device_info.power_cap_info(ui);
-            self.app_device_info.temp_info(ui);
+            self.app_device_info.temp_info(ui, self.plot_options.temp_unit);
             self.app_device_info.fan_info(ui);
             self.app_device_info.link_info(ui);
 
@@ -246,6 +337,24 @@ impl MyApp {
         });
     }
 
+    pub fn egui_capabilities(&self, ui: &mut egui::Ui) {
+        egui::Grid::new("capabilities").show(ui, |ui| {
+            for cap in &self.capabilities {
+                ui.label(cap.name).highlight();
+
+                let status = RichText::new(cap.status_str());
+
+                if cap.status.is_ok() {
+                    ui.label(status);
+                } else {
+                    ui.label(status.color(ui.visuals().weak_text_color()));
+                }
+
+                ui.end_row();
+            }
+        });
+    }
+
     pub fn egui_ip_discovery_table(&self, ui: &mut egui::Ui) {
         let gpu_die = fl!("gpu_die");
         for die in &self.app_device_info.ip_die_entries {
@@ -257,16 +366,53 @@ impl MyApp {
     pub fn egui_ip_discovery_table_per_die(ip_die_entry: &IpDieEntry, ui: &mut egui::Ui) {
         egui::Grid::new(format!("ip_discovery_table die{}", ip_die_entry.die_id)).show(ui, |ui| {
             ui.label(fl!("ip_hw")).highlight();
+            ui.label(fl!("instance")).highlight();
             ui.label(fl!("version")).highlight();
-            ui.label(fl!("num")).highlight();
+            ui.label(fl!("harvest")).highlight();
             ui.end_row();
 
             for ip_hw in &ip_die_entry.ip_hw_ids {
                 let hw_id = ip_hw.hw_id.to_string();
-                let Some(inst) = ip_hw.instances.first() else { continue };
-                ui.label(hw_id);
-                ui.label(format!("{}.{}.{}", inst.major, inst.minor, inst.revision));
-                ui.label(ip_hw.instances.len().to_string());
+
+                for (i, inst) in ip_hw.instances.iter().enumerate() {
+                    // the name only in the first row
+                    ui.label(if i == 0 { hw_id.as_str() } else { "" });
+                    ui.label(inst.num_instance.to_string());
+                    ui.label(format!("{}.{}.{}", inst.major, inst.minor, inst.revision));
+                    if is_harvested(inst) {
+                        ui.label(fl!("harvested")).on_hover_text(format!("{:#X}", inst.harvest));
+                    } else {
+                        ui.label("");
+                    }
+                    ui.end_row();
+                }
+            }
+        });
+    }
+
+    pub fn egui_p2p_matrix(&self, ui: &mut egui::Ui) {
+        let Some(matrix) = &self.p2p_matrix else { return };
+
+        ui.label(fl!("p2p_access_desc"));
+        egui::Grid::new("p2p_matrix").show(ui, |ui| {
+            ui.label("");
+            for pci in &matrix.devices {
+                ui.label(pci.to_string()).highlight();
+            }
+            ui.end_row();
+
+            for (pci, row) in matrix.devices.iter().zip(matrix.access.iter()) {
+                let label = ui.label(pci.to_string());
+                if *pci == self.app_device_info.pci_bus {
+                    label.highlight();
+                }
+
+                for access in row {
+                    match access {
+                        Some(access) => { ui.label(access.label()).on_hover_text(access.to_string()); },
+                        None => { ui.label(""); },
+                    }
+                }
                 ui.end_row();
             }
         });
@@ -338,15 +484,21 @@ impl MyApp {
         };
 
         egui::Grid::new(name).show(ui, |ui| {
-            for ((name, pos), history) in pc.index.iter().zip(history.iter()) {
-                let usage = pc.bits.get(*pos);
-                ui.label(name);
-                ui.label(format!("{usage:3}%"));
+            for (i, ((name, _), history)) in pc.index.iter().zip(history.iter()).enumerate() {
+                let usage = pc.get_usage(i);
+                let stats = self.buf_data.summary.stats.perf_counter(pc.pc_type, i);
+                // localized here, to follow the language switched at runtime
+                ui.label(LANGUAGE_LOADER.get(&name.replace(' ', "_").replace('/', "")));
+                let response = ui.label(format!("{usage:3}%"));
+
+                if let Some(stats) = stats_text(stats, "%", |v| v as f64) {
+                    response.on_hover_text(stats);
+                }
 
-                let points: PlotPoints = history.iter()
+                let points: Vec<[f64; 2]> = history.iter()
                     .map(|(i, val)| [i, val as f64]).collect();
-                let line = Line::new(points).fill(1.0);
-                Plot::new(name)
+                let line = Line::new(PlotPoints::new(points.clone())).fill(1.0);
+                let response = Plot::new(name)
                     .allow_drag(false)
                     .allow_zoom(false)
                     .allow_scroll(false)
@@ -355,9 +507,13 @@ impl MyApp {
                     .y_axis_formatter(empty_y_fmt)
                     .label_formatter(label_fmt)
                     .auto_bounds_x()
+                    .link_axis(PLOT_LINK_ID, true, false)
+                    .link_cursor(PLOT_LINK_ID, true, false)
                     .height(PLOT_HEIGHT)
                     .width(PLOT_WIDTH)
-                    .show(ui, |plot_ui| plot_ui.line(line));
+                    .show(ui, |plot_ui| plot_ui.line(line))
+                    .response;
+                plot_context_menu(response, name, &[(name.to_string(), points)]);
                 ui.end_row();
             }
         });
@@ -366,17 +522,70 @@ impl MyApp {
     pub fn egui_vram(&self, ui: &mut egui::Ui) {
         egui::Grid::new("VRAM").show(ui, |ui| {
             let mib = fl!("mib");
-            for (v, name) in [
+            for ((v, name), peak) in [
                 (&self.buf_data.vram_usage.0.vram, fl!("vram")),
                 (&self.buf_data.vram_usage.0.cpu_accessible_vram, fl!("cpu_visible_vram")),
                 (&self.buf_data.vram_usage.0.gtt, fl!("gtt")),
-            ] {
+            ].into_iter().zip(&self.peaks.vram) {
                 let progress = (v.heap_usage >> 20) as f32 / (v.total_heap_size >> 20) as f32;
-                let text = format!("{:5} / {:5} {mib}", v.heap_usage >> 20, v.total_heap_size >> 20);
+                let text = format!(
+                    "{:>6} / {:>6} {mib}",
+                    self.number_format.int(v.heap_usage >> 20),
+                    self.number_format.int(v.total_heap_size >> 20),
+                );
                 let bar = egui::ProgressBar::new(progress)
                     .text(RichText::new(&text).font(BASE));
-                ui.label(RichText::new(name).font(MEDIUM));
-                ui.add_sized([360.0, 16.0], bar);
+                ui.label(RichText::new(&name).font(MEDIUM));
+                let response = ui.add_sized([360.0, 16.0], bar);
+                response.widget_info(|| egui::WidgetInfo::labeled(
+                    egui::WidgetType::Other,
+                    format!("{name}: {text}"),
+                ));
+
+                if let Some(peak) = peak.get(self.plot_options.peak_hold(), Instant::now()) {
+                    let peak = peak as f32 / (v.total_heap_size >> 20) as f32;
+
+                    if peak > progress {
+                        let rect = response.rect;
+                        let x = rect.left() + rect.width() * peak.min(1.0);
+
+                        ui.painter().vline(x, rect.y_range(), egui::Stroke::new(2.0, PEAK_HOLD_COLOR));
+                    }
+                }
+
+                ui.end_row();
+            }
+        });
+
+        if let Some(bo_stat) = &self.buf_data.bo_stat {
+            ui.collapsing(fl!("bo_breakdown"), |ui| self.egui_bo_stat(ui, bo_stat));
+        }
+    }
+
+    /// The BOs of all processes, compared with the heap usage
+    fn egui_bo_stat(&self, ui: &mut egui::Ui, bo_stat: &stat::BoStat) {
+        let mib = fl!("mib");
+        let vram_usage = self.buf_data.vram_usage.0.vram.heap_usage;
+        let gtt_usage = self.buf_data.vram_usage.0.gtt.heap_usage;
+
+        egui::Grid::new("BO breakdown").show(ui, |ui| {
+            ui.label(fl!("buffer_objects"));
+            ui.label(format!("{:>6} {mib} ({} BOs)", self.number_format.int(bo_stat.total >> 20), bo_stat.bos));
+            ui.end_row();
+
+            for (label, size) in [
+                (fl!("vram"), bo_stat.vram),
+                (fl!("cpu_visible_vram"), bo_stat.vis_vram),
+                (fl!("gtt"), bo_stat.gtt),
+                (fl!("bo_system"), bo_stat.cpu),
+                (fl!("bo_pinned"), bo_stat.pinned),
+                (fl!("bo_shared"), bo_stat.shared),
+                (fl!("bo_duplicated"), bo_stat.duplicated),
+                (fl!("bo_kernel_vram"), vram_usage.saturating_sub(bo_stat.vram)),
+                (fl!("bo_kernel_gtt"), gtt_usage.saturating_sub(bo_stat.gtt)),
+            ] {
+                ui.label(label);
+                ui.label(format!("{:>6} {mib}", self.number_format.int(size >> 20)));
                 ui.end_row();
             }
         });
@@ -391,6 +600,30 @@ impl MyApp {
         self.fdinfo_sort = sort_type;
     }
 
+    pub fn fdinfo_sort_shortcuts(&mut self, ctx: &egui::Context) {
+        for (shortcut, sort_type) in FDINFO_SORT_SHORTCUTS {
+            if ctx.input_mut(|i| i.consume_shortcut(shortcut)) {
+                self.set_fdinfo_sort_type(sort_type.clone());
+            }
+        }
+
+        if ctx.input_mut(|i| i.consume_shortcut(&REVERSE_SORT_SHORTCUT)) {
+            self.reverse_sort ^= true;
+        }
+    }
+
+    fn sort_button(&mut self, ui: &mut egui::Ui, text: String, sort_type: FdInfoSortType) {
+        let mut res = ui.button(rt_base(text));
+
+        if let Some((shortcut, _)) = FDINFO_SORT_SHORTCUTS.iter().find(|(_, s)| *s == sort_type) {
+            res = res.on_hover_text(ui.ctx().format_shortcut(shortcut));
+        }
+
+        if res.clicked() {
+            self.set_fdinfo_sort_type(sort_type);
+        }
+    }
+
     pub fn egui_fdinfo_plot(&self, ui: &mut egui::Ui) {
         let label_fmt = |name: &str, val: &PlotPoint| {
             format!("{:.1}s : {name} {:.0}%", val.x, val.y)
@@ -410,7 +643,24 @@ impl MyApp {
             enc.push([i, usage_enc as f64]);
         }
 
-        Plot::new(fl!("fdinfo_plot"))
+        let series: Vec<PlotSeries> = if self.has_vcn_unified {
+            vec![
+                (fl!("gfx"), gfx),
+                (fl!("compute"), compute),
+                (fl!("dma"), dma),
+                (fl!("media"), enc),
+            ]
+        } else {
+            vec![
+                (fl!("gfx"), gfx),
+                (fl!("compute"), compute),
+                (fl!("dma"), dma),
+                (fl!("decode"), dec),
+                (fl!("encode"), enc),
+            ]
+        };
+
+        let response = Plot::new(fl!("fdinfo_plot"))
             .allow_drag(false)
             .allow_zoom(false)
             .allow_scroll(false)
@@ -419,252 +669,1426 @@ impl MyApp {
             .y_axis_formatter(empty_y_fmt)
             .label_formatter(label_fmt)
             .auto_bounds_x()
+            .link_axis(PLOT_LINK_ID, true, false)
+            .link_cursor(PLOT_LINK_ID, true, false)
             .height(ui.available_width() / 4.0)
             .width(ui.available_width() - 36.0)
             .legend(Legend::default().position(Corner::LeftTop))
             .show(ui, |plot_ui| {
-                for (usage, name) in [
-                    (gfx, fl!("gfx")),
-                    (compute, fl!("compute")),
-                    (dma, fl!("dma")),
-                ] {
-                    plot_ui.line(Line::new(PlotPoints::new(usage)).name(name));
+                for (name, usage) in &series {
+                    plot_ui.line(Line::new(PlotPoints::new(usage.clone())).name(name));
                 }
 
-                if self.has_vcn_unified {
-                    plot_ui.line(Line::new(PlotPoints::new(enc)).name(fl!("media")));
-                } else {
-                    plot_ui.line(Line::new(PlotPoints::new(dec)).name(fl!("decode")));
-                    plot_ui.line(Line::new(PlotPoints::new(enc)).name(fl!("encode")));
+                // mark the kernel messages, e.g. ring timeouts, next to the activity drop
+                for sec in self.kmsg_marks() {
+                    plot_ui.vline(VLine::new(sec).name(fl!("kernel_messages")));
                 }
-            });
-    }
+            })
+            .response;
 
-    pub fn egui_grid_fdinfo(&mut self, ui: &mut egui::Ui) {
-        collapsing_plot(ui, "fdinfo Plot", true, |ui| self.egui_fdinfo_plot(ui));
+        plot_context_menu(response, "fdinfo", &series);
+    }
 
-        egui::Grid::new("fdinfo").show(ui, |ui| {
-            ui.style_mut().override_font_id = Some(MEDIUM);
-            ui.label(rt_base(format!("{:^15}", fl!("name")))).highlight();
-            ui.label(rt_base(format!("{:^8}", fl!("pid")))).highlight();
-            if ui.button(rt_base(format!("{:^10}", fl!("vram")))).clicked() {
-                self.set_fdinfo_sort_type(FdInfoSortType::VRAM);
-            }
-            if ui.button(rt_base(format!("{:^10}", fl!("gtt")))).clicked() {
-                self.set_fdinfo_sort_type(FdInfoSortType::GTT);
-            }
-            if ui.button(rt_base(format!("{:^5}", fl!("cpu")))).clicked() {
-                self.set_fdinfo_sort_type(FdInfoSortType::CPU);
-            }
-            if ui.button(rt_base(format!("{:^5}", fl!("gfx")))).clicked() {
-                self.set_fdinfo_sort_type(FdInfoSortType::GFX);
-            }
-            if ui.button(rt_base(fl!("compute"))).clicked() {
-                self.set_fdinfo_sort_type(FdInfoSortType::Compute);
-            }
-            if ui.button(rt_base(format!("{:^5}", fl!("dma")))).clicked() {
-                self.set_fdinfo_sort_type(FdInfoSortType::DMA);
+    /// The GFX usage of each process as a layer of the total, the largest at the bottom.
+    /// Each layer is filled with a quad per sample interval, since the polygons of egui_plot are filled as convex.
+    pub fn egui_fdinfo_stacked_plot(&self, ui: &mut egui::Ui) {
+        let fl_total = fl!("total");
+        let label_fmt = move |name: &str, val: &PlotPoint| {
+            format!("{:.1}s : {name} ({fl_total} {:.0}%)", val.x, val.y)
+        };
+        let history = &self.buf_data.proc_gfx_history;
+        let secs: Vec<f64> = history.secs.iter().copied().collect();
+        let procs = history.procs_by_usage();
+        let mut base = vec![0i64; secs.len()];
+        let mut series: Vec<PlotSeries> = Vec::with_capacity(procs.len());
+        // (name, color, lower, upper)
+        let mut layers: Vec<(String, egui::Color32, Vec<i64>, Vec<i64>)> = Vec::with_capacity(procs.len());
+
+        for (i, (pid, name, usage)) in procs {
+            let name = format!("{name} ({pid})");
+            let lower = base.clone();
+
+            for (b, u) in base.iter_mut().zip(usage.iter()) {
+                *b += u;
             }
-            if self.has_vcn_unified {
-                if ui.button(rt_base(format!("{:^5}", fl!("media")))).clicked() {
-                    self.set_fdinfo_sort_type(FdInfoSortType::Encode);
-                }
-            } else {
-                if ui.button(rt_base(fl!("decode"))).clicked() {
-                    self.set_fdinfo_sort_type(FdInfoSortType::Decode);
-                }
-                if ui.button(rt_base(fl!("encode"))).clicked() {
-                    self.set_fdinfo_sort_type(FdInfoSortType::Encode);
+
+            series.push((
+                name.clone(),
+                secs.iter().zip(usage.iter()).map(|(sec, u)| [*sec, *u as f64]).collect(),
+            ));
+            layers.push((name, proc_color(i), lower, base.clone()));
+        }
+
+        let response = Plot::new("fdinfo stacked plot")
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .include_y(100.0)
+            .y_axis_formatter(empty_y_fmt)
+            .label_formatter(label_fmt)
+            .auto_bounds_x()
+            .link_axis(PLOT_LINK_ID, true, false)
+            .link_cursor(PLOT_LINK_ID, true, false)
+            .height(ui.available_width() / 4.0)
+            .width(ui.available_width() - 36.0)
+            .legend(Legend::default().position(Corner::LeftTop))
+            .show(ui, |plot_ui| {
+                for (name, color, lower, upper) in &layers {
+                    let fill = color.gamma_multiply(0.6);
+
+                    for j in 1..secs.len() {
+                        let [x0, x1] = [secs[j-1], secs[j]];
+                        if lower[j-1] == upper[j-1] && lower[j] == upper[j] { continue }
+
+                        plot_ui.polygon(Polygon::new(PlotPoints::new(vec![
+                            [x0, lower[j-1] as f64],
+                            [x1, lower[j] as f64],
+                            [x1, upper[j] as f64],
+                            [x0, upper[j-1] as f64],
+                        ])).name(name).fill_color(fill).stroke(egui::Stroke::new(0.0, *color)));
+                    }
+
+                    let top: Vec<[f64; 2]> = secs.iter().zip(upper.iter()).map(|(sec, v)| [*sec, *v as f64]).collect();
+                    plot_ui.line(Line::new(PlotPoints::new(top)).name(name).color(*color));
                 }
-            }
-            ui.end_row();
 
-            stat::sort_proc_usage(
-                &mut self.buf_data.fdinfo.proc_usage,
-                &self.fdinfo_sort,
-                self.reverse_sort,
-            );
+                for sec in self.kmsg_marks() {
+                    plot_ui.vline(VLine::new(sec).name(fl!("kernel_messages")));
+                }
+            })
+            .response;
 
-            let mib = fl!("mib");
+        plot_context_menu(response, "fdinfo_stacked", &series);
+    }
 
-            for pu in &self.buf_data.fdinfo.proc_usage {
-                ui.label(pu.name.to_string());
-                ui.label(format!("{:>8}", pu.pid));
-                ui.label(format!("{:5} {mib}", pu.usage.vram_usage >> 10));
-                ui.label(format!("{:5} {mib}", pu.usage.gtt_usage >> 10));
-                for usage in [
-                    pu.cpu_usage,
-                    pu.usage.gfx,
-                    pu.usage.compute,
-                    pu.usage.dma,
-                ] {
-                    ui.label(format!("{usage:3} %"));
+    /// The GFX usage of the top N processes in the time range of the history
+    pub fn egui_fdinfo_top_plot(&self, ui: &mut egui::Ui) {
+        let label_fmt = |name: &str, val: &PlotPoint| {
+            format!("{:.1}s : {name} {:.0}%", val.x, val.y)
+        };
+        let history = &self.buf_data.proc_gfx_history;
+        let series: Vec<(egui::Color32, PlotSeries)> = history.procs_by_usage()
+            .into_iter()
+            .take(self.top_procs)
+            .map(|(i, (pid, name, usage))| {
+                let points = history.secs.iter().zip(usage.iter()).map(|(sec, u)| [*sec, *u as f64]).collect();
+
+                (proc_color(i), (format!("{name} ({pid})"), points))
+            })
+            .collect();
+
+        let response = Plot::new("fdinfo top processes plot")
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .include_y(100.0)
+            .y_axis_formatter(empty_y_fmt)
+            .label_formatter(label_fmt)
+            .auto_bounds_x()
+            .link_axis(PLOT_LINK_ID, true, false)
+            .link_cursor(PLOT_LINK_ID, true, false)
+            .height(ui.available_width() / 4.0)
+            .width(ui.available_width() - 36.0)
+            .legend(Legend::default().position(Corner::LeftTop))
+            .show(ui, |plot_ui| {
+                for (color, (name, points)) in &series {
+                    plot_ui.line(Line::new(PlotPoints::new(points.clone())).name(name).color(*color));
                 }
 
-                if self.has_vcn_unified {
-                    ui.label(format!("{:3} %", pu.usage.media));
-                } else {
-                    let dec_usage = pu.usage.dec + pu.usage.vcn_jpeg;
-                    let enc_usage = pu.usage.enc + pu.usage.uvd_enc;
-                    ui.label(format!("{dec_usage:3} %"));
-                    ui.label(format!("{enc_usage:3} %"));
+                for sec in self.kmsg_marks() {
+                    plot_ui.vline(VLine::new(sec).name(fl!("kernel_messages")));
                 }
+            })
+            .response;
+        let series: Vec<PlotSeries> = series.into_iter().map(|(_, s)| s).collect();
+
+        plot_context_menu(response, "fdinfo_top", &series);
+    }
+
+    fn kmsg_marks(&self) -> Vec<f64> {
+        let Some(kmsg) = &self.buf_data.kmsg else { return Vec::new() };
+        let Some((start, _)) = self.buf_data.fdinfo_history.iter().next() else { return Vec::new() };
+
+        kmsg.iter().map(|(sec, _)| *sec).filter(|sec| *sec >= start).collect()
+    }
+
+    /// The timestamps are the seconds since startup, as the x-axis of the plots.
+    pub fn egui_kmsg(&self, ui: &mut egui::Ui) {
+        let Some(kmsg) = &self.buf_data.kmsg else { return };
+        ui.style_mut().override_font_id = Some(MEDIUM);
+
+        egui::Grid::new("Kernel Messages").show(ui, |ui| {
+            for (sec, msg) in kmsg.iter().rev() {
+                ui.label(format!("{sec:+.1}s"));
+                ui.label(msg);
                 ui.end_row();
-            } // proc_usage
+            }
         });
     }
 
-    pub fn egui_sensors(&self, ui: &mut egui::Ui) {
+    /// `--sysfs-busy`, `gpu_busy_percent`/`mem_busy_percent` next to GRBM and gpu_metrics
+    pub fn egui_activity_sources(&self, ui: &mut egui::Ui) {
+        let Some(sources) = &self.buf_data.activity_sources else { return };
         ui.style_mut().override_font_id = Some(MEDIUM);
-        let sensors = &self.buf_data.sensors;
-        egui::Grid::new("Sensors").show(ui, |ui| {
-            for (history, val, label, min, max, unit) in [
-                (
-                    &self.buf_data.sensors_history.sclk,
-                    sensors.sclk,
-                    "GFX_SCLK",
-                    self.app_device_info.min_gpu_clk,
-                    self.app_device_info.max_gpu_clk,
-                    fl!("mhz"),
-                ),
-                (
-                    &self.buf_data.sensors_history.mclk,
-                    sensors.mclk,
-                    "GFX_MCLK",
-                    self.app_device_info.min_mem_clk,
-                    self.app_device_info.max_mem_clk,
-                    fl!("mhz"),
-                ),
-                (
-                    &self.buf_data.sensors_history.vddgfx,
-                    sensors.vddgfx,
-                    "VDDGFX",
-                    500, // "500 mV" is not an exact value
-                    1500, // "1500 mV" is not an exact value
-                    fl!("mv"),
-                ),
-                (
-                    &self.buf_data.sensors_history.power,
-                    sensors.power,
-                    "GFX Power",
-                    0,
-                    if let Some(ref cap) = sensors.power_cap { cap.current } else { 350 }, // "350 W" is not an exact value
-                    fl!("w"),
-                ),
-                (
-                    &self.buf_data.sensors_history.fan_rpm,
-                    sensors.fan_rpm,
-                    "Fan",
-                    0,
-                    sensors.fan_max_rpm.unwrap_or(6000), // "6000 RPM" is not an exact value
-                    fl!("rpm"),
-                ),
-            ] {
-                let Some(val) = val else { continue };
 
-                ui.label(format!("{label}\n({val:4} {unit})"));
+        egui::Grid::new("Activity Sources").show(ui, |ui| {
+            ui.label(rt_base(fl!("source")));
+            ui.label(rt_base(fl!("gfx")));
+            ui.label(rt_base(fl!("memory")));
+            ui.end_row();
+
+            for (source, gfx, umc) in sources.rows() {
+                ui.label(source);
 
-                if min == max {
-                    ui.end_row();
-                    continue;
+                for v in [gfx, umc] {
+                    ui.label(v.map_or("___%".to_string(), |v| format!("{v:>3}%")));
                 }
 
-                let label_fmt = move |_name: &str, val: &PlotPoint| {
-                    format!("{:.1}s\n{:.0} {unit}", val.x, val.y)
-                };
-                let points: PlotPoints = history.iter()
-                    .map(|(i, val)| [i, val as f64]).collect();
-                let line = Line::new(points).fill(1.0);
-                Plot::new(label)
-                    .allow_zoom(false)
-                    .allow_scroll(false)
-                    .include_y(min)
-                    .include_y(max)
-                    .y_axis_formatter(empty_y_fmt)
-                    .label_formatter(label_fmt)
-                    .auto_bounds_x()
-                    .height(PLOT_HEIGHT * 1.5)
-                    .width(PLOT_WIDTH)
-                    .show(ui, |plot_ui| plot_ui.line(line));
                 ui.end_row();
             }
         });
-
-        self.egui_temp_plot(ui);
-
-        if let Some(cur) = sensors.current_link {
-            let min_max = if let [Some(min), Some(max)] = [sensors.min_dpm_link, sensors.max_dpm_link] {
-                format!(
-                    " (Gen{}x{} - Gen{}x{})",
-                    min.gen,
-                    min.width,
-                    max.gen,
-                    max.width,
-                )
-            } else if let Some(max) = sensors.max_dpm_link {
-                format!(" ({} Gen{}x{})", fl!("max"), max.gen, max.width)
-            } else {
-                String::new()
-            };
-
-            ui.label(format!(
-                "{} => Gen{}x{} {min_max}",
-                fl!("pcie_link_speed"),
-                cur.gen,
-                cur.width,
-            ));
-        }
     }
 
-    pub fn egui_temp_plot(&self, ui: &mut egui::Ui) {
+    /// The high priority rings are highlighted.
+    pub fn egui_queue_priority(&self, ui: &mut egui::Ui) {
+        let Some(procs) = &self.buf_data.queue_priority else { return };
         ui.style_mut().override_font_id = Some(MEDIUM);
-        let sensors = &self.buf_data.sensors;
-        let label_fmt = |_name: &str, val: &PlotPoint| {
-            format!("{:.1}s\n{:.0} C", val.x, val.y)
-        };
 
-        egui::Grid::new("Temp. Sensors").show(ui, |ui| {
-            for (label, temp, temp_history) in [
-                ("Edge", &sensors.edge_temp, &self.buf_data.sensors_history.edge_temp),
-                ("Junction", &sensors.junction_temp, &self.buf_data.sensors_history.junction_temp),
-                ("Memory", &sensors.memory_temp, &self.buf_data.sensors_history.memory_temp),
-            ] {
-                let Some(temp) = temp else { continue };
-                let val = temp.current;
-                let max = temp.critical.unwrap_or(105) as f64;
+        if procs.is_empty() {
+            ui.label(fl!("no_submissions"));
+            return;
+        }
 
-                ui.label(format!("{label} Temp.\n({val:4} C)"));
+        egui::Grid::new("Queue Priority").show(ui, |ui| {
+            ui.label(rt_base(fl!("name")));
+            ui.label(rt_base(fl!("pid")));
+            ui.label(rt_base(fl!("ring")));
+            ui.label(rt_base(fl!("priority")));
+            ui.label(rt_base(fl!("submissions")));
+            ui.end_row();
 
-                let points: PlotPoints = temp_history.iter()
-                    .map(|(i, val)| [i, val as f64]).collect();
-                let line = Line::new(points).fill(1.0);
-                Plot::new(label)
-                    .allow_zoom(false)
-                    .allow_scroll(false)
-                    .include_y(0.0)
-                    .include_y(max)
-                    .y_axis_formatter(empty_y_fmt)
-                    .label_formatter(label_fmt)
-                    .auto_bounds_x()
-                    .auto_bounds_y()
-                    .height(PLOT_HEIGHT * 1.5)
-                    .width(PLOT_WIDTH)
-                    .show(ui, |plot_ui| plot_ui.line(line));
-                ui.end_row();
+            for proc in procs {
+                for (i, q) in proc.queues.iter().enumerate() {
+                    if i == 0 {
+                        ui.label(&proc.name);
+                        ui.label(proc.pid.to_string());
+                    } else {
+                        ui.label("");
+                        ui.label("");
+                    }
+
+                    ui.label(&q.ring);
+
+                    if q.priority == QueuePriority::High {
+                        ui.label(RichText::new(q.priority.to_string()).color(egui::Color32::LIGHT_GREEN));
+                    } else {
+                        ui.label(q.priority.to_string());
+                    }
+
+                    ui.label(q.submissions.to_string());
+                    ui.end_row();
+                }
+            }
+        });
+    }
+
+    /// The frame times of `--frame-source` on the same timeline as the GFX usage of the processes,
+    /// with the stutters, the changes of the throttlers and the kernel messages.
+    pub fn egui_frame_times(&self, ui: &mut egui::Ui) {
+        let Some(frame_times) = &self.buf_data.frame_times else { return };
+        let fl_frame_time = fl!("frame_time");
+        let fl_gfx = fl!("gfx");
+        let label_fmt = {
+            let fl_gfx = fl_gfx.clone();
+
+            move |name: &str, val: &PlotPoint| {
+                let unit = if name == fl_gfx { "%" } else { "ms" };
+                format!("{:.1}s : {name} {:.1} {unit}", val.x, val.y)
+            }
+        };
+        let start = frame_times.first().map_or(0.0, |p| p[0]);
+        let gfx: Vec<[f64; 2]> = self.buf_data.fdinfo_history.iter()
+            .map(|(i, usage)| [i, usage.gfx as f64])
+            .collect();
+        let stutters: Vec<[f64; 2]> = self.buf_data.stutters.iter()
+            .filter(|s| s.sec >= start)
+            .map(|s| [s.sec, s.ms])
+            .collect();
+        let series: Vec<PlotSeries> = vec![
+            (format!("{fl_frame_time} (ms)"), frame_times.clone()),
+            (format!("{fl_gfx} (%)"), gfx.clone()),
+        ];
+
+        let response = Plot::new("frame times plot")
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .y_axis_formatter(empty_y_fmt)
+            .label_formatter(label_fmt)
+            .auto_bounds_x()
+            .auto_bounds_y()
+            .link_axis(PLOT_LINK_ID, true, false)
+            .link_cursor(PLOT_LINK_ID, true, false)
+            .height(ui.available_width() / 4.0)
+            .width(ui.available_width() - 36.0)
+            .legend(Legend::default().position(Corner::LeftTop))
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(PlotPoints::new(frame_times.clone())).name(&fl_frame_time));
+                plot_ui.line(Line::new(PlotPoints::new(gfx)).name(&fl_gfx));
+
+                if self.has_vr_compositor() {
+                    for (hz, ms) in VR_BUDGETS {
+                        plot_ui.hline(HLine::new(ms)
+                            .name(fl!("vr_budget", hz = hz, ms = format!("{ms:.1}")))
+                            .style(LineStyle::dashed_loose()));
+                    }
+                }
+
+                if !stutters.is_empty() {
+                    plot_ui.points(Points::new(stutters)
+                        .name(fl!("stutter"))
+                        .color(egui::Color32::RED)
+                        .shape(MarkerShape::Circle)
+                        .filled(false)
+                        .radius(5.0));
+                }
+
+                for (sec, throttlers) in self.buf_data.throttle_marks.iter().filter(|(sec, _)| *sec >= start) {
+                    let name = if throttlers.is_empty() { "-" } else { throttlers };
+                    plot_ui.vline(VLine::new(*sec).name(format!("{}: {name}", fl!("throttle_status"))));
+                }
+
+                for sec in self.kmsg_marks() {
+                    plot_ui.vline(VLine::new(sec).name(fl!("kernel_messages")));
+                }
+            })
+            .response;
+
+        plot_context_menu(response, "frame_times", &series);
+
+        if self.buf_data.stutters.is_empty() { return }
+
+        ui.style_mut().override_font_id = Some(MEDIUM);
+        let mhz = fl!("mhz");
+
+        egui::Grid::new("Stutters").show(ui, |ui| {
+            for label in [String::new(), fl!("frame_time"), fl!("median"), fl!("gfx"), fl!("sclk"), fl!("throttle_status")] {
+                ui.label(label).highlight();
+            }
+            ui.end_row();
+
+            for s in self.buf_data.stutters.iter().rev() {
+                ui.label(format!("{:+.1}s", s.sec));
+                ui.label(format!("{:6.1} ms", s.ms));
+                ui.label(format!("{:6.1} ms", s.median));
+                ui.label(s.gfx.map_or("-".to_string(), |gfx| format!("{gfx:3}%")));
+                ui.label(s.sclk.map_or("-".to_string(), |sclk| format!("{sclk:4} {mhz}")));
+                ui.label(if s.throttlers.is_empty() { "-" } else { &s.throttlers });
+                ui.end_row();
+            }
+        });
+    }
+
+    pub fn has_vr_compositor(&self) -> bool {
+        self.buf_data.proc_gfx_history.vr_compositors().next().is_some()
+    }
+
+    /// The GFX usage of the VR compositors and of the other processes (the VR apps),
+    /// with the changes of the throttlers, which drop the clocks and cause the reprojection.
+    /// The frame times of `--frame-source` are compared with the 90/120 Hz budgets.
+    pub fn egui_vr(&self, ui: &mut egui::Ui) {
+        let history = &self.buf_data.proc_gfx_history;
+        let fl_compositor = fl!("vr_compositor");
+        let fl_others = fl!("vr_apps");
+        let fl_frame_time = fl!("frame_time");
+        let label_fmt = {
+            let fl_frame_time = fl_frame_time.clone();
+
+            move |name: &str, val: &PlotPoint| {
+                let unit = if name == fl_frame_time { "ms" } else { "%" };
+                format!("{:.1}s : {name} {:.1} {unit}", val.x, val.y)
+            }
+        };
+        let (compositor, others) = history.vr_split();
+        let start = history.secs.front().copied().unwrap_or(0.0);
+        let frame_times = self.buf_data.frame_times.as_ref();
+        let mut series: Vec<PlotSeries> = vec![
+            (format!("{fl_compositor} (%)"), compositor.clone()),
+            (format!("{fl_others} (%)"), others.clone()),
+        ];
+
+        if let Some(frame_times) = frame_times {
+            series.push((format!("{fl_frame_time} (ms)"), frame_times.clone()));
+        }
+
+        let response = Plot::new("vr plot")
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .include_y(100.0)
+            .y_axis_formatter(empty_y_fmt)
+            .label_formatter(label_fmt)
+            .auto_bounds_x()
+            .link_axis(PLOT_LINK_ID, true, false)
+            .link_cursor(PLOT_LINK_ID, true, false)
+            .height(ui.available_width() / 4.0)
+            .width(ui.available_width() - 36.0)
+            .legend(Legend::default().position(Corner::LeftTop))
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(PlotPoints::new(compositor)).name(&fl_compositor));
+                plot_ui.line(Line::new(PlotPoints::new(others)).name(&fl_others));
+
+                if let Some(frame_times) = frame_times {
+                    plot_ui.line(Line::new(PlotPoints::new(frame_times.clone())).name(&fl_frame_time));
+
+                    for (hz, ms) in VR_BUDGETS {
+                        plot_ui.hline(HLine::new(ms)
+                            .name(fl!("vr_budget", hz = hz, ms = format!("{ms:.1}")))
+                            .style(LineStyle::dashed_loose()));
+                    }
+                }
+
+                for (sec, throttlers) in self.buf_data.throttle_marks.iter().filter(|(sec, _)| *sec >= start) {
+                    let name = if throttlers.is_empty() { "-" } else { throttlers };
+                    plot_ui.vline(VLine::new(*sec).name(format!("{}: {name}", fl!("throttle_status"))));
+                }
+            })
+            .response;
+
+        plot_context_menu(response, "vr", &series);
+
+        ui.style_mut().override_font_id = Some(MEDIUM);
+
+        egui::Grid::new("VR").show(ui, |ui| {
+            for label in [fl!("name"), fl!("pid"), fl!("gfx"), fl!("avg"), fl!("max")] {
+                ui.label(rt_base(label));
+            }
+            ui.end_row();
+
+            for (pid, name, usage) in history.vr_compositors() {
+                let avg = usage.iter().sum::<i64>() / (usage.len().max(1) as i64);
+                let max = usage.iter().max().copied().unwrap_or(0);
+
+                ui.label(name);
+                ui.label(pid.to_string());
+                ui.label(format!("{:3}%", usage.back().copied().unwrap_or(0)));
+                ui.label(format!("{avg:3}%"));
+                ui.label(format!("{max:3}%"));
+                ui.end_row();
+            }
+        });
+
+        let throttles = self.buf_data.throttle_marks.iter()
+            .filter(|(sec, throttlers)| *sec >= start && !throttlers.is_empty())
+            .count();
+        ui.label(fl!("vr_throttles", count = throttles));
+
+        let Some(frame_times) = frame_times else {
+            ui.label(fl!("vr_frame_source_hint"));
+            return;
+        };
+
+        for (hz, ms) in VR_BUDGETS {
+            let over = frame_times.iter().filter(|[_, t]| *t > ms).count();
+            let pct = if frame_times.is_empty() { 0.0 } else { over as f64 * 100.0 / frame_times.len() as f64 };
+
+            ui.label(fl!("vr_over_budget", hz = hz, count = over, total = frame_times.len(), pct = format!("{pct:.1}")));
+        }
+    }
+
+    pub fn egui_grid_fdinfo(&mut self, ui: &mut egui::Ui) {
+        collapsing_plot(ui, "fdinfo Plot", true, |ui| {
+            ui.horizontal(|ui| {
+                for plot in FdInfoPlot::ALL {
+                    ui.selectable_value(&mut self.fdinfo_plot, plot, plot.label());
+                }
+
+                if self.fdinfo_plot == FdInfoPlot::TopProcesses {
+                    ui.add(egui::DragValue::new(&mut self.top_procs).clamp_range(1..=20).prefix("N: "));
+                }
+            });
+
+            match self.fdinfo_plot {
+                FdInfoPlot::Engines => self.egui_fdinfo_plot(ui),
+                FdInfoPlot::Stacked => self.egui_fdinfo_stacked_plot(ui),
+                FdInfoPlot::TopProcesses => self.egui_fdinfo_top_plot(ui),
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.fdinfo_raw, fl!("fdinfo_raw"));
+            ui.checkbox(&mut self.fdinfo_shared, fl!("fdinfo_shared"))
+                .on_hover_text(fl!("fdinfo_shared_note"));
+            ui.label(fl!("fdinfo_group_by"));
+            for group_by in [FdInfoGroupBy::Process, FdInfoGroupBy::Cgroup, FdInfoGroupBy::User] {
+                ui.selectable_value(&mut self.fdinfo_group, group_by, group_by.label());
+            }
+        });
+
+        egui::Grid::new("fdinfo").show(ui, |ui| {
+            ui.style_mut().override_font_id = Some(MEDIUM);
+            ui.label(rt_base(format!("{:^15}", fl!("name")))).highlight();
+            self.sort_button(ui, format!("{:^8}", fl!("pid")), FdInfoSortType::PID);
+            self.sort_button(ui, format!("{:^10}", fl!("vram")), FdInfoSortType::VRAM);
+            self.sort_button(ui, format!("{:^10}", fl!("gtt")), FdInfoSortType::GTT);
+            self.sort_button(ui, format!("{:^5}", fl!("cpu")), FdInfoSortType::CPU);
+            self.sort_button(ui, format!("{:^5}", fl!("gfx")), FdInfoSortType::GFX);
+            self.sort_button(ui, fl!("compute"), FdInfoSortType::Compute);
+            self.sort_button(ui, format!("{:^5}", fl!("dma")), FdInfoSortType::DMA);
+            if self.has_vcn_unified {
+                self.sort_button(ui, format!("{:^5}", fl!("media")), FdInfoSortType::MediaEngine);
+            } else {
+                self.sort_button(ui, fl!("decode"), FdInfoSortType::Decode);
+                self.sort_button(ui, fl!("encode"), FdInfoSortType::Encode);
+            }
+            ui.end_row();
+
+            stat::sort_proc_usage(
+                &mut self.buf_data.fdinfo.proc_usage,
+                &self.fdinfo_sort,
+                self.reverse_sort,
+            );
+
+            if let Some(mut groups) = stat::group_proc_usage(&self.buf_data.fdinfo.proc_usage, self.fdinfo_group) {
+                stat::sort_group_usage(&mut groups, &self.fdinfo_sort, self.reverse_sort);
+
+                for g in &groups {
+                    let pids: Vec<String> = g.pids.iter().map(|pid| pid.to_string()).collect();
+
+                    ui.label(g.name.to_string());
+                    ui.label(format!("{:>8}", g.pids.len())).on_hover_text(pids.join(", "));
+                    self.egui_fdinfo_usage(ui, &g.usage, &g.delta, g.cpu_usage);
+                    ui.end_row();
+                }
+
+                return;
+            }
+
+            let mut selected = None;
+
+            for pu in &self.buf_data.fdinfo.proc_usage {
+                let name = if pu.is_display_server() {
+                    ui.label(format!("{} *", pu.name)).on_hover_text(fl!("display_server_note"))
+                } else {
+                    ui.label(pu.name.to_string())
+                };
+
+                if self.instance.is_some() && name.interact(egui::Sense::click()).on_hover_text(fl!("bo_list_note")).clicked() {
+                    selected = Some(pu.pid);
+                }
+
+                ui.label(format!("{:>8}", pu.pid));
+                self.egui_fdinfo_usage(ui, &pu.usage, &pu.delta, pu.cpu_usage);
+                ui.end_row();
+            } // proc_usage
+
+            if let Some(pid) = selected {
+                self.load_bo_list(pid);
+            }
+        });
+    }
+
+    fn load_bo_list(&mut self, pid: i32) {
+        let Some(instance) = self.instance else { return };
+        let list = ProcBoList::get(instance, pid).map_err(|err| format!("{err} ({})", fl!("requires_root")));
+
+        self.bo_list = Some((pid, list));
+    }
+
+    pub fn egui_bo_list(&mut self, ctx: &egui::Context) {
+        let Some((pid, list)) = &self.bo_list else { return };
+        let pid = *pid;
+        let mut open = true;
+        let mut refresh = false;
+
+        egui::Window::new(format!("{} ({pid})", fl!("buffer_objects")))
+            .open(&mut open)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                refresh = ui.button(fl!("refresh")).clicked();
+
+                let list = match list {
+                    Ok(list) => list,
+                    Err(err) => {
+                        ui.label(err);
+                        return;
+                    },
+                };
+
+                ui.label(format!("{} ({} BOs)", list.name, list.bos.len()));
+
+                egui::Grid::new("BO domain totals").show(ui, |ui| {
+                    for (domain, size, count) in list.domain_totals() {
+                        ui.label(domain);
+                        ui.label(format!("{:>8} KiB", size >> 10));
+                        ui.label(format!("{count:>5} BOs"));
+                        ui.end_row();
+                    }
+
+                    for st in &list.vm_states {
+                        ui.label(&st.state);
+                        ui.label(format!("{:>8} KiB", st.size >> 10));
+                        ui.label(format!("{:>5} BOs", st.objs));
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+
+                egui::Grid::new("BO list").striped(true).show(ui, |ui| {
+                    ui.style_mut().override_font_id = Some(MEDIUM);
+                    for label in ["ID", "Size", "Domain", "Pin", "dma-buf", "Flags"] {
+                        ui.label(label).highlight();
+                    }
+                    ui.end_row();
+
+                    for bo in &list.bos {
+                        ui.label(format!("{:#010x}", bo.id));
+                        ui.label(format!("{:>8} KiB", bo.size >> 10));
+                        ui.label(&bo.domain);
+                        ui.label(bo.pin_count.to_string());
+                        ui.label(match bo.dma_buf {
+                            Some(stat::BoDmaBuf::Exported(ino)) => format!("exported ({ino})"),
+                            Some(stat::BoDmaBuf::Imported(ino)) => format!("imported ({ino})"),
+                            None => String::new(),
+                        });
+                        ui.label(bo.flags.join(" "));
+                        ui.end_row();
+                    }
+                });
+            });
+
+        if !open {
+            self.bo_list = None;
+        } else if refresh {
+            self.load_bo_list(pid);
+        }
+    }
+
+    fn egui_fdinfo_usage(&self, ui: &mut egui::Ui, usage: &FdInfoUsage, delta: &FdInfoUsage, cpu_usage: i64) {
+        let mib = fl!("mib");
+        let ms = fl!("ms");
+        let (usage, delta) = if self.fdinfo_shared {
+            (*usage, *delta)
+        } else {
+            (usage.exclude_shared(), delta.exclude_shared())
+        };
+
+        if self.fdinfo_raw {
+            // engine busy time delta (ms per interval) and memory usage in bytes
+            ui.label(format!("{:13} B", delta.vram_usage << 10));
+            ui.label(format!("{:13} B", delta.gtt_usage << 10));
+            ui.label(format!("{cpu_usage:3} %"));
+
+            let engines = if self.has_vcn_unified {
+                vec![delta.gfx, delta.compute, delta.dma, delta.media]
+            } else {
+                vec![
+                    delta.gfx,
+                    delta.compute,
+                    delta.dma,
+                    delta.dec + delta.vcn_jpeg,
+                    delta.enc + delta.uvd_enc,
+                ]
+            };
+
+            for ns in engines {
+                ui.label(format!("{:>8} {ms}", self.number_format.float(ns as f64 / 1_000_000.0, 3)));
+            }
+
+            return;
+        }
+
+        for (val, resident, purgeable, shared) in [
+            (usage.vram_usage, usage.resident.vram, usage.purgeable.vram, usage.shared.vram),
+            (usage.gtt_usage, usage.resident.gtt, usage.purgeable.gtt, usage.shared.gtt),
+        ] {
+            ui.label(format!("{:5} {mib}", val >> 10)).on_hover_text(format!(
+                "{}: {:5} {mib}\n{}: {:5} {mib}\n{}: {:5} {mib}",
+                fl!("resident"),
+                resident >> 10,
+                fl!("purgeable"),
+                purgeable >> 10,
+                fl!("shared"),
+                shared >> 10,
+            ));
+        }
+        for usage in [
+            cpu_usage,
+            usage.gfx,
+            usage.compute,
+            usage.dma,
+        ] {
+            ui.label(format!("{usage:3} %"));
+        }
+
+        if self.has_vcn_unified {
+            ui.label(format!("{:3} %", usage.media));
+        } else {
+            let dec_usage = usage.dec + usage.vcn_jpeg;
+            let enc_usage = usage.enc + usage.uvd_enc;
+            ui.label(format!("{dec_usage:3} %"));
+            ui.label(format!("{enc_usage:3} %"));
+        }
+    }
+
+    pub fn egui_sensors(&self, ui: &mut egui::Ui) {
+        ui.style_mut().override_font_id = Some(MEDIUM);
+        let sensors = &self.buf_data.sensors;
+        egui::Grid::new("Sensors").show(ui, |ui| {
+            let power_unit = self.plot_options.power_unit;
+
+            for (history, val, status, label, min, max, unit, scale) in [
+                (
+                    &self.buf_data.sensors_history.sclk,
+                    sensors.sclk,
+                    sensors.status.sclk,
+                    "GFX_SCLK",
+                    self.app_device_info.min_gpu_clk,
+                    self.app_device_info.max_gpu_clk,
+                    fl!("mhz"),
+                    1,
+                ),
+                (
+                    &self.buf_data.sensors_history.mclk,
+                    sensors.mclk,
+                    sensors.status.mclk,
+                    "GFX_MCLK",
+                    self.app_device_info.min_mem_clk,
+                    self.app_device_info.max_mem_clk,
+                    fl!("mhz"),
+                    1,
+                ),
+                (
+                    &self.buf_data.sensors_history.vddgfx,
+                    sensors.vddgfx,
+                    sensors.status.vddgfx,
+                    "VDDGFX",
+                    500, // "500 mV" is not an exact value
+                    1500, // "1500 mV" is not an exact value
+                    fl!("mv"),
+                    1,
+                ),
+                (
+                    &self.buf_data.sensors_history.power,
+                    sensors.power,
+                    sensors.status.power,
+                    "GFX Power",
+                    0,
+                    if let Some(ref cap) = sensors.power_cap { cap.current } else { 350 }, // "350 W" is not an exact value
+                    power_unit.label().to_string(),
+                    power_unit.scale(),
+                ),
+                (
+                    &self.buf_data.sensors_history.fan_rpm,
+                    sensors.fan_rpm,
+                    sensors.status.fan_rpm,
+                    "Fan",
+                    0,
+                    sensors.fan_max_rpm.unwrap_or(6000), // "6000 RPM" is not an exact value
+                    fl!("rpm"),
+                    1,
+                ),
+            ] {
+                let Some(val) = val else { continue };
+                let peak = (label == "GFX Power")
+                    .then(|| self.peak_line(&self.peaks.power, val as f64, |v| v * scale as f64))
+                    .flatten();
+                let [val, min, max] = [val, min, max].map(|v| v.saturating_mul(scale));
+                let stats = stats_text(
+                    self.buf_data.summary.stats.sensor(label),
+                    &unit,
+                    |v| v.saturating_mul(scale as u64) as f64,
+                );
+
+                self.value_label(ui, format!("{label}\n({val:4} {unit})"), status.updated, stats);
+
+                if min == max {
+                    ui.end_row();
+                    continue;
+                }
+
+                let series_name = format!("{label} ({unit})");
+                let label_fmt = move |_name: &str, val: &PlotPoint| {
+                    format!("{:.1}s\n{:.0} {unit}", val.x, val.y)
+                };
+                let points: Vec<[f64; 2]> = history.iter()
+                    .map(|(i, val)| [i, val.saturating_mul(scale) as f64]).collect();
+                let line = Line::new(PlotPoints::new(points.clone())).fill(1.0);
+                let baseline = ["SCLK", "MCLK", "Power", "Fan"].into_iter()
+                    .find(|metric| label.ends_with(metric))
+                    .and_then(|metric| self.baseline_line(metric, &points, |v| v * scale as f64));
+                let anomalies = self.anomaly_points(label, &points, |v| v * scale as f64);
+                let response = Plot::new(label)
+                    .allow_zoom(false)
+                    .allow_scroll(false)
+                    .include_y(min)
+                    .include_y(max)
+                    .y_axis_formatter(empty_y_fmt)
+                    .label_formatter(label_fmt)
+                    .auto_bounds_x()
+                    .link_axis(PLOT_LINK_ID, true, false)
+                    .link_cursor(PLOT_LINK_ID, true, false)
+                    .height(PLOT_HEIGHT * 1.5)
+                    .width(PLOT_WIDTH)
+                    .show(ui, |plot_ui| {
+                        plot_ui.line(line);
+
+                        if let Some(baseline) = baseline {
+                            plot_ui.line(baseline);
+                        }
+
+                        if let Some(anomalies) = anomalies {
+                            plot_ui.points(anomalies);
+                        }
+
+                        if let Some(peak) = peak {
+                            plot_ui.hline(peak);
+                        }
+                    })
+                    .response;
+                plot_context_menu(response, label, &[(series_name, points)]);
+                ui.end_row();
+            }
+        });
+
+        self.egui_power_limits(ui);
+        self.egui_temp_plot(ui);
+
+        if let Some(cur) = sensors.current_link {
+            let min_max = if let [Some(min), Some(max)] = [sensors.min_dpm_link, sensors.max_dpm_link] {
+                format!(
+                    " (Gen{}x{} - Gen{}x{})",
+                    min.gen,
+                    min.width,
+                    max.gen,
+                    max.width,
+                )
+            } else if let Some(max) = sensors.max_dpm_link {
+                format!(" ({} Gen{}x{})", fl!("max"), max.gen, max.width)
+            } else {
+                String::new()
+            };
+
+            ui.label(format!(
+                "{} => Gen{}x{} {min_max}",
+                fl!("pcie_link_speed"),
+                cur.gen,
+                cur.width,
+            ));
+        }
+
+        self.egui_vcn_state(ui);
+        self.egui_fw_error(ui);
+    }
+
+    pub fn egui_dpm_clocks(&self, ui: &mut egui::Ui) {
+        ui.style_mut().override_font_id = Some(MEDIUM);
+        let mhz = fl!("mhz");
+
+        egui::Grid::new("DPM Clocks").show(ui, |ui| {
+            for table in &self.buf_data.dpm_clocks {
+                ui.label(&table.name);
+
+                ui.horizontal(|ui| {
+                    for (i, level) in table.levels.iter().enumerate() {
+                        let label = ui.label(format!("{}", level.clock))
+                            .on_hover_text(format!("{}: {} {mhz}", level.label, level.clock));
+
+                        if Some(i) == table.current {
+                            label.highlight();
+                        }
+                    }
+
+                    ui.label(&mhz);
+                });
+                ui.end_row();
+            }
+        });
+    }
+
+    pub fn egui_pm_info(&self, ui: &mut egui::Ui) {
+        let Some(pm_info) = &self.buf_data.pm_info else { return };
+        ui.style_mut().override_font_id = Some(MEDIUM);
+
+        egui::Grid::new("amdgpu_pm_info").show(ui, |ui| {
+            for val in &pm_info.gfx {
+                ui.label(&val.label);
+                let precision = if val.unit == "W" { 2 } else { 0 };
+                ui.label(format!("{:>7} {}", self.number_format.float(val.value as f64, precision), val.unit));
+                ui.end_row();
+            }
+
+            let temp_unit = self.plot_options.temp_unit;
+
+            for (label, val, unit) in [
+                ("GPU Temp.", pm_info.gpu_temp.map(|c| temp_unit.convert_int(c as i64)), temp_unit.label()),
+                ("GPU Load", pm_info.gpu_load.map(i64::from), "%"),
+                ("MEM Load", pm_info.mem_load.map(i64::from), "%"),
+                ("VCN Load", pm_info.vcn_load.map(i64::from), "%"),
+            ] {
+                let Some(val) = val else { continue };
+                ui.label(label);
+                ui.label(format!("{val:7} {unit}"));
+                ui.end_row();
+            }
+
+            for (label, block) in [("UVD/VCN", &pm_info.uvd), ("VCE", &pm_info.vce)] {
+                let Some(block) = block else { continue };
+                ui.label(label);
+                if block.enabled {
+                    let clk: Vec<String> = block.clocks.iter()
+                        .map(|clk| format!("{} {:.0} {}", clk.label, clk.value, clk.unit))
+                        .collect();
+                    ui.label(format!("{} {}", fl!("enabled"), clk.join(", ")));
+                } else {
+                    ui.label(fl!("disabled"));
+                }
+                ui.end_row();
+            }
+
+            if let Some(mask) = pm_info.smc_feature_mask {
+                ui.label("SMC Feature Mask");
+                ui.label(format!("{mask:#018x}"));
+                ui.end_row();
+            }
+        });
+
+        if pm_info.clock_gating.is_empty() { return }
+
+        let label = match pm_info.clock_gating_flags {
+            Some(mask) => format!("{} ({mask:#x})", fl!("clock_gating")),
+            None => fl!("clock_gating"),
+        };
+
+        collapsing(ui, &label, false, |ui| {
+            egui::Grid::new("Clock Gating").show(ui, |ui| {
+                for (name, on) in &pm_info.clock_gating {
+                    ui.label(name);
+                    ui.label(if *on { fl!("on") } else { fl!("off") });
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    pub fn egui_vcn_state(&self, ui: &mut egui::Ui) {
+        let Some(vcn_state) = &self.buf_data.vcn_state else { return };
+        let Some(state) = vcn_state.power_state() else { return };
+        let state = match state {
+            VcnPowerState::PowerGated => fl!("vcn_power_gated"),
+            VcnPowerState::Idle => fl!("vcn_idle"),
+            VcnPowerState::Busy => fl!("vcn_busy"),
+        };
+        let mhz = fl!("mhz");
+        let clk: Vec<String> = [("VCLK", vcn_state.vclk), ("DCLK", vcn_state.dclk)]
+            .iter()
+            .filter_map(|(name, clk)| clk.map(|clk| format!("{name} {clk} {mhz}")))
+            .collect();
+
+        if clk.is_empty() {
+            ui.label(format!("VCN => {state}"));
+        } else {
+            ui.label(format!("VCN => {state} ({})", clk.join(", ")));
+        }
+
+        let busy: Vec<&str> = vcn_state.busy_rings().map(|ring| ring.name.as_str()).collect();
+
+        if !busy.is_empty() {
+            ui.label(format!("{} => {}", fl!("vcn_busy_rings"), busy.join(", ")));
+        }
+    }
+
+    /// The codec and the resolution are known only for ffmpeg (from the command line).
+    pub fn egui_media_sessions(&self, ui: &mut egui::Ui) {
+        ui.style_mut().override_font_id = Some(MEDIUM);
+
+        egui::Grid::new("Media Sessions").show(ui, |ui| {
+            let usage_labels = if self.has_vcn_unified {
+                vec![fl!("media")]
+            } else {
+                vec![fl!("decode"), fl!("encode")]
+            };
+
+            for label in [fl!("name"), fl!("pid"), fl!("sessions"), "API".to_string(), fl!("codec"), fl!("resolution")]
+                .into_iter()
+                .chain(usage_labels)
+            {
+                ui.label(label).highlight();
+            }
+            ui.end_row();
+
+            for s in &self.buf_data.media_sessions {
+                let apis: Vec<String> = s.hint.apis.iter().map(|api| api.to_string()).collect();
+
+                ui.label(&s.name);
+                ui.label(format!("{:>8}", s.pid));
+                ui.label(format!("{:>3}", s.sessions));
+                ui.label(if apis.is_empty() { "-".to_string() } else { apis.join(", ") });
+                ui.label(if s.hint.codecs.is_empty() { "-".to_string() } else { s.hint.codecs.join(", ") });
+                ui.label(s.hint.resolution.as_deref().unwrap_or("-"));
+
+                if self.has_vcn_unified {
+                    ui.label(format!("{:3} %", s.media));
+                } else {
+                    ui.label(format!("{:3} %", s.dec));
+                    ui.label(format!("{:3} %", s.enc));
+                }
+
+                ui.end_row();
+            }
+        });
+    }
+
+    pub fn egui_leak_suspects(&self, ui: &mut egui::Ui, suspects: &[LeakSuspect]) {
+        if suspects.is_empty() {
+            ui.label(fl!("no_leak_suspects"));
+            return;
+        }
+
+        let mib = fl!("mib");
+        let rate = fl!("mib_per_min");
+
+        ui.style_mut().override_font_id = Some(MEDIUM);
+
+        egui::Grid::new("Leak Suspects").show(ui, |ui| {
+            for label in [fl!("name"), fl!("pid"), fl!("vram"), fl!("growth"), fl!("gtt"), fl!("growth"), fl!("duration")] {
+                ui.label(label).highlight();
+            }
+            ui.end_row();
+
+            for s in suspects {
+                ui.label(&s.name);
+                ui.label(format!("{:>8}", s.pid));
+                ui.label(format!("{:5} {mib}", s.vram >> 10));
+                ui.label(format!("{:+7.1} {rate}", s.vram_rate));
+                ui.label(format!("{:5} {mib}", s.gtt >> 10));
+                ui.label(format!("{:+7.1} {rate}", s.gtt_rate));
+                ui.label(format!("{:>5} s", s.duration.as_secs()));
+                ui.end_row();
+            }
+        });
+    }
+
+    /// The dumps are removed by the kernel 5 minutes after the GPU reset.
+    pub fn egui_crash_dumps(&mut self, ui: &mut egui::Ui) {
+        let Some(dir) = crash_dump_dir() else { return };
+        let mut save = None;
+
+        egui::Grid::new("Crash Dumps").show(ui, |ui| {
+            for dump in &self.buf_data.crash_dumps {
+                ui.label(&dump.name);
+
+                match dump.elapsed() {
+                    Some(elapsed) => ui.label(fl!("seconds_ago", secs = elapsed.as_secs())),
+                    None => ui.label(""),
+                };
+
+                if ui.button(fl!("save")).clicked() {
+                    save = Some((dump.clone(), false));
+                }
+
+                if ui.button(fl!("save_gzip")).clicked() {
+                    save = Some((dump.clone(), true));
+                }
+
+                ui.end_row();
+            }
+        });
+
+        if let Some((dump, compress)) = save {
+            self.crash_dump_status = Some(match dump.save(&dir, compress) {
+                Ok(path) => fl!("crash_dump_saved", path = path.display().to_string()),
+                Err(err) => format!("{}: {err}", fl!("failed_to_save")),
+            });
+        }
+
+        ui.label(fl!("crash_dump_dir", path = dir.display().to_string()));
+
+        if let Some(status) = &self.crash_dump_status {
+            ui.label(status);
+        }
+    }
+
+    pub fn trigger_sqtt(&mut self) {
+        let Some(Ok(sqtt)) = self.sqtt.as_ref().map(|sqtt| sqtt.lock()) else { return };
+
+        self.sqtt_status = Some(match sqtt.trigger() {
+            Ok(path) => fl!("sqtt_triggered", samples = WINDOW_LEN, path = path.display().to_string()),
+            Err(err) => format!("{}: {err}", fl!("failed_to_trigger")),
+        });
+    }
+
+    pub fn egui_sqtt(&mut self, ui: &mut egui::Ui) {
+        let Some(path) = self.sqtt.as_ref()
+            .and_then(|sqtt| sqtt.lock().ok().map(|sqtt| sqtt.path.clone())) else { return };
+
+        let response = ui.button(fl!("sqtt_trigger"))
+            .on_hover_text(ui.ctx().format_shortcut(&SQTT_TRIGGER_SHORTCUT));
+
+        if response.clicked() {
+            self.trigger_sqtt();
+        }
+
+        ui.label(fl!("sqtt_trigger_file", path = path.display().to_string()));
+
+        if let Some(status) = &self.sqtt_status {
+            ui.label(status);
+        }
+    }
+
+    pub fn inspect_umr(&mut self, ring: &str) {
+        let Some(umr) = &self.umr else { return };
+
+        if let Ok(mut report) = self.umr_report.lock() {
+            *report = None;
+        }
+
+        self.umr_inspecting = true;
+        umr.spawn_inspect(ring, self.umr_report.clone());
+    }
+
+    /// Inspects the ring with umr when the driver reports a timeout of the ring of the device,
+    /// before the GPU reset.
+    pub fn detect_hang(&mut self) {
+        if self.umr.is_none() { return }
+        let Some(kmsg) = &self.buf_data.kmsg else { return };
+        // the messages before the startup have negative seconds
+        let Some((sec, ring)) = kmsg.iter().rev()
+            .filter(|(sec, _)| *sec >= 0.0)
+            .find_map(|(sec, msg)| Some((*sec, parse_ring_timeout(msg)?.to_string())))
+            else { return };
+
+        if self.umr_hang.as_ref().is_some_and(|(last, _)| *last >= sec) { return }
+
+        self.inspect_umr(&ring);
+        self.umr_hang = Some((sec, ring));
+    }
+
+    pub fn egui_umr(&mut self, ui: &mut egui::Ui) {
+        let Some(umr) = &self.umr else { return };
+        let ring = self.umr_hang.as_ref().map_or(DEFAULT_RING, |(_, ring)| ring.as_str()).to_string();
+
+        ui.label(fl!("umr_path", path = umr.path.display().to_string()));
+
+        if let Some((sec, ring)) = &self.umr_hang {
+            ui.label(fl!("umr_hang_detected", ring = ring.clone(), sec = format!("{sec:+.1}")));
+        }
+
+        if ui.button(fl!("umr_inspect", ring = ring.clone())).clicked() {
+            self.inspect_umr(&ring);
+        }
+
+        let Ok(report) = self.umr_report.lock() else { return };
+        let Some(report) = report.as_ref() else {
+            if self.umr_inspecting {
+                ui.label(fl!("umr_running"));
+            }
+            return;
+        };
+
+        for (title, res) in [(fl!("umr_waves"), &report.waves), (report.ring.clone(), &report.ring_contents)] {
+            ui.collapsing(&title, |ui| {
+                egui::ScrollArea::both().id_source(&title).max_height(320.0).show(ui, |ui| {
+                    match res {
+                        Ok(s) => ui.monospace(s),
+                        Err(err) => ui.label(format!("{}: {err}", fl!("umr_failed"))),
+                    };
+                });
+            });
+        }
+    }
+
+    pub fn egui_fw_error(&self, ui: &mut egui::Ui) {
+        let Some(fw_error) = &self.buf_data.fw_error else { return };
+
+        ui.label(format!(
+            "{} => {}, {} => {}",
+            fl!("smu_msg_failures"),
+            fw_error.smu_msg_failures,
+            fl!("fw_errors"),
+            fw_error.fw_errors,
+        ));
+
+        if let (Some(msg), Some(ts)) = (&fw_error.last_error, fw_error.last_error_timestamp_us) {
+            ui.label(format!("{} => [{}.{:06}]", fl!("last_error"), ts / 1_000_000, ts % 1_000_000))
+                .on_hover_text(msg);
+        }
+    }
+
+    pub fn egui_xgmi(&self, ui: &mut egui::Ui) {
+        let Some(xgmi) = &self.buf_data.xgmi else { return };
+
+        ui.label(format!(
+            "{} => {:#X}, {} => {}",
+            fl!("xgmi_hive_id"),
+            xgmi.node.hive_id,
+            fl!("xgmi_physical_id"),
+            xgmi.node.physical_id,
+        ));
+
+        if let [Some(width), Some(speed)] = [xgmi.link_width, xgmi.link_speed] {
+            ui.label(format!("{} => x{width} {speed} Gbps", fl!("xgmi_link")));
+        }
+
+        let mut errors = format!("{} => {}", fl!("xgmi_errors"), xgmi.errors);
+
+        if let [Some(ce), Some(ue)] = [xgmi.wafl_ce, xgmi.wafl_ue] {
+            errors.push_str(&format!(", WAFL CE/UE => {ce}/{ue}"));
+        }
+
+        ui.label(errors);
+
+        if xgmi.peers.is_empty() { return }
+
+        Self::egui_xgmi_topology(xgmi, ui);
+
+        egui::Grid::new("xgmi_peers").show(ui, |ui| {
+            ui.label(fl!("xgmi_peer")).highlight();
+            ui.label("PCI").highlight();
+            ui.label(fl!("xgmi_hops")).highlight();
+            ui.label(fl!("xgmi_links")).highlight();
+            ui.end_row();
+
+            for peer in &xgmi.peers {
+                ui.label(format!("GPU{}", peer.physical_id));
+                ui.label(peer.pci_bus.map(|pci| pci.to_string()).unwrap_or_default());
+                ui.label(peer.num_hops.to_string());
+                ui.label(peer.num_links.map(|n| n.to_string()).unwrap_or_default());
+                ui.end_row();
+            }
+        });
+    }
+
+    /// The nodes of the hive on a circle, the device is at the top.
+    /// The width of the line is the number of the links, the label is the number of the hops.
+    fn egui_xgmi_topology(xgmi: &stat::XgmiStat, ui: &mut egui::Ui) {
+        use egui::{Align2, FontId, Pos2, Stroke, Vec2};
+
+        const SIZE: f32 = 240.0;
+        const NODE_RADIUS: f32 = 16.0;
+
+        let (response, painter) = ui.allocate_painter(Vec2::splat(SIZE), egui::Sense::hover());
+        let visuals = ui.visuals();
+        let center = response.rect.center();
+        let radius = SIZE / 2.0 - NODE_RADIUS - 4.0;
+        let num_nodes = xgmi.peers.len() + 1;
+        let pos = |i: usize| -> Pos2 {
+            let angle = std::f32::consts::TAU * i as f32 / num_nodes as f32 - std::f32::consts::FRAC_PI_2;
+
+            center + radius * Vec2::angled(angle)
+        };
+        let self_pos = pos(0);
+
+        for (i, peer) in xgmi.peers.iter().enumerate() {
+            let peer_pos = pos(i + 1);
+            let width = peer.num_links.unwrap_or(1).max(1) as f32;
+            let color = if peer.num_hops > 1 { visuals.weak_text_color() } else { visuals.text_color() };
+
+            painter.line_segment([self_pos, peer_pos], Stroke::new(width, color));
+            painter.text(
+                self_pos.lerp(peer_pos, 0.5),
+                Align2::CENTER_CENTER,
+                peer.num_hops.to_string(),
+                FontId::monospace(BASE.size * 0.8),
+                visuals.strong_text_color(),
+            );
+        }
+
+        let nodes = std::iter::once((self_pos, xgmi.node.physical_id, true))
+            .chain(xgmi.peers.iter().enumerate().map(|(i, peer)| (pos(i + 1), peer.physical_id, false)));
+
+        for (pos, physical_id, is_self) in nodes {
+            let fill = if is_self { visuals.selection.bg_fill } else { visuals.extreme_bg_color };
+
+            painter.circle(pos, NODE_RADIUS, fill, visuals.widgets.noninteractive.fg_stroke);
+            painter.text(
+                pos,
+                Align2::CENTER_CENTER,
+                format!("GPU{physical_id}"),
+                FontId::monospace(BASE.size * 0.7),
+                visuals.strong_text_color(),
+            );
+        }
+    }
+
+    pub fn egui_power_limits(&self, ui: &mut egui::Ui) {
+        let Some(limits) = &self.buf_data.sensors.power_limits else { return };
+        let w = fl!("w");
+
+        ui.label(fl!("apu_power_limits"));
+        egui::Grid::new("APU Power Limits").show(ui, |ui| {
+            ui.label(fl!("power_limit_type")).highlight();
+            ui.label(fl!("power_limit_current")).highlight();
+            ui.label(fl!("power_limit_cap")).highlight();
+            ui.end_row();
+
+            for limit in limits.iter() {
+                ui.label(limit.type_.to_string());
+                if let Some(power) = limit.power {
+                    ui.label(format!("{power:3} {w}"));
+                } else {
+                    ui.label(fl!("n_a"));
+                }
+                if let [Some(min), Some(max)] = [limit.min, limit.max] {
+                    ui.label(format!("{:3} {w} ({min}-{max} {w})", limit.cap));
+                } else {
+                    ui.label(format!("{:3} {w}", limit.cap));
+                }
+                ui.end_row();
+            }
+        });
+    }
+
+    /// The ghosted line of `--baseline` over the time range of the live plot
+    fn baseline_line<F: Fn(f64) -> f64>(&self, metric: &str, points: &[[f64; 2]], convert: F) -> Option<Line> {
+        let baseline = self.baseline.as_ref()?;
+        let [start, end] = [points.first()?[0], points.last()?[0]];
+        let points = baseline.points(metric, start, end, PLOT_WIDTH as usize)
+            .into_iter()
+            .map(|[x, y]| [x, convert(y)])
+            .collect();
+
+        Some(baseline.line(points))
+    }
+
+    /// The decaying peak-hold marker of [`PlotOptions::peak_hold`], only above the current value
+    fn peak_line<F: Fn(f64) -> f64>(&self, peak: &PeakHold, current: f64, convert: F) -> Option<HLine> {
+        let peak = peak.get(self.plot_options.peak_hold(), Instant::now())?;
+
+        (peak > current).then(|| HLine::new(convert(peak))
+            .name(fl!("peak_hold"))
+            .color(PEAK_HOLD_COLOR)
+            .style(LineStyle::dashed_loose()))
+    }
+
+    /// Highlights the anomalies of the metric (the name of [`libamdgpu_top::AnomalyMetric`]) in the time range of the live plot
+    fn anomaly_points<F: Fn(f64) -> f64>(&self, metric: &str, points: &[[f64; 2]], convert: F) -> Option<Points> {
+        let start = points.first()?[0];
+        let points: Vec<[f64; 2]> = self.buf_data.anomalies.iter()
+            .filter(|(sec, a)| *sec >= start && a.metric.name() == metric)
+            .map(|(sec, a)| [*sec, convert(a.value)])
+            .collect();
+
+        if points.is_empty() { return None }
+
+        Some(Points::new(points)
+            .name(fl!("anomaly"))
+            .color(egui::Color32::RED)
+            .shape(MarkerShape::Circle)
+            .filled(false)
+            .radius(5.0))
+    }
+
+    /// Greyed out if the value is not updated for [`stat::STALE_PERIODS`] update intervals
+    /// (e.g. the SMU or the sampling thread is stuck), `stats` is shown on hover
+    fn value_label(
+        &self,
+        ui: &mut egui::Ui,
+        text: String,
+        updated: Option<Instant>,
+        stats: Option<String>,
+    ) {
+        let (text, stale) = if stat::is_stale(updated, self.buf_data.interval) {
+            let secs = updated.map_or(0, |t| t.elapsed().as_secs());
+
+            (RichText::new(text).weak(), Some(fl!("stale", secs = secs)))
+        } else {
+            (RichText::new(text), None)
+        };
+        let hover: Vec<String> = [stale, stats].into_iter().flatten().collect();
+        let response = ui.label(text);
+
+        if !hover.is_empty() {
+            response.on_hover_text(hover.join("\n"));
+        }
+    }
+
+    pub fn egui_temp_plot(&self, ui: &mut egui::Ui) {
+        ui.style_mut().override_font_id = Some(MEDIUM);
+        let sensors = &self.buf_data.sensors;
+        let temp_unit = self.plot_options.temp_unit;
+        let unit = temp_unit.label();
+        let label_fmt = move |_name: &str, val: &PlotPoint| {
+            format!("{:.1}s\n{:.0} {unit}", val.x, val.y)
+        };
+
+        egui::Grid::new("Temp. Sensors").show(ui, |ui| {
+            for ((label, temp, status, temp_history), peak) in [
+                ("Edge", &sensors.edge_temp, sensors.status.edge_temp, &self.buf_data.sensors_history.edge_temp),
+                ("Junction", &sensors.junction_temp, sensors.status.junction_temp, &self.buf_data.sensors_history.junction_temp),
+                ("Memory", &sensors.memory_temp, sensors.status.memory_temp, &self.buf_data.sensors_history.memory_temp),
+            ].into_iter().zip(&self.peaks.temp) {
+                let Some(temp) = temp else { continue };
+                let peak = self.peak_line(peak, temp.current as f64, |v| temp_unit.convert(v));
+                let val = temp_unit.convert(temp.current as f64);
+                let max = temp_unit.convert(temp.critical.unwrap_or(105) as f64);
+                let stats = stats_text(
+                    self.buf_data.summary.stats.sensor(&format!("{label} Temperature")),
+                    unit,
+                    |v| temp_unit.convert(v as f64),
+                );
+
+                self.value_label(ui, format!("{label} Temp.\n({val:4.0} {unit})"), status.updated, stats);
+
+                let points: Vec<[f64; 2]> = temp_history.iter()
+                    .map(|(i, val)| [i, temp_unit.convert(val as f64)]).collect();
+                let line = Line::new(PlotPoints::new(points.clone())).fill(1.0);
+                let baseline = self.baseline_line(&format!("{label} Temp"), &points, |v| temp_unit.convert(v));
+                let anomalies = self.anomaly_points(&format!("{label} Temperature"), &points, |v| temp_unit.convert(v));
+                let response = Plot::new(label)
+                    .allow_zoom(false)
+                    .allow_scroll(false)
+                    .include_y(temp_unit.convert(0.0))
+                    .include_y(max)
+                    .y_axis_formatter(empty_y_fmt)
+                    .label_formatter(label_fmt)
+                    .auto_bounds_x()
+                    .link_axis(PLOT_LINK_ID, true, false)
+                    .link_cursor(PLOT_LINK_ID, true, false)
+                    .auto_bounds_y()
+                    .height(PLOT_HEIGHT * 1.5)
+                    .width(PLOT_WIDTH)
+                    .show(ui, |plot_ui| {
+                        plot_ui.line(line);
+
+                        if let Some(baseline) = baseline {
+                            plot_ui.line(baseline);
+                        }
+
+                        if let Some(anomalies) = anomalies {
+                            plot_ui.points(anomalies);
+                        }
+
+                        if let Some(peak) = peak {
+                            plot_ui.hline(peak);
+                        }
+                    })
+                    .response;
+                plot_context_menu(response, label, &[(format!("{label} Temp. ({unit})"), points)]);
+                ui.end_row();
             }
         });
     }
 
     pub fn egui_pcie_bw(&self, ui: &mut egui::Ui) {
-        let label_fmt = |name: &str, val: &PlotPoint| {
-            format!("{:.1}s : {name} {:.0} {}", val.x, val.y, fl!("mib_s"))
+        let log_scale = self.plot_options.pcie_bw_log_scale;
+        let label_fmt = move |name: &str, val: &PlotPoint| {
+            let y = if log_scale { from_log_scale(val.y) } else { val.y };
+            format!("{:.1}s : {name} {y:.0} {}", val.x, fl!("mib_s"))
         };
 
         let fl_sent = fl!("sent");
         let fl_rec = fl!("received");
+        let fl_total = fl!("total");
         let mib_s = fl!("mib_s");
+        // the total of both directions is stored as "sent"
+        let is_total = self.pcie_bw_source == Ok(PcieBwSource::GpuMetrics);
 
-        let [sent, rec] = {
+        let series: Vec<PlotSeries> = {
             let [mut sent_history, mut rec_history] = [0; 2].map(|_| Vec::<[f64; 2]>::new());
 
             for (i, (sent, rec)) in self.buf_data.pcie_bw_history.iter() {
@@ -672,35 +2096,186 @@ impl MyApp {
                 rec_history.push([i, rec as f64]);
             }
 
-            [
-                Line::new(PlotPoints::new(sent_history)).name(&fl_sent),
-                Line::new(PlotPoints::new(rec_history)).name(&fl_rec),
-            ]
+            if is_total {
+                vec![(fl_total.clone(), sent_history)]
+            } else {
+                vec![
+                    (fl_sent.clone(), sent_history),
+                    (fl_rec.clone(), rec_history),
+                ]
+            }
         };
 
-        Plot::new("pcie_bw plot")
+        let response = Plot::new("pcie_bw plot")
             .allow_zoom(false)
             .allow_scroll(false)
             .include_y(0.0)
             .y_axis_formatter(empty_y_fmt)
             .label_formatter(label_fmt)
             .auto_bounds_x()
+            .link_axis(PLOT_LINK_ID, true, false)
+            .link_cursor(PLOT_LINK_ID, true, false)
             .auto_bounds_y()
             .height(ui.available_width() / 4.0)
             .width(ui.available_width() - 36.0)
             .legend(Legend::default().position(Corner::LeftTop))
             .show(ui, |plot_ui| {
-                plot_ui.line(sent);
-                plot_ui.line(rec);
-            });
+                for (name, history) in &series {
+                    let points = if log_scale {
+                        history.iter().map(|[x, y]| [*x, to_log_scale(*y)]).collect()
+                    } else {
+                        history.clone()
+                    };
+
+                    plot_ui.line(Line::new(PlotPoints::new(points)).name(name));
+                }
+            })
+            .response;
 
-        if let Some((sent, rec)) = self.buf_data.pcie_bw_history.latest() {
+        plot_context_menu(response, "pcie_bw", &series);
+
+        if is_total {
+            let total = self.buf_data.pcie_bw_history.latest().map(|(total, _)| total.to_string());
+            ui.label(format!("{fl_total}: {:>5} {mib_s} (gpu_metrics)", total.as_deref().unwrap_or("_")));
+        } else if let Some((sent, rec)) = self.buf_data.pcie_bw_history.latest() {
             ui.label(format!("{fl_sent}: {sent:5} {mib_s}, {fl_rec}: {rec:5} {mib_s}"));
         } else {
             ui.label(format!("{fl_sent}: _ {mib_s}, {fl_rec}: _ {mib_s}"));
         }
     }
 
+    /// Zoom/pan over the whole session, with the tier of `--history-tiers` for the visible range
+    pub fn egui_timeline(&mut self, ui: &mut egui::Ui) {
+        const SPANS: [(&str, Option<f64>); 4] = [
+            ("30s", Some(30.0)),
+            ("5m", Some(5.0 * 60.0)),
+            ("1h", Some(60.0 * 60.0)),
+            ("24h", Some(24.0 * 60.0 * 60.0)),
+        ];
+        let Ok(timeline) = self.buf_data.timeline.lock() else { return };
+        let view = &mut self.timeline_view;
+        let baseline = self.baseline.as_ref();
+        let temp_unit = self.plot_options.temp_unit;
+
+        ui.horizontal(|ui| {
+            let selected = timeline.metrics.get(view.metric).map_or("", |(label, _, _)| label);
+
+            egui::ComboBox::from_id_source("timeline metric")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (i, (label, _, _)) in timeline.metrics.iter().enumerate() {
+                        ui.selectable_value(&mut view.metric, i, *label);
+                    }
+                });
+
+            for (text, span) in SPANS.iter().map(|(text, span)| (text.to_string(), *span))
+                .chain([(fl!("timeline_all"), None)])
+            {
+                if ui.selectable_label(view.follow && view.span == span, text).clicked() {
+                    view.span = span;
+                    view.follow = true;
+                }
+            }
+        });
+
+        let Some((label, unit, history)) = timeline.metrics.get(view.metric) else { return };
+        let Some(latest) = history.latest() else { return };
+        let (unit, convert): (&str, fn(TempUnit, f64) -> f64) = if *unit == "C" {
+            (temp_unit.label(), |temp_unit, v| temp_unit.convert(v))
+        } else {
+            (unit, |_, v| v)
+        };
+        // only for drawing, the exported series are not scaled
+        let log_scale = self.plot_options.vram_log_scale && matches!(*label, "VRAM" | "GTT");
+        let scale = move |v: f64| if log_scale { to_log_scale(v) } else { v };
+        let max_points = ui.available_width().max(1.0) as usize;
+        let label_fmt = move |name: &str, val: &PlotPoint| {
+            let y = if log_scale { from_log_scale(val.y) } else { val.y };
+            format!("{:.1}s : {name} {y:.1} {unit}", val.x)
+        };
+
+        let response = Plot::new("timeline plot")
+            .y_axis_formatter(empty_y_fmt)
+            .label_formatter(label_fmt)
+            .height(ui.available_width() / 4.0)
+            .width(ui.available_width() - 36.0)
+            .legend(Legend::default().position(Corner::LeftTop))
+            .show(ui, |plot_ui| {
+                let interacted = {
+                    let response = plot_ui.response();
+                    let zoomed = response.hovered() && plot_ui.ctx().input(|i| {
+                        i.zoom_delta() != 1.0 || i.scroll_delta != egui::Vec2::ZERO
+                    });
+
+                    response.dragged() || zoomed
+                };
+
+                if interacted {
+                    view.follow = false;
+                }
+
+                let end = latest.sec + 1.0;
+                let [start, end] = if view.follow {
+                    let start = view.span.map_or(history.start().unwrap_or(0.0), |span| end - span);
+                    [start, end]
+                } else {
+                    let bounds = plot_ui.plot_bounds();
+                    [bounds.min()[0], bounds.max()[0]]
+                };
+                let tier = history.select(start, end, max_points)?;
+                let resolution = tier.tier.resolution.as_secs_f64();
+                let points: Vec<_> = tier.points()
+                    .filter(|p| p.sec + resolution >= start && p.sec <= end)
+                    .map(|p| [p.sec, convert(temp_unit, p.min), convert(temp_unit, p.avg), convert(temp_unit, p.max)])
+                    .collect();
+                let baseline = baseline.map(|baseline| {
+                    let points: Vec<[f64; 2]> = baseline.points(label, start, end, max_points)
+                        .into_iter()
+                        .map(|[x, y]| [x, convert(temp_unit, y)])
+                        .collect();
+
+                    (baseline, points)
+                });
+
+                if view.follow && !points.is_empty() {
+                    let baseline_y = baseline.iter().flat_map(|(_, points)| points.iter().map(|p| p[1]));
+                    let min = points.iter().map(|p| p[1]).chain(baseline_y.clone()).fold(f64::INFINITY, f64::min);
+                    let max = points.iter().map(|p| p[3]).chain(baseline_y).fold(f64::NEG_INFINITY, f64::max);
+                    let [min, max] = [scale(min), scale(max)];
+                    let pad = ((max - min) * 0.1).max(if log_scale { 0.1 } else { 1.0 });
+
+                    plot_ui.set_plot_bounds(PlotBounds::from_min_max([start, min - pad], [end, max + pad]));
+                }
+
+                let series: Vec<PlotSeries> = [(fl!("avg"), 2), (fl!("min"), 1), (fl!("max"), 3)]
+                    .into_iter()
+                    .map(|(name, i)| (name, points.iter().map(|p| [p[0], p[i]]).collect()))
+                    .collect();
+
+                for (name, points) in &series {
+                    let points = points.iter().map(|[x, y]| [*x, scale(*y)]).collect();
+                    plot_ui.line(Line::new(PlotPoints::new(points)).name(name));
+                }
+
+                if let Some((baseline, points)) = baseline {
+                    plot_ui.line(baseline.line(points.into_iter().map(|[x, y]| [x, scale(y)]).collect()));
+                }
+
+                Some((tier.tier.resolution, series))
+            });
+
+        let Some((resolution, series)) = response.inner else { return };
+
+        plot_context_menu(response.response, &format!("timeline {label}"), &series);
+        let points = series.first().map_or(0, |(_, points)| points.len());
+
+        ui.label(fl!("timeline_resolution", resolution = resolution.as_secs(), points = points));
+
+        if !view.follow {
+            ui.label(fl!("timeline_zoomed"));
+        }
+    }
+
     pub fn egui_gpu_metrics_v1(&self, ui: &mut egui::Ui) {
         let gpu_metrics = &self.buf_data.gpu_metrics;
 
@@ -917,6 +2492,13 @@ impl MyApp {
     }
 }
 
+/// "Session min/avg/max: 40 / 52 / 87 C", the values are converted to the display unit
+fn stats_text(stats: Option<[u64; 3]>, unit: &str, convert: impl Fn(u64) -> f64) -> Option<String> {
+    let [min, avg, max] = stats?.map(convert);
+
+    Some(fl!("session_stats", stats = format!("{min:.0} / {avg:.0} / {max:.0} {unit}")))
+}
+
 fn empty_y_fmt(_y: f64, _max_len: usize, _range: &RangeInclusive<f64>) -> String {
     String::new()
 }
@@ -0,0 +1,78 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use eframe::egui::Color32;
+use egui_plot::{Line, LineStyle, PlotPoints};
+use serde_json::Value;
+use libamdgpu_top::PCI;
+use crate::util::TimelineHistory;
+
+// `--baseline <file>`: a session recorded with `-J` (stdout) or `--json-output-dir` (NDJSON),
+// plotted over the live plots of the same metric, aligned by the elapsed time since the start
+// of each session, e.g. to compare the thermals and clocks before/after a driver update.
+
+/// The points of each metric of [`TimelineHistory::METRICS`], `[sec, value]`
+pub struct Baseline {
+    pub name: String,
+    series: Vec<Vec<[f64; 2]>>,
+}
+
+impl Baseline {
+    /// The device with the same PCI bus in the recording, otherwise the first device.
+    pub fn load(path: &str, pci: &PCI::BUS_INFO) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let pci = pci.to_string();
+        let mut series = vec![Vec::new(); TimelineHistory::METRICS.len()];
+
+        for line in reader.lines() {
+            let line = line?;
+            let Ok(v) = serde_json::from_str::<Value>(&line) else { continue };
+            let Some(ms) = v["period"]["duration"].as_f64() else { continue };
+            // `--json-output-dir`: "device", stdout: "devices"
+            let device = match v["devices"].as_array() {
+                Some(devices) => devices.iter()
+                    .find(|d| d["Info"]["PCI"].as_str() == Some(&pci))
+                    .or(devices.first()),
+                None => Some(&v["device"]),
+            };
+            let Some(device) = device else { continue };
+
+            for ((_, _, [key, sub]), points) in TimelineHistory::METRICS.iter().zip(series.iter_mut()) {
+                let Some(val) = device[key][sub]["value"].as_f64() else { continue };
+
+                points.push([ms / 1000.0, val]);
+            }
+        }
+
+        if series.iter().all(|points| points.is_empty()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "no samples of amdgpu_top JSON"));
+        }
+
+        let name = std::path::Path::new(path)
+            .file_name()
+            .map_or(path.to_string(), |name| name.to_string_lossy().into_owned());
+
+        Ok(Self { name, series })
+    }
+
+    /// The points in `start..=end`, thinned out to `max_points`
+    pub fn points(&self, metric: &str, start: f64, end: f64, max_points: usize) -> Vec<[f64; 2]> {
+        let Some(i) = TimelineHistory::METRICS.iter().position(|(label, _, _)| *label == metric) else {
+            return Vec::new();
+        };
+        let points = &self.series[i];
+        let first = points.partition_point(|p| p[0] < start);
+        let last = points.partition_point(|p| p[0] <= end);
+        let range = &points[first..last];
+        let step = range.len().div_ceil(max_points.max(1)).max(1);
+
+        range.iter().step_by(step).copied().collect()
+    }
+
+    /// Ghosted
+    pub fn line(&self, points: Vec<[f64; 2]>) -> Line {
+        Line::new(PlotPoints::new(points))
+            .name(&self.name)
+            .color(Color32::from_gray(128).gamma_multiply(0.6))
+            .style(LineStyle::dashed_loose())
+    }
+}
@@ -0,0 +1,209 @@
+// Renders the GUI to a PNG file without a window or OpenGL (`--screenshot <png>`),
+// for the screenshots of the docs and the golden-image test of the layout (`tests/screenshot.rs`).
+// The meshes tessellated by egui are rasterized on the CPU, so the output does not depend
+// on the GPU driver, and is the same for the same fixture (`--mock`, `--demo`) on every run.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+use eframe::egui;
+use egui::{Color32, Pos2, Rect, TextureId};
+use egui::epaint::{ImageData, ImageDelta, Mesh, Primitive};
+
+use libamdgpu_top::{MockDevice, SessionOptions, TempUnit};
+
+use crate::{set_fonts, HISTORY_LENGTH, WINDOW_SIZE};
+use crate::localize::select_language;
+use crate::mock::{mock_app, MockHistory};
+
+/// The layout of some widgets (e.g. `Grid`) is decided in the previous frame
+const FRAMES: usize = 3;
+
+struct Texture {
+    size: [usize; 2],
+    pixels: Vec<Color32>,
+}
+
+impl Texture {
+    fn from_delta(delta: &ImageDelta) -> Self {
+        match &delta.image {
+            ImageData::Color(image) => Self { size: image.size, pixels: image.pixels.clone() },
+            ImageData::Font(image) => Self { size: image.size, pixels: image.srgba_pixels(None).collect() },
+        }
+    }
+
+    fn patch(&mut self, [x0, y0]: [usize; 2], src: &Self) {
+        for y in 0..src.size[1] {
+            let dst = (y0 + y) * self.size[0] + x0;
+            let row = &src.pixels[y * src.size[0]..(y + 1) * src.size[0]];
+
+            self.pixels[dst..dst + src.size[0]].copy_from_slice(row);
+        }
+    }
+
+    /// nearest
+    fn sample(&self, uv: Pos2) -> Color32 {
+        let x = ((uv.x * self.size[0] as f32) as usize).min(self.size[0] - 1);
+        let y = ((uv.y * self.size[1] as f32) as usize).min(self.size[1] - 1);
+
+        self.pixels[y * self.size[0] + x]
+    }
+}
+
+struct Canvas {
+    size: [usize; 2],
+    pixels: Vec<Color32>,
+}
+
+impl Canvas {
+    fn new(size: [usize; 2], clear: Color32) -> Self {
+        Self { size, pixels: vec![clear; size[0] * size[1]] }
+    }
+
+    /// The colors of egui are premultiplied alpha in the gamma space, blended like the glow backend
+    fn blend(&mut self, x: usize, y: usize, src: [f32; 4]) {
+        let dst = &mut self.pixels[y * self.size[0] + x];
+        let inv = 1.0 - src[3] / 255.0;
+        let [r, g, b, a] = [0, 1, 2, 3].map(|i| (src[i] + dst[i] as f32 * inv).round().clamp(0.0, 255.0) as u8);
+
+        *dst = Color32::from_rgba_premultiplied(r, g, b, a);
+    }
+
+    fn paint_mesh(&mut self, clip_rect: Rect, mesh: &Mesh, texture: Option<&Texture>) {
+        let clip_rect = clip_rect.intersect(Rect::from_min_size(
+            Pos2::ZERO,
+            egui::vec2(self.size[0] as f32, self.size[1] as f32),
+        ));
+
+        if !clip_rect.is_positive() { return }
+
+        for tri in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| &mesh.vertices[i as usize]);
+            let area = edge(a.pos, b.pos, c.pos);
+
+            if area.abs() < f32::EPSILON { continue }
+
+            let min = a.pos.min(b.pos).min(c.pos).max(clip_rect.min);
+            let max = a.pos.max(b.pos).max(c.pos).min(clip_rect.max);
+
+            for y in (min.y.floor() as usize)..(max.y.ceil() as usize) {
+                for x in (min.x.floor() as usize)..(max.x.ceil() as usize) {
+                    // the center of the pixel
+                    let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                    let w0 = edge(b.pos, c.pos, p) / area;
+                    let w1 = edge(c.pos, a.pos, p) / area;
+                    let w2 = 1.0 - w0 - w1;
+
+                    if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 { continue }
+
+                    let texel = texture.map_or(Color32::WHITE, |texture| {
+                        let uv = a.uv.to_vec2() * w0 + b.uv.to_vec2() * w1 + c.uv.to_vec2() * w2;
+
+                        texture.sample(uv.to_pos2())
+                    });
+                    let src = [0, 1, 2, 3].map(|i| {
+                        let color = a.color[i] as f32 * w0 + b.color[i] as f32 * w1 + c.color[i] as f32 * w2;
+
+                        color * texel[i] as f32 / 255.0
+                    });
+
+                    self.blend(x, y, src);
+                }
+            }
+        }
+    }
+
+    fn to_rgba(&self) -> Vec<u8> {
+        self.pixels.iter().flat_map(|c| c.to_array()).collect()
+    }
+}
+
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Runs `run_ui` for a few frames at 1 pixel per point, and returns the RGBA pixels of the last frame.
+pub fn render<F: FnMut(&egui::Context)>(ctx: &egui::Context, size: [usize; 2], mut run_ui: F) -> Vec<u8> {
+    let raw_input = egui::RawInput {
+        screen_rect: Some(Rect::from_min_size(Pos2::ZERO, egui::vec2(size[0] as f32, size[1] as f32))),
+        pixels_per_point: Some(1.0),
+        time: Some(0.0),
+        ..Default::default()
+    };
+    let mut textures: HashMap<TextureId, Texture> = HashMap::new();
+    let mut shapes = Vec::new();
+
+    for _ in 0..FRAMES {
+        let output = ctx.run(raw_input.clone(), &mut run_ui);
+
+        for (id, delta) in &output.textures_delta.set {
+            let src = Texture::from_delta(delta);
+
+            match (delta.pos, textures.get_mut(id)) {
+                (Some(pos), Some(texture)) => texture.patch(pos, &src),
+                _ => { textures.insert(*id, src); },
+            }
+        }
+
+        for id in &output.textures_delta.free {
+            textures.remove(id);
+        }
+
+        shapes = output.shapes;
+    }
+
+    let mut canvas = Canvas::new(size, Color32::BLACK);
+
+    for primitive in ctx.tessellate(shapes) {
+        // the plots and the widgets of egui do not use paint callbacks
+        let Primitive::Mesh(mesh) = &primitive.primitive else { continue };
+
+        canvas.paint_mesh(primitive.clip_rect, mesh, textures.get(&mesh.texture_id));
+    }
+
+    canvas.to_rgba()
+}
+
+pub fn save_png<P: AsRef<Path>>(path: P, size: [usize; 2], rgba: &[u8]) -> io::Result<()> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), size[0] as u32, size[1] as u32);
+
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+
+    writer.write_image_data(rgba).map_err(io::Error::other)
+}
+
+/// `--screenshot <png>` with `--mock` or `--demo`, renders the GUI of the mock device
+/// with the history of [`HISTORY_LENGTH`] samples (1s each) at the default window size.
+/// The panels of debugfs, the crash dumps and the device list are not shown,
+/// since they are read from the local system.
+pub fn screenshot<P: AsRef<Path>>(
+    mock: &mut MockDevice,
+    lang: Option<&str>,
+    temp_unit: Option<TempUnit>,
+    path: P,
+) -> io::Result<()> {
+    select_language(lang);
+
+    let mut history = MockHistory::new(mock);
+
+    mock.fdinfo.interval = std::time::Duration::from_secs(1);
+
+    for sec in HISTORY_LENGTH {
+        mock.update();
+        history.add(sec as f64, mock);
+    }
+
+    let mut app = mock_app(mock, history.data(mock), lang, temp_unit, SessionOptions::default());
+    let ctx = egui::Context::default();
+
+    set_fonts(&ctx);
+
+    let rgba = render(&ctx, WINDOW_SIZE, |ctx| app.show(ctx));
+
+    save_png(path, WINDOW_SIZE, &rgba)
+}
@@ -4,7 +4,6 @@ use std::ops::Range;
 use std::path::PathBuf;
 use eframe::egui;
 use egui::{FontFamily, FontId, RichText, util::History};
-use i18n_embed::DesktopLanguageRequester;
 
 use libamdgpu_top::AMDGPU::{
     DeviceHandle,
@@ -12,22 +11,60 @@ use libamdgpu_top::AMDGPU::{
     MetricsInfo,
     GPU_INFO,
 };
-use libamdgpu_top::{AppDeviceInfo, DevicePath, Sampling, VramUsage};
-use libamdgpu_top::stat::{self, FdInfoUsage, Sensors, FdInfoStat, PerfCounter, PcieBw};
+use libamdgpu_top::{Anomaly, AnomalyDetector, AppDeviceInfo, Capability, CrashDump, DevicePath, DeviceSample, HistoryTiers, LeakDetector, LeakSuspect, NumberFormat, P2pMatrix, PCI, SAMPLE_BUS, Sampling, SessionOptions, SessionSummary, TempUnit, TuningProfile, VramUsage};
+use libamdgpu_top::priv_helper::ControlCapabilities;
+use libamdgpu_top::sqtt::SqttTrigger;
+use libamdgpu_top::umr::Umr;
+use libamdgpu_top::ring_trace::{list_rings, ProcQueueUsage, RingTrace};
+use libamdgpu_top::stat::{self, gpu_metrics_util::{get_gpu_metrics, GpuMetricsTime}, uptime_us, ActivitySources, BoStat, DpmClockTable, FdInfoUsage, FrameSource, FrameTimes, FwErrorStat, MediaSession, MediaSessions, GpuActivity, KmsgTail, Sensors, FdInfoStat, PerfCounter, PcieBw, PmInfo, Smoothing, VcnState, XgmiStat};
 
 mod app;
-use app::MyApp;
+use app::{MyApp, FDINFO_SORT_SHORTCUTS, REVERSE_SORT_SHORTCUT, SIDE_PANEL_SHORTCUT, SQTT_TRIGGER_SHORTCUT};
 mod util;
 use util::*;
+mod baseline;
+use baseline::Baseline;
+mod headless;
+pub use headless::screenshot;
+mod mock;
+pub use mock::run_mock;
 mod localize;
 pub use localize::LANGUAGE_LOADER;
-use localize::localizer;
+use localize::{available_languages, select_language};
 
 const SPACE: f32 = 8.0;
 const BASE: FontId = FontId::new(14.0, FontFamily::Monospace);
 const MEDIUM: FontId = FontId::new(15.0, FontFamily::Monospace);
 const HEADING: FontId = FontId::new(16.0, FontFamily::Monospace);
 const HISTORY_LENGTH: Range<usize> = 0..30; // seconds
+const ANOMALY_LENGTH: usize = 64;
+const STUTTER_LENGTH: usize = 32;
+const LANGUAGE_STORAGE_KEY: &str = "language";
+const HIGH_CONTRAST_STORAGE_KEY: &str = "high_contrast";
+const WINDOW_SIZE: [usize; 2] = [1080, 840];
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum GuiBackend {
+    #[default]
+    Glow,
+    Wgpu,
+    /// Mesa llvmpipe (CPU) via glow,
+    /// for monitoring a GPU that may hang without rendering on it
+    Software,
+}
+
+impl std::str::FromStr for GuiBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "glow" => Ok(Self::Glow),
+            "wgpu" => Ok(Self::Wgpu),
+            "software" | "sw" => Ok(Self::Software),
+            _ => Err(format!("unknown GUI backend: {s:?} (glow, wgpu, software)")),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct CentralData {
@@ -37,13 +74,48 @@ pub struct CentralData {
     pub grbm2_history: Vec<History<u8>>,
     pub fdinfo: FdInfoStat,
     pub fdinfo_history: History<FdInfoUsage>,
+    pub proc_gfx_history: ProcGfxHistory,
+    /// The processes using the media engines
+    pub media_sessions: Vec<MediaSession>,
+    /// `--leak-detect`, `None` if disabled
+    pub leak_suspects: Option<Vec<LeakSuspect>>,
     pub gpu_metrics: GpuMetrics,
+    pub gpu_metrics_time: GpuMetricsTime,
     pub vram_usage: VramUsage,
     pub sensors: Sensors,
     pub sensors_history: SensorsHistory,
     pub pcie_bw_history: History<(u64, u64)>,
+    /// Shared with the sampling thread, not cloned every update
+    pub timeline: Arc<Mutex<TimelineHistory>>,
+    /// (seconds since startup, anomaly), the last [`ANOMALY_LENGTH`] anomalies
+    pub anomalies: Vec<(f64, Anomaly)>,
+    /// `--frame-source`, [sec, frame time (ms)] in the time range of the history
+    pub frame_times: Option<Vec<[f64; 2]>>,
+    /// The last [`STUTTER_LENGTH`] stutters
+    pub stutters: Vec<Stutter>,
+    /// (sec, throttlers), when the throttlers change
+    pub throttle_marks: Vec<(f64, String)>,
+    pub vcn_state: Option<VcnState>,
+    pub fw_error: Option<FwErrorStat>,
+    /// (seconds since startup, message), aligned with the history
+    pub kmsg: Option<Vec<(f64, String)>>,
+    /// `--queue-priority`, the rings used by each process
+    pub queue_priority: Option<Vec<ProcQueueUsage>>,
+    /// `--sysfs-busy`, the activity of GRBM, gpu_metrics and sysfs
+    pub activity_sources: Option<ActivitySources>,
+    pub crash_dumps: Vec<CrashDump>,
+    pub pm_info: Option<PmInfo>,
+    /// The BOs of all processes (debugfs, root)
+    pub bo_stat: Option<BoStat>,
+    pub dpm_clocks: Vec<DpmClockTable>,
+    pub xgmi: Option<XgmiStat>,
+    pub summary: SessionSummary,
+    /// The update interval of the sampling thread, the values not updated for
+    /// [`stat::STALE_PERIODS`] intervals are greyed out
+    pub interval: Duration,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     app_name: &str,
     title_with_version: &str,
@@ -51,17 +123,46 @@ pub fn run(
     amdgpu_dev: DeviceHandle,
     device_path_list: &[DevicePath],
     interval: u64,
+    smoothing: Smoothing,
+    backend: GuiBackend,
+    render_device: Option<PCI::BUS_INFO>,
+    lang: Option<&str>,
+    temp_unit: Option<TempUnit>,
+    session: SessionOptions,
+    history_tiers: HistoryTiers,
+    baseline: Option<&str>,
+    anomaly_threshold: f64,
+    frame_source: Option<FrameSource>,
+    leak_threshold: f64,
+    sysfs_busy: bool,
 ) {
-    let localizer = localizer();
-    let requested_languages = DesktopLanguageRequester::requested_languages();
+    // the control features ask for the password with the polkit agent, instead of requiring sudo
+    libamdgpu_top::priv_helper::set_use_pkexec(true);
 
-    if let Err(error) = localizer.select(&requested_languages) {
-        eprintln!("Error while loading languages for library_fluent {error}");
+    // Mesa: render the GUI on another GPU than the monitored one (e.g. iGPU)
+    if let Some(pci) = render_device {
+        let dri_prime = format!(
+            "pci-{:04x}_{:02x}_{:02x}_{:x}",
+            pci.domain,
+            pci.bus,
+            pci.dev,
+            pci.func,
+        );
+        std::env::set_var("DRI_PRIME", dri_prime);
     }
 
+    let options = native_options(app_name, backend);
+
+    select_language(lang);
+
+    let pci_bus = amdgpu_dev.get_pci_bus_info().unwrap();
+    let baseline = baseline.map(|path| Baseline::load(path, &pci_bus).unwrap_or_else(|err| {
+        eprintln!("Failed to load the baseline {path:?}: {err}");
+        std::process::exit(1);
+    }));
+
     let ext_info = amdgpu_dev.device_info().unwrap();
     let memory_info = amdgpu_dev.memory_info().unwrap();
-    let pci_bus = amdgpu_dev.get_pci_bus_info().unwrap();
     let chip_class = ext_info.get_chip_class();
     let sysfs_path = pci_bus.get_sysfs_path();
     let has_vcn = libamdgpu_top::has_vcn(&amdgpu_dev);
@@ -69,13 +170,12 @@ pub fn run(
 
     let mut grbm = PerfCounter::new_with_chip_class(stat::PCType::GRBM, chip_class);
     let mut grbm2 = PerfCounter::new_with_chip_class(stat::PCType::GRBM2, chip_class);
-    grbm.get_i18n_index(&LANGUAGE_LOADER);
-    grbm2.get_i18n_index(&LANGUAGE_LOADER);
 
     let mut proc_index: Vec<stat::ProcInfo> = Vec::new();
     let sample = Sampling::low();
     let mut fdinfo = FdInfoStat {
         interval: sample.to_duration(),
+        smoothing,
         has_vcn,
         has_vcn_unified,
         ..Default::default()
@@ -87,23 +187,54 @@ pub fn run(
         }
     }
 
-    let mut gpu_metrics = amdgpu_dev.get_gpu_metrics_from_sysfs_path(&sysfs_path)
-        .unwrap_or(GpuMetrics::Unknown);
+    let mut gpu_metrics = get_gpu_metrics(&sysfs_path).unwrap_or(GpuMetrics::Unknown);
+    let mut gpu_metrics_time = GpuMetricsTime::default();
+    gpu_metrics_time.update(&gpu_metrics);
     let mut sensors = Sensors::new(&amdgpu_dev, &pci_bus, &ext_info);
     let mut vram_usage = VramUsage::new(&memory_info);
     let mut grbm_history = vec![History::new(HISTORY_LENGTH, f32::INFINITY); grbm.index.len()];
     let mut grbm2_history = vec![History::new(HISTORY_LENGTH, f32::INFINITY); grbm2.index.len()];
     let mut fdinfo_history = History::new(HISTORY_LENGTH, f32::INFINITY);
+    let mut proc_gfx_history = ProcGfxHistory::default();
     let mut sensors_history = SensorsHistory::default();
-    let share_pcie_bw = {
-        let pcie_bw = PcieBw::new(&sysfs_path);
-        if pcie_bw.check_pcie_bw_support(&ext_info) {
-            Some(pcie_bw.spawn_update_thread())
-        } else {
-            None
-        }
-    };
+    let mut pcie_bw = PcieBw::new(&sysfs_path);
+    let pcie_bw_source = pcie_bw.detect_source(&ext_info);
+    let share_pcie_bw = pcie_bw_source.is_ok().then(|| pcie_bw.spawn_update_thread());
+    let mut media_sessions = MediaSessions::default();
+    let mut leak = LeakDetector::new(leak_threshold);
+    let mut leak_suspects = leak.is_enabled().then(Vec::new);
     let mut pcie_bw_history: History<(u64, u64)> = History::new(HISTORY_LENGTH, f32::INFINITY);
+    let timeline = Arc::new(Mutex::new(TimelineHistory::new(&history_tiers)));
+    let mut anomaly = AnomalyDetector::new(anomaly_threshold);
+    let mut anomalies: Vec<(f64, Anomaly)> = Vec::new();
+    let share_frame_times = frame_source.map(|source| {
+        FrameTimes::spawn_update_thread(&source, &device_path.card, FrameTimes::DEFAULT_LEN).unwrap_or_else(|err| {
+            eprintln!("Failed to open the frame source {source:?}: {err}");
+            std::process::exit(1);
+        })
+    });
+    let mut frame_times = share_frame_times.as_ref().map(|_| Vec::new());
+    let mut stutters: Vec<Stutter> = Vec::new();
+    let mut throttle_marks: Vec<(f64, String)> = Vec::new();
+    let share_fw_error = FwErrorStat::spawn_update_thread(&pci_bus);
+    let mut fw_error = share_fw_error.as_ref().map(|_| FwErrorStat::default());
+    let share_kmsg = KmsgTail::spawn_update_thread(&pci_bus, KmsgTail::DEFAULT_LEN);
+    let mut kmsg = share_kmsg.as_ref().map(|_| Vec::new());
+    let crash_dumps = CrashDump::list(&pci_bus);
+    let instance = device_path.get_instance_number();
+    let mut vcn_state = instance.and_then(|instance| VcnState::get(instance).ok());
+    let mut pm_info = instance.and_then(|instance| PmInfo::get(instance).ok());
+    let mut bo_stat = instance.and_then(|instance| BoStat::get(instance).ok());
+    let share_ring_trace = RingTrace::shared().and_then(|arc| {
+        let rings = instance.and_then(|instance| list_rings(instance).ok())?;
+
+        Some((arc, rings))
+    });
+    let mut queue_priority = share_ring_trace.as_ref().map(|_| Vec::new());
+    let mut activity_sources = sysfs_busy.then(ActivitySources::default);
+    let mut dpm_clocks = DpmClockTable::get_all_from_sysfs_path(&sysfs_path);
+    let mut xgmi = XgmiStat::new(&pci_bus);
+    let mut summary = SessionSummary::new(pci_bus);
 
     let data = CentralData {
         grbm: grbm.clone(),
@@ -113,61 +244,132 @@ pub fn run(
         vram_usage: vram_usage.clone(),
         fdinfo: fdinfo.clone(),
         fdinfo_history: fdinfo_history.clone(),
+        proc_gfx_history: proc_gfx_history.clone(),
+        media_sessions: media_sessions.sessions.clone(),
+        leak_suspects: leak_suspects.clone(),
         gpu_metrics: gpu_metrics.clone(),
+        gpu_metrics_time: gpu_metrics_time.clone(),
         sensors: sensors.clone(),
         sensors_history: sensors_history.clone(),
         pcie_bw_history: pcie_bw_history.clone(),
+        timeline: timeline.clone(),
+        anomalies: anomalies.clone(),
+        frame_times: frame_times.clone(),
+        stutters: stutters.clone(),
+        throttle_marks: throttle_marks.clone(),
+        vcn_state: vcn_state.clone(),
+        fw_error: fw_error.clone(),
+        kmsg: kmsg.clone(),
+        queue_priority: queue_priority.clone(),
+        activity_sources: activity_sources.clone(),
+        crash_dumps: crash_dumps.clone(),
+        pm_info: pm_info.clone(),
+        bo_stat: bo_stat.clone(),
+        dpm_clocks: dpm_clocks.clone(),
+        xgmi: xgmi.clone(),
+        summary: summary.clone(),
+        interval: sample.to_duration(),
     };
 
     let app_device_info = AppDeviceInfo::new(&amdgpu_dev, &ext_info, &memory_info, &sensors);
+    let control_caps = ControlCapabilities::detect(&app_device_info.pci_bus);
+    let capabilities = Capability::detect_all(&app_device_info, &control_caps);
     let device_list = device_path_list.iter().flat_map(DeviceListMenu::new).collect();
+    let p2p_matrix = (device_path_list.len() > 1).then(|| {
+        let devices: Vec<_> = device_path_list.iter().filter_map(|device_path| device_path.pci).collect();
+
+        P2pMatrix::new(&devices)
+    });
     let command_path = std::fs::read_link("/proc/self/exe").unwrap_or(PathBuf::from(app_name));
+    let title = match device_path.nickname() {
+        Some(nickname) => format!("{title_with_version} - {nickname}"),
+        None => title_with_version.to_string(),
+    };
 
-    let mut app = MyApp {
+    let app = MyApp {
         app_device_info,
         device_list,
+        p2p_matrix,
         command_path,
         has_vcn_unified,
-        support_pcie_bw: share_pcie_bw.is_some(),
+        pcie_bw_source,
         fdinfo_sort: Default::default(),
         reverse_sort: false,
+        fdinfo_raw: false,
+        fdinfo_shared: false,
+        fdinfo_group: Default::default(),
+        fdinfo_plot: Default::default(),
+        top_procs: FdInfoPlot::DEFAULT_TOP_N,
+        instance,
+        bo_list: None,
+        smoothing,
+        arc_smoothing: Arc::new(Mutex::new(smoothing)),
+        plot_options: PlotOptions::default(),
+        peaks: PeakHolds::default(),
+        timeline_view: TimelineView::default(),
+        baseline,
+        number_format: NumberFormat::from_env(),
+        control_caps,
+        capabilities,
+        tuning_profiles: TuningProfile::load_all(),
+        pending_profile: None,
+        tuning_backup: None,
+        tuning_status: None,
+        crash_dump_status: None,
+        sqtt: SqttTrigger::new().map(|trigger| Arc::new(Mutex::new(trigger))),
+        sqtt_status: None,
+        launch_status: None,
+        umr: instance.and_then(Umr::new),
+        umr_report: Default::default(),
+        umr_hang: None,
+        umr_inspecting: false,
+        revert_on_exit: true,
+        lang: lang.map(|lang| lang.to_string()),
+        high_contrast: false,
+        session,
+        start: std::time::Instant::now(),
         buf_data: data.clone(),
         arc_data: Arc::new(Mutex::new(data)),
         show_sidepanel: true,
         gl_vendor_info: None,
     };
 
-    let options = eframe::NativeOptions {
-        initial_window_size: Some(egui::vec2(1080.0, 840.0)),
-        app_id: Some(app_name.to_string()),
-        ..Default::default()
-    };
-
     let share_proc_index = Arc::new(Mutex::new(proc_index));
     {
         let index = share_proc_index.clone();
+        let device_path = device_path.clone();
         let mut buf_index: Vec<stat::ProcInfo> = Vec::new();
 
-        std::thread::spawn(move || loop {
-            std::thread::sleep(Duration::from_secs(interval));
-
+        libamdgpu_top::scheduler::spawn_periodic(Duration::from_secs(interval), move || {
             stat::update_index(&mut buf_index, &device_path);
 
             let lock = index.lock();
             if let Ok(mut index) = lock {
                 *index = buf_index.clone();
             }
+
+            true
         });
     }
 
     {
         let now = std::time::Instant::now();
+        let kmsg_base_us = uptime_us();
+        let mut last_frame: Option<std::time::Instant> = None;
         let share_data = app.arc_data.clone();
+        let share_smoothing = app.arc_smoothing.clone();
+        let share_sqtt = app.sqtt.clone();
 
         std::thread::spawn(move || loop {
             grbm.bits.clear();
             grbm2.bits.clear();
 
+            if let Ok(smoothing) = share_smoothing.try_lock() {
+                grbm.smoothing = *smoothing;
+                grbm2.smoothing = *smoothing;
+                fdinfo.smoothing = *smoothing;
+            }
+
             for _ in 0..sample.count {
                 grbm.read_reg(&amdgpu_dev);
                 grbm2.read_reg(&amdgpu_dev);
@@ -178,48 +380,178 @@ pub fn run(
             let sec = now.elapsed().as_secs_f64();
 
             for (pc, history) in [
-                (&grbm, &mut grbm_history),
-                (&grbm2, &mut grbm2_history),
+                (&mut grbm, &mut grbm_history),
+                (&mut grbm2, &mut grbm2_history),
             ] {
-                for ((_name, pos), h) in pc.index.iter().zip(history.iter_mut()) {
-                    h.add(sec, pc.bits.get(*pos));
+                pc.update_usage(sample.to_duration());
+
+                for (i, h) in history.iter_mut().enumerate() {
+                    h.add(sec, pc.get_usage(i));
                 }
+
+                summary.add_perf_counter(pc);
             }
 
             vram_usage.update_usage(&amdgpu_dev);
             sensors.update(&amdgpu_dev);
             sensors_history.add(sec, &sensors);
+            anomalies.extend(anomaly.update(&sensors).into_iter().map(|a| (sec, a)));
+            anomalies.drain(..anomalies.len().saturating_sub(ANOMALY_LENGTH));
+            summary.add(&sensors, &vram_usage, None);
 
-            if let Ok(v) = amdgpu_dev.get_gpu_metrics_from_sysfs_path(&sysfs_path) {
+            if let Ok(v) = get_gpu_metrics(&sysfs_path) {
+                gpu_metrics_time.update(&v);
                 gpu_metrics = v;
             }
 
+            if activity_sources.is_some() {
+                activity_sources = Some(ActivitySources::get(
+                    &sysfs_path,
+                    Some(&grbm),
+                    (!matches!(gpu_metrics, GpuMetrics::Unknown)).then_some(&gpu_metrics),
+                ));
+            }
+
+            if let Some(vcn_state) = &mut vcn_state {
+                let _ = vcn_state.update();
+            }
+
+            if let (Some(instance), Some(_)) = (instance, &pm_info) {
+                pm_info = PmInfo::get(instance).ok();
+            }
+
+            if let (Some(instance), Some(_)) = (instance, &bo_stat) {
+                bo_stat = BoStat::get(instance).ok();
+            }
+
+            for table in dpm_clocks.iter_mut() {
+                let _ = table.update();
+            }
+
+            if let Some(xgmi) = &mut xgmi {
+                xgmi.update();
+            }
+
             if let Some(arc_pcie_bw) = &share_pcie_bw {
                 let lock = arc_pcie_bw.try_lock();
                 if let Ok(pcie_bw) = lock {
-                    if let (Some(sent), Some(rec), Some(mps)) = (
-                        pcie_bw.sent,
-                        pcie_bw.received,
-                        pcie_bw.max_payload_size,
-                    ) {
-                        let sent = (sent * mps as u64) >> 20;
-                        let rec = (rec * mps as u64) >> 20;
+                    if let Some(total) = pcie_bw.total {
+                        pcie_bw_history.add(sec, (total, 0));
+                    } else if let Some((sent, rec)) = pcie_bw.sent_received_mib() {
                         pcie_bw_history.add(sec, (sent, rec));
                     }
                 }
             }
 
+            if let Some(arc_fw_error) = &share_fw_error {
+                let lock = arc_fw_error.try_lock();
+                if let Ok(stat) = lock {
+                    fw_error = Some(stat.clone());
+                }
+            }
+
+            let crash_dumps = CrashDump::list(&pci_bus);
+
+            if let (Some(arc_kmsg), Some(base_us)) = (&share_kmsg, kmsg_base_us) {
+                let lock = arc_kmsg.try_lock();
+                if let Ok(tail) = lock {
+                    kmsg = Some(tail.records.iter().map(|r| (r.sec_since(base_us), r.message.clone())).collect());
+                }
+            }
+
+            if let Some((arc_trace, rings)) = &share_ring_trace {
+                let lock = arc_trace.try_lock();
+                if let Ok(trace) = lock {
+                    queue_priority = Some(trace.procs(rings));
+                }
+            }
+
             {
                 let lock = share_proc_index.lock();
                 if let Ok(proc_index) = lock {
                     fdinfo.get_all_proc_usage(&proc_index);
                     fdinfo.interval = sample.to_duration();
                     fdinfo_history.add(sec, fdinfo.fold_fdinfo_usage());
+                    proc_gfx_history.add(sec, &fdinfo.proc_usage);
+                    media_sessions.update(&fdinfo.proc_usage);
+
+                    if leak.is_enabled() {
+                        leak.update(&fdinfo.proc_usage);
+                        leak_suspects = Some(leak.suspects());
+                    }
                 } else {
                     fdinfo.interval += sample.to_duration();
                 }
             }
 
+            let activity = GpuActivity::from_gpu_metrics(&gpu_metrics);
+
+            if let Ok(mut timeline) = timeline.lock() {
+                timeline.add(sec, &sensors, &vram_usage, &activity);
+            }
+
+            let throttlers = gpu_metrics.get_throttle_status_info()
+                .map(|thr| thr.get_all_throttler().iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", "))
+                .unwrap_or_default();
+
+            // also for the VR view without `--frame-source`
+            if throttle_marks.last().map_or(!throttlers.is_empty(), |(_, last)| *last != throttlers) {
+                throttle_marks.push((sec, throttlers.clone()));
+                throttle_marks.drain(..throttle_marks.len().saturating_sub(STUTTER_LENGTH));
+            }
+
+            if let Some(arc_frames) = &share_frame_times {
+                let lock = arc_frames.try_lock();
+                if let Ok(frames) = lock {
+                    let median = frames.median().unwrap_or(0.0);
+                    let to_sec = |t: &std::time::Instant| t.saturating_duration_since(now).as_secs_f64();
+                    let start = sec - HISTORY_LENGTH.end as f64;
+
+                    // the frames since the last sample, correlated with the current GPU state
+                    for (t, ms) in frames.frames.iter().filter(|(t, _)| last_frame.is_none_or(|last| *t > last)) {
+                        if !FrameTimes::is_stutter(*ms, median) { continue }
+
+                        stutters.push(Stutter {
+                            sec: to_sec(t),
+                            ms: *ms,
+                            median,
+                            gfx: activity.gfx,
+                            sclk: sensors.sclk,
+                            throttlers: throttlers.clone(),
+                        });
+                    }
+
+                    stutters.drain(..stutters.len().saturating_sub(STUTTER_LENGTH));
+                    last_frame = frames.frames.back().map(|(t, _)| *t);
+                    frame_times = Some(frames.frames.iter()
+                        .map(|(t, ms)| [to_sec(t), *ms])
+                        .filter(|[sec, _]| *sec >= start)
+                        .collect());
+                }
+            }
+
+            if SAMPLE_BUS.has_subscribers() {
+                SAMPLE_BUS.send(Arc::new(vec![DeviceSample::new(
+                    &device_path,
+                    &sensors,
+                    &vram_usage,
+                    &activity,
+                    (!matches!(gpu_metrics, GpuMetrics::Unknown)).then_some(&gpu_metrics),
+                    &fdinfo,
+                )]));
+            }
+
+            if let Some(Ok(mut sqtt)) = share_sqtt.as_ref().map(|sqtt| sqtt.lock()) {
+                sqtt.push(DeviceSample::new(
+                    &device_path,
+                    &sensors,
+                    &vram_usage,
+                    &activity,
+                    (!matches!(gpu_metrics, GpuMetrics::Unknown)).then_some(&gpu_metrics),
+                    &fdinfo,
+                ));
+            }
+
             {
                 let lock = share_data.lock();
                 if let Ok(mut share_data) = lock {
@@ -231,42 +563,95 @@ pub fn run(
                         vram_usage: vram_usage.clone(),
                         fdinfo: fdinfo.clone(),
                         fdinfo_history: fdinfo_history.clone(),
+                        proc_gfx_history: proc_gfx_history.clone(),
+                        media_sessions: media_sessions.sessions.clone(),
+                        leak_suspects: leak_suspects.clone(),
                         gpu_metrics: gpu_metrics.clone(),
+                        gpu_metrics_time: gpu_metrics_time.clone(),
                         sensors: sensors.clone(),
                         sensors_history: sensors_history.clone(),
                         pcie_bw_history: pcie_bw_history.clone(),
+                        timeline: timeline.clone(),
+                        anomalies: anomalies.clone(),
+                        frame_times: frame_times.clone(),
+                        stutters: stutters.clone(),
+                        throttle_marks: throttle_marks.clone(),
+                        vcn_state: vcn_state.clone(),
+                        fw_error: fw_error.clone(),
+                        kmsg: kmsg.clone(),
+                        queue_priority: queue_priority.clone(),
+                        activity_sources: activity_sources.clone(),
+                        crash_dumps,
+                        pm_info: pm_info.clone(),
+                        bo_stat: bo_stat.clone(),
+                        dpm_clocks: dpm_clocks.clone(),
+                        xgmi: xgmi.clone(),
+                        summary: summary.clone(),
+                        interval: sample.to_duration(),
                     };
                 }
             }
         });
     }
 
+    run_native(&title, options, app, temp_unit);
+}
+
+fn native_options(app_name: &str, backend: GuiBackend) -> eframe::NativeOptions {
+    let (renderer, hardware_acceleration) = match backend {
+        GuiBackend::Glow => (eframe::Renderer::Glow, eframe::HardwareAcceleration::Preferred),
+        GuiBackend::Wgpu => (eframe::Renderer::Wgpu, eframe::HardwareAcceleration::Preferred),
+        GuiBackend::Software => {
+            // Mesa: use llvmpipe instead of the hardware driver
+            std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+            std::env::set_var("GALLIUM_DRIVER", "llvmpipe");
+            (eframe::Renderer::Glow, eframe::HardwareAcceleration::Off)
+        },
+    };
+
+    eframe::NativeOptions {
+        initial_window_size: Some(egui::vec2(WINDOW_SIZE[0] as f32, WINDOW_SIZE[1] as f32)),
+        app_id: Some(app_name.to_string()),
+        renderer,
+        hardware_acceleration,
+        ..Default::default()
+    }
+}
+
+/// Restores the options of the previous session, `--temp-unit` and `--lang` take precedence
+fn run_native(title: &str, options: eframe::NativeOptions, mut app: MyApp, temp_unit: Option<TempUnit>) {
     eframe::run_native(
-        title_with_version,
+        title,
         options,
-        Box::new(|cc| {
+        Box::new(move |cc| {
             use eframe::glow::HasContext;
-            use crate::egui::FontDefinitions;
-            use crate::egui::FontData;
 
             if let Some(ctx) = &cc.gl {
                 let ver = ctx.version().vendor_info.trim_start_matches("(Core Profile) ");
                 app.gl_vendor_info = Some(ver.to_string());
             }
 
-            let mut fonts = FontDefinitions::default();
+            if let Some(s) = cc.storage.and_then(|storage| storage.get_string(PlotOptions::STORAGE_KEY)) {
+                app.plot_options = PlotOptions::parse(&s);
+            }
+
+            if let Some(temp_unit) = temp_unit {
+                app.plot_options.temp_unit = temp_unit;
+            }
 
-            fonts.font_data.insert(
-                "BIZUDGothic".to_string(),
-                FontData::from_static(include_bytes!("../fonts/BIZUDGothic-Regular.ttf")),
-            );
+            // "--lang" takes precedence over the language selected in the previous session
+            if app.lang.is_none() {
+                app.lang = cc.storage
+                    .and_then(|storage| storage.get_string(LANGUAGE_STORAGE_KEY))
+                    .filter(|lang| !lang.is_empty());
+                select_language(app.lang.as_deref());
+            }
 
-            fonts.families.get_mut(&FontFamily::Proportional).unwrap()
-                .insert(3, "BIZUDGothic".to_owned());
-            fonts.families.get_mut(&FontFamily::Monospace).unwrap()
-                .insert(3, "BIZUDGothic".to_owned());
+            app.high_contrast = cc.storage
+                .and_then(|storage| storage.get_string(HIGH_CONTRAST_STORAGE_KEY))
+                .is_some_and(|s| s == "true");
 
-            cc.egui_ctx.set_fonts(fonts);
+            set_fonts(&cc.egui_ctx);
 
             Box::new(app)
         }),
@@ -277,16 +662,40 @@ pub fn run(
     });
 }
 
+fn set_fonts(ctx: &egui::Context) {
+    use crate::egui::FontDefinitions;
+    use crate::egui::FontData;
+
+    let mut fonts = FontDefinitions::default();
+
+    fonts.font_data.insert(
+        "BIZUDGothic".to_string(),
+        FontData::from_static(include_bytes!("../fonts/BIZUDGothic-Regular.ttf")),
+    );
+
+    fonts.families.get_mut(&FontFamily::Proportional).unwrap()
+        .insert(3, "BIZUDGothic".to_owned());
+    fonts.families.get_mut(&FontFamily::Monospace).unwrap()
+        .insert(3, "BIZUDGothic".to_owned());
+
+    ctx.set_fonts(fonts);
+}
+
 impl MyApp {
-    fn egui_device_list(&self, ui: &mut egui::Ui) {
+    fn egui_device_list(&mut self, ui: &mut egui::Ui) {
+        let mut launch = None;
+
         ui.menu_button(RichText::new("Device List").font(BASE), |ui| {
             ui.set_width(360.0);
             for device in &self.device_list {
                 ui.horizontal(|ui| {
+                    let name = match &device.nickname {
+                        Some(nickname) => format!("{nickname}: {}", device.name),
+                        None => device.name.clone(),
+                    };
                     let text = RichText::new(format!(
                         "#{instance} {name} ({pci})",
                         instance = device.instance,
-                        name = device.name,
                         pci = device.pci,
                     )).font(BASE);
 
@@ -295,16 +704,43 @@ impl MyApp {
                     } else {
                         ui.menu_button(text, |ui| {
                             if ui.button(&fl!("launch_new_process")).clicked() {
-                                std::process::Command::new(&self.command_path)
-                                    .args(["--gui", "--pci", &device.pci.to_string()])
-                                    .spawn()
-                                    .unwrap();
+                                launch = Some(device.pci);
                             }
                         });
                     }
                 });
             }
+
+            if let Some(status) = &self.launch_status {
+                ui.label(status);
+            }
         });
+
+        if let Some(pci) = launch {
+            self.launch_new_process(pci);
+        }
+    }
+
+    fn launch_new_process(&mut self, pci: PCI::BUS_INFO) {
+        let mut cmd = std::process::Command::new(&self.command_path);
+        cmd.args(["--gui", "--pci", &pci.to_string()]);
+
+        if let Some(lang) = &self.lang {
+            cmd.args(["--lang", lang]);
+        }
+
+        self.launch_status = match cmd.spawn() {
+            Ok(mut child) => {
+                // reap the process when it exits, not to leave a zombie
+                std::thread::spawn(move || { let _ = child.wait(); });
+                None
+            },
+            Err(err) => {
+                let status = format!("{}: {err}", fl!("failed_to_launch"));
+                eprintln!("{status}");
+                Some(status)
+            },
+        };
     }
 
     fn egui_side_panel(&self, ui: &mut egui::Ui) {
@@ -317,6 +753,9 @@ impl MyApp {
                 |ui| self.egui_app_device_info(ui, &self.gl_vendor_info),
             );
 
+            ui.add_space(SPACE);
+            collapsing(ui, &fl!("capabilities"), false, |ui| self.egui_capabilities(ui));
+
             if !self.app_device_info.ip_die_entries.is_empty() {
                 ui.add_space(SPACE);
                 collapsing(
@@ -327,6 +766,11 @@ impl MyApp {
                 );
             }
 
+            if self.p2p_matrix.is_some() {
+                ui.add_space(SPACE);
+                collapsing(ui, &fl!("p2p_access"), false, |ui| self.egui_p2p_matrix(ui));
+            }
+
             if self.app_device_info.decode.is_some() && self.app_device_info.encode.is_some() {
                 ui.add_space(SPACE);
                 collapsing(ui, &fl!("video_caps_info"), false, |ui| self.egui_video_caps_info(ui));
@@ -340,6 +784,193 @@ impl MyApp {
         });
     }
 
+    fn egui_smoothing(&mut self, ui: &mut egui::Ui) {
+        let pre = self.smoothing;
+
+        egui::ComboBox::from_label(RichText::new(fl!("smoothing")).font(BASE))
+            .selected_text(self.smoothing.to_string())
+            .show_ui(ui, |ui| {
+                for s in Smoothing::ALL {
+                    ui.selectable_value(&mut self.smoothing, s, s.to_string());
+                }
+            });
+
+        if pre != self.smoothing {
+            if let Ok(mut smoothing) = self.arc_smoothing.lock() {
+                *smoothing = self.smoothing;
+            }
+        }
+    }
+
+    fn egui_plot_options(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button(RichText::new(fl!("plot_options")).font(BASE), |ui| {
+            ui.checkbox(&mut self.plot_options.pcie_bw_log_scale, fl!("pcie_bw_log_scale"));
+            ui.checkbox(&mut self.plot_options.vram_log_scale, fl!("vram_log_scale"));
+
+            ui.horizontal(|ui| {
+                ui.label(fl!("temp_unit"));
+                for unit in [TempUnit::Celsius, TempUnit::Fahrenheit] {
+                    ui.radio_value(&mut self.plot_options.temp_unit, unit, unit.label());
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label(fl!("power_unit"));
+                for unit in [PowerUnit::Watt, PowerUnit::MilliWatt] {
+                    ui.radio_value(&mut self.plot_options.power_unit, unit, unit.label());
+                }
+            });
+
+            ui.add(
+                egui::Slider::new(&mut self.plot_options.peak_hold, 0..=PlotOptions::MAX_PEAK_HOLD)
+                    .text(fl!("peak_hold"))
+                    .suffix("s")
+            ).on_hover_text(fl!("peak_hold_desc"));
+        });
+    }
+
+    fn egui_language(&mut self, ui: &mut egui::Ui) {
+        let pre = self.lang.clone();
+
+        egui::ComboBox::from_label(RichText::new(fl!("language")).font(BASE))
+            .selected_text(self.lang.clone().unwrap_or_else(|| fl!("system_language")))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.lang, None, fl!("system_language"));
+
+                for lang in available_languages() {
+                    ui.selectable_value(&mut self.lang, Some(lang.clone()), lang);
+                }
+            });
+
+        if pre != self.lang {
+            select_language(self.lang.as_deref());
+        }
+    }
+
+    fn egui_accessibility(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button(RichText::new(fl!("accessibility")).font(BASE), |ui| {
+            ui.checkbox(&mut self.high_contrast, fl!("high_contrast"));
+            ui.separator();
+            ui.label(fl!("keyboard_shortcuts"));
+
+            egui::Grid::new("keyboard shortcuts").show(ui, |ui| {
+                let ctx = ui.ctx().clone();
+                let sort = fl!("sort_by");
+
+                ui.label(ctx.format_shortcut(&SIDE_PANEL_SHORTCUT));
+                ui.label(fl!("toggle_side_panel"));
+                ui.end_row();
+
+                for (shortcut, sort_type) in FDINFO_SORT_SHORTCUTS {
+                    ui.label(ctx.format_shortcut(shortcut));
+                    ui.label(format!("{sort} {sort_type:?}"));
+                    ui.end_row();
+                }
+
+                ui.label(ctx.format_shortcut(&REVERSE_SORT_SHORTCUT));
+                ui.label(fl!("reverse_sort"));
+                ui.end_row();
+
+                if self.sqtt.is_some() {
+                    ui.label(ctx.format_shortcut(&SQTT_TRIGGER_SHORTCUT));
+                    ui.label(fl!("sqtt_trigger"));
+                    ui.end_row();
+                }
+
+                ui.label("Tab / Shift+Tab");
+                ui.label(fl!("move_focus"));
+                ui.end_row();
+            });
+        });
+    }
+
+    fn egui_tuning_profiles(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button(RichText::new(fl!("tuning_profiles")).font(BASE), |ui| {
+            if libamdgpu_top::priv_helper::is_read_only() {
+                ui.label(fl!("read_only_mode"));
+            } else if !self.control_caps.any() {
+                ui.label(fl!("no_writable_controls"));
+            }
+
+            if self.tuning_profiles.is_empty() {
+                ui.label(fl!("no_tuning_profiles"));
+            }
+
+            for profile in &self.tuning_profiles {
+                let supported = profile.required_nodes().iter().all(|node| self.control_caps.is_writable(*node));
+                let enabled = self.tuning_backup.is_none() && supported;
+                let button = ui.add_enabled(enabled, egui::Button::new(&profile.name));
+                let button = if supported {
+                    button
+                } else {
+                    button.on_disabled_hover_text(fl!("profile_not_writable"))
+                };
+
+                if button.clicked() {
+                    self.pending_profile = Some(profile.clone());
+                    ui.close_menu();
+                }
+            }
+
+            ui.separator();
+
+            if ui.add_enabled(self.tuning_backup.is_some(), egui::Button::new(fl!("revert"))).clicked() {
+                self.revert_tuning_profile();
+                ui.close_menu();
+            }
+
+            ui.checkbox(&mut self.revert_on_exit, fl!("revert_on_exit"));
+        });
+
+        if let Some(status) = &self.tuning_status {
+            ui.label(status);
+        }
+    }
+
+    fn egui_confirm_tuning_profile(&mut self, ctx: &egui::Context) {
+        let Some(profile) = &self.pending_profile else { return };
+        let mut apply = false;
+        let mut cancel = false;
+
+        egui::Window::new(fl!("apply_tuning_profile"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!("{} ({})", profile.name, self.app_device_info.pci_bus));
+                for change in profile.changes() {
+                    ui.label(change);
+                }
+                ui.label(fl!("auth_may_be_required"));
+                ui.horizontal(|ui| {
+                    apply = ui.button(fl!("apply")).clicked();
+                    cancel = ui.button(fl!("cancel")).clicked();
+                });
+            });
+
+        if apply {
+            self.tuning_status = Some(match profile.apply(&self.app_device_info.pci_bus) {
+                Ok(backup) => {
+                    self.tuning_backup = Some(backup);
+                    fl!("tuning_profile_applied", name = profile.name.clone())
+                },
+                Err(err) => format!("{}: {err}", fl!("failed_to_apply")),
+            });
+        }
+
+        if apply || cancel {
+            self.pending_profile = None;
+        }
+    }
+
+    fn revert_tuning_profile(&mut self) {
+        let Some(backup) = self.tuning_backup.take() else { return };
+
+        self.tuning_status = match backup.revert() {
+            Ok(_) => None,
+            Err(err) => Some(format!("{}: {err}", fl!("failed_to_revert"))),
+        };
+    }
+
     fn egui_central_panel(&mut self, ui: &mut egui::Ui) {
         // ui.set_min_width(540.0);
         egui::ScrollArea::both().show(ui, |ui| {
@@ -357,27 +988,115 @@ impl MyApp {
                 &self.buf_data.grbm2_history,
             ));
             ui.add_space(SPACE);
+
+            if self.buf_data.activity_sources.is_some() {
+                collapsing(ui, &fl!("activity_sources"), true, |ui| self.egui_activity_sources(ui));
+                ui.add_space(SPACE);
+            }
+
             collapsing(ui, &fl!("vram"), true, |ui| self.egui_vram(ui));
             ui.add_space(SPACE);
             collapsing(ui, &fl!("fdinfo"), true, |ui| self.egui_grid_fdinfo(ui));
+
+            if !self.buf_data.media_sessions.is_empty() {
+                ui.add_space(SPACE);
+                collapsing(ui, &fl!("media_sessions"), true, |ui| self.egui_media_sessions(ui));
+            }
+
+            if let Some(suspects) = &self.buf_data.leak_suspects {
+                ui.add_space(SPACE);
+                collapsing(ui, &fl!("leak_suspects"), true, |ui| self.egui_leak_suspects(ui, suspects));
+            }
             ui.add_space(SPACE);
             collapsing(ui, &fl!("sensor"), true, |ui| self.egui_sensors(ui));
+            ui.add_space(SPACE);
+            collapsing(ui, &fl!("timeline"), false, |ui| self.egui_timeline(ui));
+
+            if self.buf_data.frame_times.is_some() {
+                ui.add_space(SPACE);
+                collapsing(ui, &fl!("frame_times"), true, |ui| self.egui_frame_times(ui));
+            }
+
+            if self.has_vr_compositor() {
+                ui.add_space(SPACE);
+                collapsing(ui, &fl!("vr"), true, |ui| self.egui_vr(ui));
+            }
+
+            match self.pcie_bw_source {
+                Ok(_) => {
+                    ui.add_space(SPACE);
+                    collapsing(ui, &fl!("pcie_bw"), true, |ui| self.egui_pcie_bw(ui));
+                },
+                Err(reason) => {
+                    ui.add_space(SPACE);
+                    collapsing(ui, &fl!("pcie_bw"), false, |ui| {
+                        ui.label(fl!("pcie_bw_unsupported", reason = reason));
+                    });
+                },
+            }
 
-            if self.support_pcie_bw {
+            if !self.buf_data.crash_dumps.is_empty() || self.crash_dump_status.is_some() {
                 ui.add_space(SPACE);
-                collapsing(ui, &fl!("pcie_bw"), true, |ui| self.egui_pcie_bw(ui));
+                collapsing(ui, &fl!("crash_dumps"), true, |ui| self.egui_crash_dumps(ui));
             }
 
+            if self.sqtt.is_some() {
+                ui.add_space(SPACE);
+                collapsing(ui, &fl!("sqtt_capture"), true, |ui| self.egui_sqtt(ui));
+            }
+
+            if self.umr.is_some() {
+                ui.add_space(SPACE);
+                collapsing(ui, &fl!("deep_inspect"), false, |ui| self.egui_umr(ui));
+            }
+
+            if self.buf_data.kmsg.is_some() {
+                ui.add_space(SPACE);
+                collapsing(ui, &fl!("kernel_messages"), false, |ui| self.egui_kmsg(ui));
+            }
+
+            if self.buf_data.queue_priority.is_some() {
+                ui.add_space(SPACE);
+                collapsing(ui, &fl!("queue_priority"), true, |ui| self.egui_queue_priority(ui));
+            }
+
+            if self.buf_data.xgmi.is_some() {
+                ui.add_space(SPACE);
+                collapsing(ui, &fl!("xgmi"), true, |ui| self.egui_xgmi(ui));
+            }
+
+            if !self.buf_data.dpm_clocks.is_empty() {
+                ui.add_space(SPACE);
+                collapsing(ui, &fl!("dpm_clocks"), false, |ui| self.egui_dpm_clocks(ui));
+            }
+
+            if self.buf_data.pm_info.is_some() {
+                ui.add_space(SPACE);
+                collapsing(ui, "amdgpu_pm_info", false, |ui| self.egui_pm_info(ui));
+            }
+
+            let metrics_time = &self.buf_data.gpu_metrics_time;
+            let stale = metrics_time.is_stale(self.buf_data.interval);
             let header = if let Some(h) = self.buf_data.gpu_metrics.get_header() {
-                format!(
+                let mut header = format!(
                     "{} v{}.{}",
                     fl!("gpu_metrics"),
                     h.format_revision,
                     h.content_revision
-                )
+                );
+
+                if stale {
+                    let secs = metrics_time.updated.map_or(0, |t| t.elapsed().as_secs());
+                    header.push_str(&format!(" ({})", fl!("stale", secs = secs)));
+                }
+
+                header
             } else {
                 String::new()
             };
+            let grey_out = |ui: &mut egui::Ui| if stale {
+                ui.visuals_mut().override_text_color = Some(ui.visuals().weak_text_color());
+            };
 
             match self.buf_data.gpu_metrics {
                 GpuMetrics::V1_0(_) |
@@ -385,7 +1104,10 @@ impl MyApp {
                 GpuMetrics::V1_2(_) |
                 GpuMetrics::V1_3(_) => {
                     ui.add_space(SPACE);
-                    collapsing(ui, &header, true, |ui| self.egui_gpu_metrics_v1(ui));
+                    collapsing(ui, &header, true, |ui| {
+                        grey_out(ui);
+                        self.egui_gpu_metrics_v1(ui);
+                    });
                 },
                 GpuMetrics::V2_0(_) |
                 GpuMetrics::V2_1(_) |
@@ -393,7 +1115,10 @@ impl MyApp {
                 GpuMetrics::V2_3(_) |
                 GpuMetrics::V2_4(_) => {
                     ui.add_space(SPACE);
-                    collapsing(ui, &header, true, |ui| self.egui_gpu_metrics_v2(ui));
+                    collapsing(ui, &header, true, |ui| {
+                        grey_out(ui);
+                        self.egui_gpu_metrics_v2(ui);
+                    });
                 },
                 _ => {},
             }
@@ -402,50 +1127,97 @@ impl MyApp {
     }
 }
 
-impl eframe::App for MyApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+impl MyApp {
+    /// One frame of the GUI, also rendered without a window by `--screenshot`
+    fn show(&mut self, ctx: &egui::Context) {
         {
             let lock = self.arc_data.try_lock();
             if let Ok(data) = lock {
                 self.buf_data = data.clone();
             }
         }
+        self.peaks.update(
+            &self.buf_data.vram_usage,
+            &self.buf_data.sensors,
+            self.plot_options.peak_hold(),
+            std::time::Instant::now(),
+        );
         {
             let mut style = (*ctx.style()).clone();
             style.override_font_id = Some(BASE);
+            style.visuals = if self.high_contrast { high_contrast_visuals() } else { egui::Visuals::dark() };
             ctx.set_style(style);
         }
         ctx.clear_animations();
 
+        if ctx.input_mut(|i| i.consume_shortcut(&SIDE_PANEL_SHORTCUT)) {
+            self.show_sidepanel ^= true;
+        }
+        if ctx.input_mut(|i| i.consume_shortcut(&SQTT_TRIGGER_SHORTCUT)) {
+            self.trigger_sqtt();
+        }
+        self.fdinfo_sort_shortcuts(ctx);
+        self.detect_hang();
+
         egui::TopBottomPanel::top("menu bar").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 ui.toggle_value(&mut self.show_sidepanel, RichText::new(fl!("info"))
                     .font(BASE))
-                    .on_hover_text(fl!("toggle_side_panel"));
+                    .on_hover_text(format!(
+                        "{} ({})",
+                        fl!("toggle_side_panel"),
+                        ctx.format_shortcut(&SIDE_PANEL_SHORTCUT),
+                    ));
                 self.egui_device_list(ui);
+                self.egui_smoothing(ui);
+                self.egui_plot_options(ui);
+                self.egui_language(ui);
+                self.egui_accessibility(ui);
+                self.egui_tuning_profiles(ui);
+
+                if let Some(status) = csv_status(ctx) {
+                    ui.label(status);
+
+                    if ui.small_button("x").clicked() {
+                        clear_csv_status(ctx);
+                    }
+                }
             });
         });
 
+        self.egui_confirm_tuning_profile(ctx);
+        self.egui_bo_list(ctx);
+
         if self.show_sidepanel {
             egui::SidePanel::left(egui::Id::new(3)).show(ctx, |ui| self.egui_side_panel(ui));
         }
 
         egui::CentralPanel::default().show(ctx, |ui| self.egui_central_panel(ui));
-
-        ctx.request_repaint_after(Duration::from_millis(500));
     }
 }
 
-use i18n_embed::fluent::FluentLanguageLoader;
+impl eframe::App for MyApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.show(ctx);
 
-trait I18nPerfCounter {
-    fn get_i18n_index(&mut self, loader: &FluentLanguageLoader);
-}
+        ctx.request_repaint_after(Duration::from_millis(500));
 
-impl I18nPerfCounter for PerfCounter {
-    fn get_i18n_index(&mut self, loader: &FluentLanguageLoader) {
-        for (ref mut name, _) in self.index.iter_mut() {
-            *name = loader.get(&name.replace(' ', "_").replace('/', ""));
+        if self.session.is_over(self.start, self.buf_data.summary.samples()) {
+            frame.close();
         }
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(PlotOptions::STORAGE_KEY, self.plot_options.to_string());
+        storage.set_string(LANGUAGE_STORAGE_KEY, self.lang.clone().unwrap_or_default());
+        storage.set_string(HIGH_CONTRAST_STORAGE_KEY, self.high_contrast.to_string());
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if self.revert_on_exit {
+            self.revert_tuning_profile();
+        }
+
+        self.session.write_summary(std::slice::from_ref(&self.buf_data.summary));
+    }
 }
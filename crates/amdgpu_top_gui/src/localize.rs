@@ -1,7 +1,9 @@
 use i18n_embed::{
     fluent::{fluent_language_loader, FluentLanguageLoader},
-    DefaultLocalizer, LanguageLoader, Localizer,
+    unic_langid::LanguageIdentifier,
+    DefaultLocalizer, DesktopLanguageRequester, LanguageLoader, Localizer,
 };
+use libamdgpu_top::{pseudo_localize, PSEUDO_LOCALE};
 // use i18n_embed_fl::fl;
 use once_cell::sync::Lazy;
 use rust_embed::RustEmbed;
@@ -26,6 +28,45 @@ pub fn localizer() -> Box<dyn Localizer> {
     Box::from(DefaultLocalizer::new(&*LANGUAGE_LOADER, &Localizations))
 }
 
+/// Select the language (e.g. "ja", "pseudo"), or the desktop languages for `None`.
+/// Can be called at runtime, the next frame uses the new language.
+pub fn select_language(lang: Option<&str>) {
+    let pseudo = lang == Some(PSEUDO_LOCALE);
+    let requested_languages: Vec<LanguageIdentifier> = match lang {
+        Some(_) if pseudo => vec![LANGUAGE_LOADER.fallback_language().clone()],
+        Some(lang) => match lang.parse() {
+            Ok(lang) => vec![lang],
+            Err(_) => {
+                eprintln!("invalid language: {lang:?}");
+                DesktopLanguageRequester::requested_languages()
+            },
+        },
+        None => DesktopLanguageRequester::requested_languages(),
+    };
+
+    if let Err(error) = localizer().select(&requested_languages) {
+        eprintln!("Error while loading languages for library_fluent {error}");
+    }
+
+    LANGUAGE_LOADER.with_bundles_mut(|bundle| {
+        bundle.set_transform(if pseudo { Some(pseudo_localize) } else { None });
+    });
+}
+
+/// The languages in `i18n/`, and the pseudo-locale
+pub fn available_languages() -> Vec<String> {
+    let mut langs: Vec<String> = localizer()
+        .available_languages()
+        .unwrap_or_default()
+        .iter()
+        .map(|lang| lang.to_string())
+        .collect();
+
+    langs.push(PSEUDO_LOCALE.to_string());
+
+    langs
+}
+
 #[macro_export]
 macro_rules! fl {
     ($message_id:literal) => {{
@@ -0,0 +1,231 @@
+// The GUI of the mock devices (`--mock <dir>`, `--demo`), also rendered by `--screenshot`.
+// GRBM is not read from the mock device (0%), and the panels of debugfs, the crash dumps,
+// the device list and the control features are not shown, since they are for the local system.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use egui::util::History;
+use eframe::egui;
+
+use libamdgpu_top::AMDGPU::{GpuMetrics, GPU_INFO};
+use libamdgpu_top::{HistoryTiers, MockDevice, NumberFormat, SAMPLE_BUS, SessionOptions, SessionSummary, TempUnit};
+use libamdgpu_top::priv_helper::ControlCapabilities;
+use libamdgpu_top::stat::{self, gpu_metrics_util::GpuMetricsTime, PerfCounter, Smoothing};
+
+use crate::{native_options, run_native, CentralData, GuiBackend, HISTORY_LENGTH};
+use crate::app::MyApp;
+use crate::localize::select_language;
+use crate::util::{FdInfoPlot, PeakHolds, PlotOptions, ProcGfxHistory, SensorsHistory, TimelineHistory, TimelineView};
+
+/// The history of a mock device, like the sampling thread of [`run`](crate::run)
+pub(crate) struct MockHistory {
+    grbm: PerfCounter,
+    grbm2: PerfCounter,
+    grbm_history: Vec<History<u8>>,
+    grbm2_history: Vec<History<u8>>,
+    sensors_history: SensorsHistory,
+    fdinfo_history: History<stat::FdInfoUsage>,
+    proc_gfx_history: ProcGfxHistory,
+    timeline: Arc<Mutex<TimelineHistory>>,
+    gpu_metrics_time: GpuMetricsTime,
+    summary: SessionSummary,
+}
+
+impl MockHistory {
+    pub(crate) fn new(mock: &MockDevice) -> Self {
+        let chip_class = mock.ext_info.get_chip_class();
+        let grbm = PerfCounter::new_with_chip_class(stat::PCType::GRBM, chip_class);
+        let grbm2 = PerfCounter::new_with_chip_class(stat::PCType::GRBM2, chip_class);
+
+        Self {
+            grbm_history: vec![History::new(HISTORY_LENGTH, f32::INFINITY); grbm.index.len()],
+            grbm2_history: vec![History::new(HISTORY_LENGTH, f32::INFINITY); grbm2.index.len()],
+            grbm,
+            grbm2,
+            sensors_history: SensorsHistory::default(),
+            fdinfo_history: History::new(HISTORY_LENGTH, f32::INFINITY),
+            proc_gfx_history: ProcGfxHistory::default(),
+            timeline: Arc::new(Mutex::new(TimelineHistory::new(&HistoryTiers::default()))),
+            gpu_metrics_time: GpuMetricsTime::default(),
+            summary: SessionSummary::new(mock.sensors.bus_info),
+        }
+    }
+
+    /// Adds the current values of `mock` at `sec`, call after [`MockDevice::update`]
+    pub(crate) fn add(&mut self, sec: f64, mock: &MockDevice) {
+        // GRBM is not read from the mock device, 0%
+        for (pc, history) in [(&self.grbm, &mut self.grbm_history), (&self.grbm2, &mut self.grbm2_history)] {
+            for (i, h) in history.iter_mut().enumerate() {
+                h.add(sec, pc.get_usage(i));
+            }
+
+            self.summary.add_perf_counter(pc);
+        }
+
+        self.sensors_history.add(sec, &mock.sensors);
+        self.fdinfo_history.add(sec, mock.fdinfo.fold_fdinfo_usage());
+        self.proc_gfx_history.add(sec, &mock.fdinfo.proc_usage);
+        self.summary.add(&mock.sensors, &mock.vram_usage, Some(&mock.activity));
+
+        if let Some(metrics) = &mock.metrics {
+            self.gpu_metrics_time.update(metrics);
+        }
+
+        if let Ok(mut timeline) = self.timeline.lock() {
+            timeline.add(sec, &mock.sensors, &mock.vram_usage, &mock.activity);
+        }
+    }
+
+    pub(crate) fn data(&self, mock: &MockDevice) -> CentralData {
+        CentralData {
+            grbm_history: self.grbm_history.clone(),
+            grbm2_history: self.grbm2_history.clone(),
+            grbm: self.grbm.clone(),
+            grbm2: self.grbm2.clone(),
+            vram_usage: mock.vram_usage.clone(),
+            fdinfo: mock.fdinfo.clone(),
+            fdinfo_history: self.fdinfo_history.clone(),
+            proc_gfx_history: self.proc_gfx_history.clone(),
+            // the hints are read from the local `/proc`
+            media_sessions: Vec::new(),
+            leak_suspects: None,
+            gpu_metrics: mock.metrics.clone().unwrap_or(GpuMetrics::Unknown),
+            gpu_metrics_time: self.gpu_metrics_time.clone(),
+            sensors: mock.sensors.clone(),
+            sensors_history: self.sensors_history.clone(),
+            pcie_bw_history: History::new(HISTORY_LENGTH, f32::INFINITY),
+            timeline: self.timeline.clone(),
+            anomalies: Vec::new(),
+            frame_times: None,
+            stutters: Vec::new(),
+            throttle_marks: Vec::new(),
+            vcn_state: None,
+            fw_error: None,
+            kmsg: None,
+            queue_priority: None,
+            activity_sources: None,
+            crash_dumps: Vec::new(),
+            pm_info: None,
+            bo_stat: None,
+            dpm_clocks: Vec::new(),
+            xgmi: None,
+            summary: self.summary.clone(),
+            interval: mock.fdinfo.interval,
+        }
+    }
+}
+
+pub(crate) fn mock_app(
+    mock: &MockDevice,
+    data: CentralData,
+    lang: Option<&str>,
+    temp_unit: Option<TempUnit>,
+    session: SessionOptions,
+) -> MyApp {
+    let smoothing = Smoothing::default();
+
+    MyApp {
+        command_path: PathBuf::new(),
+        app_device_info: mock.app_device_info(),
+        device_list: Vec::new(),
+        p2p_matrix: None,
+        has_vcn_unified: false,
+        pcie_bw_source: Err("pcie_bw is not available"),
+        fdinfo_sort: Default::default(),
+        reverse_sort: false,
+        fdinfo_raw: false,
+        fdinfo_shared: false,
+        fdinfo_group: Default::default(),
+        fdinfo_plot: Default::default(),
+        top_procs: FdInfoPlot::DEFAULT_TOP_N,
+        instance: None,
+        bo_list: None,
+        smoothing,
+        arc_smoothing: Arc::new(Mutex::new(smoothing)),
+        plot_options: PlotOptions {
+            temp_unit: temp_unit.unwrap_or_default(),
+            ..Default::default()
+        },
+        peaks: PeakHolds::default(),
+        timeline_view: TimelineView::default(),
+        baseline: None,
+        number_format: NumberFormat::default(),
+        control_caps: ControlCapabilities::default(),
+        capabilities: Vec::new(),
+        tuning_profiles: Vec::new(),
+        pending_profile: None,
+        tuning_backup: None,
+        tuning_status: None,
+        crash_dump_status: None,
+        sqtt: None,
+        sqtt_status: None,
+        launch_status: None,
+        umr: None,
+        umr_report: Default::default(),
+        umr_hang: None,
+        umr_inspecting: false,
+        revert_on_exit: false,
+        lang: lang.map(|lang| lang.to_string()),
+        high_contrast: false,
+        session,
+        start: Instant::now(),
+        buf_data: data.clone(),
+        arc_data: Arc::new(Mutex::new(data)),
+        show_sidepanel: true,
+        gl_vendor_info: None,
+    }
+}
+
+/// `--mock <dir>` and `--demo` in GUI mode, the first device is updated every `interval`
+/// (one device per window, like [`run`](crate::run)).
+#[allow(clippy::too_many_arguments)]
+pub fn run_mock(
+    app_name: &str,
+    title_with_version: &str,
+    mut mock: MockDevice,
+    interval: Duration,
+    backend: GuiBackend,
+    lang: Option<&str>,
+    temp_unit: Option<TempUnit>,
+    session: SessionOptions,
+) {
+    let options = native_options(app_name, backend);
+
+    select_language(lang);
+
+    mock.fdinfo.interval = interval;
+
+    let mut history = MockHistory::new(&mock);
+    let app = mock_app(&mock, history.data(&mock), lang, temp_unit, session);
+    let title = format!("{title_with_version} - {}", mock.name);
+
+    {
+        let now = Instant::now();
+        let share_data = app.arc_data.clone();
+        let share_smoothing = app.arc_smoothing.clone();
+
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+
+            if let Ok(smoothing) = share_smoothing.try_lock() {
+                mock.fdinfo.smoothing = *smoothing;
+            }
+
+            mock.update();
+            history.add(now.elapsed().as_secs_f64(), &mock);
+
+            if SAMPLE_BUS.has_subscribers() {
+                SAMPLE_BUS.send(Arc::new(vec![mock.sample()]));
+            }
+
+            let data = history.data(&mock);
+
+            if let Ok(mut share_data) = share_data.lock() {
+                *share_data = data;
+            }
+        });
+    }
+
+    run_native(&title, options, app, temp_unit);
+}
@@ -1,10 +1,14 @@
-use crate::{BASE, HEADING, HISTORY_LENGTH};
+use crate::{BASE, HEADING, HISTORY_LENGTH, fl};
 use eframe::egui::{self, collapsing_header::CollapsingState, FontId, util::History, Id, RichText};
-use libamdgpu_top::{DevicePath, PCI, stat::Sensors};
+use libamdgpu_top::{DevicePath, HistoryTiers, PCI, TempUnit, TieredHistory, VramUsage};
+use libamdgpu_top::stat::{is_vr_compositor, GpuActivity, PeakHold, ProcUsage, Sensors};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
 pub struct DeviceListMenu {
     pub instance: u32,
     pub name: String,
+    pub nickname: Option<String>,
     pub pci: PCI::BUS_INFO,
 }
 
@@ -17,7 +21,9 @@ impl DeviceListMenu {
             amdgpu_dev.get_marketing_name_or_default()
         };
 
-        Some(Self { instance, pci, name })
+        let nickname = device_path.nickname();
+
+        Some(Self { instance, pci, name, nickname })
     }
 }
 
@@ -74,6 +80,207 @@ impl Default for SensorsHistory {
     }
 }
 
+/// (pid, name, GFX usage (%) at each of [`ProcGfxHistory::secs`])
+pub type ProcGfx = (i32, String, VecDeque<i64>);
+
+/// The GFX usage of each process at the same timestamps, for the fdinfo plots by process
+#[derive(Clone, Debug, Default)]
+pub struct ProcGfxHistory {
+    pub secs: VecDeque<f64>,
+    /// The processes without the GFX usage in the time range are removed
+    pub procs: Vec<ProcGfx>,
+}
+
+impl ProcGfxHistory {
+    pub fn add(&mut self, sec: f64, proc_usage: &[ProcUsage]) {
+        self.secs.push_back(sec);
+        let len = self.secs.len();
+
+        for pu in proc_usage {
+            let gfx = pu.usage.gfx.clamp(0, 100);
+
+            match self.procs.iter_mut().find(|(pid, _, _)| *pid == pu.pid) {
+                // the same PID twice in the list
+                Some((_, _, usage)) if usage.len() == len => {},
+                Some((_, _, usage)) => usage.push_back(gfx),
+                None if gfx != 0 => {
+                    let mut usage: VecDeque<i64> = std::iter::repeat_n(0, len - 1).collect();
+                    usage.push_back(gfx);
+                    self.procs.push((pu.pid, pu.name.clone(), usage));
+                },
+                None => {},
+            }
+        }
+
+        // exited processes
+        for (_, _, usage) in self.procs.iter_mut() {
+            if usage.len() < len { usage.push_back(0) }
+        }
+
+        if len > HISTORY_LENGTH.end {
+            self.secs.pop_front();
+
+            for (_, _, usage) in self.procs.iter_mut() {
+                usage.pop_front();
+            }
+        }
+
+        self.procs.retain(|(_, _, usage)| usage.iter().any(|v| *v != 0));
+    }
+
+    /// (index in `procs`, process), sorted by the GFX usage in the time range
+    pub fn procs_by_usage(&self) -> Vec<(usize, &ProcGfx)> {
+        let mut procs: Vec<_> = self.procs.iter().enumerate().collect();
+        procs.sort_by_key(|(_, (_, _, usage))| std::cmp::Reverse(usage.iter().sum::<i64>()));
+
+        procs
+    }
+
+    pub fn vr_compositors(&self) -> impl Iterator<Item = &ProcGfx> {
+        self.procs.iter().filter(|(_, name, _)| is_vr_compositor(name))
+    }
+
+    /// The sum of the GFX usage of the VR compositors and of the other processes (%)
+    pub fn vr_split(&self) -> (Vec<[f64; 2]>, Vec<[f64; 2]>) {
+        let mut compositor: Vec<[f64; 2]> = self.secs.iter().map(|sec| [*sec, 0.0]).collect();
+        let mut others = compositor.clone();
+
+        for (_, name, usage) in &self.procs {
+            let dst = if is_vr_compositor(name) { &mut compositor } else { &mut others };
+
+            for (p, u) in dst.iter_mut().zip(usage.iter()) {
+                p[1] += *u as f64;
+            }
+        }
+
+        (compositor, others)
+    }
+}
+
+/// The frame budgets of the VR headsets, (refresh rate (Hz), ms)
+pub const VR_BUDGETS: [(u32, f64); 2] = [(90, 1000.0 / 90.0), (120, 1000.0 / 120.0)];
+
+/// The peak-hold markers of the VRAM bars and the power/temperature plots,
+/// updated every frame with the latest values
+#[derive(Clone, Debug, Default)]
+pub struct PeakHolds {
+    /// VRAM, CPU-visible VRAM, GTT (MiB)
+    pub vram: [PeakHold; 3],
+    pub power: PeakHold, // W
+    /// Edge, Junction, Memory (C)
+    pub temp: [PeakHold; 3],
+}
+
+impl PeakHolds {
+    pub fn update(&mut self, vram_usage: &VramUsage, sensors: &Sensors, hold: Duration, now: Instant) {
+        let m = &vram_usage.0;
+
+        for (peak, v) in self.vram.iter_mut().zip([&m.vram, &m.cpu_accessible_vram, &m.gtt]) {
+            peak.update((v.heap_usage >> 20) as f64, hold, now);
+        }
+
+        if let Some(power) = sensors.power {
+            self.power.update(power as f64, hold, now);
+        }
+
+        for (peak, temp) in self.temp.iter_mut().zip([
+            &sensors.edge_temp,
+            &sensors.junction_temp,
+            &sensors.memory_temp,
+        ]) {
+            let Some(temp) = temp else { continue };
+            peak.update(temp.current as f64, hold, now);
+        }
+    }
+}
+
+/// A frame over [`libamdgpu_top::stat::FrameTimes::STUTTER_RATIO`] times the median,
+/// with the state of the GPU at the next sample
+#[derive(Debug, Clone)]
+pub struct Stutter {
+    /// seconds since startup
+    pub sec: f64,
+    pub ms: f64,
+    pub median: f64,
+    pub gfx: Option<u16>,
+    pub sclk: Option<u32>,
+    /// empty if not throttled
+    pub throttlers: String,
+}
+
+/// The state of the timeline plot
+#[derive(Debug, Clone)]
+pub struct TimelineView {
+    /// The index of [`TimelineHistory::metrics`]
+    pub metric: usize,
+    /// seconds, `None`: the whole session
+    pub span: Option<f64>,
+    /// Scroll with the latest sample, until the plot is zoomed or dragged
+    pub follow: bool,
+}
+
+impl Default for TimelineView {
+    fn default() -> Self {
+        Self { metric: 0, span: Some(30.0), follow: true }
+    }
+}
+
+/// The main metrics of the whole session, at the resolutions of `--history-tiers`
+#[derive(Debug, Clone)]
+pub struct TimelineHistory {
+    /// (label, unit, history)
+    pub metrics: Vec<(&'static str, &'static str, TieredHistory)>,
+}
+
+impl TimelineHistory {
+    /// (label, unit, the keys in the JSON output)
+    pub const METRICS: [(&'static str, &'static str, [&'static str; 2]); 12] = [
+        ("GFX", "%", ["gpu_activity", "GFX"]),
+        ("Memory", "%", ["gpu_activity", "Memory"]),
+        ("Media", "%", ["gpu_activity", "MediaEngine"]),
+        ("VRAM", "MiB", ["VRAM", "Total VRAM Usage"]),
+        ("GTT", "MiB", ["VRAM", "Total GTT Usage"]),
+        ("SCLK", "MHz", ["Sensors", "GFX_SCLK"]),
+        ("MCLK", "MHz", ["Sensors", "GFX_MCLK"]),
+        ("Power", "W", ["Sensors", "GFX Power"]),
+        ("Edge Temp", "C", ["Sensors", "Edge Temperature"]),
+        ("Junction Temp", "C", ["Sensors", "Junction Temperature"]),
+        ("Memory Temp", "C", ["Sensors", "Memory Temperature"]),
+        ("Fan", "RPM", ["Sensors", "Fan"]),
+    ];
+
+    pub fn new(tiers: &HistoryTiers) -> Self {
+        let metrics = Self::METRICS.iter()
+            .map(|(label, unit, _)| (*label, *unit, TieredHistory::new(tiers)))
+            .collect();
+
+        Self { metrics }
+    }
+
+    pub fn add(&mut self, sec: f64, sensors: &Sensors, vram_usage: &VramUsage, activity: &GpuActivity) {
+        let temp = |t: &Option<libamdgpu_top::AMDGPU::HwmonTemp>| t.as_ref().map(|t| t.current as f64);
+        let vals = [
+            activity.gfx.map(|v| v as f64),
+            activity.umc.map(|v| v as f64),
+            activity.media.map(|v| v as f64),
+            Some((vram_usage.0.vram.heap_usage >> 20) as f64),
+            Some((vram_usage.0.gtt.heap_usage >> 20) as f64),
+            sensors.sclk.map(|v| v as f64),
+            sensors.mclk.map(|v| v as f64),
+            sensors.power.map(|v| v as f64),
+            temp(&sensors.edge_temp),
+            temp(&sensors.junction_temp),
+            temp(&sensors.memory_temp),
+            sensors.fan_rpm.map(|v| v as f64),
+        ];
+
+        for ((_, _, history), val) in self.metrics.iter_mut().zip(vals) {
+            let Some(val) = val else { continue };
+            history.add(sec, val);
+        }
+    }
+}
+
 pub fn label(text: &str, font: FontId) -> egui::Label {
     egui::Label::new(RichText::new(text).font(font)).sense(egui::Sense::click())
 }
@@ -125,3 +332,303 @@ pub fn collapsing(
 pub fn rt_base<T: Into<String>>(s: T) -> RichText {
     RichText::new(s.into()).font(BASE)
 }
+
+/// Plot series: (name, [[sec, value]])
+pub type PlotSeries = (String, Vec<[f64; 2]>);
+
+/// The variants of the fdinfo plot
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FdInfoPlot {
+    /// The total usage of each engine
+    #[default]
+    Engines,
+    /// The GFX usage of each process as a layer of the total
+    Stacked,
+    /// The GFX usage of the top N processes as lines
+    TopProcesses,
+}
+
+impl FdInfoPlot {
+    pub const ALL: [Self; 3] = [Self::Engines, Self::Stacked, Self::TopProcesses];
+    pub const DEFAULT_TOP_N: usize = 5;
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Engines => fl!("fdinfo_plot_engines"),
+            Self::Stacked => fl!("fdinfo_plot_stacked"),
+            Self::TopProcesses => fl!("fdinfo_plot_top"),
+        }
+    }
+}
+
+/// The colors of the processes in the plots, same as the auto colors of egui_plot.
+/// `i` is the index in [`ProcGfxHistory::procs`], to keep the color of a process between the plots.
+pub fn proc_color(i: usize) -> egui::Color32 {
+    let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0;
+    let h = (i as f32 * golden_ratio).fract();
+
+    egui::epaint::Hsva::new(h, 0.85, 0.5, 1.0).into()
+}
+
+/// RFC 4180: a field with a comma, a quote or a line break is quoted, and quotes are doubled
+fn csv_field(s: &str) -> std::borrow::Cow<'_, str> {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\"")).into()
+    } else {
+        s.into()
+    }
+}
+
+/// CSV of the plot series, one row per time (in ms) of any series, an empty field if a series has no point at the time
+pub fn plot_series_csv(series: &[PlotSeries]) -> String {
+    use std::collections::BTreeMap;
+    use std::fmt::Write;
+
+    let mut buf = String::from("time_s");
+
+    for (name, _) in series {
+        let _ = write!(buf, ",{}", csv_field(name));
+    }
+    buf.push('\n');
+
+    let mut rows: BTreeMap<i64, Vec<Option<f64>>> = BTreeMap::new();
+
+    for (i, (_, points)) in series.iter().enumerate() {
+        for [sec, val] in points {
+            let row = rows.entry((sec * 1000.0).round() as i64).or_insert_with(|| vec![None; series.len()]);
+            row[i] = Some(*val);
+        }
+    }
+
+    for (ms, row) in rows {
+        let _ = write!(buf, "{:.3}", ms as f64 / 1000.0);
+
+        for val in row {
+            match val {
+                Some(val) => { let _ = write!(buf, ",{val}"); },
+                None => buf.push(','),
+            }
+        }
+        buf.push('\n');
+    }
+
+    buf
+}
+
+/// Saves to `$XDG_DATA_HOME/amdgpu_top/csv`
+fn save_plot_csv(name: &str, csv: &str) -> std::io::Result<std::path::PathBuf> {
+    let dir = libamdgpu_top::data_dir()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "$XDG_DATA_HOME and $HOME are not set"))?
+        .join("csv");
+    let name: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = dir.join(format!("amdgpu_top_{name}_{secs}.csv"));
+
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&path, csv)?;
+
+    Ok(path)
+}
+
+fn csv_status_id() -> Id {
+    Id::new("plot csv status")
+}
+
+/// The result of the last "Save as CSV", shown in the menu bar
+pub fn csv_status(ctx: &egui::Context) -> Option<String> {
+    ctx.data(|d| d.get_temp(csv_status_id()))
+}
+
+pub fn clear_csv_status(ctx: &egui::Context) {
+    ctx.data_mut(|d| d.remove::<String>(csv_status_id()));
+}
+
+/// Label for screen readers: the plot name and the latest value of each series
+fn plot_accessible_label(name: &str, series: &[PlotSeries]) -> String {
+    let values: Vec<String> = series
+        .iter()
+        .filter_map(|(s, points)| Some(format!("{s} {}", points.last()?[1])))
+        .collect();
+
+    format!("{name}: {}", values.join(", "))
+}
+
+/// Right-click menu to copy the plot series as CSV to the clipboard or save to a file
+pub fn plot_context_menu(response: egui::Response, name: &str, series: &[PlotSeries]) {
+    response.widget_info(|| egui::WidgetInfo::labeled(
+        egui::WidgetType::Other,
+        plot_accessible_label(name, series),
+    ));
+    response.context_menu(|ui| {
+        if ui.button(fl!("copy_csv")).clicked() {
+            let csv = plot_series_csv(series);
+            ui.output_mut(|o| o.copied_text = csv);
+            ui.close_menu();
+        }
+
+        if ui.button(fl!("save_csv")).clicked() {
+            let status = match save_plot_csv(name, &plot_series_csv(series)) {
+                Ok(path) => fl!("csv_saved", path = path.display().to_string()),
+                Err(err) => format!("{}: {err}", fl!("failed_to_save")),
+            };
+            ui.ctx().data_mut(|d| d.insert_temp(csv_status_id(), status));
+            ui.close_menu();
+        }
+    });
+}
+
+/// Black background, white text and thick outlines of the widgets
+pub fn high_contrast_visuals() -> egui::Visuals {
+    use egui::{Color32, Stroke};
+
+    let mut visuals = egui::Visuals::dark();
+
+    visuals.override_text_color = Some(Color32::WHITE);
+    visuals.panel_fill = Color32::BLACK;
+    visuals.window_fill = Color32::BLACK;
+    visuals.extreme_bg_color = Color32::BLACK;
+    visuals.faint_bg_color = Color32::from_gray(40);
+    visuals.selection.bg_fill = Color32::from_rgb(0, 80, 200);
+    visuals.selection.stroke = Stroke::new(2.0, Color32::WHITE);
+
+    for w in [
+        &mut visuals.widgets.noninteractive,
+        &mut visuals.widgets.inactive,
+        &mut visuals.widgets.hovered,
+        &mut visuals.widgets.active,
+        &mut visuals.widgets.open,
+    ] {
+        w.fg_stroke = Stroke::new(1.5, Color32::WHITE);
+        w.bg_stroke = Stroke::new(1.5, Color32::WHITE);
+    }
+
+    visuals.widgets.hovered.bg_stroke.color = Color32::YELLOW;
+    visuals.widgets.active.bg_stroke.color = Color32::YELLOW;
+
+    visuals
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PowerUnit {
+    #[default]
+    Watt,
+    MilliWatt,
+}
+
+impl PowerUnit {
+    pub fn scale(&self) -> u32 {
+        match self {
+            Self::Watt => 1,
+            Self::MilliWatt => 1000,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Watt => "W",
+            Self::MilliWatt => "mW",
+        }
+    }
+}
+
+/// y-axis options for plots, saved to the eframe storage
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PlotOptions {
+    pub pcie_bw_log_scale: bool,
+    /// Log scale of VRAM and GTT in the timeline
+    pub vram_log_scale: bool,
+    pub temp_unit: TempUnit,
+    pub power_unit: PowerUnit,
+    /// The hold time of the peak markers in seconds, 0: disabled
+    pub peak_hold: u32,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self {
+            pcie_bw_log_scale: false,
+            vram_log_scale: false,
+            temp_unit: TempUnit::default(),
+            power_unit: PowerUnit::default(),
+            peak_hold: Self::DEFAULT_PEAK_HOLD,
+        }
+    }
+}
+
+impl PlotOptions {
+    pub const STORAGE_KEY: &'static str = "plot_options";
+    pub const DEFAULT_PEAK_HOLD: u32 = 3;
+    pub const MAX_PEAK_HOLD: u32 = 30;
+
+    pub fn peak_hold(&self) -> Duration {
+        Duration::from_secs(self.peak_hold as u64)
+    }
+
+    /// e.g. "pcie_bw_log_scale=1,vram_log_scale=0,temp_unit=F,power_unit=mW,peak_hold=3"
+    pub fn parse(s: &str) -> Self {
+        let mut opt = Self::default();
+
+        for (key, val) in s.split(',').filter_map(|kv| kv.split_once('=')) {
+            match (key.trim(), val.trim()) {
+                ("pcie_bw_log_scale", val) => opt.pcie_bw_log_scale = val == "1",
+                ("vram_log_scale", val) => opt.vram_log_scale = val == "1",
+                ("temp_unit", "F") => opt.temp_unit = TempUnit::Fahrenheit,
+                ("power_unit", "mW") => opt.power_unit = PowerUnit::MilliWatt,
+                ("peak_hold", val) => if let Ok(v) = val.parse::<u32>() {
+                    opt.peak_hold = v.min(Self::MAX_PEAK_HOLD);
+                },
+                _ => {},
+            }
+        }
+
+        opt
+    }
+}
+
+impl std::fmt::Display for PlotOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "pcie_bw_log_scale={},vram_log_scale={},temp_unit={},power_unit={},peak_hold={}",
+            self.pcie_bw_log_scale as u8,
+            self.vram_log_scale as u8,
+            self.temp_unit.label(),
+            self.power_unit.label(),
+            self.peak_hold,
+        )
+    }
+}
+
+/// log10(v + 1), to plot values spanning several orders of magnitude
+pub fn to_log_scale(v: f64) -> f64 {
+    (v + 1.0).log10()
+}
+
+pub fn from_log_scale(v: f64) -> f64 {
+    10f64.powf(v) - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_join_on_time() {
+        let series: Vec<PlotSeries> = vec![
+            ("GFX".to_string(), vec![[0.0, 1.0], [0.5, 2.0]]),
+            ("a, \"b\"".to_string(), vec![[0.5, 3.0], [1.0, 4.0]]),
+        ];
+
+        assert_eq!(
+            plot_series_csv(&series),
+            "time_s,GFX,\"a, \"\"b\"\"\"\n0.000,1,\n0.500,2,3\n1.000,,4\n",
+        );
+    }
+
+    #[test]
+    fn csv_empty() {
+        assert_eq!(plot_series_csv(&[]), "time_s\n");
+    }
+}
@@ -0,0 +1,60 @@
+// The golden-image test of the GUI layout, `--screenshot` of `docs/mock/navi31`
+// compared with `tests/golden/navi31.png`.
+// After an intended change of the layout, regenerate the golden image with:
+//   AMDGPU_TOP_UPDATE_GOLDEN=1 cargo test -p amdgpu_top_gui --test screenshot
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use libamdgpu_top::MockDevice;
+
+/// The max difference of a channel, for the rounding of the rasterizer on the other CPUs
+const CHANNEL_TOLERANCE: u8 = 8;
+/// The max ratio of the pixels over `CHANNEL_TOLERANCE`
+const PIXEL_TOLERANCE: f64 = 0.001;
+
+fn manifest_dir() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn decode(path: &Path) -> ([u32; 2], Vec<u8>) {
+    let decoder = png::Decoder::new(File::open(path).unwrap());
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+
+    buf.truncate(info.buffer_size());
+
+    ([info.width, info.height], buf)
+}
+
+#[test]
+fn navi31() {
+    let golden = manifest_dir().join("tests/golden/navi31.png");
+    let out = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("navi31.png");
+    let mut mock = MockDevice::load(manifest_dir().join("../../docs/mock/navi31"), Duration::from_secs(1)).unwrap();
+
+    amdgpu_top_gui::screenshot(&mut mock, Some("en"), None, &out).unwrap();
+
+    if std::env::var_os("AMDGPU_TOP_UPDATE_GOLDEN").is_some() {
+        std::fs::copy(&out, &golden).unwrap();
+        return;
+    }
+
+    let (golden_size, golden_rgba) = decode(&golden);
+    let (size, rgba) = decode(&out);
+
+    assert_eq!(size, golden_size, "the size of the screenshot ({}) differs from the golden image", out.display());
+
+    let diff = rgba.chunks_exact(4).zip(golden_rgba.chunks_exact(4))
+        .filter(|(a, b)| a.iter().zip(b.iter()).any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE))
+        .count();
+    let pixels = (size[0] * size[1]) as f64;
+
+    assert!(
+        diff as f64 / pixels <= PIXEL_TOLERANCE,
+        "{diff} pixels of the screenshot ({}) differ from the golden image ({})",
+        out.display(),
+        golden.display(),
+    );
+}
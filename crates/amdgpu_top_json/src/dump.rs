@@ -1,29 +1,135 @@
 use libamdgpu_top::{
     PCI,
     AMDGPU::{
-        VIDEO_CAPS::CODEC,
+        VIDEO_CAPS::{CAP_TYPE, CODEC},
         DeviceHandle,
         GPU_INFO,
     },
     AppDeviceInfo,
+    Capability,
+    codec_level_name,
+    codec_max_refs,
+    codec_profiles,
+    DeviceNicknames,
     DevicePath,
+    get_firmware_versions,
+    is_harvested,
+    IpHwFilter,
+    priv_helper::ControlCapabilities,
     stat::Sensors,
 };
-use libamdgpu_top::AMDGPU::{drm_amdgpu_info_device, drm_amdgpu_memory_info};
+use libamdgpu_top::AMDGPU::{drm_amdgpu_info_device, drm_amdgpu_memory_info, IpDieEntry};
 use serde_json::{json, Map, Value};
 use crate::{amdgpu_top_version, OutputJson};
 
+/// `--dump` in JSON mode, the static info of the devices keyed by the PCI bus id,
+/// with the firmware versions and the capability matrix, for the hardware inventory.
+/// The devices that cannot be opened are omitted.
 pub fn dump_json(device_path_list: &[DevicePath]) {
-    let vec_json_info: Vec<Value> = device_path_list.iter().map(|device_path| {
+    let mut m = Map::new();
+
+    for device_path in device_path_list {
+        let Ok(amdgpu_dev) = device_path.init() else { continue };
+        let Ok(pci_bus) = amdgpu_dev.get_pci_bus_info() else { continue };
+        let Ok(ext_info) = amdgpu_dev.device_info() else { continue };
+        let Ok(memory_info) = amdgpu_dev.memory_info() else { continue };
+
+        let mut info = json_info(&amdgpu_dev, &pci_bus, &ext_info, &memory_info);
+        let sensors = Sensors::new(&amdgpu_dev, &pci_bus, &ext_info);
+        let app_info = AppDeviceInfo::new(&amdgpu_dev, &ext_info, &memory_info, &sensors);
+        let caps = ControlCapabilities::detect(&pci_bus);
+        let capabilities: Map<String, Value> = Capability::detect_all(&app_info, &caps).into_iter().map(|cap| (
+            cap.name.to_string(),
+            json!({
+                "status": cap.status.to_string(),
+                "detail": cap.detail,
+            }),
+        )).collect();
+        let firmware: Map<String, Value> = get_firmware_versions(&amdgpu_dev).into_iter().map(|fw| (
+            fw.fw_type.to_string(),
+            json!({
+                "feature": fw.feature,
+                "version": fw.version,
+            }),
+        )).collect();
+
+        info["Firmware"] = firmware.into();
+        info["Capabilities"] = capabilities.into();
+        m.insert(pci_bus.to_string(), info);
+    }
+
+    println!("{}", Value::Object(m));
+}
+
+/// `--list` in JSON mode, an array of the devices for the scripts.
+/// The names are `null` if the device cannot be opened (e.g. permission denied).
+pub fn list_json(device_path_list: &[DevicePath]) {
+    let vec_json: Vec<Value> = device_path_list.iter().map(|device_path| {
+        let amdgpu_dev = device_path.init().ok();
+        let marketing_name = amdgpu_dev.as_ref().map(|dev| dev.get_marketing_name_or_default());
+        let asic_name = amdgpu_dev.as_ref()
+            .and_then(|dev| dev.device_info().ok())
+            .map(|ext_info| ext_info.get_asic_name().to_string());
+
+        json!({
+            "Instance": device_path.get_instance_number(),
+            "PCI": device_path.pci.map(|pci| pci.to_string()),
+            "DeviceName": marketing_name,
+            "ASIC Name": asic_name,
+            "Nickname": device_path.nickname(),
+            "Device Nodes": device_nodes_json(device_path),
+        })
+    }).collect();
+
+    println!("{}", Value::Array(vec_json));
+}
+
+fn device_nodes_json(device_path: &DevicePath) -> Value {
+    json!({
+        "card": device_path.card,
+        "render": device_path.render,
+        "by-path card": device_path.by_path_card(),
+        "by-path render": device_path.by_path_render(),
+    })
+}
+
+/// `--dump-ip-discovery`, all instances of the IP blocks
+pub fn dump_ip_discovery_json(device_path_list: &[DevicePath], filter: &IpHwFilter) {
+    let vec_json: Vec<Value> = device_path_list.iter().map(|device_path| {
         let Ok(amdgpu_dev) = device_path.init() else { return Value::Null };
         let Ok(pci_bus) = amdgpu_dev.get_pci_bus_info() else { return Value::Null };
-        let Ok(ext_info) = amdgpu_dev.device_info() else { return Value::Null };
-        let Ok(memory_info) = amdgpu_dev.memory_info() else { return Value::Null };
+        let ip_die_entries = IpDieEntry::get_all_entries_from_sysfs(pci_bus.get_sysfs_path());
+        let dies: Vec<Value> = ip_die_entries.iter().map(|die| {
+            let ip_hw: Vec<Value> = filter.filter(die).map(|ip_hw| {
+                let instances: Vec<Value> = ip_hw.instances.iter().map(|inst| json!({
+                    "instance": inst.num_instance,
+                    "major": inst.major,
+                    "minor": inst.minor,
+                    "revision": inst.revision,
+                    "harvest": inst.harvest,
+                    "harvested": is_harvested(inst),
+                    "base_address": inst.base_address,
+                })).collect();
+
+                json!({
+                    "hw_id": ip_hw.hw_id.to_string(),
+                    "instances": instances,
+                })
+            }).collect();
 
-        json_info(&amdgpu_dev, &pci_bus, &ext_info, &memory_info)
+            json!({
+                "die_id": die.die_id,
+                "ip_hw": ip_hw,
+            })
+        }).collect();
+
+        json!({
+            "PCI": pci_bus.to_string(),
+            "dies": dies,
+        })
     }).collect();
 
-    println!("{}", Value::Array(vec_json_info));
+    println!("{}", Value::Array(vec_json));
 }
 
 pub fn json_info(
@@ -85,10 +191,17 @@ pub fn json_info(
             (CODEC::VP9, decode.vp9, encode.vp9),
             (CODEC::AV1, decode.av1, encode.av1),
         ] {
-            let [dec, enc] = [dec_cap, enc_cap].map(|cap| {
+            let [dec, enc] = [(dec_cap, CAP_TYPE::DECODE), (enc_cap, CAP_TYPE::ENCODE)].map(|(cap, cap_type)| {
                 cap.map_or(Value::Null, |cap| json!({
                     "width": cap.max_width,
                     "height": cap.max_height,
+                    "max_pixels_per_frame": cap.max_pixels_per_frame,
+                    "max_level": (cap.max_level != 0).then(|| json!({
+                        "value": cap.max_level,
+                        "name": codec_level_name(codec, cap.max_level),
+                    })),
+                    "profiles": codec_profiles(codec, cap_type),
+                    "max_refs": codec_max_refs(codec, &cap),
                 }))
             });
 
@@ -106,11 +219,27 @@ pub fn json_info(
         Value::Null
     };
 
+    let nickname = DeviceNicknames::load().get(pci_bus).map(|s| s.to_string());
+    let device_nodes = DevicePath::try_from(*pci_bus).map_or(Value::Null, |device_path| device_nodes_json(&device_path));
+
+    let iommu_group = info.placement.iommu_group.as_ref().map_or(Value::Null, |group| json!({
+        "id": group.id,
+        "devices": group.devices.iter().map(|dev| json!({
+            "PCI": dev.pci,
+            "driver": dev.driver,
+        })).collect::<Vec<_>>(),
+    }));
+
     let json = json!({
         "amdgpu_top_version": amdgpu_top_version(),
         "drm_version": drm,
         "DeviceName": info.marketing_name,
+        "Nickname": nickname,
         "PCI": info.pci_bus.to_string(),
+        "Device Nodes": device_nodes,
+        "NUMA Node": info.placement.numa_node,
+        "Local CPUs": info.placement.local_cpulist,
+        "IOMMU Group": iommu_group,
         "DeviceID": ext_info.device_id(),
         "RevisionID": ext_info.pci_rev_id(),
         "GPU Type": if ext_info.is_apu() { "APU" } else { "dGPU" },
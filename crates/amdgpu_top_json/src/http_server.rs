@@ -0,0 +1,519 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use serde_json::{json, Value};
+use libamdgpu_top::config_dir;
+use libamdgpu_top::DeviceSample;
+use libamdgpu_top::tls::{Stream, TlsAcceptor};
+use crate::output_json::proc_usage_json;
+
+const LONG_POLL_TIMEOUT: Duration = Duration::from_secs(30);
+/// For the request line and the headers, a slow client is disconnected
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// A client which does not receive (e.g. a stalled SSE stream) is disconnected
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+/// The max size of the request line and the headers
+const MAX_REQUEST_LEN: u64 = 8 * 1024;
+/// The max number of the connections handled at the same time (including the SSE streams),
+/// the others are rejected with 503
+pub(crate) const MAX_CONNECTIONS: usize = 32;
+const CONFIG_FILE_NAME: &str = "server.conf";
+#[cfg(feature = "web_ui")]
+const INDEX_HTML: &str = include_str!("../assets/index.html");
+
+#[derive(Default)]
+struct HttpState {
+    seq: u64,
+    devices: Vec<HttpDevice>,
+}
+
+struct HttpDevice {
+    pci: String,
+    instance: Option<u32>,
+    info: Value,
+    metrics: Value,
+    processes: Value,
+}
+
+impl HttpState {
+    fn find(&self, pci: &str) -> Option<&HttpDevice> {
+        self.devices.iter().find(|d| d.pci == pci)
+    }
+
+    fn devices_json(&self) -> Value {
+        self.devices.iter().map(|d| json!({
+            "pci": d.pci,
+            "instance": d.instance,
+            "info": d.info,
+        })).collect()
+    }
+}
+
+/// Settings for the listening modes, stored in `$XDG_CONFIG_HOME/amdgpu_top/server.conf`
+/// (or `~/.config/amdgpu_top/`).
+///
+/// ```text
+/// # requests without `Authorization: Bearer <token>` (or `?token=<token>`) are rejected
+/// token = <token>
+/// # `Access-Control-Allow-Origin` of the responses, for the dashboards on the other origins
+/// # (e.g. `https://grafana.example.com`, `*`), not sent by default
+/// cors_origin = <origin>
+/// # TLS for `--listen` and `--serve`, the certificate chain and the private key (PEM),
+/// # requires the build with the "tls" feature
+/// cert = <path>
+/// key = <path>
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpServerConfig {
+    pub token: Option<String>,
+    pub cors_origin: Option<String>,
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+}
+
+impl HttpServerConfig {
+    pub fn load() -> Self {
+        let Some(path) = Self::config_path() else { return Self::default() };
+        let Ok(s) = fs::read_to_string(path) else { return Self::default() };
+
+        Self::parse(&s)
+    }
+
+    pub fn parse(s: &str) -> Self {
+        let mut config = Self::default();
+
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            let Some((key, val)) = line.split_once('=') else { continue };
+            let val = val.trim();
+
+            if val.is_empty() { continue }
+
+            match key.trim() {
+                "token" => config.token = Some(val.to_string()),
+                "cors_origin" => config.cors_origin = Some(val.to_string()),
+                "cert" => config.cert = Some(PathBuf::from(val)),
+                "key" => config.key = Some(PathBuf::from(val)),
+                _ => {},
+            }
+        }
+
+        config
+    }
+
+    pub fn config_path() -> Option<PathBuf> {
+        Some(config_dir()?.join(CONFIG_FILE_NAME))
+    }
+
+    /// `None` without `cert` and `key`, `Err` if only one of them is set or they can not be loaded
+    pub fn tls_acceptor(&self) -> io::Result<Option<TlsAcceptor>> {
+        match (&self.cert, &self.key) {
+            (Some(cert), Some(key)) => TlsAcceptor::new(cert, key).map(Some),
+            (None, None) => Ok(None),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "TLS requires both `cert` and `key` in server.conf",
+            )),
+        }
+    }
+
+    /// For `--serve`
+    pub fn check_token(&self, token: Option<&str>) -> bool {
+        let Some(expected) = &self.token else { return true };
+
+        token.is_some_and(|t| constant_time_eq(t.as_bytes(), expected.as_bytes()))
+    }
+
+    fn is_authorized(&self, req: &Request) -> bool {
+        let Some(token) = &self.token else { return true };
+        let query_token = req.query.split('&').find_map(|q| q.strip_prefix("token="));
+
+        [req.bearer.as_deref(), query_token]
+            .into_iter()
+            .flatten()
+            .any(|t| constant_time_eq(t.as_bytes(), token.as_bytes()))
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Embedded HTTP server for JSON mode.
+///
+/// * `GET /` (web dashboard, with the `web_ui` feature)
+/// * `GET /devices`
+/// * `GET /devices/<pci>/metrics` (`?wait` waits for the next update, long-poll)
+/// * `GET /devices/<pci>/processes` (`?wait`)
+/// * `GET /events`, `GET /devices/<pci>/events` (Server-Sent Events, every update)
+pub struct HttpServer {
+    state: Arc<(Mutex<HttpState>, Condvar)>,
+}
+
+impl HttpServer {
+    pub fn bind<A: ToSocketAddrs>(addr: A, config: HttpServerConfig) -> io::Result<Self> {
+        let tls = config.tls_acceptor()?;
+        let listener = TcpListener::bind(addr)?;
+        let state: Arc<(Mutex<HttpState>, Condvar)> = Default::default();
+        let server_state = state.clone();
+        let config = Arc::new(config);
+
+        if config.token.is_none() && listener.local_addr().is_ok_and(|addr| !addr.ip().is_loopback()) {
+            eprintln!(
+                "Warning: the HTTP server is listening on a non-loopback address without a token ({:?})",
+                HttpServerConfig::config_path().unwrap_or_default(),
+            );
+        }
+
+        std::thread::spawn(move || {
+            let connections = Arc::new(AtomicUsize::new(0));
+
+            for tcp in listener.incoming().flatten() {
+                if tcp.set_read_timeout(Some(READ_TIMEOUT)).is_err()
+                || tcp.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+                    continue;
+                }
+
+                let Some(guard) = ConnectionGuard::acquire(&connections) else {
+                    // not for TLS, the handshake would block the accept loop
+                    if tls.is_none() {
+                        let _ = respond(&mut Stream::Plain(tcp), &config, "503 Service Unavailable", "text/plain", "503 Service Unavailable\n");
+                    }
+                    continue;
+                };
+                let stream = match &tls {
+                    Some(tls) => match tls.accept(tcp) {
+                        Ok(stream) => stream,
+                        Err(_) => continue,
+                    },
+                    None => Stream::Plain(tcp),
+                };
+                let state = server_state.clone();
+                let config = config.clone();
+
+                std::thread::spawn(move || {
+                    let _guard = guard;
+                    let _ = handle(stream, &state, &config);
+                });
+            }
+        });
+
+        Ok(Self { state })
+    }
+
+    /// `devices_json`: the JSON of each device, with the "Info"
+    pub fn update(&self, devices: &[DeviceSample], devices_json: &[Value]) {
+        let (lock, cvar) = &*self.state;
+        let Ok(mut state) = lock.lock() else { return };
+
+        state.seq += 1;
+        state.devices = devices.iter().zip(devices_json).map(|(device, metrics)| HttpDevice {
+            pci: device.device_path.pci.map(|pci| pci.to_string()).unwrap_or_default(),
+            instance: device.instance,
+            info: metrics["Info"].clone(),
+            metrics: metrics.clone(),
+            processes: proc_usage_json(&device.proc_usage),
+        }).collect();
+
+        cvar.notify_all();
+    }
+}
+
+/// Counts the connections being handled, released on drop
+pub(crate) struct ConnectionGuard(Arc<AtomicUsize>);
+
+impl ConnectionGuard {
+    pub(crate) fn acquire(connections: &Arc<AtomicUsize>) -> Option<Self> {
+        connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| (n < MAX_CONNECTIONS).then_some(n + 1))
+            .ok()
+            .map(|_| Self(connections.clone()))
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+struct Request {
+    path: String,
+    query: String,
+    bearer: Option<String>,
+}
+
+/// `Err` of `InvalidData` if the request line and the headers are longer than [`MAX_REQUEST_LEN`]
+fn read_request<R: Read>(stream: R) -> io::Result<Option<Request>> {
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_LEN));
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+
+    let mut bearer = None;
+
+    loop {
+        let mut header = String::new();
+
+        if reader.read_line(&mut header)? == 0 {
+            if reader.get_ref().limit() == 0 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "request too large"));
+            }
+
+            break;
+        }
+
+        if header.trim().is_empty() { break }
+
+        let Some((name, val)) = header.split_once(':') else { continue };
+
+        if name.trim().eq_ignore_ascii_case("authorization") {
+            bearer = val.trim().strip_prefix("Bearer ").map(|t| t.trim().to_string());
+        }
+    }
+
+    let mut split = line.split_whitespace();
+    let (Some("GET"), Some(target)) = (split.next(), split.next()) else { return Ok(None) };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    Ok(Some(Request {
+        path: path.trim_end_matches('/').to_string(),
+        query: query.to_string(),
+        bearer,
+    }))
+}
+
+fn handle(
+    mut stream: Stream,
+    state: &(Mutex<HttpState>, Condvar),
+    config: &HttpServerConfig,
+) -> io::Result<()> {
+    let res = route(&mut stream, state, config);
+    let _ = stream.close();
+
+    res
+}
+
+fn route(
+    stream: &mut Stream,
+    state: &(Mutex<HttpState>, Condvar),
+    config: &HttpServerConfig,
+) -> io::Result<()> {
+    let req = match read_request(&mut *stream) {
+        Ok(Some(req)) => req,
+        Ok(None) => return respond(stream, config, "405 Method Not Allowed", "text/plain", "405 Method Not Allowed\n"),
+        Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+            let status = "431 Request Header Fields Too Large";
+
+            return respond(stream, config, status, "text/plain", &format!("{status}\n"));
+        },
+        Err(err) => return Err(err),
+    };
+
+    if !config.is_authorized(&req) {
+        return write!(
+            stream,
+            "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Bearer\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        );
+    }
+    let wait = req.query.split('&').any(|q| q == "wait" || q.starts_with("wait="));
+    let segments: Vec<&str> = req.path.split('/').filter(|s| !s.is_empty()).collect();
+
+    match segments.as_slice() {
+        #[cfg(feature = "web_ui")]
+        [] | ["index.html"] => respond(stream, config, "200 OK", "text/html; charset=utf-8", INDEX_HTML),
+        ["devices"] => {
+            let body = state.0.lock().map(|s| s.devices_json()).unwrap_or_default();
+            respond_json(stream, config, &body)
+        },
+        ["devices", pci, "metrics"] => respond_device(stream, config, state, pci, wait, |d| &d.metrics),
+        ["devices", pci, "processes"] => respond_device(stream, config, state, pci, wait, |d| &d.processes),
+        ["events"] => sse(stream, config, state, None),
+        ["devices", pci, "events"] => sse(stream, config, state, Some(pci)),
+        _ => respond(stream, config, "404 Not Found", "text/plain", "404 Not Found\n"),
+    }
+}
+
+/// `Access-Control-Allow-Origin` of `cors_origin` in `server.conf`, empty if not set
+fn cors_header(config: &HttpServerConfig) -> String {
+    config.cors_origin.as_ref().map_or(String::new(), |origin| format!("Access-Control-Allow-Origin: {origin}\r\n"))
+}
+
+fn respond(stream: &mut Stream, config: &HttpServerConfig, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n{body}",
+        body.len(),
+        cors_header(config),
+    )
+}
+
+fn respond_json(stream: &mut Stream, config: &HttpServerConfig, body: &Value) -> io::Result<()> {
+    respond(stream, config, "200 OK", "application/json", &body.to_string())
+}
+
+fn respond_device<F: Fn(&HttpDevice) -> &Value>(
+    stream: &mut Stream,
+    config: &HttpServerConfig,
+    state: &(Mutex<HttpState>, Condvar),
+    pci: &str,
+    wait: bool,
+    f: F,
+) -> io::Result<()> {
+    let (lock, cvar) = state;
+    let Ok(mut s) = lock.lock() else { return Ok(()) };
+
+    if wait {
+        let seq = s.seq;
+        let Ok((guard, _)) = cvar.wait_timeout_while(s, LONG_POLL_TIMEOUT, |s| s.seq == seq) else {
+            return Ok(());
+        };
+        s = guard;
+    }
+
+    let body = s.find(pci).map(|d| f(d).clone());
+    drop(s);
+
+    match body {
+        Some(body) => respond_json(stream, config, &body),
+        None => respond(stream, config, "404 Not Found", "text/plain", "404 Not Found\n"),
+    }
+}
+
+// ref: https://html.spec.whatwg.org/multipage/server-sent-events.html
+fn sse(
+    stream: &mut Stream,
+    config: &HttpServerConfig,
+    state: &(Mutex<HttpState>, Condvar),
+    pci: Option<&str>,
+) -> io::Result<()> {
+    let (lock, cvar) = state;
+
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n{}Connection: keep-alive\r\n\r\n",
+        cors_header(config),
+    )?;
+
+    let mut seq = 0;
+
+    loop {
+        let data = {
+            let Ok(s) = lock.lock() else { return Ok(()) };
+            let Ok((s, _)) = cvar.wait_timeout_while(s, LONG_POLL_TIMEOUT, |s| s.seq == seq) else {
+                return Ok(());
+            };
+
+            if s.seq == seq {
+                None
+            } else {
+                seq = s.seq;
+
+                Some(match pci {
+                    Some(pci) => match s.find(pci) {
+                        Some(d) => json!({ "metrics": d.metrics, "processes": d.processes }),
+                        None => return Ok(()),
+                    },
+                    None => json!({
+                        "devices": s.devices.iter().map(|d| json!({
+                            "pci": d.pci,
+                            "metrics": d.metrics,
+                        })).collect::<Vec<_>>(),
+                    }),
+                })
+            }
+        };
+
+        match data {
+            Some(data) => write!(stream, "event: metrics\nid: {seq}\ndata: {data}\n\n")?,
+            // keep-alive
+            None => write!(stream, ": \n\n")?,
+        }
+
+        stream.flush()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config() {
+        let config = HttpServerConfig::parse("# comment\ntoken = abc\n cors_origin = https://example.com \nfoo = bar\n");
+
+        assert_eq!(config.token.as_deref(), Some("abc"));
+        assert_eq!(config.cors_origin.as_deref(), Some("https://example.com"));
+        assert_eq!(cors_header(&config), "Access-Control-Allow-Origin: https://example.com\r\n");
+
+        let config = HttpServerConfig::parse("token =\ncors_origin=\n");
+
+        assert_eq!(config, HttpServerConfig::default());
+        assert_eq!(cors_header(&config), "");
+    }
+
+    #[test]
+    fn config_tls() {
+        let config = HttpServerConfig::parse("cert = /etc/amdgpu_top/cert.pem\nkey = /etc/amdgpu_top/key.pem\n");
+
+        assert_eq!(config.cert.as_deref(), Some(std::path::Path::new("/etc/amdgpu_top/cert.pem")));
+        assert_eq!(config.key.as_deref(), Some(std::path::Path::new("/etc/amdgpu_top/key.pem")));
+
+        assert!(HttpServerConfig::default().tls_acceptor().unwrap().is_none());
+
+        let config = HttpServerConfig::parse("cert = /etc/amdgpu_top/cert.pem\n");
+
+        assert_eq!(config.tls_acceptor().err().unwrap().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn request() {
+        let req = read_request(&b"GET /devices/0000:03:00.0/metrics/?wait HTTP/1.1\r\nAuthorization: Bearer abc \r\n\r\n"[..])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(req.path, "/devices/0000:03:00.0/metrics");
+        assert_eq!(req.query, "wait");
+        assert_eq!(req.bearer.as_deref(), Some("abc"));
+
+        assert!(read_request(&b"POST / HTTP/1.1\r\n\r\n"[..]).unwrap().is_none());
+        // closed before the end of the headers
+        assert!(read_request(&b"GET / HTTP/1.1\r\nHost: a"[..]).unwrap().is_some());
+    }
+
+    #[test]
+    fn request_too_large() {
+        let mut buf = b"GET / HTTP/1.1\r\n".to_vec();
+
+        buf.extend(std::iter::repeat_n(b"X-Padding: 0123456789abcdef\r\n", 512).flatten());
+        buf.extend_from_slice(b"\r\n");
+
+        let err = read_request(&buf[..]).err().unwrap();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // a header line without the newline
+        let buf = [b'a'; MAX_REQUEST_LEN as usize * 2];
+
+        assert_eq!(read_request(&buf[..]).err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn connection_limit() {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let guards: Vec<_> = (0..MAX_CONNECTIONS).map_while(|_| ConnectionGuard::acquire(&connections)).collect();
+
+        assert_eq!(guards.len(), MAX_CONNECTIONS);
+        assert!(ConnectionGuard::acquire(&connections).is_none());
+
+        drop(guards);
+
+        assert_eq!(connections.load(Ordering::Acquire), 0);
+        assert!(ConnectionGuard::acquire(&connections).is_some());
+    }
+}
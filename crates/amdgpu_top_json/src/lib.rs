@@ -1,14 +1,30 @@
 use libamdgpu_top::AMDGPU::{ASIC_NAME, DeviceHandle, GPU_INFO, GpuMetrics};
-use libamdgpu_top::{DevicePath, stat, VramUsage};
-use stat::{FdInfoStat, GpuActivity, Sensors, PerfCounter, ProcInfo};
+use libamdgpu_top::{AlertCondition, pmu::AmdgpuPmu, Anomaly, AnomalyDetector, DevicePath, DeviceSample, exit_code, exit_code::ExitReason, is_gpu_active, IdleDetector, LeakDetector, SessionOptions, SessionSummary, SAMPLE_BUS, stat, TempUnit, VramUsage};
+use stat::{gpu_metrics_util::get_gpu_metrics, ActivitySources, DpmClockTable, FdInfoGroupBy, FdInfoStat, FwErrorStat, GpuActivity, MediaSessions, Sensors, PerfCounter, PmInfo, ProcInfo, Smoothing, VcnState, XgmiStat};
 use serde_json::{json, Value};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::sync::{Arc, Mutex};
 use std::path::PathBuf;
 
 mod output_json;
+mod output_dir;
+pub use output_dir::JsonOutputDir;
+mod otlp;
+pub use otlp::OtlpExporter;
+mod http_server;
+pub use http_server::{HttpServer, HttpServerConfig};
 mod dump;
-pub use dump::{dump_json, json_info};
+pub use dump::{dump_ip_discovery_json, dump_json, json_info, list_json};
+mod pid;
+pub use pid::run_pid;
+mod mock;
+pub use mock::{mock_line, run_mock};
+mod sample_frontends;
+mod remote_server;
+mod snapshot;
+pub use snapshot::{diff, load_snapshot, snapshot};
+pub use remote_server::RemoteServer;
+pub use sample_frontends::spawn_sample_frontends;
 
 pub fn version_json(title: &str) {
     let version = json!({
@@ -37,6 +53,19 @@ pub struct JsonApp {
     pub interval: Duration,
     pub delay: Duration,
     pub iterations: u32,
+    pub output_dir: Option<JsonOutputDir>,
+    pub otlp: Option<OtlpExporter>,
+    pub http_server: Option<HttpServer>,
+    /// `--serve`, the JSON lines are sent to the clients instead of stdout
+    pub remote: Option<RemoteServer>,
+    /// Only a hint for the consumers, the values are always in Celsius.
+    pub temp_unit: TempUnit,
+    /// `run` returns [`ExitReason::Alert`] when any condition is breached
+    pub fail_on: Vec<AlertCondition>,
+    /// `--duration` and `--summary` (the number of samples is `iterations`)
+    pub session: SessionOptions,
+    /// `--idle-timeout`
+    pub idle: IdleDetector,
 }
 
 impl JsonApp {
@@ -45,6 +74,7 @@ impl JsonApp {
         refresh_period: u64,
         update_process_index_interval: u64,
         iterations: u32,
+        smoothing: Smoothing,
     ) -> Self {
         let period = Duration::from_millis(refresh_period);
         let interval = period.clone();
@@ -52,18 +82,22 @@ impl JsonApp {
         let mut vec_device_info = JsonDeviceInfo::from_device_path_list(device_path_list);
 
         for device in vec_device_info.iter_mut() {
+            device.grbm.smoothing = smoothing;
+            device.grbm2.smoothing = smoothing;
+            device.fdinfo.smoothing = smoothing;
             device.fdinfo.interval = interval;
             device.update(interval);
         }
 
         let base_time = Instant::now();
+        let idle = IdleDetector::default();
 
         {
             let t_index: Vec<(DevicePath, Arc<Mutex<Vec<ProcInfo>>>)> = vec_device_info
                 .iter()
                 .map(|device| (device.device_path.clone(), device.arc_proc_index.clone()))
                 .collect();
-            stat::spawn_update_index_thread(t_index, update_process_index_interval);
+            stat::spawn_update_index_thread(t_index, update_process_index_interval, idle.paused_flag());
         }
 
         Self {
@@ -73,24 +107,48 @@ impl JsonApp {
             interval,
             delay,
             iterations,
+            output_dir: None,
+            otlp: None,
+            http_server: None,
+            remote: None,
+            temp_unit: TempUnit::default(),
+            fail_on: Vec::new(),
+            session: SessionOptions::default(),
+            idle,
         }
     }
 
-    pub fn run(&mut self, title: &str) {
+    pub fn run(&mut self, title: &str) -> ExitReason {
         let mut n = 0;
         let mut buf_json: Vec<Value> = Vec::with_capacity(self.vec_device_info.len());
         let devices_len = self.vec_device_info.len();
+        let display_unit = json!({ "temperature": self.temp_unit.label() });
+        let mut summaries: Vec<SessionSummary> = self.vec_device_info
+            .iter()
+            .map(|device| SessionSummary::new(device.sensors.bus_info))
+            .collect();
 
         loop {
-            for _ in 0..100 {
-                for device in self.vec_device_info.iter_mut() {
-                    device.update_pc();
+            if let Some(device_path) = exit_code::find_lost_device(self.vec_device_info.iter().map(|device| &device.device_path)) {
+                self.session.write_summary(&summaries);
+                return ExitReason::DeviceLost(device_path.clone());
+            }
+
+            let interval = self.idle.interval(self.interval);
+
+            if self.idle.is_paused() {
+                std::thread::sleep(interval);
+            } else {
+                for _ in 0..100 {
+                    for device in self.vec_device_info.iter_mut() {
+                        device.update_pc();
+                    }
+                    std::thread::sleep(self.delay);
                 }
-                std::thread::sleep(self.delay);
             }
 
             for device in self.vec_device_info.iter_mut() {
-                device.update(self.interval);
+                device.update(interval);
 
                 buf_json.push(device.json());
 
@@ -98,25 +156,114 @@ impl JsonApp {
             }
 
             let now = Instant::now();
+            let samples: Vec<DeviceSample> = if self.otlp.is_some() || self.http_server.is_some() || SAMPLE_BUS.has_subscribers() {
+                self.vec_device_info.iter().map(|device| device.sample()).collect()
+            } else {
+                Vec::new()
+            };
+
+            if let Some(otlp) = &mut self.otlp {
+                otlp.export_if_due(&samples);
+            }
+
+            if let Some(http_server) = &self.http_server {
+                http_server.update(&samples, &buf_json);
+            }
 
-            println!("{}", json!({
-                "period": {
-                    "duration": now.duration_since(self.base_time).as_millis(),
-                    "unit": "ms",
-                },
-                "devices": Value::Array(buf_json.clone()),
-                "devices_len": devices_len,
-                "amdgpu_top_version": amdgpu_top_version(),
-                "title": title,
-            }));
+            if SAMPLE_BUS.has_subscribers() {
+                SAMPLE_BUS.send(Arc::new(samples));
+            }
+
+            if let Some(output_dir) = &mut self.output_dir {
+                let unix_time = SystemTime::now().duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_millis())
+                    .unwrap_or(0);
+
+                for (device, device_json) in self.vec_device_info.iter().zip(buf_json.iter()) {
+                    let name = match device.device_path.pci {
+                        Some(pci) => pci.to_string(),
+                        None => format!("card{}", device.device_path.get_instance_number().unwrap_or(0)),
+                    };
+                    let line = json!({
+                        "period": {
+                            "duration": now.duration_since(self.base_time).as_millis(),
+                            "unit": "ms",
+                        },
+                        "timestamp": {
+                            "value": unix_time,
+                            "unit": "ms",
+                        },
+                        "device": device_json,
+                        "display_unit": display_unit,
+                        "amdgpu_top_version": amdgpu_top_version(),
+                        "title": title,
+                    });
+
+                    if let Err(err) = output_dir.write_line(&name, &line.to_string()) {
+                        eprintln!("Failed to write JSON to {:?}: {err}", output_dir.dir);
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                let line = json!({
+                    "period": {
+                        "duration": now.duration_since(self.base_time).as_millis(),
+                        "unit": "ms",
+                    },
+                    "devices": Value::Array(buf_json.clone()),
+                    "devices_len": devices_len,
+                    "display_unit": display_unit,
+                    "amdgpu_top_version": amdgpu_top_version(),
+                    "title": title,
+                });
+
+                if let Some(remote) = &self.remote {
+                    remote.send_line(&line.to_string());
+                } else {
+                    println!("{line}");
+                }
+            }
 
             buf_json.clear();
 
+            let active = self.vec_device_info
+                .iter()
+                .any(|device| is_gpu_active(&device.activity, &device.fdinfo.fold_fdinfo_usage()));
+
+            if self.idle.update(active) {
+                // the process index is not updated while paused
+                for device in &self.vec_device_info {
+                    if let Ok(mut proc_index) = device.arc_proc_index.lock() {
+                        stat::update_index(&mut proc_index, &device.device_path);
+                    }
+                }
+            }
+
+            for (summary, device) in summaries.iter_mut().zip(self.vec_device_info.iter()) {
+                summary.add(&device.sensors, &device.vram_usage, Some(&device.activity));
+            }
+
+            let alerts: Vec<String> = self.vec_device_info
+                .iter()
+                .flat_map(|device| device.alerts(&self.fail_on))
+                .collect();
+
+            if !alerts.is_empty() {
+                self.session.write_summary(&summaries);
+                return ExitReason::Alert(alerts);
+            }
+
             if self.iterations != 0 {
                 n += 1;
                 if self.iterations == n { break; }
             }
+
+            if self.session.is_over(self.base_time, 0) { break }
         }
+
+        self.session.write_summary(&summaries);
+
+        ExitReason::Done
     }
 }
 
@@ -128,13 +275,33 @@ pub struct JsonDeviceInfo {
     pub asic_name: ASIC_NAME,
     pub grbm: PerfCounter,
     pub grbm2: PerfCounter,
+    /// `--source pmu`, the GRBM registers are not polled
+    pub pmu: Option<AmdgpuPmu>,
     pub vram_usage: VramUsage,
     pub sensors: Sensors,
     pub sysfs_path: PathBuf,
     pub metrics: Option<GpuMetrics>,
     pub activity: GpuActivity,
+    /// `--sysfs-busy`, adds "Activity Sources"
+    pub sysfs_busy: bool,
+    pub activity_sources: Option<ActivitySources>,
     pub fdinfo: FdInfoStat,
     pub arc_proc_index: Arc<Mutex<Vec<ProcInfo>>>,
+    pub instance: Option<u32>,
+    pub vcn_state: Option<VcnState>,
+    pub pm_info: Option<PmInfo>,
+    pub dpm_clocks: Vec<DpmClockTable>,
+    pub arc_fw_error: Option<Arc<Mutex<FwErrorStat>>>,
+    pub xgmi: Option<XgmiStat>,
+    /// `--fdinfo-group-by`, adds "fdinfo groups"
+    pub fdinfo_group: FdInfoGroupBy,
+    /// `--anomaly-threshold`, adds "Anomalies"
+    pub anomaly: AnomalyDetector,
+    /// The anomalies of the last sample
+    pub anomalies: Vec<Anomaly>,
+    /// `--leak-threshold`, adds "Leak Suspects"
+    pub leak: LeakDetector,
+    pub media_sessions: MediaSessions,
 }
 
 impl JsonDeviceInfo {
@@ -165,11 +332,12 @@ impl JsonDeviceInfo {
             ]
         };
 
+        let pmu = AmdgpuPmu::open_if_enabled(&device_path);
         let vram_usage = VramUsage::new(&memory_info);
         let sensors = Sensors::new(&amdgpu_dev, &pci_bus, &ext_info);
 
-        let metrics = amdgpu_dev.get_gpu_metrics_from_sysfs_path(&sysfs_path).ok();
-        let activity = GpuActivity::get(&amdgpu_dev, &sysfs_path, asic_name);
+        let metrics = get_gpu_metrics(&sysfs_path).ok();
+        let activity = GpuActivity::get(&sysfs_path, asic_name);
 
         let arc_proc_index = {
             let mut proc_index: Vec<ProcInfo> = Vec::new();
@@ -182,6 +350,12 @@ impl JsonDeviceInfo {
             has_vcn_unified: libamdgpu_top::has_vcn_unified(&amdgpu_dev),
             ..Default::default()
         };
+        let instance = device_path.get_instance_number();
+        let vcn_state = instance.and_then(|instance| VcnState::get(instance).ok());
+        let pm_info = instance.and_then(|instance| PmInfo::get(instance).ok());
+        let dpm_clocks = DpmClockTable::get_all_from_sysfs_path(&sysfs_path);
+        let arc_fw_error = FwErrorStat::spawn_update_thread(&pci_bus);
+        let xgmi = XgmiStat::new(&pci_bus);
 
         Some(Self {
             amdgpu_dev,
@@ -190,21 +364,45 @@ impl JsonDeviceInfo {
             asic_name,
             grbm,
             grbm2,
+            pmu,
             vram_usage,
             sensors,
             metrics,
             activity,
+            sysfs_busy: false,
+            activity_sources: None,
             sysfs_path,
             fdinfo,
             arc_proc_index,
+            instance,
+            vcn_state,
+            pm_info,
+            dpm_clocks,
+            arc_fw_error,
+            xgmi,
+            fdinfo_group: FdInfoGroupBy::default(),
+            anomaly: AnomalyDetector::default(),
+            anomalies: Vec::new(),
+            leak: LeakDetector::default(),
+            media_sessions: MediaSessions::default(),
         })
     }
 
     pub fn update(&mut self, interval: Duration) {
+        self.grbm.update_usage(interval);
+        self.grbm2.update_usage(interval);
+        if let Some(pmu) = &mut self.pmu {
+            pmu.update();
+        }
         self.vram_usage.update_usage(&self.amdgpu_dev);
         self.sensors.update(&self.amdgpu_dev);
-        self.metrics = self.amdgpu_dev.get_gpu_metrics_from_sysfs_path(&self.sysfs_path).ok();
-        self.activity = GpuActivity::get(&self.amdgpu_dev, &self.sysfs_path, self.asic_name);
+        self.metrics = get_gpu_metrics(&self.sysfs_path).ok();
+        self.activity = GpuActivity::get(&self.sysfs_path, self.asic_name);
+        self.activity_sources = self.sysfs_busy.then(|| ActivitySources::get(
+            &self.sysfs_path,
+            self.pmu.is_none().then_some(&self.grbm),
+            self.metrics.as_ref(),
+        ));
 
         {
             let lock = self.arc_proc_index.try_lock();
@@ -216,12 +414,55 @@ impl JsonDeviceInfo {
             }
         }
 
+        self.media_sessions.update(&self.fdinfo.proc_usage);
+        self.leak.update(&self.fdinfo.proc_usage);
+
         if self.activity.media.is_none() || self.activity.media == Some(0) {
             self.activity.media = self.fdinfo.fold_fdinfo_usage().media.try_into().ok();
         }
+
+        if let Some(vcn_state) = &mut self.vcn_state {
+            let _ = vcn_state.update();
+        }
+
+        if let (Some(instance), Some(_)) = (self.instance, &self.pm_info) {
+            self.pm_info = PmInfo::get(instance).ok();
+        }
+
+        for table in self.dpm_clocks.iter_mut() {
+            let _ = table.update();
+        }
+
+        if let Some(xgmi) = &mut self.xgmi {
+            xgmi.update();
+        }
+
+        self.anomalies = self.anomaly.update(&self.sensors);
+    }
+
+    pub fn sample(&self) -> DeviceSample {
+        DeviceSample::new(
+            &self.device_path,
+            &self.sensors,
+            &self.vram_usage,
+            &self.activity,
+            self.metrics.as_ref(),
+            &self.fdinfo,
+        )
+    }
+
+    pub fn alerts(&self, fail_on: &[AlertCondition]) -> Vec<String> {
+        let pci = self.device_path.pci.map(|pci| pci.to_string()).unwrap_or_default();
+
+        AlertCondition::check_all(fail_on, &self.sensors, &self.vram_usage, &self.activity)
+            .into_iter()
+            .map(|msg| format!("{pci}: {msg}"))
+            .collect()
     }
 
     pub fn update_pc(&mut self) {
+        if self.pmu.is_some() { return }
+
         self.grbm.read_reg(&self.amdgpu_dev);
         self.grbm2.read_reg(&self.amdgpu_dev);
     }
@@ -236,13 +477,33 @@ impl OutputJson for JsonDeviceInfo {
     fn json(&self) -> Value {
         json!({
             "Info": self.info,
-            "GRBM": self.grbm.json(),
-            "GRBM2": self.grbm2.json(),
+            "GRBM": self.pmu.is_none().then(|| self.grbm.json()),
+            "GRBM2": self.pmu.is_none().then(|| self.grbm2.json()),
+            "PMU": self.pmu.as_ref().map(|pmu| pmu.json()),
             "VRAM": self.vram_usage.json(),
             "Sensors": self.sensors.json(),
             "fdinfo": self.fdinfo.json(),
             "gpu_metrics": self.metrics.as_ref().map(|m| m.json()),
             "gpu_activity": self.activity.json(),
+            "Activity Sources": self.activity_sources.as_ref().map(|a| a.json()),
+            "VCN State": self.vcn_state.as_ref().map(|v| v.json()),
+            "amdgpu_pm_info": self.pm_info.as_ref().map(|v| v.json()),
+            "DPM Clocks": self.dpm_clocks.iter()
+                .map(|table| (table.name.clone(), table.json()))
+                .collect::<serde_json::Map<_, _>>(),
+            "FW Errors": self.arc_fw_error.as_ref()
+                .and_then(|arc| arc.lock().ok().map(|fw_error| fw_error.json())),
+            "XGMI": self.xgmi.as_ref().map(|xgmi| xgmi.json()),
+            "fdinfo groups": stat::group_proc_usage(&self.fdinfo.proc_usage, self.fdinfo_group)
+                .map(|groups| groups.iter()
+                    .map(|g| (g.name.clone(), g.json()))
+                    .collect::<serde_json::Map<_, _>>()
+                ),
+            "Media Sessions": self.media_sessions.sessions.iter().map(|s| s.json()).collect::<Vec<_>>(),
+            "Anomalies": self.anomaly.is_enabled()
+                .then(|| self.anomalies.iter().map(|a| a.json()).collect::<Vec<_>>()),
+            "Leak Suspects": self.leak.is_enabled()
+                .then(|| self.leak.suspects().iter().map(|s| s.json()).collect::<Vec<_>>()),
         })
     }
 }
@@ -0,0 +1,72 @@
+use libamdgpu_top::{MockDevice, SAMPLE_BUS, SessionOptions};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::{amdgpu_top_version, OutputJson};
+
+fn device_json(device: &MockDevice) -> Value {
+    let mut v = device.sample().json();
+
+    if let Some(m) = v.as_object_mut() {
+        m.insert("Info".to_string(), json!({
+            "DeviceName": device.name,
+            "PCI": device.device_path.pci.map(|pci| pci.to_string()),
+            "VRAM Size": device.vram_usage.0.vram.total_heap_size,
+            "GTT Size": device.vram_usage.0.gtt.total_heap_size,
+            "Mock": device.path,
+        }));
+    }
+
+    v
+}
+
+/// One JSON line of the mock devices, in the same format as JSON mode
+pub fn mock_line(title: &str, devices: &[MockDevice], base_time: Instant) -> Value {
+    let devices_json: Vec<Value> = devices.iter().map(device_json).collect();
+
+    json!({
+        "period": {
+            "duration": base_time.elapsed().as_millis(),
+            "unit": "ms",
+        },
+        "devices": devices_json,
+        "devices_len": devices.len(),
+        "amdgpu_top_version": amdgpu_top_version(),
+        "title": title,
+    })
+}
+
+/// `--mock <dir>` and `--demo` in JSON mode, the samples are also published to `SAMPLE_BUS`
+/// for the HTTP server, the OTLP exporter and the database.
+pub fn run_mock(
+    title: &str,
+    devices: &mut [MockDevice],
+    refresh_period: u64,
+    iterations: u32,
+    session: &SessionOptions,
+) {
+    let period = Duration::from_millis(refresh_period);
+    let base_time = Instant::now();
+    let mut n = 0;
+
+    loop {
+        std::thread::sleep(period);
+
+        for device in devices.iter_mut() {
+            device.fdinfo.interval = period;
+            device.update();
+        }
+
+        if SAMPLE_BUS.has_subscribers() {
+            SAMPLE_BUS.send(Arc::new(devices.iter().map(|device| device.sample()).collect()));
+        }
+
+        println!("{}", mock_line(title, devices, base_time));
+
+        n += 1;
+
+        if iterations != 0 && iterations == n { break }
+        if session.is_over(base_time, n) { break }
+    }
+}
@@ -0,0 +1,245 @@
+// ref: https://opentelemetry.io/docs/specs/otlp/#otlphttp
+// ref: https://github.com/open-telemetry/opentelemetry-proto/blob/main/opentelemetry/proto/metrics/v1/metrics.proto
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde_json::{json, Value};
+use libamdgpu_top::DeviceSample;
+
+const DEFAULT_PATH: &str = "/v1/metrics";
+const TIMEOUT: Duration = Duration::from_secs(5);
+// AggregationTemporality
+const CUMULATIVE: u32 = 2;
+
+/// Pushes the device and process metrics to the OpenTelemetry collector,
+/// with OTLP/HTTP (JSON encoding).
+pub struct OtlpExporter {
+    pub host: String, // host:port
+    pub path: String,
+    pub interval: Duration,
+    last_export: Option<Instant>,
+}
+
+impl OtlpExporter {
+    /// `http://<host>:<port>[/path]` (default path: `/v1/metrics`)
+    pub fn new(endpoint: &str, interval: Duration) -> Result<Self, String> {
+        let Some(s) = endpoint.strip_prefix("http://") else {
+            return Err(format!("unsupported OTLP endpoint: {endpoint:?} (only \"http://\" is supported)"));
+        };
+        let (host, path) = match s.find('/') {
+            Some(pos) => (&s[..pos], &s[pos..]),
+            None => (s, DEFAULT_PATH),
+        };
+
+        if host.is_empty() {
+            return Err(format!("invalid OTLP endpoint: {endpoint:?}"));
+        }
+
+        let host = if host.contains(':') { host.to_string() } else { format!("{host}:4318") };
+
+        Ok(Self { host, path: path.to_string(), interval, last_export: None })
+    }
+
+    /// Exports in the background if the interval has elapsed since the last export.
+    pub fn export_if_due(&mut self, devices: &[DeviceSample]) {
+        if self.last_export.is_some_and(|t| t.elapsed() < self.interval) {
+            return;
+        }
+
+        self.last_export = Some(Instant::now());
+
+        let body = export_request(devices).to_string();
+        let host = self.host.clone();
+        let path = self.path.clone();
+
+        std::thread::spawn(move || {
+            if let Err(err) = post(&host, &path, &body) {
+                eprintln!("Failed to export metrics to OTLP endpoint ({host}{path}): {err}");
+            }
+        });
+    }
+}
+
+fn post(host: &str, path: &str, body: &str) -> io::Result<()> {
+    let mut stream = TcpStream::connect(host)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+
+    write!(
+        stream,
+        "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len(),
+    )?;
+
+    let mut res = String::new();
+    stream.read_to_string(&mut res)?;
+
+    // "HTTP/1.1 200 OK"
+    let status = res.split_whitespace().nth(1).unwrap_or("");
+
+    if status.starts_with('2') {
+        Ok(())
+    } else {
+        Err(io::Error::other(res.lines().next().unwrap_or("no response").to_string()))
+    }
+}
+
+struct Metric {
+    name: &'static str,
+    unit: &'static str,
+    cumulative: bool,
+    points: Vec<Value>,
+}
+
+impl Metric {
+    fn gauge(name: &'static str, unit: &'static str) -> Self {
+        Self { name, unit, cumulative: false, points: Vec::new() }
+    }
+
+    fn counter(name: &'static str, unit: &'static str) -> Self {
+        Self { name, unit, cumulative: true, points: Vec::new() }
+    }
+
+    fn push_int(&mut self, attr: &Value, time: &str, val: Option<i64>) {
+        let Some(val) = val else { return };
+
+        self.points.push(json!({
+            "attributes": attr,
+            "timeUnixNano": time,
+            // int64 is encoded as a string in the JSON mapping of Protobuf
+            "asInt": val.to_string(),
+        }));
+    }
+
+    fn push_double(&mut self, attr: &Value, time: &str, val: f64) {
+        self.points.push(json!({
+            "attributes": attr,
+            "timeUnixNano": time,
+            "asDouble": val,
+        }));
+    }
+
+    fn json(&self) -> Value {
+        let mut m = json!({
+            "name": self.name,
+            "unit": self.unit,
+        });
+
+        if self.cumulative {
+            m["sum"] = json!({
+                "dataPoints": self.points,
+                "aggregationTemporality": CUMULATIVE,
+                "isMonotonic": true,
+            });
+        } else {
+            m["gauge"] = json!({ "dataPoints": self.points });
+        }
+
+        m
+    }
+}
+
+fn attr(kv: &[(&str, Value)]) -> Value {
+    kv.iter().map(|(k, v)| {
+        let v = match v {
+            Value::String(s) => json!({ "stringValue": s }),
+            v => json!({ "intValue": v.to_string() }),
+        };
+
+        json!({ "key": k, "value": v })
+    }).collect()
+}
+
+fn export_request(devices: &[DeviceSample]) -> Value {
+    let time = SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+        .to_string();
+
+    let mut gfx = Metric::gauge("amdgpu.gpu.gfx_activity", "%");
+    let mut umc = Metric::gauge("amdgpu.gpu.memory_activity", "%");
+    let mut media = Metric::gauge("amdgpu.gpu.media_activity", "%");
+    let mut vram = Metric::gauge("amdgpu.gpu.vram.used", "By");
+    let mut gtt = Metric::gauge("amdgpu.gpu.gtt.used", "By");
+    let mut temp = Metric::gauge("amdgpu.gpu.temperature", "Cel");
+    let mut power = Metric::gauge("amdgpu.gpu.power", "W");
+    let mut sclk = Metric::gauge("amdgpu.gpu.sclk", "MHz");
+    let mut mclk = Metric::gauge("amdgpu.gpu.mclk", "MHz");
+    let mut fan = Metric::gauge("amdgpu.gpu.fan_speed", "{rpm}");
+    let mut proc_vram = Metric::gauge("amdgpu.process.vram.used", "By");
+    let mut proc_gtt = Metric::gauge("amdgpu.process.gtt.used", "By");
+    let mut proc_gfx = Metric::gauge("amdgpu.process.gfx_usage", "%");
+    let mut proc_compute = Metric::gauge("amdgpu.process.compute_usage", "%");
+    let mut proc_media = Metric::gauge("amdgpu.process.media_usage", "%");
+    let mut proc_cpu_time = Metric::counter("amdgpu.process.cpu.time", "s");
+
+    for device in devices {
+        let pci = device.device_path.pci.map(|pci| pci.to_string()).unwrap_or_default();
+        let dev_attr = attr(&[
+            ("gpu.pci", json!(pci)),
+            ("gpu.instance", json!(device.instance.unwrap_or(0))),
+        ]);
+        let sensors = &device.sensors;
+
+        gfx.push_int(&dev_attr, &time, device.activity.gfx.map(i64::from));
+        umc.push_int(&dev_attr, &time, device.activity.umc.map(i64::from));
+        media.push_int(&dev_attr, &time, device.activity.media.map(i64::from));
+        vram.push_int(&dev_attr, &time, Some(device.vram_usage.0.vram.heap_usage as i64));
+        gtt.push_int(&dev_attr, &time, Some(device.vram_usage.0.gtt.heap_usage as i64));
+        temp.push_int(
+            &dev_attr,
+            &time,
+            sensors.junction_temp.as_ref().or(sensors.edge_temp.as_ref()).map(|t| t.current),
+        );
+        power.push_int(&dev_attr, &time, sensors.power.map(i64::from));
+        sclk.push_int(&dev_attr, &time, sensors.sclk.map(i64::from));
+        mclk.push_int(&dev_attr, &time, sensors.mclk.map(i64::from));
+        fan.push_int(&dev_attr, &time, sensors.fan_rpm.map(i64::from));
+
+        for pu in &device.proc_usage {
+            let proc_attr = attr(&[
+                ("gpu.pci", json!(pci)),
+                ("gpu.instance", json!(device.instance.unwrap_or(0))),
+                ("process.pid", json!(pu.pid)),
+                ("process.executable.name", json!(pu.name)),
+            ]);
+            let dec = pu.usage.dec + pu.usage.vcn_jpeg;
+            let enc = pu.usage.enc + pu.usage.uvd_enc;
+
+            proc_vram.push_int(&proc_attr, &time, Some((pu.usage.vram_usage << 10) as i64));
+            proc_gtt.push_int(&proc_attr, &time, Some((pu.usage.gtt_usage << 10) as i64));
+            proc_gfx.push_int(&proc_attr, &time, Some(pu.usage.gfx));
+            proc_compute.push_int(&proc_attr, &time, Some(pu.usage.compute));
+            proc_media.push_int(&proc_attr, &time, Some((dec + enc) / 2));
+
+            if let Some(cpu_time) = device.cpu_time_map.get(&pu.pid) {
+                proc_cpu_time.push_double(&proc_attr, &time, *cpu_time as f64);
+            }
+        }
+    }
+
+    let metrics: Vec<Value> = [
+        gfx, umc, media, vram, gtt, temp, power, sclk, mclk, fan,
+        proc_vram, proc_gtt, proc_gfx, proc_compute, proc_media, proc_cpu_time,
+    ]
+        .iter()
+        .filter(|m| !m.points.is_empty())
+        .map(|m| m.json())
+        .collect();
+
+    json!({
+        "resourceMetrics": [{
+            "resource": {
+                "attributes": attr(&[("service.name", json!("amdgpu_top"))]),
+            },
+            "scopeMetrics": [{
+                "scope": {
+                    "name": "amdgpu_top",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "metrics": metrics,
+            }],
+        }],
+    })
+}
@@ -0,0 +1,110 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use flate2::{Compression, write::GzEncoder};
+
+/// Writes one NDJSON file per device to the directory.
+///
+/// The current segment is `amdgpu_top_<pci>.ndjson`.
+/// When it exceeds the size or the interval, it is renamed to
+/// `amdgpu_top_<pci>.<unix time>.ndjson` and compressed to `.ndjson.gz` in the background.
+pub struct JsonOutputDir {
+    pub dir: PathBuf,
+    pub max_size: u64, // bytes
+    pub max_age: Duration,
+    files: Vec<(String, Segment)>,
+}
+
+struct Segment {
+    writer: BufWriter<File>,
+    size: u64,
+    opened: Instant,
+}
+
+impl Segment {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        let file = fs::OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self { writer: BufWriter::new(file), size, opened: Instant::now() })
+    }
+}
+
+impl JsonOutputDir {
+    pub fn new<P: Into<PathBuf>>(dir: P, max_size: u64, max_age: Duration) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self { dir, max_size, max_age, files: Vec::new() })
+    }
+
+    /// `name` identifies the device (e.g. PCI bus).
+    pub fn write_line(&mut self, name: &str, line: &str) -> io::Result<()> {
+        let pos = match self.files.iter().position(|(n, _)| n == name) {
+            Some(pos) => pos,
+            None => {
+                let segment = Segment::open(self.segment_path(name))?;
+                self.files.push((name.to_string(), segment));
+                self.files.len() - 1
+            },
+        };
+
+        let rotate = {
+            let segment = &self.files[pos].1;
+            segment.size != 0
+                && (self.max_size <= segment.size || self.max_age <= segment.opened.elapsed())
+        };
+
+        if rotate {
+            self.rotate(pos)?;
+        }
+
+        let segment = &mut self.files[pos].1;
+        writeln!(segment.writer, "{line}")?;
+        segment.writer.flush()?;
+        segment.size += line.len() as u64 + 1;
+
+        Ok(())
+    }
+
+    fn rotate(&mut self, pos: usize) -> io::Result<()> {
+        let name = self.files[pos].0.clone();
+        let path = self.segment_path(&name);
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let old = self.dir.join(format!("amdgpu_top_{}.{secs}.ndjson", file_name(&name)));
+
+        fs::rename(&path, &old)?;
+        self.files[pos].1 = Segment::open(path)?;
+
+        std::thread::spawn(move || {
+            if let Err(err) = compress(&old) {
+                eprintln!("Failed to compress {old:?}: {err}");
+            }
+        });
+
+        Ok(())
+    }
+
+    fn segment_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("amdgpu_top_{}.ndjson", file_name(name)))
+    }
+}
+
+/// ':' is not allowed in file names on some filesystems
+fn file_name(name: &str) -> String {
+    name.replace(':', "-")
+}
+
+fn compress(path: &Path) -> io::Result<()> {
+    let mut gz_path = path.as_os_str().to_owned();
+    gz_path.push(".gz");
+
+    let mut src = File::open(path)?;
+    let mut encoder = GzEncoder::new(File::create(gz_path)?, Compression::default());
+
+    io::copy(&mut src, &mut encoder)?;
+    encoder.finish()?;
+
+    fs::remove_file(path)
+}
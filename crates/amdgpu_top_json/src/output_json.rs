@@ -1,9 +1,13 @@
 use libamdgpu_top::{
     stat,
     AMDGPU::{GpuMetrics, MetricsInfo},
+    Anomaly,
+    DeviceSample,
+    LeakSuspect,
+    pmu::AmdgpuPmu,
     VramUsage,
 };
-use stat::{FdInfoStat, GpuActivity, Sensors, PerfCounter};
+use stat::{ActivitySources, DpmClockTable, FdInfoGroupUsage, FdInfoStat, FdInfoUsage, ProcUsage, FwErrorStat, GpuActivity, MediaBlockState, MediaSession, Sensors, PerfCounter, PmInfo, PmValue, VcnState, XgmiStat};
 use serde_json::{json, Map, Value};
 use crate::OutputJson;
 
@@ -34,11 +38,11 @@ impl OutputJson for PerfCounter {
     fn json(&self) -> Value {
         let mut m = Map::new();
 
-        for (label, idx) in &self.index {
+        for (i, (label, _)) in self.index.iter().enumerate() {
             m.insert(
                 label.to_string(),
                 json!({
-                    "value": self.bits.get(*idx),
+                    "value": self.get_usage(i),
                     "unit": "%",
                 }),
             );
@@ -83,68 +87,148 @@ impl OutputJson for Sensors {
             );
         }
 
+        m.insert(
+            "Power Limits".to_string(),
+            self.power_limits.as_ref().map_or(Value::Null, |limits| {
+                let mut sub = Map::new();
+
+                for limit in limits.iter() {
+                    sub.insert(
+                        limit.type_.to_string(),
+                        json!({
+                            "power": limit.power,
+                            "cap": limit.cap,
+                            "min": limit.min,
+                            "max": limit.max,
+                            "unit": "W",
+                        }),
+                    );
+                }
+
+                sub.into()
+            }),
+        );
+
         m.insert(
             "PCIe Link Speed".to_string(),
             self.current_link.map_or(Value::Null, |link| link.json()),
         );
 
+        m.insert(
+            "Health".to_string(),
+            self.status.iter().map(|(label, status)| (label.to_string(), status.health.to_string().into())).collect::<Map<_, _>>().into(),
+        );
+
         m.into()
     }
 }
 
 impl OutputJson for FdInfoStat {
     fn json(&self) -> Value {
-        let mut m = Map::new();
+        proc_usage_json(&self.proc_usage)
+    }
+}
 
-        for pu in &self.proc_usage {
-            let mut sub = Map::new();
-            sub.insert(
-                "VRAM".to_string(),
-                json!({
-                    "value": pu.usage.vram_usage >> 10,
-                    "unit": "MiB",
-                }),
-            );
+pub(crate) fn proc_usage_json(proc_usage: &[ProcUsage]) -> Value {
+    let mut m = Map::new();
+
+    for pu in proc_usage {
+        m.insert(
+            format!("{}", pu.pid),
+            json!({
+                "name": pu.name,
+                "display_server": pu.is_display_server(),
+                "usage": fdinfo_usage_json(&pu.usage, pu.cpu_usage),
+            }),
+        );
+    }
+
+    m.into()
+}
+
+/// The subset of [`crate::JsonDeviceInfo`] sampled by the other modes, without the "Info"
+impl OutputJson for DeviceSample {
+    fn json(&self) -> Value {
+        json!({
+            "VRAM": self.vram_usage.json(),
+            "Sensors": self.sensors.json(),
+            "fdinfo": proc_usage_json(&self.proc_usage),
+            "gpu_metrics": self.metrics.as_ref().map(|m| m.json()),
+            "gpu_activity": self.activity.json(),
+        })
+    }
+}
+
+impl OutputJson for FdInfoGroupUsage {
+    fn json(&self) -> Value {
+        json!({
+            "pids": self.pids,
+            "usage": fdinfo_usage_json(&self.usage, self.cpu_usage),
+        })
+    }
+}
+
+fn fdinfo_usage_json(usage: &FdInfoUsage, cpu_usage: i64) -> Map<String, Value> {
+    let mut sub = Map::new();
+    sub.insert(
+        "VRAM".to_string(),
+        json!({
+            "value": usage.vram_usage >> 10,
+            "unit": "MiB",
+        }),
+    );
+    sub.insert(
+        "GTT".to_string(),
+        json!({
+            "value": usage.gtt_usage >> 10,
+            "unit": "MiB",
+        }),
+    );
+
+
+    for (label, regions) in [
+        ("Resident", &usage.resident),
+        ("Purgeable", &usage.purgeable),
+        ("Shared", &usage.shared),
+    ] {
+        for (region, val) in [
+            ("VRAM", regions.vram),
+            ("GTT", regions.gtt),
+            ("CPU Memory", regions.cpu),
+        ] {
             sub.insert(
-                "GTT".to_string(),
+                format!("{region} {label}"),
                 json!({
-                    "value": pu.usage.gtt_usage >> 10,
+                    "value": val >> 10,
                     "unit": "MiB",
                 }),
             );
-
-            let dec_usage = pu.usage.dec + pu.usage.vcn_jpeg;
-            let enc_usage = pu.usage.enc + pu.usage.uvd_enc;
-            let media_usage = (dec_usage + enc_usage) / 2;
-
-            for (label, val) in [
-                ("GFX", pu.usage.gfx),
-                ("Compute", pu.usage.compute),
-                ("DMA", pu.usage.dma),
-                ("Decode", dec_usage),
-                ("Encode", enc_usage),
-                ("CPU", pu.cpu_usage),
-                ("Media", media_usage),
-            ] {
-                sub.insert(
-                    label.to_string(),
-                    json!({
-                        "value": val,
-                        "unit": "%",
-                    }),
-                );
-            }
-            m.insert(
-                format!("{}", pu.pid),
-                json!({
-                    "name": pu.name,
-                    "usage": sub,
-                }),
-            );
         }
+    }
 
-        m.into()
+    let dec_usage = usage.dec + usage.vcn_jpeg;
+    let enc_usage = usage.enc + usage.uvd_enc;
+    let media_usage = (dec_usage + enc_usage) / 2;
+
+    for (label, val) in [
+        ("GFX", usage.gfx),
+        ("Compute", usage.compute),
+        ("DMA", usage.dma),
+        ("Decode", dec_usage),
+        ("Encode", enc_usage),
+        ("CPU", cpu_usage),
+        ("Media", media_usage),
+    ] {
+        sub.insert(
+            label.to_string(),
+            json!({
+                "value": val,
+                "unit": "%",
+            }),
+        );
     }
+
+    sub
 }
 
 impl OutputJson for GpuMetrics {
@@ -184,3 +268,240 @@ impl OutputJson for GpuActivity {
         m.into()
     }
 }
+
+impl OutputJson for ActivitySources {
+    fn json(&self) -> Value {
+        let mut m = Map::new();
+
+        for (source, gfx, umc) in self.rows() {
+            m.insert(
+                source.to_string(),
+                json!({
+                    "GFX": { "value": gfx, "unit": "%" },
+                    "Memory": { "value": umc, "unit": "%" },
+                }),
+            );
+        }
+
+        m.into()
+    }
+}
+
+impl OutputJson for VcnState {
+    fn json(&self) -> Value {
+        let mut m = Map::new();
+
+        m.insert(
+            "Power State".to_string(),
+            self.power_state().map_or(Value::Null, |state| state.to_string().into()),
+        );
+
+        for (label, val) in [
+            ("VCLK", self.vclk),
+            ("DCLK", self.dclk),
+        ] {
+            m.insert(
+                label.to_string(),
+                val.map_or(Value::Null, |val| json!({
+                    "value": val,
+                    "unit": "MHz",
+                })),
+            );
+        }
+
+        let rings: Vec<Value> = self.rings.iter().map(|ring| json!({
+            "name": ring.name,
+            "busy": ring.is_busy(),
+        })).collect();
+
+        m.insert("Rings".to_string(), rings.into());
+
+        m.into()
+    }
+}
+
+impl OutputJson for PmValue {
+    fn json(&self) -> Value {
+        json!({
+            "value": self.value,
+            "unit": self.unit,
+        })
+    }
+}
+
+impl OutputJson for MediaBlockState {
+    fn json(&self) -> Value {
+        let clocks: Map<String, Value> = self.clocks.iter()
+            .map(|clk| (clk.label.clone(), clk.json()))
+            .collect();
+
+        json!({
+            "enabled": self.enabled,
+            "clocks": clocks,
+        })
+    }
+}
+
+impl OutputJson for PmInfo {
+    fn json(&self) -> Value {
+        let mut m = Map::new();
+
+        m.insert(
+            "GFX".to_string(),
+            self.gfx.iter().map(|v| (v.label.clone(), v.json())).collect::<Map<_, _>>().into(),
+        );
+
+        for (label, val, unit) in [
+            ("GPU Temperature", self.gpu_temp, "C"),
+            ("GPU Load", self.gpu_load, "%"),
+            ("MEM Load", self.mem_load, "%"),
+            ("VCN Load", self.vcn_load, "%"),
+        ] {
+            m.insert(
+                label.to_string(),
+                val.map_or(Value::Null, |val| json!({
+                    "value": val,
+                    "unit": unit,
+                })),
+            );
+        }
+
+        for (label, val) in [
+            ("Clock Gating Flags Mask", self.clock_gating_flags),
+            ("SMC Feature Mask", self.smc_feature_mask),
+        ] {
+            m.insert(label.to_string(), val.map_or(Value::Null, |val| format!("{val:#x}").into()));
+        }
+
+        m.insert(
+            "Clock Gating".to_string(),
+            self.clock_gating.iter().map(|(name, on)| (name.clone(), Value::from(*on))).collect::<Map<_, _>>().into(),
+        );
+
+        for (label, block) in [("UVD", &self.uvd), ("VCE", &self.vce)] {
+            m.insert(label.to_string(), block.as_ref().map_or(Value::Null, |b| b.json()));
+        }
+
+        m.into()
+    }
+}
+
+impl OutputJson for DpmClockTable {
+    fn json(&self) -> Value {
+        let levels: Vec<Value> = self.levels.iter().map(|level| json!({
+            "level": level.label,
+            "value": level.clock,
+            "unit": "MHz",
+        })).collect();
+
+        json!({
+            "levels": levels,
+            "current": self.current,
+        })
+    }
+}
+
+impl OutputJson for FwErrorStat {
+    fn json(&self) -> Value {
+        json!({
+            "SMU Message Failures": self.smu_msg_failures,
+            "Firmware Errors": self.fw_errors,
+            "Last Error": self.last_error,
+            "Last Error Timestamp": self.last_error_timestamp_us.map(|ts| json!({
+                "value": ts,
+                "unit": "us",
+            })),
+        })
+    }
+}
+
+impl OutputJson for XgmiStat {
+    fn json(&self) -> Value {
+        let peers: Vec<Value> = self.peers.iter().map(|peer| json!({
+            "Physical ID": peer.physical_id,
+            "PCI": peer.pci_bus.map(|pci| pci.to_string()),
+            "Hops": peer.num_hops,
+            "Links": peer.num_links,
+        })).collect();
+
+        json!({
+            "Hive ID": self.node.hive_id,
+            "Node ID": self.node.node_id,
+            "Physical ID": self.node.physical_id,
+            "Link Width": self.link_width,
+            "Link Speed": self.link_speed.map(|speed| json!({
+                "value": speed,
+                "unit": "Gbps",
+            })),
+            "Errors": self.errors,
+            "WAFL Correctable Errors": self.wafl_ce,
+            "WAFL Uncorrectable Errors": self.wafl_ue,
+            "Peers": peers,
+        })
+    }
+}
+
+impl OutputJson for AmdgpuPmu {
+    fn json(&self) -> Value {
+        let mut m = Map::new();
+
+        for event in &self.events {
+            m.insert(
+                event.name.clone(),
+                json!({
+                    "value": event.rate.map(|rate| rate.round() as u64),
+                    "unit": "/s",
+                }),
+            );
+        }
+
+        json!({
+            "name": self.name,
+            "events": m,
+        })
+    }
+}
+
+impl OutputJson for Anomaly {
+    fn json(&self) -> Value {
+        json!({
+            "metric": self.metric.name(),
+            "value": self.value,
+            "mean": self.mean,
+            "z": self.z,
+            "unit": self.metric.unit(),
+        })
+    }
+}
+
+impl OutputJson for LeakSuspect {
+    fn json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "pid": self.pid,
+            "VRAM": { "value": self.vram >> 10, "unit": "MiB" },
+            "GTT": { "value": self.gtt >> 10, "unit": "MiB" },
+            "VRAM Growth": { "value": self.vram_rate, "unit": "MiB/min" },
+            "GTT Growth": { "value": self.gtt_rate, "unit": "MiB/min" },
+            "duration": { "value": self.duration.as_secs(), "unit": "s" },
+        })
+    }
+}
+
+impl OutputJson for MediaSession {
+    fn json(&self) -> Value {
+        let pct = |v: i64| json!({ "value": v, "unit": "%" });
+
+        json!({
+            "name": self.name,
+            "pid": self.pid,
+            "sessions": self.sessions,
+            "api": self.hint.apis.iter().map(|api| api.to_string()).collect::<Vec<_>>(),
+            "codecs": self.hint.codecs,
+            "resolution": self.hint.resolution,
+            "Decode": pct(self.dec),
+            "Encode": pct(self.enc),
+            "Media": pct(self.media),
+        })
+    }
+}
@@ -0,0 +1,87 @@
+use libamdgpu_top::{DevicePath, SessionOptions};
+use libamdgpu_top::stat::{PidDeviceStat, PidMonitor, Smoothing};
+use serde_json::{json, Map, Value};
+use std::time::{Duration, Instant};
+
+use crate::{amdgpu_top_version, OutputJson};
+
+fn device_json(device: &PidDeviceStat) -> Value {
+    let engine_time = device.usage.as_ref().map(|pu| {
+        let d = &pu.delta;
+        let mut m = Map::new();
+
+        for (label, val) in [
+            ("GFX", d.gfx),
+            ("Compute", d.compute),
+            ("DMA", d.dma),
+            ("Decode", d.dec + d.vcn_jpeg),
+            ("Encode", d.enc + d.uvd_enc),
+            ("Media", d.media),
+        ] {
+            m.insert(
+                label.to_string(),
+                json!({
+                    "value": val,
+                    "unit": "ns",
+                }),
+            );
+        }
+
+        Value::from(m)
+    });
+
+    json!({
+        "PCI": device.pci_bus.to_string(),
+        "DeviceName": device.marketing_name,
+        "fdinfo": device.fdinfo.json(),
+        "Engine Time": engine_time,
+    })
+}
+
+/// `--pid` in JSON mode, exits when the process exits.
+pub fn run_pid(
+    title: &str,
+    pid: i32,
+    device_path_list: &[DevicePath],
+    refresh_period: u64,
+    iterations: u32,
+    session: &SessionOptions,
+) {
+    let Some(mut monitor) = PidMonitor::new(pid, device_path_list, Smoothing::default()) else {
+        eprintln!("PID {pid} is not found.");
+        std::process::exit(1);
+    };
+    let period = Duration::from_millis(refresh_period);
+    let base_time = Instant::now();
+    let mut n = 0;
+
+    loop {
+        std::thread::sleep(period);
+
+        if !monitor.is_alive() {
+            eprintln!("PID {pid} has exited.");
+            break;
+        }
+
+        monitor.update(period);
+
+        let devices: Vec<Value> = monitor.devices.iter().map(device_json).collect();
+
+        println!("{}", json!({
+            "period": {
+                "duration": base_time.elapsed().as_millis(),
+                "unit": "ms",
+            },
+            "pid": monitor.pid,
+            "name": monitor.name,
+            "devices": devices,
+            "amdgpu_top_version": amdgpu_top_version(),
+            "title": title,
+        }));
+
+        n += 1;
+
+        if iterations != 0 && iterations == n { break }
+        if session.is_over(base_time, n) { break }
+    }
+}
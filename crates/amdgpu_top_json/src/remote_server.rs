@@ -0,0 +1,235 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicUsize;
+use std::sync::mpsc::{self, SyncSender};
+use std::time::Duration;
+use libamdgpu_top::REMOTE_MAGIC;
+use libamdgpu_top::tls::Stream;
+use crate::HttpServerConfig;
+use crate::http_server::ConnectionGuard;
+
+// `--serve` on the GPU host, `--connect` on the workstation.
+// The client sends a line of `amdgpu_top [<token>]` on connect,
+// and the server streams the JSON lines of JSON mode (one line per sample) until it disconnects.
+// The token and TLS (`cert` and `key`) are the same as the HTTP server (`server.conf`),
+// the client connects with `--connect tls://<host>:<port>` to the server with TLS.
+// The connections (including the handshakes) are limited to the same number as the HTTP server,
+// and each client is written by its own thread from a bounded queue, so a stalled client
+// does not block the sampling loop.
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// The max size of the handshake line, `amdgpu_top <token>`
+const MAX_LINE: u64 = 1024;
+/// The lines queued for a client, the client is disconnected if the queue is full
+const QUEUE_LEN: usize = 16;
+
+pub struct RemoteServer {
+    clients: Arc<Mutex<Vec<SyncSender<Arc<str>>>>>,
+}
+
+impl RemoteServer {
+    pub fn bind<A: ToSocketAddrs>(addr: A, config: HttpServerConfig) -> io::Result<Self> {
+        let tls = config.tls_acceptor()?;
+        let listener = TcpListener::bind(addr)?;
+        let clients: Arc<Mutex<Vec<SyncSender<Arc<str>>>>> = Default::default();
+        let server_clients = clients.clone();
+
+        if config.token.is_none() && listener.local_addr().is_ok_and(|addr| !addr.ip().is_loopback()) {
+            eprintln!(
+                "Warning: the server is listening on a non-loopback address without a token ({:?})",
+                HttpServerConfig::config_path().unwrap_or_default(),
+            );
+        }
+
+        std::thread::spawn(move || {
+            let connections = Arc::new(AtomicUsize::new(0));
+
+            for tcp in listener.incoming().flatten() {
+                let Some(guard) = ConnectionGuard::acquire(&connections) else { continue };
+                let clients = server_clients.clone();
+                let config = config.clone();
+                let tls = tls.clone();
+
+                std::thread::spawn(move || {
+                    let _guard = guard;
+
+                    if tcp.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).is_err()
+                    || tcp.set_write_timeout(Some(WRITE_TIMEOUT)).is_err() {
+                        return;
+                    }
+
+                    let stream = match &tls {
+                        Some(tls) => tls.accept(tcp),
+                        None => Ok(Stream::Plain(tcp)),
+                    };
+                    let Ok(stream) = stream.and_then(|stream| handshake(stream, &config)) else { return };
+                    let (tx, rx) = mpsc::sync_channel(QUEUE_LEN);
+
+                    if let Ok(mut clients) = clients.lock() {
+                        clients.push(tx);
+                    }
+
+                    write_lines(stream, rx.iter());
+                });
+            }
+        });
+
+        Ok(Self { clients })
+    }
+
+    /// Queues the line for each client without blocking,
+    /// disconnects the clients which are gone or do not receive fast enough (the queue is full).
+    pub fn send_line(&self, line: &str) {
+        let Ok(mut clients) = self.clients.lock() else { return };
+        let line: Arc<str> = line.into();
+
+        clients.retain(|tx| tx.try_send(line.clone()).is_ok());
+    }
+}
+
+/// Writes the lines until the client fails to receive or is disconnected by [`RemoteServer::send_line`]
+fn write_lines<I: Iterator<Item = Arc<str>>>(mut stream: Stream, lines: I) {
+    for line in lines {
+        if writeln!(stream, "{line}").and_then(|_| stream.flush()).is_err() { return }
+    }
+
+    let _ = stream.close();
+}
+
+/// The read timeout of `stream` is set to `HANDSHAKE_TIMEOUT`, also for the TLS handshake
+fn handshake(mut stream: Stream, config: &HttpServerConfig) -> io::Result<Stream> {
+    let mut line = String::new();
+    BufReader::new((&mut stream).take(MAX_LINE)).read_line(&mut line)?;
+
+    let mut split = line.split_whitespace();
+
+    if !line.ends_with('\n') || split.next() != Some(REMOTE_MAGIC) {
+        writeln!(stream, "error: unknown protocol")?;
+        stream.close()?;
+        return Err(io::Error::from(io::ErrorKind::InvalidData));
+    }
+
+    if !config.check_token(split.next()) {
+        writeln!(stream, "error: unauthorized")?;
+        stream.close()?;
+        return Err(io::Error::from(io::ErrorKind::PermissionDenied));
+    }
+
+    stream.tcp().set_read_timeout(None)?;
+
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_client(server: &RemoteServer) {
+        for _ in 0..100 {
+            if server.clients.lock().is_ok_and(|clients| !clients.is_empty()) { return }
+
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        panic!("the client is not accepted");
+    }
+
+    fn free_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    #[test]
+    fn plain() {
+        let port = free_port();
+        let config = HttpServerConfig::parse("token = abc\n");
+        let server = RemoteServer::bind(("127.0.0.1", port), config).unwrap();
+        let mut stream = Stream::Plain(std::net::TcpStream::connect(("127.0.0.1", port)).unwrap());
+
+        writeln!(stream, "{REMOTE_MAGIC} abc").unwrap();
+        wait_client(&server);
+        server.send_line("{}");
+
+        let mut line = String::new();
+        BufReader::new(&mut stream).read_line(&mut line).unwrap();
+        assert_eq!(line, "{}\n");
+    }
+
+    #[test]
+    fn unauthorized() {
+        let port = free_port();
+        let config = HttpServerConfig::parse("token = abc\n");
+        let _server = RemoteServer::bind(("127.0.0.1", port), config).unwrap();
+        let mut stream = Stream::Plain(std::net::TcpStream::connect(("127.0.0.1", port)).unwrap());
+
+        writeln!(stream, "{REMOTE_MAGIC} xyz").unwrap();
+
+        let mut line = String::new();
+        BufReader::new(&mut stream).read_line(&mut line).unwrap();
+        assert_eq!(line, "error: unauthorized\n");
+    }
+
+    #[test]
+    fn too_long_line() {
+        let port = free_port();
+        let _server = RemoteServer::bind(("127.0.0.1", port), HttpServerConfig::default()).unwrap();
+        let mut stream = Stream::Plain(std::net::TcpStream::connect(("127.0.0.1", port)).unwrap());
+
+        // without a line break
+        write!(stream, "{REMOTE_MAGIC} {}", "a".repeat(MAX_LINE as usize)).unwrap();
+
+        let mut line = String::new();
+        BufReader::new(&mut stream).read_line(&mut line).unwrap();
+        assert_eq!(line, "error: unknown protocol\n");
+    }
+
+    #[test]
+    fn stalled_client() {
+        let port = free_port();
+        let server = RemoteServer::bind(("127.0.0.1", port), HttpServerConfig::default()).unwrap();
+        // never reads
+        let mut stream = Stream::Plain(std::net::TcpStream::connect(("127.0.0.1", port)).unwrap());
+
+        writeln!(stream, "{REMOTE_MAGIC}").unwrap();
+        wait_client(&server);
+
+        let line = "0".repeat(1 << 20);
+        let start = std::time::Instant::now();
+
+        for _ in 0..256 {
+            server.send_line(&line);
+
+            if server.clients.lock().unwrap().is_empty() { break }
+        }
+
+        assert!(server.clients.lock().unwrap().is_empty());
+        assert!(start.elapsed() < WRITE_TIMEOUT);
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn tls() {
+        let testdata = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../libamdgpu_top/testdata/tls");
+        let port = free_port();
+        let config = HttpServerConfig {
+            cert: Some(testdata.join("cert.pem")),
+            key: Some(testdata.join("key.pem")),
+            ..Default::default()
+        };
+        let server = RemoteServer::bind(("127.0.0.1", port), config).unwrap();
+        let mut stream = libamdgpu_top::tls::connect_with_ca(
+            &format!("localhost:{port}"),
+            Some(&testdata.join("ca.pem")),
+        ).unwrap();
+
+        writeln!(stream, "{REMOTE_MAGIC}").unwrap();
+        stream.flush().unwrap();
+        wait_client(&server);
+        server.send_line("{}");
+
+        let mut line = String::new();
+        BufReader::new(&mut stream).read_line(&mut line).unwrap();
+        assert_eq!(line, "{}\n");
+    }
+}
@@ -0,0 +1,60 @@
+use libamdgpu_top::{DevicePath, DeviceSample, SAMPLE_BUS};
+use serde_json::Value;
+use crate::{json_info, HttpServer, OtlpExporter, OutputJson};
+
+/// Feed the HTTP server and the OTLP exporter with the samples of the running mode
+/// (TUI, GUI or SMI), instead of polling the devices again.
+pub fn spawn_sample_frontends(http_server: Option<HttpServer>, mut otlp: Option<OtlpExporter>) {
+    if http_server.is_none() && otlp.is_none() { return }
+
+    let rx = SAMPLE_BUS.subscribe();
+
+    std::thread::spawn(move || {
+        // the static info is read only once per device
+        let mut infos: Vec<(Option<u32>, Value)> = Vec::new();
+        let mut devices_json: Vec<Value> = Vec::new();
+
+        for samples in rx {
+            if let Some(otlp) = &mut otlp {
+                otlp.export_if_due(&samples);
+            }
+
+            let Some(http_server) = &http_server else { continue };
+
+            devices_json.clear();
+            devices_json.extend(samples.iter().map(|sample| {
+                let info = match infos.iter().find(|(instance, _)| *instance == sample.instance) {
+                    Some((_, info)) => info.clone(),
+                    None => {
+                        let info = device_info_json(&sample.device_path).unwrap_or_default();
+                        infos.push((sample.instance, info.clone()));
+                        info
+                    },
+                };
+
+                sample_json(sample, info)
+            }));
+
+            http_server.update(&samples, &devices_json);
+        }
+    });
+}
+
+fn sample_json(sample: &DeviceSample, info: Value) -> Value {
+    let mut v = sample.json();
+
+    if let Some(m) = v.as_object_mut() {
+        m.insert("Info".to_string(), info);
+    }
+
+    v
+}
+
+fn device_info_json(device_path: &DevicePath) -> Option<Value> {
+    let amdgpu_dev = device_path.init().ok()?;
+    let pci_bus = amdgpu_dev.get_pci_bus_info().ok()?;
+    let ext_info = amdgpu_dev.device_info().ok()?;
+    let memory_info = amdgpu_dev.memory_info().ok()?;
+
+    Some(json_info(&amdgpu_dev, &pci_bus, &ext_info, &memory_info))
+}
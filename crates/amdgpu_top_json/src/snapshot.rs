@@ -0,0 +1,210 @@
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use libamdgpu_top::DevicePath;
+use serde_json::{json, Map, Value};
+use crate::{amdgpu_top_version, JsonDeviceInfo, OutputJson};
+
+// `amdgpu_top snapshot [<file>]`: the full state of the devices (a `-J` sample after an interval),
+// `amdgpu_top diff <a.json> <b.json>`: a human-readable diff of two snapshots or `-J`/`--json-output-dir` lines,
+// e.g. to compare before/after a driver update in a support thread.
+
+/// The sections compared by leaf, the processes ("fdinfo") are compared by PID
+const SECTIONS: &[&str] = &["Info", "Sensors", "gpu_activity", "VRAM", "FW Errors", "DPM Clocks"];
+
+pub fn snapshot(title: &str, device_path_list: &[DevicePath], interval: Duration) -> Value {
+    let mut vec_device_info = JsonDeviceInfo::from_device_path_list(device_path_list);
+    let delay = interval / 100;
+
+    // the usage of the first sample is based on the interval
+    for _ in 0..100 {
+        for device in vec_device_info.iter_mut() {
+            device.update_pc();
+        }
+        std::thread::sleep(delay);
+    }
+
+    let devices: Vec<Value> = vec_device_info.iter_mut().map(|device| {
+        device.update(interval);
+        device.json()
+    }).collect();
+    let unix_time = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    let kernel = fs::read_to_string("/proc/sys/kernel/osrelease").ok().map(|s| s.trim().to_string());
+
+    json!({
+        "snapshot": {
+            "timestamp": { "value": unix_time, "unit": "ms" },
+            "kernel": kernel,
+        },
+        "devices": devices,
+        "devices_len": devices.len(),
+        "amdgpu_top_version": amdgpu_top_version(),
+        "title": title,
+    })
+}
+
+/// A snapshot, the last line of `-J` (stdout) or `--json-output-dir` (NDJSON)
+pub fn load_snapshot(path: &str) -> io::Result<Value> {
+    let s = fs::read_to_string(path)?;
+
+    serde_json::from_str(&s)
+        .or_else(|_| {
+            let line = s.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or_default();
+            serde_json::from_str(line)
+        })
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+fn devices(v: &Value) -> Vec<&Value> {
+    match v["devices"].as_array() {
+        Some(devices) => devices.iter().collect(),
+        None if v["device"].is_object() => vec![&v["device"]],
+        None => Vec::new(),
+    }
+}
+
+/// `{ "value": 1, "unit": "MHz" }` is a leaf
+fn flatten(prefix: &str, v: &Value, out: &mut Vec<(String, Value)>) {
+    match v {
+        Value::Object(m) if !is_value_unit(m) => for (k, v) in m {
+            let path = if prefix.is_empty() { k.clone() } else { format!("{prefix}.{k}") };
+            flatten(&path, v, out);
+        },
+        _ => out.push((prefix.to_string(), v.clone())),
+    }
+}
+
+fn is_value_unit(m: &Map<String, Value>) -> bool {
+    m.contains_key("value") && m.contains_key("unit")
+}
+
+fn leaf_str(v: &Value) -> String {
+    match v {
+        Value::Object(m) if is_value_unit(m) => format!("{} {}", leaf_str(&m["value"]), m["unit"].as_str().unwrap_or("")),
+        Value::String(s) => s.clone(),
+        Value::Null => "-".to_string(),
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if n.is_f64() => format!("{f:.2}"),
+            _ => n.to_string(),
+        },
+        _ => v.to_string(),
+    }
+}
+
+fn leaf_f64(v: &Value) -> Option<f64> {
+    match v {
+        Value::Object(m) if is_value_unit(m) => m["value"].as_f64(),
+        _ => v.as_f64(),
+    }
+}
+
+fn diff_section(buf: &mut String, name: &str, a: &Value, b: &Value) -> std::fmt::Result {
+    let [mut leaves_a, mut leaves_b] = [Vec::new(), Vec::new()];
+    flatten("", a, &mut leaves_a);
+    flatten("", b, &mut leaves_b);
+
+    let mut lines = Vec::new();
+
+    for (path, va) in &leaves_a {
+        let vb = leaves_b.iter().find(|(p, _)| p == path).map(|(_, v)| v).unwrap_or(&Value::Null);
+
+        if va == vb { continue }
+
+        let delta = match (leaf_f64(va), leaf_f64(vb)) {
+            (Some(fa), Some(fb)) => format!(" ({:+})", ((fb - fa) * 100.0).round() / 100.0),
+            _ => String::new(),
+        };
+
+        lines.push(format!("  {path}: {} -> {}{delta}", leaf_str(va), leaf_str(vb)));
+    }
+
+    for (path, vb) in leaves_b.iter().filter(|(p, _)| !leaves_a.iter().any(|(pa, _)| pa == p)) {
+        lines.push(format!("  {path}: - -> {}", leaf_str(vb)));
+    }
+
+    if !lines.is_empty() {
+        writeln!(buf, "{name}")?;
+        for line in lines {
+            writeln!(buf, "{line}")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn proc_vram(p: &Value) -> Option<f64> {
+    leaf_f64(&p["usage"]["VRAM"])
+}
+
+fn diff_processes(buf: &mut String, a: &Value, b: &Value) -> std::fmt::Result {
+    let (Some(a), Some(b)) = (a.as_object(), b.as_object()) else { return Ok(()) };
+    let mut lines = Vec::new();
+    let name = |pid: &str, p: &Value| format!("{} ({pid})", p["name"].as_str().unwrap_or(""));
+
+    for (pid, pa) in a {
+        match b.get(pid) {
+            None => lines.push(format!("  - {}", name(pid, pa))),
+            Some(pb) => {
+                let (Some(va), Some(vb)) = (proc_vram(pa), proc_vram(pb)) else { continue };
+                if va == vb { continue }
+                lines.push(format!("  ~ {}: VRAM {va} -> {vb} MiB ({:+})", name(pid, pb), vb - va));
+            },
+        }
+    }
+
+    for (pid, pb) in b.iter().filter(|(pid, _)| !a.contains_key(*pid)) {
+        lines.push(format!("  + {}: VRAM {} MiB", name(pid, pb), proc_vram(pb).unwrap_or(0.0)));
+    }
+
+    if !lines.is_empty() {
+        writeln!(buf, "Processes")?;
+        for line in lines {
+            writeln!(buf, "{line}")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The devices are matched by the PCI bus, otherwise by the order.
+pub fn diff(a: &Value, b: &Value) -> String {
+    let mut buf = String::new();
+    let [devices_a, devices_b] = [devices(a), devices(b)];
+
+    for (key, label) in [("amdgpu_top_version", "amdgpu_top"), ("snapshot", "Snapshot")] {
+        let _ = diff_section(&mut buf, label, &a[key], &b[key]);
+    }
+
+    for (i, da) in devices_a.iter().enumerate() {
+        let pci = da["Info"]["PCI"].as_str();
+        let db = devices_b.iter()
+            .find(|db| pci.is_some() && db["Info"]["PCI"].as_str() == pci)
+            .or(devices_b.get(i));
+        let title = format!(
+            "== {} ({}) ==",
+            pci.unwrap_or("unknown"),
+            da["Info"]["DeviceName"].as_str().unwrap_or(""),
+        );
+        let Some(db) = db else {
+            let _ = writeln!(buf, "{title}\n  only in A");
+            continue;
+        };
+        let mut section = String::new();
+
+        for name in SECTIONS {
+            let _ = diff_section(&mut section, name, &da[name], &db[name]);
+        }
+
+        let _ = diff_processes(&mut section, &da["fdinfo"], &db["fdinfo"]);
+
+        let _ = writeln!(buf, "{title}");
+        buf.push_str(if section.is_empty() { "  no difference\n" } else { &section });
+    }
+
+    for db in devices_b.iter().filter(|db| !devices_a.iter().any(|da| da["Info"]["PCI"] == db["Info"]["PCI"])) {
+        let _ = writeln!(buf, "== {} ==\n  only in B", db["Info"]["PCI"].as_str().unwrap_or("unknown"));
+    }
+
+    buf
+}
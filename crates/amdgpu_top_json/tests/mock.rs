@@ -0,0 +1,109 @@
+// The JSON output of `--mock docs/mock/navi31`
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use amdgpu_top_json::mock_line;
+use libamdgpu_top::MockDevice;
+use serde_json::Value;
+
+const INTERVAL: Duration = Duration::from_secs(1);
+
+fn fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../docs/mock/navi31")
+}
+
+fn value(v: &Value) -> &Value {
+    &v["value"]
+}
+
+#[test]
+fn navi31() {
+    let devices = [MockDevice::load(fixture(), INTERVAL).unwrap()];
+    let line = mock_line("navi31", &devices, Instant::now());
+
+    assert_eq!(line["title"], "navi31");
+    assert_eq!(line["devices_len"], 1);
+
+    let device = &line["devices"][0];
+    let info = &device["Info"];
+
+    assert_eq!(info["DeviceName"], "AMD Radeon RX 7900 XTX (mock)");
+    assert_eq!(info["PCI"], "0000:03:00.0");
+    assert_eq!(info["VRAM Size"], 25753026560u64);
+
+    let sensors = &device["Sensors"];
+
+    assert_eq!(value(&sensors["GFX_SCLK"]), 2304);
+    assert_eq!(value(&sensors["GFX_MCLK"]), 1249);
+    assert_eq!(value(&sensors["VDDGFX"]), 890);
+    assert_eq!(sensors["VDDNB"], Value::Null);
+    assert_eq!(value(&sensors["Edge Temperature"]), 52);
+    assert_eq!(value(&sensors["Junction Temperature"]), 61);
+    assert_eq!(value(&sensors["Memory Temperature"]), 70);
+    assert_eq!(value(&sensors["GFX Power"]), 180);
+    assert_eq!(value(&sensors["Fan"]), 1450);
+    assert_eq!(sensors["Health"]["VDDNB"], "not supported");
+
+    let vram = &device["VRAM"];
+
+    assert_eq!(value(&vram["Total VRAM"]), 24560);
+    assert_eq!(value(&vram["Total VRAM Usage"]), 3072);
+    assert_eq!(value(&vram["Total GTT Usage"]), 512);
+
+    let activity = &device["gpu_activity"];
+
+    assert_eq!(value(&activity["GFX"]), 37);
+    assert_eq!(value(&activity["Memory"]), 12);
+
+    let proc = &device["fdinfo"]["2345"];
+
+    assert_eq!(proc["name"], "vkcube");
+    assert_eq!(value(&proc["usage"]["VRAM"]), 256);
+    assert_eq!(value(&proc["usage"]["GTT"]), 8);
+    // the first sample, no busy time yet
+    assert_eq!(value(&proc["usage"]["GFX"]), 0);
+}
+
+fn copy_dir(src: &Path, dst: &Path) {
+    fs::create_dir_all(dst).unwrap();
+
+    for entry in fs::read_dir(src).unwrap() {
+        let entry = entry.unwrap();
+        let dst = dst.join(entry.file_name());
+
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&entry.path(), &dst);
+        } else {
+            fs::copy(entry.path(), dst).unwrap();
+        }
+    }
+}
+
+/// The fixture is read every update, rewrite it like a replay script
+#[test]
+fn navi31_replay() {
+    let dir = std::env::temp_dir().join(format!("amdgpu_top-mock-{}", std::process::id()));
+    copy_dir(&fixture(), &dir);
+
+    let mut devices = [MockDevice::load(&dir, INTERVAL).unwrap()];
+    let fdinfo = dir.join("proc/2345/fdinfo/5");
+    let s = fs::read_to_string(&fdinfo).unwrap()
+        .replace("drm-engine-gfx:\t1200000000 ns", "drm-engine-gfx:\t1700000000 ns");
+
+    fs::write(&fdinfo, s).unwrap();
+    fs::write(dir.join("gpu_busy_percent"), "99\n").unwrap();
+    fs::write(dir.join("hwmon/temp1_input"), "80000\n").unwrap();
+
+    devices[0].update();
+
+    let line = mock_line("navi31", &devices, Instant::now());
+    let device = &line["devices"][0];
+
+    assert_eq!(value(&device["gpu_activity"]["GFX"]), 99);
+    assert_eq!(value(&device["Sensors"]["Edge Temperature"]), 80);
+    // 500 ms of GFX in the interval of 1 s
+    assert_eq!(value(&device["fdinfo"]["2345"]["usage"]["GFX"]), 50);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
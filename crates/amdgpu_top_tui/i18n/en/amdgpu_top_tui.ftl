@@ -0,0 +1,82 @@
+device_list = Device List [ESC]
+quit = Quit
+
+# Panels
+sensors = Sensors
+gpu_metrics = GPU Metrics
+dpm_clocks = DPM Clocks
+kernel_messages = Kernel Messages
+leak_suspects = Leak Suspects
+session_stats = Session Min/Avg/Max
+sqtt_capture = SQTT Capture (RGP)
+queue_priority = Queue Priority
+activity_sources = Activity Sources
+memory_usage = Memory Usage
+processes = Processes
+
+snapshot_saved = Saved to { $path }
+snapshot_failed = Failed to save { $path }: { $err }
+
+# SMI mode
+smi_help = (p)rocesses (q)uit
+
+# PID mode
+pid_help = (q)uit
+pid_not_opened = The process does not open this device.
+pid_not_found = PID { $pid } is not found.
+pid_exited = PID { $pid } has exited.
+
+# Remote mode
+remote_help = (q)uit
+remote_devices = Devices
+remote_connecting = Connecting to { $addr } ...
+remote_connect_failed = Failed to connect to { $addr }: { $err }
+remote_disconnected = Disconnected from { $addr }.
+remote_fleet = Fleet
+remote_fleet_total = Total ({ $hosts } hosts)
+remote_fleet_gpus = { $gpus } GPUs
+
+# each line is indented by one space in the TUI
+toggle_help =
+    (g)rbm g(r)bm2 (v)ram_usage (f)dinfo
+    se(n)sor (m)etrics (h)igh_freq (q)uit
+    (P): sort_by_pid (V): sort_by_vram (G): sort_by_gfx
+    (M): sort_by_media (R): reverse (u): raw fdinfo values
+    (w): smoothing window (instant, 1s EMA, 5s EMA)
+    (i): amdgpu_pm_info (root) (d): DPM clocks
+    (k): kernel messages (c): group fdinfo by cgroup/user
+    (s): include shared buffers in fdinfo memory
+    (t): trigger an SQTT capture of RADV (--sqtt-trigger)
+    (a): session min/avg/max of the sensors and the perf counters
+    (z): compact layout, (Left/Right): switch the device
+    (e): save the panels as plain text to the current directory
+
+compact_help =
+    (z): full layout (Left/Right): device (Tab): sort
+    (f)dinfo se(n)sor (v)ram (q)uit
+
+## GRBM
+Graphics_Pipe = Graphics Pipe
+Texture_Pipe = Texture Pipe
+Command_Processor = Command_Processor
+Global_Data_Share = Global Data Share
+Shader_Export = Shader Export
+Shader_Processor_Interpolator = Shader Processor Interpolator
+Primitive_Assembly = Primitive Assembly
+Depth_Block = Depth Block
+Color_Block = Color Block
+Geometry_Engine = Geometry engine
+Vertex_Grouper__Tessellator = Vertex Grouper / Tessellator
+Input_Assembly = Input Assembly
+Work_Distributor = Work Distributor
+
+## GRBM2
+RunList_Controller = RunList Controller
+Texture_Cache = Texture Cache
+Unified_Translation_Cache_Level-2 = Unified Translation Cache Level-2
+Efficiency_Arbiter = Efficiency Arbiter
+Render_Backend_Memory_Interface = Render Backend Memory Interface
+Command_Processor_-__Fetcher = Command Processor -  Fetcher
+Command_Processor_-__Compute = Command Processor -  Compute
+Command_Processor_-_Graphics = Command Processor - Graphics
+SDMA = SDMA
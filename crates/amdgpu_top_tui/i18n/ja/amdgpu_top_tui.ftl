@@ -0,0 +1,6 @@
+device_list = デバイス一覧 [ESC]
+quit = 終了
+
+sensors = センサー
+memory_usage = メモリ使用量
+processes = プロセス
@@ -3,26 +3,44 @@ use cursive::align::HAlign;
 use cursive::views::{LinearLayout, TextView, Panel, ResizedView};
 use cursive::view::SizeConstraint;
 
-use libamdgpu_top::AMDGPU::{DeviceHandle, drm_amdgpu_info_device, drm_amdgpu_memory_info, GPU_INFO};
-use libamdgpu_top::{DevicePath, PCI, Sampling};
-use libamdgpu_top::stat::{self, PcieBw, ProcInfo, Sensors};
+use libamdgpu_top::AMDGPU::{DeviceHandle, drm_amdgpu_info_device, drm_amdgpu_memory_info, GPU_INFO, GpuMetrics};
+use libamdgpu_top::{DevicePath, DeviceSample, PCI, Sampling, SessionSummary};
+use libamdgpu_top::stat::{self, FwErrorStat, GpuActivity, PcieBw, ProcInfo, Sensors, VcnState};
 
-use crate::{TOGGLE_HELP, ToggleOptions, view::*};
+use crate::{compact_help, fl, toggle_help, ToggleOptions, view::*};
 
 pub(crate) struct TuiApp {
     pub amdgpu_dev: DeviceHandle,
     pub device_path: DevicePath,
     pub instance: u32,
     pub list_name: String,
+    pub nickname: Option<String>,
     pub device_info: String,
     pub grbm: PerfCounterView,
     pub grbm2: PerfCounterView,
+    /// `--source pmu`, the GRBM registers are not polled
+    pub pmu: Option<PmuView>,
     pub fdinfo: FdInfoView,
     pub arc_proc_index: Arc<Mutex<Vec<ProcInfo>>>,
     pub gpu_metrics: GpuMetricsView,
     pub vram_usage: VramUsageView,
     pub sensors: SensorsView,
-    pub arc_pcie_bw: Option<Arc<Mutex<PcieBw>>>,
+    /// `Err` with the reason if not supported
+    pub arc_pcie_bw: Result<Arc<Mutex<PcieBw>>, &'static str>,
+    pub arc_fw_error: Option<Arc<Mutex<FwErrorStat>>>,
+    pub vcn_state: Option<VcnState>,
+    pub pm_info: Option<PmInfoView>,
+    pub dpm_clock: Option<DpmClockView>,
+    pub kmsg: Option<KmsgView>,
+    pub queue_priority: Option<QueuePriorityView>,
+    /// `--sysfs-busy`
+    pub activity_sources: Option<ActivitySourcesView>,
+    /// `--leak-detect`
+    pub leak: Option<LeakView>,
+    /// `--sqtt-trigger`
+    pub sqtt: Option<SqttView>,
+    pub summary: SessionSummary,
+    pub stats: StatsView,
 }
 
 impl TuiApp {
@@ -35,14 +53,27 @@ impl TuiApp {
         let instance = device_path.get_instance_number().unwrap();
         let pci_bus = amdgpu_dev.get_pci_bus_info().unwrap();
         let sensors = Sensors::new(&amdgpu_dev, &pci_bus, ext_info);
+        let gpu_clk = amdgpu_dev.get_min_max_gpu_clock()
+            .unwrap_or_else(|| (0, (ext_info.max_engine_clock() / 1000) as u32));
+        let mem_clk = amdgpu_dev.get_min_max_memory_clock()
+            .unwrap_or_else(|| (0, (ext_info.max_memory_clock() / 1000) as u32));
         let device_info = info_bar(
-            &amdgpu_dev,
+            &amdgpu_dev.get_marketing_name_or_default(),
             ext_info,
             &pci_bus,
             memory_info.vram.total_heap_size,
+            gpu_clk,
+            mem_clk,
         );
         let sensors_view = SensorsView::new_with_sensors(sensors);
-        let list_name = format!("{} ({pci_bus})", amdgpu_dev.get_marketing_name_or_default());
+        let nickname = device_path.nickname();
+        let list_name = match &nickname {
+            Some(nickname) => format!(
+                "{nickname}: {} ({pci_bus})",
+                amdgpu_dev.get_marketing_name_or_default(),
+            ),
+            None => format!("{} ({pci_bus})", amdgpu_dev.get_marketing_name_or_default()),
+        };
         let chip_class = ext_info.get_chip_class();
 
         let grbm = PerfCounterView::new_with_chip_class(stat::PCType::GRBM, chip_class, instance);
@@ -67,77 +98,97 @@ impl TuiApp {
 
         let gpu_metrics = GpuMetricsView::new(&amdgpu_dev);
         let arc_pcie_bw = {
-            let pcie_bw = PcieBw::new(pci_bus.get_sysfs_path());
+            let mut pcie_bw = PcieBw::new(pci_bus.get_sysfs_path());
 
-            if pcie_bw.check_pcie_bw_support(ext_info) {
-                Some(pcie_bw.spawn_update_thread())
-            } else {
-                None
-            }
+            pcie_bw.detect_source(ext_info).map(|_| pcie_bw.spawn_update_thread())
         };
+        let arc_fw_error = FwErrorStat::spawn_update_thread(&pci_bus);
+        let vcn_state = VcnState::get(instance).ok();
+        let pm_info = PmInfoView::new(instance);
+        let dpm_clock = DpmClockView::new(pci_bus.get_sysfs_path());
+        let kmsg = KmsgView::new(&pci_bus);
+        let queue_priority = QueuePriorityView::new(instance);
 
         Self {
             amdgpu_dev,
             device_path: device_path.clone(),
             instance,
             list_name,
+            nickname,
             device_info,
             grbm,
             grbm2,
+            pmu: PmuView::new(device_path),
             arc_proc_index,
             fdinfo,
             vram_usage,
             sensors: sensors_view,
             arc_pcie_bw,
+            arc_fw_error,
+            vcn_state,
+            pm_info,
+            dpm_clock,
+            kmsg,
+            queue_priority,
+            activity_sources: None,
+            leak: None,
+            sqtt: SqttView::new(),
             gpu_metrics,
+            summary: SessionSummary::new(pci_bus),
+            stats: StatsView::default(),
         }
     }
 
     pub fn fill(&mut self, toggle_opt: &mut ToggleOptions) {
-        if self.gpu_metrics.update_metrics(&self.amdgpu_dev).is_ok() {
+        if self.gpu_metrics.update_metrics().is_ok() {
             toggle_opt.gpu_metrics = true;
-            self.gpu_metrics.print().unwrap();
+            self.gpu_metrics.print(Sampling::low().to_duration()).unwrap();
             self.gpu_metrics.text.set();
         }
 
-        self.vram_usage.set_value();
+        self.vram_usage.set_value(toggle_opt.number_format);
 
         self.sensors.update(&self.amdgpu_dev);
-        self.sensors.print().unwrap();
+        self.sensors.print(toggle_opt.temp_unit, Sampling::low().to_duration()).unwrap();
 
         self.sensors.text.set();
     }
 
-    pub fn layout(&self, title: &str, toggle_opt: &ToggleOptions) -> ResizedView<LinearLayout> {
-        let mut layout = LinearLayout::vertical()
-            .child(
-                Panel::new(
-                    TextView::new(&self.device_info).center()
-                )
-                .title(title)
-                .title_position(HAlign::Center)
-            );
-
-        layout.add_child(self.grbm.top_view(toggle_opt.grbm));
-        layout.add_child(self.grbm2.top_view(toggle_opt.grbm2));
-        layout.add_child(self.vram_usage.view());
-        layout.add_child(self.fdinfo.text.panel("fdinfo"));
-        layout.add_child(self.sensors.text.panel("Sensors"));
-
-        if toggle_opt.gpu_metrics {
-            let title = match self.gpu_metrics.version() {
-                Some(v) => format!("GPU Metrics v{}.{}", v.0, v.1),
-                None => "GPU Metrics".to_string(),
-            };
+    /// The parts of the panels, to build the layout again in the UI thread.
+    /// The contents are shared with the update thread.
+    pub fn app_layout(&self) -> AppLayout {
+        let gpu_metrics_title = match self.gpu_metrics.version() {
+            Some(v) => format!("{} v{}.{}", fl!("gpu_metrics"), v.0, v.1),
+            None => fl!("gpu_metrics"),
+        };
 
-            layout.add_child(self.gpu_metrics.text.panel(&title));
+        AppLayout {
+            instance: self.instance,
+            pci: self.device_path.pci,
+            list_name: self.list_name.clone(),
+            nickname: self.nickname.clone(),
+            device_info: self.device_info.clone(),
+            pmu: self.pmu.as_ref().map(|pmu| (pmu.title(), pmu.text.clone())),
+            grbm: self.grbm.clone(),
+            grbm2: self.grbm2.clone(),
+            vram_usage: self.vram_usage.clone(),
+            fdinfo: self.fdinfo.text.clone(),
+            leak: self.leak.as_ref().map(|leak| leak.text.clone()),
+            sensors: self.sensors.text.clone(),
+            gpu_metrics: (gpu_metrics_title, self.gpu_metrics.text.clone()),
+            pm_info: self.pm_info.as_ref().map(|pm_info| pm_info.text.clone()),
+            dpm_clock: self.dpm_clock.as_ref().map(|dpm_clock| dpm_clock.text.clone()),
+            kmsg: self.kmsg.as_ref().map(|kmsg| kmsg.text.clone()),
+            queue_priority: self.queue_priority.as_ref().map(|queue_priority| queue_priority.text.clone()),
+            activity_sources: self.activity_sources.as_ref().map(|activity_sources| activity_sources.text.clone()),
+            sqtt: self.sqtt.as_ref().map(|sqtt| sqtt.text.clone()),
+            stats: self.stats.text.clone(),
         }
-        layout.add_child(TextView::new(TOGGLE_HELP));
-
-        ResizedView::new(SizeConstraint::Free, SizeConstraint::Full, layout)
     }
 
     pub fn update_pc(&mut self, flags: &ToggleOptions) {
+        if self.pmu.is_some() { return }
+
         // high frequency accesses to registers can cause high GPU clocks
         if flags.grbm {
             self.grbm.pc.read_reg(&self.amdgpu_dev);
@@ -154,12 +205,28 @@ impl TuiApp {
 
         if flags.sensor {
             self.sensors.update(&self.amdgpu_dev);
-            self.sensors.print().unwrap();
+            self.sensors.print(flags.temp_unit, sample.to_duration()).unwrap();
+
+            match &self.arc_pcie_bw {
+                Ok(arc_pcie_bw) => {
+                    let lock = arc_pcie_bw.try_lock();
+                    if let Ok(ref pcie_bw) = lock {
+                        self.sensors.print_pcie_bw(pcie_bw).unwrap();
+                    }
+                },
+                Err(reason) => self.sensors.print_pcie_bw_unsupported(reason).unwrap(),
+            }
 
-            if let Some(arc_pcie_bw) = &self.arc_pcie_bw {
-                let lock = arc_pcie_bw.try_lock();
-                if let Ok(ref pcie_bw) = lock {
-                    self.sensors.print_pcie_bw(pcie_bw).unwrap();
+            if let Some(vcn_state) = &mut self.vcn_state {
+                if vcn_state.update().is_ok() {
+                    self.sensors.print_vcn_state(vcn_state).unwrap();
+                }
+            }
+
+            if let Some(arc_fw_error) = &self.arc_fw_error {
+                let lock = arc_fw_error.try_lock();
+                if let Ok(ref fw_error) = lock {
+                    self.sensors.print_fw_error(fw_error).unwrap();
                 }
             }
         } else {
@@ -169,8 +236,18 @@ impl TuiApp {
         if flags.fdinfo {
             let lock = self.arc_proc_index.try_lock();
             if let Ok(vec_info) = lock {
+                self.fdinfo.raw = flags.fdinfo_raw;
+                self.fdinfo.shared = flags.fdinfo_shared;
+                self.fdinfo.group_by = flags.fdinfo_group;
+                self.fdinfo.compact = flags.compact;
+                self.fdinfo.stat.smoothing = flags.smoothing;
                 self.fdinfo.print(&vec_info, &flags.fdinfo_sort, flags.reverse_sort).unwrap();
                 self.fdinfo.stat.interval = sample.to_duration();
+
+                if let Some(leak) = &mut self.leak {
+                    leak.update(&self.fdinfo.stat.proc_usage);
+                    leak.print().unwrap();
+                }
             } else {
                 self.fdinfo.stat.interval += sample.to_duration();
             }
@@ -179,34 +256,315 @@ impl TuiApp {
         }
 
         if flags.gpu_metrics {
-            if self.gpu_metrics.update_metrics(&self.amdgpu_dev).is_ok() {
-                self.gpu_metrics.print().unwrap();
-            }
+            // the last metrics are printed with the stale mark if the read fails
+            let _ = self.gpu_metrics.update_metrics();
+            self.gpu_metrics.print(sample.to_duration()).unwrap();
         } else {
             self.gpu_metrics.text.clear();
         }
 
-        self.grbm.dump();
-        self.grbm2.dump();
+        if let Some(pm_info) = &mut self.pm_info {
+            if flags.pm_info {
+                pm_info.update();
+                pm_info.print(flags.temp_unit, flags.number_format).unwrap();
+            } else {
+                pm_info.text.clear();
+            }
+
+            pm_info.text.set();
+        }
+
+        if let Some(dpm_clock) = &mut self.dpm_clock {
+            if flags.dpm_clock {
+                dpm_clock.update();
+                dpm_clock.print().unwrap();
+            } else {
+                dpm_clock.text.clear();
+            }
+
+            dpm_clock.text.set();
+        }
+
+        if let Some(kmsg) = &mut self.kmsg {
+            if flags.kmsg {
+                kmsg.print().unwrap();
+            } else {
+                kmsg.text.clear();
+            }
+
+            kmsg.text.set();
+        }
 
-        self.vram_usage.set_value();
+        if let Some(queue_priority) = &mut self.queue_priority {
+            queue_priority.print().unwrap();
+            queue_priority.text.set();
+        }
+
+        if let Some(pmu) = &mut self.pmu {
+            pmu.update();
+            pmu.print().unwrap();
+            pmu.text.set();
+        }
+
+        if self.sqtt.is_some() {
+            let device_sample = self.sample();
+
+            if let Some(sqtt) = &mut self.sqtt {
+                sqtt.update(device_sample);
+
+                if flags.sqtt_trigger {
+                    sqtt.trigger();
+                }
+
+                sqtt.print().unwrap();
+                sqtt.text.set();
+            }
+        }
+
+        self.grbm.pc.smoothing = flags.smoothing;
+        self.grbm2.pc.smoothing = flags.smoothing;
+        self.grbm.dump(sample.to_duration());
+        self.grbm2.dump(sample.to_duration());
+
+        if let Some(activity_sources) = &mut self.activity_sources {
+            let metrics = self.gpu_metrics.metrics();
+
+            activity_sources.print(
+                (flags.grbm && self.pmu.is_none()).then_some(&self.grbm.pc),
+                (!matches!(metrics, GpuMetrics::Unknown)).then_some(metrics),
+            ).unwrap();
+            activity_sources.text.set();
+        }
+
+        self.vram_usage.set_value(flags.number_format);
         self.fdinfo.text.set();
         self.sensors.text.set();
         self.gpu_metrics.text.set();
+
+        if let Some(leak) = &self.leak {
+            leak.text.set();
+        }
+
+        self.summary.add(self.sensors.sensors(), self.vram_usage.memory_info(), None);
+
+        if self.pmu.is_none() {
+            if flags.grbm { self.summary.add_perf_counter(&self.grbm.pc) }
+            if flags.grbm2 { self.summary.add_perf_counter(&self.grbm2.pc) }
+        }
+
+        if flags.stats {
+            self.stats.print(
+                &self.summary.stats,
+                &[&self.grbm.pc, &self.grbm2.pc],
+                flags.temp_unit,
+            ).unwrap();
+        } else {
+            self.stats.text.clear();
+        }
+
+        self.stats.text.set();
+    }
+
+    /// For `SAMPLE_BUS`
+    pub fn sample(&self) -> DeviceSample {
+        let metrics = self.gpu_metrics.metrics();
+
+        DeviceSample::new(
+            &self.device_path,
+            self.sensors.sensors(),
+            self.vram_usage.memory_info(),
+            &GpuActivity::from_gpu_metrics(metrics),
+            (!matches!(metrics, GpuMetrics::Unknown)).then_some(metrics),
+            &self.fdinfo.stat,
+        )
     }
 }
 
-fn info_bar(
-    amdgpu_dev: &DeviceHandle,
+#[derive(Clone)]
+pub(crate) struct AppLayout {
+    pub instance: u32,
+    pub pci: Option<PCI::BUS_INFO>,
+    pub list_name: String,
+    pub nickname: Option<String>,
+    pub device_info: String,
+    pub pmu: Option<(String, Text)>,
+    pub grbm: PerfCounterView,
+    pub grbm2: PerfCounterView,
+    pub vram_usage: VramUsageView,
+    pub fdinfo: Text,
+    pub leak: Option<Text>,
+    pub sensors: Text,
+    pub gpu_metrics: (String, Text),
+    pub pm_info: Option<Text>,
+    pub dpm_clock: Option<Text>,
+    pub kmsg: Option<Text>,
+    pub queue_priority: Option<Text>,
+    pub activity_sources: Option<Text>,
+    pub sqtt: Option<Text>,
+    pub stats: Text,
+}
+
+impl AppLayout {
+    pub fn layout(&self, title: &str, toggle_opt: &ToggleOptions) -> ResizedView<LinearLayout> {
+        if toggle_opt.compact {
+            return self.compact_layout(toggle_opt);
+        }
+
+        let title = match &self.nickname {
+            Some(nickname) => format!("{title} - {nickname}"),
+            None => title.to_string(),
+        };
+        // the device and the usage, the processes and the sensors, the others
+        let mut groups = [LinearLayout::vertical(), LinearLayout::vertical(), LinearLayout::vertical()];
+
+        groups[0].add_child(
+            Panel::new(
+                TextView::new(&self.device_info).center()
+            )
+            .title(title)
+            .title_position(HAlign::Center)
+        );
+
+        if let Some((title, text)) = &self.pmu {
+            groups[0].add_child(text.panel(title));
+        } else {
+            groups[0].add_child(self.grbm.top_view(toggle_opt.grbm, toggle_opt.ascii));
+            groups[0].add_child(self.grbm2.top_view(toggle_opt.grbm2, toggle_opt.ascii));
+        }
+        if let Some(activity_sources) = &self.activity_sources {
+            groups[0].add_child(activity_sources.panel(&fl!("activity_sources")));
+        }
+        groups[0].add_child(self.vram_usage.view(toggle_opt.vram, toggle_opt.number_format, toggle_opt.ascii));
+        groups[1].add_child(self.fdinfo.panel("fdinfo"));
+        if let Some(leak) = &self.leak {
+            groups[1].add_child(leak.panel(&fl!("leak_suspects")));
+        }
+        groups[1].add_child(self.sensors.panel(&fl!("sensors")));
+
+        if toggle_opt.gpu_metrics {
+            let (title, text) = &self.gpu_metrics;
+            groups[2].add_child(text.panel(title));
+        }
+        if let Some(pm_info) = &self.pm_info {
+            groups[2].add_child(pm_info.panel("amdgpu_pm_info"));
+        }
+        if let Some(dpm_clock) = &self.dpm_clock {
+            groups[2].add_child(dpm_clock.panel(&fl!("dpm_clocks")));
+        }
+        if let Some(kmsg) = &self.kmsg {
+            groups[2].add_child(kmsg.panel(&fl!("kernel_messages")));
+        }
+        if let Some(queue_priority) = &self.queue_priority {
+            groups[2].add_child(queue_priority.panel(&fl!("queue_priority")));
+        }
+        if let Some(sqtt) = &self.sqtt {
+            groups[2].add_child(sqtt.panel(&fl!("sqtt_capture")));
+        }
+        groups[2].add_child(self.stats.panel(&fl!("session_stats")));
+
+        let [g0, g1, g2] = groups;
+        let panels = match toggle_opt.columns {
+            0 | 1 => LinearLayout::vertical().child(g0).child(g1).child(g2),
+            2 => LinearLayout::horizontal().child(g0).child(LinearLayout::vertical().child(g1).child(g2)),
+            _ => LinearLayout::horizontal().child(g0).child(g1).child(g2),
+        };
+        let layout = LinearLayout::vertical()
+            .child(panels)
+            .child(TextView::new(toggle_help()));
+
+        ResizedView::new(SizeConstraint::Free, SizeConstraint::Full, layout)
+    }
+
+    /// The plain text of the panels, for pasting into forum posts.
+    /// The panels toggled off are omitted.
+    pub fn snapshot(&self, title: &str, toggle_opt: &ToggleOptions) -> String {
+        let title = match &self.nickname {
+            Some(nickname) => format!("{title} - {nickname}"),
+            None => title.to_string(),
+        };
+        let mut s = String::new();
+
+        push_section(&mut s, &title, &self.device_info);
+
+        if let Some((title, text)) = &self.pmu {
+            push_section(&mut s, title, &text.get());
+        } else {
+            if toggle_opt.grbm { push_section(&mut s, "GRBM", &self.grbm.snapshot()) }
+            if toggle_opt.grbm2 { push_section(&mut s, "GRBM2", &self.grbm2.snapshot()) }
+        }
+
+        if let Some(activity_sources) = &self.activity_sources {
+            push_section(&mut s, &fl!("activity_sources"), &activity_sources.get());
+        }
+
+        if toggle_opt.vram {
+            push_section(&mut s, &fl!("memory_usage"), &self.vram_usage.snapshot(toggle_opt.number_format));
+        }
+
+        push_section(&mut s, "fdinfo", &self.fdinfo.get());
+
+        if let Some(leak) = &self.leak {
+            push_section(&mut s, &fl!("leak_suspects"), &leak.get());
+        }
+
+        push_section(&mut s, &fl!("sensors"), &self.sensors.get());
+
+        if toggle_opt.gpu_metrics {
+            let (title, text) = &self.gpu_metrics;
+            push_section(&mut s, title, &text.get());
+        }
+
+        for (title, text) in [
+            ("amdgpu_pm_info".to_string(), &self.pm_info),
+            (fl!("dpm_clocks"), &self.dpm_clock),
+            (fl!("kernel_messages"), &self.kmsg),
+            (fl!("queue_priority"), &self.queue_priority),
+        ] {
+            if let Some(text) = text {
+                push_section(&mut s, &title, &text.get());
+            }
+        }
+
+        push_section(&mut s, &fl!("session_stats"), &self.stats.get());
+
+        s
+    }
+
+    /// For small terminals (80x24, the console of Steam Deck),
+    /// only the device name, VRAM, the top processes and the sensors.
+    fn compact_layout(&self, toggle_opt: &ToggleOptions) -> ResizedView<LinearLayout> {
+        let mut layout = LinearLayout::vertical()
+            .child(TextView::new(format!(" #{} {}", self.instance, self.list_name)))
+            .child(self.vram_usage.view(toggle_opt.vram, toggle_opt.number_format, toggle_opt.ascii))
+            .child(self.fdinfo.panel("fdinfo"))
+            .child(self.sensors.panel(&fl!("sensors")));
+
+        if let Some(queue_priority) = &self.queue_priority {
+            layout.add_child(queue_priority.panel(&fl!("queue_priority")));
+        }
+
+        layout.add_child(TextView::new(compact_help()));
+
+        ResizedView::new(SizeConstraint::Free, SizeConstraint::Full, layout)
+    }
+}
+
+/// The empty panels (toggled off) are skipped
+fn push_section(s: &mut String, title: &str, body: &str) {
+    if body.trim().is_empty() { return }
+
+    s.push_str(&format!("== {title} ==\n{}\n\n", body.trim_end()));
+}
+
+/// `gpu_clk`, `mem_clk`: (min, max) MHz
+pub(crate) fn info_bar(
+    mark_name: &str,
     ext_info: &drm_amdgpu_info_device,
     pci_bus: &PCI::BUS_INFO,
     total_vram_size: u64,
+    (min_gpu_clk, max_gpu_clk): (u32, u32),
+    (min_mem_clk, max_mem_clk): (u32, u32),
 ) -> String {
-    let (min_gpu_clk, max_gpu_clk) = amdgpu_dev.get_min_max_gpu_clock()
-        .unwrap_or_else(|| (0, (ext_info.max_engine_clock() / 1000) as u32));
-    let (min_mem_clk, max_mem_clk) = amdgpu_dev.get_min_max_memory_clock()
-        .unwrap_or_else(|| (0, (ext_info.max_memory_clock() / 1000) as u32));
-
     format!(
         concat!(
             "{mark_name} ({pci}, {did:#06X}:{rid:#04X})\n",
@@ -214,7 +572,7 @@ fn info_bar(
             "{vram_type} {vram_bus_width}-bit, {vram_size} MiB, ",
             "{min_memory_clk}-{max_memory_clk} MHz",
         ),
-        mark_name = amdgpu_dev.get_marketing_name_or_default(),
+        mark_name = mark_name,
         pci = pci_bus,
         did = ext_info.device_id(),
         rid = ext_info.pci_rev_id(),
@@ -1,20 +1,33 @@
 use std::sync::{Arc, Mutex};
 use cursive::view::{Nameable, Scrollable};
-use cursive::{event::Key, menu, traits::With};
+use cursive::{event::{Event, Key}, menu, traits::With};
 
 use libamdgpu_top::AMDGPU::DeviceHandle;
-use libamdgpu_top::{stat, DevicePath, Sampling};
+use libamdgpu_top::{stat, DevicePath, exit_code, exit_code::ExitReason, NumberFormat, SAMPLE_BUS, Sampling, SessionOptions, SessionSummary, TempUnit};
 use stat::{PCType, ProcInfo};
 
 mod view;
 use view::*;
 
 mod app;
-use app::TuiApp;
+use app::{AppLayout, TuiApp};
 
 mod smi;
 pub use smi::run_smi;
 
+mod pid;
+pub use pid::run_pid;
+
+mod remote;
+pub use remote::run_remote;
+
+mod mock;
+pub use mock::run_mock;
+
+mod localize;
+pub use localize::LANGUAGE_LOADER;
+use localize::select_language;
+
 #[derive(Debug, Clone)]
 struct ToggleOptions {
     grbm: bool,
@@ -24,8 +37,28 @@ struct ToggleOptions {
     high_freq: bool,
     fdinfo: bool,
     fdinfo_sort: stat::FdInfoSortType,
+    fdinfo_raw: bool,
+    fdinfo_shared: bool,
+    fdinfo_group: stat::FdInfoGroupBy,
     reverse_sort: bool,
     gpu_metrics: bool,
+    pm_info: bool,
+    dpm_clock: bool,
+    kmsg: bool,
+    /// (a), session min/avg/max
+    stats: bool,
+    /// (t), reset by the update thread
+    sqtt_trigger: bool,
+    /// (z), only the essential panels for small terminals
+    compact: bool,
+    /// (z) was pressed, the resize does not change `compact`
+    compact_fixed: bool,
+    /// The columns of the panels, by the width of the terminal
+    columns: usize,
+    smoothing: stat::Smoothing,
+    temp_unit: TempUnit,
+    number_format: NumberFormat,
+    ascii: bool,
     select_instance: u32,
     instances: Vec<u32>,
 }
@@ -40,8 +73,23 @@ impl Default for ToggleOptions {
             high_freq: false,
             fdinfo: true,
             fdinfo_sort: Default::default(),
+            fdinfo_raw: false,
+            fdinfo_shared: false,
+            fdinfo_group: Default::default(),
             reverse_sort: false,
             gpu_metrics: false,
+            pm_info: false,
+            dpm_clock: false,
+            kmsg: false,
+            stats: false,
+            sqtt_trigger: false,
+            compact: false,
+            compact_fixed: false,
+            columns: 1,
+            smoothing: Default::default(),
+            temp_unit: Default::default(),
+            number_format: NumberFormat::from_env(),
+            ascii: false,
             select_instance: 0,
             instances: Vec::new(),
         }
@@ -57,52 +105,127 @@ pub const TOGGLE_HELP: &str = concat!(
     " (P): sort_by_pid (V): sort_by_vram (G): sort_by_gfx\n (M): sort_by_media (R): reverse"
 );
 */
-pub const TOGGLE_HELP: &str = concat!(
-    " (g)rbm g(r)bm2 (v)ram_usage (f)dinfo\n se(n)sor (m)etrics (h)igh_freq (q)uit \n",
-    " (P): sort_by_pid (V): sort_by_vram (G): sort_by_gfx\n (M): sort_by_media (R): reverse"
-);
+/// Messages in the other languages are not ASCII, so `--ascii` defaults to English
+fn ascii_lang(lang: Option<&str>, ascii: bool) -> Option<&str> {
+    if ascii { lang.or(Some("en")) } else { lang }
+}
 
-pub fn run(
-    title: &str,
-    select_device_path: DevicePath,
-    select_amdgpu_dev: DeviceHandle,
-    device_path_list: &[DevicePath],
-    interval: u64,
-) {
-    let mut toggle_opt = ToggleOptions::default();
-    let mut vec_app: Vec<TuiApp> = Vec::new();
+pub fn toggle_help() -> String {
+    fl!("toggle_help").lines().map(|line| format!(" {line}")).collect::<Vec<_>>().join("\n")
+}
 
-    for device_path in device_path_list {
-        if select_device_path.render == device_path.render { continue }
+pub fn compact_help() -> String {
+    fl!("compact_help").lines().map(|line| format!(" {line}")).collect::<Vec<_>>().join("\n")
+}
 
-        let Ok(amdgpu_dev) = device_path.init() else { continue };
-        let Ok(ext_info) = amdgpu_dev.device_info() else { continue };
-        let Ok(memory_info) = amdgpu_dev.memory_info() else { continue };
+/// The terminals smaller than this start with the compact layout,
+/// e.g. 80x24, and 160x50 of the console of Steam Deck (1280x800).
+const COMPACT_COLS: u16 = 100;
+const COMPACT_ROWS: u16 = 50;
 
-        let mut app = app::TuiApp::new(amdgpu_dev, device_path, &ext_info, &memory_info);
-        app.fill(&mut toggle_opt);
+/// The width of a column of the panels, 72 characters of the panels with the margin
+const COLUMN_WIDTH: u16 = PANEL_WIDTH as u16 + 5;
+const MAX_COLUMNS: usize = 3;
 
-        vec_app.push(app);
+/// Updates the layout by the current size of the terminal, at startup and on resize (SIGWINCH)
+fn fit_terminal(opt: &mut ToggleOptions) {
+    use cursive::backends::crossterm::crossterm::terminal;
+
+    let Ok((cols, rows)) = terminal::size() else { return };
+
+    if !opt.compact_fixed {
+        opt.compact = cols < COMPACT_COLS || rows <= COMPACT_ROWS;
     }
 
-    {
-        let ext_info = select_amdgpu_dev.device_info().unwrap();
-        let memory_info = select_amdgpu_dev.memory_info().unwrap();
+    opt.columns = ((cols / COLUMN_WIDTH) as usize).clamp(1, MAX_COLUMNS);
+}
 
-        let mut app = app::TuiApp::new(
-            select_amdgpu_dev,
-            &select_device_path,
-            &ext_info,
-            &memory_info
+/// Builds the layers of all devices again, for the change of the layout.
+/// The selected device is on the front.
+fn set_layers(siv: &mut cursive::Cursive, layouts: &[AppLayout], title: &str) {
+    let opt = siv.user_data::<Opt>().unwrap().lock().unwrap().clone();
+    let screen = siv.screen_mut();
+
+    while screen.pop_layer().is_some() {}
+
+    for layout in layouts.iter().filter(|l| l.instance != opt.select_instance)
+        .chain(layouts.iter().filter(|l| l.instance == opt.select_instance))
+    {
+        screen.add_layer(
+            layout.layout(title, &opt)
+                .scrollable()
+                // the compact layout fits the width, Left/Right switch the device
+                .scroll_x(!opt.compact)
+                .scroll_y(true)
+                .show_scrollbars(!opt.ascii)
+                .with_name(layout.instance.to_string())
         );
-        app.fill(&mut toggle_opt);
+    }
+}
 
-        toggle_opt.select_instance = app.instance;
+/// `--once`, one refresh of the TUI layout to stdout, without the terminal UI
+fn print_once(app: &mut TuiApp, title: &str, toggle_opt: &ToggleOptions) {
+    let sample = Sampling::low();
 
-        vec_app.push(app);
+    for _ in 0..sample.count {
+        app.update_pc(toggle_opt);
+        std::thread::sleep(sample.delay);
     }
 
-    toggle_opt.instances = vec_app.iter().map(|app| app.instance).collect();
+    app.update(toggle_opt, &sample);
+
+    print!("{}", app.app_layout().snapshot(title, toggle_opt));
+}
+
+/// (e), saves the plain text of the selected device to
+/// `amdgpu_top_<pci>_<UNIX time>.txt` in the current directory
+fn save_snapshot(siv: &mut cursive::Cursive, layouts: &[AppLayout], title: &str) {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let opt = siv.user_data::<Opt>().unwrap().lock().unwrap().clone();
+    let Some(layout) = layouts.iter().find(|l| l.instance == opt.select_instance) else { return };
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    // ':' is not allowed in file names on some filesystems
+    let pci = layout.pci.map(|pci| pci.to_string().replace(':', "-")).unwrap_or_default();
+    let path = format!("amdgpu_top_{pci}_{secs}.txt");
+    let msg = match std::fs::write(&path, layout.snapshot(title, &opt)) {
+        Ok(_) => fl!("snapshot_saved", path = path),
+        Err(err) => fl!("snapshot_failed", path = path, err = err.to_string()),
+    };
+
+    siv.add_layer(cursive::views::Dialog::info(msg));
+}
+
+fn select_device(siv: &mut cursive::Cursive, instance: u32) {
+    let screen = siv.screen_mut();
+    let Some(pos) = screen.find_layer_from_name(&instance.to_string()) else { return };
+    screen.move_to_front(pos);
+
+    let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+    opt.select_instance = instance;
+}
+
+/// Left/Right, the previous/next device
+fn cycle_device_cb(forward: bool) -> impl Fn(&mut cursive::Cursive) {
+    move |siv: &mut cursive::Cursive| {
+        let next = {
+            let opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+            let len = opt.instances.len();
+            let Some(pos) = opt.instances.iter().position(|i| *i == opt.select_instance) else { return };
+            let pos = if forward { (pos + 1) % len } else { (pos + len - 1) % len };
+
+            opt.instances[pos]
+        };
+
+        select_device(siv, next);
+    }
+}
+
+/// The terminal UI with the key bindings, the menu bar and the layers of the devices,
+/// for the devices and the mock devices (`--mock`, `--demo`)
+fn new_siv(title: &str, layouts: Vec<AppLayout>, toggle_opt: Opt, ascii: bool) -> cursive::CursiveRunnable {
+    let layouts = Arc::new(layouts);
+    let title = title.to_string();
 
     let mut siv = cursive::default();
 
@@ -112,6 +235,9 @@ pub fn run(
         siv.add_global_callback('v', VramUsageView::cb);
         siv.add_global_callback('f', FdInfoView::cb);
         siv.add_global_callback('R', FdInfoView::cb_reverse_sort);
+        siv.add_global_callback('u', FdInfoView::cb_raw);
+        siv.add_global_callback('s', FdInfoView::cb_shared);
+        siv.add_global_callback('c', FdInfoView::cb_group_by);
         siv.add_global_callback('P', FdInfoView::cb_sort_by_pid);
         siv.add_global_callback('V', FdInfoView::cb_sort_by_vram);
         siv.add_global_callback('C', FdInfoView::cb_sort_by_cpu);
@@ -119,77 +245,210 @@ pub fn run(
         siv.add_global_callback('M', FdInfoView::cb_sort_by_media);
         siv.add_global_callback('n', SensorsView::cb);
         siv.add_global_callback('m', GpuMetricsView::cb);
+        siv.add_global_callback('i', PmInfoView::cb);
+        siv.add_global_callback('d', DpmClockView::cb);
+        siv.add_global_callback('k', KmsgView::cb);
+        siv.add_global_callback('a', StatsView::cb);
         siv.add_global_callback('q', cursive::Cursive::quit);
+        siv.add_global_callback('w', |siv| {
+            let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+            opt.smoothing = opt.smoothing.next();
+        });
         siv.add_global_callback('h', |siv| {
             let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
             opt.high_freq ^= true;
         });
+        siv.add_global_callback('t', |siv| {
+            let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+            opt.sqtt_trigger = true;
+        });
         siv.add_global_callback(Key::Esc, |siv| siv.select_menubar());
+        // for the controllers mapped to the keys (e.g. Steam Input)
+        siv.add_global_callback(Key::Left, cycle_device_cb(false));
+        siv.add_global_callback(Key::Right, cycle_device_cb(true));
+        siv.add_global_callback(Key::Tab, FdInfoView::cb_next_sort);
+
+        let layouts_snapshot = layouts.clone();
+        let title_snapshot = title.clone();
+        siv.add_global_callback('e', move |siv| save_snapshot(siv, &layouts_snapshot, &title_snapshot));
+
+        let layouts = layouts.clone();
+        let title = title.clone();
+        siv.add_global_callback('z', move |siv| {
+            {
+                let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+                opt.compact ^= true;
+                opt.compact_fixed = true;
+            }
+
+            set_layers(siv, &layouts, &title);
+        });
+    }
+    {
+        let layouts = layouts.clone();
+        let title = title.clone();
+
+        // replaces the default callback, which only clears the screen
+        siv.clear_global_callbacks(Event::WindowResize);
+        siv.set_on_pre_event(Event::WindowResize, move |siv| {
+            siv.clear();
+
+            let changed = {
+                let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+                let prev = (opt.compact, opt.columns);
+                fit_terminal(&mut opt);
+
+                prev != (opt.compact, opt.columns)
+            };
+
+            if changed {
+                set_layers(siv, &layouts, &title);
+            }
+        });
     }
     {
         let menubar = siv.menubar();
         
         menubar.add_subtree(
-            "Device List [ESC]",
+            fl!("device_list"),
             menu::Tree::new()
-                .with(|tree| { for app in &vec_app {
-                    let name = app.list_name.clone();
-                    let instance = app.instance;
+                .with(|tree| { for layout in layouts.iter() {
+                    let name = layout.list_name.clone();
+                    let instance = layout.instance;
 
                     tree.add_leaf(
                         name.clone(),
-                        move |siv: &mut cursive::Cursive| {
-                            let screen = siv.screen_mut();
-                            let Some(pos) = screen.find_layer_from_name(&instance.to_string())
-                                else { return };
-                            screen.move_to_front(pos);
-
-                            let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
-                            opt.select_instance = instance;
-                        },
+                        move |siv: &mut cursive::Cursive| select_device(siv, instance),
                     );
                 }})
-                .delimiter()
-                .leaf("Quit", cursive::Cursive::quit),
+                .with(|tree| if !ascii { tree.add_delimiter() })
+                .leaf(fl!("quit"), cursive::Cursive::quit),
         );
     }
+
+    if ascii {
+        siv.update_theme(ascii_theme);
+    }
+
+    siv.set_autohide_menu(false);
+    siv.set_user_data(toggle_opt);
+    set_layers(&mut siv, &layouts, &title);
+
+    siv
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    title: &str,
+    select_device_path: DevicePath,
+    select_amdgpu_dev: DeviceHandle,
+    device_path_list: &[DevicePath],
+    interval: u64,
+    smoothing: stat::Smoothing,
+    lang: Option<&str>,
+    temp_unit: TempUnit,
+    ascii: bool,
+    session: &SessionOptions,
+    leak_threshold: f64,
+    sysfs_busy: bool,
+    once: bool,
+) -> ExitReason {
+    select_language(ascii_lang(lang, ascii));
+
+    let mut toggle_opt = ToggleOptions { smoothing, temp_unit, ..Default::default() };
+
+    if ascii {
+        toggle_opt.ascii = true;
+        toggle_opt.number_format = toggle_opt.number_format.to_ascii();
+    }
+    let mut vec_app: Vec<TuiApp> = Vec::new();
+
+    for device_path in device_path_list {
+        // `--once` prints only the selected device
+        if once || select_device_path.render == device_path.render { continue }
+
+        let Ok(amdgpu_dev) = device_path.init() else { continue };
+        let Ok(ext_info) = amdgpu_dev.device_info() else { continue };
+        let Ok(memory_info) = amdgpu_dev.memory_info() else { continue };
+
+        let mut app = app::TuiApp::new(amdgpu_dev, device_path, &ext_info, &memory_info);
+        app.leak = LeakView::new(leak_threshold);
+        app.activity_sources = sysfs_busy.then(|| ActivitySourcesView::new(app.summary.pci_bus.get_sysfs_path()));
+        app.fill(&mut toggle_opt);
+
+        vec_app.push(app);
+    }
+
     {
-        let screen = siv.screen_mut();
-        for app in &vec_app {
-            screen.add_layer(
-                app.layout(title, &toggle_opt)
-                    .scrollable()
-                    .scroll_x(true)
-                    .scroll_y(true)
-                    .with_name(&app.instance.to_string())
-            );
+        let ext_info = select_amdgpu_dev.device_info().unwrap();
+        let memory_info = select_amdgpu_dev.memory_info().unwrap();
+
+        let mut app = app::TuiApp::new(
+            select_amdgpu_dev,
+            &select_device_path,
+            &ext_info,
+            &memory_info
+        );
+        app.leak = LeakView::new(leak_threshold);
+        app.activity_sources = sysfs_busy.then(|| ActivitySourcesView::new(app.summary.pci_bus.get_sysfs_path()));
+        app.fill(&mut toggle_opt);
+
+        toggle_opt.select_instance = app.instance;
+
+        vec_app.push(app);
+    }
+
+    toggle_opt.instances = vec_app.iter().map(|app| app.instance).collect();
+
+    if once {
+        if let Some(app) = vec_app.last_mut() {
+            print_once(app, title, &toggle_opt);
         }
+
+        return ExitReason::Done;
     }
 
+    fit_terminal(&mut toggle_opt);
+
     {
         let t_index: Vec<(DevicePath, Arc<Mutex<Vec<ProcInfo>>>)> = vec_app.iter().map(|app| {
             (app.device_path.clone(), app.arc_proc_index.clone())
         }).collect();
-        stat::spawn_update_index_thread(t_index, interval);
+        stat::spawn_update_index_thread(t_index, interval, Default::default());
     }
 
+    let layouts: Vec<AppLayout> = vec_app.iter().map(|app| app.app_layout()).collect();
     let mut flags = toggle_opt.clone();
     let toggle_opt = Arc::new(Mutex::new(toggle_opt));
-
-    siv.set_autohide_menu(false);
-    siv.set_user_data(toggle_opt.clone());
+    let mut siv = new_siv(title, layouts, toggle_opt.clone(), ascii);
 
     let cb_sink = siv.cb_sink().clone();
+    let summaries: Arc<Mutex<Vec<SessionSummary>>> = Default::default();
+    let thread_summaries = summaries.clone();
+    let lost: Arc<Mutex<Option<DevicePath>>> = Default::default();
+    let thread_lost = lost.clone();
+    let thread_session = session.clone();
+    let start = std::time::Instant::now();
+    let mut samples = 0;
 
     std::thread::spawn(move || loop {
         {
             let lock = toggle_opt.try_lock();
-            if let Ok(opt) = lock {
+            if let Ok(mut opt) = lock {
                 flags = opt.clone();
+                opt.sqtt_trigger = false;
             }
         }
 
         let sample = if flags.high_freq { Sampling::high() } else { Sampling::low() };
+        let selected = vec_app.iter().filter(|app| flags.select_instance == app.instance);
+
+        if let Some(device_path) = exit_code::find_lost_device(selected.map(|app| &app.device_path)) {
+            *thread_lost.lock().unwrap() = Some(device_path.clone());
+            // restore the terminal before exiting
+            cb_sink.send(Box::new(cursive::Cursive::quit)).unwrap();
+            break;
+        }
 
         for _ in 0..sample.count {
             for app in vec_app.iter_mut() {
@@ -205,8 +464,33 @@ pub fn run(
             app.update(&flags, &sample);
         }
 
+        flags.sqtt_trigger = false;
+
+        if SAMPLE_BUS.has_subscribers() {
+            SAMPLE_BUS.send(Arc::new(
+                vec_app.iter().filter(|app| flags.select_instance == app.instance).map(|app| app.sample()).collect()
+            ));
+        }
+
+        samples += 1;
+
+        if let Ok(mut summaries) = thread_summaries.lock() {
+            *summaries = vec_app.iter().map(|app| app.summary.clone()).collect();
+        }
+
+        if thread_session.is_over(start, samples) {
+            cb_sink.send(Box::new(cursive::Cursive::quit)).unwrap();
+            break;
+        }
+
         cb_sink.send(Box::new(cursive::Cursive::noop)).unwrap();
     });
 
     siv.run();
+
+    session.write_summary(&summaries.lock().unwrap());
+
+    let lost = lost.lock().unwrap().clone();
+
+    lost.map_or(ExitReason::Done, ExitReason::DeviceLost)
 }
@@ -0,0 +1,63 @@
+use i18n_embed::{
+    fluent::{fluent_language_loader, FluentLanguageLoader},
+    unic_langid::LanguageIdentifier,
+    DefaultLocalizer, DesktopLanguageRequester, LanguageLoader, Localizer,
+};
+use libamdgpu_top::{pseudo_localize, PSEUDO_LOCALE};
+use once_cell::sync::Lazy;
+use rust_embed::RustEmbed;
+
+#[derive(RustEmbed)]
+#[folder = "i18n/"]
+struct Localizations;
+
+pub static LANGUAGE_LOADER: Lazy<FluentLanguageLoader> = Lazy::new(|| {
+    let loader: FluentLanguageLoader = fluent_language_loader!();
+
+    loader
+        .load_fallback_language(&Localizations)
+        .expect("Error while loading fallback language");
+    // Unicode isolation marks are printed as is in the terminal
+    loader.set_use_isolating(false);
+    loader
+});
+
+pub fn localizer() -> Box<dyn Localizer> {
+    Box::from(DefaultLocalizer::new(&*LANGUAGE_LOADER, &Localizations))
+}
+
+/// Select the language (e.g. "ja", "pseudo"), or the desktop languages for `None`.
+pub fn select_language(lang: Option<&str>) {
+    let pseudo = lang == Some(PSEUDO_LOCALE);
+    let requested_languages: Vec<LanguageIdentifier> = match lang {
+        Some(_) if pseudo => vec![LANGUAGE_LOADER.fallback_language().clone()],
+        Some(lang) => match lang.parse() {
+            Ok(lang) => vec![lang],
+            Err(_) => {
+                eprintln!("invalid language: {lang:?}");
+                DesktopLanguageRequester::requested_languages()
+            },
+        },
+        None => DesktopLanguageRequester::requested_languages(),
+    };
+
+    if let Err(error) = localizer().select(&requested_languages) {
+        eprintln!("Error while loading languages for amdgpu_top_tui {error}");
+    }
+
+    LANGUAGE_LOADER.with_bundles_mut(|bundle| {
+        bundle.set_use_isolating(false);
+        bundle.set_transform(if pseudo { Some(pseudo_localize) } else { None });
+    });
+}
+
+#[macro_export]
+macro_rules! fl {
+    ($message_id:literal) => {{
+        i18n_embed_fl::fl!($crate::LANGUAGE_LOADER, $message_id)
+    }};
+
+    ($message_id:literal, $($args:expr),*) => {{
+        i18n_embed_fl::fl!($crate::LANGUAGE_LOADER, $message_id, $($args), *)
+    }};
+}
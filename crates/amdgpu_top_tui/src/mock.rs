@@ -0,0 +1,264 @@
+// The TUI of the mock devices (`--mock <dir>`, `--demo`), with the layout and the keys of the devices.
+// GRBM is not read from the mock device (0%), and the panels of debugfs, the kernel messages
+// and the PCIe bandwidth are not shown, since they are read from the local system.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use libamdgpu_top::AMDGPU::GPU_INFO;
+use libamdgpu_top::{stat, MockDevice, SAMPLE_BUS, SessionOptions, SessionSummary, TempUnit};
+use stat::PCType;
+
+use crate::{ascii_lang, fit_terminal, fl, new_siv, select_language, ToggleOptions, view::*};
+use crate::app::{info_bar, AppLayout};
+
+struct MockApp {
+    mock: MockDevice,
+    instance: u32,
+    list_name: String,
+    device_info: String,
+    grbm: PerfCounterView,
+    grbm2: PerfCounterView,
+    fdinfo: FdInfoView,
+    gpu_metrics: GpuMetricsView,
+    vram_usage: VramUsageView,
+    sensors: SensorsView,
+    summary: SessionSummary,
+    stats: StatsView,
+}
+
+impl MockApp {
+    /// `instance`: the index of the device, the mock devices do not have a DRM instance
+    fn new(mock: MockDevice, instance: u32) -> Self {
+        let info = mock.app_device_info();
+        let chip_class = mock.ext_info.get_chip_class();
+        let device_info = info_bar(
+            &info.marketing_name,
+            &mock.ext_info,
+            &info.pci_bus,
+            info.memory_info.vram.total_heap_size,
+            (info.min_gpu_clk, info.max_gpu_clk),
+            (info.min_mem_clk, info.max_mem_clk),
+        );
+        let mut gpu_metrics = GpuMetricsView::new_without_sysfs();
+
+        if let Some(metrics) = &mock.metrics {
+            gpu_metrics.set_metrics(metrics);
+        }
+
+        Self {
+            instance,
+            list_name: format!("{} ({})", info.marketing_name, info.pci_bus),
+            device_info,
+            grbm: PerfCounterView::new_with_chip_class(PCType::GRBM, chip_class, instance),
+            grbm2: PerfCounterView::new_with_chip_class(PCType::GRBM2, chip_class, instance),
+            fdinfo: FdInfoView { has_vcn_unified: mock.fdinfo.has_vcn_unified, ..Default::default() },
+            gpu_metrics,
+            vram_usage: VramUsageView::new_with_usage(mock.vram_usage.clone(), instance),
+            sensors: SensorsView::new_with_sensors(mock.sensors.clone()),
+            summary: SessionSummary::new(info.pci_bus),
+            stats: StatsView::default(),
+            mock,
+        }
+    }
+
+    fn app_layout(&self) -> AppLayout {
+        let gpu_metrics_title = match self.gpu_metrics.version() {
+            Some(v) => format!("{} v{}.{}", fl!("gpu_metrics"), v.0, v.1),
+            None => fl!("gpu_metrics"),
+        };
+
+        AppLayout {
+            instance: self.instance,
+            pci: self.mock.device_path.pci,
+            list_name: self.list_name.clone(),
+            nickname: None,
+            device_info: self.device_info.clone(),
+            pmu: None,
+            grbm: self.grbm.clone(),
+            grbm2: self.grbm2.clone(),
+            vram_usage: self.vram_usage.clone(),
+            fdinfo: self.fdinfo.text.clone(),
+            leak: None,
+            sensors: self.sensors.text.clone(),
+            gpu_metrics: (gpu_metrics_title, self.gpu_metrics.text.clone()),
+            pm_info: None,
+            dpm_clock: None,
+            kmsg: None,
+            queue_priority: None,
+            activity_sources: None,
+            sqtt: None,
+            stats: self.stats.text.clone(),
+        }
+    }
+
+    fn update(&mut self, flags: &ToggleOptions, interval: Duration) {
+        self.mock.fdinfo.interval = interval;
+        self.mock.fdinfo.smoothing = flags.smoothing;
+        self.mock.update();
+        self.print(flags, interval);
+    }
+
+    fn print(&mut self, flags: &ToggleOptions, interval: Duration) {
+        if flags.vram {
+            self.vram_usage.set_usage(&self.mock.vram_usage);
+        }
+
+        if flags.sensor {
+            self.sensors.set(&self.mock.sensors);
+            self.sensors.print(flags.temp_unit, interval).unwrap();
+        } else {
+            self.sensors.text.clear();
+        }
+
+        if flags.fdinfo {
+            self.fdinfo.stat = self.mock.fdinfo.clone();
+            self.fdinfo.raw = flags.fdinfo_raw;
+            self.fdinfo.shared = flags.fdinfo_shared;
+            self.fdinfo.group_by = flags.fdinfo_group;
+            self.fdinfo.compact = flags.compact;
+            self.fdinfo.print_stat(&flags.fdinfo_sort, flags.reverse_sort).unwrap();
+        } else {
+            self.fdinfo.text.clear();
+        }
+
+        if flags.gpu_metrics {
+            if let Some(metrics) = &self.mock.metrics {
+                self.gpu_metrics.set_metrics(metrics);
+            }
+
+            self.gpu_metrics.print(interval).unwrap();
+        } else {
+            self.gpu_metrics.text.clear();
+        }
+
+        self.grbm.dump(interval);
+        self.grbm2.dump(interval);
+        self.vram_usage.set_value(flags.number_format);
+        self.fdinfo.text.set();
+        self.sensors.text.set();
+        self.gpu_metrics.text.set();
+
+        self.summary.add(&self.mock.sensors, &self.mock.vram_usage, Some(&self.mock.activity));
+
+        if flags.grbm { self.summary.add_perf_counter(&self.grbm.pc) }
+        if flags.grbm2 { self.summary.add_perf_counter(&self.grbm2.pc) }
+
+        if flags.stats {
+            self.stats.print(
+                &self.summary.stats,
+                &[&self.grbm.pc, &self.grbm2.pc],
+                flags.temp_unit,
+            ).unwrap();
+        } else {
+            self.stats.text.clear();
+        }
+
+        self.stats.text.set();
+    }
+}
+
+/// `--mock <dir>` and `--demo` in TUI mode, the devices are updated every `interval`.
+/// The samples are also published to `SAMPLE_BUS`, like [`run`](crate::run).
+#[allow(clippy::too_many_arguments)]
+pub fn run_mock(
+    title: &str,
+    devices: Vec<MockDevice>,
+    interval: Duration,
+    smoothing: stat::Smoothing,
+    lang: Option<&str>,
+    temp_unit: TempUnit,
+    ascii: bool,
+    session: &SessionOptions,
+) {
+    select_language(ascii_lang(lang, ascii));
+
+    let mut toggle_opt = ToggleOptions { smoothing, temp_unit, ..Default::default() };
+
+    if ascii {
+        toggle_opt.ascii = true;
+        toggle_opt.number_format = toggle_opt.number_format.to_ascii();
+    }
+
+    let mut vec_app: Vec<MockApp> = devices.into_iter().zip(0..).map(|(mock, i)| MockApp::new(mock, i)).collect();
+
+    toggle_opt.gpu_metrics = vec_app.iter().any(|app| app.mock.metrics.is_some());
+
+    // the devices are already updated once by `MockDevice::load` and `MockDevice::demo_all`
+    for app in vec_app.iter_mut() {
+        app.print(&toggle_opt, interval);
+    }
+
+    toggle_opt.instances = vec_app.iter().map(|app| app.instance).collect();
+
+    fit_terminal(&mut toggle_opt);
+
+    let layouts: Vec<AppLayout> = vec_app.iter().map(|app| app.app_layout()).collect();
+    let mut flags = toggle_opt.clone();
+    let toggle_opt = Arc::new(Mutex::new(toggle_opt));
+    let mut siv = new_siv(title, layouts, toggle_opt.clone(), ascii);
+
+    let cb_sink = siv.cb_sink().clone();
+    let summaries: Arc<Mutex<Vec<SessionSummary>>> = Default::default();
+    let thread_summaries = summaries.clone();
+    let thread_session = session.clone();
+    let start = std::time::Instant::now();
+    let mut samples = 0;
+
+    std::thread::spawn(move || loop {
+        {
+            let lock = toggle_opt.try_lock();
+            if let Ok(opt) = lock {
+                flags = opt.clone();
+            }
+        }
+
+        std::thread::sleep(interval);
+
+        // all devices, the metrics of the demo devices follow the number of the updates
+        for app in vec_app.iter_mut() {
+            app.update(&flags, interval);
+        }
+
+        if SAMPLE_BUS.has_subscribers() {
+            SAMPLE_BUS.send(Arc::new(vec_app.iter().map(|app| app.mock.sample()).collect()));
+        }
+
+        samples += 1;
+
+        if let Ok(mut summaries) = thread_summaries.lock() {
+            *summaries = vec_app.iter().map(|app| app.summary.clone()).collect();
+        }
+
+        if thread_session.is_over(start, samples) {
+            cb_sink.send(Box::new(cursive::Cursive::quit)).unwrap();
+            break;
+        }
+
+        if cb_sink.send(Box::new(cursive::Cursive::noop)).is_err() { break }
+    });
+
+    siv.run();
+
+    session.write_summary(&summaries.lock().unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demo_snapshot() {
+        let interval = Duration::from_secs(1);
+        let opt = ToggleOptions::default();
+        let mut app = MockApp::new(MockDevice::demo_all(interval).remove(0), 0);
+
+        app.update(&opt, interval);
+
+        let s = app.app_layout().snapshot("amdgpu_top", &opt);
+
+        assert!(s.contains("AMD Radeon RX 7900 XTX (demo) (0000:03:00.0"), "{s}");
+        assert!(s.contains("demo-game"), "{s}");
+        assert_eq!(app.summary.samples(), 1);
+    }
+}
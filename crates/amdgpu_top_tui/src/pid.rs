@@ -0,0 +1,174 @@
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use cursive::view::Scrollable;
+use cursive::views::{LinearLayout, TextView};
+
+use libamdgpu_top::{DevicePath, Sampling, SessionOptions};
+use libamdgpu_top::stat::{PidDeviceStat, PidMonitor, Smoothing};
+
+use crate::{fl, ascii_lang, ascii_theme, select_language, Text};
+
+const SPARKLINE: &[char] = &[' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const SPARKLINE_ASCII: &[char] = &[' ', '.', ':', '-', '=', '+', '*', '#', '@'];
+
+/// The VRAM usage over time, scaled to the max in the history
+fn sparkline(history: &[u64], ascii: bool) -> String {
+    let chars = if ascii { SPARKLINE_ASCII } else { SPARKLINE };
+    let max = history.iter().copied().max().unwrap_or(0);
+
+    history.iter().map(|v| {
+        if max == 0 { return chars[0] }
+
+        chars[(*v * (chars.len() as u64 - 1)).div_ceil(max) as usize]
+    }).collect()
+}
+
+fn print_device(buf: &mut String, device: &PidDeviceStat, ascii: bool) -> Result<(), std::fmt::Error> {
+    writeln!(buf, " [{}] ({})", device.marketing_name, device.pci_bus)?;
+
+    let Some(pu) = &device.usage else {
+        writeln!(buf, "    {}\n", fl!("pid_not_opened"))?;
+        return Ok(());
+    };
+    let u = &pu.usage;
+    let d = &pu.delta;
+
+    writeln!(
+        buf,
+        "    {:>5} {:>8} {:>5} {:>5} {:>5} {:>6} | {:>9} {:>9} {:>5}",
+        "GFX", "Compute", "DMA", "Dec", "Enc", "Media", "VRAM", "GTT", "CPU",
+    )?;
+    writeln!(
+        buf,
+        "    {:>4}% {:>7}% {:>4}% {:>4}% {:>4}% {:>5}% | {:>5} MiB {:>5} MiB {:>4}%",
+        u.gfx,
+        u.compute,
+        u.dma,
+        u.dec + u.vcn_jpeg,
+        u.enc + u.uvd_enc,
+        u.media,
+        u.vram_usage >> 10,
+        u.gtt_usage >> 10,
+        pu.cpu_usage,
+    )?;
+    // busy time per interval
+    writeln!(
+        buf,
+        "    {:>4}ms {:>6}ms {:>3}ms {:>3}ms {:>3}ms {:>4}ms |",
+        d.gfx / 1_000_000,
+        d.compute / 1_000_000,
+        d.dma / 1_000_000,
+        (d.dec + d.vcn_jpeg) / 1_000_000,
+        (d.enc + d.uvd_enc) / 1_000_000,
+        d.media / 1_000_000,
+    )?;
+
+    let history: Vec<u64> = device.vram_history.iter().copied().collect();
+    let max = history.iter().copied().max().unwrap_or(0);
+
+    writeln!(
+        buf,
+        "    VRAM ({}s, max {max} MiB) [{:<len$}]\n",
+        history.len(),
+        sparkline(&history, ascii),
+        len = PidMonitor::HISTORY_LEN,
+    )?;
+
+    Ok(())
+}
+
+fn print(text: &mut Text, monitor: &PidMonitor, ascii: bool) -> Result<(), std::fmt::Error> {
+    text.clear();
+
+    writeln!(text.buf, " PID {} ({})\n", monitor.pid, monitor.name)?;
+
+    for device in &monitor.devices {
+        print_device(&mut text.buf, device, ascii)?;
+    }
+
+    text.set();
+
+    Ok(())
+}
+
+/// `--pid`, the usage of the process on all devices.
+/// Exits when the process exits.
+pub fn run_pid(
+    title: &str,
+    pid: i32,
+    device_path_list: &[DevicePath],
+    smoothing: Smoothing,
+    lang: Option<&str>,
+    ascii: bool,
+    session: &SessionOptions,
+) {
+    select_language(ascii_lang(lang, ascii));
+
+    let Some(mut monitor) = PidMonitor::new(pid, device_path_list, smoothing) else {
+        eprintln!("{}", fl!("pid_not_found", pid = pid));
+        std::process::exit(1);
+    };
+    let sample = Sampling::low();
+    let mut text = Text::default();
+
+    monitor.update(sample.to_duration());
+    print(&mut text, &monitor, ascii).unwrap();
+
+    let mut siv = cursive::default();
+    {
+        let layout = LinearLayout::vertical()
+            .child(TextView::new(title))
+            .child(text.panel(&fl!("processes")))
+            .child(TextView::new(format!("\n {}", fl!("pid_help"))));
+
+        siv.add_fullscreen_layer(
+            layout
+                .scrollable()
+                .scroll_y(true)
+                .show_scrollbars(!ascii)
+        );
+    }
+
+    siv.add_global_callback('q', cursive::Cursive::quit);
+    siv.set_theme(cursive::theme::Theme::terminal_default());
+
+    if ascii {
+        siv.update_theme(ascii_theme);
+    }
+
+    let cb_sink = siv.cb_sink().clone();
+    let exited: Arc<Mutex<bool>> = Default::default();
+    let thread_exited = exited.clone();
+    let session = session.clone();
+    let start = std::time::Instant::now();
+    let mut samples = 0;
+
+    std::thread::spawn(move ||
+        loop {
+            std::thread::sleep(sample.to_duration()); // 1s
+
+            if !monitor.is_alive() {
+                *thread_exited.lock().unwrap() = true;
+                cb_sink.send(Box::new(cursive::Cursive::quit)).unwrap();
+                break;
+            }
+
+            monitor.update(sample.to_duration());
+            print(&mut text, &monitor, ascii).unwrap();
+            samples += 1;
+
+            if session.is_over(start, samples) {
+                cb_sink.send(Box::new(cursive::Cursive::quit)).unwrap();
+                break;
+            }
+
+            cb_sink.send(Box::new(cursive::Cursive::noop)).unwrap();
+        }
+    );
+
+    siv.run();
+
+    if *exited.lock().unwrap() {
+        eprintln!("{}", fl!("pid_exited", pid = pid));
+    }
+}
@@ -0,0 +1,492 @@
+use std::fmt::Write;
+use std::io::{self, BufRead, BufReader, Read, Write as _};
+use std::net::TcpStream;
+use std::process::{Child, ChildStderr, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use cursive::view::Scrollable;
+use cursive::views::{LinearLayout, TextView};
+use serde_json::Value;
+
+use libamdgpu_top::REMOTE_MAGIC;
+use libamdgpu_top::tls::{self, Stream, TLS_SCHEME};
+
+use crate::{fl, ascii_lang, ascii_theme, select_language, Text};
+
+// `--connect <host:port>`, renders the JSON lines streamed by `amdgpu_top --serve` on the remote host.
+// This is a summary view, not `TuiApp`: the views of TUI and GUI mode are built from a `DeviceHandle`
+// and the perf counters, which the JSON output does not carry.
+// Only the values in the JSON output are shown (no GRBM/GRBM2 per-block usage).
+// `--connect ssh://[user@]host[:port]` runs `amdgpu_top -J` on the remote host over ssh instead,
+// so only the binary is required on the remote host, not `--serve`.
+// `--connect tls://<host:port>` connects to `--serve` with TLS (the "tls" feature),
+// the server certificate is verified with `$AMDGPU_TOP_TLS_CA` or the system CA certificates.
+
+/// `{ "<key>": { "<sub>": { "value": <i64> } } }`
+fn value(v: &Value, keys: &[&str]) -> Option<i64> {
+    keys.iter().try_fold(v, |v, key| v.get(key))?.get("value")?.as_i64()
+}
+
+fn fmt_value(v: Option<i64>) -> String {
+    v.map_or("_".to_string(), |v| v.to_string())
+}
+
+fn print_device(buf: &mut String, device: &Value) -> Result<(), std::fmt::Error> {
+    let info = &device["Info"];
+    let name = info["DeviceName"].as_str().unwrap_or_default();
+    let pci = info["PCI"].as_str().unwrap_or_default();
+    let [vram_total, gtt_total] = ["VRAM Size", "GTT Size"].map(|k| info[k].as_u64().unwrap_or(0) >> 20);
+
+    match info["Nickname"].as_str() {
+        Some(nickname) => writeln!(buf, " [{nickname}: {name}] ({pci})")?,
+        None => writeln!(buf, " [{name}] ({pci})")?,
+    }
+
+    writeln!(
+        buf,
+        "    GFX {:>3}%, Memory {:>3}%, Media {:>3}% | VRAM {:>6}/{:>6} MiB, GTT {:>6}/{:>6} MiB",
+        fmt_value(value(device, &["gpu_activity", "GFX"])),
+        fmt_value(value(device, &["gpu_activity", "Memory"])),
+        fmt_value(value(device, &["gpu_activity", "MediaEngine"])),
+        fmt_value(value(device, &["VRAM", "Total VRAM Usage"])),
+        vram_total,
+        fmt_value(value(device, &["VRAM", "Total GTT Usage"])),
+        gtt_total,
+    )?;
+
+    write!(buf, "   ")?;
+
+    for (label, key, unit) in [
+        ("SCLK", "GFX_SCLK", "MHz"),
+        ("MCLK", "GFX_MCLK", "MHz"),
+        ("Edge", "Edge Temperature", "C"),
+        ("Junction", "Junction Temperature", "C"),
+        ("Memory", "Memory Temperature", "C"),
+        ("Power", "GFX Power", "W"),
+        ("Fan", "Fan", "RPM"),
+    ] {
+        if let Some(v) = value(device, &["Sensors", key]) {
+            write!(buf, " {label} {v} {unit},")?;
+        }
+    }
+
+    writeln!(buf)?;
+
+    let Some(procs) = device["fdinfo"].as_object().filter(|procs| !procs.is_empty()) else {
+        return writeln!(buf);
+    };
+
+    writeln!(
+        buf,
+        "    {:>8} {:<16} {:>9} {:>9} {:>4} {:>7} {:>4} {:>4} {:>4} {:>4}",
+        "PID", "Name", "VRAM", "GTT", "GFX", "Compute", "DMA", "Dec", "Enc", "CPU",
+    )?;
+
+    let mut procs: Vec<(&String, &Value)> = procs.iter().collect();
+    procs.sort_by_key(|(_, p)| std::cmp::Reverse(value(p, &["usage", "VRAM"])));
+
+    for (pid, p) in procs {
+        let name: String = p["name"].as_str().unwrap_or_default().chars().take(16).collect();
+
+        writeln!(
+            buf,
+            "    {pid:>8} {name:<16} {:>5} MiB {:>5} MiB {:>3}% {:>6}% {:>3}% {:>3}% {:>3}% {:>3}%",
+            fmt_value(value(p, &["usage", "VRAM"])),
+            fmt_value(value(p, &["usage", "GTT"])),
+            fmt_value(value(p, &["usage", "GFX"])),
+            fmt_value(value(p, &["usage", "Compute"])),
+            fmt_value(value(p, &["usage", "DMA"])),
+            fmt_value(value(p, &["usage", "Decode"])),
+            fmt_value(value(p, &["usage", "Encode"])),
+            fmt_value(value(p, &["usage", "CPU"])),
+        )?;
+    }
+
+    writeln!(buf)
+}
+
+fn print(text: &mut Text, line: &Value) -> Result<(), std::fmt::Error> {
+    text.clear();
+
+    for device in line["devices"].as_array().map(|v| v.as_slice()).unwrap_or_default() {
+        print_device(&mut text.buf, device)?;
+    }
+
+    text.set();
+
+    Ok(())
+}
+
+/// `ssh://[user@]host[:port]` -> (`[user@]host`, port),
+/// the brackets of an IPv6 address are removed for ssh (`ssh://user@[::1]:22` -> (`user@::1`, `22`))
+fn parse_ssh_url(addr: &str) -> Option<(String, Option<&str>)> {
+    let dest = addr.strip_prefix("ssh://")?.trim_end_matches('/');
+
+    // "[::1]:22" or "host:22"
+    let (dest, port) = match dest.rsplit_once(':') {
+        Some((host, port)) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) =>
+            (host, Some(port)),
+        _ => (dest, None),
+    };
+    let (user, host) = match dest.rsplit_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, dest),
+    };
+    let host = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')).unwrap_or(host);
+    let dest = match user {
+        Some(user) => format!("{user}@{host}"),
+        None => host.to_string(),
+    };
+
+    Some((dest, port))
+}
+
+/// The arguments of ssh to run "amdgpu_top -J" on `dest`.
+/// "--" ends the options of ssh, so `dest` is not taken as an option (e.g. "-oProxyCommand=..."),
+/// and the rest is the remote command.
+fn ssh_args<'a>(dest: &'a str, port: Option<&'a str>) -> Vec<&'a str> {
+    // the terminal is used by the TUI, a password prompt is not possible
+    let mut args = vec!["-T", "-o", "BatchMode=yes"];
+
+    if let Some(port) = port {
+        args.extend(["-p", port]);
+    }
+
+    args.extend(["--", dest, "amdgpu_top", "-J"]);
+
+    args
+}
+
+/// The stream of the JSON lines and the ssh process
+fn open(addr: &str, token: Option<&str>) -> io::Result<(Box<dyn Read + Send>, Option<Child>)> {
+    let Some((dest, port)) = parse_ssh_url(addr) else {
+        let mut stream = match addr.strip_prefix(TLS_SCHEME) {
+            Some(addr) => tls::connect(addr)?,
+            None => Stream::Plain(TcpStream::connect(addr)?),
+        };
+        writeln!(stream, "{REMOTE_MAGIC} {}", token.unwrap_or_default())?;
+        stream.flush()?;
+
+        return Ok((Box::new(stream), None));
+    };
+
+    if dest.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid ssh destination: {dest:?}")));
+    }
+
+    let mut child = Command::new("ssh")
+        .args(ssh_args(&dest, port))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().ok_or(io::ErrorKind::BrokenPipe)?;
+
+    Ok((Box::new(stdout), Some(child)))
+}
+
+/// The last line of stderr of ssh or the remote amdgpu_top, for the error message
+fn last_line(stderr: Option<ChildStderr>) -> Option<String> {
+    let mut s = String::new();
+    stderr?.read_to_string(&mut s).ok()?;
+
+    s.lines().rfind(|l| !l.trim().is_empty()).map(|l| l.trim().to_string())
+}
+
+/// Reads the JSON lines until `f` returns `false` or EOF, and returns the reason of the disconnection.
+fn read_lines<F: FnMut(Value) -> bool>(
+    stream: Box<dyn Read + Send>,
+    stderr: Option<ChildStderr>,
+    addr: &str,
+    mut f: F,
+) -> Option<String> {
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break };
+
+        if let Some(err) = line.strip_prefix("error: ") {
+            return Some(err.to_string());
+        }
+
+        let Ok(v) = serde_json::from_str::<Value>(&line) else { continue };
+
+        if !f(v) { return None }
+    }
+
+    let disconnected = fl!("remote_disconnected", addr = addr);
+
+    Some(match last_line(stderr) {
+        Some(stderr) => format!("{disconnected}\n{stderr}"),
+        None => disconnected,
+    })
+}
+
+/// The latest sample of the host in the fleet table
+struct Host {
+    addr: String,
+    line: Option<Value>,
+    /// The connection error or the reason of the disconnection
+    status: Option<String>,
+}
+
+fn print_fleet(buf: &mut String, hosts: &[Host]) -> Result<(), std::fmt::Error> {
+    let mut sum_gpus = 0;
+    let mut sum_gfx = 0;
+    let mut sum_power = 0;
+    let [mut sum_vram, mut sum_vram_total] = [0u64; 2];
+
+    writeln!(
+        buf,
+        " {:<24} {:<32} {:>4} {:>15} {:>6} {:>6}",
+        "Host", "GPU", "GFX", "VRAM", "Power", "Temp",
+    )?;
+
+    for host in hosts {
+        let addr: String = host.addr.chars().take(24).collect();
+        let devices = host.line.as_ref()
+            .and_then(|line| line["devices"].as_array())
+            .map(|v| v.as_slice())
+            .unwrap_or_default();
+
+        if let Some(status) = &host.status {
+            writeln!(buf, " {addr:<24} {}", status.lines().next().unwrap_or_default())?;
+            continue;
+        }
+
+        if host.line.is_none() {
+            writeln!(buf, " {addr:<24} {}", fl!("remote_connecting", addr = host.addr.as_str()))?;
+            continue;
+        }
+
+        for device in devices {
+            let info = &device["Info"];
+            let name = info["Nickname"].as_str()
+                .or(info["DeviceName"].as_str())
+                .unwrap_or_default();
+            let name: String = format!("{} {name}", info["PCI"].as_str().unwrap_or_default())
+                .chars()
+                .take(32)
+                .collect();
+            let gfx = value(device, &["gpu_activity", "GFX"]);
+            let vram = value(device, &["VRAM", "Total VRAM Usage"]).unwrap_or(0) as u64;
+            let vram_total = info["VRAM Size"].as_u64().unwrap_or(0) >> 20;
+            let power = value(device, &["Sensors", "GFX Power"]);
+            let temp = value(device, &["Sensors", "Junction Temperature"])
+                .or(value(device, &["Sensors", "Edge Temperature"]));
+
+            writeln!(
+                buf,
+                " {addr:<24} {name:<32} {:>3}% {:>6}/{:>6}MiB {:>5}W {:>5}C",
+                fmt_value(gfx),
+                vram,
+                vram_total,
+                fmt_value(power),
+                fmt_value(temp),
+            )?;
+
+            sum_gpus += 1;
+            sum_gfx += gfx.unwrap_or(0);
+            sum_power += power.unwrap_or(0);
+            sum_vram += vram;
+            sum_vram_total += vram_total;
+        }
+    }
+
+    writeln!(
+        buf,
+        "\n {:<24} {:<32} {:>3}% {:>6}/{:>6}MiB {:>5}W",
+        fl!("remote_fleet_total", hosts = hosts.len()),
+        fl!("remote_fleet_gpus", gpus = sum_gpus),
+        if sum_gpus == 0 { 0 } else { sum_gfx / sum_gpus },
+        sum_vram,
+        sum_vram_total,
+        sum_power,
+    )
+}
+
+/// `--connect`, the token is sent if the server requires it (`server.conf` on the server).
+/// For `ssh://`, the token is not used and the authentication is done by ssh.
+/// With multiple hosts, the devices of all the hosts are shown in a table.
+pub fn run_remote(
+    title: &str,
+    addrs: &[String],
+    token: Option<&str>,
+    lang: Option<&str>,
+    ascii: bool,
+) {
+    select_language(ascii_lang(lang, ascii));
+
+    let mut siv = cursive::default();
+
+    siv.add_global_callback('q', cursive::Cursive::quit);
+    siv.set_theme(cursive::theme::Theme::terminal_default());
+
+    if ascii {
+        siv.update_theme(ascii_theme);
+    }
+
+    let (children, error) = match addrs {
+        [addr] => run_single(&mut siv, title, addr, token, ascii),
+        _ => (run_fleet(&mut siv, title, addrs, token, ascii), Default::default()),
+    };
+
+    siv.run();
+
+    for mut child in children {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    let err = error.lock().unwrap().take();
+
+    if let Some(err) = err {
+        eprintln!("{err}");
+    }
+}
+
+fn add_layer(siv: &mut cursive::CursiveRunnable, header: String, text: &Text, title: &str, ascii: bool) {
+    let layout = LinearLayout::vertical()
+        .child(TextView::new(header))
+        .child(text.panel(title))
+        .child(TextView::new(format!("\n {}", fl!("remote_help"))));
+
+    siv.add_fullscreen_layer(
+        layout
+            .scrollable()
+            .scroll_y(true)
+            .show_scrollbars(!ascii)
+    );
+}
+
+/// Exits when the host disconnects.
+fn run_single(
+    siv: &mut cursive::CursiveRunnable,
+    title: &str,
+    addr: &str,
+    token: Option<&str>,
+    ascii: bool,
+) -> (Vec<Child>, Arc<Mutex<Option<String>>>) {
+    let (stream, mut child) = open(addr, token).unwrap_or_else(|err| {
+        eprintln!("{}", fl!("remote_connect_failed", addr = addr, err = err.to_string()));
+        std::process::exit(1);
+    });
+    let stderr = child.as_mut().and_then(|child| child.stderr.take());
+    let mut text = Text { buf: fl!("remote_connecting", addr = addr), ..Default::default() };
+
+    text.set();
+    add_layer(siv, format!("{title} - {addr}"), &text, &fl!("remote_devices"), ascii);
+
+    let cb_sink = siv.cb_sink().clone();
+    let error: Arc<Mutex<Option<String>>> = Default::default();
+    let thread_error = error.clone();
+    let addr = addr.to_string();
+
+    std::thread::spawn(move || {
+        let err = read_lines(stream, stderr, &addr, |v| {
+            print(&mut text, &v).unwrap();
+
+            cb_sink.send(Box::new(cursive::Cursive::noop)).is_ok()
+        });
+
+        *thread_error.lock().unwrap() = err;
+
+        let _ = cb_sink.send(Box::new(cursive::Cursive::quit));
+    });
+
+    (child.into_iter().collect(), error)
+}
+
+/// The disconnected hosts are kept in the table.
+fn run_fleet(
+    siv: &mut cursive::CursiveRunnable,
+    title: &str,
+    addrs: &[String],
+    token: Option<&str>,
+    ascii: bool,
+) -> Vec<Child> {
+    let hosts: Arc<Mutex<Vec<Host>>> = Arc::new(Mutex::new(
+        addrs.iter().map(|addr| Host { addr: addr.clone(), line: None, status: None }).collect()
+    ));
+    let mut text = Text::default();
+    let mut children = Vec::new();
+
+    print_fleet(&mut text.buf, &hosts.lock().unwrap()).unwrap();
+    text.set();
+    add_layer(siv, format!("{title} - {}", fl!("remote_fleet")), &text, &fl!("remote_devices"), ascii);
+
+    for (i, addr) in addrs.iter().enumerate() {
+        let cb_sink = siv.cb_sink().clone();
+        let hosts = hosts.clone();
+        let mut text = text.clone();
+        let update = move |text: &mut Text, f: &dyn Fn(&mut Host)| -> bool {
+            let mut hosts = hosts.lock().unwrap();
+
+            f(&mut hosts[i]);
+            text.clear();
+            print_fleet(&mut text.buf, &hosts).unwrap();
+            text.set();
+
+            cb_sink.send(Box::new(cursive::Cursive::noop)).is_ok()
+        };
+
+        let (stream, stderr) = match open(addr, token) {
+            Ok((stream, mut child)) => {
+                let stderr = child.as_mut().and_then(|child| child.stderr.take());
+                children.extend(child);
+                (stream, stderr)
+            },
+            Err(err) => {
+                let err = fl!("remote_connect_failed", addr = addr.as_str(), err = err.to_string());
+                update(&mut text, &|host| host.status = Some(err.clone()));
+                continue;
+            },
+        };
+        let addr = addr.clone();
+
+        std::thread::spawn(move || {
+            let err = read_lines(stream, stderr, &addr, |v| {
+                update(&mut text, &|host| host.line = Some(v.clone()))
+            });
+
+            update(&mut text, &|host| host.status = err.clone());
+        });
+    }
+
+    children
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ssh_url() {
+        assert_eq!(parse_ssh_url("gpu-host:8081"), None);
+        assert_eq!(parse_ssh_url("ssh://gpu-host"), Some(("gpu-host".to_string(), None)));
+        assert_eq!(parse_ssh_url("ssh://user@gpu-host:2222/"), Some(("user@gpu-host".to_string(), Some("2222"))));
+        assert_eq!(parse_ssh_url("ssh://[::1]"), Some(("::1".to_string(), None)));
+        assert_eq!(parse_ssh_url("ssh://user@[::1]:22"), Some(("user@::1".to_string(), Some("22"))));
+        assert_eq!(parse_ssh_url("ssh://gpu-host:"), Some(("gpu-host:".to_string(), None)));
+    }
+
+    #[test]
+    fn ssh_argv() {
+        assert_eq!(
+            ssh_args("user@gpu-host", Some("2222")),
+            ["-T", "-o", "BatchMode=yes", "-p", "2222", "--", "user@gpu-host", "amdgpu_top", "-J"],
+        );
+        assert_eq!(
+            ssh_args("::1", None),
+            ["-T", "-o", "BatchMode=yes", "--", "::1", "amdgpu_top", "-J"],
+        );
+
+        // after "--", not an option of ssh
+        let (dest, port) = parse_ssh_url("ssh://-oProxyCommand=x").unwrap();
+        assert_eq!(ssh_args(&dest, port), ["-T", "-o", "BatchMode=yes", "--", "-oProxyCommand=x", "amdgpu_top", "-J"]);
+    }
+
+    #[test]
+    fn ssh_empty_destination() {
+        let err = open("ssh://", None).err().unwrap();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
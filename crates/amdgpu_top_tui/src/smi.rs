@@ -6,36 +6,41 @@ use cursive::view::{Nameable, Scrollable};
 use cursive::views::{HideableView, LinearLayout, TextContent, TextView, Panel};
 
 use libamdgpu_top::AMDGPU::{ASIC_NAME, DeviceHandle, GPU_INFO, MetricsInfo};
-use libamdgpu_top::{stat, DevicePath, PCI, Sampling, VramUsage};
-use stat::{GfxoffStatus, GpuActivity, Sensors, ProcInfo};
+use libamdgpu_top::{stat, AlertCondition, DevicePath, DeviceSample, exit_code, exit_code::ExitReason, is_gpu_active, IdleDetector, PCI, SAMPLE_BUS, Sampling, SessionOptions, SessionSummary, TempUnit, VramUsage};
+use stat::{GfxoffResidency, GfxoffStatus, GpuActivity, Sensors, ProcInfo, Smoothing};
 
-use crate::{FdInfoView, Text, ToggleOptions, stat::FdInfoSortType};
+use crate::{fl, ascii_lang, ascii_theme, select_language, FdInfoView, Text, ToggleOptions, stat::FdInfoSortType};
 
 const GPU_NAME_LEN: usize = 25;
 const LINE_LEN: usize = 150;
-const THR_LEN: usize = 60;
-const PROC_TITLE: &str = "Processes";
+const THR_LEN: usize = 52;
+const PROC_VIEW_NAME: &str = "Processes";
 
 pub(crate) struct SmiDeviceInfo {
     pub amdgpu_dev: DeviceHandle,
     pub device_path: DevicePath,
     pub instance: u32,
     pub marketing_name: String,
+    pub nickname: Option<String>,
     pub pci_bus: PCI::BUS_INFO,
     pub sysfs_path: PathBuf,
     pub cu_number: u32,
     pub vram_usage: VramUsage,
     pub sensors: Sensors,
+    pub activity: GpuActivity,
     pub check_gfxoff: bool,
+    pub gfxoff_residency: GfxoffResidency,
     pub asic_name: ASIC_NAME,
     pub fdinfo: FdInfoView,
     pub arc_proc_index: Arc<Mutex<Vec<ProcInfo>>>,
     pub info_text: Text,
+    pub summary: SessionSummary,
 }
 
 impl SmiDeviceInfo {
     pub fn new(amdgpu_dev: DeviceHandle, device_path: &DevicePath, instance: u32) -> Self {
         let marketing_name = amdgpu_dev.get_marketing_name_or_default();
+        let nickname = device_path.nickname();
         let pci_bus = match device_path.pci {
             Some(pci_bus) => pci_bus,
             None => amdgpu_dev.get_pci_bus_info().unwrap(),
@@ -70,16 +75,20 @@ impl SmiDeviceInfo {
             device_path: device_path.clone(),
             instance,
             marketing_name,
+            nickname,
             pci_bus,
             sysfs_path,
             cu_number,
             vram_usage,
             sensors,
+            activity: GpuActivity { gfx: None, umc: None, media: None },
             check_gfxoff,
+            gfxoff_residency: GfxoffResidency::default(),
             asic_name,
             fdinfo,
             arc_proc_index,
             info_text: Default::default(),
+            summary: SessionSummary::new(pci_bus),
         }
     }
 
@@ -87,7 +96,7 @@ impl SmiDeviceInfo {
         let text = format!(concat!(
             "GPU  {name:<name_len$} {pad:9}|{pci:<16}|{vram:^18}|\n",
             "SCLK    MCLK    VDDGFX  Power           | GFX% UMC%Media%|{gtt:^18}|\n",
-            "Temp    {fan:<7} {thr:<THR_LEN$}|"
+            "Temp    {fan:<7} GFXOFF% {thr:<THR_LEN$}|"
             ),
             name = "Name",
             name_len = GPU_NAME_LEN,
@@ -107,7 +116,7 @@ impl SmiDeviceInfo {
         TextView::new_with_content(self.info_text.content.clone()).no_wrap()
     }
 
-    fn update_info_text(&mut self) -> Result<(), std::fmt::Error> {
+    fn update_info_text(&mut self, temp_unit: TempUnit) -> Result<(), std::fmt::Error> {
         self.info_text.clear();
         self.vram_usage.update_usage(&self.amdgpu_dev);
 
@@ -155,7 +164,13 @@ impl SmiDeviceInfo {
         }
 
         if self.check_gfxoff {
-            match GfxoffStatus::get(self.instance) {
+            let status = GfxoffStatus::get(self.instance);
+
+            if let Ok(status) = &status {
+                self.gfxoff_residency.add(status);
+            }
+
+            match status {
                 Ok(GfxoffStatus::InGFXOFF) =>
                     write!(self.info_text.buf, "GFXOFF |")?,
                 /* for debug */
@@ -167,7 +182,7 @@ impl SmiDeviceInfo {
             write!(self.info_text.buf, "       |")?;
         }
 
-        let metrics = self.amdgpu_dev.get_gpu_metrics_from_sysfs_path(&self.sysfs_path).ok();
+        let metrics = stat::gpu_metrics_util::get_gpu_metrics(&self.sysfs_path).ok();
         let mut activity = if let Some(metrics) = &metrics {
             GpuActivity::from_gpu_metrics(metrics)
         } else {
@@ -185,6 +200,8 @@ impl SmiDeviceInfo {
             activity.media = self.fdinfo.stat.fold_fdinfo_usage().media.try_into().ok();
         }
 
+        self.activity = activity.clone();
+
         for usage in [activity.gfx, activity.umc, activity.media] {
             if let Some(usage) = usage {
                 write!(self.info_text.buf, " {usage:>3}%")?;
@@ -201,9 +218,9 @@ impl SmiDeviceInfo {
         )?;
 
         if let Some(temp) = &self.sensors.edge_temp {
-            write!(self.info_text.buf, " {:>3}C ", temp.current)?;
+            write!(self.info_text.buf, " {:>3}{temp_unit} ", temp_unit.convert_int(temp.current))?;
         } else {
-            write!(self.info_text.buf, " ___C ")?;
+            write!(self.info_text.buf, " ___{temp_unit} ")?;
         }
 
         if let Some(fan_rpm) = &self.sensors.fan_rpm {
@@ -212,6 +229,12 @@ impl SmiDeviceInfo {
             write!(self.info_text.buf, "  ____RPM ")?;
         }
 
+        if let Some(pct) = self.gfxoff_residency.percent() {
+            write!(self.info_text.buf, "{pct:>6.1}% ")?;
+        } else {
+            write!(self.info_text.buf, "{:>7} ", "___%")?;
+        }
+
         if let Some(thr) = metrics.and_then(|m| m.get_throttle_status_info()) {
             let thr = format!("{:?}", thr.get_all_throttler());
             write!(
@@ -235,12 +258,16 @@ impl SmiDeviceInfo {
     fn fdinfo_panel(&self) -> Panel<TextView> {
         let text = TextView::new_with_content(self.fdinfo.text.content.clone());
         Panel::new(text)
-            .title(format!("#{:<2} {}", self.instance, self.marketing_name))
+            .title(match &self.nickname {
+                Some(nickname) => format!("#{:<2} {nickname} ({})", self.instance, self.marketing_name),
+                None => format!("#{:<2} {}", self.instance, self.marketing_name),
+            })
             .title_position(HAlign::Left)
     }
 
-    fn update(&mut self, sample: &Sampling, _opt: &ToggleOptions) {
+    fn update(&mut self, sample: &Sampling, opt: &ToggleOptions) {
         self.sensors.update(&self.amdgpu_dev);
+        self.fdinfo.stat.smoothing = opt.smoothing;
 
         {
             let lock = self.arc_proc_index.try_lock();
@@ -252,14 +279,53 @@ impl SmiDeviceInfo {
             }
         }
 
-        self.update_info_text().unwrap();
+        self.update_info_text(opt.temp_unit).unwrap();
         self.fdinfo.text.set();
+        self.summary.add(&self.sensors, &self.vram_usage, Some(&self.activity));
+    }
+
+    /// For `SAMPLE_BUS`
+    fn sample(&self) -> DeviceSample {
+        DeviceSample::new(
+            &self.device_path,
+            &self.sensors,
+            &self.vram_usage,
+            &self.activity,
+            None,
+            &self.fdinfo.stat,
+        )
+    }
+
+    fn alerts(&self, fail_on: &[AlertCondition]) -> Vec<String> {
+        AlertCondition::check_all(fail_on, &self.sensors, &self.vram_usage, &self.activity)
+            .into_iter()
+            .map(|msg| format!("{}: {msg}", self.pci_bus))
+            .collect()
     }
 }
 
-pub fn run_smi(title: &str, device_path_list: &[DevicePath], interval: u64) {
+#[allow(clippy::too_many_arguments)]
+pub fn run_smi(
+    title: &str,
+    device_path_list: &[DevicePath],
+    interval: u64,
+    smoothing: Smoothing,
+    lang: Option<&str>,
+    temp_unit: TempUnit,
+    ascii: bool,
+    fail_on: &[AlertCondition],
+    session: &SessionOptions,
+    idle_timeout: Option<std::time::Duration>,
+) -> ExitReason {
+    select_language(ascii_lang(lang, ascii));
+
     let sample = Sampling::low();
-    let mut opt = ToggleOptions::default();
+    let mut idle = IdleDetector::new(idle_timeout);
+    let mut opt = ToggleOptions { smoothing, temp_unit, ascii, ..Default::default() };
+
+    if ascii {
+        opt.number_format = opt.number_format.to_ascii();
+    }
     let mut vec_app: Vec<SmiDeviceInfo> = device_path_list.iter().filter_map(|device_path| {
         let amdgpu_dev = device_path.init().ok()?;
         let instance = device_path.get_instance_number()?;
@@ -290,15 +356,16 @@ pub fn run_smi(title: &str, device_path_list: &[DevicePath], interval: u64) {
             for app in &vec_app {
                 proc.add_child(app.fdinfo_panel());
             }
-            let h = HideableView::new(proc).with_name(PROC_TITLE);
-            layout.add_child(Panel::new(h).title(PROC_TITLE).title_position(HAlign::Left));
+            let h = HideableView::new(proc).with_name(PROC_VIEW_NAME);
+            layout.add_child(Panel::new(h).title(fl!("processes")).title_position(HAlign::Left));
         }
-        layout.add_child(TextView::new("\n(p)rocesses (q)uit"));
+        layout.add_child(TextView::new(format!("\n{}", fl!("smi_help"))));
 
         siv.add_fullscreen_layer(
             layout
                 .scrollable()
                 .scroll_y(true)
+                .show_scrollbars(!ascii)
         );
     }
 
@@ -307,39 +374,120 @@ pub fn run_smi(title: &str, device_path_list: &[DevicePath], interval: u64) {
             .iter()
             .map(|app| (app.device_path.clone(), app.arc_proc_index.clone()))
             .collect();
-        stat::spawn_update_index_thread(t_index, interval);
+        stat::spawn_update_index_thread(t_index, interval, idle.paused_flag());
     }
 
     siv.add_global_callback('q', cursive::Cursive::quit);
     siv.add_global_callback('p', |s| {
-        s.call_on_name(PROC_TITLE, |view: &mut HideableView<LinearLayout>| {
+        s.call_on_name(PROC_VIEW_NAME, |view: &mut HideableView<LinearLayout>| {
             view.set_visible(!view.is_visible());
         });
     });
     siv.set_theme(cursive::theme::Theme::terminal_default());
 
+    if ascii {
+        siv.update_theme(ascii_theme);
+    }
+
     let toggle_opt = Arc::new(Mutex::new(opt.clone()));
     siv.set_user_data(toggle_opt.clone());
 
     let cb_sink = siv.cb_sink().clone();
+    let fail_on = fail_on.to_vec();
+    let alerts: Arc<Mutex<Vec<String>>> = Default::default();
+    let thread_alerts = alerts.clone();
+    let lost: Arc<Mutex<Option<DevicePath>>> = Default::default();
+    let thread_lost = lost.clone();
+    let summaries: Arc<Mutex<Vec<SessionSummary>>> = Default::default();
+    let thread_summaries = summaries.clone();
+    let thread_session = session.clone();
+    let start = std::time::Instant::now();
+    let mut samples = 0;
+
+    // the ticks since the last sample
+    let mut ticks: u32 = 0;
+
+    // on the scheduler thread with the other periodic tasks,
+    // every tick (1s), or every `IdleDetector::STRETCH` ticks while idle
+    libamdgpu_top::scheduler::spawn_periodic(sample.to_duration(), move || {
+        ticks += 1;
+
+        if idle.is_paused() && ticks < IdleDetector::STRETCH {
+            return true;
+        }
 
-    std::thread::spawn(move ||
-        loop {
-            std::thread::sleep(sample.to_duration()); // 1s
+        let sample = Sampling { delay: sample.delay * ticks, ..sample };
+        ticks = 0;
 
-            {
-                if let Ok(toggle_opt) = toggle_opt.try_lock() {
-                    opt = toggle_opt.clone();
-                }
+        if let Some(device_path) = exit_code::find_lost_device(vec_app.iter().map(|app| &app.device_path)) {
+            *thread_lost.lock().unwrap() = Some(device_path.clone());
+            // restore the terminal before exiting
+            let _ = cb_sink.send(Box::new(cursive::Cursive::quit));
+            return false;
+        }
+
+        {
+            if let Ok(toggle_opt) = toggle_opt.try_lock() {
+                opt = toggle_opt.clone();
             }
+        }
 
-            for app in vec_app.iter_mut() {
-                app.update(&sample, &opt);
+        for app in vec_app.iter_mut() {
+            app.update(&sample, &opt);
+        }
+
+        if SAMPLE_BUS.has_subscribers() {
+            SAMPLE_BUS.send(Arc::new(vec_app.iter().map(|app| app.sample()).collect()));
+        }
+
+        let active = vec_app
+            .iter()
+            .any(|app| is_gpu_active(&app.activity, &app.fdinfo.stat.fold_fdinfo_usage()));
+
+        if idle.update(active) {
+            // the process index is not updated while paused
+            for app in &vec_app {
+                if let Ok(mut proc_index) = app.arc_proc_index.lock() {
+                    stat::update_index(&mut proc_index, &app.device_path);
+                }
             }
+        }
 
-            cb_sink.send(Box::new(cursive::Cursive::noop)).unwrap();
+        samples += 1;
+
+        if let Ok(mut summaries) = thread_summaries.lock() {
+            *summaries = vec_app.iter().map(|app| app.summary.clone()).collect();
         }
-    );
+
+        let breached: Vec<String> = vec_app.iter().flat_map(|app| app.alerts(&fail_on)).collect();
+
+        if !breached.is_empty() {
+            *thread_alerts.lock().unwrap() = breached;
+            // restore the terminal before exiting
+            let _ = cb_sink.send(Box::new(cursive::Cursive::quit));
+            return false;
+        }
+
+        if thread_session.is_over(start, samples) {
+            let _ = cb_sink.send(Box::new(cursive::Cursive::quit));
+            return false;
+        }
+
+        // removed after the TUI is closed
+        cb_sink.send(Box::new(cursive::Cursive::noop)).is_ok()
+    });
 
     siv.run();
+
+    session.write_summary(&summaries.lock().unwrap());
+
+    let lost = lost.lock().unwrap().clone();
+
+    if let Some(device_path) = lost {
+        return ExitReason::DeviceLost(device_path);
+    }
+
+    let alerts = std::mem::take(&mut *alerts.lock().unwrap());
+
+    if alerts.is_empty() { ExitReason::Done } else { ExitReason::Alert(alerts) }
 }
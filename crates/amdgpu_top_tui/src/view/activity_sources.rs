@@ -0,0 +1,32 @@
+use std::fmt::{self, Write};
+use std::path::PathBuf;
+use super::Text;
+use libamdgpu_top::AMDGPU::GpuMetrics;
+use libamdgpu_top::stat::{ActivitySources, PerfCounter};
+
+/// `--sysfs-busy`, the GFX/memory activity of GRBM, gpu_metrics and sysfs side by side
+pub struct ActivitySourcesView {
+    sysfs_path: PathBuf,
+    pub text: Text,
+}
+
+impl ActivitySourcesView {
+    pub fn new(sysfs_path: PathBuf) -> Self {
+        Self { sysfs_path, text: Text::default() }
+    }
+
+    pub fn print(&mut self, grbm: Option<&PerfCounter>, metrics: Option<&GpuMetrics>) -> Result<(), fmt::Error> {
+        let sources = ActivitySources::get(&self.sysfs_path, grbm, metrics);
+        self.text.clear();
+
+        writeln!(self.text.buf, " {:<12} {:>4} {:>7}", "", "GFX", "Memory")?;
+
+        for (source, gfx, umc) in sources.rows() {
+            let [gfx, umc] = [gfx, umc].map(|v| v.map_or("___".to_string(), |v| v.to_string()));
+
+            writeln!(self.text.buf, " {source:<12} {gfx:>3}% {umc:>6}%")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt::{self, Write};
+use std::path::Path;
+use super::Text;
+use crate::Opt;
+use libamdgpu_top::stat::DpmClockTable;
+
+#[derive(Clone)]
+pub struct DpmClockView {
+    tables: Vec<DpmClockTable>,
+    pub text: Text,
+}
+
+impl DpmClockView {
+    pub fn new<P: AsRef<Path>>(sysfs_path: P) -> Option<Self> {
+        let tables = DpmClockTable::get_all_from_sysfs_path(sysfs_path);
+
+        (!tables.is_empty()).then(|| Self {
+            tables,
+            text: Text::default(),
+        })
+    }
+
+    pub fn update(&mut self) {
+        for table in self.tables.iter_mut() {
+            let _ = table.update();
+        }
+    }
+
+    /// The current level is enclosed in brackets.
+    pub fn print(&mut self) -> Result<(), fmt::Error> {
+        const NAME_LEN: usize = 7;
+        self.text.clear();
+
+        for table in &self.tables {
+            write!(self.text.buf, " {:<NAME_LEN$}:", table.name)?;
+
+            for (i, level) in table.levels.iter().enumerate() {
+                if Some(i) == table.current {
+                    write!(self.text.buf, " [{}]", level.clock)?;
+                } else {
+                    write!(self.text.buf, " {}", level.clock)?;
+                }
+            }
+
+            writeln!(self.text.buf, " MHz")?;
+        }
+
+        Ok(())
+    }
+
+    pub fn cb(siv: &mut cursive::Cursive) {
+        {
+            let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+            opt.dpm_clock ^= true;
+        }
+    }
+}
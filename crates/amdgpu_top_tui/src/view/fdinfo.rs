@@ -3,7 +3,16 @@ use super::Text;
 use crate::Opt;
 use std::time::Duration;
 
-use libamdgpu_top::stat::{sort_proc_usage, ProcInfo, FdInfoStat, FdInfoSortType};
+use libamdgpu_top::stat::{
+    group_proc_usage,
+    sort_group_usage,
+    sort_proc_usage,
+    ProcInfo,
+    FdInfoGroupBy,
+    FdInfoStat,
+    FdInfoSortType,
+    FdInfoUsage,
+};
 
 /// ref: drivers/gpu/drm/amd/amdgpu/amdgpu_fdinfo.c
 
@@ -18,6 +27,7 @@ const DMA_LABEL: &str = "DMA";
 const DEC_LABEL: &str = "DEC";
 const ENC_LABEL: &str = "ENC";
 const VCN_LABEL: &str = "VCN";
+const DISPLAY_SERVER_MARK: char = '*';
 // const UVD_ENC_LABEL: &str = "UVD (ENC)";
 // const JPEG_LABEL: &str = "JPEG";
 
@@ -25,9 +35,47 @@ const VCN_LABEL: &str = "VCN";
 pub struct FdInfoView {
     pub stat: FdInfoStat,
     pub has_vcn_unified: bool,
+    pub raw: bool,
+    /// Include the shared buffers (dma-buf) in the memory usage
+    pub shared: bool,
+    pub group_by: FdInfoGroupBy,
+    /// The compact layout, only the top [`COMPACT_ROWS`] rows
+    pub compact: bool,
     pub text: Text,
 }
 
+const COMPACT_ROWS: usize = 5;
+/// (Tab), the sort orders with the keys
+const SORT_CYCLE: [FdInfoSortType; 5] = [
+    FdInfoSortType::PID,
+    FdInfoSortType::VRAM,
+    FdInfoSortType::CPU,
+    FdInfoSortType::GFX,
+    FdInfoSortType::MediaEngine,
+];
+
+/// A process, or a group of the processes
+struct Row {
+    mark: char,
+    /// 26 characters
+    label: String,
+    usage: FdInfoUsage,
+    delta: FdInfoUsage,
+    cpu_usage: i64,
+}
+
+impl Row {
+    fn exclude_shared(self, exclude: bool) -> Self {
+        if !exclude { return self }
+
+        Self {
+            usage: self.usage.exclude_shared(),
+            delta: self.delta.exclude_shared(),
+            ..self
+        }
+    }
+}
+
 impl FdInfoView {
     pub fn new(interval: Duration, has_vcn: bool, has_vcn_unified: bool) -> Self {
         let stat = FdInfoStat { interval, has_vcn, has_vcn_unified, ..Default::default() };
@@ -45,42 +93,138 @@ impl FdInfoView {
         sort: &FdInfoSortType,
         reverse: bool
     ) -> Result<(), fmt::Error> {
+        self.stat.get_all_proc_usage(proc_index);
+        self.print_stat(sort, reverse)
+    }
+
+    /// Prints [`Self::stat`] without reading fdinfo, for the mock devices (`--mock`, `--demo`)
+    pub fn print_stat(&mut self, sort: &FdInfoSortType, reverse: bool) -> Result<(), fmt::Error> {
         self.text.clear();
 
+        if self.raw {
+            self.print_raw_header()?;
+        } else {
+            write!(
+                self.text.buf,
+                " {pad:25} |{VRAM_LABEL:^6}|{GTT_LABEL:^6}|{CPU_LABEL:^4}|{GFX_LABEL:^4}|{COMPUTE_LABEL:^4}|{DMA_LABEL:^4}",
+                pad = "",
+            )?;
+
+            if self.has_vcn_unified {
+                writeln!(self.text.buf, "|{VCN_LABEL:^4}|")?;
+            } else {
+                writeln!(self.text.buf, "|{DEC_LABEL:^4}|{ENC_LABEL:^4}|")?;
+            }
+        }
+
+        let rows: Vec<Row> = if let Some(mut groups) = group_proc_usage(&self.stat.proc_usage, self.group_by) {
+            sort_group_usage(&mut groups, sort, reverse);
+
+            groups.into_iter().map(|g| Row {
+                mark: ' ',
+                label: format!("{:<18.18}({:>6})", g.name, g.pids.len()),
+                usage: g.usage,
+                delta: g.delta,
+                cpu_usage: g.cpu_usage,
+            }.exclude_shared(!self.shared)).collect()
+        } else {
+            sort_proc_usage(&mut self.stat.proc_usage, sort, reverse);
+
+            self.stat.proc_usage.iter().map(|pu| Row {
+                mark: if pu.is_display_server() { DISPLAY_SERVER_MARK } else { ' ' },
+                label: format!("{name:name_len$}({pid:>8})", name = pu.name, name_len = name_len(&pu.name), pid = pu.pid),
+                usage: pu.usage,
+                delta: pu.delta,
+                cpu_usage: pu.cpu_usage,
+            }.exclude_shared(!self.shared)).collect()
+        };
+
+        let rows = if self.compact { &rows[..rows.len().min(COMPACT_ROWS)] } else { &rows[..] };
+
+        if self.raw {
+            self.print_raw_usage(rows)?;
+        } else {
+            self.print_usage(rows)?;
+        }
+
+        if self.group_by != FdInfoGroupBy::Process {
+            writeln!(self.text.buf, " grouped by {} (number of processes)", self.group_by.label())?;
+        } else if self.stat.proc_usage.iter().any(|pu| pu.is_display_server()) {
+            writeln!(
+                self.text.buf,
+                " {DISPLAY_SERVER_MARK}: Display server/compositor, includes rendering for its clients",
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn print_raw_header(&mut self) -> Result<(), fmt::Error> {
         write!(
             self.text.buf,
-            " {pad:25} |{VRAM_LABEL:^6}|{GTT_LABEL:^6}|{CPU_LABEL:^4}|{GFX_LABEL:^4}|{COMPUTE_LABEL:^4}|{DMA_LABEL:^4}",
+            " {pad:25} |{VRAM_LABEL:^13}|{GTT_LABEL:^13}|{CPU_LABEL:^4}|{GFX_LABEL:^8}|{COMPUTE_LABEL:^8}|{DMA_LABEL:^8}",
             pad = "",
         )?;
 
         if self.has_vcn_unified {
-            writeln!(self.text.buf, "|{VCN_LABEL:^4}|")?;
+            writeln!(self.text.buf, "|{VCN_LABEL:^8}|")?;
         } else {
-            writeln!(self.text.buf, "|{DEC_LABEL:^4}|{ENC_LABEL:^4}|")?;
+            writeln!(self.text.buf, "|{DEC_LABEL:^8}|{ENC_LABEL:^8}|")?;
         }
 
-        self.stat.get_all_proc_usage(proc_index);
-
-        sort_proc_usage(&mut self.stat.proc_usage, sort, reverse);
-
-        self.print_usage()?;
+        writeln!(
+            self.text.buf,
+            " {pad:25} |{unit_b:^13}|{unit_b:^13}|{pad:4}|{unit_ms:^8}|{unit_ms:^8}|{unit_ms:^8}|",
+            pad = "",
+            unit_b = "(bytes)",
+            unit_ms = "(ms)",
+        )?;
 
         Ok(())
     }
 
-    pub fn print_usage(&mut self) -> Result<(), fmt::Error> {
-        for pu in &self.stat.proc_usage {
-            let utf16_count = pu.name.encode_utf16().count();
-            let name_len = if pu.name.len() != utf16_count {
-                PROC_NAME_LEN - utf16_count
+    /// engine busy time delta (ms per interval) and memory usage in bytes
+    fn print_raw_usage(&mut self, rows: &[Row]) -> Result<(), fmt::Error> {
+        for pu in rows {
+            write!(
+                self.text.buf,
+                "{mark}{label}|{vram:>13}|{gtt:>13}|{cpu:>3}%|",
+                mark = pu.mark,
+                label = pu.label,
+                vram = pu.delta.vram_usage << 10,
+                gtt = pu.delta.gtt_usage << 10,
+                cpu = pu.cpu_usage,
+            )?;
+
+            let engines = if self.has_vcn_unified {
+                vec![pu.delta.gfx, pu.delta.compute, pu.delta.dma, pu.delta.media]
             } else {
-                PROC_NAME_LEN
+                vec![
+                    pu.delta.gfx,
+                    pu.delta.compute,
+                    pu.delta.dma,
+                    pu.delta.dec + pu.delta.vcn_jpeg,
+                    pu.delta.enc + pu.delta.uvd_enc,
+                ]
             };
+
+            for ns in engines {
+                write!(self.text.buf, "{:>8.3}|", ns as f64 / 1_000_000.0)?;
+            }
+
+            writeln!(self.text.buf)?;
+        }
+
+        Ok(())
+    }
+
+    fn print_usage(&mut self, rows: &[Row]) -> Result<(), fmt::Error> {
+        for pu in rows {
             write!(
                 self.text.buf,
-                " {name:name_len$}({pid:>8})|{vram:>5}M|{gtt:>5}M|",
-                name = pu.name,
-                pid = pu.pid,
+                "{mark}{label}|{vram:>5}M|{gtt:>5}M|",
+                mark = pu.mark,
+                label = pu.label,
                 vram = pu.usage.vram_usage >> 10,
                 gtt = pu.usage.gtt_usage >> 10,
             )?;
@@ -110,6 +254,20 @@ impl FdInfoView {
         Ok(())
     }
 
+    pub fn cb_raw(siv: &mut cursive::Cursive) {
+        {
+            let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+            opt.fdinfo_raw ^= true;
+        }
+    }
+
+    pub fn cb_shared(siv: &mut cursive::Cursive) {
+        {
+            let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+            opt.fdinfo_shared ^= true;
+        }
+    }
+
     pub fn cb(siv: &mut cursive::Cursive) {
         {
             let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
@@ -117,6 +275,13 @@ impl FdInfoView {
         }
     }
 
+    pub fn cb_group_by(siv: &mut cursive::Cursive) {
+        {
+            let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+            opt.fdinfo_group = opt.fdinfo_group.next();
+        }
+    }
+
     pub fn cb_reverse_sort(siv: &mut cursive::Cursive) {
         {
             let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
@@ -158,4 +323,22 @@ impl FdInfoView {
             opt.fdinfo_sort = FdInfoSortType::MediaEngine;
         }
     }
+
+    pub fn cb_next_sort(siv: &mut cursive::Cursive) {
+        {
+            let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+            let pos = SORT_CYCLE.iter().position(|s| *s == opt.fdinfo_sort).map_or(0, |pos| pos + 1);
+            opt.fdinfo_sort = SORT_CYCLE[pos % SORT_CYCLE.len()].clone();
+        }
+    }
+}
+
+fn name_len(name: &str) -> usize {
+    let utf16_count = name.encode_utf16().count();
+
+    if name.len() != utf16_count {
+        PROC_NAME_LEN - utf16_count
+    } else {
+        PROC_NAME_LEN
+    }
 }
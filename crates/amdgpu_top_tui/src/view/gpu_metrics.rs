@@ -4,6 +4,7 @@ use crate::Opt;
 use libamdgpu_top::AMDGPU::{DeviceHandle, GpuMetrics, MetricsInfo};
 use libamdgpu_top::stat::{gpu_metrics_util::*, GpuActivity};
 use std::path::PathBuf;
+use std::time::Duration;
 
 const CORE_TEMP_LABEL: &str = "Core Temp (C)";
 const CORE_POWER_LABEL: &str = "Core Power (mW)";
@@ -15,6 +16,7 @@ const L3_CLOCK_LABEL: &str = "L3 Cache Clock (MHz)";
 pub struct GpuMetricsView {
     sysfs_path: PathBuf,
     metrics: GpuMetrics,
+    time: GpuMetricsTime,
     pub text: Text,
 }
 
@@ -24,18 +26,39 @@ impl GpuMetricsView {
         Self {
             sysfs_path: amdgpu_dev.get_sysfs_path().unwrap(),
             metrics: GpuMetrics::Unknown,
+            time: GpuMetricsTime::default(),
             text: Text::default(),
         }
     }
 
+    /// For the mock devices (`--mock`, `--demo`), the metrics are set by [`Self::set_metrics`]
+    pub fn new_without_sysfs() -> Self {
+        Self {
+            sysfs_path: PathBuf::new(),
+            metrics: GpuMetrics::Unknown,
+            time: GpuMetricsTime::default(),
+            text: Text::default(),
+        }
+    }
+
+    pub fn set_metrics(&mut self, metrics: &GpuMetrics) {
+        self.time.update(metrics);
+        self.metrics = metrics.clone();
+    }
+
+    pub fn metrics(&self) -> &GpuMetrics {
+        &self.metrics
+    }
+
     pub fn version(&self) -> Option<(u8, u8)> {
         let header = self.metrics.get_header()?;
 
         Some((header.format_revision, header.content_revision))
     }
 
-    pub fn update_metrics(&mut self, amdgpu_dev: &DeviceHandle) -> Result<(), ()> {
-        if let Ok(metrics) = amdgpu_dev.get_gpu_metrics_from_sysfs_path(&self.sysfs_path) {
+    pub fn update_metrics(&mut self) -> Result<(), ()> {
+        if let Ok(metrics) = get_gpu_metrics(&self.sysfs_path) {
+            self.time.update(&metrics);
             self.metrics = metrics;
             Ok(())
         } else {
@@ -43,9 +66,15 @@ impl GpuMetricsView {
         }
     }
 
-    pub fn print(&mut self) -> Result<(), fmt::Error> {
+    /// `interval`: the update interval, the old metrics are marked as stale
+    pub fn print(&mut self, interval: Duration) -> Result<(), fmt::Error> {
         self.text.clear();
 
+        if !matches!(self.metrics, GpuMetrics::Unknown) && self.time.is_stale(interval) {
+            let secs = self.time.updated.map_or(0, |t| t.elapsed().as_secs());
+            writeln!(self.text.buf, " (stale) Not updated for {secs}s")?;
+        }
+
         match self.metrics {
             GpuMetrics::V1_0(_) |
             GpuMetrics::V1_1(_) |
@@ -0,0 +1,49 @@
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex};
+use super::{PANEL_WIDTH, Text};
+use crate::Opt;
+use libamdgpu_top::PCI;
+use libamdgpu_top::stat::{uptime_us, KmsgTail};
+
+const LINES: usize = 10;
+
+#[derive(Clone)]
+pub struct KmsgView {
+    arc_tail: Arc<Mutex<KmsgTail>>,
+    /// since boot, at startup
+    base_us: u64,
+    pub text: Text,
+}
+
+impl KmsgView {
+    /// Returns `None` if `/dev/kmsg` cannot be read.
+    pub fn new(pci_bus: &PCI::BUS_INFO) -> Option<Self> {
+        let base_us = uptime_us()?;
+        let arc_tail = KmsgTail::spawn_update_thread(pci_bus, KmsgTail::DEFAULT_LEN)?;
+
+        Some(Self { arc_tail, base_us, text: Text::default() })
+    }
+
+    /// The timestamps are the seconds since startup, as the elapsed time of the metrics.
+    pub fn print(&mut self) -> Result<(), fmt::Error> {
+        let Ok(tail) = self.arc_tail.try_lock() else { return Ok(()) };
+        self.text.clear();
+
+        let skip = tail.records.len().saturating_sub(LINES);
+
+        for record in tail.records.iter().skip(skip) {
+            let msg: String = record.message.chars().take(PANEL_WIDTH - 14).collect();
+
+            writeln!(self.text.buf, " [{:>+9.1}s] {msg}", record.sec_since(self.base_us))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn cb(siv: &mut cursive::Cursive) {
+        {
+            let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+            opt.kmsg ^= true;
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::fmt::{self, Write};
+use super::Text;
+use libamdgpu_top::LeakDetector;
+use libamdgpu_top::stat::ProcUsage;
+
+const PROC_NAME_LEN: usize = 16;
+
+/// `--leak-detect`
+#[derive(Clone)]
+pub struct LeakView {
+    detector: LeakDetector,
+    pub text: Text,
+}
+
+impl LeakView {
+    /// Returns `None` if the detector is disabled.
+    pub fn new(threshold: f64) -> Option<Self> {
+        let detector = LeakDetector::new(threshold);
+
+        detector.is_enabled().then(|| Self { detector, text: Text::default() })
+    }
+
+    pub fn update(&mut self, proc_usage: &[ProcUsage]) {
+        self.detector.update(proc_usage);
+    }
+
+    pub fn print(&mut self) -> Result<(), fmt::Error> {
+        self.text.clear();
+
+        let suspects = self.detector.suspects();
+
+        if suspects.is_empty() {
+            writeln!(
+                self.text.buf,
+                " No process grows faster than {} MiB/min",
+                self.detector.threshold,
+            )?;
+            return Ok(());
+        }
+
+        writeln!(
+            self.text.buf,
+            " {:PROC_NAME_LEN$} ({:>8})| VRAM  | +MiB/min |  GTT  | +MiB/min | Duration",
+            "Name",
+            "PID",
+        )?;
+
+        for s in &suspects {
+            writeln!(
+                self.text.buf,
+                " {name:PROC_NAME_LEN$.PROC_NAME_LEN$} ({pid:>8})|{vram:>5}M|{vram_rate:>9.1} |{gtt:>5}M|{gtt_rate:>9.1} |{sec:>6}s",
+                name = s.name,
+                pid = s.pid,
+                vram = s.vram >> 10,
+                vram_rate = s.vram_rate,
+                gtt = s.gtt >> 10,
+                gtt_rate = s.gtt_rate,
+                sec = s.duration.as_secs(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -2,18 +2,45 @@ pub const PANEL_WIDTH: usize = 70;
 pub const PC_BAR_WIDTH: usize = 35;
 pub const VRAM_LABEL_WIDTH: usize = 6;
 
+mod activity_sources;
+pub use activity_sources::*;
+
+mod dpm_clock;
+pub use dpm_clock::*;
+
 mod fdinfo;
 pub use fdinfo::*;
 
+mod kmsg;
+pub use kmsg::*;
+
+mod leak;
+pub use leak::*;
+
 mod gpu_metrics;
 pub use gpu_metrics::*;
 
 mod perf_counter;
 pub use perf_counter::*;
 
+mod pm_info;
+pub use pm_info::*;
+
+mod pmu;
+pub use pmu::*;
+
+mod queue_priority;
+pub use queue_priority::*;
+
 mod sensors;
 pub use sensors::*;
 
+mod sqtt;
+pub use sqtt::*;
+
+mod stats;
+pub use stats::*;
+
 mod util;
 pub use util::*;
 
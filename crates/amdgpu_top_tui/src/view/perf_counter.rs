@@ -11,9 +11,11 @@ use cursive::utils::Counter;
 use cursive::Rect;
 use cursive::align::HAlign;
 
+use std::time::Duration;
 use libamdgpu_top::AMDGPU::CHIP_CLASS;
 use libamdgpu_top::stat::{PCType, PerfCounter};
-use super::{PANEL_WIDTH, PC_BAR_WIDTH, TopView};
+use super::{ascii_bar, PANEL_WIDTH, PC_BAR_WIDTH, TopView};
+use crate::LANGUAGE_LOADER;
 
 #[derive(Clone, Debug)]
 pub struct PerfCounterView {
@@ -33,13 +35,20 @@ impl PerfCounterView {
     pub fn top_view(
         &self,
         visible: bool,
+        ascii: bool,
     ) -> TopView {
         const LEFT_LEN: usize = PANEL_WIDTH - PC_BAR_WIDTH;
         
         let title = self.pc.pc_type.to_string();
         let mut sub_layout = LinearLayout::vertical();
-        let label = |value: usize, (_, _): (usize, usize)| -> String {
-            format!("[{val:^width$}]", width = PC_BAR_WIDTH - 2, val = format!("{value:3} %"))
+        let label = move |value: usize, range: (usize, usize)| -> String {
+            let val = format!("{value:3} %");
+
+            if ascii {
+                ascii_bar(&val, value, range, PC_BAR_WIDTH)
+            } else {
+                format!("[{val:^width$}]", width = PC_BAR_WIDTH - 2)
+            }
         };
 
         for (c, (name, _)) in self.counters.iter().zip(self.pc.index.iter()) {
@@ -47,7 +56,10 @@ impl PerfCounterView {
                 FixedLayout::new()
                     .child(
                         Rect::from_size((0,0), (LEFT_LEN+1, 1)),
-                        TextView::new(format!("{name:>LEFT_LEN$}:")),
+                        TextView::new(format!(
+                            "{:>LEFT_LEN$}:",
+                            LANGUAGE_LOADER.get(&name.replace(' ', "_").replace('/', "")),
+                        )),
                     )
                     .child(
                         Rect::from_size((LEFT_LEN+2,0), (PC_BAR_WIDTH, 1)),
@@ -67,13 +79,25 @@ impl PerfCounterView {
         .title_position(HAlign::Left)
     }
 
+    /// The plain text of [`Self::top_view`], for the snapshot
+    pub fn snapshot(&self) -> String {
+        const LEFT_LEN: usize = PANEL_WIDTH - PC_BAR_WIDTH;
+
+        self.counters.iter().zip(self.pc.index.iter()).map(|(c, (name, _))| format!(
+            "{:>LEFT_LEN$}: {:3} %\n",
+            LANGUAGE_LOADER.get(&name.replace(' ', "_").replace('/', "")),
+            c.get(),
+        )).collect()
+    }
+
     pub fn set_value(&self) {
-        for (c, (_, pos)) in self.counters.iter().zip(self.pc.index.iter()) {
-            c.set(self.pc.bits.get(*pos) as usize)
+        for (i, c) in self.counters.iter().enumerate() {
+            c.set(self.pc.get_usage(i) as usize)
         }
     }
 
-    pub fn dump(&mut self) {
+    pub fn dump(&mut self, interval: Duration) {
+        self.pc.update_usage(interval);
         self.set_value();
         self.pc.bits.clear();
     }
@@ -0,0 +1,98 @@
+use std::fmt::{self, Write};
+use super::Text;
+use crate::Opt;
+use libamdgpu_top::{NumberFormat, TempUnit};
+use libamdgpu_top::stat::{MediaBlockState, PmInfo};
+
+#[derive(Clone)]
+pub struct PmInfoView {
+    instance: u32,
+    pm_info: PmInfo,
+    pub text: Text,
+}
+
+impl PmInfoView {
+    pub fn new(instance: u32) -> Option<Self> {
+        let pm_info = PmInfo::get(instance).ok()?;
+
+        Some(Self {
+            instance,
+            pm_info,
+            text: Text::default(),
+        })
+    }
+
+    pub fn update(&mut self) {
+        if let Ok(pm_info) = PmInfo::get(self.instance) {
+            self.pm_info = pm_info;
+        }
+    }
+
+    pub fn print(&mut self, temp_unit: TempUnit, number_format: NumberFormat) -> Result<(), fmt::Error> {
+        const LABEL_LEN: usize = 20;
+        let pm_info = &self.pm_info;
+        self.text.clear();
+
+        for val in &pm_info.gfx {
+            let precision = if val.unit == "W" { 2 } else { 0 };
+            let value = number_format.float(val.value as f64, precision);
+
+            writeln!(self.text.buf, " {:<LABEL_LEN$} => {value:>7} {}", val.label, val.unit)?;
+        }
+
+        for (label, val, unit) in [
+            ("GPU Temp.", pm_info.gpu_temp.map(|c| temp_unit.convert_int(c as i64)), temp_unit.label()),
+            ("GPU Load", pm_info.gpu_load.map(i64::from), "%"),
+            ("MEM Load", pm_info.mem_load.map(i64::from), "%"),
+            ("VCN Load", pm_info.vcn_load.map(i64::from), "%"),
+        ] {
+            let Some(val) = val else { continue };
+            writeln!(self.text.buf, " {label:<LABEL_LEN$} => {val:7} {unit}")?;
+        }
+
+        for (label, block) in [("UVD/VCN", &pm_info.uvd), ("VCE", &pm_info.vce)] {
+            let Some(block) = block else { continue };
+            writeln!(self.text.buf, " {label:<LABEL_LEN$} => {}", media_block(block))?;
+        }
+
+        if let Some(mask) = pm_info.clock_gating_flags {
+            let on = pm_info.clock_gating.iter().filter(|(_, on)| *on).count();
+
+            writeln!(
+                self.text.buf,
+                " {:<LABEL_LEN$} => {on}/{} On ({mask:#x})",
+                "Clock Gating",
+                pm_info.clock_gating.len(),
+            )?;
+        }
+
+        if let Some(mask) = pm_info.smc_feature_mask {
+            writeln!(self.text.buf, " {:<LABEL_LEN$} => {mask:#018x}", "SMC Feature Mask")?;
+        }
+
+        Ok(())
+    }
+
+    pub fn cb(siv: &mut cursive::Cursive) {
+        {
+            let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+            opt.pm_info ^= true;
+        }
+    }
+}
+
+fn media_block(block: &MediaBlockState) -> String {
+    if !block.enabled {
+        return "Disabled".to_string();
+    }
+
+    let clk: Vec<String> = block.clocks.iter()
+        .map(|clk| format!("{} {:.0} {}", clk.label, clk.value, clk.unit))
+        .collect();
+
+    if clk.is_empty() {
+        "Enabled".to_string()
+    } else {
+        format!("Enabled ({})", clk.join(", "))
+    }
+}
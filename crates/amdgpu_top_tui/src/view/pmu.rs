@@ -0,0 +1,43 @@
+use std::fmt::{self, Write};
+use super::Text;
+use libamdgpu_top::{DevicePath, pmu::AmdgpuPmu};
+
+const NAME_LEN: usize = 40;
+
+/// `--source pmu`, instead of the GRBM/GRBM2 panels
+pub struct PmuView {
+    pmu: AmdgpuPmu,
+    pub text: Text,
+}
+
+impl PmuView {
+    /// Returns `None` without `--source pmu`, or if the PMU is not available.
+    pub fn new(device_path: &DevicePath) -> Option<Self> {
+        let pmu = AmdgpuPmu::open_if_enabled(device_path)?;
+
+        Some(Self { pmu, text: Text::default() })
+    }
+
+    pub fn title(&self) -> String {
+        format!("PMU ({})", self.pmu.name)
+    }
+
+    pub fn update(&mut self) {
+        self.pmu.update();
+    }
+
+    pub fn print(&mut self) -> Result<(), fmt::Error> {
+        self.text.clear();
+
+        for event in &self.pmu.events {
+            let name: String = event.name.chars().take(NAME_LEN).collect();
+
+            match event.rate {
+                Some(rate) => writeln!(self.text.buf, " {name:NAME_LEN$}: {:>14.0} /s", rate)?,
+                None => writeln!(self.text.buf, " {name:NAME_LEN$}: {:>14} /s", "_")?,
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt::{self, Write};
+use std::sync::{Arc, Mutex};
+use super::Text;
+use libamdgpu_top::ring_trace::{list_rings, RingTrace};
+
+const NAME_LEN: usize = 16;
+
+/// `--queue-priority`, the rings used by each process in the last 10s
+pub struct QueuePriorityView {
+    arc_trace: Arc<Mutex<RingTrace>>,
+    rings: Vec<String>,
+    pub text: Text,
+}
+
+impl QueuePriorityView {
+    /// Returns `None` without `--queue-priority`, or if tracefs or the rings are not available.
+    pub fn new(instance: u32) -> Option<Self> {
+        let arc_trace = RingTrace::shared()?;
+        let rings = list_rings(instance)
+            .inspect_err(|err| eprintln!("\"--queue-priority\": failed to read the rings: {err}"))
+            .ok()?;
+
+        Some(Self { arc_trace, rings, text: Text::default() })
+    }
+
+    pub fn print(&mut self) -> Result<(), fmt::Error> {
+        let Ok(trace) = self.arc_trace.try_lock() else { return Ok(()) };
+        let procs = trace.procs(&self.rings);
+        self.text.clear();
+
+        if procs.is_empty() {
+            writeln!(self.text.buf, " No submissions")?;
+        }
+
+        for proc in &procs {
+            let name: String = proc.name.chars().take(NAME_LEN).collect();
+            let queues: Vec<String> = proc.queues.iter()
+                .map(|q| format!("{} ({}, {})", q.ring, q.priority, q.submissions))
+                .collect();
+
+            writeln!(self.text.buf, " {name:NAME_LEN$} {:>8}: {}", proc.pid, queues.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
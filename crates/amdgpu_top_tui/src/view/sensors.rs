@@ -3,7 +3,9 @@ use super::{PANEL_WIDTH, Text};
 use std::fmt::{self, Write};
 use crate::Opt;
 
-use libamdgpu_top::stat::{Sensors, PcieBw};
+use libamdgpu_top::TempUnit;
+use libamdgpu_top::stat::{FwErrorStat, Sensors, SensorStatus, PcieBw, VcnState};
+use std::time::Duration;
 
 const WIDTH: usize = PANEL_WIDTH / 2;
 
@@ -25,33 +27,52 @@ impl SensorsView {
         self.sensors.update(amdgpu_dev);
     }
 
-    pub fn print(&mut self) -> Result<(), fmt::Error> {
+    /// For the mock devices (`--mock`, `--demo`)
+    pub fn set(&mut self, sensors: &Sensors) {
+        self.sensors = sensors.clone();
+    }
+
+    pub fn sensors(&self) -> &Sensors {
+        &self.sensors
+    }
+
+    /// `interval`: the update interval, the values not updated for
+    /// [`STALE_PERIODS`](libamdgpu_top::stat::STALE_PERIODS) intervals are marked as stale
+    pub fn print(&mut self, temp_unit: TempUnit, interval: Duration) -> Result<(), fmt::Error> {
         let sensors = &self.sensors;
+        let stale = |status: SensorStatus| -> String {
+            if status.is_stale(interval) {
+                let secs = status.updated.map_or(0, |t| t.elapsed().as_secs());
+                format!(" (stale {secs}s)")
+            } else {
+                String::new()
+            }
+        };
         const NAME_LEN: usize = 10;
         const VAL_LEN: usize = 5;
         self.text.clear();
 
         let mut c = 0;
 
-        for (name, val, unit) in [
-            ("GFX_SCLK", sensors.sclk, "MHz"),
-            ("GFX_MCLK", sensors.mclk, "MHz"),
-            ("VDDNB", sensors.vddnb, "mV"),
-            ("VDDGFX", sensors.vddgfx, "mV"),
+        for (name, val, status, unit) in [
+            ("GFX_SCLK", sensors.sclk, sensors.status.sclk, "MHz"),
+            ("GFX_MCLK", sensors.mclk, sensors.status.mclk, "MHz"),
+            ("VDDNB", sensors.vddnb, sensors.status.vddnb, "mV"),
+            ("VDDGFX", sensors.vddgfx, sensors.status.vddgfx, "mV"),
         ] {
             let Some(val) = val else { continue };
             c += 1;
             write!(
                 self.text.buf,
                 " {:<WIDTH$} ",
-                format!("{name:<NAME_LEN$} => {val:>VAL_LEN$} {unit:3}")
+                format!("{name:<NAME_LEN$} => {val:>VAL_LEN$} {unit:3}{}", stale(status))
             )?;
             if (c % 2) == 0 { writeln!(self.text.buf)? };
         }
         if (c % 2) == 1 { writeln!(self.text.buf)?; }
 
         if let Some(power) = sensors.power {
-            write!(self.text.buf, " GPU Power  => {power:3} W")?;
+            write!(self.text.buf, " GPU Power  => {power:3} W{}", stale(sensors.status.power))?;
             if let Some(ref cap) = sensors.power_cap {
                 write!(
                     self.text.buf,
@@ -61,21 +82,43 @@ impl SensorsView {
             writeln!(self.text.buf)?;
         }
 
-        for temp in [&sensors.edge_temp, &sensors.junction_temp, &sensors.memory_temp] {
+        if let Some(ref limits) = sensors.power_limits {
+            writeln!(self.text.buf, " APU Power Limits:")?;
+            for limit in limits.iter() {
+                let label = limit.type_.to_string();
+                write!(self.text.buf, "   {label:<8} => ")?;
+                if let Some(power) = limit.power {
+                    write!(self.text.buf, "{power:3} W / ")?;
+                } else {
+                    write!(self.text.buf, "___ W / ")?;
+                }
+                write!(self.text.buf, "{:3} W", limit.cap)?;
+                if let [Some(min), Some(max)] = [limit.min, limit.max] {
+                    write!(self.text.buf, " ({min}-{max} W)")?;
+                }
+                writeln!(self.text.buf)?;
+            }
+        }
+
+        for (temp, status) in [
+            (&sensors.edge_temp, sensors.status.edge_temp),
+            (&sensors.junction_temp, sensors.status.junction_temp),
+            (&sensors.memory_temp, sensors.status.memory_temp),
+        ] {
             let Some(temp) = temp else { continue };
             let label = format!("{} Temp.", temp.type_);
-            write!(self.text.buf, " {label:<15} => {:3} C", temp.current)?;
+            write!(self.text.buf, " {label:<15} => {:3} {temp_unit}", temp_unit.convert_int(temp.current))?;
             if let Some(crit) = temp.critical {
-                write!(self.text.buf, " (Crit. {crit} C)")?;
+                write!(self.text.buf, " (Crit. {} {temp_unit})", temp_unit.convert_int(crit))?;
             }
             if let Some(e) = temp.emergency {
-                write!(self.text.buf, " (Emergency {e} C)")?;
+                write!(self.text.buf, " (Emergency {} {temp_unit})", temp_unit.convert_int(e))?;
             }
-            writeln!(self.text.buf)?;
+            writeln!(self.text.buf, "{}", stale(status))?;
         }
 
         if let Some(fan_rpm) = sensors.fan_rpm {
-            write!(self.text.buf, " Fan => {fan_rpm:4} RPM")?;
+            write!(self.text.buf, " Fan => {fan_rpm:4} RPM{}", stale(sensors.status.fan_rpm))?;
             if let Some(max_rpm) = sensors.fan_max_rpm {
                 write!(self.text.buf, " (Max. {max_rpm} RPM)")?;
             }
@@ -92,9 +135,12 @@ impl SensorsView {
     }
 
     pub fn print_pcie_bw(&mut self, pcie_bw: &PcieBw) -> Result<(), fmt::Error> {
-        let Some(mps) = pcie_bw.max_payload_size else { return Ok(()) };
-        let Some(sent) = pcie_bw.sent.map(|v| (v * mps as u64) >> 20) else { return Ok(()) };
-        let Some(rec) = pcie_bw.received.map(|v| (v * mps as u64) >> 20) else { return Ok(()) };
+        if let Some(total) = pcie_bw.total {
+            writeln!(self.text.buf, " PCIe Bandwidth Usage => Total: {total:6} MiB/s (gpu_metrics)")?;
+            return Ok(());
+        }
+
+        let Some((sent, rec)) = pcie_bw.sent_received_mib() else { return Ok(()) };
 
         writeln!(
             self.text.buf,
@@ -104,6 +150,51 @@ impl SensorsView {
         Ok(())
     }
 
+    pub fn print_pcie_bw_unsupported(&mut self, reason: &str) -> Result<(), fmt::Error> {
+        writeln!(self.text.buf, " PCIe Bandwidth Usage => unsupported ({reason})")
+    }
+
+    pub fn print_fw_error(&mut self, fw_error: &FwErrorStat) -> Result<(), fmt::Error> {
+        writeln!(
+            self.text.buf,
+            " SMU Message Failures => {:3}, Firmware Errors => {:3}",
+            fw_error.smu_msg_failures,
+            fw_error.fw_errors,
+        )?;
+
+        if let (Some(msg), Some(ts)) = (&fw_error.last_error, fw_error.last_error_timestamp_us) {
+            let msg: String = msg.chars().take(PANEL_WIDTH - 20).collect();
+            writeln!(self.text.buf, "  [{:>5}.{:06}] {msg}", ts / 1_000_000, ts % 1_000_000)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn print_vcn_state(&mut self, vcn_state: &VcnState) -> Result<(), fmt::Error> {
+        let Some(state) = vcn_state.power_state() else { return Ok(()) };
+
+        write!(self.text.buf, " VCN => {state}")?;
+
+        let clk: Vec<String> = [("VCLK", vcn_state.vclk), ("DCLK", vcn_state.dclk)]
+            .iter()
+            .filter_map(|(name, clk)| clk.map(|clk| format!("{name} {clk} MHz")))
+            .collect();
+
+        if !clk.is_empty() {
+            write!(self.text.buf, " ({})", clk.join(", "))?;
+        }
+
+        let busy: Vec<&str> = vcn_state.busy_rings().map(|ring| ring.name.as_str()).collect();
+
+        if !busy.is_empty() {
+            write!(self.text.buf, ", Rings: {}", busy.join(", "))?;
+        }
+
+        writeln!(self.text.buf)?;
+
+        Ok(())
+    }
+
     pub fn cb(siv: &mut cursive::Cursive) {
         {
             let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
@@ -0,0 +1,44 @@
+use std::fmt::{self, Write};
+use super::Text;
+use libamdgpu_top::DeviceSample;
+use libamdgpu_top::sqtt::{SqttTrigger, WINDOW_LEN};
+
+/// `--sqtt-trigger`, (t) triggers an SQTT capture of RADV
+pub struct SqttView {
+    trigger: SqttTrigger,
+    /// The result of the last trigger
+    status: Option<String>,
+    pub text: Text,
+}
+
+impl SqttView {
+    /// Returns `None` if the trigger file is not configured.
+    pub fn new() -> Option<Self> {
+        let trigger = SqttTrigger::new()?;
+
+        Some(Self { trigger, status: None, text: Text::default() })
+    }
+
+    pub fn update(&mut self, sample: DeviceSample) {
+        self.trigger.push(sample);
+    }
+
+    pub fn trigger(&mut self) {
+        self.status = Some(match self.trigger.trigger() {
+            Ok(path) => format!("Triggered, the last {WINDOW_LEN} samples are saved to {}", path.display()),
+            Err(err) => format!("Failed to trigger: {err}"),
+        });
+    }
+
+    pub fn print(&mut self) -> Result<(), fmt::Error> {
+        self.text.clear();
+
+        writeln!(self.text.buf, " (t): trigger a capture, {}", self.trigger.path.display())?;
+
+        if let Some(status) = &self.status {
+            writeln!(self.text.buf, " {status}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt::{self, Write};
+use super::Text;
+use crate::Opt;
+use libamdgpu_top::{SessionStats, TempUnit};
+use libamdgpu_top::stat::PerfCounter;
+use crate::LANGUAGE_LOADER;
+
+/// Session min/avg/max of each sensor and perf counter, toggled with (a)
+#[derive(Clone, Default)]
+pub struct StatsView {
+    pub text: Text,
+}
+
+impl StatsView {
+    pub fn print(
+        &mut self,
+        stats: &SessionStats,
+        pcs: &[&PerfCounter],
+        temp_unit: TempUnit,
+    ) -> Result<(), fmt::Error> {
+        const NAME_LEN: usize = 34;
+        self.text.clear();
+
+        writeln!(self.text.buf, " {:<NAME_LEN$}    {:>6}   {:>6}   {:>6}", "", "Min", "Avg", "Max")?;
+
+        for (label, s) in stats.sensors() {
+            let (s, unit) = match label {
+                "GFX_SCLK" | "GFX_MCLK" => (s.map(|v| v as i64), "MHz"),
+                "VDDNB" | "VDDGFX" => (s.map(|v| v as i64), "mV"),
+                "GFX Power" => (s.map(|v| v as i64), "W"),
+                "Fan" => (s.map(|v| v as i64), "RPM"),
+                _ => (s.map(|v| temp_unit.convert_int(v as i64)), temp_unit.label()),
+            };
+
+            writeln!(self.text.buf, " {label:<NAME_LEN$} => {:>6} / {:>6} / {:>6} {unit}", s[0], s[1], s[2])?;
+        }
+
+        for pc in pcs {
+            for (i, (name, _)) in pc.index.iter().enumerate() {
+                let Some(s) = stats.perf_counter(pc.pc_type, i) else { continue };
+                let name = LANGUAGE_LOADER.get(&name.replace(' ', "_").replace('/', ""));
+                let label: String = format!("{} {name}", pc.pc_type).chars().take(NAME_LEN).collect();
+
+                writeln!(self.text.buf, " {label:<NAME_LEN$} => {:>6} / {:>6} / {:>6} %", s[0], s[1], s[2])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn cb(siv: &mut cursive::Cursive) {
+        {
+            let mut opt = siv.user_data::<Opt>().unwrap().lock().unwrap();
+            opt.stats ^= true;
+        }
+    }
+}
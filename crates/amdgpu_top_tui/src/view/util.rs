@@ -7,6 +7,7 @@ use cursive::views::{
     Panel
 };
 use cursive::align::HAlign;
+use cursive::theme::{BorderStyle, Theme};
 
 #[derive(Clone)]
 pub struct Text {
@@ -23,6 +24,11 @@ impl Text {
         self.content.set_content(&self.buf);
     }
 
+    /// The current content, shared with the update thread
+    pub fn get(&self) -> String {
+        self.content.get_content().source().to_string()
+    }
+
     pub fn panel(&self, title: &str) -> Panel<TextView> {
        Panel::new(
             TextView::new_with_content(self.content.clone()).no_wrap()
@@ -46,3 +52,28 @@ pub type TopView = Panel<NamedView<HideableView<LinearLayout>>>;
 pub fn toggle_view(view: &mut HideableView<LinearLayout>) {
     view.set_visible(!view.is_visible());
 }
+
+/// For `--ascii`, no box-drawing characters (the borders of the panels and menus)
+pub fn ascii_theme(theme: &mut Theme) {
+    theme.borders = BorderStyle::None;
+}
+
+/// `[######----- 45 % -------]`, for `--ascii`.
+/// `ProgressBar` draws the bar with colors and block elements.
+pub fn ascii_bar(label: &str, value: usize, (min, max): (usize, usize), width: usize) -> String {
+    let inner = width.saturating_sub(2);
+    let filled = match max.checked_sub(min) {
+        Some(range) if range != 0 => inner * value.saturating_sub(min).min(range) / range,
+        _ => 0,
+    };
+    let mut bar: Vec<char> = (0..inner).map(|i| if i < filled { '#' } else { '-' }).collect();
+    let label = format!(" {} ", label.trim());
+    let len = label.chars().count().min(inner);
+    let offset = (inner - len) / 2;
+
+    for (c, dst) in label.chars().zip(bar[offset..].iter_mut()) {
+        *dst = c;
+    }
+
+    format!("[{}]", bar.into_iter().collect::<String>())
+}
@@ -11,19 +11,24 @@ use cursive::view::Nameable;
 use cursive::utils::Counter;
 use cursive::Rect;
 use cursive::align::HAlign;
-use super::{PANEL_WIDTH, VRAM_LABEL_WIDTH, TopView};
-use libamdgpu_top::VramUsage;
+use std::fmt::{self, Write};
+use super::{ascii_bar, PANEL_WIDTH, VRAM_LABEL_WIDTH, Text, TopView};
+use libamdgpu_top::{NumberFormat, VramUsage};
+use libamdgpu_top::stat::BoStat;
+use crate::fl;
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct VramUsageView {
     memory_info: VramUsage,
     vram_counter: Counter,
     gtt_counter: Counter,
     instance: u32,
+    /// The BOs of all processes (debugfs, root)
+    bo_stat: Option<BoStat>,
+    bo_text: Text,
 }
 
 impl VramUsageView {
-    const TITLE: &str = "Memory Usage";
 
     pub fn new(info: &drm_amdgpu_memory_info, instance: u32) -> Self {
         Self {
@@ -31,20 +36,83 @@ impl VramUsageView {
             vram_counter: Counter::new(0),
             gtt_counter: Counter::new(0),
             instance,
+            bo_stat: BoStat::get(instance).ok(),
+            bo_text: Text::default(),
         }
     }
 
+    /// For the mock devices (`--mock`, `--demo`), without the BOs of debugfs
+    pub fn new_with_usage(memory_info: VramUsage, instance: u32) -> Self {
+        Self {
+            memory_info,
+            vram_counter: Counter::new(0),
+            gtt_counter: Counter::new(0),
+            instance,
+            bo_stat: None,
+            bo_text: Text::default(),
+        }
+    }
+
+    pub fn set_usage(&mut self, memory_info: &VramUsage) {
+        self.memory_info = memory_info.clone();
+    }
+
     pub fn update_usage(&mut self, amdgpu_dev: &DeviceHandle) {
         self.memory_info.update_usage(amdgpu_dev);
+
+        if self.bo_stat.is_some() {
+            self.bo_stat = BoStat::get(self.instance).ok();
+        }
     }
 
-    pub fn view(&self) -> TopView {
+    /// The breakdown of the BOs of all processes, to explain the difference from the heap usage
+    fn print_bo_stat(&mut self, number_format: NumberFormat) -> Result<(), fmt::Error> {
+        self.bo_text.clear();
+
+        let Some(bo) = &self.bo_stat else { return Ok(()) };
+        let mib = |size: u64| number_format.int(size >> 20);
+        let vram_usage = self.memory_info.0.vram.heap_usage;
+        let gtt_usage = self.memory_info.0.gtt.heap_usage;
+
+        writeln!(
+            self.bo_text.buf,
+            " BOs: {:>6} MiB ({} BOs), Pinned: {} MiB, Shared (dma-buf): {} MiB, Imported: {} MiB",
+            mib(bo.total),
+            bo.bos,
+            mib(bo.pinned),
+            mib(bo.shared),
+            mib(bo.duplicated),
+        )?;
+        write!(
+            self.bo_text.buf,
+            " VRAM: {} MiB (Visible: {} MiB), GTT: {} MiB, System: {} MiB, Kernel: VRAM {} MiB, GTT {} MiB",
+            mib(bo.vram),
+            mib(bo.vis_vram),
+            mib(bo.gtt),
+            mib(bo.cpu),
+            mib(vram_usage.saturating_sub(bo.vram)),
+            mib(gtt_usage.saturating_sub(bo.gtt)),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn view(&self, visible: bool, number_format: NumberFormat, ascii: bool) -> TopView {
         const BAR_WIDTH: usize = PANEL_WIDTH / 2 - VRAM_LABEL_WIDTH;
 
-        let title = Self::TITLE.to_string();
-        let label = |value: usize, (_min, max): (usize, usize)| -> String {
-            let val = format!("{:5} / {:5} MiB", value >> 20, max >> 20);
-            format!("[{val:^width$}]", width = BAR_WIDTH - 2)
+        let title = fl!("memory_usage");
+        let label = move |value: usize, (min, max): (usize, usize)| -> String {
+            let val = format!(
+                "{:>6} / {:>6} MiB",
+                number_format.int((value >> 20) as u64),
+                number_format.int((max >> 20) as u64),
+            );
+
+            if ascii {
+                ascii_bar(&val, value, (min, max), BAR_WIDTH)
+            } else {
+                format!("[{val:^width$}]", width = BAR_WIDTH - 2)
+            }
         };
         let mut sub_layout = LinearLayout::horizontal();
 
@@ -69,17 +137,53 @@ impl VramUsageView {
             );
         }
 
+        let mut layout = LinearLayout::vertical().child(sub_layout);
+
+        if self.bo_stat.is_some() {
+            layout.add_child(TextView::new_with_content(self.bo_text.content.clone()).no_wrap());
+        }
+
         Panel::new(
-            HideableView::new(sub_layout)
+            HideableView::new(layout)
+                .visible(visible)
                 .with_name(vram_view_name(self.instance))
         )
         .title(title)
         .title_position(HAlign::Left)
     }
 
-    pub fn set_value(&self) {
+    /// The plain text of [`Self::view`], for the snapshot
+    pub fn snapshot(&self, number_format: NumberFormat) -> String {
+        let mut s = String::new();
+
+        for (memory, counter, name) in [
+            (&self.memory_info.0.vram, &self.vram_counter, "VRAM"),
+            (&self.memory_info.0.gtt, &self.gtt_counter, "GTT"),
+        ] {
+            let _ = writeln!(
+                s,
+                " {name:>4}: {:>6} / {:>6} MiB",
+                number_format.int((counter.get() >> 20) as u64),
+                number_format.int(memory.total_heap_size >> 20),
+            );
+        }
+
+        if self.bo_stat.is_some() {
+            let _ = writeln!(s, "{}", self.bo_text.get());
+        }
+
+        s
+    }
+
+    pub fn memory_info(&self) -> &VramUsage {
+        &self.memory_info
+    }
+
+    pub fn set_value(&mut self, number_format: NumberFormat) {
         self.vram_counter.set(self.memory_info.0.vram.heap_usage as usize);
         self.gtt_counter.set(self.memory_info.0.gtt.heap_usage as usize);
+        self.print_bo_stat(number_format).unwrap();
+        self.bo_text.set();
     }
 
     pub fn cb(siv: &mut cursive::Cursive) {
@@ -0,0 +1,89 @@
+// The sampling path without the hardware, `cargo bench -p libamdgpu_top`
+//  * fdinfo: parse the fixtures, read `/proc/self/fdinfo/*` and `/proc/self/stat`
+//  * sensors: update the power limits from hwmon, update the mock device (`docs/mock/navi31`)
+//  * history: push the samples to the tiered history
+
+use std::fs;
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use criterion::{criterion_group, criterion_main, Criterion};
+use libamdgpu_top::{HistoryTiers, MockDevice, TieredHistory};
+use libamdgpu_top::stat::{get_self_pid, parse_fdinfo, FdInfoStat, PowerLimits, ProcInfo};
+
+const INTERVAL: Duration = Duration::from_secs(1);
+
+fn manifest_dir() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn fdinfo(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fdinfo");
+
+    for name in ["amdgpu_v6_1", "amdgpu_v6_9", "cycles_capacity"] {
+        let buf = fs::read(manifest_dir().join(format!("testdata/fdinfo/{name}.txt"))).unwrap();
+
+        group.bench_function(format!("parse_fdinfo/{name}"), |b| b.iter(|| parse_fdinfo(black_box(&buf))));
+    }
+
+    // the fds of this process are not DRM clients, but each fdinfo is read
+    let pid = get_self_pid().unwrap();
+    let fds: Vec<i32> = fs::read_dir("/proc/self/fd").unwrap()
+        .filter_map(|e| e.ok()?.file_name().to_str()?.parse().ok())
+        .collect();
+    let proc_index = [ProcInfo { pid, name: "sampling".to_string(), fds }];
+    let mut stat = FdInfoStat { interval: INTERVAL, ..Default::default() };
+
+    group.bench_function("get_all_proc_usage/self", |b| b.iter(|| {
+        stat.get_all_proc_usage(black_box(&proc_index));
+    }));
+
+    group.finish();
+}
+
+/// hwmon of an APU with the three power limits
+fn hwmon_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("amdgpu_top-bench-hwmon-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    for (i, label) in [(1, "slowPPT"), (2, "fastPPT"), (3, "PPT")] {
+        fs::write(dir.join(format!("power{i}_label")), format!("{label}\n")).unwrap();
+
+        for (name, uw) in [("average", 12_000_000), ("cap", 15_000_000), ("cap_default", 15_000_000), ("cap_min", 0), ("cap_max", 30_000_000)] {
+            fs::write(dir.join(format!("power{i}_{name}")), format!("{uw}\n")).unwrap();
+        }
+    }
+
+    dir
+}
+
+fn sensors(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sensors");
+    let dir = hwmon_dir();
+    let mut limits = PowerLimits::from_hwmon_path(&dir).unwrap();
+
+    group.bench_function("power_limits_update", |b| b.iter(|| limits.update(black_box(&dir))));
+
+    let mut mock = MockDevice::load(manifest_dir().join("../../docs/mock/navi31"), INTERVAL).unwrap();
+
+    group.bench_function("mock_update/navi31", |b| b.iter(|| mock.update()));
+
+    group.finish();
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn history(c: &mut Criterion) {
+    let mut group = c.benchmark_group("history");
+    let mut history = TieredHistory::new(&HistoryTiers::default());
+    let mut sec = 0.0;
+
+    group.bench_function("tiered_add", |b| b.iter(|| {
+        sec += 0.5;
+        history.add(black_box(sec), black_box(sec.sin()));
+    }));
+
+    group.finish();
+}
+
+criterion_group!(benches, fdinfo, sensors, history);
+criterion_main!(benches);
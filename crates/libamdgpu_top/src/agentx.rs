@@ -0,0 +1,391 @@
+// ref: https://www.rfc-editor.org/rfc/rfc2741 (AgentX Protocol Version 1)
+
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use crate::{PCI, SysfsMetric};
+
+pub const DEFAULT_SOCKET: &str = "/var/agentx/master";
+/// `NET-SNMP-MIB::netSnmpPlaypen` (`.1.3.6.1.4.1.8072.9999`) is reserved for local use.
+/// Sites with their own Private Enterprise Number can override it with `--agentx-oid`.
+pub const DEFAULT_BASE_OID: &[u32] = &[1, 3, 6, 1, 4, 1, 8072, 9999, 4098];
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(15);
+
+const HEADER_LEN: usize = 20;
+const FLAG_NETWORK_BYTE_ORDER: u8 = 0x10;
+
+mod pdu {
+    pub const OPEN: u8 = 1;
+    pub const CLOSE: u8 = 2;
+    pub const REGISTER: u8 = 3;
+    pub const GET: u8 = 5;
+    pub const GET_NEXT: u8 = 6;
+    pub const GET_BULK: u8 = 7;
+    pub const TEST_SET: u8 = 8;
+    pub const COMMIT_SET: u8 = 9;
+    pub const UNDO_SET: u8 = 10;
+    pub const CLEANUP_SET: u8 = 11;
+    pub const RESPONSE: u8 = 18;
+}
+
+mod var_type {
+    pub const INTEGER: u16 = 2;
+    pub const OCTET_STRING: u16 = 4;
+    pub const GAUGE32: u16 = 66;
+    pub const NO_SUCH_OBJECT: u16 = 128;
+    pub const END_OF_MIB_VIEW: u16 = 130;
+}
+
+const ERR_NOT_WRITABLE: u16 = 17;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Value {
+    Integer(i32),
+    OctetString(String),
+    Gauge32(u32),
+}
+
+/// AgentX subagent, registers the GPU table under the base OID.
+///
+/// ```text
+/// <base>.1.0          gpuCount        INTEGER
+/// <base>.2.1.1.<idx>  gpuIndex        INTEGER
+/// <base>.2.1.2.<idx>  gpuPciBus       OCTET STRING
+/// <base>.2.1.3.<idx>  gpuTemp         Gauge32 (C)
+/// <base>.2.1.4.<idx>  gpuPower        Gauge32 (W)
+/// <base>.2.1.5.<idx>  gpuGfxActivity  Gauge32 (%)
+/// <base>.2.1.6.<idx>  gpuVramUsed     Gauge32 (MiB)
+/// <base>.2.1.7.<idx>  gpuSclk         Gauge32 (MHz)
+/// <base>.2.1.8.<idx>  gpuMclk         Gauge32 (MHz)
+/// <base>.2.1.9.<idx>  gpuFanRpm       Gauge32 (RPM)
+/// ```
+pub struct AgentX {
+    pub socket_path: PathBuf,
+    pub base_oid: Vec<u32>,
+    devices: Vec<AgentXDevice>,
+    session_id: u32,
+    packet_id: u32,
+    start: Instant,
+}
+
+struct AgentXDevice {
+    pci_bus: PCI::BUS_INFO,
+    sysfs_path: PathBuf,
+    hwmon_path: PathBuf,
+}
+
+impl AgentX {
+    pub fn new<P: Into<PathBuf>>(socket_path: P, base_oid: &[u32], pci_list: &[PCI::BUS_INFO]) -> Self {
+        let devices = pci_list.iter().filter_map(|pci_bus| Some(AgentXDevice {
+            pci_bus: *pci_bus,
+            sysfs_path: pci_bus.get_sysfs_path(),
+            hwmon_path: pci_bus.get_hwmon_path()?,
+        })).collect();
+
+        Self {
+            socket_path: socket_path.into(),
+            base_oid: base_oid.to_vec(),
+            devices,
+            session_id: 0,
+            packet_id: 0,
+            start: Instant::now(),
+        }
+    }
+
+    /// `.1.3.6.1.4.1.XXXX` or `1.3.6.1.4.1.XXXX`
+    pub fn parse_oid(s: &str) -> Option<Vec<u32>> {
+        let oid: Vec<u32> = s.trim_start_matches('.')
+            .split('.')
+            .map(|id| id.parse().ok())
+            .collect::<Option<_>>()?;
+
+        (2 <= oid.len() && oid.len() <= 128).then_some(oid)
+    }
+
+    /// Connects to the master agent, and reconnects if the session is closed.
+    pub fn spawn_thread(mut self) {
+        std::thread::spawn(move || loop {
+            if let Err(err) = self.run() {
+                eprintln!("AgentX ({}): {err}", self.socket_path.display());
+            }
+
+            std::thread::sleep(RECONNECT_INTERVAL);
+        });
+    }
+
+    fn run(&mut self) -> io::Result<()> {
+        let mut stream = UnixStream::connect(&self.socket_path)?;
+
+        self.session_id = 0;
+        self.open(&mut stream)?;
+        self.register(&mut stream)?;
+
+        loop {
+            let (header, payload) = read_pdu(&mut stream)?;
+
+            match header.type_ {
+                pdu::GET | pdu::GET_NEXT | pdu::GET_BULK => {
+                    let var_binds = self.get(&header, &payload);
+                    self.response(&mut stream, &header, 0, &var_binds)?;
+                },
+                pdu::TEST_SET => self.response(&mut stream, &header, ERR_NOT_WRITABLE, &[])?,
+                pdu::COMMIT_SET | pdu::UNDO_SET | pdu::CLEANUP_SET =>
+                    self.response(&mut stream, &header, 0, &[])?,
+                pdu::CLOSE => return Err(io::Error::new(io::ErrorKind::ConnectionAborted, "closed by the master agent")),
+                _ => {},
+            }
+        }
+    }
+
+    fn open(&mut self, stream: &mut UnixStream) -> io::Result<()> {
+        let mut payload = vec![0u8; 4]; // timeout: default, reserved
+        encode_oid(&mut payload, &[], false);
+        encode_octet_string(&mut payload, "amdgpu_top");
+
+        self.request(stream, pdu::OPEN, &payload)
+    }
+
+    fn register(&mut self, stream: &mut UnixStream) -> io::Result<()> {
+        let mut payload = vec![0, 127, 0, 0]; // timeout: default, priority: default
+        encode_oid(&mut payload, &self.base_oid, false);
+
+        self.request(stream, pdu::REGISTER, &payload)
+    }
+
+    /// Sends a request and waits for the response.
+    fn request(&mut self, stream: &mut UnixStream, type_: u8, payload: &[u8]) -> io::Result<()> {
+        self.packet_id = self.packet_id.wrapping_add(1);
+        let header = Header {
+            type_,
+            big_endian: true,
+            session_id: self.session_id,
+            transaction_id: 0,
+            packet_id: self.packet_id,
+        };
+
+        write_pdu(stream, &header, payload)?;
+
+        let (res, payload) = read_pdu(stream)?;
+        let error = payload.get(4..6).map(|v| u16_from_bytes([v[0], v[1]], res.big_endian)).unwrap_or(0);
+
+        if res.type_ != pdu::RESPONSE || error != 0 {
+            return Err(io::Error::other(format!("request (type: {type_}) failed (error: {error})")));
+        }
+
+        self.session_id = res.session_id;
+
+        Ok(())
+    }
+
+    fn response(
+        &self,
+        stream: &mut UnixStream,
+        req: &Header,
+        error: u16,
+        var_binds: &[(Vec<u32>, Option<Value>, u16)],
+    ) -> io::Result<()> {
+        let uptime = (self.start.elapsed().as_millis() / 10) as u32; // TimeTicks
+        let mut payload = Vec::new();
+        payload.extend(uptime.to_be_bytes());
+        payload.extend(error.to_be_bytes());
+        payload.extend(0u16.to_be_bytes()); // index
+
+        for (oid, value, exception) in var_binds {
+            encode_var_bind(&mut payload, oid, value.as_ref(), *exception);
+        }
+
+        // always responds in network byte order
+        write_pdu(stream, &Header { type_: pdu::RESPONSE, big_endian: true, ..*req }, &payload)
+    }
+
+    fn get(&self, header: &Header, payload: &[u8]) -> Vec<(Vec<u32>, Option<Value>, u16)> {
+        let mut pos = 0;
+        let mut repeat = (0, 1); // (non_repeaters, max_repetitions)
+
+        if header.type_ == pdu::GET_BULK {
+            let [a, b, c, d] = payload.get(0..4)
+                .and_then(|v| <[u8; 4]>::try_from(v).ok())
+                .unwrap_or_default();
+            repeat = (
+                u16_from_bytes([a, b], header.big_endian) as usize,
+                u16_from_bytes([c, d], header.big_endian).max(1) as usize,
+            );
+            pos = 4;
+        }
+
+        let table = self.table();
+        let mut var_binds = Vec::new();
+        let mut i = 0;
+
+        while let Some((start, include)) = decode_oid(payload, &mut pos, header.big_endian) {
+            let Some((_end, _)) = decode_oid(payload, &mut pos, header.big_endian) else { break };
+
+            if header.type_ == pdu::GET {
+                let v = table.iter().find(|(oid, _)| *oid == start);
+
+                var_binds.push(match v {
+                    Some((oid, v)) => (oid.clone(), Some(v.clone()), 0),
+                    None => (start, None, var_type::NO_SUCH_OBJECT),
+                });
+
+                continue;
+            }
+
+            let n = if i < repeat.0 { 1 } else { repeat.1 };
+            let mut cur = start;
+            let mut include = include;
+
+            for _ in 0..n {
+                let next = table.iter().find(|(oid, _)| {
+                    if include { *oid >= cur } else { *oid > cur }
+                });
+
+                match next {
+                    Some((oid, v)) => {
+                        var_binds.push((oid.clone(), Some(v.clone()), 0));
+                        cur = oid.clone();
+                        include = false;
+                    },
+                    None => {
+                        var_binds.push((cur, None, var_type::END_OF_MIB_VIEW));
+                        break;
+                    },
+                }
+            }
+
+            i += 1;
+        }
+
+        var_binds
+    }
+
+    /// Sorted by OID
+    fn table(&self) -> Vec<(Vec<u32>, Value)> {
+        let oid = |sub: &[u32]| [self.base_oid.as_slice(), sub].concat();
+        let mut table = vec![(oid(&[1, 0]), Value::Integer(self.devices.len() as i32))];
+
+        for (i, dev) in self.devices.iter().enumerate() {
+            let idx = i as u32 + 1;
+
+            table.push((oid(&[2, 1, 1, idx]), Value::Integer(idx as i32)));
+            table.push((oid(&[2, 1, 2, idx]), Value::OctetString(dev.pci_bus.to_string())));
+
+            for (col, metric) in SysfsMetric::ALL.iter().enumerate() {
+                let Some(val) = metric.read(&dev.sysfs_path, &dev.hwmon_path) else { continue };
+                let col = col as u32 + 3;
+
+                table.push((oid(&[2, 1, col, idx]), Value::Gauge32(val.min(u32::MAX as u64) as u32)));
+            }
+        }
+
+        table.sort_by(|a, b| a.0.cmp(&b.0));
+
+        table
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Header {
+    type_: u8,
+    big_endian: bool,
+    session_id: u32,
+    transaction_id: u32,
+    packet_id: u32,
+}
+
+fn write_pdu(stream: &mut UnixStream, header: &Header, payload: &[u8]) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
+    buf.extend([1, header.type_, FLAG_NETWORK_BYTE_ORDER, 0]);
+    buf.extend(header.session_id.to_be_bytes());
+    buf.extend(header.transaction_id.to_be_bytes());
+    buf.extend(header.packet_id.to_be_bytes());
+    buf.extend((payload.len() as u32).to_be_bytes());
+    buf.extend(payload);
+
+    stream.write_all(&buf)
+}
+
+fn read_pdu(stream: &mut UnixStream) -> io::Result<(Header, Vec<u8>)> {
+    let mut h = [0u8; HEADER_LEN];
+    stream.read_exact(&mut h)?;
+
+    let big_endian = h[2] & FLAG_NETWORK_BYTE_ORDER != 0;
+    let u32_at = |i: usize| {
+        let v = [h[i], h[i+1], h[i+2], h[i+3]];
+
+        if big_endian { u32::from_be_bytes(v) } else { u32::from_le_bytes(v) }
+    };
+    let header = Header {
+        type_: h[1],
+        big_endian,
+        session_id: u32_at(4),
+        transaction_id: u32_at(8),
+        packet_id: u32_at(12),
+    };
+    let mut payload = vec![0u8; u32_at(16) as usize];
+    stream.read_exact(&mut payload)?;
+
+    Ok((header, payload))
+}
+
+fn encode_oid(buf: &mut Vec<u8>, oid: &[u32], include: bool) {
+    // "internet" prefix: 1.3.6.1.<prefix>
+    let (prefix, sub) = match oid {
+        [1, 3, 6, 1, p, rest @ ..] if *p <= 255 && !rest.is_empty() => (*p as u8, rest),
+        _ => (0, oid),
+    };
+
+    buf.extend([sub.len() as u8, prefix, include as u8, 0]);
+
+    for id in sub {
+        buf.extend(id.to_be_bytes());
+    }
+}
+
+fn decode_oid(buf: &[u8], pos: &mut usize, big_endian: bool) -> Option<(Vec<u32>, bool)> {
+    let [n, prefix, include, _] = <[u8; 4]>::try_from(buf.get(*pos..*pos+4)?).ok()?;
+    *pos += 4;
+
+    let mut oid = if prefix != 0 { vec![1, 3, 6, 1, prefix as u32] } else { Vec::new() };
+
+    for _ in 0..n {
+        let v = buf.get(*pos..*pos+4)?;
+        let v = [v[0], v[1], v[2], v[3]];
+        oid.push(if big_endian { u32::from_be_bytes(v) } else { u32::from_le_bytes(v) });
+        *pos += 4;
+    }
+
+    Some((oid, include != 0))
+}
+
+fn u16_from_bytes(v: [u8; 2], big_endian: bool) -> u16 {
+    if big_endian { u16::from_be_bytes(v) } else { u16::from_le_bytes(v) }
+}
+
+fn encode_octet_string(buf: &mut Vec<u8>, s: &str) {
+    buf.extend((s.len() as u32).to_be_bytes());
+    buf.extend(s.as_bytes());
+    buf.resize(buf.len() + (4 - s.len() % 4) % 4, 0);
+}
+
+fn encode_var_bind(buf: &mut Vec<u8>, oid: &[u32], value: Option<&Value>, exception: u16) {
+    let type_ = match value {
+        Some(Value::Integer(_)) => var_type::INTEGER,
+        Some(Value::OctetString(_)) => var_type::OCTET_STRING,
+        Some(Value::Gauge32(_)) => var_type::GAUGE32,
+        None => exception,
+    };
+
+    buf.extend(type_.to_be_bytes());
+    buf.extend([0, 0]);
+    encode_oid(buf, oid, false);
+
+    match value {
+        Some(Value::Integer(v)) => buf.extend(v.to_be_bytes()),
+        Some(Value::OctetString(s)) => encode_octet_string(buf, s),
+        Some(Value::Gauge32(v)) => buf.extend(v.to_be_bytes()),
+        None => {},
+    }
+}
@@ -0,0 +1,211 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::VramUsage;
+use crate::stat::{GpuActivity, Sensors};
+
+/// Exit code when a `--fail-on` condition is breached (1 is used for errors)
+pub const ALERT_EXIT_CODE: i32 = crate::exit_code::ALERT;
+
+/// Prints the breached conditions to stderr (and rings the terminal bell),
+/// then exits with [`ALERT_EXIT_CODE`].
+/// The conditions are not printed with `--quiet`.
+pub fn exit_on_alert(alerts: &[String], bell: bool) -> ! {
+    if !crate::exit_code::is_quiet() {
+        for alert in alerts {
+            eprintln!("fail-on: {alert}");
+        }
+    }
+
+    if bell {
+        eprint!("\x07");
+    }
+
+    std::process::exit(ALERT_EXIT_CODE);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertMetric {
+    Temp, // C, junction (or edge)
+    Vram, // MiB
+    Gtt, // MiB
+    Power, // W
+    Gfx, // %
+    Memory, // %
+    Media, // %
+    Sclk, // MHz
+    Fan, // RPM
+}
+
+impl AlertMetric {
+    const ALL: &'static [(&'static str, Self)] = &[
+        ("temp", Self::Temp),
+        ("vram", Self::Vram),
+        ("gtt", Self::Gtt),
+        ("power", Self::Power),
+        ("gfx", Self::Gfx),
+        ("memory", Self::Memory),
+        ("media", Self::Media),
+        ("sclk", Self::Sclk),
+        ("fan", Self::Fan),
+    ];
+
+    fn unit(&self) -> &'static str {
+        match self {
+            Self::Temp => "C",
+            Self::Vram | Self::Gtt => "MiB",
+            Self::Power => "W",
+            Self::Gfx | Self::Memory | Self::Media => "%",
+            Self::Sclk => "MHz",
+            Self::Fan => "RPM",
+        }
+    }
+
+    /// (value, max) for the percentage
+    fn get(
+        &self,
+        sensors: &Sensors,
+        vram: &VramUsage,
+        activity: &GpuActivity,
+    ) -> Option<(u64, Option<u64>)> {
+        let pct = |v: Option<u16>| v.map(|v| (v as u64, Some(100)));
+
+        match self {
+            Self::Temp => sensors.junction_temp.as_ref().or(sensors.edge_temp.as_ref())
+                .map(|t| (t.current.max(0) as u64, t.critical.map(|c| c.max(0) as u64))),
+            Self::Vram => Some((vram.0.vram.heap_usage >> 20, Some(vram.0.vram.total_heap_size >> 20))),
+            Self::Gtt => Some((vram.0.gtt.heap_usage >> 20, Some(vram.0.gtt.total_heap_size >> 20))),
+            Self::Power => sensors.power
+                .map(|p| (p as u64, sensors.power_cap.as_ref().map(|cap| cap.current as u64))),
+            Self::Gfx => pct(activity.gfx),
+            Self::Memory => pct(activity.umc),
+            Self::Media => pct(activity.media),
+            Self::Sclk => sensors.sclk.map(|v| (v as u64, None)),
+            Self::Fan => sensors.fan_rpm.map(|v| (v as u64, sensors.fan_max_rpm.map(|m| m as u64))),
+        }
+    }
+}
+
+impl fmt::Display for AlertMetric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = Self::ALL.iter().find(|(_, m)| m == self).map(|(name, _)| *name).unwrap_or("");
+
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertOp {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl AlertOp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+        }
+    }
+
+    fn cmp(&self, val: u64, threshold: u64) -> bool {
+        match self {
+            Self::Gt => val > threshold,
+            Self::Ge => val >= threshold,
+            Self::Lt => val < threshold,
+            Self::Le => val <= threshold,
+        }
+    }
+}
+
+/// `<metric><op><value>[%]`, e.g. `temp>100`, `vram>95%`, `power>=300`
+///
+/// With `%`, the value is compared to the percentage of the total VRAM/GTT, the power cap,
+/// the critical temperature or the max fan speed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AlertCondition {
+    pub metric: AlertMetric,
+    pub op: AlertOp,
+    pub threshold: u64,
+    pub percent: bool,
+}
+
+impl AlertCondition {
+    /// Comma-separated conditions, e.g. `temp>100,vram>95%`
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        s.split(',').filter(|c| !c.trim().is_empty()).map(Self::from_str).collect()
+    }
+
+    /// Messages of the breached conditions
+    pub fn check_all(
+        conds: &[Self],
+        sensors: &Sensors,
+        vram: &VramUsage,
+        activity: &GpuActivity,
+    ) -> Vec<String> {
+        conds.iter().filter_map(|cond| cond.check(sensors, vram, activity)).collect()
+    }
+
+    /// Returns the message if the condition is breached.
+    pub fn check(&self, sensors: &Sensors, vram: &VramUsage, activity: &GpuActivity) -> Option<String> {
+        let (val, max) = self.metric.get(sensors, vram, activity)?;
+        let unit = self.metric.unit();
+
+        let (val, unit) = if self.percent && unit != "%" {
+            let max = max.filter(|max| *max != 0)?;
+
+            (val * 100 / max, "%")
+        } else {
+            (val, unit)
+        };
+
+        self.op.cmp(val, self.threshold)
+            .then(|| format!("{} {val}{unit} ({self})", self.metric))
+    }
+}
+
+impl fmt::Display for AlertCondition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}{}",
+            self.metric,
+            self.op.as_str(),
+            self.threshold,
+            if self.percent { "%" } else { "" },
+        )
+    }
+}
+
+impl FromStr for AlertCondition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!(
+            "invalid condition: {s:?} (<metric><op><value>[%], metric: {}, op: > >= < <=)",
+            AlertMetric::ALL.iter().map(|(name, _)| *name).collect::<Vec<_>>().join("|"),
+        );
+        let s = s.trim();
+        let pos = s.find(['>', '<']).ok_or_else(err)?;
+        let (name, rest) = s.split_at(pos);
+        let metric = AlertMetric::ALL.iter()
+            .find(|(n, _)| *n == name.trim().to_ascii_lowercase())
+            .map(|(_, m)| *m)
+            .ok_or_else(err)?;
+        let (op, val) = [AlertOp::Ge, AlertOp::Le, AlertOp::Gt, AlertOp::Lt]
+            .into_iter()
+            .find_map(|op| Some((op, rest.strip_prefix(op.as_str())?)))
+            .ok_or_else(err)?;
+        let val = val.trim();
+        let (val, percent) = match val.strip_suffix('%') {
+            Some(val) => (val.trim(), true),
+            None => (val, false),
+        };
+        let threshold = val.parse::<u64>().map_err(|_| err())?;
+
+        Ok(Self { metric, op, threshold, percent })
+    }
+}
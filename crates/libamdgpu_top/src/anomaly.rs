@@ -0,0 +1,187 @@
+use std::collections::VecDeque;
+use std::fmt;
+use crate::stat::Sensors;
+
+/// The key metrics watched for anomalies, the names are the keys of "Sensors" in the JSON output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnomalyMetric {
+    Sclk,
+    Mclk,
+    Power,
+    EdgeTemp,
+    JunctionTemp,
+    Fan,
+}
+
+impl AnomalyMetric {
+    pub const ALL: [Self; 6] = [
+        Self::Sclk,
+        Self::Mclk,
+        Self::Power,
+        Self::EdgeTemp,
+        Self::JunctionTemp,
+        Self::Fan,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sclk => "GFX_SCLK",
+            Self::Mclk => "GFX_MCLK",
+            Self::Power => "GFX Power",
+            Self::EdgeTemp => "Edge Temperature",
+            Self::JunctionTemp => "Junction Temperature",
+            Self::Fan => "Fan",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Sclk | Self::Mclk => "MHz",
+            Self::Power => "W",
+            Self::EdgeTemp | Self::JunctionTemp => "C",
+            Self::Fan => "RPM",
+        }
+    }
+
+    pub fn get(&self, sensors: &Sensors) -> Option<f64> {
+        let val = match self {
+            Self::Sclk => sensors.sclk?,
+            Self::Mclk => sensors.mclk?,
+            Self::Power => sensors.power?,
+            Self::EdgeTemp => return sensors.edge_temp.as_ref().map(|t| t.current as f64),
+            Self::JunctionTemp => return sensors.junction_temp.as_ref().map(|t| t.current as f64),
+            Self::Fan => sensors.fan_rpm?,
+        };
+
+        Some(val as f64)
+    }
+}
+
+impl fmt::Display for AnomalyMetric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Anomaly {
+    pub metric: AnomalyMetric,
+    pub value: f64,
+    /// The mean of the window before the sample
+    pub mean: f64,
+    /// Negative for a drop
+    pub z: f64,
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {:.0} {unit} (mean {:.0} {unit}, z {:+.1})",
+            self.metric,
+            self.value,
+            self.mean,
+            self.z,
+            unit = self.metric.unit(),
+        )
+    }
+}
+
+/// The mean and the standard deviation of the last `len` samples
+#[derive(Clone, Debug)]
+struct RollingStat {
+    samples: VecDeque<f64>,
+    len: usize,
+    sum: f64,
+    sum_sq: f64,
+}
+
+impl RollingStat {
+    fn new(len: usize) -> Self {
+        Self { samples: VecDeque::with_capacity(len), len, sum: 0.0, sum_sq: 0.0 }
+    }
+
+    fn push(&mut self, val: f64) {
+        if self.samples.len() >= self.len {
+            if let Some(old) = self.samples.pop_front() {
+                self.sum -= old;
+                self.sum_sq -= old * old;
+            }
+        }
+
+        self.samples.push_back(val);
+        self.sum += val;
+        self.sum_sq += val * val;
+    }
+
+    fn mean_std(&self) -> (f64, f64) {
+        let n = self.samples.len() as f64;
+        let mean = self.sum / n;
+        let var = (self.sum_sq / n - mean * mean).max(0.0);
+
+        (mean, var.sqrt())
+    }
+}
+
+/// `--anomaly-threshold`
+/// Rolling z-score of the key metrics: a sample more than `threshold` standard deviations away
+/// from the mean of the last [`Self::WINDOW`] samples is reported, e.g. an intermittent clock drop.
+/// The anomalies are also added to the window, so a lasting change becomes the new normal.
+#[derive(Clone, Debug)]
+pub struct AnomalyDetector {
+    /// `0.0`: disabled
+    pub threshold: f64,
+    stats: Vec<(AnomalyMetric, RollingStat)>,
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_THRESHOLD)
+    }
+}
+
+impl AnomalyDetector {
+    pub const DEFAULT_THRESHOLD: f64 = 4.0;
+    /// The number of the samples of the mean and the standard deviation
+    pub const WINDOW: usize = 60;
+    /// No anomaly is reported until the window is half filled
+    const MIN_SAMPLES: usize = Self::WINDOW / 2;
+    /// The standard deviation is at least 1% of the mean,
+    /// so the noise of a flat signal (e.g. the fan at a fixed speed) is not reported.
+    const MIN_STD_RATIO: f64 = 0.01;
+
+    pub fn new(threshold: f64) -> Self {
+        let stats = AnomalyMetric::ALL.iter().map(|m| (*m, RollingStat::new(Self::WINDOW))).collect();
+
+        Self { threshold, stats }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.threshold > 0.0
+    }
+
+    /// Adds the sample of the key metrics, and returns the anomalies of the sample.
+    pub fn update(&mut self, sensors: &Sensors) -> Vec<Anomaly> {
+        if !self.is_enabled() { return Vec::new() }
+
+        let mut anomalies = Vec::new();
+
+        for (metric, stat) in self.stats.iter_mut() {
+            let Some(value) = metric.get(sensors) else { continue };
+
+            if stat.samples.len() >= Self::MIN_SAMPLES {
+                let (mean, std) = stat.mean_std();
+                let std = std.max(mean.abs() * Self::MIN_STD_RATIO).max(f64::EPSILON);
+                let z = (value - mean) / std;
+
+                if z.abs() >= self.threshold {
+                    anomalies.push(Anomaly { metric: *metric, value, mean, z });
+                }
+            }
+
+            stat.push(value);
+        }
+
+        anomalies
+    }
+}
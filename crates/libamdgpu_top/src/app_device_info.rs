@@ -10,7 +10,7 @@ use crate::AMDGPU::{
     VBIOS::VbiosInfo,
     VIDEO_CAPS::{CAP_TYPE, VideoCapsInfo},
 };
-use crate::{PCI, stat::Sensors};
+use crate::{PCI, PciPlacement, stat::Sensors};
 
 #[derive(Debug)]
 pub struct AppDeviceInfo {
@@ -27,6 +27,7 @@ pub struct AppDeviceInfo {
     pub max_mem_clk: u32,
     pub marketing_name: String,
     pub pci_bus: PCI::BUS_INFO,
+    pub placement: PciPlacement,
     pub edge_temp: Option<HwmonTemp>,
     pub junction_temp: Option<HwmonTemp>,
     pub memory_temp: Option<HwmonTemp>,
@@ -75,6 +76,7 @@ impl AppDeviceInfo {
             max_mem_clk,
             marketing_name,
             pci_bus: sensors.bus_info,
+            placement: PciPlacement::get(&sensors.bus_info),
             edge_temp: sensors.edge_temp.clone(),
             junction_temp: sensors.junction_temp.clone(),
             memory_temp: sensors.memory_temp.clone(),
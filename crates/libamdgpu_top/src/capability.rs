@@ -0,0 +1,195 @@
+// The capability matrix of the device: which metrics and controls are available on the ASIC,
+// with the reason if not (e.g. "pcie_bw: unsupported (dGPU only)", "RAS: disabled by firmware").
+// Shown in `--dump` and the device info of GUI mode, instead of blanks in the other panels.
+
+// ## Reference
+//  * <https://www.kernel.org/doc/html/latest/gpu/amdgpu/thermal.html>
+//  * <https://www.kernel.org/doc/html/latest/gpu/amdgpu/ras.html>
+//  * Linux Kernel
+//    * `drivers/gpu/drm/amd/pm/amdgpu_pm.c`
+//      * `default_attr_update`
+//    * `drivers/gpu/drm/amd/amdgpu/amdgpu_ras.c`
+//      * `amdgpu_ras_sysfs_features_read`
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::AppDeviceInfo;
+use crate::AMDGPU::drm_amdgpu_info_device;
+use crate::priv_helper::{self, ControlCapabilities};
+use crate::stat::{PcieBw, PcieBwSource, XgmiStat};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CapabilityStatus {
+    Ok,
+    /// Not supported by the ASIC or the driver
+    Unsupported,
+    /// Supported, but disabled by the firmware or the module parameter
+    Disabled,
+    /// Readable, but the control is not writable (permission or read-only mode)
+    NotWritable,
+    /// The file exists, but the permission is denied
+    NoPermission,
+}
+
+impl CapabilityStatus {
+    pub fn is_ok(&self) -> bool {
+        *self == Self::Ok
+    }
+}
+
+impl fmt::Display for CapabilityStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::Ok => "OK",
+            Self::Unsupported => "unsupported",
+            Self::Disabled => "disabled",
+            Self::NotWritable => "not writable",
+            Self::NoPermission => "no permission",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capability {
+    pub name: &'static str,
+    pub status: CapabilityStatus,
+    /// e.g. the version, the range, or the reason if not available
+    pub detail: Option<String>,
+}
+
+impl Capability {
+    fn new(name: &'static str, status: CapabilityStatus, detail: Option<String>) -> Self {
+        Self { name, status, detail }
+    }
+
+    /// `"gpu_metrics: v2.3 OK"`, `"pcie_bw: unsupported (dGPU only)"`
+    pub fn status_str(&self) -> String {
+        match (&self.detail, self.status) {
+            (Some(detail), CapabilityStatus::Ok) => format!("{detail} OK"),
+            (Some(detail), status) => format!("{status} ({detail})"),
+            (None, status) => status.to_string(),
+        }
+    }
+
+    pub fn detect_all(info: &AppDeviceInfo, caps: &ControlCapabilities) -> Vec<Self> {
+        let sysfs_path = info.pci_bus.get_sysfs_path();
+        let hwmon_path = info.pci_bus.get_hwmon_path();
+
+        vec![
+            Self::gpu_metrics(&sysfs_path),
+            Self::pcie_bw(&sysfs_path, &info.ext_info),
+            Self::ras(&sysfs_path),
+            Self::sensor("temperature", info.edge_temp.is_some() || info.junction_temp.is_some(), None),
+            Self::sensor("power cap", info.power_cap.is_some(), info.power_cap.as_ref().map(|cap| {
+                format!("{}-{} W", cap.min, cap.max)
+            })),
+            Self::control("power cap control", info.power_cap.is_some(), caps.power_cap),
+            Self::sensor("fan", info.fan_max_rpm.is_some(), None),
+            Self::control("fan control", hwmon_path.is_some_and(|path| path.join("pwm1").exists()), caps.fan),
+            Self::control(
+                "fan curve",
+                sysfs_path.join("gpu_od/fan_ctrl/fan_curve").exists(),
+                caps.fan_curve,
+            ),
+            Self::overdrive(&sysfs_path, caps.od_clk),
+            Self::control("power profile", !info.power_profiles.is_empty(), caps.power_profile),
+            Self::sensor(
+                "IP discovery",
+                !info.ip_die_entries.is_empty(),
+                info.ip_die_entries.is_empty().then(|| "requires Linux 5.19 or later".to_string()),
+            ),
+            Self::sensor("video caps", info.decode.is_some() && info.encode.is_some(), None),
+            Self::sensor("XGMI", XgmiStat::new(&info.pci_bus).is_some(), None),
+        ]
+    }
+
+    fn sensor(name: &'static str, available: bool, detail: Option<String>) -> Self {
+        let status = if available { CapabilityStatus::Ok } else { CapabilityStatus::Unsupported };
+
+        Self::new(name, status, detail)
+    }
+
+    fn control(name: &'static str, exists: bool, writable: bool) -> Self {
+        match (exists, writable) {
+            (true, true) => Self::new(name, CapabilityStatus::Ok, None),
+            (true, false) => Self::new(name, CapabilityStatus::NotWritable, Some(not_writable_reason())),
+            (false, _) => Self::new(name, CapabilityStatus::Unsupported, None),
+        }
+    }
+
+    fn gpu_metrics(sysfs_path: &Path) -> Self {
+        const NAME: &str = "gpu_metrics";
+
+        match fs::read(sysfs_path.join(NAME)) {
+            // `struct metrics_table_header { structure_size: u16, format_revision: u8, content_revision: u8 }`
+            Ok(raw) if raw.len() >= 4 => Self::new(NAME, CapabilityStatus::Ok, Some(format!("v{}.{}", raw[2], raw[3]))),
+            Ok(_) => Self::new(NAME, CapabilityStatus::Unsupported, None),
+            Err(err) => Self::from_io_error(NAME, &err),
+        }
+    }
+
+    fn pcie_bw(sysfs_path: &Path, ext_info: &drm_amdgpu_info_device) -> Self {
+        const NAME: &str = "pcie_bw";
+
+        match PcieBw::new(sysfs_path).detect_source(ext_info) {
+            Ok(PcieBwSource::Sysfs) => Self::new(NAME, CapabilityStatus::Ok, None),
+            Ok(PcieBwSource::GpuMetrics) => Self::new(NAME, CapabilityStatus::Ok, Some("total, gpu_metrics".to_string())),
+            Err(reason) => Self::new(NAME, CapabilityStatus::Unsupported, Some(reason.to_string())),
+        }
+    }
+
+    fn ras(sysfs_path: &Path) -> Self {
+        const NAME: &str = "RAS";
+
+        // "feature mask: 0x0"
+        let mask = match fs::read_to_string(sysfs_path.join("ras/features")) {
+            Ok(s) => s.trim().rsplit(' ').next()
+                .and_then(|mask| u32::from_str_radix(mask.trim_start_matches("0x"), 16).ok()),
+            Err(err) => return Self::from_io_error(NAME, &err),
+        };
+
+        match mask {
+            Some(0) => Self::new(NAME, CapabilityStatus::Disabled, Some("by firmware".to_string())),
+            Some(mask) => Self::new(NAME, CapabilityStatus::Ok, Some(format!("{mask:#x}"))),
+            None => Self::new(NAME, CapabilityStatus::Unsupported, None),
+        }
+    }
+
+    fn overdrive(sysfs_path: &Path, writable: bool) -> Self {
+        const NAME: &str = "overdrive";
+
+        if sysfs_path.join("pp_od_clk_voltage").exists() {
+            Self::control(NAME, true, writable)
+        } else {
+            // `pp_od_clk_voltage` is removed if `PP_OVERDRIVE_MASK` is not set
+            Self::new(NAME, CapabilityStatus::Disabled, Some("not set in amdgpu.ppfeaturemask".to_string()))
+        }
+    }
+
+    fn from_io_error(name: &'static str, err: &io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::PermissionDenied => Self::new(name, CapabilityStatus::NoPermission, None),
+            io::ErrorKind::NotFound => Self::new(name, CapabilityStatus::Unsupported, None),
+            // e.g. `EOPNOTSUPP`
+            _ => Self::new(name, CapabilityStatus::Unsupported, Some(err.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.status_str())
+    }
+}
+
+fn not_writable_reason() -> String {
+    if priv_helper::is_read_only() {
+        "read-only mode".to_string()
+    } else {
+        format!("requires root or {}", priv_helper::HELPER_NAME)
+    }
+}
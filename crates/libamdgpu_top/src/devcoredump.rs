@@ -0,0 +1,103 @@
+// ref: https://docs.kernel.org/driver-api/devcoredump.html
+// The kernel removes a dump after 5 minutes, or when `data` is written.
+// `data` is readable by root only.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use flate2::{Compression, write::GzEncoder};
+use crate::PCI;
+
+const DEVCOREDUMP_PATH: &str = "/sys/class/devcoredump";
+
+/// `$XDG_DATA_HOME/amdgpu_top` (or `~/.local/share/amdgpu_top`)
+pub fn data_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+
+    Some(base.join("amdgpu_top"))
+}
+
+/// `$XDG_DATA_HOME/amdgpu_top/crash_dumps` (or `~/.local/share/amdgpu_top/crash_dumps`)
+pub fn crash_dump_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("crash_dumps"))
+}
+
+/// A devcoredump of the device, created by the driver after a GPU hang (reset)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrashDump {
+    /// `devcd<N>`
+    pub name: String,
+    pub path: PathBuf,
+    pub pci_bus: PCI::BUS_INFO,
+    /// The creation time of the sysfs directory
+    pub time: Option<SystemTime>,
+}
+
+impl CrashDump {
+    pub fn list(pci_bus: &PCI::BUS_INFO) -> Vec<Self> {
+        let Ok(dir) = fs::read_dir(DEVCOREDUMP_PATH) else { return Vec::new() };
+        let pci = pci_bus.to_string();
+
+        let mut dumps: Vec<Self> = dir.flatten().filter_map(|entry| {
+            let path = entry.path();
+            let failing_device = fs::read_link(path.join("failing_device")).ok()?;
+
+            if failing_device.file_name()? != pci.as_str() { return None }
+
+            Some(Self {
+                name: entry.file_name().to_string_lossy().to_string(),
+                time: fs::symlink_metadata(&path).and_then(|m| m.modified()).ok(),
+                pci_bus: *pci_bus,
+                path,
+            })
+        }).collect();
+
+        dumps.sort_by_key(|dump| dump.time);
+
+        dumps
+    }
+
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.time?.elapsed().ok()
+    }
+
+    /// `amdgpu_devcoredump_<pci>_<UNIX time>.txt` (`.txt.gz`)
+    pub fn file_name(&self, compress: bool) -> String {
+        let secs = self.time
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        // ':' is not allowed in file names on some filesystems
+        let pci = self.pci_bus.to_string().replace(':', "-");
+        let ext = if compress { "txt.gz" } else { "txt" };
+
+        format!("amdgpu_devcoredump_{pci}_{secs}.{ext}")
+    }
+
+    /// Copies the dump to the directory, and returns the path.
+    /// The dump is kept in sysfs, it is removed by the kernel later.
+    pub fn save<P: AsRef<Path>>(&self, dir: P, compress: bool) -> io::Result<PathBuf> {
+        let data = fs::read(self.path.join("data"))
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", self.path.join("data").display())))?;
+        let dir = dir.as_ref();
+
+        fs::create_dir_all(dir)?;
+
+        let dst = dir.join(self.file_name(compress));
+
+        if compress {
+            let mut encoder = GzEncoder::new(File::create(&dst)?, Compression::default());
+
+            encoder.write_all(&data)?;
+            encoder.finish()?;
+        } else {
+            fs::write(&dst, data)?;
+        }
+
+        Ok(dst)
+    }
+}
@@ -5,6 +5,7 @@ use std::fs;
 use std::fmt;
 
 const DRM_RENDER: u32 = 128;
+const DRI_BY_PATH: &str = "/dev/dri/by-path";
 
 #[derive(Clone)]
 pub struct DevicePath {
@@ -56,11 +57,14 @@ impl DevicePath {
         list: &[Self],
     ) -> (Self, DeviceHandle) {
         Self::fallback(instance).unwrap_or_else(|err| {
-            eprintln!("{err}");
-            eprintln!("Fallback: list: {list:#?}");
             let device_path = list[0].clone();
             let amdgpu_dev = device_path.init().unwrap();
-            eprintln!("Fallback: to: {device_path:?}");
+
+            if !crate::exit_code::is_quiet() {
+                eprintln!("{err}");
+                eprintln!("Fallback: list: {list:#?}");
+                eprintln!("Fallback: to: {device_path:?}");
+            }
 
             (device_path, amdgpu_dev)
         })
@@ -73,18 +77,51 @@ impl DevicePath {
             .parse::<u32>().ok()
     }
 
+    pub fn nickname(&self) -> Option<String> {
+        let pci = self.pci?;
+
+        crate::DeviceNicknames::load().get(&pci).map(|s| s.to_string())
+    }
+
+    /// `/dev/dri/by-path/pci-<domain:bus:dev.func>-card` link, if exists
+    pub fn by_path_card(&self) -> Option<PathBuf> {
+        self.by_path("card")
+    }
+
+    /// `/dev/dri/by-path/pci-<domain:bus:dev.func>-render` link, if exists
+    pub fn by_path_render(&self) -> Option<PathBuf> {
+        self.by_path("render")
+    }
+
+    fn by_path(&self, node: &str) -> Option<PathBuf> {
+        let path = PathBuf::from(DRI_BY_PATH).join(format!("pci-{}-{node}", self.pci?));
+
+        path.is_symlink().then_some(path)
+    }
+
+    /// The render node or the PCI device in sysfs is removed,
+    /// e.g. the device is unbound from the driver or hot-unplugged.
+    pub fn is_lost(&self) -> bool {
+        !self.render.exists() || self.pci.is_some_and(|pci| !pci.get_sysfs_path().exists())
+    }
+
     pub fn get_device_path_list() -> Vec<Self> {
-        let amdgpu_devices = fs::read_dir("/sys/bus/pci/drivers/amdgpu").unwrap_or_else(|_| {
+        Self::try_get_device_path_list().unwrap_or_else(|_| {
             eprintln!("The AMDGPU driver is not loaded.");
             panic!();
-        });
+        })
+    }
+
+    /// `Err` if the AMDGPU driver is not loaded
+    pub fn try_get_device_path_list() -> std::io::Result<Vec<Self>> {
+        let amdgpu_devices = fs::read_dir("/sys/bus/pci/drivers/amdgpu")?;
 
-        amdgpu_devices.flat_map(|v| {
+        Ok(amdgpu_devices.flat_map(|v| {
             let name = v.ok()?.file_name();
             let pci = name.into_string().ok()?.parse::<PCI::BUS_INFO>().ok()?;
 
             Self::try_from(pci).ok()
-        }).collect()
+        }).collect())
     }
 }
 
@@ -92,7 +129,7 @@ impl TryFrom<PCI::BUS_INFO> for DevicePath {
     type Error = std::io::Error;
 
     fn try_from(pci: PCI::BUS_INFO) -> Result<Self, Self::Error> {
-        let base = PathBuf::from("/dev/dri/by-path");
+        let base = PathBuf::from(DRI_BY_PATH);
 
         let [render, card] = ["render", "card"].map(|v| -> std::io::Result<PathBuf> {
             let name = format!("pci-{pci}-{v}");
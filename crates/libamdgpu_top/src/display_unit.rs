@@ -0,0 +1,125 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// The unit to display temperatures in.
+/// The values are read and exported (JSON) in Celsius, and converted only for display.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TempUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TempUnit {
+    pub fn convert(&self, c: f64) -> f64 {
+        match self {
+            Self::Celsius => c,
+            Self::Fahrenheit => c * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    pub fn convert_int(&self, c: i64) -> i64 {
+        self.convert(c as f64).round() as i64
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Celsius => "C",
+            Self::Fahrenheit => "F",
+        }
+    }
+}
+
+impl fmt::Display for TempUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl FromStr for TempUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "c" | "celsius" => Ok(Self::Celsius),
+            "f" | "fahrenheit" => Ok(Self::Fahrenheit),
+            _ => Err(format!("invalid temperature unit: {s:?} (C|F)")),
+        }
+    }
+}
+
+/// Decimal and digit group separators of the numeric locale
+/// (`LC_ALL`, `LC_NUMERIC` or `LANG`), e.g. "16,368.50" (en), "16.368,50" (de).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NumberFormat {
+    pub decimal_sep: char,
+    pub group_sep: Option<char>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self::C
+    }
+}
+
+impl NumberFormat {
+    /// "C"/"POSIX" locale, no digit grouping
+    pub const C: Self = Self { decimal_sep: '.', group_sep: None };
+
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|val| !val.is_empty())
+            .unwrap_or_default();
+
+        Self::from_locale(&locale)
+    }
+
+    /// e.g. "de_DE.UTF-8"
+    pub fn from_locale(locale: &str) -> Self {
+        let locale = locale.split(['.', '@']).next().unwrap_or("");
+        let (lang, region) = locale.split_once('_').unwrap_or((locale, ""));
+
+        let (decimal_sep, group_sep) = match (lang, region) {
+            ("de" | "it", "CH") => ('.', '\''),
+            ("en" | "ja" | "zh" | "ko" | "th" | "he", _) => ('.', ','),
+            ("de" | "nl" | "it" | "es" | "pt" | "id" | "tr" | "da" | "el" | "ro", _) => (',', '.'),
+            // no-break space
+            ("fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "uk" | "hu" | "bg", _) => (',', '\u{a0}'),
+            _ => return Self::C,
+        };
+
+        Self { decimal_sep, group_sep: Some(group_sep) }
+    }
+
+    /// Replaces the non-ASCII separators (no-break space) with a space
+    pub fn to_ascii(self) -> Self {
+        let ascii = |c: char| if c.is_ascii() { c } else { ' ' };
+
+        Self { decimal_sep: ascii(self.decimal_sep), group_sep: self.group_sep.map(ascii) }
+    }
+
+    /// e.g. 16368 => "16,368"
+    pub fn int(&self, n: u64) -> String {
+        let digits = n.to_string();
+        let Some(sep) = self.group_sep else { return digits };
+        let mut end = match digits.len() % 3 { 0 => 3, n => n };
+        let mut s = digits[..end].to_string();
+
+        while end < digits.len() {
+            s.push(sep);
+            s.push_str(&digits[end..end+3]);
+            end += 3;
+        }
+
+        s
+    }
+
+    /// e.g. (1.5, 2) => "1.50" or "1,50"
+    pub fn float(&self, n: f64, precision: usize) -> String {
+        let s = format!("{n:.precision$}");
+
+        if self.decimal_sep == '.' { s } else { s.replace('.', &self.decimal_sep.to_string()) }
+    }
+}
@@ -0,0 +1,109 @@
+// The exit codes and `--quiet`, so amdgpu_top can be used in the shell pipelines.
+//  * 0: success
+//  * 1: the other errors (e.g. invalid arguments, I/O errors)
+//  * 2: a `--fail-on` condition is breached
+//  * 3: no AMDGPU device is found (or the driver is not loaded)
+//  * 4: permission denied (e.g. the render node, debugfs)
+//  * 5: the device is lost while running (e.g. unbound, hot-unplugged)
+// `--quiet` suppresses the output that is not the data (hints, warnings, progress messages),
+// the error messages are still printed to stderr.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::DevicePath;
+
+pub const ERROR: i32 = 1;
+pub const ALERT: i32 = 2;
+pub const NO_DEVICE: i32 = 3;
+pub const PERMISSION_DENIED: i32 = 4;
+pub const DEVICE_LOST: i32 = 5;
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// `--quiet`
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// The first device of `list` that is lost, see [`DevicePath::is_lost`]
+pub fn find_lost_device<'a, I: IntoIterator<Item = &'a DevicePath>>(list: I) -> Option<&'a DevicePath> {
+    list.into_iter().find(|device_path| device_path.is_lost())
+}
+
+/// Why a mode returned, the caller exits with the code after the cleanup (e.g. `--revert-on-exit`)
+#[derive(Clone, Debug, Default)]
+pub enum ExitReason {
+    #[default]
+    Done,
+    /// The breached `--fail-on` conditions
+    Alert(Vec<String>),
+    DeviceLost(DevicePath),
+}
+
+impl ExitReason {
+    /// Exits with [`ALERT`] or [`DEVICE_LOST`], returns for `Done`
+    pub fn exit(self, bell: bool) {
+        match self {
+            Self::Done => {},
+            Self::Alert(alerts) => crate::exit_on_alert(&alerts, bell),
+            Self::DeviceLost(device_path) => exit_device_lost(&device_path),
+        }
+    }
+}
+
+/// Prints the lost device to stderr, then exits with [`DEVICE_LOST`].
+pub fn exit_device_lost(device_path: &DevicePath) -> ! {
+    let name = match device_path.pci {
+        Some(pci) => pci.to_string(),
+        None => device_path.render.display().to_string(),
+    };
+
+    eprintln!("error: the device is lost: {name}");
+
+    std::process::exit(DEVICE_LOST);
+}
+
+/// SIGINT, SIGTERM and SIGHUP
+fn termination_sigset() -> libc::sigset_t {
+    let mut set = unsafe { std::mem::zeroed::<libc::sigset_t>() };
+
+    unsafe {
+        libc::sigemptyset(&mut set);
+
+        for sig in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+            libc::sigaddset(&mut set, sig);
+        }
+    }
+
+    set
+}
+
+/// Blocks SIGINT, SIGTERM and SIGHUP in the calling thread and the threads spawned after it,
+/// so they are only received by [`on_termination_signal`].
+/// Call it before spawning any thread, a thread spawned before would be killed by the signal.
+pub fn block_termination_signals() -> io::Result<()> {
+    let set = termination_sigset();
+    let ret = unsafe { libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut()) };
+
+    if ret == 0 { Ok(()) } else { Err(io::Error::from_raw_os_error(ret)) }
+}
+
+/// Spawns a thread that waits for the signals blocked by [`block_termination_signals`],
+/// runs `f` (e.g. the revert of `--revert-on-exit`), then exits with 128 + the signal number,
+/// the same code as the shell reports for a process killed by the signal.
+pub fn on_termination_signal<F: FnOnce() + Send + 'static>(f: F) {
+    std::thread::spawn(move || {
+        let set = termination_sigset();
+        let mut sig: libc::c_int = 0;
+
+        if unsafe { libc::sigwait(&set, &mut sig) } != 0 { return }
+
+        f();
+
+        std::process::exit(128 + sig);
+    });
+}
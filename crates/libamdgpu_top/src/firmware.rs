@@ -0,0 +1,33 @@
+use crate::AMDGPU::{DeviceHandle, FW_VERSION::{FW_TYPE, FwVer}};
+
+/// The firmware shown in `--dump`
+pub const FW_LIST: &[FW_TYPE] = &[
+    FW_TYPE::VCE,
+    FW_TYPE::UVD,
+    FW_TYPE::GMC,
+    FW_TYPE::GFX_ME,
+    FW_TYPE::GFX_PFP,
+    FW_TYPE::GFX_CE,
+    FW_TYPE::GFX_RLC,
+    FW_TYPE::GFX_MEC,
+    FW_TYPE::SMC,
+    FW_TYPE::SDMA,
+    FW_TYPE::SOS,
+    FW_TYPE::ASD,
+    FW_TYPE::VCN,
+    FW_TYPE::GFX_RLC_RESTORE_LIST_CNTL,
+    FW_TYPE::GFX_RLC_RESTORE_LIST_GPM_MEM,
+    FW_TYPE::GFX_RLC_RESTORE_LIST_SRM_MEM,
+    FW_TYPE::DMCU,
+    FW_TYPE::TA,
+    FW_TYPE::DMCUB,
+    FW_TYPE::TOC,
+];
+
+/// The loaded firmware of [`FW_LIST`], the firmware not loaded (version 0) are skipped.
+pub fn get_firmware_versions(amdgpu_dev: &DeviceHandle) -> Vec<FwVer> {
+    FW_LIST.iter()
+        .filter_map(|fw_type| amdgpu_dev.query_firmware_version(*fw_type, 0, 0).ok())
+        .filter(|fw| fw.version != 0)
+        .collect()
+}
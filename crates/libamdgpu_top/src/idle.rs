@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use crate::stat::{FdInfoUsage, GpuActivity};
+
+/// `--idle-timeout`
+/// When the GPU has been idle (no activity, no engine usage of the processes) for the timeout,
+/// the polling interval is stretched and the scan of the processes (`/proc/<pid>/fd`) is skipped,
+/// to reduce the wakeups of amdgpu_top itself.
+/// The processes that open the device while paused are found when it resumes on GPU activity.
+#[derive(Clone, Debug, Default)]
+pub struct IdleDetector {
+    /// `None`: disabled
+    pub timeout: Option<Duration>,
+    idle_since: Option<Instant>,
+    paused: Arc<AtomicBool>,
+}
+
+impl IdleDetector {
+    /// The polling interval is multiplied by this while paused
+    pub const STRETCH: u32 = 5;
+
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self { timeout, ..Default::default() }
+    }
+
+    /// For the process index thread
+    pub fn paused_flag(&self) -> Arc<AtomicBool> {
+        self.paused.clone()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Returns `true` when it resumes from the pause.
+    pub fn update(&mut self, active: bool) -> bool {
+        let Some(timeout) = self.timeout else { return false };
+
+        if active {
+            self.idle_since = None;
+            return self.paused.swap(false, Ordering::Relaxed);
+        }
+
+        let idle_since = *self.idle_since.get_or_insert_with(Instant::now);
+
+        if idle_since.elapsed() >= timeout {
+            self.paused.store(true, Ordering::Relaxed);
+        }
+
+        false
+    }
+
+    /// The interval of the next sample
+    pub fn interval(&self, interval: Duration) -> Duration {
+        if self.is_paused() { interval * Self::STRETCH } else { interval }
+    }
+}
+
+/// Memory usage and the UMC (memory controller, also busy with the display scanout) are not counted.
+pub fn is_gpu_active(activity: &GpuActivity, fdinfo: &FdInfoUsage) -> bool {
+    [activity.gfx, activity.media].iter().any(|v| v.is_some_and(|v| v != 0))
+        || [
+            fdinfo.gfx,
+            fdinfo.compute,
+            fdinfo.dma,
+            fdinfo.dec,
+            fdinfo.enc,
+            fdinfo.uvd_enc,
+            fdinfo.vcn_jpeg,
+            fdinfo.media,
+        ].iter().any(|v| *v != 0)
+}
@@ -0,0 +1,39 @@
+use crate::AMDGPU::{HwId, IpDieEntry, IpHwId, IpHwInstance};
+
+/// The largest HWID in `soc15_hw_ip.h` is `DAZ_HWID` (274)
+const MAX_HW_ID: isize = 300;
+
+/// `--ip-hw`, comma-separated IP block names (e.g. `GC,SDMA0,VCN`), case-insensitive.
+/// Empty matches all IP blocks.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IpHwFilter(Vec<HwId>);
+
+impl IpHwFilter {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let ids = s.split(',').map(str::trim).filter(|name| !name.is_empty()).map(|name| {
+            // VCN shares the HWID with UVD
+            let name = if name.eq_ignore_ascii_case("VCN") { "UVD" } else { name };
+
+            (0..=MAX_HW_ID)
+                .map(HwId::from)
+                .filter(|id| !matches!(id, HwId::Unknown(_)))
+                .find(|id| id.to_string().eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("unknown IP block: {name:?} (e.g. GC, SDMA0, VCN, MP1, UMC)"))
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self(ids))
+    }
+
+    pub fn matches(&self, hw_id: &HwId) -> bool {
+        self.0.is_empty() || self.0.contains(hw_id)
+    }
+
+    pub fn filter<'a>(&'a self, die: &'a IpDieEntry) -> impl Iterator<Item = &'a IpHwId> {
+        die.ip_hw_ids.iter().filter(|ip_hw| self.matches(&ip_hw.hw_id))
+    }
+}
+
+/// The harvested (fused off) instances are disabled on the SKU.
+pub fn is_harvested(inst: &IpHwInstance) -> bool {
+    inst.harvest != 0
+}
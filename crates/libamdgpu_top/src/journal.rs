@@ -0,0 +1,129 @@
+// ref: https://systemd.io/JOURNAL_NATIVE_PROTOCOL/
+
+use std::fmt::Write;
+use std::os::unix::net::UnixDatagram;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use crate::{PCI, SysfsMetric};
+
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+const SYSLOG_SOCKET: &str = "/dev/log";
+const SYSLOG_IDENTIFIER: &str = "amdgpu_top";
+/// Fixed `MESSAGE_ID` for the metrics of amdgpu_top, to filter with `journalctl MESSAGE_ID=...`.
+pub const MESSAGE_ID: &str = "4f1d6c0b8a3e4e6f9d2b7a5c3e1f0a92";
+
+/// syslog(3) priority
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum JournalPriority {
+    Emerg = 0,
+    Alert = 1,
+    Crit = 2,
+    Error = 3,
+    Warning = 4,
+    Notice = 5,
+    Info = 6,
+    Debug = 7,
+}
+
+impl FromStr for JournalPriority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let p = match s.to_ascii_lowercase().as_str() {
+            "emerg" | "0" => Self::Emerg,
+            "alert" | "1" => Self::Alert,
+            "crit" | "2" => Self::Crit,
+            "err" | "3" => Self::Error,
+            "warning" | "4" => Self::Warning,
+            "notice" | "5" => Self::Notice,
+            "info" | "6" => Self::Info,
+            "debug" | "7" => Self::Debug,
+            _ => return Err(format!("invalid priority: {s:?} (emerg|alert|crit|err|warning|notice|info|debug)")),
+        };
+
+        Ok(p)
+    }
+}
+
+/// Sends the selected metrics of the device as structured journald fields
+/// (`AMDGPU_TEMP=65`, `AMDGPU_POWER=120`, ...).
+/// Falls back to syslog (`/dev/log`) if journald is not running.
+pub struct JournalLogger {
+    pub pci_bus: PCI::BUS_INFO,
+    pub fields: Vec<SysfsMetric>,
+    pub priority: JournalPriority,
+    sysfs_path: PathBuf,
+    hwmon_path: PathBuf,
+    socket: UnixDatagram,
+}
+
+impl JournalLogger {
+    pub fn new(
+        pci_bus: PCI::BUS_INFO,
+        fields: &[SysfsMetric],
+        priority: JournalPriority,
+    ) -> Option<Self> {
+        let hwmon_path = pci_bus.get_hwmon_path()?;
+        let sysfs_path = pci_bus.get_sysfs_path();
+        let socket = UnixDatagram::unbound().ok()?;
+
+        Some(Self { pci_bus, fields: fields.to_vec(), priority, sysfs_path, hwmon_path, socket })
+    }
+
+    /// Returns the `(field, value)` pairs that could be read.
+    pub fn sample(&self) -> Vec<(SysfsMetric, u64)> {
+        self.fields.iter().filter_map(|f| Some((*f, f.read(&self.sysfs_path, &self.hwmon_path)?))).collect()
+    }
+
+    fn message(&self, sample: &[(SysfsMetric, u64)]) -> String {
+        let mut s = format!("{}:", self.pci_bus);
+
+        for (f, val) in sample {
+            let _ = write!(s, " {f}={val}{}", f.unit());
+        }
+
+        s
+    }
+
+    pub fn send(&self) -> std::io::Result<()> {
+        let sample = self.sample();
+        let msg = self.message(&sample);
+        let mut buf = format!(
+            "MESSAGE={msg}\nMESSAGE_ID={MESSAGE_ID}\nPRIORITY={}\nSYSLOG_IDENTIFIER={SYSLOG_IDENTIFIER}\nAMDGPU_PCI={}\n",
+            self.priority as u8,
+            self.pci_bus,
+        );
+
+        for (f, val) in &sample {
+            let _ = writeln!(buf, "AMDGPU_{}={val}", f.key());
+        }
+
+        match self.socket.send_to(buf.as_bytes(), JOURNAL_SOCKET) {
+            Ok(_) => Ok(()),
+            Err(_) => {
+                // facility: user (1)
+                let pri = 8 + self.priority as u8;
+                let buf = format!("<{pri}>{SYSLOG_IDENTIFIER}: {msg}");
+
+                self.socket.send_to(buf.as_bytes(), SYSLOG_SOCKET).map(|_| ())
+            },
+        }
+    }
+
+    pub fn spawn_thread(self, interval: Duration) {
+        if let Err(err) = self.send() {
+            eprintln!("Failed to send to journald/syslog: {err}");
+            return;
+        }
+
+        crate::scheduler::spawn_periodic(interval, move || {
+            if let Err(err) = self.send() {
+                eprintln!("Failed to send to journald/syslog: {err}");
+                return false;
+            }
+
+            true
+        });
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use crate::stat::ProcUsage;
+
+/// A process whose VRAM or GTT usage keeps growing
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeakSuspect {
+    pub pid: i32,
+    pub name: String,
+    /// KiB
+    pub vram: u64,
+    /// KiB
+    pub gtt: u64,
+    /// MiB/min, the slope of the window
+    pub vram_rate: f64,
+    /// MiB/min
+    pub gtt_rate: f64,
+    /// The duration of the window
+    pub duration: Duration,
+}
+
+/// (the seconds since the start, VRAM KiB, GTT KiB)
+#[derive(Clone, Debug, Default)]
+struct MemTrend {
+    name: String,
+    samples: VecDeque<(f64, u64, u64)>,
+}
+
+impl MemTrend {
+    /// MiB/min, if the usage grows (almost) monotonically
+    fn growth_rate(&self, get: impl Fn(&(f64, u64, u64)) -> u64) -> Option<f64> {
+        let first = self.samples.front()?;
+        let last = self.samples.back()?;
+
+        if get(last) <= get(first) { return None }
+
+        let steps = self.samples.len() - 1;
+        let growing_steps = self.samples.iter().zip(self.samples.iter().skip(1))
+            .filter(|(a, b)| get(b) >= get(a))
+            .count();
+
+        if (growing_steps as f64) < steps as f64 * LeakDetector::MONOTONIC_RATIO {
+            return None;
+        }
+
+        // least squares
+        let n = self.samples.len() as f64;
+        let [sum_x, sum_y, sum_xx, sum_xy] = self.samples.iter().fold([0.0; 4], |[x, y, xx, xy], s| {
+            let (sx, sy) = (s.0, get(s) as f64);
+            [x + sx, y + sy, xx + sx * sx, xy + sx * sy]
+        });
+        let denom = n * sum_xx - sum_x * sum_x;
+
+        if denom <= 0.0 { return None }
+
+        let kib_per_sec = (n * sum_xy - sum_x * sum_y) / denom;
+
+        Some(kib_per_sec * 60.0 / 1024.0)
+    }
+}
+
+/// `--leak-threshold`
+/// Tracks the VRAM/GTT usage of each process, and reports the processes whose usage grows monotonically
+/// faster than `threshold` (MiB/min) over at least [`Self::MIN_DURATION`], e.g. a leak of the buffers.
+/// A temporary growth (e.g. loading a level of a game) stops being reported when the usage drops or stays flat
+/// for the window.
+#[derive(Clone, Debug)]
+pub struct LeakDetector {
+    /// MiB/min, `0.0`: disabled
+    pub threshold: f64,
+    start: Instant,
+    last_sample: Option<Instant>,
+    procs: HashMap<i32, MemTrend>,
+}
+
+impl Default for LeakDetector {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+impl LeakDetector {
+    pub const DEFAULT_THRESHOLD: f64 = 4.0;
+    pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+    /// 10 minutes
+    pub const WINDOW: usize = 120;
+    pub const MIN_DURATION: Duration = Duration::from_secs(60);
+    /// The ratio of the non-decreasing steps in the window
+    const MONOTONIC_RATIO: f64 = 0.9;
+
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            start: Instant::now(),
+            last_sample: None,
+            procs: HashMap::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.threshold > 0.0
+    }
+
+    /// Sampled every [`Self::SAMPLE_INTERVAL`], regardless of the update interval
+    pub fn update(&mut self, proc_usage: &[ProcUsage]) {
+        if !self.is_enabled() { return }
+
+        let now = Instant::now();
+
+        if self.last_sample.is_some_and(|last| now.duration_since(last) < Self::SAMPLE_INTERVAL) {
+            return;
+        }
+
+        self.last_sample = Some(now);
+        let sec = now.duration_since(self.start).as_secs_f64();
+
+        self.procs.retain(|pid, _| proc_usage.iter().any(|pu| pu.pid == *pid));
+
+        for pu in proc_usage {
+            let trend = self.procs.entry(pu.pid).or_default();
+
+            if trend.samples.len() >= Self::WINDOW {
+                trend.samples.pop_front();
+            }
+
+            if trend.name != pu.name {
+                trend.name.clone_from(&pu.name);
+            }
+
+            trend.samples.push_back((sec, pu.usage.vram_usage, pu.usage.gtt_usage));
+        }
+    }
+
+    /// Sorted by the growth rate (descending)
+    pub fn suspects(&self) -> Vec<LeakSuspect> {
+        if !self.is_enabled() { return Vec::new() }
+
+        let mut suspects: Vec<LeakSuspect> = self.procs.iter().filter_map(|(pid, trend)| {
+            let first = trend.samples.front()?;
+            let last = trend.samples.back()?;
+            let duration = Duration::from_secs_f64(last.0 - first.0);
+
+            if duration < Self::MIN_DURATION { return None }
+
+            let vram_rate = trend.growth_rate(|s| s.1).unwrap_or(0.0);
+            let gtt_rate = trend.growth_rate(|s| s.2).unwrap_or(0.0);
+
+            if vram_rate < self.threshold && gtt_rate < self.threshold { return None }
+
+            Some(LeakSuspect {
+                pid: *pid,
+                name: trend.name.clone(),
+                vram: last.1,
+                gtt: last.2,
+                vram_rate,
+                gtt_rate,
+                duration,
+            })
+        }).collect();
+
+        suspects.sort_by(|a, b| (b.vram_rate + b.gtt_rate).total_cmp(&(a.vram_rate + a.gtt_rate)));
+
+        suspects
+    }
+}
@@ -9,11 +9,102 @@ use libdrm_amdgpu_sys::AMDGPU::{
 mod app_device_info;
 pub use app_device_info::*;
 
+mod capability;
+pub use capability::{Capability, CapabilityStatus};
+
+mod firmware;
+pub use firmware::{FW_LIST, get_firmware_versions};
+
 pub mod stat;
 
 mod device_path;
 pub use device_path::DevicePath;
 
+mod nickname;
+pub use nickname::{config_dir, DeviceNicknames};
+
+pub mod priv_helper;
+
+mod tuning_profile;
+pub use tuning_profile::{TuningBackup, TuningProfile};
+
+mod watchdog;
+pub use watchdog::{Watchdog, WatchdogAction, WatchdogRule};
+
+mod sysfs_metric;
+pub use sysfs_metric::SysfsMetric;
+
+mod journal;
+pub use journal::{JournalLogger, JournalPriority};
+
+pub mod agentx;
+pub use agentx::AgentX;
+
+mod display_unit;
+pub use display_unit::{NumberFormat, TempUnit};
+
+mod alert;
+pub use alert::{AlertCondition, AlertMetric, AlertOp, ALERT_EXIT_CODE, exit_on_alert};
+
+mod session;
+pub use session::{parse_duration, MinAvgMax, SessionOptions, SessionStats, SessionSummary};
+
+mod idle;
+pub use idle::{is_gpu_active, IdleDetector};
+
+pub mod scheduler;
+
+pub mod uring;
+
+pub mod pmu;
+
+pub mod sqtt;
+
+pub mod umr;
+
+pub mod ring_trace;
+
+pub mod exit_code;
+
+pub mod tls;
+
+mod tiered_history;
+pub use tiered_history::{HistoryPoint, HistoryTier, HistoryTiers, TierHistory, TieredHistory};
+
+mod anomaly;
+pub use anomaly::{Anomaly, AnomalyDetector, AnomalyMetric};
+
+mod leak_detector;
+pub use leak_detector::{LeakDetector, LeakSuspect};
+
+mod sample_bus;
+pub use sample_bus::{Broadcast, DeviceSample, Samples, SAMPLE_BUS};
+
+mod ip_discovery;
+pub use ip_discovery::{is_harvested, IpHwFilter};
+
+mod pci_placement;
+pub use pci_placement::{IommuGroup, IommuGroupDevice, PciPlacement};
+
+mod p2p;
+pub use p2p::{P2pAccess, P2pMatrix, PcieP2pPath};
+
+mod devcoredump;
+pub use devcoredump::{crash_dump_dir, data_dir, CrashDump};
+
+mod mock;
+pub use mock::MockDevice;
+
+mod video_caps;
+pub use video_caps::{codec_level_name, codec_max_refs, codec_profiles};
+
+mod pseudo_locale;
+pub use pseudo_locale::{pseudo_localize, PSEUDO_LOCALE};
+
+/// The first word of the handshake line of `--connect` (`amdgpu_top [<token>]`)
+pub const REMOTE_MAGIC: &str = "amdgpu_top";
+
+#[derive(Clone, Copy, Debug)]
 pub struct Sampling {
     pub count: usize,
     pub delay: Duration,
@@ -0,0 +1,482 @@
+// `MockDevice` serves the metrics of a device from a fixture directory instead of the hardware,
+// to run the frontends and test them on machines without AMD GPUs (`--mock <dir>`).
+// The fixture is laid out like the sysfs directory of the device, with a fake `/proc`:
+//
+//   <dir>/device                "name=<marketing name>", "pci=0000:03:00.0", "apu=0", and optionally
+//                               the device info of the ioctl: "device_id=0x744c", "rev_id=0xc8",
+//                               "family=145", "external_rev=0x1", "cu=96", "se=6", "sa_per_se=2",
+//                               "rb=24", "tcc=16", "max_sclk=2500", "max_mclk=1249" (MHz),
+//                               "vram_type=9", "vram_bit_width=384"
+//   <dir>/gpu_busy_percent, mem_busy_percent
+//   <dir>/mem_info_{vram,vis_vram,gtt}_{total,used}
+//   <dir>/gpu_metrics           the raw binary (optional)
+//   <dir>/hwmon/                temp{1,2,3}_input, power1_average, power1_cap{,_min,_max},
+//                               fan1_input, fan1_max, freq{1,2}_input (Hz), in{0,1}_input (mV)
+//   <dir>/proc/<pid>/comm, stat, fdinfo/<fd>
+//
+// The files are read every update, so a script can rewrite them to replay a recording.
+// The values of the ioctls (SCLK, MCLK, voltages, power) are read from hwmon instead.
+//
+// `--demo` uses the same device without a fixture, the metrics are generated from the number of
+// the updates (not the clock or a random number), so the output is the same on every run.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::{AppDeviceInfo, DevicePath, DeviceSample, PCI, VramUsage};
+use crate::AMDGPU::{
+    drm_amdgpu_heap_info,
+    drm_amdgpu_info_device,
+    drm_amdgpu_memory_info,
+    GpuMetrics,
+    GPU_INFO,
+    HwmonTemp,
+    HwmonTempType,
+    PowerCap,
+    PowerCapType,
+};
+use crate::stat::{parse_hwmon, FdInfoStat, FdInfoUsage, GpuActivity, ProcInfo, ProcUsage, Sensors, SensorsStatus};
+
+const DEFAULT_NAME: &str = "AMD Radeon Graphics (mock)";
+
+/// The synthetic devices of `--demo`, in the format of `<dir>/device`,
+/// with the sizes of VRAM and GTT (MiB) and the power cap (W)
+const DEMO_DEVICES: &[&str] = &[
+    "name=AMD Radeon RX 7900 XTX (demo)
+pci=0000:03:00.0
+apu=0
+device_id=0x744c
+rev_id=0xc8
+family=145
+external_rev=0x1
+cu=96
+se=6
+sa_per_se=2
+rb=24
+tcc=16
+max_sclk=2500
+max_mclk=1249
+vram_type=9
+vram_bit_width=384
+vram=24560
+gtt=31948
+power_cap=327",
+    "name=AMD Radeon 780M (demo)
+pci=0000:c4:00.0
+apu=1
+device_id=0x15bf
+rev_id=0xc1
+family=148
+external_rev=0x1
+cu=12
+se=1
+sa_per_se=2
+rb=4
+tcc=4
+max_sclk=2700
+max_mclk=2800
+vram_type=12
+vram_bit_width=128
+vram=512
+gtt=15872
+power_cap=54",
+];
+
+/// The synthetic processes of `--demo`: (pid, name, GFX weight, VRAM MiB, encode/decode)
+const DEMO_PROCS: &[(i32, &str, f64, u64, bool)] = &[
+    (4242, "demo-game", 0.8, 6144, false),
+    (4310, "gnome-shell", 0.08, 512, false),
+    (5120, "ffmpeg", 0.02, 256, true),
+];
+
+pub struct MockDevice {
+    pub path: PathBuf,
+    pub name: String,
+    pub device_path: DevicePath,
+    pub sensors: Sensors,
+    pub vram_usage: VramUsage,
+    pub activity: GpuActivity,
+    pub metrics: Option<GpuMetrics>,
+    pub fdinfo: FdInfoStat,
+    /// From the optional keys of `<dir>/device`, the others are 0
+    pub ext_info: drm_amdgpu_info_device,
+    proc_index: Vec<ProcInfo>,
+    /// The number of the updates of the demo device, `None` for a fixture
+    demo: Option<u64>,
+}
+
+impl MockDevice {
+    /// `Err` if `<dir>/device` cannot be read
+    pub fn load<P: Into<PathBuf>>(path: P, interval: Duration) -> io::Result<Self> {
+        let path = path.into();
+        let device = fs::read_to_string(path.join("device"))
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.join("device").display())))?;
+        let mut mock = Self::new(path, &device, interval);
+
+        mock.update();
+
+        Ok(mock)
+    }
+
+    /// The synthetic devices of `--demo`, a dGPU and an APU
+    pub fn demo_all(interval: Duration) -> Vec<Self> {
+        DEMO_DEVICES.iter().enumerate().map(|(i, device)| {
+            let mut mock = Self::new(PathBuf::from(format!("demo{i}")), device, interval);
+            let [vram, gtt, cap] = ["vram", "gtt", "power_cap"].map(|key| parse_value(device, key).unwrap_or(0));
+            let is_apu = mock.sensors.is_apu;
+
+            for (heap, mib) in [
+                (&mut mock.vram_usage.0.vram, vram),
+                (&mut mock.vram_usage.0.cpu_accessible_vram, vram.min(256)),
+                (&mut mock.vram_usage.0.gtt, gtt),
+            ] {
+                heap.total_heap_size = mib << 20;
+                heap.usable_heap_size = mib << 20;
+                heap.max_allocation = mib << 20;
+            }
+
+            let cap = cap as u32;
+
+            mock.sensors.power_cap = Some(PowerCap {
+                type_: PowerCapType::PPT,
+                current: cap,
+                default: cap,
+                min: 0,
+                max: cap + cap / 5,
+            });
+            mock.sensors.fan_max_rpm = (!is_apu).then_some(3300);
+            mock.demo = Some(0);
+            mock.update();
+
+            mock
+        }).collect()
+    }
+
+    /// `device`: the contents of `<dir>/device`
+    fn new(path: PathBuf, device: &str, interval: Duration) -> Self {
+        let name = value(device, "name").unwrap_or(DEFAULT_NAME).to_string();
+        let pci = value(device, "pci").and_then(|s| s.parse::<PCI::BUS_INFO>().ok());
+        let is_apu = value(device, "apu").is_some_and(|s| s == "1");
+        // not to open a real device with the same instance
+        let device_path = DevicePath { render: path.join("renderD"), card: path.join("card"), pci };
+
+        let hwmon_path = path.join("hwmon");
+        let sensors = Sensors {
+            fan_input_path: hwmon_path.join("fan1_input"),
+            is_apu,
+            vega10_and_later: true,
+            current_link: None,
+            min_dpm_link: None,
+            max_dpm_link: None,
+            max_gpu_link: None,
+            max_system_link: None,
+            bus_info: pci.unwrap_or(PCI::BUS_INFO { domain: 0, bus: 0, dev: 0, func: 0 }),
+            sclk: None,
+            mclk: None,
+            vddnb: None,
+            vddgfx: None,
+            edge_temp: HwmonTemp::from_hwmon_path(&hwmon_path, HwmonTempType::Edge),
+            junction_temp: HwmonTemp::from_hwmon_path(&hwmon_path, HwmonTempType::Junction),
+            memory_temp: HwmonTemp::from_hwmon_path(&hwmon_path, HwmonTempType::Memory),
+            power: None,
+            power_cap: PowerCap::from_hwmon_path(&hwmon_path),
+            power_limits: None,
+            fan_rpm: None,
+            fan_max_rpm: parse_hwmon(hwmon_path.join("fan1_max")),
+            status: SensorsStatus::default(),
+            hwmon_path,
+        };
+        let fdinfo = FdInfoStat {
+            interval,
+            proc_root: Some(path.join("proc").to_string_lossy().to_string()),
+            ..Default::default()
+        };
+
+        Self {
+            path,
+            name,
+            device_path,
+            sensors,
+            vram_usage: VramUsage(drm_amdgpu_memory_info {
+                vram: empty_heap(),
+                cpu_accessible_vram: empty_heap(),
+                gtt: empty_heap(),
+            }),
+            activity: GpuActivity { gfx: None, umc: None, media: None },
+            metrics: None,
+            fdinfo,
+            ext_info: ext_info(device, is_apu),
+            proc_index: Vec::new(),
+            demo: None,
+        }
+    }
+
+    pub fn update(&mut self) {
+        if let Some(tick) = self.demo.as_mut() {
+            *tick += 1;
+            let tick = *tick;
+
+            self.update_demo(tick);
+            return;
+        }
+
+        let hwmon_path = &self.sensors.hwmon_path;
+        let mhz = |name: &str| parse_hwmon::<u64, _>(hwmon_path.join(name)).map(|hz| (hz / 1_000_000) as u32);
+
+        self.sensors.sclk = mhz("freq1_input");
+        self.sensors.mclk = mhz("freq2_input");
+        self.sensors.vddgfx = parse_hwmon(hwmon_path.join("in0_input"));
+        self.sensors.vddnb = parse_hwmon(hwmon_path.join("in1_input"));
+        self.sensors.power = ["power1_average", "power1_input"].iter()
+            .find_map(|name| parse_hwmon::<u64, _>(hwmon_path.join(name)))
+            .map(|uw| (uw / 1_000_000) as u32);
+
+        let status = &mut self.sensors.status;
+        let now = Instant::now();
+
+        status.sclk.update(self.sensors.sclk.is_some(), now);
+        status.mclk.update(self.sensors.mclk.is_some(), now);
+        status.vddgfx.update(self.sensors.vddgfx.is_some(), now);
+        status.vddnb.update(self.sensors.vddnb.is_some(), now);
+        status.power.update(self.sensors.power.is_some(), now);
+
+        self.sensors.update_hwmon();
+
+        for (heap, name) in [
+            (&mut self.vram_usage.0.vram, "vram"),
+            (&mut self.vram_usage.0.cpu_accessible_vram, "vis_vram"),
+            (&mut self.vram_usage.0.gtt, "gtt"),
+        ] {
+            let [total, used] = ["total", "used"]
+                .map(|s| parse_hwmon::<u64, _>(self.path.join(format!("mem_info_{name}_{s}"))).unwrap_or(0));
+
+            heap.total_heap_size = total;
+            heap.usable_heap_size = total;
+            heap.heap_usage = used;
+            heap.max_allocation = total;
+        }
+
+        self.metrics = GpuMetrics::get_from_sysfs_path(&self.path).ok();
+        self.activity = match &self.metrics {
+            Some(metrics) => GpuActivity::from_gpu_metrics(metrics),
+            None => GpuActivity::get_from_sysfs(&self.path),
+        };
+
+        self.proc_index = proc_index(&self.path.join("proc"));
+        self.fdinfo.get_all_proc_usage(&self.proc_index);
+    }
+
+    /// Plausible values for the load of a game: GFX follows slow waves,
+    /// the clocks, the power and the temperatures follow GFX with a delay.
+    fn update_demo(&mut self, tick: u64) {
+        let t = tick as f64 * self.fdinfo.interval.as_secs_f64().max(0.1);
+        let phase = self.sensors.bus_info.bus as f64;
+        let load = |t: f64| (0.6 + 0.3 * (t / 11.0 + phase).sin() + 0.1 * (t * 1.7).sin()).clamp(0.0, 1.0);
+        let [gfx, delayed] = [load(t), load(t - 6.0)];
+        let max_sclk = (self.ext_info.max_engine_clock / 1000).max(500) as u32;
+        let max_mclk = (self.ext_info.max_memory_clock / 1000) as u32;
+        let cap = self.sensors.power_cap.as_ref().map_or(100.0, |cap| cap.current as f64);
+        let idle_w = if self.sensors.is_apu { 4.0 } else { 18.0 };
+
+        self.activity = GpuActivity {
+            gfx: Some((gfx * 100.0) as u16),
+            umc: Some((gfx * 45.0) as u16),
+            media: Some((12.0 + 8.0 * (t / 5.0).sin()) as u16),
+        };
+        self.sensors.sclk = Some(500 + ((max_sclk - 500) as f64 * gfx) as u32);
+        self.sensors.mclk = Some(if gfx > 0.2 { max_mclk } else { max_mclk / 3 });
+        self.sensors.vddgfx = Some(700 + (400.0 * gfx) as u32);
+        self.sensors.vddnb = self.sensors.is_apu.then_some(850);
+        self.sensors.power = Some((idle_w + (cap - idle_w) * gfx * 0.9) as u32);
+
+        let edge = 38.0 + 40.0 * delayed;
+
+        for (temp, type_, offset, critical) in [
+            (&mut self.sensors.edge_temp, HwmonTempType::Edge, 0.0, 100),
+            (&mut self.sensors.junction_temp, HwmonTempType::Junction, 14.0 * delayed, 110),
+            (&mut self.sensors.memory_temp, HwmonTempType::Memory, 6.0, 108),
+        ] {
+            if self.sensors.is_apu && type_ != HwmonTempType::Edge { continue }
+
+            *temp = Some(HwmonTemp {
+                type_,
+                current: (edge + offset) as i64,
+                critical: Some(critical),
+                critical_hyst: None,
+                emergency: None,
+            });
+        }
+
+        self.sensors.fan_rpm = self.sensors.fan_max_rpm.map(|max| ((max as f64) * (0.2 + 0.5 * delayed)) as u32);
+
+        let s = &mut self.sensors;
+
+        let now = Instant::now();
+
+        for (status, ok) in [
+            (&mut s.status.sclk, s.sclk.is_some()),
+            (&mut s.status.mclk, s.mclk.is_some()),
+            (&mut s.status.vddnb, s.vddnb.is_some()),
+            (&mut s.status.vddgfx, s.vddgfx.is_some()),
+            (&mut s.status.edge_temp, s.edge_temp.is_some()),
+            (&mut s.status.junction_temp, s.junction_temp.is_some()),
+            (&mut s.status.memory_temp, s.memory_temp.is_some()),
+            (&mut s.status.power, s.power.is_some()),
+            (&mut s.status.fan_rpm, s.fan_rpm.is_some()),
+        ] {
+            status.update(ok, now);
+        }
+
+        let mut vram_kib = 0;
+        let mut gtt_kib = 0;
+
+        self.fdinfo.proc_usage = DEMO_PROCS.iter().map(|(pid, name, weight, vram_mib, media)| {
+            let vram_mib = if self.sensors.is_apu { vram_mib / 16 } else { *vram_mib };
+            let vram = (vram_mib << 10) + ((vram_mib << 10) as f64 * 0.1 * (t / 23.0).sin().abs()) as u64;
+            let gtt = vram / 8;
+            let [enc, dec] = if *media { [(25.0 + 15.0 * (t / 5.0).sin()) as i64, 10] } else { [0, 0] };
+
+            vram_kib += vram;
+            gtt_kib += gtt;
+
+            ProcUsage {
+                pid: *pid,
+                name: name.to_string(),
+                usage: FdInfoUsage {
+                    vram_usage: vram,
+                    gtt_usage: gtt,
+                    gfx: (gfx * weight * 100.0) as i64,
+                    dma: (gfx * weight * 5.0) as i64,
+                    enc,
+                    dec,
+                    media: enc.max(dec),
+                    ..Default::default()
+                },
+                cpu_usage: (gfx * weight * 60.0) as i64,
+                media_clients: (*media).into(),
+                ..Default::default()
+            }
+        }).collect();
+
+        let memory = &mut self.vram_usage.0;
+
+        memory.vram.heap_usage = (vram_kib << 10).min(memory.vram.total_heap_size);
+        memory.cpu_accessible_vram.heap_usage = memory.vram.heap_usage.min(memory.cpu_accessible_vram.total_heap_size);
+        memory.gtt.heap_usage = (gtt_kib << 10).min(memory.gtt.total_heap_size);
+    }
+
+    /// For the device info panels, without the ioctls
+    pub fn app_device_info(&self) -> AppDeviceInfo {
+        let ext_info = &self.ext_info;
+
+        AppDeviceInfo {
+            ext_info: *ext_info,
+            memory_info: self.vram_usage.0,
+            resizable_bar: self.vram_usage.0.check_resizable_bar(),
+            min_dpm_link: None,
+            max_dpm_link: None,
+            max_gpu_link: None,
+            max_system_link: None,
+            min_gpu_clk: 0,
+            max_gpu_clk: (ext_info.max_engine_clock / 1000) as u32,
+            min_mem_clk: 0,
+            max_mem_clk: (ext_info.max_memory_clock / 1000) as u32,
+            marketing_name: self.name.clone(),
+            pci_bus: self.sensors.bus_info,
+            placement: Default::default(),
+            edge_temp: self.sensors.edge_temp.clone(),
+            junction_temp: self.sensors.junction_temp.clone(),
+            memory_temp: self.sensors.memory_temp.clone(),
+            power_cap: self.sensors.power_cap.clone(),
+            fan_max_rpm: self.sensors.fan_max_rpm,
+            decode: None,
+            encode: None,
+            vbios: None,
+            actual_num_tcc_blocks: ext_info.get_actual_num_tcc_blocks(),
+            l1_cache_size_kib_per_cu: ext_info.get_l1_cache_size() >> 10,
+            gl1_cache_size_kib_per_sa: ext_info.get_gl1_cache_size() >> 10,
+            total_l2_cache_size_kib: ext_info.calc_l2_cache_size() >> 10,
+            total_l3_cache_size_mib: ext_info.calc_l3_cache_size_mb(),
+            ip_die_entries: Vec::new(),
+            power_profiles: Vec::new(),
+        }
+    }
+
+    pub fn sample(&self) -> DeviceSample {
+        DeviceSample::new(
+            &self.device_path,
+            &self.sensors,
+            &self.vram_usage,
+            &self.activity,
+            self.metrics.as_ref(),
+            &self.fdinfo,
+        )
+    }
+}
+
+/// `<key>=<value>` in `<dir>/device`
+fn value<'a>(device: &'a str, key: &str) -> Option<&'a str> {
+    device.lines().find_map(|l| l.strip_prefix(key)?.strip_prefix('=')).map(str::trim)
+}
+
+/// decimal or hex (`0x`)
+fn parse_value(device: &str, key: &str) -> Option<u64> {
+    let s = value(device, key)?;
+
+    match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+fn ext_info(device: &str, is_apu: bool) -> drm_amdgpu_info_device {
+    // SAFETY: the struct of the ioctl only has the integers and the arrays of them
+    let mut ext_info: drm_amdgpu_info_device = unsafe { std::mem::zeroed() };
+    let get = |key: &str| parse_value(device, key).unwrap_or(0);
+
+    ext_info.device_id = get("device_id") as u32;
+    ext_info.pci_rev = get("rev_id") as u32;
+    ext_info.family = get("family") as u32;
+    ext_info.external_rev = get("external_rev") as u32;
+    ext_info.cu_active_number = get("cu") as u32;
+    // not to divide by 0 in `get_{min,max}_good_cu_per_sa`
+    ext_info.num_shader_engines = get("se").max(1) as u32;
+    ext_info.num_shader_arrays_per_engine = get("sa_per_se").max(1) as u32;
+    ext_info.num_rb_pipes = get("rb") as u32;
+    ext_info.num_tcc_blocks = get("tcc") as u32;
+    ext_info.max_engine_clock = get("max_sclk") * 1000;
+    ext_info.max_memory_clock = get("max_mclk") * 1000;
+    ext_info.vram_type = get("vram_type") as u32;
+    ext_info.vram_bit_width = get("vram_bit_width") as u32;
+    // AMDGPU_IDS_FLAGS_FUSION
+    ext_info.ids_flags = is_apu.into();
+
+    ext_info
+}
+
+fn empty_heap() -> drm_amdgpu_heap_info {
+    drm_amdgpu_heap_info { total_heap_size: 0, usable_heap_size: 0, heap_usage: 0, max_allocation: 0 }
+}
+
+/// `<proc>/<pid>/comm`, `<proc>/<pid>/fdinfo/<fd>`
+fn proc_index(proc_path: &Path) -> Vec<ProcInfo> {
+    let Ok(dir) = fs::read_dir(proc_path) else { return Vec::new() };
+
+    let mut index: Vec<ProcInfo> = dir.filter_map(|entry| {
+        let entry = entry.ok()?;
+        let pid: i32 = entry.file_name().to_str()?.parse().ok()?;
+        let name = fs::read_to_string(entry.path().join("comm")).ok()?.trim_end().to_string();
+        let mut fds: Vec<i32> = fs::read_dir(entry.path().join("fdinfo")).ok()?
+            .filter_map(|fd| fd.ok()?.file_name().to_str()?.parse().ok())
+            .collect();
+
+        fds.sort();
+
+        Some(ProcInfo { pid, name, fds })
+    }).collect();
+
+    index.sort_by_key(|pu| pu.pid);
+
+    index
+}
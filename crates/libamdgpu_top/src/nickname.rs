@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io;
+use std::path::PathBuf;
+use std::fs;
+use crate::PCI;
+
+const FILE_NAME: &str = "nicknames.conf";
+
+/// User-defined friendly names for devices, keyed by PCI bus.
+///
+/// Stored in `$XDG_CONFIG_HOME/amdgpu_top/nicknames.conf` (or `~/.config/amdgpu_top/`),
+/// one `<domain:bus:dev.func> = <nickname>` entry per line.
+#[derive(Clone, Debug, Default)]
+pub struct DeviceNicknames(BTreeMap<String, String>);
+
+impl DeviceNicknames {
+    pub fn load() -> Self {
+        let Some(path) = Self::config_path() else { return Self::default() };
+        let Ok(s) = fs::read_to_string(path) else { return Self::default() };
+
+        Self::parse(&s)
+    }
+
+    pub fn parse(s: &str) -> Self {
+        let map = s.lines().filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { return None }
+
+            let (pci, name) = line.split_once('=')?;
+            let pci = pci.trim().parse::<PCI::BUS_INFO>().ok()?;
+            let name = name.trim();
+
+            (!name.is_empty()).then(|| (pci.to_string(), name.to_string()))
+        }).collect();
+
+        Self(map)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::config_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "config directory not found"))?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, self.to_conf())
+    }
+
+    /// The content of `nicknames.conf`, [`Self::parse`] reads it back.
+    fn to_conf(&self) -> String {
+        let mut buf = String::from("# <domain:bus:dev.func> = <nickname>\n");
+
+        for (pci, name) in &self.0 {
+            let _ = writeln!(buf, "{pci} = {name}");
+        }
+
+        buf
+    }
+
+    pub fn get(&self, pci: &PCI::BUS_INFO) -> Option<&str> {
+        self.0.get(&pci.to_string()).map(|s| s.as_str())
+    }
+
+    /// Removes the nickname if `name` is empty.
+    pub fn set(&mut self, pci: &PCI::BUS_INFO, name: &str) {
+        let name = name.trim();
+
+        if name.is_empty() {
+            self.0.remove(&pci.to_string());
+        } else {
+            self.0.insert(pci.to_string(), name.to_string());
+        }
+    }
+
+    pub fn config_path() -> Option<PathBuf> {
+        Some(config_dir()?.join(FILE_NAME))
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    Some(base.join("amdgpu_top"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pci(s: &str) -> PCI::BUS_INFO {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let nicknames = DeviceNicknames::parse(include_str!("../testdata/nicknames.conf"));
+
+        // the later entry of the same device wins
+        assert_eq!(nicknames.get(&pci("0000:03:00.0")), Some("Main GPU (renamed)"));
+        assert_eq!(nicknames.get(&pci("0000:0b:00.0")), Some("iGPU (Raphael)"));
+        assert_eq!(nicknames.get(&pci("0000:0e:00.0")), Some("name = with = equals"));
+        // commented out, the empty name
+        assert_eq!(nicknames.get(&pci("0000:0c:00.0")), None);
+        assert_eq!(nicknames.get(&pci("0000:0d:00.0")), None);
+        assert_eq!(nicknames.0.len(), 3);
+    }
+
+    #[test]
+    fn odd_input() {
+        assert!(DeviceNicknames::parse("").0.is_empty());
+        assert!(DeviceNicknames::parse("=\n = \n0000:03:00 = Foo\n\u{feff}").0.is_empty());
+        // truncated in the middle of the line
+        assert!(DeviceNicknames::parse("0000:03:00.0 =").0.is_empty());
+        assert!(DeviceNicknames::parse("0000:03:0").0.is_empty());
+
+        let nicknames = DeviceNicknames::parse("0000:03:00.0 = GPU\r\n0000:0b:00.0 = APU");
+        assert_eq!(nicknames.get(&pci("0000:03:00.0")), Some("GPU"));
+        assert_eq!(nicknames.get(&pci("0000:0b:00.0")), Some("APU"));
+    }
+
+    #[test]
+    fn set() {
+        let mut nicknames = DeviceNicknames::default();
+
+        nicknames.set(&pci("0000:03:00.0"), "  GPU ");
+        assert_eq!(nicknames.get(&pci("0000:03:00.0")), Some("GPU"));
+
+        nicknames.set(&pci("0000:03:00.0"), " ");
+        assert_eq!(nicknames.get(&pci("0000:03:00.0")), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let nicknames = DeviceNicknames::parse(include_str!("../testdata/nicknames.conf"));
+        let conf = nicknames.to_conf();
+
+        assert_eq!(
+            conf,
+            "# <domain:bus:dev.func> = <nickname>\n\
+             0000:03:00.0 = Main GPU (renamed)\n\
+             0000:0b:00.0 = iGPU (Raphael)\n\
+             0000:0e:00.0 = name = with = equals\n",
+        );
+        assert_eq!(DeviceNicknames::parse(&conf).0, nicknames.0);
+
+        let mut nicknames = DeviceNicknames::default();
+        nicknames.set(&pci("0000:c1:00.0"), "#1 = eGPU");
+        assert_eq!(DeviceNicknames::parse(&nicknames.to_conf()).0, nicknames.0);
+    }
+}
@@ -0,0 +1,222 @@
+// PCIe peer-to-peer (P2P) access between the AMD GPUs.
+// The driver maps the VRAM of the peer directly only if the whole VRAM is visible through the BAR
+// (large BAR) and the PCIe path between the devices supports P2P DMA.
+// The KFD topology lists the links the driver actually set up (`p2p_links`, and XGMI in `io_links`),
+// `p2p_links` is populated only if the kernel is built with `CONFIG_HSA_AMD_P2P`,
+// otherwise the access is estimated from the BAR size and the PCIe path.
+
+// ## Reference
+//  * <https://docs.kernel.org/driver-api/pci/p2pdma.html>
+//  * Linux Kernel
+//    * `drivers/gpu/drm/amd/amdgpu/amdgpu_device.c`
+//      * `amdgpu_device_is_peer_accessible`
+//    * `drivers/gpu/drm/amd/amdkfd/kfd_topology.c`
+//    * `drivers/pci/p2pdma.c`
+//      * `calc_map_type_and_dist`
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::PCI;
+
+const KFD_TOPOLOGY_NODES: &str = "/sys/class/kfd/kfd/topology/nodes";
+// `CRAT_IOLINK_TYPE_XGMI`
+const IOLINK_TYPE_XGMI: u32 = 16;
+
+/// The closest common upstream of two devices in the PCIe hierarchy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PcieP2pPath {
+    /// Through a PCIe switch, P2P DMA is always supported
+    Switch,
+    /// Through the root complex, supported only on the allowlisted host bridges (e.g. AMD Zen)
+    HostBridge,
+    /// Different host bridges (or PCI domains)
+    CrossHostBridge,
+}
+
+impl PcieP2pPath {
+    fn get(a: &Path, b: &Path) -> Self {
+        // `/sys/devices/pci0000:00/0000:00:01.1/0000:01:00.0/0000:02:00.0/0000:03:00.0`
+        let [a, b]: [Vec<_>; 2] = [a, b].map(|path| path.components().collect());
+        let common = a.iter().zip(b.iter()).take_while(|(a, b)| a == b).count();
+        let host_bridge = a.iter().position(|c| c.as_os_str().to_string_lossy().starts_with("pci"));
+
+        match host_bridge {
+            Some(pos) if common > pos + 1 => Self::Switch,
+            Some(pos) if common > pos => Self::HostBridge,
+            _ => Self::CrossHostBridge,
+        }
+    }
+}
+
+impl fmt::Display for PcieP2pPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::Switch => "PCIe switch",
+            Self::HostBridge => "host bridge",
+            Self::CrossHostBridge => "cross host bridge",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+/// Access from a GPU to the VRAM of the peer GPU
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct P2pAccess {
+    pub path: PcieP2pPath,
+    /// The whole VRAM of the peer is visible through the BAR
+    pub peer_large_bar: bool,
+    /// The link in the KFD topology, `None` if it is not available
+    pub kfd_link: Option<bool>,
+    pub xgmi: bool,
+}
+
+impl P2pAccess {
+    /// The peer VRAM can be mapped directly
+    pub fn is_direct(&self) -> bool {
+        if self.xgmi { return true }
+
+        match self.kfd_link {
+            Some(link) => link,
+            None => self.peer_large_bar && self.path != PcieP2pPath::CrossHostBridge,
+        }
+    }
+
+    /// For the cells of the matrix
+    pub fn label(&self) -> &'static str {
+        if self.xgmi {
+            "XGMI"
+        } else if self.is_direct() {
+            "P2P"
+        } else if !self.peer_large_bar {
+            "no BAR"
+        } else {
+            "-"
+        }
+    }
+}
+
+impl fmt::Display for P2pAccess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+
+        write!(
+            f,
+            "{}, {}, large BAR of peer: {}, KFD link: {}",
+            self.label(),
+            self.path,
+            yes_no(self.peer_large_bar),
+            self.kfd_link.map_or("n/a", yes_no),
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct KfdNode {
+    pci_bus: PCI::BUS_INFO,
+    node: String,
+}
+
+impl KfdNode {
+    fn get_all() -> Vec<Self> {
+        let Ok(dir) = fs::read_dir(KFD_TOPOLOGY_NODES) else { return Vec::new() };
+
+        dir.flatten().filter_map(|entry| {
+            let gpu_id: u32 = fs::read_to_string(entry.path().join("gpu_id")).ok()?.trim().parse().ok()?;
+            // CPU nodes
+            if gpu_id == 0 { return None }
+
+            let props = read_properties(&entry.path().join("properties"));
+            let get = |key: &str| props.iter().find(|(k, _)| k == key).map(|(_, v)| *v);
+            let location_id = get("location_id")?;
+            let domain = get("domain").unwrap_or(0);
+
+            Some(Self {
+                pci_bus: PCI::BUS_INFO {
+                    domain: domain as u16,
+                    bus: (location_id >> 8) as u8,
+                    dev: ((location_id >> 3) & 0x1F) as u8,
+                    func: (location_id & 0x7) as u8,
+                },
+                node: entry.file_name().to_string_lossy().to_string(),
+            })
+        }).collect()
+    }
+
+    /// (`node_to`, type, is `p2p_links`) of `io_links` and `p2p_links`
+    fn links(&self) -> Vec<(String, u32, bool)> {
+        let base = PathBuf::from(KFD_TOPOLOGY_NODES).join(&self.node);
+
+        [("io_links", false), ("p2p_links", true)].iter().flat_map(|(name, p2p)| {
+            fs::read_dir(base.join(name)).into_iter().flatten().flatten().filter_map(|entry| {
+                let props = read_properties(&entry.path().join("properties"));
+                let get = |key: &str| props.iter().find(|(k, _)| k == key).map(|(_, v)| *v);
+
+                Some((get("node_to")?.to_string(), get("type").unwrap_or(0) as u32, *p2p))
+            })
+        }).collect()
+    }
+}
+
+/// `<key> <value>` per line
+fn read_properties(path: &Path) -> Vec<(String, u64)> {
+    let Ok(s) = fs::read_to_string(path) else { return Vec::new() };
+
+    s.lines().filter_map(|line| {
+        let (key, val) = line.split_once(' ')?;
+
+        Some((key.to_string(), val.trim().parse().ok()?))
+    }).collect()
+}
+
+fn is_large_bar(pci_bus: &PCI::BUS_INFO) -> bool {
+    let sysfs_path = pci_bus.get_sysfs_path();
+    let [vis, total] = ["mem_info_vis_vram_total", "mem_info_vram_total"].map(|name| {
+        fs::read_to_string(sysfs_path.join(name)).ok().and_then(|s| s.trim().parse::<u64>().ok())
+    });
+
+    matches!((vis, total), (Some(vis), Some(total)) if total != 0 && vis >= total)
+}
+
+/// P2P access between each pair of the devices
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct P2pMatrix {
+    pub devices: Vec<PCI::BUS_INFO>,
+    /// `access[from][to]`, `None` on the diagonal
+    pub access: Vec<Vec<Option<P2pAccess>>>,
+}
+
+impl P2pMatrix {
+    pub fn new(devices: &[PCI::BUS_INFO]) -> Self {
+        let paths: Vec<PathBuf> = devices.iter()
+            .map(|pci| fs::canonicalize(pci.get_sysfs_path()).unwrap_or_default())
+            .collect();
+        let large_bar: Vec<bool> = devices.iter().map(is_large_bar).collect();
+        let kfd_nodes = KfdNode::get_all();
+        let kfd_links: Vec<Option<Vec<(String, u32, bool)>>> = devices.iter().map(|pci| {
+            kfd_nodes.iter().find(|node| node.pci_bus == *pci).map(|node| node.links())
+        }).collect();
+
+        let access = devices.iter().enumerate().map(|(i, _)| {
+            devices.iter().enumerate().map(|(j, peer)| {
+                if i == j { return None }
+
+                let peer_node = kfd_nodes.iter().find(|node| node.pci_bus == *peer);
+                let link = kfd_links[i].as_ref().zip(peer_node).and_then(|(links, peer_node)| {
+                    links.iter().find(|(node_to, _, _)| *node_to == peer_node.node)
+                });
+                let has_p2p_links = kfd_links[i].as_ref().is_some_and(|links| links.iter().any(|(_, _, p2p)| *p2p));
+
+                Some(P2pAccess {
+                    path: PcieP2pPath::get(&paths[i], &paths[j]),
+                    peer_large_bar: large_bar[j],
+                    kfd_link: if link.is_some() { Some(true) } else { has_p2p_links.then_some(false) },
+                    xgmi: link.is_some_and(|(_, type_, _)| *type_ == IOLINK_TYPE_XGMI),
+                })
+            }).collect()
+        }).collect();
+
+        Self { devices: devices.to_vec(), access }
+    }
+}
@@ -0,0 +1,67 @@
+// ref: https://docs.kernel.org/PCI/sysfs-pci.html
+// ref: https://docs.kernel.org/driver-api/vfio.html#groups-devices-and-iommus
+
+use std::fs;
+use std::path::Path;
+use crate::PCI;
+
+/// A device in the same IOMMU group, which has to be passed through (VFIO) together.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IommuGroupDevice {
+    pub pci: String,
+    pub driver: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IommuGroup {
+    pub id: u32,
+    /// Including the GPU itself
+    pub devices: Vec<IommuGroupDevice>,
+}
+
+impl IommuGroup {
+    fn from_sysfs_path(sysfs_path: &Path) -> Option<Self> {
+        let group_path = fs::canonicalize(sysfs_path.join("iommu_group")).ok()?;
+        let id = group_path.file_name()?.to_str()?.parse().ok()?;
+
+        let mut devices: Vec<IommuGroupDevice> = fs::read_dir(group_path.join("devices")).ok()?
+            .flatten()
+            .map(|entry| IommuGroupDevice {
+                pci: entry.file_name().to_string_lossy().to_string(),
+                driver: fs::read_link(entry.path().join("driver")).ok()
+                    .and_then(|driver| Some(driver.file_name()?.to_string_lossy().to_string())),
+            })
+            .collect();
+
+        devices.sort_by(|a, b| a.pci.cmp(&b.pci));
+
+        Some(Self { id, devices })
+    }
+}
+
+/// NUMA node, local CPUs and IOMMU group of the device, to check the placement of
+/// the processes (HPC) and the passthrough (VFIO).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PciPlacement {
+    /// `None` if the system has only one NUMA node (`numa_node` is -1)
+    pub numa_node: Option<u32>,
+    /// `local_cpulist`, e.g. `0-15,32-47`
+    pub local_cpulist: Option<String>,
+    /// `None` if the IOMMU is disabled
+    pub iommu_group: Option<IommuGroup>,
+}
+
+impl PciPlacement {
+    pub fn get(pci_bus: &PCI::BUS_INFO) -> Self {
+        let sysfs_path = pci_bus.get_sysfs_path();
+        let read = |name: &str| -> Option<String> {
+            Some(fs::read_to_string(sysfs_path.join(name)).ok()?.trim().to_string())
+        };
+
+        Self {
+            numa_node: read("numa_node").and_then(|s| s.parse::<i32>().ok()).and_then(|n| u32::try_from(n).ok()),
+            local_cpulist: read("local_cpulist").filter(|s| !s.is_empty()),
+            iommu_group: IommuGroup::from_sysfs_path(&sysfs_path),
+        }
+    }
+}
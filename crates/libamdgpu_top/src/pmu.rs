@@ -0,0 +1,217 @@
+// The counters of the amdgpu perf PMU (`perf_event_open`), selected with `--source pmu`.
+// The kernel accumulates the counters, so the GRBM registers are not polled 100 times per interval,
+// the usage of the engines is from `gpu_metrics`/`gpu_busy_percent` (`GpuActivity`) instead.
+// The PMU is registered for the data fabric (DF) and XGMI on Vega20, MI100 and later,
+// the events are listed in `/sys/bus/event_source/devices/amdgpu_<card>/events/`.
+// Opening the events requires `kernel.perf_event_paranoid <= 0` or `CAP_PERFMON`.
+
+// ## Reference
+//  * <https://man7.org/linux/man-pages/man2/perf_event_open.2.html>
+//  * <https://docs.kernel.org/admin-guide/perf-security.html>
+//  * Linux Kernel
+//    * `drivers/gpu/drm/amd/amdgpu/amdgpu_pmu.c`
+//    * `include/uapi/linux/perf_event.h`
+
+use std::fs;
+use std::io::{self, Read};
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use crate::DevicePath;
+
+const EVENT_SOURCE: &str = "/sys/bus/event_source/devices";
+/// `PERF_ATTR_SIZE_VER0`
+const PERF_ATTR_SIZE: u32 = 64;
+const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// `struct perf_event_attr` up to `config1` (`PERF_ATTR_SIZE_VER0`)
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    type_: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    /// `disabled`, `inherit`, ... bitfields
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+}
+
+#[derive(Debug)]
+pub struct PmuEvent {
+    /// e.g. `"df_cake0_pcsout_txdata"`, `"xgmi_link0_data_outbound"`
+    pub name: String,
+    file: fs::File,
+    pre: Option<u64>,
+    /// per second
+    pub rate: Option<f64>,
+}
+
+impl PmuEvent {
+    fn read_count(&self) -> io::Result<u64> {
+        let mut buf = [0u8; 8];
+        (&self.file).read_exact(&mut buf)?;
+
+        Ok(u64::from_ne_bytes(buf))
+    }
+}
+
+#[derive(Debug)]
+pub struct AmdgpuPmu {
+    /// e.g. `"amdgpu_0"`
+    pub name: String,
+    pub events: Vec<PmuEvent>,
+    last: Instant,
+}
+
+impl AmdgpuPmu {
+    /// `amdgpu_<card>`, or `amdgpu_df_<card>` (the legacy name, DF only)
+    pub fn pmu_path(instance: u32) -> Option<PathBuf> {
+        [format!("amdgpu_{instance}"), format!("amdgpu_df_{instance}")]
+            .into_iter()
+            .map(|name| Path::new(EVENT_SOURCE).join(name))
+            .find(|path| path.exists())
+    }
+
+    /// `None` without `--source pmu`, or if the PMU is not available (falls back to GRBM with a warning)
+    pub fn open_if_enabled(device_path: &DevicePath) -> Option<Self> {
+        if !is_enabled() { return None }
+
+        let pci = device_path.pci.map(|pci| pci.to_string()).unwrap_or_default();
+        let res = device_path.get_instance_number()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "unknown card number"))
+            .and_then(Self::open);
+
+        res.inspect_err(|err| if !crate::exit_code::is_quiet() {
+            eprintln!("{pci}: \"--source pmu\" is not available, falls back to GRBM: {err}");
+        }).ok()
+    }
+
+    /// Opens all events of the PMU of the device,
+    /// `Err` if the PMU is not registered or no event can be opened.
+    pub fn open(instance: u32) -> io::Result<Self> {
+        let path = Self::pmu_path(instance).ok_or_else(|| io::Error::new(
+            io::ErrorKind::NotFound,
+            "the amdgpu PMU is not registered for this device (Vega20, MI100 and later only)",
+        ))?;
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let type_: u32 = fs::read_to_string(path.join("type"))?.trim().parse()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid PMU type"))?;
+        let formats = read_formats(&path.join("format"));
+        let mut names: Vec<String> = fs::read_dir(path.join("events"))?
+            .filter_map(|entry| Some(entry.ok()?.file_name().to_string_lossy().to_string()))
+            .collect();
+        let mut events = Vec::new();
+        let mut last_err = None;
+
+        names.sort();
+
+        for name in names {
+            let Ok(s) = fs::read_to_string(path.join("events").join(&name)) else { continue };
+            let Some(config) = parse_event_config(&s, &formats) else { continue };
+
+            match open_event(type_, config) {
+                Ok(fd) => events.push(PmuEvent { name, file: fd.into(), pre: None, rate: None }),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        if events.is_empty() {
+            return Err(last_err.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no PMU events")));
+        }
+
+        Ok(Self { name, events, last: Instant::now() })
+    }
+
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let secs = now.duration_since(self.last).as_secs_f64();
+        self.last = now;
+
+        for event in self.events.iter_mut() {
+            let Ok(count) = event.read_count() else { continue };
+
+            event.rate = event.pre
+                .filter(|_| secs > 0.0)
+                .map(|pre| count.saturating_sub(pre) as f64 / secs);
+            event.pre = Some(count);
+        }
+    }
+}
+
+/// `format/<field>`: `"config:0-7"` -> (field, shift, bits)
+fn read_formats(path: &Path) -> Vec<(String, u32, u32)> {
+    let Ok(dir) = fs::read_dir(path) else { return Vec::new() };
+
+    dir.filter_map(|entry| {
+        let entry = entry.ok()?;
+        let s = fs::read_to_string(entry.path()).ok()?;
+        let range = s.trim().strip_prefix("config:")?;
+        let (lo, hi) = range.split_once('-').unwrap_or((range, range));
+        let [lo, hi]: [u32; 2] = [lo.parse().ok()?, hi.parse().ok()?];
+
+        Some((entry.file_name().to_string_lossy().to_string(), lo, hi - lo + 1))
+    }).collect()
+}
+
+/// `"event=0x7,instance=0x46,umask=0x2"`
+fn parse_event_config(s: &str, formats: &[(String, u32, u32)]) -> Option<u64> {
+    let mut config = 0u64;
+
+    for term in s.trim().split(',') {
+        let (key, val) = term.split_once('=')?;
+        let val = match val.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+            None => val.parse().ok()?,
+        };
+        let (_, shift, bits) = formats.iter().find(|(name, _, _)| name == key)?;
+        let mask = if *bits >= 64 { u64::MAX } else { (1 << bits) - 1 };
+
+        config |= (val & mask) << shift;
+    }
+
+    Some(config)
+}
+
+fn open_event(type_: u32, config: u64) -> io::Result<OwnedFd> {
+    let attr = PerfEventAttr { type_, size: PERF_ATTR_SIZE, config, ..Default::default() };
+    // the PMU counts system-wide (`perf_invalid_context`), on any CPU
+    let (pid, cpu) = (-1 as libc::pid_t, 0 as libc::c_int);
+    let fd = unsafe { libc::syscall(
+        libc::SYS_perf_event_open,
+        &attr as *const PerfEventAttr,
+        pid,
+        cpu,
+        -1 as libc::c_int,
+        PERF_FLAG_FD_CLOEXEC,
+    ) };
+
+    if fd < 0 {
+        let err = io::Error::last_os_error();
+
+        return Err(match err.kind() {
+            io::ErrorKind::PermissionDenied => io::Error::new(
+                err.kind(),
+                format!("{err} (requires kernel.perf_event_paranoid <= 0 or CAP_PERFMON)"),
+            ),
+            _ => err,
+        });
+    }
+
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) })
+}
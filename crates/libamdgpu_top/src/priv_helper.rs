@@ -0,0 +1,434 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::PCI;
+
+pub const HELPER_NAME: &str = "amdgpu_top_helper";
+/// The helper is only launched from the installed path, the same as `org.freedesktop.policykit.exec.path`
+/// of the polkit action, not from PATH or next to the executable.
+pub const HELPER_PATH: &str = "/usr/bin/amdgpu_top_helper";
+const PKEXEC_PATH: &str = "/usr/bin/pkexec";
+/// Longer than the values of the control features (e.g. `s 1 2500` of `pp_od_clk_voltage`)
+const MAX_VALUE_LEN: usize = 32;
+
+static HELPER: Mutex<Option<HelperClient>> = Mutex::new(None);
+static USE_PKEXEC: AtomicBool = AtomicBool::new(false);
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Reject all writes (`--read-only`).
+pub fn set_read_only(enable: bool) {
+    READ_ONLY.store(enable, Ordering::Relaxed);
+}
+
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+/// Launch the helper with `pkexec` if it is not installed setuid root.
+/// The polkit authentication agent asks for the password, so this is for the GUI,
+/// not for the terminal frontends.
+/// The action is described in `assets/io.github.umio_yasuno.amdgpu_top.policy`.
+pub fn set_use_pkexec(enable: bool) {
+    USE_PKEXEC.store(enable, Ordering::Relaxed);
+}
+
+/// The sysfs/hwmon files that the privileged helper is allowed to write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SysfsNode {
+    PowerCap,
+    PwmEnable,
+    Pwm,
+    FanCurve,
+    OdClkVoltage,
+    PerfLevel,
+    PowerProfileMode,
+}
+
+impl SysfsNode {
+    const ALL: &'static [Self] = &[
+        Self::PowerCap,
+        Self::PwmEnable,
+        Self::Pwm,
+        Self::FanCurve,
+        Self::OdClkVoltage,
+        Self::PerfLevel,
+        Self::PowerProfileMode,
+    ];
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Self::PowerCap => "power1_cap",
+            Self::PwmEnable => "pwm1_enable",
+            Self::Pwm => "pwm1",
+            Self::FanCurve => "gpu_od/fan_ctrl/fan_curve",
+            Self::OdClkVoltage => "pp_od_clk_voltage",
+            Self::PerfLevel => "power_dpm_force_performance_level",
+            Self::PowerProfileMode => "pp_power_profile_mode",
+        }
+    }
+
+    fn is_hwmon(&self) -> bool {
+        matches!(self, Self::PowerCap | Self::PwmEnable | Self::Pwm)
+    }
+
+    pub fn path(&self, pci_bus: &PCI::BUS_INFO) -> Option<PathBuf> {
+        let base = if self.is_hwmon() { pci_bus.get_hwmon_path()? } else { pci_bus.get_sysfs_path() };
+
+        Some(base.join(self.file_name()))
+    }
+
+    /// Only the values the control features write are accepted.
+    fn is_valid_value(&self, val: &str) -> bool {
+        let is_num = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        let is_nums = |s: &str, n: usize| {
+            let v: Vec<&str> = s.split(' ').collect();
+            v.len() == n && v.iter().all(|s| is_num(s))
+        };
+
+        if val.len() > MAX_VALUE_LEN { return false }
+
+        match self {
+            Self::PowerCap | Self::PwmEnable | Self::Pwm | Self::PowerProfileMode => is_num(val),
+            Self::FanCurve => matches!(val, "c" | "r") || is_nums(val, 3),
+            Self::OdClkVoltage => matches!(val, "c" | "r")
+                || val.strip_prefix("s ").or_else(|| val.strip_prefix("m ")).is_some_and(|v| is_nums(v, 2)),
+            Self::PerfLevel => matches!(
+                val,
+                "auto" | "low" | "high" | "manual" | "profile_standard"
+                | "profile_min_sclk" | "profile_min_mclk" | "profile_peak"
+            ),
+        }
+    }
+}
+
+impl fmt::Display for SysfsNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.file_name())
+    }
+}
+
+impl FromStr for SysfsNode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        Self::ALL.iter().find(|node| node.file_name() == s).copied().ok_or(())
+    }
+}
+
+/// Writes the value to the sysfs/hwmon file of the device.
+/// If the write is denied, the request is forwarded to `amdgpu_top_helper`
+/// (installed setuid root, or launched with `pkexec`), so the frontends can run unprivileged.
+pub fn write_node(pci_bus: &PCI::BUS_INFO, node: SysfsNode, val: &str) -> io::Result<()> {
+    if is_read_only() {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "read-only mode"));
+    }
+
+    let path = node.path(pci_bus)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "hwmon not found"))?;
+
+    match fs::write(&path, val) {
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {},
+        res => return res.map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display()))),
+    }
+
+    let mut helper = HELPER.lock().map_err(|_| io::Error::other("helper lock poisoned"))?;
+
+    if helper.is_none() {
+        *helper = Some(HelperClient::spawn()?);
+    }
+
+    let res = helper.as_mut().unwrap().request(pci_bus, node, val);
+
+    if res.as_ref().is_err_and(|err| err.kind() == io::ErrorKind::BrokenPipe) {
+        *helper = None;
+    }
+
+    res.map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))
+}
+
+/// Control knobs that can be written for the device, detected at startup.
+/// All are `false` in read-only mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ControlCapabilities {
+    pub power_cap: bool,
+    pub fan: bool,
+    pub fan_curve: bool,
+    pub od_clk: bool,
+    pub power_profile: bool,
+}
+
+impl ControlCapabilities {
+    pub fn detect(pci_bus: &PCI::BUS_INFO) -> Self {
+        if is_read_only() { return Self::default() }
+
+        let helper = HelperClient::is_available();
+        let writable = |node: SysfsNode| {
+            let Some(path) = node.path(pci_bus) else { return false };
+
+            // opening for write does not change anything
+            path.exists() && (helper || fs::OpenOptions::new().write(true).open(&path).is_ok())
+        };
+
+        Self {
+            power_cap: writable(SysfsNode::PowerCap),
+            fan: writable(SysfsNode::PwmEnable) && writable(SysfsNode::Pwm),
+            fan_curve: writable(SysfsNode::FanCurve),
+            od_clk: writable(SysfsNode::OdClkVoltage),
+            power_profile: writable(SysfsNode::PerfLevel) && writable(SysfsNode::PowerProfileMode),
+        }
+    }
+
+    pub fn is_writable(&self, node: SysfsNode) -> bool {
+        match node {
+            SysfsNode::PowerCap => self.power_cap,
+            SysfsNode::PwmEnable | SysfsNode::Pwm => self.fan,
+            SysfsNode::FanCurve => self.fan_curve,
+            SysfsNode::OdClkVoltage => self.od_clk,
+            SysfsNode::PerfLevel | SysfsNode::PowerProfileMode => self.power_profile,
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        self.power_cap || self.fan || self.fan_curve || self.od_clk || self.power_profile
+    }
+}
+
+struct HelperClient {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl HelperClient {
+    fn is_setuid_root(path: &Path) -> bool {
+        fs::metadata(path).is_ok_and(|m| m.uid() == 0 && m.mode() & 0o4000 != 0)
+    }
+
+    fn is_available() -> bool {
+        let helper_path = Path::new(HELPER_PATH);
+
+        if !helper_path.exists() { return false }
+
+        Self::is_setuid_root(helper_path)
+            || (USE_PKEXEC.load(Ordering::Relaxed) && Path::new(PKEXEC_PATH).exists())
+    }
+
+    fn spawn() -> io::Result<Self> {
+        let helper_path = Path::new(HELPER_PATH);
+        let mut cmd = if USE_PKEXEC.load(Ordering::Relaxed) && !Self::is_setuid_root(helper_path) {
+            let mut cmd = Command::new(PKEXEC_PATH);
+            cmd.arg(helper_path);
+            cmd
+        } else {
+            Command::new(helper_path)
+        };
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| io::Error::new(err.kind(), format!("Failed to launch {HELPER_NAME}: {err}")))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+
+        Ok(Self { child, stdin, stdout })
+    }
+
+    /// `<pci> <node> <value>` => `ok` or `err <message>`
+    fn request(&mut self, pci_bus: &PCI::BUS_INFO, node: SysfsNode, val: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{pci_bus} {node} {val}")?;
+        self.stdin.flush()?;
+
+        let mut res = String::new();
+
+        if self.stdout.read_line(&mut res)? == 0 {
+            // pkexec: 126 (the authentication dialog was dismissed), 127 (not authorized)
+            let msg = match self.child.wait().ok().and_then(|status| status.code()) {
+                Some(126 | 127) => "not authorized".to_string(),
+                _ => format!("{HELPER_NAME} exited"),
+            };
+
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, msg));
+        }
+
+        match res.trim_end().strip_prefix("err ") {
+            Some(msg) => Err(io::Error::new(io::ErrorKind::PermissionDenied, msg.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Entry point of `amdgpu_top_helper`.
+/// Reads `<pci> <node> <value>` requests from stdin, and writes only to the allowed files of
+/// AMDGPU devices.
+pub fn run_helper() -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+
+        match handle_request(&line) {
+            Ok(_) => writeln!(stdout, "ok")?,
+            Err(err) => writeln!(stdout, "err {err}")?,
+        }
+
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+fn handle_request(line: &str) -> Result<(), String> {
+    let mut split = line.splitn(3, ' ');
+    let [Some(pci), Some(node), Some(val)] = [split.next(), split.next(), split.next()] else {
+        return Err("invalid request".to_string());
+    };
+    let pci_bus = pci.parse::<PCI::BUS_INFO>().map_err(|_| format!("invalid PCI bus: {pci:?}"))?;
+    let node = node.parse::<SysfsNode>().map_err(|_| format!("not allowed: {node:?}"))?;
+
+    if !node.is_valid_value(val) {
+        return Err(format!("invalid value for {node}: {val:?}"));
+    }
+
+    if !is_amdgpu(&pci_bus.get_sysfs_path()).map_err(|err| err.to_string())? {
+        return Err(format!("{pci_bus} is not an AMDGPU device"));
+    }
+
+    let path = node.path(&pci_bus).ok_or_else(|| "hwmon not found".to_string())?;
+
+    fs::write(path, val).map_err(|err| err.to_string())
+}
+
+/// The driver of the PCI device is `amdgpu`, `sysfs_path`: `/sys/bus/pci/devices/<pci>`
+fn is_amdgpu(sysfs_path: &Path) -> io::Result<bool> {
+    let driver = fs::read_link(sysfs_path.join("driver"))?;
+
+    Ok(driver.ends_with("amdgpu"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowlist() {
+        for node in SysfsNode::ALL {
+            assert_eq!(node.file_name().parse::<SysfsNode>(), Ok(*node));
+        }
+
+        for name in [
+            "",
+            "power1_cap ",
+            "POWER1_CAP",
+            "power1_input",
+            "pp_dpm_sclk",
+            "gpu_od/fan_ctrl",
+            "../power1_cap",
+            "hwmon/hwmon0/power1_cap",
+            "gpu_od/fan_ctrl/../../power1_cap",
+            "/sys/class/drm/card0/device/power_dpm_force_performance_level",
+        ] {
+            assert_eq!(name.parse::<SysfsNode>(), Err(()), "{name:?}");
+        }
+    }
+
+    #[test]
+    fn valid_values() {
+        for (node, val) in [
+            (SysfsNode::PowerCap, "150000000"),
+            (SysfsNode::PwmEnable, "1"),
+            (SysfsNode::Pwm, "255"),
+            (SysfsNode::PowerProfileMode, "5"),
+            (SysfsNode::FanCurve, "0 40 30"),
+            (SysfsNode::FanCurve, "c"),
+            (SysfsNode::OdClkVoltage, "s 1 2500"),
+            (SysfsNode::OdClkVoltage, "m 1 1249"),
+            (SysfsNode::OdClkVoltage, "r"),
+            (SysfsNode::PerfLevel, "manual"),
+        ] {
+            assert!(node.is_valid_value(val), "{node} {val:?}");
+        }
+    }
+
+    #[test]
+    fn invalid_values() {
+        let long = "1".repeat(MAX_VALUE_LEN + 1);
+
+        for (node, val) in [
+            (SysfsNode::PowerCap, ""),
+            (SysfsNode::PowerCap, "150000000\n"),
+            (SysfsNode::PowerCap, "150000000\n0"),
+            (SysfsNode::PowerCap, "-1"),
+            (SysfsNode::PowerCap, "1.5"),
+            (SysfsNode::PowerCap, "0x10"),
+            (SysfsNode::PowerCap, " 1"),
+            (SysfsNode::PowerCap, &long),
+            (SysfsNode::Pwm, "auto"),
+            (SysfsNode::PowerProfileMode, "5 1 2 3"),
+            (SysfsNode::FanCurve, "0 40"),
+            (SysfsNode::FanCurve, "0 40 30 1"),
+            (SysfsNode::FanCurve, "0  40 30"),
+            (SysfsNode::FanCurve, "c\n"),
+            (SysfsNode::OdClkVoltage, "s 1"),
+            (SysfsNode::OdClkVoltage, "vc 0 500 700"),
+            (SysfsNode::OdClkVoltage, "s 1 2500\nc"),
+            (SysfsNode::OdClkVoltage, &format!("s 1 {long}")),
+            (SysfsNode::PerfLevel, "manual\n"),
+            (SysfsNode::PerfLevel, "performance"),
+            (SysfsNode::PerfLevel, ""),
+        ] {
+            assert!(!node.is_valid_value(val), "{node} {val:?}");
+        }
+    }
+
+    #[test]
+    fn malformed_requests() {
+        for line in [
+            "",
+            " ",
+            "0000:03:00.0",
+            "0000:03:00.0 power1_cap",
+            "0000:03:00.0 power1_cap ",
+            "power1_cap 150000000",
+            "../../.. power1_cap 150000000",
+            "0000:03:00.0/.. power1_cap 150000000",
+            "0000:03:00.0 ../../../../etc/passwd x",
+            "0000:03:00.0 power1_input 150000000",
+            "0000:03:00.0 power1_cap abc",
+            "0000:03:00.0 power1_cap 150000000 1",
+            "0000:03:00.0 power_dpm_force_performance_level manual\nhigh",
+        ] {
+            assert!(handle_request(line).is_err(), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn amdgpu_driver() {
+        let dir = std::env::temp_dir().join(format!("amdgpu_top-driver-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // not bound to a driver
+        assert!(is_amdgpu(&dir).is_err());
+
+        for (target, expected) in [
+            ("../../../bus/pci/drivers/amdgpu", true),
+            ("../../../bus/pci/drivers/nouveau", false),
+            ("../../../bus/pci/drivers/not_amdgpu", false),
+            ("../../../bus/pci/drivers/amdgpu/..", false),
+        ] {
+            let _ = fs::remove_file(dir.join("driver"));
+            std::os::unix::fs::symlink(target, dir.join("driver")).unwrap();
+
+            assert_eq!(is_amdgpu(&dir).unwrap(), expected, "{target}");
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
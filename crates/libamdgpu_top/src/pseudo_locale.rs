@@ -0,0 +1,32 @@
+use std::borrow::Cow;
+
+/// `--lang pseudo`: the fallback (English) messages are transformed with [`pseudo_localize`],
+/// to find hard-coded (not localized) strings and truncated labels.
+pub const PSEUDO_LOCALE: &str = "pseudo";
+
+/// "Sensors" => "[Šéñšöŕš~~~]"
+/// Accented letters show the localized text, the padding (about 30%) shows the truncation.
+pub fn pseudo_localize(s: &str) -> Cow<'_, str> {
+    if s.trim().is_empty() { return Cow::Borrowed(s) }
+
+    let mut buf = String::with_capacity(s.len() * 2);
+    let mut len: usize = 0;
+
+    buf.push('[');
+
+    for c in s.chars() {
+        buf.push(match c {
+            'a' => 'á', 'e' => 'é', 'i' => 'í', 'o' => 'ö', 'u' => 'ü',
+            'A' => 'Å', 'E' => 'É', 'I' => 'Î', 'O' => 'Ø', 'U' => 'Û',
+            'c' => 'ç', 'n' => 'ñ', 's' => 'š', 'r' => 'ŕ', 'y' => 'ý',
+            'C' => 'Ç', 'N' => 'Ñ', 'S' => 'Š', 'R' => 'Ŕ', 'Y' => 'Ý',
+            c => c,
+        });
+        len += 1;
+    }
+
+    buf.push_str(&"~".repeat(len.div_ceil(3)));
+    buf.push(']');
+
+    Cow::Owned(buf)
+}
@@ -0,0 +1,263 @@
+// `--queue-priority`, the hardware queues (rings) used by each process and the priority of them.
+// The priority of a context (`AMDGPU_CTX_PRIORITY_*`, e.g. `VK_EXT_global_priority` of RADV)
+// is not exposed in fdinfo, but the contexts of the high priority are scheduled to the rings of
+// the high priority, e.g. the async compute queue of gamescope or the VR compositors.
+// The command submissions are traced with the `amdgpu_cs_ioctl` event of tracefs,
+// in the tracefs instance "amdgpu_top", so the other users of tracefs are not affected.
+// Requires root.
+//
+// The high priority rings follow the policy of the driver:
+//  * compute: the first compute ring, if the device has more than one compute ring
+//  * gfx: the rings on the second pipe or queue (`gfx_0.1.0`, `gfx_0.0.1`), if any
+// The software priority (low/normal/high/realtime) of the context is not visible,
+// "high" and "realtime" are scheduled to the same rings.
+
+// ## Reference
+//  * Linux Kernel
+//    * `drivers/gpu/drm/amd/amdgpu/amdgpu_trace.h`
+//      * `amdgpu_cs_ioctl`
+//    * `drivers/gpu/drm/amd/amdgpu/amdgpu_gfx.c`
+//      * `amdgpu_gfx_is_high_priority_compute_queue`
+//      * `amdgpu_gfx_is_high_priority_graphics_queue`
+//    * `drivers/gpu/drm/amd/amdgpu/amdgpu_ctx.c`
+//      * `amdgpu_ctx_get_hw_prio`
+
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+const TRACEFS: [&str; 2] = ["/sys/kernel/tracing", "/sys/kernel/debug/tracing"];
+const DEBUG_DRI: &str = "/sys/kernel/debug/dri";
+const INSTANCE_NAME: &str = "amdgpu_top";
+const EVENT: &str = "events/amdgpu/amdgpu_cs_ioctl/enable";
+/// The processes without submissions for this duration are removed
+pub const WINDOW: Duration = Duration::from_secs(10);
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// `--queue-priority`
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum QueuePriority {
+    #[default]
+    Normal,
+    High,
+}
+
+impl fmt::Display for QueuePriority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::Normal => "normal",
+            Self::High => "high",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+/// The names of the rings of the device, from `amdgpu_ring_<name>` of debugfs
+pub fn list_rings(instance: u32) -> io::Result<Vec<String>> {
+    let mut rings: Vec<String> = fs::read_dir(format!("{DEBUG_DRI}/{instance}"))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str()?.strip_prefix("amdgpu_ring_").map(str::to_string))
+        .collect();
+    rings.sort();
+
+    Ok(rings)
+}
+
+/// `comp_<me>.<pipe>.<queue>`, `gfx_<me>.<pipe>.<queue>`
+pub fn ring_priority(ring: &str, rings: &[String]) -> QueuePriority {
+    let is_high = if ring.starts_with("comp_") {
+        let mut comp = rings.iter().filter(|r| r.starts_with("comp_"));
+
+        comp.next().is_some_and(|first| first == ring) && comp.next().is_some()
+    } else if let Some(pos) = ring.strip_prefix("gfx_") {
+        let mut split = pos.split('.').skip(1);
+        let [pipe, queue] = [split.next(), split.next()];
+
+        pipe.is_some_and(|p| p != "0") || queue.is_some_and(|q| q != "0")
+    } else {
+        false
+    };
+
+    if is_high { QueuePriority::High } else { QueuePriority::Normal }
+}
+
+/// A line of `trace_pipe` with the `record-tgid` option, returns (tgid, comm, ring name).
+/// e.g. `  gamescope-wl-1234  (   1200) [003] ..... 123.456: amdgpu_cs_ioctl: sched_job=1, ..., ring_name=comp_1.0.0, num_ibs=1`
+/// The TID is used without the `record-tgid` option.
+pub fn parse_cs_ioctl(line: &str) -> Option<(i32, &str, &str)> {
+    let (header, args) = line.split_once(": amdgpu_cs_ioctl:")?;
+    let ring = args.split(", ")
+        .find_map(|kv| kv.trim().strip_prefix("ring_name="))?
+        .trim();
+    let (task, rest) = match header.find(" (") {
+        Some(pos) => header.split_at(pos),
+        None => header.split_at(header.find(" [")?),
+    };
+    let (comm, tid) = task.trim().rsplit_once('-')?;
+    let tgid = rest.trim_start()
+        .strip_prefix('(')
+        .and_then(|s| s.split_once(')'))
+        .and_then(|(tgid, _)| tgid.trim().parse().ok());
+    let pid = match tgid {
+        Some(tgid) => tgid,
+        None => tid.parse().ok()?,
+    };
+
+    Some((pid, comm, ring))
+}
+
+#[derive(Clone, Debug)]
+pub struct QueueUsage {
+    pub ring: String,
+    pub priority: QueuePriority,
+    /// The submissions in [`WINDOW`]
+    pub submissions: u64,
+    pub last: Instant,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProcQueueUsage {
+    pub pid: i32,
+    /// The name of the thread of the last submission
+    pub name: String,
+    pub queues: Vec<QueueUsage>,
+}
+
+impl ProcQueueUsage {
+    pub fn has_high_priority(&self) -> bool {
+        self.queues.iter().any(|q| q.priority == QueuePriority::High)
+    }
+}
+
+/// The submissions of all devices, `amdgpu_cs_ioctl` does not have the device.
+#[derive(Clone, Debug, Default)]
+pub struct RingTrace {
+    procs: Vec<ProcQueueUsage>,
+}
+
+static SHARED: OnceLock<Option<Arc<Mutex<RingTrace>>>> = OnceLock::new();
+
+impl RingTrace {
+    pub fn push(&mut self, pid: i32, comm: &str, ring: &str, now: Instant) {
+        let proc = match self.procs.iter().position(|p| p.pid == pid) {
+            Some(i) => &mut self.procs[i],
+            None => {
+                self.procs.push(ProcQueueUsage { pid, name: String::new(), queues: Vec::new() });
+                self.procs.last_mut().unwrap()
+            },
+        };
+
+        comm.clone_into(&mut proc.name);
+
+        match proc.queues.iter_mut().find(|q| q.ring == ring) {
+            Some(q) => {
+                q.submissions = q.submissions.saturating_add(1);
+                q.last = now;
+            },
+            None => proc.queues.push(QueueUsage {
+                ring: ring.to_string(),
+                priority: QueuePriority::Normal,
+                submissions: 1,
+                last: now,
+            }),
+        }
+
+        for proc in self.procs.iter_mut() {
+            proc.queues.retain(|q| now.duration_since(q.last) < WINDOW);
+        }
+
+        self.procs.retain(|p| !p.queues.is_empty());
+    }
+
+    /// The processes with submissions to `rings` (of [`list_rings`]) in [`WINDOW`],
+    /// the processes with the high priority first.
+    pub fn procs(&self, rings: &[String]) -> Vec<ProcQueueUsage> {
+        let now = Instant::now();
+        let mut procs: Vec<ProcQueueUsage> = self.procs.iter()
+            .cloned()
+            .filter_map(|mut p| {
+                p.queues.retain(|q| now.duration_since(q.last) < WINDOW && rings.contains(&q.ring));
+
+                for q in p.queues.iter_mut() {
+                    q.priority = ring_priority(&q.ring, rings);
+                }
+
+                p.queues.sort_by(|a, b| b.priority.cmp(&a.priority).then_with(|| a.ring.cmp(&b.ring)));
+
+                (!p.queues.is_empty()).then_some(p)
+            })
+            .collect();
+        procs.sort_by(|a, b| b.has_high_priority().cmp(&a.has_high_priority()).then_with(|| a.pid.cmp(&b.pid)));
+
+        procs
+    }
+
+    /// Enables `amdgpu_cs_ioctl` in the tracefs instance and reads `trace_pipe` in a thread,
+    /// shared by all devices.
+    /// `None` if not enabled with [`set_enabled`] or tracefs is not available.
+    pub fn shared() -> Option<Arc<Mutex<Self>>> {
+        if !is_enabled() { return None }
+
+        SHARED.get_or_init(|| {
+            Self::spawn_update_thread()
+                .inspect_err(|err| if !crate::exit_code::is_quiet() {
+                    eprintln!("\"--queue-priority\" is not available: {err}");
+                })
+                .ok()
+        }).clone()
+    }
+
+    fn spawn_update_thread() -> io::Result<Arc<Mutex<Self>>> {
+        let dir = setup_instance()?;
+        let reader = BufReader::new(fs::File::open(dir.join("trace_pipe"))?);
+        let arc = Arc::new(Mutex::new(Self::default()));
+        let arc_trace = arc.clone();
+
+        std::thread::spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else { break };
+                let Some((pid, comm, ring)) = parse_cs_ioctl(&line) else { continue };
+                let lock = arc.lock();
+
+                if let Ok(mut trace) = lock {
+                    trace.push(pid, comm, ring, Instant::now());
+                }
+            }
+        });
+
+        Ok(arc_trace)
+    }
+}
+
+/// Creates (or reuses) the tracefs instance, enables `record-tgid` and the event in it
+fn setup_instance() -> io::Result<PathBuf> {
+    let tracefs = TRACEFS.iter()
+        .map(Path::new)
+        .find(|path| path.join("instances").is_dir())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "tracefs is not mounted"))?;
+    let dir = tracefs.join("instances").join(INSTANCE_NAME);
+
+    if let Err(err) = fs::create_dir(&dir) {
+        if err.kind() != io::ErrorKind::AlreadyExists { return Err(err) }
+    }
+
+    // older kernels do not have the option, the TID is used
+    let _ = fs::write(dir.join("options/record-tgid"), "1");
+    fs::write(dir.join(EVENT), "1")?;
+
+    Ok(dir)
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::time::Instant;
+use crate::{DevicePath, VramUsage};
+use crate::AMDGPU::GpuMetrics;
+use crate::stat::{FdInfoStat, GpuActivity, ProcUsage, Sensors};
+
+// The sampling loop of the running mode (TUI, GUI, SMI or JSON) publishes the sample of
+// the devices every interval, and the other frontends (HTTP server, OTLP exporter)
+// subscribe to it, instead of polling the devices again on their own.
+
+/// The samples are dropped for a subscriber which is this many samples behind
+const CAPACITY: usize = 4;
+
+/// All the devices sampled by the mode at once
+pub type Samples = Arc<Vec<DeviceSample>>;
+
+pub static SAMPLE_BUS: Broadcast<Samples> = Broadcast::new();
+
+/// A snapshot of the device after the update of the mode.
+#[derive(Clone)]
+pub struct DeviceSample {
+    pub device_path: DevicePath,
+    pub instance: Option<u32>,
+    pub sensors: Sensors,
+    pub vram_usage: VramUsage,
+    pub activity: GpuActivity,
+    pub metrics: Option<GpuMetrics>,
+    pub proc_usage: Vec<ProcUsage>,
+    /// sec, for the cumulative CPU time of the processes
+    pub cpu_time_map: HashMap<i32, f32>,
+    /// The time of the sample, see also `Sensors::status` for the time of each sensor
+    pub time: Instant,
+}
+
+impl DeviceSample {
+    pub fn new(
+        device_path: &DevicePath,
+        sensors: &Sensors,
+        vram_usage: &VramUsage,
+        activity: &GpuActivity,
+        metrics: Option<&GpuMetrics>,
+        fdinfo: &FdInfoStat,
+    ) -> Self {
+        Self {
+            device_path: device_path.clone(),
+            instance: device_path.get_instance_number(),
+            sensors: sensors.clone(),
+            vram_usage: vram_usage.clone(),
+            activity: activity.clone(),
+            metrics: metrics.cloned(),
+            proc_usage: fdinfo.proc_usage.clone(),
+            cpu_time_map: fdinfo.cpu_time_map.clone(),
+            time: Instant::now(),
+        }
+    }
+}
+
+/// Sends a clone of each message to all the subscribers, without blocking the sender.
+pub struct Broadcast<T> {
+    subscribers: Mutex<Vec<SyncSender<T>>>,
+}
+
+impl<T: Clone> Broadcast<T> {
+    pub const fn new() -> Self {
+        Self { subscribers: Mutex::new(Vec::new()) }
+    }
+
+    pub fn subscribe(&self) -> Receiver<T> {
+        let (tx, rx) = mpsc::sync_channel(CAPACITY);
+
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(tx);
+        }
+
+        rx
+    }
+
+    /// To skip building the message if no one receives it
+    pub fn has_subscribers(&self) -> bool {
+        self.subscribers.lock().is_ok_and(|subscribers| !subscribers.is_empty())
+    }
+
+    pub fn send(&self, msg: T) {
+        let Ok(mut subscribers) = self.subscribers.lock() else { return };
+
+        // remove the closed subscribers, skip the slow ones
+        subscribers.retain(|tx| !matches!(tx.try_send(msg.clone()), Err(TrySendError::Disconnected(_))));
+    }
+}
+
+impl<T: Clone> Default for Broadcast<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,162 @@
+// A single thread which runs the periodic tasks of the background workers
+// (process index, journal logger, watchdog) and the sampling of SMI mode, woken by a timerfd through epoll,
+// instead of a sleep loop in each thread.
+// The tasks due within `SLACK` of the earliest deadline are run in the same wakeup,
+// so the ticks of the tasks at different intervals (and of multiple devices) are coalesced.
+// The timer slack of the thread is also raised, so the kernel can merge the wakeup with others.
+// The tasks must not block, e.g. `pcie_bw` (msleep(1000) in the driver) and `/dev/kmsg` keep their own threads,
+// and the watchdog takes the action on a short-lived thread, since the write may wait for the password of pkexec.
+// The sampling loops of TUI, GUI and JSON mode also keep their own threads,
+// they read the GRBM/GRBM2 registers every 10ms (`Sampling::delay`) for the activity of the blocks,
+// the ticks would be delayed by up to `SLACK` (and run late after the other tasks) on this thread.
+
+// ## Reference
+//  * <https://man7.org/linux/man-pages/man2/timerfd_create.2.html>
+//  * <https://man7.org/linux/man-pages/man2/PR_SET_TIMERSLACK.2const.html>
+
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+const SLACK: Duration = Duration::from_millis(50);
+const TOKEN_TIMER: u64 = 0;
+const TOKEN_EVENT: u64 = 1;
+
+/// Returns `false` to remove the task
+type TaskFn = Box<dyn FnMut() -> bool + Send>;
+
+struct Task {
+    interval: Duration,
+    /// CLOCK_MONOTONIC, ns
+    next: u64,
+    f: TaskFn,
+}
+
+struct Scheduler {
+    new_tasks: Mutex<Vec<Task>>,
+    event_fd: OwnedFd,
+}
+
+static SCHEDULER: OnceLock<Option<Scheduler>> = OnceLock::new();
+
+/// Run the task every `interval` on the scheduler thread, until it returns `false`.
+/// Falls back to a thread with a sleep loop if timerfd/epoll is not available.
+pub fn spawn_periodic<F: FnMut() -> bool + Send + 'static>(interval: Duration, f: F) {
+    let Some(scheduler) = SCHEDULER.get_or_init(|| Scheduler::spawn().ok()) else {
+        let mut f = f;
+
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+
+            if !f() { return }
+        });
+
+        return;
+    };
+
+    let task = Task { interval, next: monotonic_ns() + interval.as_nanos() as u64, f: Box::new(f) };
+
+    if let Ok(mut new_tasks) = scheduler.new_tasks.lock() {
+        new_tasks.push(task);
+    }
+
+    // wake up the scheduler thread to re-arm the timer
+    let one: u64 = 1;
+    unsafe {
+        libc::write(scheduler.event_fd.as_raw_fd(), &one as *const u64 as *const libc::c_void, 8);
+    }
+}
+
+impl Scheduler {
+    fn spawn() -> io::Result<Self> {
+        let epoll_fd = cvt(unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) })?;
+        let timer_fd = cvt(unsafe { libc::timerfd_create(libc::CLOCK_MONOTONIC, libc::TFD_CLOEXEC) })?;
+        let event_fd = cvt(unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) })?;
+        let [epoll_fd, timer_fd, event_fd] = [epoll_fd, timer_fd, event_fd]
+            .map(|fd| unsafe { OwnedFd::from_raw_fd(fd) });
+
+        for (fd, token) in [(&timer_fd, TOKEN_TIMER), (&event_fd, TOKEN_EVENT)] {
+            let mut ev = libc::epoll_event { events: libc::EPOLLIN as u32, u64: token };
+            cvt(unsafe { libc::epoll_ctl(epoll_fd.as_raw_fd(), libc::EPOLL_CTL_ADD, fd.as_raw_fd(), &mut ev) })?;
+        }
+
+        let thread_event_fd = event_fd.try_clone()?;
+
+        std::thread::Builder::new()
+            .name("amdgpu_top-scheduler".to_string())
+            .spawn(move || run(epoll_fd, timer_fd, thread_event_fd))?;
+
+        Ok(Self { new_tasks: Mutex::new(Vec::new()), event_fd })
+    }
+}
+
+fn run(epoll_fd: OwnedFd, timer_fd: OwnedFd, event_fd: OwnedFd) {
+    let mut tasks: Vec<Task> = Vec::new();
+    let mut events = [libc::epoll_event { events: 0, u64: 0 }; 2];
+    let mut buf = [0u8; 8];
+
+    unsafe { libc::prctl(libc::PR_SET_TIMERSLACK, SLACK.as_nanos() as libc::c_ulong) };
+
+    loop {
+        if let Some(Some(scheduler)) = SCHEDULER.get() {
+            if let Ok(mut new_tasks) = scheduler.new_tasks.lock() {
+                tasks.append(&mut new_tasks);
+            }
+        }
+
+        let now = monotonic_ns();
+        let slack = SLACK.as_nanos() as u64;
+
+        for task in tasks.iter_mut() {
+            if task.next > now + slack { continue }
+
+            if !(task.f)() {
+                task.next = u64::MAX;
+                continue;
+            }
+
+            let interval = task.interval.as_nanos() as u64;
+            let next = task.next + interval;
+
+            // do not catch up the missed ticks
+            task.next = if next <= now { now + interval } else { next };
+        }
+
+        tasks.retain(|task| task.next != u64::MAX);
+
+        // disarmed if there are no tasks
+        let next = tasks.iter().map(|task| task.next).min().unwrap_or(0);
+        let spec = libc::itimerspec {
+            it_interval: libc::timespec { tv_sec: 0, tv_nsec: 0 },
+            it_value: libc::timespec {
+                tv_sec: (next / 1_000_000_000) as libc::time_t,
+                tv_nsec: (next % 1_000_000_000) as libc::c_long,
+            },
+        };
+
+        unsafe {
+            libc::timerfd_settime(timer_fd.as_raw_fd(), libc::TFD_TIMER_ABSTIME, &spec, std::ptr::null_mut());
+        }
+
+        let n = unsafe { libc::epoll_wait(epoll_fd.as_raw_fd(), events.as_mut_ptr(), events.len() as i32, -1) };
+
+        for ev in events.iter().take(n.max(0) as usize) {
+            let fd = if ev.u64 == TOKEN_TIMER { &timer_fd } else { &event_fd };
+
+            unsafe { libc::read(fd.as_raw_fd(), buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        }
+    }
+}
+
+fn monotonic_ns() -> u64 {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+fn cvt(ret: i32) -> io::Result<i32> {
+    if ret < 0 { Err(io::Error::last_os_error()) } else { Ok(ret) }
+}
@@ -0,0 +1,230 @@
+use std::fmt;
+use std::fs;
+use std::time::{Duration, Instant};
+use crate::{AMDGPU::HwmonTemp, PCI, VramUsage};
+use crate::stat::{GpuActivity, PCType, PerfCounter, Sensors};
+
+/// `<N>[s|m|h]`, e.g. `60s`, `5m`, `1h` (without suffix: seconds)
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let err = || format!("invalid duration: {s:?} (<N>[s|m|h])");
+    let s = s.trim();
+    let (num, mul) = match s.char_indices().last().ok_or_else(err)? {
+        (i, 's') => (&s[..i], 1),
+        (i, 'm') => (&s[..i], 60),
+        (i, 'h') => (&s[..i], 60 * 60),
+        _ => (s, 1),
+    };
+    let num = num.trim().parse::<u64>().map_err(|_| err())?;
+
+    Ok(Duration::from_secs(num * mul))
+}
+
+/// `--duration`, `-n` and `--summary`, to run the frontends for a fixed window
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SessionOptions {
+    pub duration: Option<Duration>,
+    pub samples: u32, // 0: unlimited
+    /// "-" is stderr
+    pub summary_path: Option<String>,
+}
+
+impl SessionOptions {
+    pub fn is_over(&self, start: Instant, samples: u32) -> bool {
+        self.duration.is_some_and(|d| start.elapsed() >= d)
+            || (self.samples != 0 && samples >= self.samples)
+    }
+
+    pub fn write_summary(&self, summaries: &[SessionSummary]) {
+        let Some(path) = &self.summary_path else { return };
+        // the devices that were not selected in TUI mode
+        let s: String = summaries.iter().filter(|s| s.samples != 0).map(|s| format!("{s}\n")).collect();
+
+        let res = if path == "-" {
+            eprint!("{s}");
+            Ok(())
+        } else {
+            fs::write(path, s)
+        };
+
+        if let Err(err) = res {
+            eprintln!("Failed to write the session summary to {path:?}: {err}");
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MinAvgMax {
+    min: u64,
+    max: u64,
+    sum: u64,
+    count: u64,
+}
+
+impl MinAvgMax {
+    pub fn add(&mut self, val: Option<u64>) {
+        let Some(val) = val else { return };
+
+        if self.count == 0 || val < self.min { self.min = val }
+        if val > self.max { self.max = val }
+
+        self.sum = self.sum.saturating_add(val);
+        self.count += 1;
+    }
+
+    /// `[min, avg, max]`, `None` before the first value
+    pub fn get(&self) -> Option<[u64; 3]> {
+        let avg = self.sum.checked_div(self.count)?;
+
+        Some([self.min, avg, self.max])
+    }
+}
+
+impl fmt::Display for MinAvgMax {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.sum.checked_div(self.count) {
+            Some(avg) => write!(f, "{}/{avg}/{}", self.min, self.max),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+/// Min/avg/max of each sensor and perf counter during the session,
+/// for the hover text (GUI) and the stats panel (TUI)
+#[derive(Clone, Debug, Default)]
+pub struct SessionStats {
+    /// The labels and the order of [`crate::stat::SensorsStatus::iter`], temperatures in C
+    sensors: Vec<(&'static str, MinAvgMax)>,
+    /// %, same order as [`PerfCounter::index`]
+    grbm: Vec<MinAvgMax>,
+    grbm2: Vec<MinAvgMax>,
+}
+
+impl SessionStats {
+    /// The failed reads are skipped, the sensors keep the last value
+    pub fn add_sensors(&mut self, sensors: &Sensors) {
+        let temp = |t: &Option<HwmonTemp>| t.as_ref().map(|t| t.current.max(0) as u64);
+        let values = [
+            sensors.sclk.map(|v| v as u64),
+            sensors.mclk.map(|v| v as u64),
+            sensors.vddnb.map(|v| v as u64),
+            sensors.vddgfx.map(|v| v as u64),
+            temp(&sensors.edge_temp),
+            temp(&sensors.junction_temp),
+            temp(&sensors.memory_temp),
+            sensors.power.map(|v| v as u64),
+            sensors.fan_rpm.map(|v| v as u64),
+        ];
+
+        for ((label, status), val) in sensors.status.iter().zip(values) {
+            if status.is_failed() { continue }
+
+            match self.sensors.iter_mut().find(|(l, _)| *l == label) {
+                Some((_, s)) => s.add(val),
+                None => {
+                    let mut s = MinAvgMax::default();
+                    s.add(val);
+                    self.sensors.push((label, s));
+                },
+            }
+        }
+    }
+
+    pub fn add_perf_counter(&mut self, pc: &PerfCounter) {
+        let stats = match pc.pc_type {
+            PCType::GRBM => &mut self.grbm,
+            PCType::GRBM2 => &mut self.grbm2,
+        };
+
+        stats.resize(pc.index.len(), MinAvgMax::default());
+
+        for (i, s) in stats.iter_mut().enumerate() {
+            s.add(Some(pc.get_usage(i) as u64));
+        }
+    }
+
+    pub fn sensor(&self, label: &str) -> Option<[u64; 3]> {
+        self.sensors.iter().find(|(l, _)| *l == label)?.1.get()
+    }
+
+    /// `(label, [min, avg, max])` of the available sensors
+    pub fn sensors(&self) -> impl Iterator<Item = (&'static str, [u64; 3])> + '_ {
+        self.sensors.iter().filter_map(|(label, s)| Some((*label, s.get()?)))
+    }
+
+    pub fn perf_counter(&self, pc_type: PCType, i: usize) -> Option<[u64; 3]> {
+        let stats = match pc_type {
+            PCType::GRBM => &self.grbm,
+            PCType::GRBM2 => &self.grbm2,
+        };
+
+        stats.get(i)?.get()
+    }
+}
+
+/// Min/avg/max of the main metrics of the device during the session
+#[derive(Clone, Debug)]
+pub struct SessionSummary {
+    pub pci_bus: PCI::BUS_INFO,
+    start: Instant,
+    samples: u32,
+    temp: MinAvgMax, // C
+    power: MinAvgMax, // W
+    sclk: MinAvgMax, // MHz
+    gfx: MinAvgMax, // %
+    vram: MinAvgMax, // MiB
+    pub stats: SessionStats,
+}
+
+impl SessionSummary {
+    pub fn new(pci_bus: PCI::BUS_INFO) -> Self {
+        Self {
+            pci_bus,
+            start: Instant::now(),
+            samples: 0,
+            temp: Default::default(),
+            power: Default::default(),
+            sclk: Default::default(),
+            gfx: Default::default(),
+            vram: Default::default(),
+            stats: Default::default(),
+        }
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    pub fn add(&mut self, sensors: &Sensors, vram: &VramUsage, activity: Option<&GpuActivity>) {
+        let temp = sensors.junction_temp.as_ref().or(sensors.edge_temp.as_ref());
+
+        self.samples += 1;
+        self.temp.add(temp.map(|t| t.current.max(0) as u64));
+        self.power.add(sensors.power.map(|v| v as u64));
+        self.sclk.add(sensors.sclk.map(|v| v as u64));
+        self.gfx.add(activity.and_then(|a| a.gfx).map(|v| v as u64));
+        self.vram.add(Some(vram.0.vram.heap_usage >> 20));
+        self.stats.add_sensors(sensors);
+    }
+
+    pub fn add_perf_counter(&mut self, pc: &PerfCounter) {
+        self.stats.add_perf_counter(pc);
+    }
+}
+
+impl fmt::Display for SessionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{pci}: {secs}s, {samples} samples, min/avg/max: \
+            Temp {temp} C, Power {power} W, SCLK {sclk} MHz, GFX {gfx} %, VRAM {vram} MiB",
+            pci = self.pci_bus,
+            secs = self.start.elapsed().as_secs(),
+            samples = self.samples,
+            temp = self.temp,
+            power = self.power,
+            sclk = self.sclk,
+            gfx = self.gfx,
+            vram = self.vram,
+        )
+    }
+}
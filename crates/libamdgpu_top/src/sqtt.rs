@@ -0,0 +1,117 @@
+// Triggers an SQTT (thread trace) capture of RADV for Radeon GPU Profiler (RGP) from amdgpu_top,
+// and saves the metrics of the last samples alongside, to correlate the capture with the GPU state.
+// The kernel has no interface to start SQTT, RADV captures the next frame if the trigger file exists
+// (and removes it), when the application is started with `MESA_VK_TRACE=rgp MESA_VK_TRACE_TRIGGER=<file>`
+// (`RADV_THREAD_TRACE_TRIGGER` before Mesa 23.1). RADV saves the capture to `/tmp/<app>_<date>.rgp`.
+
+// ## Reference
+//  * <https://docs.mesa3d.org/envvars.html>
+//  * <https://gpuopen.com/rgp/>
+//  * Mesa
+//    * `src/amd/vulkan/radv_sqtt.c`
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::DeviceSample;
+use crate::AMDGPU::HwmonTemp;
+
+/// The environment variables of the trigger file, used if `--sqtt-trigger` is not specified
+pub const TRIGGER_ENV: &[&str] = &["MESA_VK_TRACE_TRIGGER", "RADV_THREAD_TRACE_TRIGGER"];
+/// The number of the samples saved with the capture
+pub const WINDOW_LEN: usize = 30;
+
+static TRIGGER_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// `--sqtt-trigger <file>`
+pub fn set_trigger_path(path: Option<PathBuf>) {
+    if let Ok(mut trigger_path) = TRIGGER_PATH.lock() {
+        *trigger_path = path;
+    }
+}
+
+/// `--sqtt-trigger`, or [`TRIGGER_ENV`]
+pub fn trigger_path() -> Option<PathBuf> {
+    TRIGGER_PATH.lock().ok().and_then(|path| path.clone()).or_else(|| {
+        TRIGGER_ENV.iter()
+            .find_map(|name| std::env::var_os(name).filter(|v| !v.is_empty()))
+            .map(PathBuf::from)
+    })
+}
+
+#[derive(Clone)]
+pub struct SqttTrigger {
+    /// The trigger file of RADV
+    pub path: PathBuf,
+    window: VecDeque<(SystemTime, DeviceSample)>,
+}
+
+impl SqttTrigger {
+    /// `None` if the trigger file is not configured
+    pub fn new() -> Option<Self> {
+        Some(Self { path: trigger_path()?, window: VecDeque::with_capacity(WINDOW_LEN) })
+    }
+
+    pub fn push(&mut self, sample: DeviceSample) {
+        if self.window.len() >= WINDOW_LEN {
+            self.window.pop_front();
+        }
+
+        self.window.push_back((SystemTime::now(), sample));
+    }
+
+    /// Creates the trigger file, and saves the metrics of the last samples to
+    /// `amdgpu_top_sqtt_<pci>_<UNIX time>.csv` in the directory of the trigger file.
+    /// Returns the path of the metrics.
+    pub fn trigger(&self) -> io::Result<PathBuf> {
+        fs::File::create(&self.path)
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", self.path.display())))?;
+
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        // ':' is not allowed in file names on some filesystems
+        let pci = self.window.back()
+            .and_then(|(_, sample)| sample.device_path.pci)
+            .map(|pci| pci.to_string().replace(':', "-"))
+            .unwrap_or_default();
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        let dst = dir.join(format!("amdgpu_top_sqtt_{pci}_{secs}.csv"));
+
+        fs::write(&dst, self.window_csv())?;
+
+        Ok(dst)
+    }
+
+    fn window_csv(&self) -> String {
+        let mut s = String::from(
+            "time_ms,gfx_%,memory_%,media_%,sclk_mhz,mclk_mhz,power_w,edge_temp_c,junction_temp_c,vram_mib,gtt_mib\n",
+        );
+        let opt = |v: Option<u64>| v.map(|v| v.to_string()).unwrap_or_default();
+        let temp = |t: Option<&HwmonTemp>| t.map(|t| t.current.to_string()).unwrap_or_default();
+
+        for (time, sample) in &self.window {
+            let ms = time.duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+            let memory = &sample.vram_usage.0;
+
+            let _ = writeln!(
+                s,
+                "{ms},{gfx},{umc},{media},{sclk},{mclk},{power},{edge},{junction},{vram},{gtt}",
+                gfx = opt(sample.activity.gfx.map(u64::from)),
+                umc = opt(sample.activity.umc.map(u64::from)),
+                media = opt(sample.activity.media.map(u64::from)),
+                sclk = opt(sample.sensors.sclk.map(u64::from)),
+                mclk = opt(sample.sensors.mclk.map(u64::from)),
+                power = opt(sample.sensors.power.map(u64::from)),
+                edge = temp(sample.sensors.edge_temp.as_ref()),
+                junction = temp(sample.sensors.junction_temp.as_ref()),
+                vram = memory.vram.heap_usage >> 20,
+                gtt = memory.gtt.heap_usage >> 20,
+            );
+        }
+
+        s
+    }
+}
@@ -0,0 +1,259 @@
+// ref: drivers/gpu/drm/amd/amdgpu/amdgpu_gem.c (`amdgpu_debugfs_gem_info_show`)
+// ref: drivers/gpu/drm/amd/amdgpu/amdgpu_object.c (`amdgpu_bo_print_info`)
+// ref: drivers/gpu/drm/amd/amdgpu/amdgpu_vm.c (`amdgpu_debugfs_vm_bo_info`)
+
+// Requires root (debugfs).
+// The buffer objects (BOs) of a process, to find the BOs that keep growing (e.g. a VRAM leak).
+// amdgpu_gem_info lists the BOs of each DRM file by the GEM handle,
+// amdgpu_vm_info has the sum of the BOs of each VM state (e.g. evicted).
+
+use std::io;
+use std::fs;
+
+const BASE: &str = "/sys/kernel/debug/dri";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoDmaBuf {
+    /// The inode of the dma-buf
+    Exported(u64),
+    Imported(u64),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BoInfo {
+    /// GEM handle
+    pub id: u32,
+    /// bytes
+    pub size: u64,
+    /// The current placement, e.g. "VRAM", "VRAM VISIBLE" (Linux v6.10 and later), "GTT", "CPU"
+    pub domain: String,
+    pub pin_count: u32,
+    pub dma_buf: Option<BoDmaBuf>,
+    /// The creation flags, e.g. "CPU_ACCESS_REQUIRED", "NO_CPU_ACCESS", "VRAM_CONTIGUOUS"
+    pub flags: Vec<String>,
+}
+
+impl BoInfo {
+    /// `0x00000001:      2097152 byte VRAM pin count 1 exported as ino:1234 CPU_ACCESS_REQUIRED`
+    pub fn parse(s: &str) -> Option<Self> {
+        let (id, rest) = s.trim().split_once(':')?;
+        let id = u32::from_str_radix(id.trim_start_matches("0x"), 16).ok()?;
+        let mut iter = rest.split_whitespace();
+        let size: u64 = iter.next()?.parse().ok()?;
+
+        if iter.next()? != "byte" { return None }
+
+        let mut bo = Self { id, size, domain: iter.next()?.to_string(), ..Default::default() };
+        let mut iter = iter.peekable();
+
+        if iter.next_if_eq(&"VISIBLE").is_some() {
+            bo.domain.push_str(" VISIBLE");
+        }
+
+        while let Some(word) = iter.next() {
+            match word {
+                "pin" => {
+                    let _count = iter.next();
+                    bo.pin_count = iter.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+                },
+                "exported" | "imported" => {
+                    let _as_from = iter.next();
+                    let ino = iter.next()
+                        .and_then(|ino| ino.strip_prefix("ino:"))
+                        .and_then(|ino| ino.parse().ok())
+                        .unwrap_or(0);
+
+                    bo.dma_buf = Some(if word == "exported" {
+                        BoDmaBuf::Exported(ino)
+                    } else {
+                        BoDmaBuf::Imported(ino)
+                    });
+                },
+                _ => bo.flags.push(word.to_string()),
+            }
+        }
+
+        Some(bo)
+    }
+}
+
+/// The sum of the BOs of a VM state
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VmBoState {
+    /// e.g. "idle", "evicted", "relocated", "moved", "invalidated", "done"
+    pub state: String,
+    /// bytes
+    pub size: u64,
+    pub objs: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProcBoList {
+    pub pid: i32,
+    pub name: String,
+    /// The BOs of all DRM files of the process, sorted by size (descending)
+    pub bos: Vec<BoInfo>,
+    pub vm_states: Vec<VmBoState>,
+}
+
+impl ProcBoList {
+    pub fn get(instance: u32, pid: i32) -> io::Result<Self> {
+        let gem_info = fs::read_to_string(format!("{BASE}/{instance}/amdgpu_gem_info"))?;
+        let mut list = Self::parse_gem_info(&gem_info, pid)
+            .ok_or(io::Error::new(io::ErrorKind::NotFound, format!("no DRM file of the process {pid}")))?;
+
+        // amdgpu_vm_info is available from Linux v6.6
+        if let Ok(vm_info) = fs::read_to_string(format!("{BASE}/{instance}/amdgpu_vm_info")) {
+            list.vm_states = Self::parse_vm_info(&vm_info, pid);
+        }
+
+        Ok(list)
+    }
+
+    /// `pid     1234 command Xorg:`, followed by the BOs of the DRM file
+    pub fn parse_gem_info(s: &str, pid: i32) -> Option<Self> {
+        let mut list: Option<Self> = None;
+        let mut is_target = false;
+
+        for line in s.lines() {
+            if let Some(header) = line.strip_prefix("pid ") {
+                let Some((p, name)) = header.trim_start().split_once(" command ") else {
+                    is_target = false;
+                    continue;
+                };
+                is_target = p.parse::<i32>().ok() == Some(pid);
+
+                if is_target && list.is_none() {
+                    let name = name.strip_suffix(':').unwrap_or(name).to_string();
+                    list = Some(Self { pid, name, ..Default::default() });
+                }
+
+                continue;
+            }
+
+            if !is_target { continue }
+
+            if let (Some(list), Some(bo)) = (list.as_mut(), BoInfo::parse(line)) {
+                list.bos.push(bo);
+            }
+        }
+
+        let mut list = list?;
+        list.bos.sort_by_key(|bo| std::cmp::Reverse(bo.size));
+
+        Some(list)
+    }
+
+    /// `pid:1234\tProcess:Xorg ----------`, followed by `\tTotal idle size:        2097152\tobjs:\t1`
+    pub fn parse_vm_info(s: &str, pid: i32) -> Vec<VmBoState> {
+        let mut states: Vec<VmBoState> = Vec::new();
+        let mut is_target = false;
+
+        for line in s.lines() {
+            if let Some(header) = line.strip_prefix("pid:") {
+                is_target = header.split('\t').next().and_then(|p| p.parse::<i32>().ok()) == Some(pid);
+                continue;
+            }
+
+            if !is_target { continue }
+
+            let Some(total) = line.trim().strip_prefix("Total ") else { continue };
+            let Some((state, rest)) = total.split_once(" size:") else { continue };
+            let mut iter = rest.split_whitespace();
+            let Some(size) = iter.next().and_then(|n| n.parse::<u64>().ok()) else { continue };
+            let objs = iter.nth(1).and_then(|n| n.parse::<u32>().ok()).unwrap_or(0);
+
+            // multiple VMs (DRM files) of the process
+            if let Some(st) = states.iter_mut().find(|st| st.state == state) {
+                st.size += size;
+                st.objs += objs;
+            } else {
+                states.push(VmBoState { state: state.to_string(), size, objs });
+            }
+        }
+
+        states
+    }
+
+    /// (domain, the sum of the size in bytes, the number of the BOs)
+    pub fn domain_totals(&self) -> Vec<(&str, u64, usize)> {
+        let mut totals: Vec<(&str, u64, usize)> = Vec::new();
+
+        for bo in &self.bos {
+            if let Some(t) = totals.iter_mut().find(|t| t.0 == bo.domain) {
+                t.1 += bo.size;
+                t.2 += 1;
+            } else {
+                totals.push((&bo.domain, bo.size, 1));
+            }
+        }
+
+        totals
+    }
+}
+
+/// The BOs of all processes (amdgpu_gem_info), to explain the difference between the heap usage
+/// (includes the BOs of the kernel/driver) and the sum of fdinfo (a shared BO is counted in each process).
+/// A dma-buf is counted once.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BoStat {
+    pub bos: usize,
+    /// bytes
+    pub total: u64,
+    pub vram: u64,
+    /// The resident in CPU-visible VRAM, "VRAM VISIBLE" (Linux v6.10 and later), included in `vram`
+    pub vis_vram: u64,
+    pub gtt: u64,
+    /// The BOs evicted to the system memory, their pages may be swapped out
+    pub cpu: u64,
+    pub pinned: u64,
+    /// dma-buf (exported/imported)
+    pub shared: u64,
+    /// The BOs counted in multiple DRM files (the imports of the same dma-buf)
+    pub duplicated: u64,
+}
+
+impl BoStat {
+    pub fn get(instance: u32) -> io::Result<Self> {
+        let gem_info = fs::read_to_string(format!("{BASE}/{instance}/amdgpu_gem_info"))?;
+
+        Ok(Self::parse(&gem_info))
+    }
+
+    pub fn parse(s: &str) -> Self {
+        let mut stat = Self::default();
+        let mut dma_bufs: Vec<u64> = Vec::new();
+
+        for bo in s.lines().filter_map(BoInfo::parse) {
+            if let Some(BoDmaBuf::Exported(ino) | BoDmaBuf::Imported(ino)) = bo.dma_buf {
+                if ino != 0 && dma_bufs.contains(&ino) {
+                    stat.duplicated += bo.size;
+                    continue;
+                }
+
+                dma_bufs.push(ino);
+                stat.shared += bo.size;
+            }
+
+            stat.bos += 1;
+            stat.total += bo.size;
+
+            if bo.pin_count != 0 {
+                stat.pinned += bo.size;
+            }
+
+            match bo.domain.as_str() {
+                "VRAM" => stat.vram += bo.size,
+                "VRAM VISIBLE" => {
+                    stat.vram += bo.size;
+                    stat.vis_vram += bo.size;
+                },
+                "GTT" => stat.gtt += bo.size,
+                "CPU" => stat.cpu += bo.size,
+                _ => {},
+            }
+        }
+
+        stat
+    }
+}
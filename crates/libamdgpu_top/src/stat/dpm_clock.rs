@@ -0,0 +1,81 @@
+// ref: https://www.kernel.org/doc/html/latest/gpu/amdgpu/thermal.html#pp-dpm-sclk-pp-dpm-mclk-pp-dpm-socclk-pp-dpm-fclk-pp-dpm-dcefclk-pp-dpm-pcie
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// `pp_dpm_pcie` is not included, the PCIe link is read from `{current,max}_link_{speed,width}`.
+const DPM_CLOCK_NAMES: &[&str] = &[
+    "sclk",
+    "mclk",
+    "fclk",
+    "socclk",
+    "dcefclk",
+    "vclk",
+    "dclk",
+    "vclk1",
+    "dclk1",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DpmLevel {
+    pub label: String, // "0", "1", ..., "S" (deep sleep)
+    pub clock: u32, // MHz
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DpmClockTable {
+    pub name: String,
+    path: PathBuf,
+    pub levels: Vec<DpmLevel>,
+    pub current: Option<usize>, // index of `levels`
+}
+
+impl DpmClockTable {
+    pub fn from_sysfs_path<P: AsRef<Path>>(sysfs_path: P, name: &str) -> Option<Self> {
+        let path = sysfs_path.as_ref().join(format!("pp_dpm_{name}"));
+        let mut table = Self {
+            name: name.to_uppercase(),
+            path,
+            levels: Vec::new(),
+            current: None,
+        };
+
+        table.update().then_some(table)
+    }
+
+    /// Returns `false` if the file could not be read or has no levels.
+    pub fn update(&mut self) -> bool {
+        let Ok(s) = fs::read_to_string(&self.path) else { return false };
+
+        self.levels.clear();
+        self.current = None;
+
+        for line in s.lines() {
+            // "1: 1800Mhz *"
+            let Some((label, val)) = line.split_once(':') else { continue };
+            let mut split = val.split_whitespace();
+            let Some(clock) = split.next()
+                .map(|clk| clk.trim_end_matches("Mhz").trim_end_matches("MHz"))
+                .and_then(|clk| clk.parse::<u32>().ok()) else { continue };
+
+            if split.next() == Some("*") {
+                self.current = Some(self.levels.len());
+            }
+
+            self.levels.push(DpmLevel { label: label.trim().to_string(), clock });
+        }
+
+        !self.levels.is_empty()
+    }
+
+    pub fn current_level(&self) -> Option<&DpmLevel> {
+        self.levels.get(self.current?)
+    }
+
+    pub fn get_all_from_sysfs_path<P: AsRef<Path>>(sysfs_path: P) -> Vec<Self> {
+        DPM_CLOCK_NAMES
+            .iter()
+            .filter_map(|name| Self::from_sysfs_path(&sysfs_path, name))
+            .collect()
+    }
+}
@@ -1,9 +1,13 @@
 use std::fs;
+use std::fmt::Write;
 use std::io::Read;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use crate::DevicePath;
+use crate::uring::{self, BatchRead};
+use super::Smoothing;
 
 /// ref: drivers/gpu/drm/amd/amdgpu/amdgpu_fdinfo.c
 
@@ -28,21 +32,60 @@ pub struct FdInfoUsage {
     pub uvd_enc: i64,
     pub vcn_jpeg: i64,
     pub media: i64,
+    /// `drm-resident-<region>`, the memory in the region
+    pub resident: FdInfoMemRegions,
+    /// `drm-purgeable-<region>`, the resident memory that the process marked as purgeable (e.g. a cache),
+    /// the driver can free it under memory pressure without evicting the buffers in use
+    pub purgeable: FdInfoMemRegions,
+    /// `drm-shared-<region>`, the buffers shared with the other clients (dma-buf exported/imported, flink),
+    /// e.g. the buffers of the windows are counted in both the application and the compositor
+    pub shared: FdInfoMemRegions,
+}
+
+/// The memory usage of each region in KiB
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, PartialOrd)]
+pub struct FdInfoMemRegions {
+    pub vram: u64,
+    pub gtt: u64,
+    /// The system memory domain (`AMDGPU_GEM_DOMAIN_CPU`)
+    pub cpu: u64,
+}
+
+impl FdInfoMemRegions {
+    fn add(&mut self, region: &str, kib: u64) {
+        match region {
+            "vram" => self.vram = self.vram.saturating_add(kib),
+            "gtt" => self.gtt = self.gtt.saturating_add(kib),
+            "cpu" => self.cpu = self.cpu.saturating_add(kib),
+            _ => {},
+        }
+    }
+}
+
+impl std::ops::AddAssign for FdInfoMemRegions {
+    fn add_assign(&mut self, other: Self) {
+        self.vram = self.vram.saturating_add(other.vram);
+        self.gtt = self.gtt.saturating_add(other.gtt);
+        self.cpu = self.cpu.saturating_add(other.cpu);
+    }
 }
 
 impl std::ops::AddAssign for FdInfoUsage {
     fn add_assign(&mut self, other: Self) {
-        self.vram_usage += other.vram_usage;
-        self.gtt_usage += other.gtt_usage;
-        self.cpu_accessible_usage += other.cpu_accessible_usage;
-        self.gfx += other.gfx;
-        self.compute += other.compute;
-        self.dma += other.dma;
-        self.dec += other.dec;
-        self.enc += other.enc;
-        self.uvd_enc += other.uvd_enc;
-        self.vcn_jpeg += other.vcn_jpeg;
-        self.media += other.media;
+        self.vram_usage = self.vram_usage.saturating_add(other.vram_usage);
+        self.gtt_usage = self.gtt_usage.saturating_add(other.gtt_usage);
+        self.cpu_accessible_usage = self.cpu_accessible_usage.saturating_add(other.cpu_accessible_usage);
+        self.gfx = self.gfx.saturating_add(other.gfx);
+        self.compute = self.compute.saturating_add(other.compute);
+        self.dma = self.dma.saturating_add(other.dma);
+        self.dec = self.dec.saturating_add(other.dec);
+        self.enc = self.enc.saturating_add(other.enc);
+        self.uvd_enc = self.uvd_enc.saturating_add(other.uvd_enc);
+        self.vcn_jpeg = self.vcn_jpeg.saturating_add(other.vcn_jpeg);
+        self.media = self.media.saturating_add(other.media);
+        self.resident += other.resident;
+        self.purgeable += other.purgeable;
+        self.shared += other.shared;
     }
 }
 
@@ -51,18 +94,82 @@ pub struct ProcUsage {
     pub pid: i32,
     pub name: String,
     pub usage: FdInfoUsage,
+    /// Raw values, engine busy time delta in ns per interval, memory usage in KiB
+    pub delta: FdInfoUsage,
     pub cpu_usage: i64, // %
+    /// The DRM clients that used the media engines in the interval, e.g. the encode/decode sessions
+    pub media_clients: u32,
+}
+
+impl ProcUsage {
+    /// The usage of display servers and compositors includes the work
+    /// they do on behalf of their clients (X11 apps, window composition).
+    pub fn is_display_server(&self) -> bool {
+        is_display_server(&self.name)
+    }
+
+    pub fn is_vr_compositor(&self) -> bool {
+        is_vr_compositor(&self.name)
+    }
+}
+
+// process names (comm), max 15 characters
+const DISPLAY_SERVER_NAMES: &[&str] = &[
+    "Xorg",
+    "X",
+    "Xwayland",
+    "gnome-shell",
+    "mutter",
+    "kwin_wayland",
+    "kwin_x11",
+    "sway",
+    "weston",
+    "Hyprland",
+    "gamescope",
+    "gamescope-wl",
+    "labwc",
+    "wayfire",
+    "river",
+    "niri",
+    "cosmic-comp",
+];
+
+pub fn is_display_server(name: &str) -> bool {
+    DISPLAY_SERVER_NAMES.contains(&name)
+}
+
+// SteamVR, Monado, WiVRn
+const VR_COMPOSITOR_NAMES: &[&str] = &[
+    "vrcompositor",
+    "vrserver",
+    "monado-service",
+    "wivrn-server",
+];
+
+pub fn is_vr_compositor(name: &str) -> bool {
+    VR_COMPOSITOR_NAMES.contains(&name)
 }
 
 #[derive(Clone, Default)]
 pub struct FdInfoStat {
-    pub pid_map: HashMap<i32, FdInfoUsage>,
+    pub client_map: HashMap<usize, FdInfoUsage>, // drm-client-id
+    pub smoothing: Smoothing,
+    pub smoothed_map: HashMap<i32, [f32; 8]>, // pid, engine usage
     pub drm_client_ids: HashSet<usize>,
     pub proc_usage: Vec<ProcUsage>,
     pub interval: Duration,
     pub cpu_time_map: HashMap<i32, f32>, // sec
     pub has_vcn: bool,
     pub has_vcn_unified: bool,
+    /// Reused for each read of `/proc/<pid>/{fdinfo/<fd>,stat}`, to avoid the allocations per sample
+    pub buf: Vec<u8>,
+    pub path_buf: String,
+    /// The names of the previous `proc_usage`, reused for the next
+    pub spare_names: Vec<String>,
+    /// `--io-uring`
+    pub batch: BatchRead,
+    /// The fake `/proc` of `MockDevice`, `None` is `/proc`
+    pub proc_root: Option<String>,
 }
 
 impl FdInfoStat {
@@ -74,30 +181,29 @@ impl FdInfoStat {
         }
     }
 */
-    pub fn get_cpu_usage(&mut self, pid: i32, name: &str) -> f32 {
-        const OFFSET: usize = 3;
-        const HZ: f32 = 100.0;
-        let Ok(s) = std::fs::read_to_string(format!("/proc/{pid}/stat")) else { return 0.0 };
-        // for process names with spaces
-        let len = format!("{pid} ({name}) ").len();
+    pub fn get_cpu_usage(&mut self, pid: i32) -> f32 {
+        self.path_buf.clear();
+        let _ = write!(self.path_buf, "{}/{pid}/stat", self.proc_root.as_deref().unwrap_or("/proc"));
+        self.buf.clear();
 
-        let split: Vec<&str> = if let Some(s) = s.get(len..) {
-            s.split(" ").collect()
-        } else {
-            return 0.0;
-        };
+        let Ok(mut f) = fs::File::open(&self.path_buf) else { return 0.0 };
+        if f.read_to_end(&mut self.buf).is_err() { return 0.0 }
 
-        // ref: https://man7.org/linux/man-pages/man5/proc.5.html
-        let [utime, stime] = [
-            split.get(14-OFFSET),
-            split.get(15-OFFSET),
-        ].map(|t| t.and_then(|tt|
-                tt.parse::<f32>().ok()
-            ).unwrap_or(0.0)
-        );
+        let buf = std::mem::take(&mut self.buf);
+        let cpu_usage = self.calc_cpu_usage(pid, &buf);
+        self.buf = buf;
+
+        cpu_usage
+    }
+
+    /// `stat`: the content of `/proc/<pid>/stat`
+    fn calc_cpu_usage(&mut self, pid: i32, stat: &[u8]) -> f32 {
+        const HZ: f32 = 100.0;
+
+        let Some((utime, stime)) = parse_proc_stat_cpu_ticks(stat) else { return 0.0 };
 
         // ref: https://stackoverflow.com/questions/16726779/how-do-i-get-the-total-cpu-usage-of-an-application-from-proc-pid-stat
-        let total_time = (utime + stime) / HZ; // sec = (tick + tick) / HZ
+        let total_time = utime.saturating_add(stime) as f32 / HZ; // sec = (tick + tick) / HZ
 
         if let Some(pre_cpu_time) = self.cpu_time_map.get_mut(&pid) {
             let tmp = total_time - *pre_cpu_time;
@@ -113,73 +219,177 @@ impl FdInfoStat {
 
     pub fn get_proc_usage(&mut self, proc_info: &ProcInfo) {
         let pid = proc_info.pid;
-        let name = &proc_info.name;
-        let mut stat = FdInfoUsage::default();
-        let mut buf = String::new();
-
+        let mut diff = FdInfoUsage::default();
+        let mut delta = FdInfoUsage::default();
+        let mut media_clients = 0;
+        let mut buf = std::mem::take(&mut self.buf);
+
+        // A process may open the device several times (e.g. Vulkan apps), and an fd may be
+        // duplicated or shared with child processes.
+        // Each DRM client (drm-client-id) is counted only once,
+        // and its usage is calculated from the previous stat of the same client.
         for fd in &proc_info.fds {
             buf.clear();
-            let path = format!("/proc/{pid}/fdinfo/{fd}");
-            let Ok(mut f) = fs::File::open(&path) else { continue };
-            if f.read_to_string(&mut buf).is_err() { continue }
-
-            let mut lines = buf.lines().skip_while(|l| !l.starts_with("drm-client-id"));
-            if let Some(id) = lines.next().and_then(|l| FdInfoUsage::id_parse(l)) {
-                if !self.drm_client_ids.insert(id) { continue }
-            } else {
-                continue;
-            }
+            self.path_buf.clear();
+            let _ = write!(self.path_buf, "{}/{pid}/fdinfo/{fd}", self.proc_root.as_deref().unwrap_or("/proc"));
+            let Ok(mut f) = fs::File::open(&self.path_buf) else { continue };
+            if f.read_to_end(&mut buf).is_err() { continue }
 
-            'fdinfo: for l in lines {
-                if l.starts_with("drm-memory") {
-                    stat.mem_usage_parse(l);
-                    continue 'fdinfo;
-                }
-                if l.starts_with("drm-engine") {
-                    stat.engine_parse(l);
-                    continue 'fdinfo;
-                }
-            }
+            media_clients += self.add_client_usage(&buf, &mut diff, &mut delta) as u32;
         }
 
-        let diff = if let Some(pre_stat) = self.pid_map.get_mut(&pid) {
-            let tmp = stat.calc_usage(pre_stat, &self.interval, self.has_vcn, self.has_vcn_unified);
+        self.buf = buf;
+
+        let cpu_usage = self.get_cpu_usage(pid);
+
+        self.push_proc_usage(proc_info, diff, delta, cpu_usage, media_clients);
+    }
+
+    /// `fdinfo`: the content of `/proc/<pid>/fdinfo/<fd>`
+    /// Returns `true` if the client used the media engines in the interval.
+    fn add_client_usage(&mut self, fdinfo: &[u8], diff: &mut FdInfoUsage, delta: &mut FdInfoUsage) -> bool {
+        let Some((id, stat)) = parse_fdinfo(fdinfo) else { return false };
+        if !self.drm_client_ids.insert(id) { return false }
+
+        if let Some(pre_stat) = self.client_map.get_mut(&id) {
+            let client_delta = stat.calc_delta(pre_stat);
+
+            *diff += stat.calc_usage(pre_stat, &self.interval, self.has_vcn, self.has_vcn_unified);
+            *delta += client_delta;
             *pre_stat = stat;
 
-            tmp
+            client_delta.media != 0
         } else {
-            let [vram_usage, gtt_usage, cpu_accessible_usage] = [
-                stat.vram_usage,
-                stat.gtt_usage,
-                stat.cpu_accessible_usage,
-            ];
-
-            self.pid_map.insert(pid, stat);
-
-            FdInfoUsage {
-                vram_usage,
-                gtt_usage,
-                cpu_accessible_usage,
+            self.client_map.insert(id, stat);
+
+            let mem_usage = FdInfoUsage {
+                vram_usage: stat.vram_usage,
+                gtt_usage: stat.gtt_usage,
+                cpu_accessible_usage: stat.cpu_accessible_usage,
+                resident: stat.resident,
+                purgeable: stat.purgeable,
+                shared: stat.shared,
                 ..Default::default()
-            }
-        };
+            };
+
+            *diff += mem_usage;
+            *delta += mem_usage;
+
+            false
+        }
+    }
 
-        let cpu_usage = self.get_cpu_usage(pid, &name);
+    fn push_proc_usage(
+        &mut self,
+        proc_info: &ProcInfo,
+        diff: FdInfoUsage,
+        delta: FdInfoUsage,
+        cpu_usage: f32,
+        media_clients: u32,
+    ) {
+        let diff = self.smooth_usage(proc_info.pid, diff);
+        let mut proc_name = self.spare_names.pop().unwrap_or_default();
+
+        proc_name.clear();
+        proc_name.push_str(&proc_info.name);
 
         self.proc_usage.push(ProcUsage {
-            pid,
-            name: name.to_string(),
+            pid: proc_info.pid,
+            name: proc_name,
             usage: diff,
+            delta,
             cpu_usage: cpu_usage as i64,
+            media_clients,
         });
     }
 
+    /// Read the fdinfo and stat files of all the processes in one batch (`--io-uring`)
+    fn get_all_proc_usage_batch(&mut self, proc_index: &[ProcInfo]) {
+        let mut batch = std::mem::take(&mut self.batch);
+        let root = self.proc_root.as_deref().unwrap_or("/proc");
+
+        batch.clear();
+
+        for pu in proc_index {
+            for fd in &pu.fds {
+                batch.push_path(format_args!("{root}/{}/fdinfo/{fd}", pu.pid));
+            }
+
+            batch.push_path(format_args!("{root}/{}/stat", pu.pid));
+        }
+
+        batch.read_all();
+
+        let mut i = 0;
+
+        for pu in proc_index {
+            let mut diff = FdInfoUsage::default();
+            let mut delta = FdInfoUsage::default();
+            let mut media_clients = 0;
+
+            for _ in &pu.fds {
+                if let Some(fdinfo) = batch.get(i) {
+                    media_clients += self.add_client_usage(fdinfo, &mut diff, &mut delta) as u32;
+                }
+
+                i += 1;
+            }
+
+            let cpu_usage = batch.get(i).map_or(0.0, |stat| self.calc_cpu_usage(pu.pid, stat));
+            i += 1;
+
+            self.push_proc_usage(pu, diff, delta, cpu_usage, media_clients);
+        }
+
+        self.batch = batch;
+    }
+
     pub fn get_all_proc_usage(&mut self, proc_index: &[ProcInfo]) {
-        self.proc_usage.clear();
+        self.spare_names.extend(self.proc_usage.drain(..).map(|pu| pu.name));
         self.drm_client_ids.clear();
-        for pu in proc_index {
-            self.get_proc_usage(pu);
+
+        if uring::is_enabled() {
+            self.get_all_proc_usage_batch(proc_index);
+        } else {
+            for pu in proc_index {
+                self.get_proc_usage(pu);
+            }
         }
+
+        // remove closed clients
+        self.client_map.retain(|id, _| self.drm_client_ids.contains(id));
+        self.smoothed_map.retain(|pid, _| self.proc_usage.iter().any(|pu| pu.pid == *pid));
+    }
+
+    fn smooth_usage(&mut self, pid: i32, usage: FdInfoUsage) -> FdInfoUsage {
+        let cur = [
+            usage.gfx,
+            usage.compute,
+            usage.dma,
+            usage.dec,
+            usage.enc,
+            usage.uvd_enc,
+            usage.vcn_jpeg,
+            usage.media,
+        ].map(|v| v as f32);
+
+        let smoothed = if let Some(pre) = self.smoothed_map.get(&pid) {
+            let mut tmp = cur;
+
+            for (v, pre) in tmp.iter_mut().zip(pre.iter()) {
+                *v = self.smoothing.apply(*pre, *v, self.interval);
+            }
+
+            tmp
+        } else {
+            cur
+        };
+
+        self.smoothed_map.insert(pid, smoothed);
+
+        let [gfx, compute, dma, dec, enc, uvd_enc, vcn_jpeg, media] = smoothed.map(|v| v.round() as i64);
+
+        FdInfoUsage { gfx, compute, dma, dec, enc, uvd_enc, vcn_jpeg, media, ..usage }
     }
 
     pub fn fold_fdinfo_usage(&self) -> FdInfoUsage {
@@ -250,48 +460,90 @@ impl FdInfoUsage {
         s.get(LEN..)?.parse().ok()
     }
 
+    /// `drm-memory-<region>:\t<n> [KiB|MiB]`
     pub fn mem_usage_parse(&mut self, s: &str) {
-        const PRE: usize = "drm-memory-xxxx:\t".len(); // "vram:" or "gtt: " or "cpu: "
-        const KIB: usize = " KiB".len();
-        let len = s.len();
-
-        const MEM_TYPE: std::ops::Range<usize> = {
-            const PRE_LEN: usize = "drm-memory-".len();
-
-            PRE_LEN..(PRE_LEN+5)
-        };
+        let Some((key, val)) = split_key(s) else { return };
+        let Some(region) = key.strip_prefix("memory-") else { return };
+        let Some(kib) = parse_kib(val) else { return };
 
-        let usage = s.get(PRE..len-KIB).and_then(|s| s.parse().ok()).unwrap_or(0);
+        self.add_mem_usage(region, kib);
+    }
 
-        match &s[MEM_TYPE] {
-            "vram:" => self.vram_usage += usage,
-            "gtt: " => self.gtt_usage += usage,
-            "cpu: " => self.cpu_accessible_usage += usage,
+    fn add_mem_usage(&mut self, region: &str, kib: u64) {
+        match region {
+            "vram" => self.vram_usage = self.vram_usage.saturating_add(kib),
+            "gtt" => self.gtt_usage = self.gtt_usage.saturating_add(kib),
+            "cpu" => self.cpu_accessible_usage = self.cpu_accessible_usage.saturating_add(kib),
             _ => {},
-        };
+        }
     }
 
+    /// The memory usage without the shared buffers, so the buffers are not double counted
+    /// in the sum of the processes.
+    pub fn exclude_shared(&self) -> Self {
+        Self {
+            vram_usage: self.vram_usage.saturating_sub(self.shared.vram),
+            gtt_usage: self.gtt_usage.saturating_sub(self.shared.gtt),
+            cpu_accessible_usage: self.cpu_accessible_usage.saturating_sub(self.shared.cpu),
+            ..*self
+        }
+    }
+
+    /// `drm-engine-<engine>:\t<n> ns`
     pub fn engine_parse(&mut self, s: &str) {
-        const PRE: usize = "drm-engine-".len();
-        const NS: usize = " ns".len();
-        let Some(pos) = s.find('\t') else { return };
+        let Some((key, val)) = split_key(s) else { return };
+        let Some(engine) = key.strip_prefix("engine-") else { return };
+        let Some(ns) = val.split_whitespace().next().and_then(|n| n.parse::<i64>().ok()) else { return };
 
-        let ns: i64 = {
-            let len = s.len();
-            s.get(pos+1..len-NS).and_then(|s| s.parse().ok()).unwrap_or(0)
-        };
-        let Some(s) = s.get(PRE..pos) else { return };
-
-        match s {
-            "gfx:" => self.gfx += ns,
-            "compute:" => self.compute += ns,
-            "dma:" => self.dma += ns,
-            "dec:" => self.dec += ns,
-            "enc:" => self.enc += ns,
-            "enc_1:" => self.uvd_enc += ns,
-            "jpeg:" => self.vcn_jpeg += ns,
-            _ => {},
+        if let Some(e) = self.engine_mut(engine) {
+            *e = e.saturating_add(ns);
+        }
+    }
+
+    fn engine_mut(&mut self, engine: &str) -> Option<&mut i64> {
+        let e = match engine {
+            "gfx" => &mut self.gfx,
+            "compute" => &mut self.compute,
+            "dma" => &mut self.dma,
+            "dec" => &mut self.dec,
+            "enc" => &mut self.enc,
+            "enc_1" => &mut self.uvd_enc,
+            "jpeg" => &mut self.vcn_jpeg,
+            _ => return None,
         };
+
+        Some(e)
+    }
+
+    /// Engine busy time delta in ns, without dividing by the interval.
+    /// `media` is the sum of the media engines.
+    pub fn calc_delta(&self, pre_stat: &Self) -> Self {
+        let [gfx, compute, dma, dec, enc, uvd_enc, vcn_jpeg] = [
+            (pre_stat.gfx, self.gfx),
+            (pre_stat.compute, self.compute),
+            (pre_stat.dma, self.dma),
+            (pre_stat.dec, self.dec),
+            (pre_stat.enc, self.enc),
+            (pre_stat.uvd_enc, self.uvd_enc),
+            (pre_stat.vcn_jpeg, self.vcn_jpeg),
+        ].map(|(pre, cur)| if pre == 0 { 0 } else { cur.saturating_sub(pre).max(0) });
+
+        Self {
+            vram_usage: self.vram_usage,
+            gtt_usage: self.gtt_usage,
+            cpu_accessible_usage: self.cpu_accessible_usage,
+            resident: self.resident,
+            purgeable: self.purgeable,
+            shared: self.shared,
+            gfx,
+            compute,
+            dma,
+            dec,
+            enc,
+            uvd_enc,
+            vcn_jpeg,
+            media: [dec, enc, uvd_enc, vcn_jpeg].into_iter().fold(0, i64::saturating_add),
+        }
     }
 
     pub fn calc_usage(
@@ -312,15 +564,16 @@ impl FdInfoUsage {
                 (pre_stat.vcn_jpeg, self.vcn_jpeg),
             ]
             .map(|(pre, cur)| {
-                let usage = if pre == 0 {
+                let usage: u128 = if pre == 0 {
                     0
                 } else {
                     let tmp = cur.saturating_sub(pre);
 
-                    if tmp.is_negative() { 0 } else { tmp * 100 }
-                } as u128;
+                    if tmp.is_negative() { 0 } else { tmp as u128 * 100 }
+                };
 
-                usage.checked_div(interval.as_nanos()).unwrap_or(0) as i64
+                // bounded, so the sums of the engines and the processes do not overflow
+                usage.checked_div(interval.as_nanos()).map_or(0, |v| v.min(u32::MAX as u128) as i64)
             })
         };
 
@@ -328,18 +581,22 @@ impl FdInfoUsage {
             From VCN4, the encoding queue and decoding queue have been unified.
             The AMDGPU driver handles both decoding and encoding as contexts for the encoding engine.
         */
+        let sum = |v: &[i64]| v.iter().fold(0i64, |acc, v| acc.saturating_add(*v));
         let media = if has_vcn_unified {
-            (vcn_jpeg + enc) / 2
+            sum(&[vcn_jpeg, enc]) / 2
         } else if has_vcn {
-            (dec + vcn_jpeg + enc) / 3
+            sum(&[dec, vcn_jpeg, enc]) / 3
         } else {
-            (dec + vcn_jpeg + enc + uvd_enc) / 4
+            sum(&[dec, vcn_jpeg, enc, uvd_enc]) / 4
         };
 
         Self {
             vram_usage: self.vram_usage,
             gtt_usage: self.gtt_usage,
             cpu_accessible_usage: self.cpu_accessible_usage,
+            resident: self.resident,
+            purgeable: self.purgeable,
+            shared: self.shared,
             gfx,
             compute,
             dma,
@@ -403,6 +660,26 @@ pub fn get_all_processes() -> Vec<i32> {
     }).collect()
 }
 
+pub fn get_proc_name(pid: i32) -> Option<String> {
+    // Maximum 16 characters
+    // https://www.kernel.org/doc/html/latest/filesystems/proc.html#proc-pid-comm-proc-pid-task-tid-comm
+    let mut name = fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    name.pop(); // trim '\n'
+
+    Some(name)
+}
+
+/// Returns `None` if the process does not open the device.
+pub fn get_proc_info(pid: i32, device_path: &DevicePath) -> Option<ProcInfo> {
+    let fds = get_fds(pid, device_path);
+
+    if fds.is_empty() { return None }
+
+    let name = get_proc_name(pid)?;
+
+    Some(ProcInfo { pid, name, fds })
+}
+
 pub fn update_index_by_all_proc(
     vec_info: &mut Vec<ProcInfo>,
     device_path: &DevicePath,
@@ -411,17 +688,9 @@ pub fn update_index_by_all_proc(
     vec_info.clear();
 
     for p in all_proc {
-        let pid = *p;
-        let fds = get_fds(pid, device_path);
-
-        if fds.is_empty() { continue }
-
-        // Maximum 16 characters
-        // https://www.kernel.org/doc/html/latest/filesystems/proc.html#proc-pid-comm-proc-pid-task-tid-comm
-        let Ok(mut name) = fs::read_to_string(format!("/proc/{pid}/comm")) else { continue };
-        name.pop(); // trim '\n'
+        let Some(info) = get_proc_info(*p, device_path) else { continue };
 
-        vec_info.push(ProcInfo { pid, name, fds });
+        vec_info.push(info);
     }
 }
 
@@ -429,15 +698,17 @@ pub fn update_index(vec_info: &mut Vec<ProcInfo>, device_path: &DevicePath) {
     update_index_by_all_proc(vec_info, device_path, &get_all_processes());
 }
 
+/// The scan is skipped while `paused` is set (`IdleDetector`).
 pub fn spawn_update_index_thread(
     t_index: Vec<(DevicePath, Arc<Mutex<Vec<ProcInfo>>>)>,
     interval: u64,
+    paused: Arc<AtomicBool>,
 ) {
     let mut buf_index: Vec<ProcInfo> = Vec::new();
     let interval = Duration::from_secs(interval);
 
-    std::thread::spawn(move || loop {
-        std::thread::sleep(interval);
+    crate::scheduler::spawn_periodic(interval, move || {
+        if paused.load(Ordering::Relaxed) { return true }
 
         let all_proc = get_all_processes();
 
@@ -449,5 +720,397 @@ pub fn spawn_update_index_thread(
                 *index = buf_index.clone();
             }
         }
+
+        true
     });
 }
+
+/// Parse the content of `/proc/<pid>/fdinfo/<fd>`.
+/// Returns the DRM client id (`drm-client-id`) and the stat of the client
+/// (the accumulated busy time in ns, memory in KiB), `None` if the fd is not a DRM client.
+/// The lines that are not valid UTF-8 or not a known key are ignored,
+/// and the values are saturated instead of overflowing.
+pub fn parse_fdinfo(fdinfo: &[u8]) -> Option<(usize, FdInfoUsage)> {
+    let mut lines = fdinfo
+        .split(|b| *b == b'\n')
+        .filter_map(|l| std::str::from_utf8(l).ok())
+        .map(str::trim_end)
+        .skip_while(|l| !l.starts_with("drm-client-id"));
+    let id = lines.next().and_then(FdInfoUsage::id_parse)?;
+    let mut parser = FdInfoParser::default();
+
+    for l in lines {
+        parser.parse_line(l);
+    }
+
+    Some((id, parser.finish()))
+}
+
+/// Parse the content of `/proc/<pid>/stat`, returns (utime, stime) in clock ticks.
+/// The fields are counted after the last `)`, the process name (`comm`) may contain spaces and `)`.
+/// ref: <https://man7.org/linux/man-pages/man5/proc_pid_stat.5.html>
+pub fn parse_proc_stat_cpu_ticks(stat: &[u8]) -> Option<(u64, u64)> {
+    let pos = stat.iter().rposition(|b| *b == b')')?;
+    let s = std::str::from_utf8(&stat[pos+1..]).ok()?;
+    // the fields after `(comm)` start from `state` (3)
+    let mut split = s.split_ascii_whitespace();
+    let utime = split.nth(14-3)?.parse().ok()?;
+    let stime = split.next()?.parse().ok()?;
+
+    Some((utime, stime))
+}
+
+/// `drm-<key>:\t<value>`, returns (`<key>`, `<value>`)
+fn split_key(s: &str) -> Option<(&str, &str)> {
+    let (key, val) = s.split_once(':')?;
+
+    Some((key.strip_prefix("drm-")?, val.trim()))
+}
+
+/// `<n> [KiB|MiB]`, bytes without the unit
+fn parse_kib(val: &str) -> Option<u64> {
+    let mut iter = val.split_whitespace();
+    let n: u64 = iter.next()?.parse().ok()?;
+
+    match iter.next() {
+        None => Some(n >> 10),
+        Some("KiB") => Some(n),
+        Some("MiB") => n.checked_mul(1 << 10),
+        Some("GiB") => n.checked_mul(1 << 20),
+        Some(_) => None,
+    }
+}
+
+/// `<n> [Hz|KHz|MHz]`
+fn parse_hz(val: &str) -> Option<u64> {
+    let mut iter = val.split_whitespace();
+    let n: u64 = iter.next()?.parse().ok()?;
+
+    match iter.next() {
+        None | Some("Hz") => Some(n),
+        Some("KHz" | "kHz") => n.checked_mul(1_000),
+        Some("MHz") => n.checked_mul(1_000_000),
+        Some(_) => None,
+    }
+}
+
+/// The keys of a DRM client, ref: Documentation/gpu/drm-usage-stats.rst
+///
+/// * `drm-engine-<engine>`: busy time in ns
+/// * `drm-cycles-<engine>` and `drm-maxfreq-<engine>`: busy cycles and the max frequency,
+///   converted to ns and preferred over `drm-engine-<engine>` if both are reported
+/// * `drm-engine-capacity-<engine>`: the number of the engines of the same type,
+///   the busy time is divided by it
+/// * `drm-memory-<region>`: the legacy key of amdgpu, the resident memory
+/// * `drm-total-<region>`: used if `drm-memory-<region>` is not reported
+/// * `drm-shared-<region>`
+/// * `drm-resident-<region>`, `drm-purgeable-<region>`: the resident memory is `drm-memory-<region>`
+///   if the former is not reported (the kernel before v6.9)
+#[derive(Debug, Default)]
+struct FdInfoParser {
+    stat: FdInfoUsage,
+    total: FdInfoUsage,
+    has_memory: bool,
+    has_resident: bool,
+    /// (engine, cycles, maxfreq in Hz)
+    cycles: Vec<(String, Option<u64>, Option<u64>)>,
+    /// (engine, capacity)
+    capacity: Vec<(String, u64)>,
+}
+
+impl FdInfoParser {
+    fn parse_line(&mut self, l: &str) {
+        let Some((key, val)) = split_key(l) else { return };
+
+        if let Some(engine) = key.strip_prefix("engine-capacity-") {
+            let Some(cap) = val.parse::<u64>().ok().filter(|cap| *cap > 1) else { return };
+
+            self.capacity.push((engine.to_string(), cap));
+        } else if key.starts_with("engine-") {
+            self.stat.engine_parse(l);
+        } else if let Some(engine) = key.strip_prefix("cycles-") {
+            let cycles = val.split_whitespace().next().and_then(|n| n.parse().ok());
+
+            self.cycles_entry(engine).1 = cycles;
+        } else if let Some(engine) = key.strip_prefix("maxfreq-") {
+            self.cycles_entry(engine).2 = parse_hz(val);
+        } else if let Some(region) = key.strip_prefix("memory-") {
+            let Some(kib) = parse_kib(val) else { return };
+
+            self.has_memory = true;
+            self.stat.add_mem_usage(region, kib);
+        } else if let Some(region) = key.strip_prefix("total-") {
+            let Some(kib) = parse_kib(val) else { return };
+
+            self.total.add_mem_usage(region, kib);
+        } else if let Some(region) = key.strip_prefix("resident-") {
+            let Some(kib) = parse_kib(val) else { return };
+
+            self.has_resident = true;
+            self.stat.resident.add(region, kib);
+        } else if let Some(region) = key.strip_prefix("purgeable-") {
+            let Some(kib) = parse_kib(val) else { return };
+
+            self.stat.purgeable.add(region, kib);
+        } else if let Some(region) = key.strip_prefix("shared-") {
+            let Some(kib) = parse_kib(val) else { return };
+
+            self.stat.shared.add(region, kib);
+        }
+    }
+
+    fn cycles_entry(&mut self, engine: &str) -> &mut (String, Option<u64>, Option<u64>) {
+        let i = self.cycles.iter().position(|(e, _, _)| e == engine).unwrap_or_else(|| {
+            self.cycles.push((engine.to_string(), None, None));
+            self.cycles.len() - 1
+        });
+
+        &mut self.cycles[i]
+    }
+
+    fn finish(mut self) -> FdInfoUsage {
+        for (engine, cycles, maxfreq) in &self.cycles {
+            let (Some(cycles), Some(maxfreq)) = (cycles, maxfreq) else { continue };
+            let Some(ns) = (*cycles as u128 * 1_000_000_000).checked_div(*maxfreq as u128) else { continue };
+
+            if let Some(e) = self.stat.engine_mut(engine) {
+                *e = i64::try_from(ns).unwrap_or(i64::MAX);
+            }
+        }
+
+        for (engine, cap) in &self.capacity {
+            if let Some(e) = self.stat.engine_mut(engine) {
+                *e /= i64::try_from(*cap).unwrap_or(i64::MAX);
+            }
+        }
+
+        if !self.has_memory {
+            self.stat.vram_usage = self.total.vram_usage;
+            self.stat.gtt_usage = self.total.gtt_usage;
+            self.stat.cpu_accessible_usage = self.total.cpu_accessible_usage;
+        } else if !self.has_resident {
+            self.stat.resident = FdInfoMemRegions {
+                vram: self.stat.vram_usage,
+                gtt: self.stat.gtt_usage,
+                cpu: self.stat.cpu_accessible_usage,
+            };
+        }
+
+        self.stat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> FdInfoUsage {
+        parse_fdinfo(s.as_bytes()).unwrap().1
+    }
+
+    #[test]
+    fn amdgpu_legacy_memory() {
+        let (id, stat) = parse_fdinfo(include_bytes!("../../testdata/fdinfo/amdgpu_v6_1.txt")).unwrap();
+
+        assert_eq!(id, 17);
+        assert_eq!((stat.vram_usage, stat.gtt_usage, stat.cpu_accessible_usage), (131072, 6144, 0));
+        // the kernel before v6.9, the resident memory is `drm-memory-<region>`
+        assert_eq!(stat.resident, FdInfoMemRegions { vram: 131072, gtt: 6144, cpu: 0 });
+        assert_eq!(stat.shared, FdInfoMemRegions::default());
+        assert_eq!(
+            [stat.gfx, stat.compute, stat.dma, stat.dec, stat.enc, stat.uvd_enc, stat.vcn_jpeg],
+            [5_000_000_000, 250_000, 1000, 3_000_000, 40_000, 50, 600],
+        );
+    }
+
+    #[test]
+    fn amdgpu_resident_shared() {
+        let (id, stat) = parse_fdinfo(include_bytes!("../../testdata/fdinfo/amdgpu_v6_9.txt")).unwrap();
+
+        assert_eq!(id, 61);
+        // `drm-memory-<region>` is preferred over `drm-total-<region>`
+        assert_eq!((stat.vram_usage, stat.gtt_usage), (262144, 12288));
+        assert_eq!(stat.resident, FdInfoMemRegions { vram: 256 << 10, gtt: 12 << 10, cpu: 0 });
+        assert_eq!(stat.purgeable, FdInfoMemRegions { vram: 0, gtt: 2 << 10, cpu: 0 });
+        assert_eq!(stat.shared, FdInfoMemRegions { vram: 32 << 10, gtt: 4 << 10, cpu: 0 });
+        assert_eq!(stat.exclude_shared().vram_usage, 262144 - (32 << 10));
+        assert_eq!((stat.gfx, stat.dma), (1_200_000_000, 20_000_000));
+    }
+
+    #[test]
+    fn cycles_maxfreq_capacity_total() {
+        let stat = parse(include_str!("../../testdata/fdinfo/cycles_capacity.txt"));
+
+        // 1.5G cycles at 3 GHz, preferred over `drm-engine-gfx`
+        assert_eq!(stat.gfx, 500_000_000);
+        // 250k cycles at 500 MHz
+        assert_eq!(stat.dec, 500_000);
+        // the cycles without `drm-maxfreq-<engine>`
+        assert_eq!(stat.enc, 0);
+        // divided by `drm-engine-capacity-compute`, the capacity of 1 is ignored
+        assert_eq!(stat.compute, 2_000_000);
+        assert_eq!(stat.dma, 30);
+        // `drm-total-<region>` without `drm-memory-<region>`, bytes without the unit
+        assert_eq!((stat.vram_usage, stat.gtt_usage), (4, 2048));
+    }
+
+    #[test]
+    fn cycles_odd_input() {
+        let base = "drm-client-id:\t1\ndrm-engine-gfx:\t100 ns\n";
+
+        // the zero max frequency, the busy time of `drm-engine-gfx` is kept
+        assert_eq!(parse(&format!("{base}drm-cycles-gfx:\t10\ndrm-maxfreq-gfx:\t0 Hz\n")).gfx, 100);
+        // the unknown unit of the frequency
+        assert_eq!(parse(&format!("{base}drm-cycles-gfx:\t10\ndrm-maxfreq-gfx:\t1 THz\n")).gfx, 100);
+        // maxfreq before cycles, kHz
+        assert_eq!(parse(&format!("{base}drm-maxfreq-gfx:\t1 kHz\ndrm-cycles-gfx:\t3\n")).gfx, 3_000_000);
+        // saturated
+        assert_eq!(parse(&format!("{base}drm-cycles-gfx:\t{}\ndrm-maxfreq-gfx:\t1\n", u64::MAX)).gfx, i64::MAX);
+        // the invalid and the zero capacity
+        assert_eq!(parse(&format!("{base}drm-engine-capacity-gfx:\tx\ndrm-engine-capacity-gfx:\t0\n")).gfx, 100);
+        // the cycles and the capacity of the unknown engines
+        assert_eq!(
+            parse(&format!("{base}drm-cycles-vpe:\t10\ndrm-maxfreq-vpe:\t1 Hz\ndrm-engine-capacity-vpe:\t2\n")),
+            parse(base),
+        );
+    }
+
+    #[test]
+    fn mem_units() {
+        assert_eq!(parse_kib("2048"), Some(2));
+        assert_eq!(parse_kib("1023"), Some(0));
+        assert_eq!(parse_kib("3 KiB"), Some(3));
+        assert_eq!(parse_kib("3 MiB"), Some(3 << 10));
+        assert_eq!(parse_kib("3 GiB"), Some(3 << 20));
+        assert_eq!(parse_kib("3 TiB"), None);
+        assert_eq!(parse_kib(&format!("{} GiB", u64::MAX)), None);
+        assert_eq!(parse_kib("-1 KiB"), None);
+        assert_eq!(parse_kib(""), None);
+
+        assert_eq!(parse_hz("5"), Some(5));
+        assert_eq!(parse_hz("5 Hz"), Some(5));
+        assert_eq!(parse_hz("5 KHz"), Some(5_000));
+        assert_eq!(parse_hz("5 MHz"), Some(5_000_000));
+        assert_eq!(parse_hz("5 GHz"), None);
+    }
+
+    #[test]
+    fn truncated() {
+        let s = include_str!("../../testdata/fdinfo/amdgpu_v6_9.txt");
+        let full = parse(s);
+
+        for len in 0..s.len() {
+            let Some((_, stat)) = parse_fdinfo(&s.as_bytes()[..len]) else { continue };
+
+            // `drm-total-vram` (260 MiB) if cut before `drm-memory-vram`
+            assert!(stat.vram_usage <= 260 << 10);
+            assert!(stat.resident.vram <= full.resident.vram);
+            assert!(stat.gfx <= full.gfx);
+        }
+
+        // not a DRM client
+        assert_eq!(parse_fdinfo(b"pos:\t0\nflags:\t02100002\n"), None);
+        assert_eq!(parse_fdinfo(b"drm-client-id:\t"), None);
+    }
+}
+
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const KEYS: &[&str] = &[
+        "drm-client-id", "drm-engine-gfx", "drm-engine-compute", "drm-engine-dec", "drm-engine-enc_1",
+        "drm-engine-capacity-gfx", "drm-cycles-gfx", "drm-maxfreq-gfx", "drm-memory-vram", "drm-total-gtt",
+        "drm-resident-vram", "drm-purgeable-gtt", "drm-shared-vram", "drm-foo",
+    ];
+    const UNITS: &[&str] = &["", " ns", " KiB", " MiB", " GiB", " Hz", " kHz", " MHz", " ?"];
+
+    /// The lines of the known keys with random values and units
+    fn fdinfo_lines() -> impl Strategy<Value = String> {
+        prop::collection::vec(
+            (prop::sample::select(KEYS), any::<u64>(), prop::sample::select(UNITS)),
+            0..24,
+        ).prop_map(|lines| {
+            lines.iter().map(|(key, v, unit)| format!("{key}:\t{v}{unit}\n")).collect()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn fdinfo_no_panic(data in prop::collection::vec(any::<u8>(), 0..512)) {
+            let _ = parse_fdinfo(&data);
+        }
+
+        #[test]
+        fn fdinfo_usage(lines in fdinfo_lines(), id in any::<usize>()) {
+            let s = format!("pos:\t0\ndrm-client-id:\t{id}\n{lines}");
+            let (parsed_id, stat) = parse_fdinfo(s.as_bytes()).unwrap();
+
+            prop_assert_eq!(parsed_id, id);
+
+            // the same stat twice, no busy time in the interval
+            let delta = stat.calc_delta(&stat);
+            let usage = stat.calc_usage(&stat, &Duration::from_secs(1), false, false);
+
+            for v in [delta.gfx, delta.compute, delta.dma, delta.dec, delta.enc, delta.uvd_enc, delta.vcn_jpeg, delta.media] {
+                prop_assert_eq!(v, 0);
+            }
+            for v in [usage.gfx, usage.compute, usage.dma, usage.dec, usage.enc, usage.uvd_enc, usage.vcn_jpeg, usage.media] {
+                prop_assert_eq!(v, 0);
+            }
+
+            // from the first sample, the usage is bounded and not negative
+            let pre = FdInfoUsage { gfx: 1, compute: 1, dma: 1, dec: 1, enc: 1, uvd_enc: 1, vcn_jpeg: 1, ..Default::default() };
+            let usage = stat.calc_usage(&pre, &Duration::from_nanos(1), true, true);
+
+            for v in [usage.gfx, usage.compute, usage.dma, usage.dec, usage.enc, usage.uvd_enc, usage.vcn_jpeg, usage.media] {
+                prop_assert!((0..=u32::MAX as i64).contains(&v));
+            }
+
+            let excluded = stat.exclude_shared();
+
+            prop_assert!(excluded.vram_usage <= stat.vram_usage);
+            prop_assert!(excluded.gtt_usage <= stat.gtt_usage);
+            prop_assert!(excluded.cpu_accessible_usage <= stat.cpu_accessible_usage);
+
+            // the sum of the processes saturates
+            let mut sum = stat;
+            sum += stat;
+            sum += usage;
+        }
+
+        #[test]
+        fn fdinfo_truncated(lines in fdinfo_lines(), cut in any::<prop::sample::Index>()) {
+            let s = format!("drm-client-id:\t1\n{lines}");
+            let _ = parse_fdinfo(&s.as_bytes()[..cut.index(s.len() + 1)]);
+        }
+
+        #[test]
+        fn proc_stat_no_panic(data in prop::collection::vec(any::<u8>(), 0..512)) {
+            let _ = parse_proc_stat_cpu_ticks(&data);
+        }
+
+        #[test]
+        fn proc_stat_comm(comm in prop::collection::vec(any::<u8>(), 0..32), utime in any::<u64>(), stime in any::<u64>()) {
+            // the process name may contain spaces and `)`
+            let mut stat = b"2345 (".to_vec();
+            stat.extend_from_slice(&comm);
+            stat.extend_from_slice(
+                format!(") S 1 2345 2345 0 -1 4194560 1 0 0 0 {utime} {stime} 0 0 20 0 1 0 1 1 1\n").as_bytes()
+            );
+
+            prop_assert_eq!(parse_proc_stat_cpu_ticks(&stat), Some((utime, stime)));
+        }
+
+        #[test]
+        fn proc_stat_truncated(utime in any::<u64>(), stime in any::<u64>(), cut in any::<prop::sample::Index>()) {
+            let stat = format!("2345 (a b) S 1 2345 2345 0 -1 4194560 1 0 0 0 {utime} {stime}");
+            let stime_pos = stat.len() - stime.to_string().len();
+            let len = cut.index(stime_pos);
+
+            // cut before stime
+            prop_assert_eq!(parse_proc_stat_cpu_ticks(&stat.as_bytes()[..len]), None);
+        }
+    }
+}
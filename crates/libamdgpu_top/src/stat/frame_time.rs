@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Presentation timing for `--frame-source`, to correlate the stutters with the GPU activity.
+//
+// vblank: waits for each vblank of the CRTC with `DRM_IOCTL_WAIT_VBLANK` on the primary node,
+// the interval of the vblanks is the frame time with VRR (FreeSync).
+// With a fixed refresh rate, the vblanks do not follow the frames of the application.
+//
+// pipe: reads the frame times (ms) from a FIFO or a file, the last number of each line is used,
+// e.g. `frametime=16.7`, so a frame-time logger (e.g. the stats of a compositor) can be adapted with a script.
+
+// ## Reference
+//  * <https://gitlab.freedesktop.org/mesa/drm/-/blob/main/include/drm/drm.h> (`drm_wait_vblank`)
+
+const DRM_IOCTL_WAIT_VBLANK: libc::c_ulong = 0xC018643A; // DRM_IOWR(0x3a, union drm_wait_vblank)
+const DRM_VBLANK_RELATIVE: u32 = 0x1;
+const DRM_VBLANK_HIGH_CRTC_SHIFT: u32 = 1;
+const DRM_VBLANK_HIGH_CRTC_MASK: u32 = 0x3e;
+
+/// `union drm_wait_vblank`, the request and the reply share the memory
+#[repr(C)]
+#[derive(Default)]
+struct DrmWaitVblank {
+    /// request: type, reply: type
+    type_: u32,
+    /// request: the number of the vblanks to wait, reply: the vblank counter
+    sequence: u32,
+    /// reply only, the request has `signal` (unsigned long) at the same offset
+    tval_sec: libc::c_long,
+    tval_usec: libc::c_long,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FrameSource {
+    Vblank { crtc: u32 },
+    Pipe(PathBuf),
+}
+
+impl FromStr for FrameSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid frame source: {s:?} (vblank[:<crtc>], pipe:<path>)");
+
+        match s.split_once(':') {
+            None if s == "vblank" => Ok(Self::Vblank { crtc: 0 }),
+            Some(("vblank", crtc)) => Ok(Self::Vblank { crtc: crtc.parse().map_err(|_| err())? }),
+            Some(("pipe", path)) if !path.is_empty() => Ok(Self::Pipe(PathBuf::from(path))),
+            _ => Err(err()),
+        }
+    }
+}
+
+/// The last frame times of the source
+#[derive(Clone, Debug, Default)]
+pub struct FrameTimes {
+    /// (the time the frame is received, frame time in ms)
+    pub frames: VecDeque<(Instant, f64)>,
+    max_len: usize,
+}
+
+impl FrameTimes {
+    /// About 30 seconds at 144 fps
+    pub const DEFAULT_LEN: usize = 4096;
+    /// A frame longer than the median by this ratio is a stutter
+    pub const STUTTER_RATIO: f64 = 2.0;
+
+    pub fn new(max_len: usize) -> Self {
+        Self { frames: VecDeque::with_capacity(max_len), max_len }
+    }
+
+    pub fn push(&mut self, ms: f64) {
+        if self.frames.len() >= self.max_len {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back((Instant::now(), ms));
+    }
+
+    pub fn median(&self) -> Option<f64> {
+        let mut ms: Vec<f64> = self.frames.iter().map(|(_, ms)| *ms).collect();
+
+        if ms.is_empty() { return None }
+
+        let mid = ms.len() / 2;
+        let (_, median, _) = ms.select_nth_unstable_by(mid, |a, b| a.total_cmp(b));
+
+        Some(*median)
+    }
+
+    pub fn is_stutter(ms: f64, median: f64) -> bool {
+        ms >= median * Self::STUTTER_RATIO
+    }
+
+    /// Returns an error if the source cannot be opened.
+    pub fn spawn_update_thread(source: &FrameSource, card: &Path, max_len: usize) -> io::Result<Arc<Mutex<Self>>> {
+        let arc = Arc::new(Mutex::new(Self::new(max_len)));
+        let arc_frames = arc.clone();
+        let push = move |ms: f64| {
+            let lock = arc.lock();
+            if let Ok(mut frames) = lock {
+                frames.push(ms);
+            }
+        };
+
+        match source {
+            FrameSource::Vblank { crtc } => {
+                let file = File::open(card)?;
+                let crtc = *crtc;
+
+                // fails if the CRTC is not active
+                wait_vblank(&file, crtc, 0)?;
+
+                std::thread::spawn(move || {
+                    let mut last: Option<f64> = None;
+
+                    loop {
+                        match wait_vblank(&file, crtc, 1) {
+                            Ok(sec) => {
+                                if let Some(last) = last {
+                                    push((sec - last) * 1000.0);
+                                }
+
+                                last = Some(sec);
+                            },
+                            // e.g. DPMS off
+                            Err(_) => {
+                                last = None;
+                                std::thread::sleep(Duration::from_secs(1));
+                            },
+                        }
+                    }
+                });
+            },
+            FrameSource::Pipe(path) => {
+                // a FIFO blocks until the writer opens it
+                let path = path.clone();
+
+                if !path.exists() {
+                    return Err(io::Error::from(io::ErrorKind::NotFound));
+                }
+
+                std::thread::spawn(move || loop {
+                    let Ok(file) = File::open(&path) else {
+                        std::thread::sleep(Duration::from_secs(1));
+                        continue;
+                    };
+
+                    for line in BufReader::new(file).lines() {
+                        let Ok(line) = line else { break };
+                        let Some(ms) = parse_frame_time(&line) else { continue };
+
+                        push(ms);
+                    }
+
+                    // EOF: the writer is closed, wait for the next one
+                    std::thread::sleep(Duration::from_secs(1));
+                });
+            },
+        }
+
+        Ok(arc_frames)
+    }
+}
+
+/// The last number of the line
+fn parse_frame_time(line: &str) -> Option<f64> {
+    line.split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter_map(|s| s.parse::<f64>().ok())
+        .next_back()
+        .filter(|ms| ms.is_finite() && *ms > 0.0)
+}
+
+/// Waits for `count` vblanks of the CRTC, and returns the timestamp of the vblank (sec, CLOCK_MONOTONIC).
+fn wait_vblank(file: &File, crtc: u32, count: u32) -> io::Result<f64> {
+    let mut vbl = DrmWaitVblank {
+        type_: DRM_VBLANK_RELATIVE | ((crtc << DRM_VBLANK_HIGH_CRTC_SHIFT) & DRM_VBLANK_HIGH_CRTC_MASK),
+        sequence: count,
+        ..Default::default()
+    };
+
+    let r = unsafe { libc::ioctl(file.as_raw_fd(), DRM_IOCTL_WAIT_VBLANK, &mut vbl as *mut DrmWaitVblank) };
+
+    if r < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(vbl.tval_sec as f64 + vbl.tval_usec as f64 / 1_000_000.0)
+}
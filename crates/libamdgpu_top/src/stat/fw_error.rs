@@ -0,0 +1,99 @@
+use crate::PCI;
+use std::sync::{Arc, Mutex};
+use super::{KmsgReader, KmsgRecord};
+
+// SMU message failures and firmware errors, parsed from the kernel log ring buffer (`/dev/kmsg`).
+// Only the messages prefixed with the PCI bus of the device
+// (`amdgpu 0000:03:00.0: amdgpu: SMU: ...`) are counted.
+
+// ## Reference
+//  * Linux Kernel
+//    * `drivers/gpu/drm/amd/pm/swsmu/smu_cmn.c`
+//      * `__smu_cmn_reg_print_error`
+
+const LAST_ERROR_LEN: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FwErrorKind {
+    SmuMsg,
+    Firmware,
+}
+
+impl FwErrorKind {
+    pub fn from_record(record: &KmsgRecord) -> Option<Self> {
+        let msg = &record.message;
+        let lower = msg.to_ascii_lowercase();
+
+        if (msg.contains("SMU") && [
+            "response:",
+            "not done with your previous command",
+            "very busy",
+            "hanged state",
+        ].iter().any(|s| msg.contains(s)))
+        || lower.contains("failed send message")
+        || lower.contains("failed to send message") {
+            return Some(Self::SmuMsg);
+        }
+
+        // LOG_ERR or higher
+        if record.priority <= 3 && ["firmware", "psp", "ucode", "smu", "fw "].iter().any(|s| lower.contains(s)) {
+            return Some(Self::Firmware);
+        }
+
+        None
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FwErrorStat {
+    pub smu_msg_failures: u32,
+    pub fw_errors: u32,
+    pub last_error: Option<String>,
+    /// since boot
+    pub last_error_timestamp_us: Option<u64>,
+}
+
+impl FwErrorStat {
+    pub fn total(&self) -> u32 {
+        self.smu_msg_failures + self.fw_errors
+    }
+
+    pub fn add(&mut self, kind: FwErrorKind, record: &KmsgRecord) {
+        match kind {
+            FwErrorKind::SmuMsg => self.smu_msg_failures += 1,
+            FwErrorKind::Firmware => self.fw_errors += 1,
+        }
+
+        let mut msg = record.message.clone();
+
+        if let Some((pos, _)) = msg.char_indices().nth(LAST_ERROR_LEN) {
+            msg.truncate(pos);
+        }
+
+        self.last_error = Some(msg);
+        self.last_error_timestamp_us = Some(record.timestamp_us);
+    }
+
+    /// Returns `None` if `/dev/kmsg` cannot be read.
+    /// The errors since boot are counted as far as the ring buffer goes.
+    pub fn spawn_update_thread(pci_bus: &PCI::BUS_INFO) -> Option<Arc<Mutex<Self>>> {
+        let reader = KmsgReader::open().ok()?;
+        let arc = Arc::new(Mutex::new(Self::default()));
+        let arc_fw_error = arc.clone();
+        let prefix = format!("{pci_bus}:");
+
+        std::thread::spawn(move || {
+            for record in reader {
+                if !record.message.contains(&prefix) { continue }
+                let Some(kind) = FwErrorKind::from_record(&record) else { continue };
+
+                let lock = arc.lock();
+                if let Ok(mut fw_error) = lock {
+                    fw_error.add(kind, &record);
+                }
+            }
+        });
+
+        Some(arc_fw_error)
+    }
+}
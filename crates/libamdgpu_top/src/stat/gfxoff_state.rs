@@ -72,6 +72,32 @@ impl From<u32> for GfxoffStatus {
     }
 }
 
+/// Ratio of samples in which the GFX block was in GFXOFF.
+/// The accuracy depends on the sampling interval.
+#[derive(Clone, Debug, Default)]
+pub struct GfxoffResidency {
+    pub samples: u64,
+    pub in_gfxoff: u64,
+}
+
+impl GfxoffResidency {
+    pub fn add(&mut self, status: &GfxoffStatus) {
+        self.samples += 1;
+
+        if *status == GfxoffStatus::InGFXOFF {
+            self.in_gfxoff += 1;
+        }
+    }
+
+    pub fn percent(&self) -> Option<f32> {
+        (self.samples != 0).then(|| self.in_gfxoff as f32 * 100.0 / self.samples as f32)
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
 fn read_gfxoff<P: Into<PathBuf>>(path: P) -> io::Result<u32> {
     let mut buf = [0xFFu8; 4];
     
@@ -1,5 +1,7 @@
-use std::path::PathBuf;
-use crate::AMDGPU::{ASIC_NAME, DeviceHandle, GpuMetrics, MetricsInfo};
+use std::path::{Path, PathBuf};
+use crate::AMDGPU::{ASIC_NAME, GpuMetrics, MetricsInfo};
+use super::gpu_metrics_util::get_gpu_metrics;
+use super::PerfCounter;
 
 #[derive(Debug, Clone)]
 pub struct GpuActivity {
@@ -10,13 +12,12 @@ pub struct GpuActivity {
 
 impl GpuActivity {
     pub fn get<P: Into<PathBuf>>(
-        amdgpu_dev: &DeviceHandle,
         sysfs_path: P,
         asic_name: ASIC_NAME,
     ) -> Self {
         let path = sysfs_path.into();
 
-        if let Ok(metrics) = amdgpu_dev.get_gpu_metrics_from_sysfs_path(&path) {
+        if let Ok(metrics) = get_gpu_metrics(&path) {
             Self::from_gpu_metrics(&metrics)
         } else {
             // Some Raven/Picasso/Raven2 APU always report gpu_busy_percent as 100.
@@ -46,9 +47,9 @@ impl GpuActivity {
                     None
                 } else if header.format_revision == 2 {
                     /* for APU (gpu_metrics v2.x) */
-                    Some(v.saturating_div(100))
+                    Some(v.saturating_div(100).min(100))
                 } else {
-                    Some(v)
+                    Some(v.min(100))
                 },
                 None => None,
             }
@@ -67,3 +68,45 @@ impl GpuActivity {
         Self { gfx, umc, media: None }
     }
 }
+
+/// `--sysfs-busy`, the GFX and memory activity of each source side by side,
+/// to reconcile amdgpu_top with the other monitors that read `gpu_busy_percent`/`mem_busy_percent`.
+/// The sysfs values are read as is, e.g. always 100 on some Raven APUs.
+#[derive(Debug, Clone, Default)]
+pub struct ActivitySources {
+    /// "Graphics Pipe" (GUI_ACTIVE) of GRBM, `None` if GRBM is not read (e.g. `--source pmu`)
+    pub grbm_gfx: Option<u16>,
+    pub metrics_gfx: Option<u16>,
+    pub metrics_umc: Option<u16>,
+    pub sysfs_gfx: Option<u16>,
+    pub sysfs_umc: Option<u16>,
+}
+
+impl ActivitySources {
+    pub fn get(sysfs_path: &Path, grbm: Option<&PerfCounter>, metrics: Option<&GpuMetrics>) -> Self {
+        let grbm_gfx = grbm.and_then(|pc| {
+            let i = pc.index.iter().position(|(name, _)| name == "Graphics Pipe")?;
+
+            Some(pc.get_usage(i) as u16)
+        });
+        let metrics = metrics.map(GpuActivity::from_gpu_metrics);
+        let sysfs = GpuActivity::get_from_sysfs(sysfs_path);
+
+        Self {
+            grbm_gfx,
+            metrics_gfx: metrics.as_ref().and_then(|m| m.gfx),
+            metrics_umc: metrics.as_ref().and_then(|m| m.umc),
+            sysfs_gfx: sysfs.gfx,
+            sysfs_umc: sysfs.umc,
+        }
+    }
+
+    /// (source, GFX, memory)
+    pub fn rows(&self) -> [(&'static str, Option<u16>, Option<u16>); 3] {
+        [
+            ("GRBM", self.grbm_gfx, None),
+            ("gpu_metrics", self.metrics_gfx, self.metrics_umc),
+            ("sysfs", self.sysfs_gfx, self.sysfs_umc),
+        ]
+    }
+}
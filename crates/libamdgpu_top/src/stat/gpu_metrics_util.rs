@@ -1,4 +1,59 @@
-use libdrm_amdgpu_sys::AMDGPU::NUM_HBM_INSTANCES;
+use libdrm_amdgpu_sys::AMDGPU::{GpuMetrics, MetricsInfo, NUM_HBM_INSTANCES};
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The size of `metrics_table_header`
+const HEADER_SIZE: usize = 4;
+
+/// Decode the content of `gpu_metrics`.
+/// `None` if the header is truncated, the table is shorter than `structure_size` of the header
+/// (a partial read would leave the rest of the fields as "not supported"), or the revision is unknown.
+pub fn parse_gpu_metrics(raw: &[u8]) -> Option<GpuMetrics> {
+    let header = raw.get(..HEADER_SIZE)?;
+    let structure_size = u16::from_le_bytes([header[0], header[1]]) as usize;
+
+    if structure_size < HEADER_SIZE || raw.len() < structure_size {
+        return None;
+    }
+
+    let metrics = GpuMetrics::from_raw(&raw[..structure_size]);
+
+    metrics.get_header().is_some().then_some(metrics)
+}
+
+/// The time `gpu_metrics` was refreshed last.
+/// `system_clock_counter` is updated with the table (by the SMU or the driver),
+/// so an unchanged counter is an old copy of the table, e.g. from a stuck SMU.
+#[derive(Clone, Debug, Default)]
+pub struct GpuMetricsTime {
+    counter: Option<u64>,
+    pub updated: Option<Instant>,
+}
+
+impl GpuMetricsTime {
+    /// Call after every successful read of `gpu_metrics`
+    pub fn update(&mut self, metrics: &GpuMetrics) {
+        let counter = metrics.get_system_clock_counter().filter(|c| *c != u64::MAX);
+
+        if counter.is_none() || counter != self.counter {
+            self.updated = Some(Instant::now());
+        }
+
+        self.counter = counter;
+    }
+
+    pub fn is_stale(&self, interval: Duration) -> bool {
+        super::is_stale(self.updated, interval)
+    }
+}
+
+/// Read and decode `<sysfs_path>/gpu_metrics`, see [`parse_gpu_metrics`]
+pub fn get_gpu_metrics<P: AsRef<Path>>(sysfs_path: P) -> io::Result<GpuMetrics> {
+    let raw = std::fs::read(sysfs_path.as_ref().join("gpu_metrics"))?;
+
+    parse_gpu_metrics(&raw).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid gpu_metrics"))
+}
 
 pub fn check_metrics_val(val: Option<u16>) -> String {
     if let Some(v) = val {
@@ -0,0 +1,166 @@
+use crate::PCI;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::sync::{Arc, Mutex};
+
+// Reader for the kernel log ring buffer (`/dev/kmsg`).
+// Reading `/dev/kmsg` requires `CAP_SYSLOG` if `kernel.dmesg_restrict` is 1.
+// The timestamps are `local_clock()`, which is close to `/proc/uptime` unless the system was suspended.
+
+// ## Reference
+//  * <https://www.kernel.org/doc/Documentation/ABI/testing/dev-kmsg>
+
+const KMSG_PATH: &str = "/dev/kmsg";
+
+/// `<priority>,<sequence>,<timestamp (us)>,<flags>;<message>`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KmsgRecord {
+    /// syslog(3) priority, without the facility
+    pub priority: u8,
+    pub seq: u64,
+    /// since boot
+    pub timestamp_us: u64,
+    pub message: String,
+}
+
+impl fmt::Display for KmsgRecord {
+    /// as dmesg(1)
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{:>5}.{:06}] {}", self.timestamp_us / 1_000_000, self.timestamp_us % 1_000_000, self.message)
+    }
+}
+
+impl KmsgRecord {
+    /// Seconds relative to `base_us` (since boot), negative for the messages before it
+    pub fn sec_since(&self, base_us: u64) -> f64 {
+        (self.timestamp_us as f64 - base_us as f64) / 1_000_000.0
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        let (header, message) = s.split_once(';')?;
+        let mut split = header.split(',');
+        let prefix: u32 = split.next()?.parse().ok()?;
+        let seq = split.next()?.parse().ok()?;
+        let timestamp_us = split.next()?.parse().ok()?;
+        // the continuation lines (` SUBSYSTEM=...`, ` DEVICE=...`) are not the message
+        let message = message.lines().next().unwrap_or("").to_string();
+
+        Some(Self { priority: (prefix & 0x7) as u8, seq, timestamp_us, message })
+    }
+}
+
+/// Reads the records from the start of the kernel log ring buffer, then blocks for new records.
+pub struct KmsgReader {
+    file: File,
+    buf: Vec<u8>,
+}
+
+impl KmsgReader {
+    pub fn open() -> io::Result<Self> {
+        let file = File::open(KMSG_PATH)?;
+
+        // a read returns a record, and fails with EINVAL if the buffer is too small
+        Ok(Self { file, buf: vec![0u8; 8192] })
+    }
+
+    /// Reads the records in the ring buffer without blocking.
+    pub fn read_all() -> io::Result<Vec<KmsgRecord>> {
+        let file = fs::OpenOptions::new().read(true).custom_flags(libc::O_NONBLOCK).open(KMSG_PATH)?;
+        let mut reader = Self { file, buf: vec![0u8; 8192] };
+        let mut records = Vec::new();
+
+        loop {
+            match reader.read_record() {
+                Ok(record) => records.push(record),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(records),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    pub fn read_record(&mut self) -> io::Result<KmsgRecord> {
+        loop {
+            let len = match self.file.read(&mut self.buf) {
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+                Ok(len) => len,
+                // EPIPE: the records were overwritten before being read
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => continue,
+                Err(err) => return Err(err),
+            };
+
+            if let Some(record) = KmsgRecord::parse(&String::from_utf8_lossy(&self.buf[..len])) {
+                return Ok(record);
+            }
+        }
+    }
+}
+
+impl Iterator for KmsgReader {
+    type Item = KmsgRecord;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().ok()
+    }
+}
+
+/// Since boot, to align the timestamps of [`KmsgRecord`] with the metric history.
+pub fn uptime_us() -> Option<u64> {
+    let s = fs::read_to_string("/proc/uptime").ok()?;
+    let (sec, frac) = s.split_whitespace().next()?.split_once('.')?;
+
+    Some(sec.parse::<u64>().ok()? * 1_000_000 + frac.parse::<u64>().ok()? * 10_000)
+}
+
+/// The last kernel messages of the device, and the `[drm]` messages without the PCI bus.
+#[derive(Clone, Debug, Default)]
+pub struct KmsgTail {
+    pub records: VecDeque<KmsgRecord>,
+    max_len: usize,
+}
+
+impl KmsgTail {
+    pub const DEFAULT_LEN: usize = 32;
+
+    pub fn new(max_len: usize) -> Self {
+        Self { records: VecDeque::with_capacity(max_len), max_len }
+    }
+
+    pub fn is_device_message(pci_bus: &PCI::BUS_INFO, msg: &str) -> bool {
+        if msg.contains(&format!("{pci_bus}:")) { return true }
+
+        // `[drm:amdgpu_job_timedout [amdgpu]] *ERROR* ring gfx timeout, ...`
+        msg.starts_with("[drm") || msg.starts_with("amdgpu:")
+    }
+
+    pub fn push(&mut self, record: KmsgRecord) {
+        if self.records.len() >= self.max_len {
+            self.records.pop_front();
+        }
+
+        self.records.push_back(record);
+    }
+
+    /// Returns `None` if `/dev/kmsg` cannot be read.
+    pub fn spawn_update_thread(pci_bus: &PCI::BUS_INFO, max_len: usize) -> Option<Arc<Mutex<Self>>> {
+        let reader = KmsgReader::open().ok()?;
+        let arc = Arc::new(Mutex::new(Self::new(max_len)));
+        let arc_tail = arc.clone();
+        let pci_bus = *pci_bus;
+
+        std::thread::spawn(move || {
+            for record in reader {
+                if !Self::is_device_message(&pci_bus, &record.message) { continue }
+
+                let lock = arc.lock();
+                if let Ok(mut tail) = lock {
+                    tail.push(record);
+                }
+            }
+        });
+
+        Some(arc_tail)
+    }
+}
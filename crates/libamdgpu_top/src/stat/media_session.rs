@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use super::ProcUsage;
+
+// The encode/decode sessions of the processes.
+// The kernel reports only the busy time of the media engines per DRM client (fdinfo),
+// so a session is a DRM client that used the media engines in the interval.
+// The codec and the resolution are not exposed by the driver (VCN firmware),
+// they are taken from the command line of ffmpeg (e.g. the transcoder of media servers) if possible.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaApi {
+    VaApi,
+    Vdpau,
+    Amf,
+}
+
+impl MediaApi {
+    /// The libraries in `/proc/<pid>/maps`
+    const LIBS: &'static [(&'static str, Self)] = &[
+        ("libva.so", Self::VaApi),
+        ("_drv_video.so", Self::VaApi),
+        ("libvdpau", Self::Vdpau),
+        ("libamfrt64.so", Self::Amf),
+    ];
+
+    fn from_maps(pid: i32) -> Vec<Self> {
+        let Ok(maps) = fs::read_to_string(format!("/proc/{pid}/maps")) else { return Vec::new() };
+        let mut apis = Vec::new();
+
+        for (lib, api) in Self::LIBS {
+            if !apis.contains(api) && maps.contains(lib) {
+                apis.push(*api);
+            }
+        }
+
+        apis
+    }
+}
+
+impl fmt::Display for MediaApi {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::VaApi => "VA-API",
+            Self::Vdpau => "VDPAU",
+            Self::Amf => "AMF",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+/// The details of the sessions of a process, read once per process
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MediaHint {
+    pub apis: Vec<MediaApi>,
+    /// ffmpeg only, e.g. `hevc_vaapi`
+    pub codecs: Vec<String>,
+    /// ffmpeg only, the output size (`-s` or the scale filter)
+    pub resolution: Option<String>,
+}
+
+impl MediaHint {
+    pub fn get(pid: i32) -> Self {
+        let apis = MediaApi::from_maps(pid);
+        let args: Vec<String> = fs::read(format!("/proc/{pid}/cmdline"))
+            .map(|cmdline| cmdline
+                .split(|b| *b == 0)
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect()
+            )
+            .unwrap_or_default();
+        let is_ffmpeg = args.first().is_some_and(|arg0| arg0.rsplit('/').next().is_some_and(|name| name.contains("ffmpeg")));
+
+        if !is_ffmpeg {
+            return Self { apis, ..Default::default() };
+        }
+
+        let (codecs, resolution) = parse_ffmpeg_args(&args);
+
+        Self { apis, codecs, resolution }
+    }
+}
+
+/// (the video codecs, the output resolution)
+fn parse_ffmpeg_args(args: &[String]) -> (Vec<String>, Option<String>) {
+    let mut codecs: Vec<String> = Vec::new();
+    let mut resolution = None;
+
+    for pair in args.windows(2) {
+        let [opt, val] = [&pair[0], &pair[1]];
+        let opt = opt.as_str();
+
+        if matches!(opt, "-vcodec" | "-c:v" | "-codec:v") || opt.starts_with("-c:v:") || opt.starts_with("-codec:v:") {
+            if val != "copy" && !codecs.contains(val) {
+                codecs.push(val.to_string());
+            }
+        } else if matches!(opt, "-s" | "-s:v") {
+            resolution = Some(val.to_string());
+        } else if matches!(opt, "-vf" | "-filter:v" | "-filter_complex") || opt.starts_with("-filter:v:") {
+            resolution = parse_scale_filter(val).or(resolution);
+        }
+    }
+
+    (codecs, resolution)
+}
+
+/// `scale=1280:720`, `scale_vaapi=w=1280:h=720`, ..
+/// The expressions (e.g. `trunc(iw/2)*2`) are ignored.
+fn parse_scale_filter(filter: &str) -> Option<String> {
+    filter.split([',', ';']).filter_map(|f| {
+        let mut f = f.trim();
+
+        // the input and output labels, `[0:v]scale=1280:720[v]`
+        while f.starts_with('[') {
+            f = &f[f.find(']')?+1..];
+        }
+
+        let (name, params) = f.split('[').next()?.split_once('=')?;
+
+        if !name.starts_with("scale") { return None }
+
+        let [mut w, mut h] = [None; 2];
+
+        for (i, param) in params.split(':').enumerate() {
+            let (key, val) = param.split_once('=').unwrap_or(match i {
+                0 => ("w", param),
+                1 => ("h", param),
+                _ => ("", param),
+            });
+
+            match key {
+                "w" | "width" => w = val.parse::<u32>().ok(),
+                "h" | "height" => h = val.parse::<u32>().ok(),
+                _ => {},
+            }
+        }
+
+        Some(format!("{}x{}", w?, h?))
+    }).next_back()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MediaSession {
+    pub pid: i32,
+    pub name: String,
+    /// The DRM clients that used the media engines
+    pub sessions: u32,
+    /// %, including JPEG
+    pub dec: i64,
+    /// %
+    pub enc: i64,
+    /// %, VCN4 and later (unified queue)
+    pub media: i64,
+    pub hint: MediaHint,
+}
+
+/// The processes using the media engines, with the cache of [`MediaHint`]
+#[derive(Clone, Debug, Default)]
+pub struct MediaSessions {
+    pub sessions: Vec<MediaSession>,
+    hints: HashMap<i32, MediaHint>,
+}
+
+impl MediaSessions {
+    pub fn update(&mut self, proc_usage: &[ProcUsage]) {
+        self.sessions.clear();
+
+        for pu in proc_usage.iter().filter(|pu| pu.media_clients != 0) {
+            let hint = self.hints.entry(pu.pid).or_insert_with(|| MediaHint::get(pu.pid));
+
+            self.sessions.push(MediaSession {
+                pid: pu.pid,
+                name: pu.name.clone(),
+                sessions: pu.media_clients,
+                dec: pu.usage.dec + pu.usage.vcn_jpeg,
+                enc: pu.usage.enc + pu.usage.uvd_enc,
+                media: pu.usage.media,
+                hint: hint.clone(),
+            });
+        }
+
+        self.hints.retain(|pid, _| proc_usage.iter().any(|pu| pu.pid == *pid));
+    }
+
+    pub fn total_sessions(&self) -> u32 {
+        self.sessions.iter().map(|s| s.sessions).sum()
+    }
+}
@@ -70,21 +70,167 @@ pub use perf_counter::*;
 mod fdinfo;
 pub use fdinfo::*;
 
+mod media_session;
+pub use media_session::*;
+
+mod pid_monitor;
+pub use pid_monitor::*;
+
+mod proc_group;
+pub use proc_group::*;
+
+mod smoothing;
+pub use smoothing::*;
+
+mod peak_hold;
+pub use peak_hold::*;
+
 mod sensors;
 pub use sensors::*;
 
+mod power_limits;
+pub use power_limits::*;
+
 mod pcie_bw;
 pub use pcie_bw::*;
 
+mod kmsg;
+pub use kmsg::*;
+
+mod frame_time;
+pub use frame_time::*;
+
+mod fw_error;
+pub use fw_error::*;
+
+mod xgmi;
+pub use xgmi::*;
+
 mod gfxoff_state;
 pub use gfxoff_state::*;
 
+mod dpm_clock;
+pub use dpm_clock::*;
+
+mod pm_info;
+pub use pm_info::*;
+
+mod bo_info;
+pub use bo_info::*;
+
+mod vcn_state;
+pub use vcn_state::*;
+
 mod gpu_activity;
 pub use gpu_activity::*;
 
 pub mod gpu_metrics_util;
 
-pub(crate) fn parse_hwmon<T: std::str::FromStr, P: Into<std::path::PathBuf>>(path: P) -> Option<T> {
-    std::fs::read_to_string(path.into()).ok()
-        .and_then(|file| file.trim_end().parse::<T>().ok())
+/// The values not updated for more than this many update intervals are shown as stale
+pub const STALE_PERIODS: u32 = 3;
+
+/// `updated`: the time of the last successful read (or the last change) of a value
+pub fn is_stale(updated: Option<std::time::Instant>, interval: std::time::Duration) -> bool {
+    updated.is_none_or(|t| t.elapsed() > interval.saturating_mul(STALE_PERIODS))
+}
+
+/// Read into the stack buffer until EOF, the sysfs/hwmon files are a single short value.
+/// Only for the numeric values (or the short strings), `None` if the content does not fit in
+/// the buffer (`HWMON_BUF_LEN - 1` bytes), use `fs::read_to_string` for the tables (e.g. `pp_dpm_*`).
+pub(crate) fn parse_hwmon<T: std::str::FromStr, P: AsRef<std::path::Path>>(path: P) -> Option<T> {
+    use std::io::{ErrorKind, Read};
+
+    let mut buf = [0u8; HWMON_BUF_LEN];
+    let mut f = std::fs::File::open(path).ok()?;
+    let mut len = 0;
+
+    loop {
+        match f.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(_) => return None,
+        }
+
+        // not a short value, do not parse the truncated content
+        if len == buf.len() { return None }
+    }
+
+    parse_hwmon_value(&buf[..len])
+}
+
+const HWMON_BUF_LEN: usize = 32;
+
+/// Parse the content of a sysfs/hwmon file (e.g. `"45000\n"`),
+/// `None` if it is not valid UTF-8 or not a value of `T`.
+pub fn parse_hwmon_value<T: std::str::FromStr>(buf: &[u8]) -> Option<T> {
+    std::str::from_utf8(buf).ok()?.trim_end().parse::<T>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn hwmon_value() {
+        assert_eq!(parse_hwmon_value::<u32>(b"45000\n"), Some(45000));
+        assert_eq!(parse_hwmon_value::<i64>(b"-273000\n"), Some(-273000));
+        assert_eq!(parse_hwmon_value::<u64>(b"1800000000"), Some(1800000000));
+        assert_eq!(parse_hwmon_value::<u32>(b""), None);
+        assert_eq!(parse_hwmon_value::<u32>(b"\n"), None);
+        assert_eq!(parse_hwmon_value::<u32>(b" 45000\n"), None);
+        assert_eq!(parse_hwmon_value::<u32>(b"45000 mV\n"), None);
+        assert_eq!(parse_hwmon_value::<u32>(b"-1\n"), None);
+        assert_eq!(parse_hwmon_value::<u32>(b"4294967296\n"), None);
+        assert_eq!(parse_hwmon_value::<u32>(b"\xff\xfe"), None);
+    }
+
+    #[test]
+    fn hwmon_file() {
+        let dir = std::env::temp_dir().join(format!("amdgpu_top-hwmon-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("temp1_input");
+        let long = "1".repeat(HWMON_BUF_LEN - 1);
+
+        std::fs::write(&path, "45000\n").unwrap();
+        assert_eq!(parse_hwmon::<u32, _>(&path), Some(45000));
+
+        std::fs::write(&path, &long).unwrap();
+        assert_eq!(parse_hwmon::<String, _>(&path), Some(long.clone()));
+
+        // does not fit in the buffer
+        std::fs::write(&path, format!("{long}\n")).unwrap();
+        assert_eq!(parse_hwmon::<String, _>(&path), None);
+
+        assert_eq!(parse_hwmon::<u32, _>(dir.join("not_found")), None);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    proptest! {
+        #[test]
+        fn hwmon_no_panic(data in prop::collection::vec(any::<u8>(), 0..64)) {
+            let _ = parse_hwmon_value::<u32>(&data);
+            let _ = parse_hwmon_value::<u64>(&data);
+            let _ = parse_hwmon_value::<i64>(&data);
+        }
+
+        #[test]
+        fn hwmon_round_trip(v in any::<u32>(), temp in any::<i64>(), newlines in "[\n\r\t ]{0,3}") {
+            prop_assert_eq!(parse_hwmon_value::<u32>(format!("{v}{newlines}").as_bytes()), Some(v));
+            prop_assert_eq!(parse_hwmon_value::<i64>(format!("{temp}\n").as_bytes()), Some(temp));
+        }
+
+        #[test]
+        fn hwmon_truncated(v in 10u64.., cut in any::<prop::sample::Index>()) {
+            let s = v.to_string();
+            let len = cut.index(s.len());
+
+            // a prefix of the digits is a smaller value, or nothing
+            let parsed = parse_hwmon_value::<u64>(&s.as_bytes()[..len]);
+            prop_assert!(parsed.is_none_or(|p| p < v));
+        }
+    }
 }
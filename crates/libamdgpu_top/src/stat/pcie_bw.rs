@@ -2,12 +2,14 @@ use crate::AMDGPU::{drm_amdgpu_info_device, GPU_INFO, ASIC_NAME};
 use std::fs;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 // PCIe bandwidth (throughput) available from `pcie_bw` sysfs
 // `pcie_bw` is supported on dGPUs only
 // The AMDGPU driver waits 1s (`msleep(1000)`) for pcie performance counters.
 // So we should read the file in a separate thread.
+// RDNA (Navi) and later do not have the counters (`get_pcie_usage`),
+// MI300 reports the total bandwidth of both directions in `gpu_metrics` v1.4/v1.5 instead.
 
 // ## Reference
 //  * <https://www.kernel.org/doc/html/latest/gpu/amdgpu/driver-misc.html#pcie-bw>
@@ -16,28 +18,59 @@ use std::path::PathBuf;
 //    * `drivers/gpu/drm/amd/amdgpu_pm.c`
 //    * `drivers/gpu/drm/amd/amdgpu/{cik,si,vi,soc15}.c`
 //      * `{cik,si,vi,soc15}_get_pcie_usage`
+//    * `drivers/gpu/drm/amd/include/kgd_pp_interface.h`
+//      * `gpu_metrics_v1_4`, `gpu_metrics_v1_5`
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PcieBwSource {
+    /// `pcie_bw` sysfs, sent/received
+    Sysfs,
+    /// `pcie_bandwidth_inst` of `gpu_metrics` v1.4/v1.5, the total of both directions
+    GpuMetrics,
+}
 
 #[derive(Clone, Debug)]
 pub struct PcieBw {
     path: PathBuf,
+    gpu_metrics_path: PathBuf,
+    pub source: PcieBwSource,
     pub sent: Option<u64>,
     pub received: Option<u64>,
     pub max_payload_size: Option<i32>,
+    /// MiB/s, `PcieBwSource::GpuMetrics` only
+    pub total: Option<u64>,
 }
 
 impl PcieBw {
     pub fn new<P: Into<PathBuf>>(sysfs_path: P) -> Self {
-        let path = sysfs_path.into().join("pcie_bw");
+        let sysfs_path = sysfs_path.into();
 
         Self {
-            path,
+            path: sysfs_path.join("pcie_bw"),
+            gpu_metrics_path: sysfs_path.join("gpu_metrics"),
+            source: PcieBwSource::Sysfs,
             sent: None,
             received: None,
             max_payload_size: None,
+            total: None,
         }
     }
 
+    /// (Sent, Received) in MiB/s
+    pub fn sent_received_mib(&self) -> Option<(u64, u64)> {
+        let mps = self.max_payload_size? as u64;
+
+        Some(((self.sent? * mps) >> 20, (self.received? * mps) >> 20))
+    }
+
     pub fn update(&mut self) {
+        if self.source == PcieBwSource::GpuMetrics {
+            // GB/s
+            self.total = pcie_bandwidth_from_raw_gpu_metrics(&self.gpu_metrics_path)
+                .map(|gb| (gb * 1_000_000_000) >> 20);
+            return;
+        }
+
         let Ok(s) = fs::read_to_string(&self.path) else { return };
         let mut split = s.trim_end().split(' ');
 
@@ -56,7 +89,8 @@ impl PcieBw {
 
             if buf_pcie_bw.sent.is_none()
             && buf_pcie_bw.received.is_none()
-            && buf_pcie_bw.max_payload_size.is_none() {
+            && buf_pcie_bw.max_payload_size.is_none()
+            && buf_pcie_bw.total.is_none() {
                 return;
             }
 
@@ -78,4 +112,38 @@ impl PcieBw {
         && !ext_info.is_apu()
         && ext_info.get_asic_name() < ASIC_NAME::CHIP_NAVI10
     }
+
+    /// Selects the source, or `Err` with the reason,
+    /// so the frontends can show "unsupported" instead of zero (idle) or nothing.
+    pub fn detect_source(&mut self, ext_info: &drm_amdgpu_info_device) -> Result<PcieBwSource, &'static str> {
+        let source = if self.check_pcie_bw_support(ext_info) {
+            PcieBwSource::Sysfs
+        } else if pcie_bandwidth_from_raw_gpu_metrics(&self.gpu_metrics_path).is_some() {
+            PcieBwSource::GpuMetrics
+        } else if ext_info.is_apu() {
+            return Err("APU");
+        } else if ext_info.get_asic_name() >= ASIC_NAME::CHIP_NAVI10 {
+            return Err("no PCIe performance counters on RDNA and later");
+        } else {
+            return Err("pcie_bw is not available");
+        };
+
+        self.source = source;
+
+        Ok(source)
+    }
+}
+
+/// `pcie_bandwidth_inst` (GB/s) of `gpu_metrics` v1.4/v1.5
+fn pcie_bandwidth_from_raw_gpu_metrics(path: &Path) -> Option<u64> {
+    let raw = fs::read(path).ok()?;
+    let offset = match (raw.get(2)?, raw.get(3)?) {
+        (1, 4) => 72,
+        // `jpeg_activity[32]` is added
+        (1, 5) => 136,
+        _ => return None,
+    };
+    let v = u64::from_le_bytes(raw.get(offset..offset+8)?.try_into().ok()?);
+
+    (v != u64::MAX).then_some(v)
 }
@@ -0,0 +1,31 @@
+use std::time::{Duration, Instant};
+
+/// The peak of a value, held for the hold time and then falling to zero in the same time,
+/// as the peak meters of audio.
+/// A new value above the falling peak becomes the new peak.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PeakHold {
+    peak: f64,
+    time: Option<Instant>,
+}
+
+impl PeakHold {
+    pub fn update(&mut self, val: f64, hold: Duration, now: Instant) {
+        if self.get(hold, now).is_none_or(|peak| val >= peak) {
+            self.peak = val;
+            self.time = Some(now);
+        }
+    }
+
+    /// `None` after the peak has fallen, or if `hold` is zero (disabled)
+    pub fn get(&self, hold: Duration, now: Instant) -> Option<f64> {
+        let elapsed = now.saturating_duration_since(self.time?);
+
+        if hold.is_zero() { return None }
+        if elapsed <= hold { return Some(self.peak) }
+
+        let fall = (elapsed - hold).as_secs_f64() / hold.as_secs_f64();
+
+        (fall < 1.0).then_some(self.peak * (1.0 - fall))
+    }
+}
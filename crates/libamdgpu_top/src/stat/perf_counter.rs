@@ -4,23 +4,29 @@ use libdrm_amdgpu_sys::AMDGPU::{
     GRBM_OFFSET,
     GRBM2_OFFSET,
 };
-use crate::stat;
+use std::time::Duration;
+use crate::stat::{self, Smoothing};
 
 #[derive(Clone, Debug)]
 pub struct PerfCounter {
     pub pc_type: PCType,
     pub bits: PCAcc,
     pub index: Vec<(String, usize)>,
+    pub smoothing: Smoothing,
+    pub usage: Vec<f32>, // %, same order as index
 }
 
 impl PerfCounter {
     pub fn new(pc_type: PCType, s: &[(&str, usize)]) -> Self {
-        let index = s.iter().map(|(name, idx)| (name.to_string(), *idx)).collect();
+        let index: Vec<(String, usize)> = s.iter().map(|(name, idx)| (name.to_string(), *idx)).collect();
+        let usage = vec![0.0; index.len()];
 
         Self {
             pc_type,
             bits: PCAcc::default(),
             index,
+            smoothing: Smoothing::default(),
+            usage,
         }
     }
 
@@ -52,6 +58,17 @@ impl PerfCounter {
             self.bits.acc(out);
         }
     }
+
+    /// Update `usage` from the accumulated bits (100 samples), call before `bits.clear()`
+    pub fn update_usage(&mut self, interval: Duration) {
+        for (usage, (_, pos)) in self.usage.iter_mut().zip(self.index.iter()) {
+            *usage = self.smoothing.apply(*usage, self.bits.get(*pos) as f32, interval);
+        }
+    }
+
+    pub fn get_usage(&self, i: usize) -> u8 {
+        self.usage.get(i).map_or(0, |usage| usage.round() as u8)
+    }
 }
 
 
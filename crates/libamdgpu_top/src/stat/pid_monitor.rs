@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::Duration;
+use crate::{DevicePath, PCI};
+use super::{get_proc_info, get_proc_name, FdInfoStat, FdInfoUsage, ProcInfo, ProcUsage, Smoothing};
+
+// `--pid`, the fdinfo of a single process on all devices.
+// The fds of the process are looked up on every update, instead of the process index thread,
+// so the devices the process opens later are also tracked.
+// The usage of the last interval before a process exits is lost, because its fdinfo is removed
+// with the process.
+
+/// The usage of the process on the device
+#[derive(Clone)]
+pub struct PidDeviceStat {
+    pub device_path: DevicePath,
+    pub pci_bus: PCI::BUS_INFO,
+    pub marketing_name: String,
+    pub fdinfo: FdInfoStat,
+    /// `None` if the process does not open the device
+    pub usage: Option<ProcUsage>,
+    /// MiB, the oldest first
+    pub vram_history: VecDeque<u64>,
+    /// Engine busy time in ns accumulated since the start
+    pub total: FdInfoUsage,
+    /// KiB
+    pub peak_vram: u64,
+    /// KiB
+    pub peak_gtt: u64,
+}
+
+impl PidDeviceStat {
+    fn update(&mut self, pids: &[i32], interval: Duration) {
+        self.fdinfo.interval = interval;
+
+        let proc_index: Vec<ProcInfo> = pids.iter()
+            .filter_map(|pid| get_proc_info(*pid, &self.device_path))
+            .collect();
+
+        self.fdinfo.get_all_proc_usage(&proc_index);
+        // the sum of the usage of the process and its children, as the first process that opens the device
+        self.usage = self.fdinfo.proc_usage.iter().cloned().reduce(|mut sum, pu| {
+            sum.usage += pu.usage;
+            sum.delta += pu.delta;
+            sum.cpu_usage += pu.cpu_usage;
+            sum
+        });
+
+        if let Some(pu) = &self.usage {
+            // memory usage is not accumulated
+            self.total += FdInfoUsage {
+                vram_usage: 0,
+                gtt_usage: 0,
+                cpu_accessible_usage: 0,
+                resident: Default::default(),
+                purgeable: Default::default(),
+                shared: Default::default(),
+                ..pu.delta
+            };
+            self.peak_vram = self.peak_vram.max(pu.usage.vram_usage);
+            self.peak_gtt = self.peak_gtt.max(pu.usage.gtt_usage);
+        }
+
+        if self.vram_history.len() >= PidMonitor::HISTORY_LEN {
+            self.vram_history.pop_front();
+        }
+
+        self.vram_history.push_back(self.usage.as_ref().map_or(0, |pu| pu.usage.vram_usage >> 10));
+    }
+}
+
+#[derive(Clone)]
+pub struct PidMonitor {
+    pub pid: i32,
+    pub name: String,
+    /// Also monitor the descendant processes
+    pub include_children: bool,
+    pub devices: Vec<PidDeviceStat>,
+}
+
+impl PidMonitor {
+    /// The number of samples of `vram_history`
+    pub const HISTORY_LEN: usize = 60;
+
+    /// Returns `None` if the process does not exist.
+    pub fn new(pid: i32, device_path_list: &[DevicePath], smoothing: Smoothing) -> Option<Self> {
+        let name = get_proc_name(pid)?;
+        let devices = device_path_list.iter().filter_map(|device_path| {
+            let amdgpu_dev = device_path.init().ok()?;
+            let pci_bus = match device_path.pci {
+                Some(pci_bus) => pci_bus,
+                None => amdgpu_dev.get_pci_bus_info().ok()?,
+            };
+            let fdinfo = FdInfoStat {
+                smoothing,
+                has_vcn: crate::has_vcn(&amdgpu_dev),
+                has_vcn_unified: crate::has_vcn_unified(&amdgpu_dev),
+                ..Default::default()
+            };
+
+            Some(PidDeviceStat {
+                device_path: device_path.clone(),
+                pci_bus,
+                marketing_name: amdgpu_dev.get_marketing_name_or_default(),
+                fdinfo,
+                usage: None,
+                vram_history: VecDeque::with_capacity(Self::HISTORY_LEN),
+                total: FdInfoUsage::default(),
+                peak_vram: 0,
+                peak_gtt: 0,
+            })
+        }).collect();
+
+        Some(Self { pid, name, include_children: false, devices })
+    }
+
+    pub fn is_alive(&self) -> bool {
+        Path::new(&format!("/proc/{}", self.pid)).exists()
+    }
+
+    pub fn update(&mut self, interval: Duration) {
+        let mut pids = vec![self.pid];
+
+        if self.include_children {
+            pids.extend(get_descendant_pids(self.pid));
+        }
+
+        for device in self.devices.iter_mut() {
+            device.update(&pids, interval);
+        }
+    }
+}
+
+/// All descendants of the process, from the parent PID in `/proc/<pid>/stat`
+pub fn get_descendant_pids(pid: i32) -> Vec<i32> {
+    let Ok(proc_dir) = std::fs::read_dir("/proc") else { return Vec::new() };
+
+    let parents: Vec<(i32, i32)> = proc_dir.flatten().filter_map(|entry| {
+        let pid = entry.file_name().to_str()?.parse::<i32>().ok()?;
+        let stat = std::fs::read_to_string(entry.path().join("stat")).ok()?;
+        // "<pid> (<comm>) <state> <ppid> ...", comm may contain spaces and parentheses
+        let (_, rest) = stat.rsplit_once(')')?;
+        let ppid = rest.split_whitespace().nth(1)?.parse::<i32>().ok()?;
+
+        Some((pid, ppid))
+    }).collect();
+
+    let mut descendants = Vec::new();
+    let mut i = 0;
+    let mut parent = pid;
+
+    loop {
+        descendants.extend(parents.iter().filter(|(_, ppid)| *ppid == parent).map(|(pid, _)| *pid));
+
+        let Some(next) = descendants.get(i) else { break };
+        parent = *next;
+        i += 1;
+    }
+
+    descendants
+}
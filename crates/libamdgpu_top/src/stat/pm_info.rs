@@ -0,0 +1,253 @@
+// ref: drivers/gpu/drm/amd/pm/amdgpu_pm.c (`amdgpu_debugfs_pm_info_pp`, `amdgpu_parse_cg_state`)
+
+// Requires root (debugfs).
+// On VCN-based GPUs, the VCN power state is reported as "UVD".
+
+use std::io;
+use std::fs;
+
+const BASE: &str = "/sys/kernel/debug/dri";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PmValue {
+    pub label: String,
+    pub value: f32,
+    pub unit: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MediaBlockState {
+    pub enabled: bool,
+    pub clocks: Vec<PmValue>, // MHz
+}
+
+impl MediaBlockState {
+    pub fn get_clock(&self, label: &str) -> Option<u32> {
+        self.clocks.iter().find(|clk| clk.label == label).map(|clk| clk.value as u32)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PmInfo {
+    pub clock_gating_flags: Option<u64>,
+    pub clock_gating: Vec<(String, bool)>, // (name, On/Off)
+    pub gfx: Vec<PmValue>, // clocks, voltages and power
+    pub gpu_temp: Option<u32>, // C
+    pub gpu_load: Option<u32>, // %
+    pub mem_load: Option<u32>, // %
+    pub vcn_load: Option<u32>, // %
+    pub smc_feature_mask: Option<u64>,
+    pub uvd: Option<MediaBlockState>,
+    pub vce: Option<MediaBlockState>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Section {
+    None,
+    ClockGating,
+    Gfx,
+    Uvd,
+    Vce,
+}
+
+impl PmInfo {
+    pub fn get(instance: u32) -> io::Result<Self> {
+        let s = fs::read_to_string(format!("{BASE}/{instance}/amdgpu_pm_info"))?;
+
+        Ok(Self::parse(&s))
+    }
+
+    pub fn parse(s: &str) -> Self {
+        let mut info = Self::default();
+        let mut section = Section::None;
+
+        for line in s.lines() {
+            if line.trim().is_empty() {
+                section = Section::None;
+                continue;
+            }
+
+            if line.starts_with('\t') {
+                let line = line.trim();
+
+                match section {
+                    Section::ClockGating => {
+                        let Some((name, state)) = line.rsplit_once(": ") else { continue };
+                        info.clock_gating.push((name.to_string(), state == "On"));
+                    },
+                    Section::Gfx => {
+                        let Some(val) = parse_value(line) else { continue };
+                        info.gfx.push(val);
+                    },
+                    Section::Uvd | Section::Vce => {
+                        let Some(val) = parse_value(line) else { continue };
+                        let block = if section == Section::Uvd { &mut info.uvd } else { &mut info.vce };
+                        let Some(block) = block else { continue };
+                        block.clocks.push(val);
+                    },
+                    Section::None => {},
+                }
+
+                continue;
+            }
+
+            let Some((key, val)) = line.split_once(':') else { continue };
+            let val = val.trim();
+
+            match key {
+                "Clock Gating Flags Mask" => {
+                    info.clock_gating_flags = parse_hex(val);
+                    section = Section::ClockGating;
+                },
+                "GFX Clocks and Power" => section = Section::Gfx,
+                "GPU Temperature" => info.gpu_temp = parse_num(val),
+                "GPU Load" => info.gpu_load = parse_num(val),
+                "MEM Load" => info.mem_load = parse_num(val),
+                "VCN Load" => info.vcn_load = parse_num(val),
+                "SMC Feature Mask" => info.smc_feature_mask = parse_hex(val),
+                "UVD" | "VCN" => {
+                    info.uvd = Some(MediaBlockState { enabled: val == "Enabled", clocks: Vec::new() });
+                    section = Section::Uvd;
+                },
+                "VCE" => {
+                    info.vce = Some(MediaBlockState { enabled: val == "Enabled", clocks: Vec::new() });
+                    section = Section::Vce;
+                },
+                _ => {},
+            }
+        }
+
+        info
+    }
+}
+
+/// "1800 MHz (SCLK)", "1.050 W (average SoC)"
+fn parse_value(s: &str) -> Option<PmValue> {
+    let (val, label) = s.split_once(" (")?;
+    let (value, unit) = val.split_once(' ')?;
+
+    Some(PmValue {
+        label: label.strip_suffix(')')?.to_string(),
+        value: value.parse().ok()?,
+        unit: unit.to_string(),
+    })
+}
+
+fn parse_num(s: &str) -> Option<u32> {
+    s.split_once(' ').map_or(s, |(num, _)| num).parse().ok()
+}
+
+fn parse_hex(s: &str) -> Option<u64> {
+    u64::from_str_radix(s.trim_start_matches("0x"), 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(label: &str, value: f32, unit: &str) -> PmValue {
+        PmValue { label: label.to_string(), value, unit: unit.to_string() }
+    }
+
+    fn gfx_value(info: &PmInfo, label: &str) -> Option<f32> {
+        info.gfx.iter().find(|v| v.label == label).map(|v| v.value)
+    }
+
+    #[test]
+    fn navi21() {
+        let info = PmInfo::parse(include_str!("../../testdata/pm_info/navi21.txt"));
+
+        assert_eq!(info.clock_gating_flags, Some(0x3ff2df700));
+        assert_eq!(info.clock_gating.len(), 34);
+        assert_eq!(info.clock_gating[0], ("Graphics Fine Grain Clock Gating".to_string(), false));
+        assert_eq!(info.clock_gating[1], ("Graphics Medium Grain Clock Gating".to_string(), true));
+        assert_eq!(info.gfx.len(), 6);
+        assert_eq!(info.gfx[0], value("MCLK", 96.0, "MHz"));
+        assert_eq!(info.gfx[4], value("VDDGFX", 825.0, "mV"));
+        assert_eq!(gfx_value(&info, "average SoC"), Some(9.0));
+        assert_eq!(gfx_value(&info, "VDDNB"), None);
+        assert_eq!(info.gpu_temp, Some(43));
+        assert_eq!(info.gpu_load, Some(2));
+        assert_eq!(info.mem_load, Some(0));
+        assert_eq!(info.vcn_load, None);
+        assert_eq!(info.smc_feature_mask, Some(0x00003763a37fcffb));
+        assert_eq!(info.uvd, Some(MediaBlockState { enabled: false, clocks: Vec::new() }));
+        assert_eq!(info.vce, None);
+    }
+
+    #[test]
+    fn renoir_apu() {
+        let info = PmInfo::parse(include_str!("../../testdata/pm_info/renoir.txt"));
+
+        assert_eq!(info.clock_gating_flags, Some(0x2c0301c1));
+        assert_eq!(info.clock_gating.len(), 30);
+        assert_eq!(gfx_value(&info, "VDDNB"), Some(825.0));
+        assert_eq!(gfx_value(&info, "average SoC"), Some(3.72));
+        assert_eq!(gfx_value(&info, "current SoC including CPU"), Some(4.15));
+        assert_eq!(info.gpu_temp, Some(48));
+        assert_eq!(info.gpu_load, Some(5));
+        assert_eq!(info.mem_load, None);
+        assert_eq!(info.vcn_load, Some(12));
+
+        let vcn = info.uvd.unwrap();
+        assert!(vcn.enabled);
+        assert_eq!(vcn.get_clock("DCLK"), Some(370));
+        assert_eq!(vcn.get_clock("VCLK"), Some(410));
+        assert_eq!(vcn.get_clock("ECCLK"), None);
+        assert_eq!(info.vce, None);
+    }
+
+    #[test]
+    fn polaris_uvd_vce() {
+        let info = PmInfo::parse(include_str!("../../testdata/pm_info/polaris10.txt"));
+
+        assert_eq!(info.clock_gating.len(), 16);
+        assert_eq!(gfx_value(&info, "average GPU"), Some(33.16));
+        assert_eq!(info.gpu_temp, Some(39));
+        assert_eq!(info.mem_load, Some(1));
+        assert_eq!(info.smc_feature_mask, Some(0xe77fff));
+
+        let uvd = info.uvd.unwrap();
+        assert!(uvd.enabled);
+        assert_eq!(uvd.clocks, vec![value("DCLK", 600.0, "MHz"), value("VCLK", 600.0, "MHz")]);
+        assert_eq!(info.vce, Some(MediaBlockState { enabled: false, clocks: Vec::new() }));
+    }
+
+    #[test]
+    fn truncated() {
+        let s = include_str!("../../testdata/pm_info/renoir.txt");
+        let full = PmInfo::parse(s);
+
+        for len in 0..s.len() {
+            let info = PmInfo::parse(&s[..len]);
+            assert!(info.clock_gating.len() <= full.clock_gating.len());
+            assert!(info.gfx.len() <= full.gfx.len());
+        }
+
+        // cut in the middle of a value
+        let info = PmInfo::parse("GFX Clocks and Power:\n\t1600 MHz (MCLK)\n\t400 MH");
+        assert_eq!(info.gfx, vec![value("MCLK", 1600.0, "MHz")]);
+
+        let info = PmInfo::parse("GPU Temperature: \nGPU Load: %\nSMC Feature Mask: 0x");
+        assert_eq!(info, PmInfo::default());
+    }
+
+    #[test]
+    fn odd_input() {
+        assert_eq!(PmInfo::parse(""), PmInfo::default());
+
+        // indented lines without a section header are ignored
+        assert_eq!(PmInfo::parse("\t1600 MHz (MCLK)\n\tFoo: On\n"), PmInfo::default());
+
+        // a clock of the disabled block without the header, after a blank line
+        let info = PmInfo::parse("VCE: Disabled\n\n\t600 MHz (ECCLK)\n");
+        assert_eq!(info.vce, Some(MediaBlockState { enabled: false, clocks: Vec::new() }));
+
+        // CRLF, unknown keys and the malformed values
+        let info = PmInfo::parse(
+            "GFX Clocks and Power:\r\n\tabc MHz (SCLK)\r\n\t300 MHz SCLK\r\n\t500 MHz (SCLK)\r\nFoo: 1\r\nGPU Load: 99 %\r\n",
+        );
+        assert_eq!(info.gfx, vec![value("SCLK", 500.0, "MHz")]);
+        assert_eq!(info.gpu_load, Some(99));
+    }
+}
@@ -0,0 +1,131 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use super::parse_hwmon;
+
+// APUs report up to three power limits via hwmon at the same time.
+// VanGogh APU reports `slowPPT` (power1) and `fastPPT` (power2),
+// other APUs report the sustained (STAPM) limit as `PPT`.
+// ref: drivers/gpu/drm/amd/pm/amdgpu_pm.c (`amdgpu_hwmon_show_power_label`)
+// ref: drivers/gpu/drm/amd/pm/swsmu/smu11/vangogh_ppt.c
+
+const MAX_HWMON_POWER_INDEX: u32 = 3;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum PowerLimitType {
+    STAPM,
+    FastPPT,
+    SlowPPT,
+}
+
+impl fmt::Display for PowerLimitType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::STAPM => "STAPM",
+            Self::FastPPT => "Fast PPT",
+            Self::SlowPPT => "Slow PPT",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+impl PowerLimitType {
+    fn from_label(s: &str) -> Option<Self> {
+        match s {
+            "PPT" | "STAPM" => Some(Self::STAPM),
+            "fastPPT" => Some(Self::FastPPT),
+            "slowPPT" => Some(Self::SlowPPT),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PowerLimit {
+    pub type_: PowerLimitType,
+    hwmon_index: u32,
+    pub power: Option<u32>, // W
+    pub cap: u32, // W
+    pub default: Option<u32>, // W
+    pub min: Option<u32>, // W
+    pub max: Option<u32>, // W
+}
+
+impl PowerLimit {
+    fn from_hwmon_path(path: &Path, hwmon_index: u32) -> Option<Self> {
+        let label = std::fs::read_to_string(path.join(format!("power{hwmon_index}_label"))).ok()?;
+        let type_ = PowerLimitType::from_label(label.trim_end())?;
+        let [cap, default, min, max] = ["cap", "cap_default", "cap_min", "cap_max"].map(|name| {
+            parse_uw_to_w(path.join(format!("power{hwmon_index}_{name}")))
+        });
+
+        let mut limit = Self {
+            type_,
+            hwmon_index,
+            power: None,
+            cap: cap?,
+            default,
+            min,
+            max,
+        };
+
+        limit.update(path);
+
+        Some(limit)
+    }
+
+    pub fn update(&mut self, path: &Path) {
+        let i = self.hwmon_index;
+
+        self.power = parse_uw_to_w(path.join(format!("power{i}_average")))
+            .or_else(|| parse_uw_to_w(path.join(format!("power{i}_input"))));
+
+        if let Some(cap) = parse_uw_to_w(path.join(format!("power{i}_cap"))) {
+            self.cap = cap;
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PowerLimits {
+    pub stapm: Option<PowerLimit>,
+    pub fast_ppt: Option<PowerLimit>,
+    pub slow_ppt: Option<PowerLimit>,
+}
+
+impl PowerLimits {
+    pub fn from_hwmon_path<P: Into<PathBuf>>(path: P) -> Option<Self> {
+        let path = path.into();
+        let mut limits = Self::default();
+
+        for i in 1..=MAX_HWMON_POWER_INDEX {
+            let Some(limit) = PowerLimit::from_hwmon_path(&path, i) else { continue };
+
+            match limit.type_ {
+                PowerLimitType::STAPM => limits.stapm = Some(limit),
+                PowerLimitType::FastPPT => limits.fast_ppt = Some(limit),
+                PowerLimitType::SlowPPT => limits.slow_ppt = Some(limit),
+            }
+        }
+
+        (limits.iter().count() != 0).then_some(limits)
+    }
+
+    pub fn update<P: Into<PathBuf>>(&mut self, path: P) {
+        let path = path.into();
+
+        for limit in [&mut self.stapm, &mut self.fast_ppt, &mut self.slow_ppt] {
+            let Some(limit) = limit else { continue };
+            limit.update(&path);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PowerLimit> {
+        [&self.stapm, &self.fast_ppt, &self.slow_ppt].into_iter().flatten()
+    }
+}
+
+fn parse_uw_to_w(path: PathBuf) -> Option<u32> {
+    parse_hwmon::<u32, _>(path).map(|v| v.saturating_div(1_000_000))
+}
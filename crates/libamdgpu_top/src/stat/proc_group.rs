@@ -0,0 +1,164 @@
+use std::fs;
+use super::{FdInfoSortType, FdInfoUsage, ProcUsage};
+
+// Aggregate the fdinfo usage of the processes by the group they belong to.
+// The systemd unit is taken from the cgroup v2 path in `/proc/<pid>/cgroup`,
+// or the `name=systemd` hierarchy of cgroup v1.
+// The user is the real UID of the process, the name is looked up in `/etc/passwd`
+// (users from LDAP/NIS are shown as the UID).
+
+// ## Reference
+//  * <https://docs.kernel.org/admin-guide/cgroup-v2.html>
+//  * <https://systemd.io/CGROUP_DELEGATION/>
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FdInfoGroupBy {
+    /// Not grouped
+    #[default]
+    Process,
+    /// systemd slice/scope/service
+    Cgroup,
+    /// UID/username
+    User,
+}
+
+impl FdInfoGroupBy {
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Process => Self::Cgroup,
+            Self::Cgroup => Self::User,
+            Self::User => Self::Process,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Process => "process",
+            Self::Cgroup => "cgroup",
+            Self::User => "user",
+        }
+    }
+}
+
+impl std::str::FromStr for FdInfoGroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "process" | "pid" => Ok(Self::Process),
+            "cgroup" | "slice" => Ok(Self::Cgroup),
+            "user" | "uid" => Ok(Self::User),
+            _ => Err(format!("unknown group: {s:?} (process, cgroup, user)")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FdInfoGroupUsage {
+    pub name: String,
+    pub pids: Vec<i32>,
+    pub usage: FdInfoUsage,
+    /// Raw values, engine busy time delta in ns per interval, memory usage in KiB
+    pub delta: FdInfoUsage,
+    pub cpu_usage: i64, // %
+}
+
+/// Returns `None` for `FdInfoGroupBy::Process`.
+pub fn group_proc_usage(proc_usage: &[ProcUsage], group_by: FdInfoGroupBy) -> Option<Vec<FdInfoGroupUsage>> {
+    if group_by == FdInfoGroupBy::Process { return None }
+
+    let mut groups: Vec<FdInfoGroupUsage> = Vec::new();
+    let users = if group_by == FdInfoGroupBy::User { read_passwd() } else { Vec::new() };
+
+    for pu in proc_usage {
+        let name = match group_by {
+            FdInfoGroupBy::Process => unreachable!(),
+            FdInfoGroupBy::Cgroup => get_cgroup_unit(pu.pid),
+            FdInfoGroupBy::User => get_proc_uid(pu.pid).map(|uid| {
+                users.iter().find(|(id, _)| *id == uid)
+                    .map(|(_, name)| name.clone())
+                    .unwrap_or_else(|| uid.to_string())
+            }),
+        }.unwrap_or_else(|| "unknown".to_string());
+
+        let group = if let Some(group) = groups.iter_mut().find(|g| g.name == name) {
+            group
+        } else {
+            groups.push(FdInfoGroupUsage { name, ..Default::default() });
+            groups.last_mut().unwrap()
+        };
+
+        group.pids.push(pu.pid);
+        group.usage += pu.usage;
+        group.delta += pu.delta;
+        group.cpu_usage += pu.cpu_usage;
+    }
+
+    Some(groups)
+}
+
+/// The systemd unit the process belongs to.
+/// The processes of a user session are grouped into `user-<uid>.slice`,
+/// the others into the first scope/service in the cgroup path (e.g. `docker-<id>.scope`).
+pub fn get_cgroup_unit(pid: i32) -> Option<String> {
+    let s = fs::read_to_string(format!("/proc/{pid}/cgroup")).ok()?;
+    // "0::/system.slice/docker-<id>.scope", "1:name=systemd:/user.slice/user-1000.slice/session-2.scope"
+    let path = s.lines().find_map(|l| l.strip_prefix("0::"))
+        .or_else(|| s.lines().find_map(|l| l.split_once(":name=systemd:").map(|(_, path)| path)))?;
+    let units: Vec<&str> = path.split('/').filter(|u| !u.is_empty()).collect();
+
+    if path.starts_with("/user.slice/") {
+        return units.get(1).map(|u| u.to_string());
+    }
+
+    let unit = units.iter()
+        .find(|u| u.ends_with(".scope") || u.ends_with(".service"))
+        .or_else(|| units.last())
+        .unwrap_or(&"-.slice"); // root
+
+    Some(unit.to_string())
+}
+
+/// The real UID of the process
+pub fn get_proc_uid(pid: i32) -> Option<u32> {
+    let s = fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    // "Uid:\t1000\t1000\t1000\t1000"
+    let uids = s.lines().find_map(|l| l.strip_prefix("Uid:"))?;
+
+    uids.split_whitespace().next()?.parse().ok()
+}
+
+/// (UID, username)
+fn read_passwd() -> Vec<(u32, String)> {
+    let Ok(s) = fs::read_to_string("/etc/passwd") else { return Vec::new() };
+
+    // "name:password:UID:GID:GECOS:directory:shell"
+    s.lines().filter_map(|l| {
+        let mut split = l.split(':');
+        let name = split.next()?;
+        let uid = split.nth(1)?.parse().ok()?;
+
+        Some((uid, name.to_string()))
+    }).collect()
+}
+
+pub fn sort_group_usage(groups: &mut [FdInfoGroupUsage], sort: &FdInfoSortType, reverse: bool) {
+    groups.sort_by(|a, b| {
+        let [a_dec, b_dec] = [a, b].map(|g| g.usage.dec + g.usage.vcn_jpeg);
+        let [a_enc, b_enc] = [a, b].map(|g| g.usage.enc + g.usage.uvd_enc);
+        let ord = match sort {
+            FdInfoSortType::PID => b.name.cmp(&a.name),
+            FdInfoSortType::VRAM => a.usage.vram_usage.cmp(&b.usage.vram_usage),
+            FdInfoSortType::GTT => a.usage.gtt_usage.cmp(&b.usage.gtt_usage),
+            FdInfoSortType::CPU => a.cpu_usage.cmp(&b.cpu_usage),
+            FdInfoSortType::GFX => a.usage.gfx.cmp(&b.usage.gfx),
+            FdInfoSortType::Compute => a.usage.compute.cmp(&b.usage.compute),
+            FdInfoSortType::DMA => a.usage.dma.cmp(&b.usage.dma),
+            FdInfoSortType::Decode => a_dec.cmp(&b_dec),
+            FdInfoSortType::Encode => a_enc.cmp(&b_enc),
+            FdInfoSortType::MediaEngine => (a_dec + a_enc).cmp(&(b_dec + b_enc)),
+        };
+
+        if reverse { ord } else { ord.reverse() }
+    });
+}
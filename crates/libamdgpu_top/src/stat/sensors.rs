@@ -13,11 +13,119 @@ use libdrm_amdgpu_sys::{
         PowerCap,
     },
 };
-use super::parse_hwmon;
+use std::time::{Duration, Instant};
+use super::{is_stale, parse_hwmon, PowerLimits};
+
+/// The state of a sensor in the last update
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum SensorHealth {
+    /// Read in the last update
+    Ok,
+    /// Not reported by the device
+    #[default]
+    NotSupported,
+    /// Reported before, but the read failed in the last update (driver reload, BACO, SMU hang).
+    /// The temperatures keep the last value.
+    Failed,
+}
+
+impl SensorHealth {
+    fn update(&mut self, ok: bool) {
+        *self = match (*self, ok) {
+            (_, true) => Self::Ok,
+            (Self::NotSupported, false) => Self::NotSupported,
+            _ => Self::Failed,
+        };
+    }
+}
+
+impl fmt::Display for SensorHealth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::Ok => "ok",
+            Self::NotSupported => "not supported",
+            Self::Failed => "failed",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SensorStatus {
+    pub health: SensorHealth,
+    /// The time of the last successful read
+    pub updated: Option<Instant>,
+}
+
+impl SensorStatus {
+    fn new(ok: bool, now: Instant) -> Self {
+        if ok {
+            Self { health: SensorHealth::Ok, updated: Some(now) }
+        } else {
+            Self::default()
+        }
+    }
+
+    pub(crate) fn update(&mut self, ok: bool, now: Instant) {
+        self.health.update(ok);
+
+        if ok {
+            self.updated = Some(now);
+        }
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.health == SensorHealth::Failed
+    }
+
+    /// Not read for more than [`STALE_PERIODS`] update intervals
+    pub fn is_stale(&self, interval: Duration) -> bool {
+        is_stale(self.updated, interval)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SensorsStatus {
+    pub sclk: SensorStatus,
+    pub mclk: SensorStatus,
+    pub vddnb: SensorStatus,
+    pub vddgfx: SensorStatus,
+    pub edge_temp: SensorStatus,
+    pub junction_temp: SensorStatus,
+    pub memory_temp: SensorStatus,
+    pub power: SensorStatus,
+    pub fan_rpm: SensorStatus,
+}
+
+impl SensorsStatus {
+    /// The labels are the keys of "Sensors" in the JSON output
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, SensorStatus)> {
+        [
+            ("GFX_SCLK", self.sclk),
+            ("GFX_MCLK", self.mclk),
+            ("VDDNB", self.vddnb),
+            ("VDDGFX", self.vddgfx),
+            ("Edge Temperature", self.edge_temp),
+            ("Junction Temperature", self.junction_temp),
+            ("Memory Temperature", self.memory_temp),
+            ("GFX Power", self.power),
+            ("Fan", self.fan_rpm),
+        ].into_iter()
+    }
+
+    pub fn has_failed(&self) -> bool {
+        self.iter().any(|(_, h)| h.is_failed())
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct Sensors {
+    /// Re-resolved when the directory disappears or a hwmon read fails
+    /// (the hwmon index may change after the driver is reloaded)
     pub hwmon_path: PathBuf,
+    /// `<hwmon_path>/fan1_input`, read every update
+    pub fan_input_path: PathBuf,
     pub is_apu: bool,
     pub vega10_and_later: bool,
     pub current_link: Option<PCI::LINK>,
@@ -35,8 +143,10 @@ pub struct Sensors {
     pub memory_temp: Option<HwmonTemp>,
     pub power: Option<u32>,
     pub power_cap: Option<PowerCap>,
+    pub power_limits: Option<PowerLimits>,
     pub fan_rpm: Option<u32>,
     pub fan_max_rpm: Option<u32>,
+    pub status: SensorsStatus,
 }
 
 impl Sensors {
@@ -99,12 +209,27 @@ impl Sensors {
         let junction_temp = HwmonTemp::from_hwmon_path(&hwmon_path, HwmonTempType::Junction);
         let memory_temp = HwmonTemp::from_hwmon_path(&hwmon_path, HwmonTempType::Memory);
         let power_cap = PowerCap::from_hwmon_path(&hwmon_path);
+        let power_limits = if is_apu { PowerLimits::from_hwmon_path(&hwmon_path) } else { None };
 
-        let fan_rpm = parse_hwmon(hwmon_path.join("fan1_input"));
+        let fan_input_path = hwmon_path.join("fan1_input");
+        let fan_rpm = parse_hwmon(&fan_input_path);
         let fan_max_rpm = parse_hwmon(hwmon_path.join("fan1_max"));
+        let now = Instant::now();
+        let status = SensorsStatus {
+            sclk: SensorStatus::new(sclk.is_some(), now),
+            mclk: SensorStatus::new(mclk.is_some(), now),
+            vddnb: SensorStatus::new(vddnb.is_some(), now),
+            vddgfx: SensorStatus::new(vddgfx.is_some(), now),
+            edge_temp: SensorStatus::new(edge_temp.is_some(), now),
+            junction_temp: SensorStatus::new(junction_temp.is_some(), now),
+            memory_temp: SensorStatus::new(memory_temp.is_some(), now),
+            power: SensorStatus::new(power.is_some(), now),
+            fan_rpm: SensorStatus::new(fan_rpm.is_some(), now),
+        };
 
         Self {
             hwmon_path,
+            fan_input_path,
             is_apu,
             vega10_and_later,
             current_link,
@@ -122,8 +247,10 @@ impl Sensors {
             memory_temp,
             power,
             power_cap,
+            power_limits,
             fan_rpm,
             fan_max_rpm,
+            status,
         }
     }
 
@@ -139,14 +266,84 @@ impl Sensors {
         self.mclk = amdgpu_dev.sensor_info(SENSOR_TYPE::GFX_MCLK).ok();
         self.vddnb = amdgpu_dev.sensor_info(SENSOR_TYPE::VDDNB).ok();
         self.vddgfx = amdgpu_dev.sensor_info(SENSOR_TYPE::VDDGFX).ok();
+        self.power = amdgpu_dev.sensor_info(SENSOR_TYPE::GPU_AVG_POWER).ok();
+
+        let now = Instant::now();
+
+        self.status.sclk.update(self.sclk.is_some(), now);
+        self.status.mclk.update(self.mclk.is_some(), now);
+        self.status.vddnb.update(self.vddnb.is_some(), now);
+        self.status.vddgfx.update(self.vddgfx.is_some(), now);
+        self.status.power.update(self.power.is_some(), now);
+
+        self.update_hwmon();
+    }
+
+    /// Read the temperatures, the fan speed and the power limits from hwmon.
+    pub(crate) fn update_hwmon(&mut self) {
+        let hwmon_failed = [self.status.edge_temp, self.status.junction_temp, self.status.memory_temp, self.status.fan_rpm]
+            .iter()
+            .any(|h| h.is_failed());
 
-        for temp in [&mut self.edge_temp, &mut self.junction_temp, &mut self.memory_temp] {
+        if hwmon_failed || !self.hwmon_path.is_dir() {
+            self.revalidate_hwmon_path();
+        }
+
+        let now = Instant::now();
+
+        for (temp, status) in [
+            (&mut self.edge_temp, &mut self.status.edge_temp),
+            (&mut self.junction_temp, &mut self.status.junction_temp),
+            (&mut self.memory_temp, &mut self.status.memory_temp),
+        ] {
             let Some(temp) = temp else { continue };
-            temp.update(&self.hwmon_path);
+            // `HwmonTemp::update` keeps the last value if the read fails
+            let current = parse_hwmon::<i64, _>(self.hwmon_path.join(temp_input_file_name(&temp.type_)));
+
+            if let Some(current) = current {
+                temp.current = current.saturating_div(1_000);
+            }
+
+            status.update(current.is_some(), now);
         }
 
-        self.power = amdgpu_dev.sensor_info(SENSOR_TYPE::GPU_AVG_POWER).ok();
-        self.fan_rpm = parse_hwmon(self.hwmon_path.join("fan1_input"));
+        if let Some(power_limits) = &mut self.power_limits {
+            power_limits.update(&self.hwmon_path);
+        }
+        self.fan_rpm = parse_hwmon(&self.fan_input_path);
+        self.status.fan_rpm.update(self.fan_rpm.is_some(), now);
+    }
+
+    /// Resolve the hwmon directory of the device again, and re-create the hwmon sensors
+    /// if it has changed. Returns `true` if the path has changed.
+    pub fn revalidate_hwmon_path(&mut self) -> bool {
+        let Some(hwmon_path) = self.bus_info.get_hwmon_path() else { return false };
+
+        if hwmon_path == self.hwmon_path { return false }
+
+        for (temp, type_) in [
+            (&mut self.edge_temp, HwmonTempType::Edge),
+            (&mut self.junction_temp, HwmonTempType::Junction),
+            (&mut self.memory_temp, HwmonTempType::Memory),
+        ] {
+            if let Some(new) = HwmonTemp::from_hwmon_path(&hwmon_path, type_) {
+                *temp = Some(new);
+            }
+        }
+
+        if let Some(power_cap) = PowerCap::from_hwmon_path(&hwmon_path) {
+            self.power_cap = Some(power_cap);
+        }
+
+        if self.is_apu {
+            self.power_limits = PowerLimits::from_hwmon_path(&hwmon_path).or(self.power_limits.take());
+        }
+
+        self.fan_input_path = hwmon_path.join("fan1_input");
+        self.fan_max_rpm = parse_hwmon(hwmon_path.join("fan1_max")).or(self.fan_max_rpm);
+        self.hwmon_path = hwmon_path;
+
+        true
     }
 
     pub fn print_pcie_link(&self) -> Result<String, fmt::Error> {
@@ -227,3 +424,11 @@ impl Sensors {
         Some(PCI::LINK { gen, width })
     }
 }
+
+const fn temp_input_file_name(type_: &HwmonTempType) -> &'static str {
+    match type_ {
+        HwmonTempType::Edge => "temp1_input",
+        HwmonTempType::Junction => "temp2_input",
+        HwmonTempType::Memory => "temp3_input",
+    }
+}
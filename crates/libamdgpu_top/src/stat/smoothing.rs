@@ -0,0 +1,66 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Averaging window for activity values (GRBM, GRBM2, fdinfo)
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum Smoothing {
+    #[default]
+    Instant,
+    EMA1s,
+    EMA5s,
+}
+
+impl Smoothing {
+    pub const ALL: [Self; 3] = [Self::Instant, Self::EMA1s, Self::EMA5s];
+
+    const fn time_constant(&self) -> Option<Duration> {
+        match self {
+            Self::Instant => None,
+            Self::EMA1s => Some(Duration::from_secs(1)),
+            Self::EMA5s => Some(Duration::from_secs(5)),
+        }
+    }
+
+    /// Exponential moving average with the time constant of the window
+    pub fn apply(&self, pre: f32, cur: f32, interval: Duration) -> f32 {
+        let Some(tau) = self.time_constant() else { return cur };
+        let alpha = 1.0 - (-interval.as_secs_f32() / tau.as_secs_f32()).exp();
+
+        pre + alpha * (cur - pre)
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Instant => Self::EMA1s,
+            Self::EMA1s => Self::EMA5s,
+            Self::EMA5s => Self::Instant,
+        }
+    }
+}
+
+impl fmt::Display for Smoothing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::Instant => "Instant",
+            Self::EMA1s => "1s EMA",
+            Self::EMA5s => "5s EMA",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+impl FromStr for Smoothing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "instant" | "0" => Ok(Self::Instant),
+            "1s" | "1" => Ok(Self::EMA1s),
+            "5s" | "5" => Ok(Self::EMA5s),
+            _ => Err(format!("unknown smoothing window: {s:?} (instant, 1s, 5s)")),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+// ref: drivers/gpu/drm/amd/amdgpu/amdgpu_fence.c (`amdgpu_debugfs_fence_info_show`)
+
+// Requires root (debugfs).
+
+use std::fmt;
+use std::io;
+use std::fs;
+use super::PmInfo;
+
+const BASE: &str = "/sys/kernel/debug/dri";
+const MEDIA_RING_PREFIX: &[&str] = &["vcn", "jpeg", "uvd", "vce"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VcnPowerState {
+    PowerGated,
+    Idle,
+    Busy,
+}
+
+impl fmt::Display for VcnPowerState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Self::PowerGated => "Power Gated",
+            Self::Idle => "Idle",
+            Self::Busy => "Busy",
+        };
+
+        write!(f, "{s}")
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RingFence {
+    pub name: String,
+    pub last_signaled: u32,
+    pub last_emitted: u32,
+}
+
+impl RingFence {
+    /// The ring has submitted jobs that have not been signaled yet.
+    pub fn is_busy(&self) -> bool {
+        self.last_signaled != self.last_emitted
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct VcnState {
+    instance: u32,
+    pub power_gated: Option<bool>,
+    pub vclk: Option<u32>, // MHz
+    pub dclk: Option<u32>, // MHz
+    pub rings: Vec<RingFence>, // VCN, JPEG, UVD and VCE rings
+}
+
+impl VcnState {
+    pub fn get(instance: u32) -> io::Result<Self> {
+        let mut s = Self { instance, ..Default::default() };
+        s.update()?;
+
+        Ok(s)
+    }
+
+    pub fn update(&mut self) -> io::Result<()> {
+        let fence_info = fs::read_to_string(format!("{BASE}/{}/amdgpu_fence_info", self.instance))?;
+        self.rings = parse_fence_info(&fence_info)
+            .into_iter()
+            .filter(|ring| MEDIA_RING_PREFIX.iter().any(|p| ring.name.starts_with(p)))
+            .collect();
+
+        // amdgpu_pm_info is not available on some configurations (e.g. SR-IOV VF)
+        if let Ok(pm_info) = PmInfo::get(self.instance) {
+            self.set_pm_info(&pm_info);
+        }
+
+        Ok(())
+    }
+
+    fn set_pm_info(&mut self, pm_info: &PmInfo) {
+        let uvd = pm_info.uvd.as_ref();
+
+        self.power_gated = uvd.map(|uvd| !uvd.enabled);
+        self.vclk = uvd.and_then(|uvd| uvd.get_clock("VCLK"));
+        self.dclk = uvd.and_then(|uvd| uvd.get_clock("DCLK"));
+    }
+
+    pub fn busy_rings(&self) -> impl Iterator<Item = &RingFence> {
+        self.rings.iter().filter(|ring| ring.is_busy())
+    }
+
+    pub fn power_state(&self) -> Option<VcnPowerState> {
+        if self.power_gated == Some(true) {
+            return Some(VcnPowerState::PowerGated);
+        }
+
+        if self.busy_rings().next().is_some() {
+            return Some(VcnPowerState::Busy);
+        }
+
+        (self.power_gated.is_some() || !self.rings.is_empty()).then_some(VcnPowerState::Idle)
+    }
+}
+
+fn parse_fence_info(s: &str) -> Vec<RingFence> {
+    let mut rings: Vec<RingFence> = Vec::new();
+    // the gfx ring also reports the trailing fence as "Last emitted"
+    let mut has_emitted = false;
+
+    for line in s.lines() {
+        if let Some(header) = line.strip_prefix("--- ring ") {
+            let Some(name) = header.split_once('(').and_then(|(_, n)| n.split_once(')')) else { continue };
+
+            rings.push(RingFence { name: name.0.to_string(), ..Default::default() });
+            has_emitted = false;
+            continue;
+        }
+
+        let Some(ring) = rings.last_mut() else { continue };
+        let Some((label, val)) = line.split_once("0x") else { continue };
+        let Ok(val) = u32::from_str_radix(val.trim(), 16) else { continue };
+
+        match label.trim() {
+            "Last signaled fence" => ring.last_signaled = val,
+            "Last emitted" if !has_emitted => {
+                ring.last_emitted = val;
+                has_emitted = true;
+            },
+            _ => {},
+        }
+    }
+
+    rings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stat::pm_info::MediaBlockState;
+
+    fn media_rings(s: &str) -> Vec<RingFence> {
+        parse_fence_info(s)
+            .into_iter()
+            .filter(|ring| MEDIA_RING_PREFIX.iter().any(|p| ring.name.starts_with(p)))
+            .collect()
+    }
+
+    fn names(rings: &[RingFence]) -> Vec<&str> {
+        rings.iter().map(|ring| ring.name.as_str()).collect()
+    }
+
+    #[test]
+    fn navi31_vcn_unified() {
+        let rings = parse_fence_info(include_str!("../../testdata/fence_info/navi31.txt"));
+
+        assert_eq!(rings.len(), 9);
+        // the trailing fence of the gfx ring is not the last emitted fence
+        assert_eq!(rings[0], RingFence { name: "gfx_0.0.0".to_string(), last_signaled: 0x12a4f, last_emitted: 0x12a4f });
+        assert!(!rings[0].is_busy());
+        assert_eq!(rings[5], RingFence { name: "vcn_unified_0".to_string(), last_signaled: 0x81, last_emitted: 0x83 });
+
+        let media = media_rings(include_str!("../../testdata/fence_info/navi31.txt"));
+        assert_eq!(names(&media), ["vcn_unified_0", "vcn_unified_1", "jpeg_dec"]);
+    }
+
+    #[test]
+    fn renoir_vcn_dec_enc() {
+        let media = media_rings(include_str!("../../testdata/fence_info/renoir.txt"));
+
+        assert_eq!(names(&media), ["vcn_dec", "vcn_enc0", "vcn_enc1", "jpeg_dec"]);
+        assert!(media[0].is_busy());
+        assert!(media[1..].iter().all(|ring| !ring.is_busy()));
+    }
+
+    #[test]
+    fn polaris_uvd_vce() {
+        let media = media_rings(include_str!("../../testdata/fence_info/polaris10.txt"));
+
+        assert_eq!(names(&media), ["uvd", "uvd_enc0", "uvd_enc1", "vce0", "vce1", "vce2"]);
+        assert_eq!(media.iter().filter(|ring| ring.is_busy()).count(), 1);
+        assert_eq!(media[3].last_emitted, 0x9);
+    }
+
+    #[test]
+    fn truncated() {
+        let s = include_str!("../../testdata/fence_info/navi31.txt");
+
+        for len in 0..s.len() {
+            assert!(parse_fence_info(&s[..len]).len() <= 9);
+        }
+
+        // cut in the middle of the header and of the value
+        let rings = parse_fence_info("--- ring 5 (vcn_unified_0) ---\nLast signaled fence          0x00000081\nLast emitted                 0x0000\n--- ring 6 (vcn_un");
+        assert_eq!(rings, vec![RingFence { name: "vcn_unified_0".to_string(), last_signaled: 0x81, last_emitted: 0 }]);
+    }
+
+    #[test]
+    fn odd_input() {
+        assert!(parse_fence_info("").is_empty());
+        // values before the first ring header
+        assert!(parse_fence_info("Last signaled fence          0x00000001\n").is_empty());
+
+        let rings = parse_fence_info("--- ring 0 (vcn_dec) ---\nLast signaled fence          0xzz\nLast emitted                 0xffffffff\nFoo                          0x1\n");
+        assert_eq!(rings, vec![RingFence { name: "vcn_dec".to_string(), last_signaled: 0, last_emitted: 0xffffffff }]);
+    }
+
+    #[test]
+    fn power_state() {
+        let busy = VcnState { rings: media_rings(include_str!("../../testdata/fence_info/renoir.txt")), ..Default::default() };
+        assert_eq!(busy.power_state(), Some(VcnPowerState::Busy));
+
+        let mut state = VcnState::default();
+        assert_eq!(state.power_state(), None);
+
+        let mut pm_info = PmInfo {
+            uvd: Some(MediaBlockState { enabled: false, clocks: Vec::new() }),
+            ..Default::default()
+        };
+        state.set_pm_info(&pm_info);
+        assert_eq!(state.power_state(), Some(VcnPowerState::PowerGated));
+
+        pm_info = PmInfo::parse(include_str!("../../testdata/pm_info/renoir.txt"));
+        state.set_pm_info(&pm_info);
+        assert_eq!((state.vclk, state.dclk), (Some(410), Some(370)));
+        assert_eq!(state.power_state(), Some(VcnPowerState::Idle));
+    }
+}
@@ -0,0 +1,185 @@
+use crate::PCI;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// XGMI (the inter-GPU links of the MI-series) hive topology and errors, from sysfs.
+// The sysfs files are created only for the devices in an XGMI hive.
+// `xgmi_num_hops` and `xgmi_num_links` list the values for each node of the hive,
+// the nodes are assumed to be in the order of `xgmi_physical_id`.
+// The driver exposes the XGMI error count per device, not per link, and clears it when it is read,
+// so it is accumulated here.
+// The link width/speed are available in `gpu_metrics` v1.4 and v1.5 (MI300), which are not parsed
+// by libdrm_amdgpu_sys, so they are read from the raw table.
+
+// ## Reference
+//  * <https://github.com/ROCm/rocm_smi_lib> (`rocm-smi --showtopo`)
+//  * Linux Kernel
+//    * `drivers/gpu/drm/amd/amdgpu/amdgpu_xgmi.c`
+//      * `amdgpu_xgmi_show_num_hops`, `amdgpu_xgmi_show_num_links`, `amdgpu_xgmi_show_error`
+//    * `drivers/gpu/drm/amd/include/kgd_pp_interface.h`
+//      * `gpu_metrics_v1_4`, `gpu_metrics_v1_5`
+
+const AMDGPU_DRIVER_PATH: &str = "/sys/bus/pci/drivers/amdgpu";
+// the upper bits are flags
+const NUM_HOPS_MASK: u8 = 0xF;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XgmiNode {
+    pub pci_bus: PCI::BUS_INFO,
+    pub hive_id: u64,
+    /// `xgmi_device_id`
+    pub node_id: u64,
+    pub physical_id: u32,
+}
+
+impl XgmiNode {
+    /// Returns `None` if the device is not in an XGMI hive.
+    pub fn from_pci(pci_bus: &PCI::BUS_INFO) -> Option<Self> {
+        let sysfs_path = pci_bus.get_sysfs_path();
+        let hive_id = read_u64(sysfs_path.join("xgmi_hive_info/xgmi_hive_id"))?;
+
+        if hive_id == 0 { return None }
+
+        Some(Self {
+            pci_bus: *pci_bus,
+            hive_id,
+            node_id: read_u64(sysfs_path.join("xgmi_device_id"))?,
+            physical_id: read_u64(sysfs_path.join("xgmi_physical_id"))? as u32,
+        })
+    }
+
+    /// All nodes of the hive, sorted by the physical id
+    pub fn hive_nodes(&self) -> Vec<Self> {
+        let Ok(dir) = fs::read_dir(AMDGPU_DRIVER_PATH) else { return vec![self.clone()] };
+
+        let mut nodes: Vec<Self> = dir.flatten().filter_map(|entry| {
+            let pci = entry.file_name().into_string().ok()?.parse::<PCI::BUS_INFO>().ok()?;
+
+            Self::from_pci(&pci).filter(|node| node.hive_id == self.hive_id)
+        }).collect();
+
+        nodes.sort_by_key(|node| node.physical_id);
+
+        nodes
+    }
+}
+
+/// A link from the device to the other node of the hive
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XgmiPeer {
+    pub physical_id: u32,
+    /// `None` if the node is not bound to the amdgpu driver on this system
+    pub pci_bus: Option<PCI::BUS_INFO>,
+    pub num_hops: u8,
+    pub num_links: Option<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct XgmiStat {
+    sysfs_path: PathBuf,
+    pub node: XgmiNode,
+    pub peers: Vec<XgmiPeer>,
+    /// lanes
+    pub link_width: Option<u16>,
+    /// Gbps
+    pub link_speed: Option<u16>,
+    /// Accumulated from `xgmi_error` since the start
+    pub errors: u64,
+    /// `ras/xgmi_wafl_err_count`, correctable
+    pub wafl_ce: Option<u64>,
+    /// `ras/xgmi_wafl_err_count`, uncorrectable
+    pub wafl_ue: Option<u64>,
+}
+
+impl XgmiStat {
+    /// Returns `None` if the device is not in an XGMI hive.
+    pub fn new(pci_bus: &PCI::BUS_INFO) -> Option<Self> {
+        let node = XgmiNode::from_pci(pci_bus)?;
+        let sysfs_path = pci_bus.get_sysfs_path();
+        let hive_nodes = node.hive_nodes();
+        let num_hops = read_hex_list(sysfs_path.join("xgmi_num_hops"));
+        let num_links = read_hex_list(sysfs_path.join("xgmi_num_links"));
+
+        let peers = num_hops.iter().enumerate().filter_map(|(i, hops)| {
+            let physical_id = i as u32;
+
+            if physical_id == node.physical_id { return None }
+
+            Some(XgmiPeer {
+                physical_id,
+                pci_bus: hive_nodes.iter().find(|n| n.physical_id == physical_id).map(|n| n.pci_bus),
+                num_hops: hops & NUM_HOPS_MASK,
+                num_links: num_links.get(i).copied(),
+            })
+        }).collect();
+
+        let mut stat = Self {
+            sysfs_path,
+            node,
+            peers,
+            link_width: None,
+            link_speed: None,
+            errors: 0,
+            wafl_ce: None,
+            wafl_ue: None,
+        };
+
+        stat.update();
+
+        Some(stat)
+    }
+
+    pub fn update(&mut self) {
+        if let Some(err) = read_u64(self.sysfs_path.join("xgmi_error")) {
+            self.errors += err;
+        }
+
+        if let Ok(s) = fs::read_to_string(self.sysfs_path.join("ras/xgmi_wafl_err_count")) {
+            // "ue: 0\nce: 0\n"
+            for line in s.lines() {
+                let Some((name, val)) = line.split_once(':') else { continue };
+                let val = val.trim().parse::<u64>().ok();
+
+                match name.trim() {
+                    "ue" => self.wafl_ue = val,
+                    "ce" => self.wafl_ce = val,
+                    _ => {},
+                }
+            }
+        }
+
+        if let Some((width, speed)) = link_from_raw_gpu_metrics(&self.sysfs_path) {
+            self.link_width = width;
+            self.link_speed = speed;
+        }
+    }
+}
+
+fn read_u64<P: AsRef<Path>>(path: P) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// `"00 01 01 01 \n"`
+fn read_hex_list<P: AsRef<Path>>(path: P) -> Vec<u8> {
+    let Ok(s) = fs::read_to_string(path) else { return Vec::new() };
+
+    s.split_whitespace().filter_map(|v| u8::from_str_radix(v, 16).ok()).collect()
+}
+
+fn link_from_raw_gpu_metrics(sysfs_path: &Path) -> Option<(Option<u16>, Option<u16>)> {
+    let raw = fs::read(sysfs_path.join("gpu_metrics")).ok()?;
+    // offset of `xgmi_link_width`, followed by `xgmi_link_speed`
+    let offset = match (raw.get(2)?, raw.get(3)?) {
+        (1, 4) => 52,
+        // `jpeg_activity[32]` is added
+        (1, 5) => 116,
+        _ => return None,
+    };
+    let get = |pos: usize| -> Option<u16> {
+        let v = u16::from_le_bytes([*raw.get(pos)?, *raw.get(pos+1)?]);
+
+        (v != u16::MAX).then_some(v)
+    };
+
+    Some((get(offset), get(offset+2)))
+}
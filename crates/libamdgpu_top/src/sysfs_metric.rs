@@ -0,0 +1,104 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use crate::AMDGPU::{HwmonTemp, HwmonTempType};
+use crate::stat::parse_hwmon;
+
+/// Basic metrics read directly from sysfs/hwmon, without opening the device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SysfsMetric {
+    Temp,
+    Power,
+    GfxActivity,
+    Vram,
+    Sclk,
+    Mclk,
+    FanRpm,
+}
+
+impl SysfsMetric {
+    pub const ALL: &'static [Self] = &[
+        Self::Temp,
+        Self::Power,
+        Self::GfxActivity,
+        Self::Vram,
+        Self::Sclk,
+        Self::Mclk,
+        Self::FanRpm,
+    ];
+
+    /// Field name for journald and other backends, without the `AMDGPU_` prefix
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Temp => "TEMP",
+            Self::Power => "POWER",
+            Self::GfxActivity => "GFX_ACTIVITY",
+            Self::Vram => "VRAM_USED",
+            Self::Sclk => "SCLK",
+            Self::Mclk => "MCLK",
+            Self::FanRpm => "FAN_RPM",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Temp => "C",
+            Self::Power => "W",
+            Self::GfxActivity => "%",
+            Self::Vram => "MiB",
+            Self::Sclk | Self::Mclk => "MHz",
+            Self::FanRpm => "RPM",
+        }
+    }
+
+    pub fn read(&self, sysfs_path: &Path, hwmon_path: &Path) -> Option<u64> {
+        match self {
+            Self::Temp => [HwmonTempType::Junction, HwmonTempType::Edge]
+                .into_iter()
+                .find_map(|type_| HwmonTemp::from_hwmon_path(hwmon_path, type_))
+                .and_then(|temp| u64::try_from(temp.current).ok()),
+            Self::Power => ["power1_average", "power1_input"]
+                .into_iter()
+                .find_map(|name| parse_hwmon::<u64, _>(hwmon_path.join(name)))
+                .map(|uw| uw / 1_000_000),
+            Self::GfxActivity => parse_hwmon(sysfs_path.join("gpu_busy_percent")),
+            Self::Vram => parse_hwmon::<u64, _>(sysfs_path.join("mem_info_vram_used"))
+                .map(|b| b >> 20),
+            Self::Sclk => parse_hwmon::<u64, _>(hwmon_path.join("freq1_input"))
+                .map(|hz| hz / 1_000_000),
+            Self::Mclk => parse_hwmon::<u64, _>(hwmon_path.join("freq2_input"))
+                .map(|hz| hz / 1_000_000),
+            Self::FanRpm => parse_hwmon(hwmon_path.join("fan1_input")),
+        }
+    }
+
+    /// Comma-separated list, e.g. `temp,power`
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        s.split(',').map(|f| f.trim().parse()).collect()
+    }
+}
+
+impl FromStr for SysfsMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let f = match s.to_ascii_lowercase().as_str() {
+            "temp" => Self::Temp,
+            "power" => Self::Power,
+            "gfx" | "gfx_activity" => Self::GfxActivity,
+            "vram" => Self::Vram,
+            "sclk" => Self::Sclk,
+            "mclk" => Self::Mclk,
+            "fan" | "fan_rpm" => Self::FanRpm,
+            _ => return Err(format!("invalid field: {s:?} (temp|power|gfx|vram|sclk|mclk|fan)")),
+        };
+
+        Ok(f)
+    }
+}
+
+impl fmt::Display for SysfsMetric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.key().to_ascii_lowercase())
+    }
+}
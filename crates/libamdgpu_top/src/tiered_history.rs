@@ -0,0 +1,225 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use crate::parse_duration;
+
+/// The samples are kept at `resolution` (min/avg/max of the samples in each interval) for `span`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HistoryTier {
+    pub resolution: Duration,
+    pub span: Duration,
+}
+
+impl HistoryTier {
+    pub const fn new(resolution: Duration, span: Duration) -> Self {
+        Self { resolution, span }
+    }
+
+    /// The max number of the points in the tier
+    pub fn capacity(&self) -> usize {
+        self.span.as_secs_f64().div_euclid(self.resolution.as_secs_f64()).max(1.0) as usize
+    }
+}
+
+/// `--history-tiers <resolution>:<span>,..`, from the finest to the coarsest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryTiers(pub Vec<HistoryTier>);
+
+impl Default for HistoryTiers {
+    /// 1s for 10 minutes, 10s for 2 hours, 1m for 24 hours
+    fn default() -> Self {
+        Self(vec![
+            HistoryTier::new(Duration::from_secs(1), Duration::from_secs(10 * 60)),
+            HistoryTier::new(Duration::from_secs(10), Duration::from_secs(2 * 60 * 60)),
+            HistoryTier::new(Duration::from_secs(60), Duration::from_secs(24 * 60 * 60)),
+        ])
+    }
+}
+
+impl FromStr for HistoryTiers {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid history tiers: {s:?} (<resolution>:<span>,.. e.g. 1s:10m,10s:2h,1m:24h)");
+        let mut tiers = Vec::new();
+
+        for tier in s.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let (resolution, span) = tier.split_once(':').ok_or_else(err)?;
+            let [resolution, span] = [resolution, span].map(parse_duration);
+            let tier = HistoryTier::new(resolution?, span?);
+
+            if tier.resolution.is_zero() || tier.span < tier.resolution {
+                return Err(err());
+            }
+
+            // the coarser tier must cover a longer span
+            if tiers.last().is_some_and(|last: &HistoryTier| tier.resolution <= last.resolution || tier.span <= last.span) {
+                return Err(err());
+            }
+
+            tiers.push(tier);
+        }
+
+        if tiers.is_empty() {
+            return Err(err());
+        }
+
+        Ok(Self(tiers))
+    }
+}
+
+impl fmt::Display for HistoryTiers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, tier) in self.0.iter().enumerate() {
+            if i != 0 { write!(f, ",")? }
+
+            write!(f, "{}s:{}s", tier.resolution.as_secs(), tier.span.as_secs())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HistoryPoint {
+    /// The start of the interval, seconds since the start of the history
+    pub sec: f64,
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+}
+
+#[derive(Clone, Debug)]
+struct Bucket {
+    index: u64,
+    min: f64,
+    max: f64,
+    sum: f64,
+    count: u32,
+}
+
+impl Bucket {
+    fn new(index: u64, val: f64) -> Self {
+        Self { index, min: val, max: val, sum: val, count: 1 }
+    }
+
+    fn add(&mut self, val: f64) {
+        self.min = self.min.min(val);
+        self.max = self.max.max(val);
+        self.sum += val;
+        self.count += 1;
+    }
+
+    fn point(&self, resolution: f64) -> HistoryPoint {
+        HistoryPoint {
+            sec: self.index as f64 * resolution,
+            min: self.min,
+            avg: self.sum / self.count as f64,
+            max: self.max,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TierHistory {
+    pub tier: HistoryTier,
+    points: VecDeque<HistoryPoint>,
+    /// The current interval, not completed yet
+    pending: Option<Bucket>,
+}
+
+impl TierHistory {
+    fn new(tier: HistoryTier) -> Self {
+        Self { tier, points: VecDeque::with_capacity(tier.capacity()), pending: None }
+    }
+
+    fn add(&mut self, sec: f64, val: f64) {
+        let resolution = self.tier.resolution.as_secs_f64();
+        let index = (sec / resolution).floor() as u64;
+
+        match &mut self.pending {
+            Some(bucket) if bucket.index == index => bucket.add(val),
+            pending => {
+                if let Some(bucket) = pending.replace(Bucket::new(index, val)) {
+                    if self.points.len() >= self.tier.capacity() {
+                        self.points.pop_front();
+                    }
+
+                    self.points.push_back(bucket.point(resolution));
+                }
+            },
+        }
+    }
+
+    /// The completed intervals and the current one, in time order
+    pub fn points(&self) -> impl Iterator<Item = HistoryPoint> + '_ {
+        let resolution = self.tier.resolution.as_secs_f64();
+
+        self.points.iter().copied().chain(self.pending.iter().map(move |b| b.point(resolution)))
+    }
+
+    /// The start of the oldest point
+    pub fn start(&self) -> Option<f64> {
+        let resolution = self.tier.resolution.as_secs_f64();
+
+        self.points.front().map(|p| p.sec).or(self.pending.as_ref().map(|b| b.index as f64 * resolution))
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len() + self.pending.is_some() as usize
+    }
+
+    /// No points are dropped yet, or the oldest point is not after `sec`
+    pub fn covers(&self, sec: f64) -> bool {
+        self.points.len() < self.tier.capacity() || self.start().is_some_and(|start| start <= sec)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The history of a metric at multiple resolutions, the memory usage is bounded by the spans of the tiers.
+/// Each sample is added to all the tiers, so the coarser tiers do not lose the peaks of the finer ones (min/max).
+#[derive(Clone, Debug)]
+pub struct TieredHistory {
+    tiers: Vec<TierHistory>,
+}
+
+impl TieredHistory {
+    pub fn new(tiers: &HistoryTiers) -> Self {
+        Self { tiers: tiers.0.iter().copied().map(TierHistory::new).collect() }
+    }
+
+    /// `sec`: seconds since the start of the history
+    pub fn add(&mut self, sec: f64, val: f64) {
+        for tier in self.tiers.iter_mut() {
+            tier.add(sec, val);
+        }
+    }
+
+    /// From the finest to the coarsest
+    pub fn tiers(&self) -> &[TierHistory] {
+        &self.tiers
+    }
+
+    pub fn latest(&self) -> Option<HistoryPoint> {
+        self.tiers.first()?.points().last()
+    }
+
+    /// The start of the whole history that is kept
+    pub fn start(&self) -> Option<f64> {
+        self.tiers.last()?.start()
+    }
+
+    /// Level of detail: the finest tier which covers `start..end` within `max_points`,
+    /// otherwise the coarsest tier.
+    pub fn select(&self, start: f64, end: f64, max_points: usize) -> Option<&TierHistory> {
+        self.tiers.iter().find(|t| {
+            let points = (end - start) / t.tier.resolution.as_secs_f64();
+
+            t.covers(start) && points <= max_points as f64
+        }).or(self.tiers.last())
+    }
+}
@@ -0,0 +1,338 @@
+// TLS for `--listen`, `--serve` and `--connect tls://<host>:<port>`, with rustls (the "tls" feature).
+// The server uses the certificate chain and the private key (PEM) set by `cert` and `key` in `server.conf`.
+// The client verifies the server with the CA certificates (PEM) in `$AMDGPU_TOP_TLS_CA`,
+// or in the system bundle (`$SSL_CERT_FILE`, `/etc/ssl/certs/ca-certificates.crt`, ...).
+// Without the "tls" feature, `Stream` is only a plain `TcpStream` and the TLS setup returns `Unsupported`.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+
+/// The env var of the CA certificates (PEM) for `--connect tls://<host>:<port>`
+pub const TLS_CA_ENV: &str = "AMDGPU_TOP_TLS_CA";
+
+/// The scheme of `--connect` for TLS
+pub const TLS_SCHEME: &str = "tls://";
+
+#[cfg(feature = "tls")]
+const SYSTEM_CA_BUNDLES: &[&str] = &[
+    "/etc/ssl/certs/ca-certificates.crt", // Debian, Ubuntu, Arch
+    "/etc/pki/tls/certs/ca-bundle.crt", // Fedora, RHEL
+    "/etc/ssl/ca-bundle.pem", // openSUSE
+    "/etc/ssl/cert.pem", // Alpine
+];
+
+/// A connection of the servers and `--connect`, plain TCP or TLS over TCP
+pub enum Stream {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Server(Box<rustls::StreamOwned<rustls::ServerConnection, TcpStream>>),
+    #[cfg(feature = "tls")]
+    Client(Box<rustls::StreamOwned<rustls::ClientConnection, TcpStream>>),
+}
+
+impl Stream {
+    /// The underlying TCP stream, to set the timeouts and to shut down
+    pub fn tcp(&self) -> &TcpStream {
+        match self {
+            Self::Plain(s) => s,
+            #[cfg(feature = "tls")]
+            Self::Server(s) => s.get_ref(),
+            #[cfg(feature = "tls")]
+            Self::Client(s) => s.get_ref(),
+        }
+    }
+
+    pub fn is_tls(&self) -> bool {
+        !matches!(self, Self::Plain(_))
+    }
+
+    /// Send `close_notify` for TLS, then flush
+    pub fn close(&mut self) -> io::Result<()> {
+        #[cfg(feature = "tls")]
+        match self {
+            Self::Plain(_) => {},
+            Self::Server(s) => s.conn.send_close_notify(),
+            Self::Client(s) => s.conn.send_close_notify(),
+        }
+
+        self.flush()
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(s) => s.read(buf),
+            #[cfg(feature = "tls")]
+            Self::Server(s) => s.read(buf),
+            #[cfg(feature = "tls")]
+            Self::Client(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(s) => s.write(buf),
+            #[cfg(feature = "tls")]
+            Self::Server(s) => s.write(buf),
+            #[cfg(feature = "tls")]
+            Self::Client(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(s) => s.flush(),
+            #[cfg(feature = "tls")]
+            Self::Server(s) => s.flush(),
+            #[cfg(feature = "tls")]
+            Self::Client(s) => s.flush(),
+        }
+    }
+}
+
+/// The TLS server config, shared by the connections of `--listen` or `--serve`
+#[derive(Clone)]
+pub struct TlsAcceptor {
+    #[cfg(feature = "tls")]
+    config: Arc<rustls::ServerConfig>,
+    #[cfg(not(feature = "tls"))]
+    never: std::convert::Infallible,
+}
+
+impl TlsAcceptor {
+    /// Load the certificate chain and the private key (PEM)
+    #[cfg(feature = "tls")]
+    pub fn new(cert: &Path, key: &Path) -> io::Result<Self> {
+        let certs = rustls_pemfile::certs(&mut open_pem(cert)?)
+            .collect::<io::Result<Vec<_>>>()?;
+
+        if certs.is_empty() {
+            return Err(invalid_data(format!("no certificate in {cert:?}")));
+        }
+
+        let key = rustls_pemfile::private_key(&mut open_pem(key)?)?
+            .ok_or_else(|| invalid_data(format!("no private key in {key:?}")))?;
+        let config = rustls::ServerConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .map_err(io::Error::other)?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(invalid_data)?;
+
+        Ok(Self { config: Arc::new(config) })
+    }
+
+    #[cfg(not(feature = "tls"))]
+    pub fn new(_cert: &Path, _key: &Path) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "TLS (`cert` and `key` in server.conf) requires amdgpu_top built with the \"tls\" feature",
+        ))
+    }
+
+    /// Wrap an accepted connection, the handshake is done by the first read or write
+    #[cfg(feature = "tls")]
+    pub fn accept(&self, tcp: TcpStream) -> io::Result<Stream> {
+        let conn = rustls::ServerConnection::new(self.config.clone()).map_err(io::Error::other)?;
+
+        Ok(Stream::Server(Box::new(rustls::StreamOwned::new(conn, tcp))))
+    }
+
+    #[cfg(not(feature = "tls"))]
+    pub fn accept(&self, _tcp: TcpStream) -> io::Result<Stream> {
+        match self.never {}
+    }
+}
+
+/// Connect to `<host>:<port>` with TLS, the server name is `<host>` (without `[]` for IPv6)
+#[cfg(feature = "tls")]
+pub fn connect(addr: &str) -> io::Result<Stream> {
+    let ca = std::env::var_os(TLS_CA_ENV).map(std::path::PathBuf::from);
+
+    connect_with_ca(addr, ca.as_deref())
+}
+
+#[cfg(not(feature = "tls"))]
+pub fn connect(_addr: &str) -> io::Result<Stream> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "`tls://` requires amdgpu_top built with the \"tls\" feature",
+    ))
+}
+
+/// `ca`: the CA certificates (PEM), or `None` for the system bundle
+#[cfg(feature = "tls")]
+pub fn connect_with_ca(addr: &str, ca: Option<&Path>) -> io::Result<Stream> {
+    let host = server_name(addr)?;
+    let name = rustls::pki_types::ServerName::try_from(host.to_string())
+        .map_err(|e| invalid_input(format!("{host:?}: {e}")))?;
+    let mut roots = rustls::RootCertStore::empty();
+
+    for cert in rustls_pemfile::certs(&mut open_pem(&ca_path(ca)?)?) {
+        // skip the certificates rustls does not support, as the system bundle may have
+        let _ = roots.add(cert?);
+    }
+
+    if roots.is_empty() {
+        return Err(invalid_data("no CA certificate"));
+    }
+
+    let config = rustls::ClientConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .map_err(io::Error::other)?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let conn = rustls::ClientConnection::new(Arc::new(config), name).map_err(io::Error::other)?;
+    let tcp = TcpStream::connect(addr)?;
+
+    Ok(Stream::Client(Box::new(rustls::StreamOwned::new(conn, tcp))))
+}
+
+/// `<host>` of `<host>:<port>`, `[<ipv6>]:<port>` -> `<ipv6>`
+pub fn server_name(addr: &str) -> io::Result<&str> {
+    let (host, port) = addr.rsplit_once(':')
+        .ok_or_else(|| invalid_input(format!("{addr:?}: expected <host>:<port>")))?;
+    let host = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')).unwrap_or(host);
+
+    if host.is_empty() || port.parse::<u16>().is_err() {
+        return Err(invalid_input(format!("{addr:?}: expected <host>:<port>")));
+    }
+
+    Ok(host)
+}
+
+#[cfg(feature = "tls")]
+fn ca_path(ca: Option<&Path>) -> io::Result<std::path::PathBuf> {
+    if let Some(ca) = ca {
+        return Ok(ca.to_path_buf());
+    }
+
+    std::env::var_os("SSL_CERT_FILE")
+        .map(std::path::PathBuf::from)
+        .into_iter()
+        .chain(SYSTEM_CA_BUNDLES.iter().map(std::path::PathBuf::from))
+        .find(|path| path.is_file())
+        .ok_or_else(|| io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no CA certificates, set ${TLS_CA_ENV}"),
+        ))
+}
+
+#[cfg(feature = "tls")]
+fn provider() -> Arc<rustls::crypto::CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+#[cfg(feature = "tls")]
+fn open_pem(path: &Path) -> io::Result<io::BufReader<std::fs::File>> {
+    std::fs::File::open(path)
+        .map(io::BufReader::new)
+        .map_err(|e| io::Error::new(e.kind(), format!("{path:?}: {e}")))
+}
+
+#[cfg(feature = "tls")]
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_name_of_addr() {
+        assert_eq!(server_name("localhost:8080").unwrap(), "localhost");
+        assert_eq!(server_name("192.168.0.2:8080").unwrap(), "192.168.0.2");
+        assert_eq!(server_name("[::1]:8080").unwrap(), "::1");
+        assert!(server_name("localhost").is_err());
+        assert!(server_name(":8080").is_err());
+        assert!(server_name("localhost:http").is_err());
+    }
+
+    #[cfg(feature = "tls")]
+    fn testdata(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/tls").join(name)
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn round_trip() {
+        use std::io::{BufRead, BufReader};
+        use std::net::TcpListener;
+
+        let acceptor = TlsAcceptor::new(&testdata("cert.pem"), &testdata("key.pem")).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = std::thread::spawn(move || {
+            let (tcp, _) = listener.accept().unwrap();
+            let mut stream = acceptor.accept(tcp).unwrap();
+            let mut line = String::new();
+
+            BufReader::new(&mut stream).read_line(&mut line).unwrap();
+            stream.write_all(line.to_uppercase().as_bytes()).unwrap();
+            stream.flush().unwrap();
+        });
+
+        let mut stream = connect_with_ca(&format!("localhost:{port}"), Some(&testdata("ca.pem"))).unwrap();
+        assert!(stream.is_tls());
+
+        stream.write_all(b"amdgpu_top\n").unwrap();
+        stream.flush().unwrap();
+
+        let mut line = String::new();
+        BufReader::new(&mut stream).read_line(&mut line).unwrap();
+        assert_eq!(line, "AMDGPU_TOP\n");
+
+        server.join().unwrap();
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn unknown_ca() {
+        use std::net::TcpListener;
+
+        let acceptor = TlsAcceptor::new(&testdata("cert.pem"), &testdata("key.pem")).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = std::thread::spawn(move || {
+            let (tcp, _) = listener.accept().unwrap();
+            let mut stream = acceptor.accept(tcp).unwrap();
+
+            assert!(stream.read(&mut [0u8; 16]).is_err());
+        });
+
+        // the leaf certificate is not a CA
+        let mut stream = connect_with_ca(&format!("localhost:{port}"), Some(&testdata("cert.pem"))).unwrap();
+        assert!(stream.write_all(b"amdgpu_top\n").and_then(|_| stream.flush()).is_err());
+
+        server.join().unwrap();
+    }
+
+    #[cfg(feature = "tls")]
+    #[test]
+    fn missing_key() {
+        assert!(TlsAcceptor::new(&testdata("cert.pem"), &testdata("ca.pem")).is_err());
+        assert!(TlsAcceptor::new(&testdata("key.pem"), &testdata("key.pem")).is_err());
+    }
+
+    #[cfg(not(feature = "tls"))]
+    #[test]
+    fn unsupported() {
+        let err = TlsAcceptor::new(Path::new("cert.pem"), Path::new("key.pem")).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert_eq!(connect("localhost:8080").err().unwrap().kind(), io::ErrorKind::Unsupported);
+    }
+}
@@ -0,0 +1,252 @@
+use std::fmt::Write;
+use std::io;
+use std::path::PathBuf;
+use std::fs;
+use crate::{config_dir, PCI};
+use crate::AMDGPU::PowerProfile;
+use crate::priv_helper::{SysfsNode, write_node};
+
+// ref: https://www.kernel.org/doc/html/latest/gpu/amdgpu/thermal.html
+
+const FILE_NAME: &str = "profiles.conf";
+
+/// Named tuning profile, stored in `$XDG_CONFIG_HOME/amdgpu_top/profiles.conf`
+/// (or `~/.config/amdgpu_top/`).
+///
+/// ```text
+/// [quiet]
+/// power_cap = 150
+/// fan_curve = 40:20, 60:35, 80:60, 90:80, 100:100
+/// max_sclk = 1800
+/// max_mclk = 1000
+/// power_profile = POWER_SAVING
+/// ```
+///
+/// `fan_curve` is a list of `<temperature (C)>:<fan speed (%)>` points (RDNA 3 or later).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TuningProfile {
+    pub name: String,
+    pub power_cap: Option<u32>, // W
+    pub fan_curve: Vec<(u32, u32)>, // (C, %)
+    pub max_sclk: Option<u32>, // MHz
+    pub max_mclk: Option<u32>, // MHz
+    pub power_profile: Option<PowerProfile>,
+}
+
+impl TuningProfile {
+    pub fn load_all() -> Vec<Self> {
+        let Some(path) = Self::config_path() else { return Vec::new() };
+        let Ok(s) = fs::read_to_string(path) else { return Vec::new() };
+
+        Self::parse_all(&s)
+    }
+
+    pub fn find(name: &str) -> Option<Self> {
+        Self::load_all().into_iter().find(|profile| profile.name == name)
+    }
+
+    pub fn parse_all(s: &str) -> Vec<Self> {
+        let mut profiles: Vec<Self> = Vec::new();
+
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                profiles.push(Self { name: name.trim().to_string(), ..Default::default() });
+                continue;
+            }
+
+            let Some(profile) = profiles.last_mut() else { continue };
+            let Some((key, val)) = line.split_once('=') else { continue };
+            let val = val.trim();
+
+            match key.trim() {
+                "power_cap" => profile.power_cap = val.parse().ok(),
+                "max_sclk" => profile.max_sclk = val.parse().ok(),
+                "max_mclk" => profile.max_mclk = val.parse().ok(),
+                "power_profile" => profile.power_profile = parse_power_profile(val),
+                "fan_curve" => profile.fan_curve = val.split(',').filter_map(|point| {
+                    let (temp, speed) = point.trim().split_once(':')?;
+
+                    Some((temp.trim().parse().ok()?, speed.trim().parse().ok()?))
+                }).collect(),
+                _ => {},
+            }
+        }
+
+        profiles
+    }
+
+    pub fn config_path() -> Option<PathBuf> {
+        Some(config_dir()?.join(FILE_NAME))
+    }
+
+    /// Human-readable list of changes, for the confirmation.
+    pub fn changes(&self) -> Vec<String> {
+        let mut v = Vec::new();
+
+        if let Some(cap) = self.power_cap {
+            v.push(format!("Power Cap => {cap} W"));
+        }
+
+        if !self.fan_curve.is_empty() {
+            let mut s = String::from("Fan Curve =>");
+
+            for (temp, speed) in &self.fan_curve {
+                let _ = write!(s, " {temp}C:{speed}%");
+            }
+
+            v.push(s);
+        }
+
+        if let Some(sclk) = self.max_sclk {
+            v.push(format!("Max SCLK => {sclk} MHz"));
+        }
+
+        if let Some(mclk) = self.max_mclk {
+            v.push(format!("Max MCLK => {mclk} MHz"));
+        }
+
+        if let Some(profile) = self.power_profile {
+            v.push(format!("Power Profile => {profile}"));
+        }
+
+        v
+    }
+
+    /// The files to write, to check with [`ControlCapabilities`](crate::priv_helper::ControlCapabilities).
+    pub fn required_nodes(&self) -> Vec<SysfsNode> {
+        let mut v = Vec::new();
+
+        if self.power_cap.is_some() { v.push(SysfsNode::PowerCap) }
+        if !self.fan_curve.is_empty() { v.push(SysfsNode::FanCurve) }
+        if self.max_sclk.is_some() || self.max_mclk.is_some() { v.push(SysfsNode::OdClkVoltage) }
+        if self.power_profile.is_some() { v.push(SysfsNode::PowerProfileMode) }
+
+        v
+    }
+
+    /// Requires root, or `amdgpu_top_helper`.
+    /// Returns the previous settings to revert the changes.
+    /// If any of the settings fails, the applied settings are reverted.
+    pub fn apply(&self, pci_bus: &PCI::BUS_INFO) -> io::Result<TuningBackup> {
+        let mut backup = TuningBackup::new(*pci_bus);
+
+        if let Err(err) = self.apply_with_backup(&mut backup) {
+            let _ = backup.revert();
+            return Err(err);
+        }
+
+        Ok(backup)
+    }
+
+    fn apply_with_backup(&self, backup: &mut TuningBackup) -> io::Result<()> {
+        let pci_bus = backup.pci_bus;
+
+        if let Some(cap) = self.power_cap {
+            backup.power_cap = read_node(&pci_bus, SysfsNode::PowerCap);
+            write_node(&pci_bus, SysfsNode::PowerCap, &(cap as u64 * 1_000_000).to_string())?;
+        }
+
+        if !self.fan_curve.is_empty() {
+            backup.fan_curve = true;
+
+            for (i, (temp, speed)) in self.fan_curve.iter().enumerate() {
+                write_node(&pci_bus, SysfsNode::FanCurve, &format!("{i} {temp} {speed}"))?;
+            }
+
+            write_node(&pci_bus, SysfsNode::FanCurve, "c")?;
+        }
+
+        if self.max_sclk.is_some() || self.max_mclk.is_some() {
+            backup.od_clk = true;
+
+            for (cmd, clk) in [("s", self.max_sclk), ("m", self.max_mclk)] {
+                let Some(clk) = clk else { continue };
+                write_node(&pci_bus, SysfsNode::OdClkVoltage, &format!("{cmd} 1 {clk}"))?;
+            }
+
+            write_node(&pci_bus, SysfsNode::OdClkVoltage, "c")?;
+        }
+
+        if let Some(profile) = self.power_profile {
+            backup.perf_level = read_node(&pci_bus, SysfsNode::PerfLevel);
+            backup.power_profile = PowerProfile::get_current_profile_from_sysfs(pci_bus.get_sysfs_path());
+
+            write_node(&pci_bus, SysfsNode::PerfLevel, "manual")?;
+            write_node(&pci_bus, SysfsNode::PowerProfileMode, &(profile as u32).to_string())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Settings before applying a [`TuningProfile`].
+#[derive(Clone, Debug)]
+pub struct TuningBackup {
+    pci_bus: PCI::BUS_INFO,
+    power_cap: Option<String>, // uW
+    fan_curve: bool,
+    od_clk: bool,
+    perf_level: Option<String>,
+    power_profile: Option<PowerProfile>,
+}
+
+impl TuningBackup {
+    fn new(pci_bus: PCI::BUS_INFO) -> Self {
+        Self {
+            pci_bus,
+            power_cap: None,
+            fan_curve: false,
+            od_clk: false,
+            perf_level: None,
+            power_profile: None,
+        }
+    }
+
+    /// Tries all settings and returns the first error.
+    pub fn revert(&self) -> io::Result<()> {
+        let pci_bus = &self.pci_bus;
+        let mut results = Vec::new();
+
+        if let Some(cap) = &self.power_cap {
+            results.push(write_node(pci_bus, SysfsNode::PowerCap, cap));
+        }
+
+        if self.fan_curve {
+            results.push(write_node(pci_bus, SysfsNode::FanCurve, "r"));
+            results.push(write_node(pci_bus, SysfsNode::FanCurve, "c"));
+        }
+
+        if self.od_clk {
+            results.push(write_node(pci_bus, SysfsNode::OdClkVoltage, "r"));
+            results.push(write_node(pci_bus, SysfsNode::OdClkVoltage, "c"));
+        }
+
+        if let Some(profile) = self.power_profile {
+            results.push(write_node(pci_bus, SysfsNode::PowerProfileMode, &(profile as u32).to_string()));
+        }
+
+        if let Some(level) = &self.perf_level {
+            results.push(write_node(pci_bus, SysfsNode::PerfLevel, level));
+        }
+
+        results.into_iter().collect()
+    }
+}
+
+/// Accepts the index (`2`) or the name (`POWER_SAVING`) of the power profile.
+fn parse_power_profile(s: &str) -> Option<PowerProfile> {
+    if let Ok(i) = s.parse::<u32>() {
+        return PowerProfile::try_from(i).ok();
+    }
+
+    (0..PowerProfile::COUNT as u32)
+        .filter_map(|i| PowerProfile::try_from(i).ok())
+        .find(|profile| profile.to_string().eq_ignore_ascii_case(s))
+}
+
+fn read_node(pci_bus: &PCI::BUS_INFO, node: SysfsNode) -> Option<String> {
+    fs::read_to_string(node.path(pci_bus)?).ok().map(|s| s.trim().to_string())
+}
@@ -0,0 +1,106 @@
+// UMR (the user mode register debugger for AMDGPU) integration, if `umr` is installed.
+// When the driver reports a ring timeout (`amdgpu_job_timedout`: "ring gfx_0.0.0 timeout, ..."),
+// the waves and the ring contents are read with umr, before the GPU reset discards them.
+// umr reads the registers and the rings through debugfs, so it requires root.
+
+// ## Reference
+//  * <https://gitlab.freedesktop.org/tomstdenis/umr>
+//  * Linux Kernel
+//    * `drivers/gpu/drm/amd/amdgpu/amdgpu_job.c`
+//      * `amdgpu_job_timedout`
+
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+pub const UMR_NAME: &str = "umr";
+/// The ring inspected without a ring timeout
+pub const DEFAULT_RING: &str = "gfx_0.0.0";
+/// The output of umr is truncated to this many lines
+const MAX_LINES: usize = 2048;
+
+/// "ring gfx_0.0.0 timeout, signaled seq=1234, emitted seq=1236" -> "gfx_0.0.0"
+pub fn parse_ring_timeout(msg: &str) -> Option<&str> {
+    let (_, s) = msg.split_once("ring ")?;
+    let (ring, _) = s.split_once(" timeout")?;
+
+    (!ring.is_empty() && !ring.contains(' ')).then_some(ring)
+}
+
+#[derive(Clone, Debug)]
+pub struct UmrReport {
+    pub ring: String,
+    pub time: SystemTime,
+    /// `umr --waves`
+    pub waves: Result<String, String>,
+    /// `umr --ring-stream`
+    pub ring_contents: Result<String, String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Umr {
+    pub path: PathBuf,
+    /// The DRI instance of the device (`umr --instance`)
+    pub instance: u32,
+}
+
+impl Umr {
+    /// `None` if `umr` is not found in `PATH`
+    pub fn new(instance: u32) -> Option<Self> {
+        let paths = std::env::var_os("PATH")?;
+        let path = std::env::split_paths(&paths)
+            .map(|dir| dir.join(UMR_NAME))
+            .find(|path| path.is_file())?;
+
+        Some(Self { path, instance })
+    }
+
+    fn run(&self, args: &[&str]) -> Result<String, String> {
+        let output = Command::new(&self.path)
+            .args(["--instance", &self.instance.to_string()])
+            .args(args)
+            .output()
+            .map_err(|err| format!("{}: {err}", self.path.display()))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            return Err(format!("{} ({})", stderr.trim(), output.status));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines: Vec<&str> = stdout.lines().take(MAX_LINES + 1).collect();
+
+        if lines.len() > MAX_LINES {
+            lines.truncate(MAX_LINES);
+            lines.push("...");
+        }
+
+        Ok(lines.join("\n"))
+    }
+
+    /// Reads the status of the active waves and the contents of the ring.
+    pub fn inspect(&self, ring: &str) -> UmrReport {
+        UmrReport {
+            ring: ring.to_string(),
+            time: SystemTime::now(),
+            waves: self.run(&["--waves", ring]),
+            ring_contents: self.run(&["--ring-stream", ring]),
+        }
+    }
+
+    /// Runs [`Self::inspect`] in a thread, umr may take a few seconds on a hung GPU.
+    pub fn spawn_inspect(&self, ring: &str, report: Arc<Mutex<Option<UmrReport>>>) {
+        let umr = self.clone();
+        let ring = ring.to_string();
+
+        std::thread::spawn(move || {
+            let r = umr.inspect(&ring);
+
+            if let Ok(mut report) = report.lock() {
+                *report = Some(r);
+            }
+        });
+    }
+}
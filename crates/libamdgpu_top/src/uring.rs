@@ -0,0 +1,452 @@
+// Batch the reads of the small procfs/sysfs files (`/proc/<pid>/fdinfo/<fd>`, `/proc/<pid>/stat`)
+// with io_uring, instead of open/read/close syscalls for each file.
+// The files are opened, read (until EOF) and closed in one submission each, up to `ENTRIES` files at a time.
+// Enabled by `--io-uring`, each sampling thread sets up its own ring on the first use.
+// Falls back to the std reads if io_uring is not available (Linux < 5.6, `kernel.io_uring_disabled`, seccomp).
+
+// ## Reference
+//  * <https://man7.org/linux/man-pages/man7/io_uring.7.html>
+//  * <https://kernel.dk/io_uring.pdf>
+//  * include/uapi/linux/io_uring.h
+
+use std::cell::RefCell;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+const ENTRIES: u32 = 64;
+/// Reserved for each read, fdinfo and `/proc/<pid>/stat` are less than 4 KiB
+const BUF_LEN: usize = 4096;
+
+const IORING_OP_OPENAT: u8 = 18;
+const IORING_OP_CLOSE: u8 = 19;
+const IORING_OP_READ: u8 = 22;
+const IORING_ENTER_GETEVENTS: u32 = 1 << 0;
+const IORING_FEAT_SINGLE_MMAP: u32 = 1 << 0;
+/// Linux 5.6, with `IORING_OP_{OPENAT,CLOSE,READ}`
+const IORING_FEAT_RW_CUR_POS: u32 = 1 << 3;
+const IORING_OFF_SQ_RING: libc::off_t = 0;
+const IORING_OFF_CQ_RING: libc::off_t = 0x8000000;
+const IORING_OFF_SQES: libc::off_t = 0x10000000;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// `None`: not set up yet, `Some(None)`: not available
+    static RING: RefCell<Option<Option<Ring>>> = const { RefCell::new(None) };
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// The paths and the buffers are reused for each batch.
+#[derive(Clone, Debug, Default)]
+pub struct BatchRead {
+    /// nul-terminated paths
+    paths: Vec<u8>,
+    path_offsets: Vec<usize>,
+    bufs: Vec<Vec<u8>>,
+    ok: Vec<bool>,
+}
+
+impl BatchRead {
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.path_offsets.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.path_offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.path_offsets.is_empty()
+    }
+
+    pub fn push_path(&mut self, path: fmt::Arguments) {
+        self.path_offsets.push(self.paths.len());
+        let _ = self.paths.write_fmt(path);
+        self.paths.push(0);
+    }
+
+    fn path(&self, i: usize) -> &[u8] {
+        path_at(&self.paths, &self.path_offsets, i)
+    }
+
+    /// Read all the files, with io_uring if enabled and available.
+    pub fn read_all(&mut self) {
+        let n = self.len();
+
+        self.bufs.resize_with(n.max(self.bufs.len()), Vec::new);
+        self.ok.clear();
+        self.ok.resize(n, false);
+
+        for buf in self.bufs.iter_mut().take(n) {
+            buf.clear();
+        }
+
+        let done = is_enabled() && RING.with(|ring| {
+            let mut ring = ring.borrow_mut();
+            let Some(ring) = ring.get_or_insert_with(|| Ring::new().ok()) else { return false };
+
+            (0..n).step_by(ENTRIES as usize).all(|start| {
+                let end = (start + ENTRIES as usize).min(n);
+
+                ring.read_chunk(self, start..end).is_ok()
+            })
+        });
+
+        if !done {
+            self.read_all_std();
+        }
+    }
+
+    fn read_all_std(&mut self) {
+        for (i, (buf, ok)) in self.bufs.iter_mut().zip(self.ok.iter_mut()).enumerate() {
+            let path = path_at(&self.paths, &self.path_offsets, i);
+            let path = OsStr::from_bytes(&path[..path.len()-1]);
+
+            buf.clear();
+            *ok = fs::File::open(path)
+                .and_then(|mut f| f.read_to_end(buf))
+                .is_ok();
+        }
+    }
+
+    pub fn get(&self, i: usize) -> Option<&[u8]> {
+        if !*self.ok.get(i)? { return None }
+
+        self.bufs.get(i).map(|buf| buf.as_slice())
+    }
+}
+
+/// nul-terminated
+fn path_at<'a>(paths: &'a [u8], path_offsets: &[usize], i: usize) -> &'a [u8] {
+    let start = path_offsets[i];
+    let end = path_offsets.get(i+1).copied().unwrap_or(paths.len());
+
+    &paths[start..end]
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+#[allow(dead_code)]
+struct Sqe {
+    opcode: u8,
+    flags: u8,
+    ioprio: u16,
+    fd: i32,
+    off: u64,
+    addr: u64,
+    len: u32,
+    op_flags: u32,
+    user_data: u64,
+    buf_index: u16,
+    personality: u16,
+    splice_fd_in: i32,
+    addr3: u64,
+    pad: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+#[allow(dead_code)]
+struct Cqe {
+    user_data: u64,
+    res: i32,
+    flags: u32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+#[allow(dead_code)]
+struct SqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    array: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+#[allow(dead_code)]
+struct CqringOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+    flags: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+#[allow(dead_code)]
+struct Params {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_cpu: u32,
+    sq_thread_idle: u32,
+    features: u32,
+    wq_fd: u32,
+    resv: [u32; 3],
+    sq_off: SqringOffsets,
+    cq_off: CqringOffsets,
+}
+
+struct Mmap {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Mmap {
+    fn new(fd: &OwnedFd, len: usize, offset: libc::off_t) -> io::Result<Self> {
+        let ptr = unsafe { libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED | libc::MAP_POPULATE,
+            fd.as_raw_fd(),
+            offset,
+        ) };
+
+        if ptr == libc::MAP_FAILED { return Err(io::Error::last_os_error()) }
+
+        Ok(Self { ptr: ptr as *mut u8, len })
+    }
+
+    fn at<T>(&self, offset: u32) -> *mut T {
+        unsafe { self.ptr.add(offset as usize) as *mut T }
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len) };
+    }
+}
+
+struct Ring {
+    // unmapped before closing the fd
+    sq_map: Mmap,
+    cq_map: Option<Mmap>,
+    sqes_map: Mmap,
+    fd: OwnedFd,
+    sq_off: SqringOffsets,
+    cq_off: CqringOffsets,
+    sq_mask: u32,
+    cq_mask: u32,
+}
+
+impl Ring {
+    fn new() -> io::Result<Self> {
+        let mut p = Params::default();
+        let fd = unsafe { libc::syscall(libc::SYS_io_uring_setup, ENTRIES, &mut p as *mut Params) };
+
+        if fd < 0 { return Err(io::Error::last_os_error()) }
+
+        let fd = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+        if p.features & IORING_FEAT_RW_CUR_POS == 0 {
+            return Err(io::Error::from(io::ErrorKind::Unsupported));
+        }
+
+        let sq_len = p.sq_off.array as usize + p.sq_entries as usize * std::mem::size_of::<u32>();
+        let cq_len = p.cq_off.cqes as usize + p.cq_entries as usize * std::mem::size_of::<Cqe>();
+        let single_mmap = p.features & IORING_FEAT_SINGLE_MMAP != 0;
+
+        let sq_map = Mmap::new(&fd, if single_mmap { sq_len.max(cq_len) } else { sq_len }, IORING_OFF_SQ_RING)?;
+        let cq_map = if single_mmap { None } else { Some(Mmap::new(&fd, cq_len, IORING_OFF_CQ_RING)?) };
+        let sqes_map = Mmap::new(&fd, p.sq_entries as usize * std::mem::size_of::<Sqe>(), IORING_OFF_SQES)?;
+
+        let sq_mask = unsafe { *sq_map.at::<u32>(p.sq_off.ring_mask) };
+        let cq_mask = unsafe { *cq_map.as_ref().unwrap_or(&sq_map).at::<u32>(p.cq_off.ring_mask) };
+
+        Ok(Self {
+            sq_map,
+            cq_map,
+            sqes_map,
+            fd,
+            sq_off: p.sq_off,
+            cq_off: p.cq_off,
+            sq_mask,
+            cq_mask,
+        })
+    }
+
+    fn sq_atomic(&self, offset: u32) -> &AtomicU32 {
+        unsafe { AtomicU32::from_ptr(self.sq_map.at::<u32>(offset)) }
+    }
+
+    fn cq_atomic(&self, offset: u32) -> &AtomicU32 {
+        unsafe { AtomicU32::from_ptr(self.cq_map.as_ref().unwrap_or(&self.sq_map).at::<u32>(offset)) }
+    }
+
+    /// Submit the entries and wait for all the completions.
+    /// `on_cqe` is called with (`user_data`, `res`).
+    fn submit_and_wait<F: FnMut(u64, i32)>(&mut self, sqes: &[Sqe], mut on_cqe: F) -> io::Result<()> {
+        if sqes.is_empty() { return Ok(()) }
+
+        let tail = self.sq_atomic(self.sq_off.tail).load(Ordering::Relaxed);
+        let array = self.sq_map.at::<u32>(self.sq_off.array);
+        let sqes_ptr = self.sqes_map.at::<Sqe>(0);
+
+        for (i, sqe) in sqes.iter().enumerate() {
+            let idx = tail.wrapping_add(i as u32) & self.sq_mask;
+
+            unsafe {
+                *sqes_ptr.add(idx as usize) = *sqe;
+                *array.add(idx as usize) = idx;
+            }
+        }
+
+        self.sq_atomic(self.sq_off.tail).store(tail.wrapping_add(sqes.len() as u32), Ordering::Release);
+
+        let mut to_submit = sqes.len() as u32;
+        let mut remaining = sqes.len();
+
+        while remaining != 0 {
+            let ret = unsafe { libc::syscall(
+                libc::SYS_io_uring_enter,
+                self.fd.as_raw_fd(),
+                to_submit,
+                remaining as u32,
+                IORING_ENTER_GETEVENTS,
+                std::ptr::null::<libc::c_void>(),
+                0usize,
+            ) };
+
+            if ret < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted { continue }
+                return Err(err);
+            }
+
+            to_submit = to_submit.saturating_sub(ret as u32);
+
+            let cq_head = self.cq_atomic(self.cq_off.head);
+            let mut head = cq_head.load(Ordering::Relaxed);
+            let cq_tail = self.cq_atomic(self.cq_off.tail).load(Ordering::Acquire);
+            let cqes = self.cq_map.as_ref().unwrap_or(&self.sq_map).at::<Cqe>(self.cq_off.cqes);
+
+            while head != cq_tail {
+                let cqe = unsafe { *cqes.add((head & self.cq_mask) as usize) };
+                on_cqe(cqe.user_data, cqe.res);
+                head = head.wrapping_add(1);
+                remaining = remaining.saturating_sub(1);
+            }
+
+            cq_head.store(head, Ordering::Release);
+        }
+
+        Ok(())
+    }
+
+    fn read_chunk(&mut self, batch: &mut BatchRead, range: std::ops::Range<usize>) -> io::Result<()> {
+        let mut sqes = [Sqe::default(); ENTRIES as usize];
+        let mut fds = [-1i32; ENTRIES as usize];
+        let base = range.start;
+        let n = range.len();
+
+        for (sqe, i) in sqes.iter_mut().zip(range.clone()) {
+            *sqe = Sqe {
+                opcode: IORING_OP_OPENAT,
+                fd: libc::AT_FDCWD,
+                addr: batch.path(i).as_ptr() as u64,
+                op_flags: (libc::O_RDONLY | libc::O_CLOEXEC) as u32,
+                user_data: i as u64,
+                ..Default::default()
+            };
+        }
+
+        self.submit_and_wait(&sqes[..n], |i, res| fds[i as usize - base] = res)?;
+
+        // read until EOF in rounds, seq_file (procfs) returns a short read at the end of a page
+        let mut reading = fds.map(|fd| fd >= 0);
+        let mut ret = Ok(());
+
+        loop {
+            let mut n_read = 0;
+
+            for (j, i) in range.clone().enumerate() {
+                if !reading[j] { continue }
+
+                let buf = &mut batch.bufs[i];
+                buf.reserve(BUF_LEN);
+
+                sqes[n_read] = Sqe {
+                    opcode: IORING_OP_READ,
+                    fd: fds[j],
+                    // the current file position
+                    off: u64::MAX,
+                    addr: unsafe { buf.as_mut_ptr().add(buf.len()) } as u64,
+                    len: (buf.capacity() - buf.len()).min(u32::MAX as usize) as u32,
+                    user_data: i as u64,
+                    ..Default::default()
+                };
+                n_read += 1;
+            }
+
+            if n_read == 0 { break }
+
+            let mut lens = [-1i32; ENTRIES as usize];
+            ret = self.submit_and_wait(&sqes[..n_read], |i, res| lens[i as usize - base] = res);
+
+            if ret.is_err() { break }
+
+            for (j, i) in range.clone().enumerate() {
+                if !reading[j] { continue }
+
+                match usize::try_from(lens[j]) {
+                    Ok(0) => {
+                        reading[j] = false;
+                        batch.ok[i] = true;
+                    },
+                    Ok(len) => {
+                        let buf = &mut batch.bufs[i];
+                        unsafe { buf.set_len(buf.len() + len) };
+                    },
+                    Err(_) => reading[j] = false,
+                }
+            }
+        }
+
+        let mut n_close = 0;
+
+        for fd in fds.iter().take(n).filter(|fd| **fd >= 0) {
+            sqes[n_close] = Sqe {
+                opcode: IORING_OP_CLOSE,
+                fd: *fd,
+                user_data: base as u64,
+                ..Default::default()
+            };
+            n_close += 1;
+        }
+
+        if self.submit_and_wait(&sqes[..n_close], |_, _| {}).is_err() {
+            for fd in fds.iter().take(n).filter(|fd| **fd >= 0) {
+                unsafe { libc::close(*fd) };
+            }
+        }
+
+        ret
+    }
+}
@@ -0,0 +1,109 @@
+use crate::AMDGPU::VIDEO_CAPS::{CAP_TYPE, CODEC, drm_amdgpu_info_video_codec_info};
+
+// The kernel reports the max size, the max pixels per frame and the max level of each codec.
+// The profiles are the ones that Mesa (radeonsi) exposes for VCN, they are not reported per device,
+// and the max number of the reference frames is the DPB size of the spec at the max level and the max size.
+
+// ## Reference
+//  * <https://gitlab.freedesktop.org/mesa/mesa/-/blob/main/src/gallium/drivers/radeonsi/si_get.c>
+//  * ITU-T H.264 Table A-1 (MaxDpbMbs), ITU-T H.265 A.4.2 (maxDpbSize)
+
+/// `max_level` is a codec specific value, e.g. `52` for H.264 level 5.2 and `186` for HEVC level 6.2
+pub fn codec_level_name(codec: CODEC, max_level: u32) -> Option<String> {
+    if max_level == 0 { return None }
+
+    let name = match codec {
+        CODEC::MPEG4_AVC => format!("{}.{}", max_level / 10, max_level % 10),
+        CODEC::HEVC => format!("{}.{}", max_level / 30, (max_level % 30) / 3),
+        // seq_level_idx
+        CODEC::AV1 => format!("{}.{}", 2 + max_level / 4, max_level % 4),
+        _ => max_level.to_string(),
+    };
+
+    Some(name)
+}
+
+pub fn codec_profiles(codec: CODEC, cap_type: CAP_TYPE) -> &'static [&'static str] {
+    let decode = matches!(cap_type, CAP_TYPE::DECODE);
+
+    match codec {
+        CODEC::MPEG2 if decode => &["Simple", "Main"],
+        CODEC::MPEG4 if decode => &["Simple", "Advanced Simple"],
+        CODEC::VC1 if decode => &["Simple", "Main", "Advanced"],
+        CODEC::MPEG4_AVC => &["Constrained Baseline", "Main", "High"],
+        CODEC::HEVC => &["Main", "Main 10"],
+        CODEC::JPEG if decode => &["Baseline"],
+        CODEC::VP9 if decode => &["Profile 0", "Profile 2"],
+        CODEC::AV1 => &["Main"],
+        _ => &[],
+    }
+}
+
+/// The max number of the reference frames (DPB size) at the max level and the max size
+pub fn codec_max_refs(codec: CODEC, info: &drm_amdgpu_info_video_codec_info) -> Option<u32> {
+    let refs = match codec {
+        CODEC::MPEG2 | CODEC::MPEG4 | CODEC::VC1 => 2,
+        CODEC::JPEG => 0,
+        // NUM_REF_FRAMES
+        CODEC::VP9 | CODEC::AV1 => 8,
+        CODEC::MPEG4_AVC => {
+            let max_dpb_mbs = h264_max_dpb_mbs(info.max_level)?;
+            let mbs = info.max_width.div_ceil(16) * info.max_height.div_ceil(16);
+
+            max_dpb_mbs.checked_div(mbs)?.min(16)
+        },
+        CODEC::HEVC => {
+            const MAX_DPB_PIC_BUF: u32 = 6;
+            let max_luma_ps = hevc_max_luma_ps(info.max_level)?;
+            let pic_size = info.max_width as u64 * info.max_height as u64;
+
+            if pic_size <= max_luma_ps >> 2 {
+                16
+            } else if pic_size <= max_luma_ps >> 1 {
+                MAX_DPB_PIC_BUF * 2
+            } else if pic_size <= (3 * max_luma_ps) >> 2 {
+                MAX_DPB_PIC_BUF * 4 / 3
+            } else {
+                MAX_DPB_PIC_BUF
+            }
+        },
+    };
+
+    Some(refs)
+}
+
+fn h264_max_dpb_mbs(level: u32) -> Option<u32> {
+    let mbs = match level {
+        10 | 9 => 396,
+        11 => 900,
+        12 | 13 | 20 => 2376,
+        21 => 4752,
+        22 | 30 => 8100,
+        31 => 18000,
+        32 => 20480,
+        40 | 41 => 32768,
+        42 => 34816,
+        50 => 110400,
+        51 | 52 => 184320,
+        60..=62 => 696320,
+        _ => return None,
+    };
+
+    Some(mbs)
+}
+
+fn hevc_max_luma_ps(level: u32) -> Option<u64> {
+    let ps = match level / 3 {
+        10 => 36864,
+        20 => 122880,
+        21 => 245760,
+        30 => 552960,
+        31 => 983040,
+        40 | 41 => 2228224,
+        50..=52 => 8912896,
+        60..=62 => 35651584,
+        _ => return None,
+    };
+
+    Some(ps)
+}
@@ -0,0 +1,187 @@
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::{config_dir, PCI};
+use crate::AMDGPU::{HwmonTemp, HwmonTempType};
+use crate::priv_helper::{SysfsNode, write_node};
+
+const LOG_FILE_NAME: &str = "watchdog.log";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchdogAction {
+    PowerCap(u32), // W
+    MaxFan,
+}
+
+impl WatchdogAction {
+    pub fn required_node(&self) -> SysfsNode {
+        match self {
+            Self::PowerCap(_) => SysfsNode::PowerCap,
+            Self::MaxFan => SysfsNode::Pwm,
+        }
+    }
+}
+
+impl fmt::Display for WatchdogAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PowerCap(cap) => write!(f, "lower the power cap to {cap} W"),
+            Self::MaxFan => write!(f, "set the fan to max speed"),
+        }
+    }
+}
+
+/// `<temp (C)>:<seconds>:<action>`, e.g. `95:10:power_cap=150`, `100:5:max_fan`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchdogRule {
+    pub temp_limit: i64, // C
+    pub duration: Duration,
+    pub action: WatchdogAction,
+}
+
+impl FromStr for WatchdogRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid watchdog rule: {s:?} (<temp>:<seconds>:<power_cap=W|max_fan>)");
+        let mut split = s.splitn(3, ':');
+        let [temp, secs, action] = [split.next(), split.next(), split.next()]
+            .map(|v| v.map(|v| v.trim()));
+        let temp_limit = temp.and_then(|v| v.parse::<i64>().ok()).ok_or_else(err)?;
+        let secs = secs.and_then(|v| v.parse::<u64>().ok()).ok_or_else(err)?;
+        let action = match action.ok_or_else(err)? {
+            "max_fan" => WatchdogAction::MaxFan,
+            a => {
+                let cap = a.strip_prefix("power_cap=")
+                    .and_then(|cap| cap.parse::<u32>().ok())
+                    .ok_or_else(err)?;
+
+                WatchdogAction::PowerCap(cap)
+            },
+        };
+
+        Ok(Self { temp_limit, duration: Duration::from_secs(secs), action })
+    }
+}
+
+/// Applies the action once the junction temperature (or edge temperature)
+/// exceeds the limit for the duration.
+/// The action is not reverted, and is applied again only after the temperature drops below the limit.
+/// Requires root, or `amdgpu_top_helper`.
+pub struct Watchdog {
+    pub rule: WatchdogRule,
+    pub pci_bus: PCI::BUS_INFO,
+    hwmon_path: PathBuf,
+    over_since: Option<Instant>,
+    triggered: bool,
+}
+
+impl Watchdog {
+    pub fn new(rule: WatchdogRule, pci_bus: PCI::BUS_INFO) -> Option<Self> {
+        let hwmon_path = pci_bus.get_hwmon_path()?;
+
+        Some(Self { rule, pci_bus, hwmon_path, over_since: None, triggered: false })
+    }
+
+    fn temp(&self) -> Option<i64> {
+        [HwmonTempType::Junction, HwmonTempType::Edge]
+            .into_iter()
+            .find_map(|type_| HwmonTemp::from_hwmon_path(&self.hwmon_path, type_))
+            .map(|temp| temp.current)
+    }
+
+    /// Returns the temperature if the action is to be taken now.
+    fn check(&mut self) -> Option<i64> {
+        let temp = self.temp()?;
+
+        if temp < self.rule.temp_limit {
+            self.over_since = None;
+            self.triggered = false;
+            return None;
+        }
+
+        let since = *self.over_since.get_or_insert_with(Instant::now);
+
+        if self.triggered || since.elapsed() < self.rule.duration {
+            return None;
+        }
+
+        self.triggered = true;
+
+        Some(temp)
+    }
+
+    /// Takes the action, and returns the log message.
+    /// Blocks while `pkexec` asks for the password of the helper.
+    fn act(rule: &WatchdogRule, pci_bus: &PCI::BUS_INFO, temp: i64) -> String {
+        let result = match rule.action {
+            WatchdogAction::PowerCap(cap) =>
+                write_node(pci_bus, SysfsNode::PowerCap, &(cap as u64 * 1_000_000).to_string()),
+            WatchdogAction::MaxFan => write_node(pci_bus, SysfsNode::PwmEnable, "1")
+                .and_then(|_| write_node(pci_bus, SysfsNode::Pwm, "255")),
+        };
+
+        let msg = format!(
+            "{pci_bus}: {temp} C >= {} C for {}s, {}",
+            rule.temp_limit,
+            rule.duration.as_secs(),
+            rule.action,
+        );
+
+        match result {
+            Ok(_) => msg,
+            Err(err) => format!("{msg}: failed ({err})"),
+        }
+    }
+
+    /// The temperature is checked on the scheduler thread, and the action is taken
+    /// on a short-lived thread, not to block the other tasks of the scheduler.
+    pub fn spawn_thread(mut self, interval: Duration) {
+        crate::scheduler::spawn_periodic(interval, move || {
+            if let Some(temp) = self.check() {
+                let (rule, pci_bus) = (self.rule, self.pci_bus);
+
+                std::thread::spawn(move || {
+                    let msg = Self::act(&rule, &pci_bus, temp);
+
+                    log(&msg);
+                    notify(&msg);
+                });
+            }
+
+            true
+        });
+    }
+
+    pub fn log_path() -> Option<PathBuf> {
+        Some(config_dir()?.join(LOG_FILE_NAME))
+    }
+}
+
+fn log(msg: &str) {
+    let Some(path) = Watchdog::log_path() else { return };
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+    let _ = path.parent().map(fs::create_dir_all);
+    let f = fs::OpenOptions::new().create(true).append(true).open(path);
+
+    if let Ok(mut f) = f {
+        let _ = writeln!(f, "[{secs}] {msg}");
+    }
+}
+
+/// Desktop notification via `notify-send`, if available.
+/// The process is waited on a short-lived thread, not to leave a zombie or block the caller.
+fn notify(msg: &str) {
+    let mut cmd = std::process::Command::new("notify-send");
+
+    cmd.args(["--urgency=critical", "amdgpu_top watchdog", msg])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+
+    std::thread::spawn(move || { let _ = cmd.status(); });
+}
@@ -0,0 +1,39 @@
+// `/proc/<pid>/fdinfo/<fd>`, seed: `docs/mock/navi31/proc/2345/fdinfo/5`
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libamdgpu_top::stat::{parse_fdinfo, FdInfoUsage};
+use std::time::Duration;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((_id, stat)) = parse_fdinfo(data) else { return };
+
+    // the same stat twice, no busy time in the interval
+    let delta = stat.calc_delta(&stat);
+    let usage = stat.calc_usage(&stat, &Duration::from_secs(1), false, false);
+
+    for v in [delta.gfx, delta.compute, delta.dma, delta.dec, delta.enc, delta.uvd_enc, delta.vcn_jpeg, delta.media] {
+        assert_eq!(v, 0);
+    }
+    for v in [usage.gfx, usage.compute, usage.dma, usage.dec, usage.enc, usage.uvd_enc, usage.vcn_jpeg, usage.media] {
+        assert_eq!(v, 0);
+    }
+
+    // from the first sample, the usage is bounded and not negative
+    let usage = stat.calc_usage(&FdInfoUsage { gfx: 1, compute: 1, dma: 1, dec: 1, enc: 1, uvd_enc: 1, vcn_jpeg: 1, ..Default::default() }, &Duration::from_nanos(1), true, true);
+
+    for v in [usage.gfx, usage.compute, usage.dma, usage.dec, usage.enc, usage.uvd_enc, usage.vcn_jpeg, usage.media] {
+        assert!((0..=u32::MAX as i64).contains(&v));
+    }
+
+    let excluded = stat.exclude_shared();
+
+    assert!(excluded.vram_usage <= stat.vram_usage);
+    assert!(excluded.gtt_usage <= stat.gtt_usage);
+    assert!(excluded.cpu_accessible_usage <= stat.cpu_accessible_usage);
+
+    // the sum of the processes saturates
+    let mut sum = stat;
+    sum += stat;
+    sum += usage;
+});
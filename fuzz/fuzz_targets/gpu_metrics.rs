@@ -0,0 +1,19 @@
+// `gpu_metrics`, the binary table of the SMU
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libamdgpu_top::AMDGPU::MetricsInfo;
+use libamdgpu_top::stat::{gpu_metrics_util::parse_gpu_metrics, GpuActivity};
+
+fuzz_target!(|data: &[u8]| {
+    let Some(metrics) = parse_gpu_metrics(data) else { return };
+    let header = metrics.get_header().unwrap();
+
+    assert!(header.structure_size as usize <= data.len());
+
+    let activity = GpuActivity::from_gpu_metrics(&metrics);
+
+    for v in [activity.gfx, activity.umc, activity.media].into_iter().flatten() {
+        assert!(v <= 100);
+    }
+});
@@ -0,0 +1,16 @@
+// sysfs/hwmon files, a single value
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libamdgpu_top::stat::parse_hwmon_value;
+
+fuzz_target!(|input: (&[u8], u32, i64)| {
+    let (data, v, temp) = input;
+
+    let _ = parse_hwmon_value::<u32>(data);
+    let _ = parse_hwmon_value::<u64>(data);
+    let _ = parse_hwmon_value::<i64>(data);
+
+    assert_eq!(parse_hwmon_value::<u32>(format!("{v}\n").as_bytes()), Some(v));
+    assert_eq!(parse_hwmon_value::<i64>(format!("{temp}\n").as_bytes()), Some(temp));
+});
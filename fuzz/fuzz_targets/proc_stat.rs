@@ -0,0 +1,20 @@
+// `/proc/<pid>/stat`
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libamdgpu_top::stat::parse_proc_stat_cpu_ticks;
+
+fuzz_target!(|input: (&[u8], &[u8], u64, u64)| {
+    let (data, comm, utime, stime) = input;
+
+    let _ = parse_proc_stat_cpu_ticks(data);
+
+    // the process name may contain spaces and `)`
+    let mut stat = b"2345 (".to_vec();
+    stat.extend_from_slice(comm);
+    stat.extend_from_slice(
+        format!(") S 1 2345 2345 0 -1 4194560 1 0 0 0 {utime} {stime} 0 0 20 0 1 0 1 1 1\n").as_bytes()
+    );
+
+    assert_eq!(parse_proc_stat_cpu_ticks(&stat), Some((utime, stime)));
+});
@@ -1,14 +1,92 @@
+use libamdgpu_top::stat::{FdInfoGroupBy, FrameSource, Smoothing};
+use crate::output_format::OutputFormat;
+use crate::completion::Shell;
+use libamdgpu_top::{agentx, parse_duration, AgentX, AlertCondition, AnomalyDetector, HistoryTiers, IpHwFilter, JournalPriority, LeakDetector, SessionOptions, SysfsMetric, TempUnit, WatchdogRule};
+use std::time::Duration;
+
 pub struct MainOpt {
     pub instance: u32,
     pub pid: Option<i32>,
     pub refresh_period: u64, // ms
     pub update_process_index: u64, // sec
     pub pci_path: Option<String>,
+    pub device_name: Option<String>,
+    pub set_nickname: Option<String>,
     pub dump: bool,
+    pub dump_ip_discovery: bool,
+    pub ip_hw: IpHwFilter,
     pub version: bool,
     pub list: bool,
     pub select_apu: bool,
+    pub select_all: bool,
+    pub exclude_pci: Vec<String>,
     pub json_iterations: u32,
+    pub duration: Option<Duration>,
+    pub idle_timeout: Option<Duration>,
+    pub anomaly_threshold: f64,
+    pub leak_threshold: f64,
+    pub io_uring: bool,
+    pub queue_priority: bool,
+    pub sysfs_busy: bool,
+    /// `--once`, TUI mode
+    pub once: bool,
+    /// `--source pmu`
+    pub pmu: bool,
+    /// `--sqtt-trigger <file>`
+    pub sqtt_trigger: Option<String>,
+    pub summary: Option<String>,
+    pub db: Option<String>,
+    pub db_interval: Duration,
+    pub db_retention: Duration,
+    pub query: Option<String>,
+    pub smoothing: Smoothing,
+    pub fdinfo_group: FdInfoGroupBy,
+    pub gui_backend: Option<String>,
+    pub gui_render_pci: Option<String>,
+    pub history_tiers: HistoryTiers,
+    pub baseline: Option<String>,
+    pub frame_source: Option<FrameSource>,
+    pub lang: Option<String>,
+    pub temp_unit: Option<TempUnit>,
+    pub ascii: bool,
+    pub apply_profile: Option<String>,
+    pub revert_on_exit: bool,
+    /// `--yes`, apply the profile without the confirmation
+    pub yes: bool,
+    pub read_only: bool,
+    pub quiet: bool,
+    pub watchdog: Option<WatchdogRule>,
+    pub fail_on: Vec<AlertCondition>,
+    pub bell: bool,
+    pub json_output_dir: Option<String>,
+    pub json_rotate_size: u64, // MiB
+    pub json_rotate_interval: u64, // hours
+    pub journald: bool,
+    pub journald_interval: u64, // sec
+    pub journald_priority: JournalPriority,
+    pub journald_fields: Vec<SysfsMetric>,
+    pub agentx: bool,
+    pub agentx_socket: String,
+    pub agentx_oid: Vec<u32>,
+    pub output_format: Option<OutputFormat>,
+    pub otlp_endpoint: Option<String>,
+    pub otlp_interval: u64, // sec
+    pub listen: Option<String>,
+    pub serve: Option<String>,
+    pub connect: Vec<String>,
+    /// `--mock <dir>`, the fixture directories of `MockDevice`
+    pub mock: Vec<String>,
+    /// `--demo`, the synthetic devices of `MockDevice`
+    pub demo: bool,
+    /// `--screenshot <png>`, renders the GUI of `--mock` or `--demo` without a window
+    pub screenshot: Option<String>,
+    pub bug_report: Option<String>,
+    /// `-- <command> [args ..]`
+    pub launch: Vec<String>,
+    /// `snapshot` and `diff`
+    pub subcommand: Option<Subcommand>,
+    pub generate_completion: Option<Shell>,
+    pub generate_man: bool,
     pub app_mode: AppMode,
 }
 
@@ -20,16 +98,89 @@ impl Default for MainOpt {
             refresh_period: 1000, // 1000ms, 1s
             update_process_index: 5, // sec
             pci_path: None,
+            device_name: None,
+            set_nickname: None,
             dump: false,
+            dump_ip_discovery: false,
+            ip_hw: IpHwFilter::default(),
             version: false,
             list: false,
             select_apu: false,
+            select_all: false,
+            exclude_pci: Vec::new(),
             app_mode: AppMode::TUI,
             json_iterations: 0,
+            duration: None,
+            idle_timeout: None,
+            anomaly_threshold: AnomalyDetector::DEFAULT_THRESHOLD,
+            leak_threshold: 0.0,
+            io_uring: false,
+            queue_priority: false,
+            sysfs_busy: false,
+            once: false,
+            pmu: false,
+            sqtt_trigger: None,
+            summary: None,
+            db: None,
+            db_interval: Duration::from_secs(60),
+            db_retention: Duration::from_secs(30 * 24 * 60 * 60),
+            query: None,
+            smoothing: Smoothing::default(),
+            fdinfo_group: FdInfoGroupBy::default(),
+            gui_backend: None,
+            gui_render_pci: None,
+            history_tiers: HistoryTiers::default(),
+            baseline: None,
+            frame_source: None,
+            lang: None,
+            temp_unit: None,
+            ascii: false,
+            apply_profile: None,
+            revert_on_exit: false,
+            yes: false,
+            read_only: false,
+            quiet: false,
+            watchdog: None,
+            fail_on: Vec::new(),
+            bell: false,
+            json_output_dir: None,
+            json_rotate_size: 64, // MiB
+            json_rotate_interval: 24, // hours
+            journald: false,
+            journald_interval: 10, // sec
+            journald_priority: JournalPriority::Info,
+            journald_fields: SysfsMetric::ALL.to_vec(),
+            agentx: false,
+            agentx_socket: agentx::DEFAULT_SOCKET.to_string(),
+            agentx_oid: agentx::DEFAULT_BASE_OID.to_vec(),
+            output_format: None,
+            otlp_endpoint: None,
+            otlp_interval: 10, // sec
+            listen: None,
+            serve: None,
+            connect: Vec::new(),
+            mock: Vec::new(),
+            demo: false,
+            screenshot: None,
+            bug_report: None,
+            launch: Vec::new(),
+            subcommand: None,
+            generate_completion: None,
+            generate_man: false,
         }
     }
 }
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Subcommand {
+    /// `snapshot [<file>]`, stdout if `None`
+    Snapshot { output: Option<String> },
+    /// `diff <a.json> <b.json>`
+    Diff { a: String, b: String },
+    /// `doctor`
+    Doctor,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 #[allow(non_camel_case_types)]
 #[allow(clippy::upper_case_acronyms)]
@@ -43,54 +194,1039 @@ pub enum AppMode {
     SMI,
 }
 
-const HELP_MSG: &str = concat!(
-    env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"), "\n",
-    env!("CARGO_PKG_HOMEPAGE"), "\n",
-    "\n",
-    "USAGE:\n",
-    "    cargo run -- [options ..] or <", env!("CARGO_PKG_NAME"), "> [options ..]\n",
-    "\n",
-    "FLAGS:\n",
-    "   -d, --dump\n",
-    "       Dump AMDGPU info. (Specifications, VRAM, PCI, ResizableBAR, VBIOS, Video caps)\n",
-    "       This option can be combined with the \"-J\" option.\n",
-    "   --list\n",
-    "       Display a list of AMDGPU devices.\n",
-    "       This option can be combined with the \"-d\" option.\n",
-    "   -J, --json\n",
-    "       Output JSON formatted data.\n",
-    "   --gui\n",
-    "       Launch GUI mode.\n",
-    "   --smi\n",
-    "       Launch Simple TUI mode. (like nvidia-smi, rocm-smi)\n",
-    "   --apu, --select-apu\n",
-    "       Select APU instance.\n",
-    "   -V, --version\n",
-    "       Print version information.\n",
-    "   -h, --help\n",
-    "       Print help information.\n",
-    "\n",
-    "OPTIONS:\n",
-    "   -i <u32>\n",
-    "       Select GPU instance.\n",
-    "   --pci <String>\n",
-    "       Specifying PCI path. (domain:bus:dev.func)\n",
-    "   -s <u64>, -s <u64>ms\n",
-    "       Refresh period (interval) in milliseconds for JSON mode. (default: 1000ms)\n",
-    "   -n <u32>\n",
-    "       Specifies the maximum number of iteration for JSON mode.\n",
-    "       If 0 is specified, it will be an infinite loop. (default: 0)\n",
-    "   -u <u64>, --update-process-index <u64>\n",
-    "       Update interval in seconds of the process index for fdinfo. (default: 5s)\n",
-);
+/// The subcommands that monitor the devices
+const MONITOR: &[&str] = &["top", "gui", "smi", "json", "record", "replay"];
+
+/// The subcommands that monitor the local devices, `replay` reads the fixtures instead
+const LIVE: &[&str] = &["top", "gui", "smi", "json", "record"];
+
+/// `<amdgpu_top> <subcommand> [args ..] [options ..]`
+/// A subcommand selects the mode, and accepts only the common options and its own options.
+/// The old flags of the modes (e.g. "--gui", "-J") still work without a subcommand.
+///
+/// A subcommand is a shorthand for the flags of the mode: [`MainOpt::subcommand_args`] rewrites
+/// it to the flags (e.g. `record <dir>` to `-J --json-output-dir <dir>`) before the options are parsed,
+/// so both forms set the same [`MainOpt`] fields and the modes dispatch on [`AppMode`] and the flags only.
+/// Only the subcommands without a flag (`snapshot`, `diff`, `doctor`) are kept as [`Subcommand`].
+/// A new subcommand should be an alias of the existing flags in the same way.
+pub struct SubcommandDef {
+    pub name: &'static str,
+    /// The positional arguments, e.g. `"<a.json> <b.json>"`
+    pub args: &'static str,
+    pub about: &'static [&'static str],
+}
+
+pub const SUBCOMMANDS: &[SubcommandDef] = &[
+    SubcommandDef {
+        name: "top",
+        args: "",
+        about: &["Launch TUI mode. (default)"],
+    },
+    SubcommandDef {
+        name: "gui",
+        args: "",
+        about: &["Launch GUI mode. (\"--gui\")"],
+    },
+    SubcommandDef {
+        name: "smi",
+        args: "",
+        about: &["Launch Simple TUI mode. (like nvidia-smi, rocm-smi, \"--smi\")"],
+    },
+    SubcommandDef {
+        name: "json",
+        args: "",
+        about: &["Output JSON formatted data. (\"-J\")"],
+    },
+    SubcommandDef {
+        name: "dump",
+        args: "",
+        about: &[
+            "Dump AMDGPU info. (\"-d\")",
+            "\"--dump-ip-discovery\" dumps the IP Discovery table instead, \"-J\" outputs JSON.",
+        ],
+    },
+    SubcommandDef {
+        name: "list",
+        args: "",
+        about: &[
+            "Display a list of AMDGPU devices. (\"--list\")",
+            "\"-d\" also displays the P2P (peer-to-peer) access matrix between the devices.",
+        ],
+    },
+    SubcommandDef {
+        name: "set",
+        args: "nickname <String> | profile <String>",
+        about: &[
+            "Save a nickname for the selected device (\"--set-nickname\"),",
+            "or apply the named tuning profile to the selected device (\"--apply-profile\").",
+        ],
+    },
+    SubcommandDef {
+        name: "record",
+        args: "<dir>",
+        about: &[
+            "Record JSON data to one NDJSON file per device in the directory.",
+            "(\"-J --json-output-dir <dir>\")",
+        ],
+    },
+    SubcommandDef {
+        name: "replay",
+        args: "<dir> [<dir> ..]",
+        about: &[
+            "Output JSON data of the fixture directories (fake sysfs, hwmon and fdinfo files),",
+            "without a local GPU. (\"-J --mock <dir>\")",
+        ],
+    },
+    SubcommandDef {
+        name: "query",
+        args: "<from>[..<to>]",
+        about: &["Print the min/avg/max of the metrics in \"--db\" over the time window. (\"--query\")"],
+    },
+    SubcommandDef {
+        name: "snapshot",
+        args: "[<file>]",
+        about: &[
+            "Save the full state of the devices (info, sensors, activity, VRAM, processes, ...)",
+            "sampled for \"-s\" to the JSON file. (default: stdout)",
+        ],
+    },
+    SubcommandDef {
+        name: "diff",
+        args: "<a.json> <b.json>",
+        about: &[
+            "Print the differences between two snapshots (or the last lines of \"-J\" outputs),",
+            "e.g. before/after a driver update.",
+        ],
+    },
+    SubcommandDef {
+        name: "doctor",
+        args: "",
+        about: &[
+            "Check the access to the device nodes, hwmon, debugfs and the ppfeaturemask bits,",
+            "and report which features will be unavailable and why.",
+        ],
+    },
+    SubcommandDef {
+        name: "bug-report",
+        args: "<out.tar.gz>",
+        about: &["Collect dump info, logs and metrics into an archive. (\"--bug-report\")"],
+    },
+];
+
+impl SubcommandDef {
+    pub fn find(name: &str) -> Option<&'static Self> {
+        SUBCOMMANDS.iter().find(|sub| sub.name == name)
+    }
+}
+
+pub struct OptDef {
+    /// e.g. `&["-d", "--dump"]`
+    pub names: &'static [&'static str],
+    /// `None` for the flags
+    pub value: Option<&'static str>,
+    pub help: &'static [&'static str],
+    /// `None`: common to all subcommands,
+    /// `Some(&[])`: only without a subcommand (replaced by a subcommand)
+    pub subcommands: Option<&'static [&'static str]>,
+}
+
+impl OptDef {
+    pub fn find(name: &str) -> Option<&'static Self> {
+        OPTIONS.iter().find(|def| def.names.contains(&name))
+    }
+
+    pub fn is_available(&self, subcommand: &str) -> bool {
+        self.subcommands.is_none_or(|list| list.contains(&subcommand))
+    }
+}
+
+pub const OPTIONS: &[OptDef] = &[
+    OptDef {
+        names: &["-d", "--dump"],
+        value: None,
+        help: &[
+            "Dump AMDGPU info. (Specifications, VRAM, PCI, ResizableBAR, VBIOS, Video caps, Capabilities)",
+            "This option can be combined with the \"-J\" option.",
+        ],
+        subcommands: Some(&["list"]),
+    },
+    OptDef {
+        names: &["--dump-ip-discovery"],
+        value: None,
+        help: &[
+            "Dump all instances of the IP blocks in the IP Discovery table,",
+            "with the version, harvest (fused off) state and base addresses.",
+            "This option can be combined with the \"-J\" and \"--ip-hw\" options.",
+        ],
+        subcommands: Some(&["dump"]),
+    },
+    OptDef {
+        names: &["-l", "--list"],
+        value: None,
+        help: &[
+            "Display a list of AMDGPU devices.",
+            "This option can be combined with the \"-d\" option,",
+            "which also displays the P2P (peer-to-peer) access matrix between the devices.",
+            "With \"-J\", outputs a JSON array of the devices (instance, PCI bus, names and device nodes).",
+        ],
+        subcommands: Some(&[]),
+    },
+    OptDef {
+        names: &["-J", "--json"],
+        value: None,
+        help: &[
+            "Output JSON formatted data.",
+        ],
+        subcommands: Some(&["dump", "list"]),
+    },
+    OptDef {
+        names: &["--gui"],
+        value: None,
+        help: &[
+            "Launch GUI mode.",
+        ],
+        subcommands: Some(&[]),
+    },
+    OptDef {
+        names: &["--smi"],
+        value: None,
+        help: &[
+            "Launch Simple TUI mode. (like nvidia-smi, rocm-smi)",
+        ],
+        subcommands: Some(&[]),
+    },
+    OptDef {
+        names: &["--apu", "--select-apu"],
+        value: None,
+        help: &[
+            "Select APU instance.",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["-a", "--select-all"],
+        value: None,
+        help: &[
+            "Watch all AMDGPU devices in JSON and SMI mode,",
+            "even if a device is selected with \"-i\", \"--pci\", \"--name\" or \"--apu\".",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["--revert-on-exit"],
+        value: None,
+        help: &[
+            "Used with \"--apply-profile\". Launch the selected mode after applying the profile,",
+            "and revert the changes when it exits.",
+        ],
+        subcommands: Some(LIVE),
+    },
+    OptDef {
+        names: &["-y", "--yes"],
+        value: None,
+        help: &[
+            "Used with \"--apply-profile\". Apply the profile without the confirmation.",
+            "Required if stdin or stdout is not a terminal (e.g. \"-J\" piped to another program).",
+        ],
+        subcommands: Some(&["top", "gui", "smi", "json", "record", "set"]),
+    },
+    OptDef {
+        names: &["--read-only"],
+        value: None,
+        help: &[
+            "Disable all control features (tuning profiles, watchdog actions).",
+            "The GUI greys out the controls, and \"--apply-profile\" and \"--watchdog\" are rejected.",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["-q", "--quiet"],
+        value: None,
+        help: &[
+            "Suppress the output that is not the data (hints, warnings, progress messages).",
+            "The errors are still printed to stderr, and the exit code tells the reason:",
+            "1: error, 2: \"--fail-on\", 3: no device, 4: permission denied, 5: device lost",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["-V", "--version"],
+        value: None,
+        help: &[
+            "Print version information.",
+        ],
+        subcommands: Some(&[]),
+    },
+    OptDef {
+        names: &["-h", "--help"],
+        value: None,
+        help: &[
+            "Print help information.",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["-i"],
+        value: Some("<u32>"),
+        help: &[
+            "Select GPU instance.",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["--pci"],
+        value: Some("<String>"),
+        help: &[
+            "Specifying PCI path. (domain:bus:dev.func)",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["--name"],
+        value: Some("<String>"),
+        help: &[
+            "Select GPU by a substring of the marketing name or ASIC name. (case-insensitive)",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["--pid"],
+        value: Some("<i32>"),
+        help: &[
+            "Monitor the GPU usage of the process on all devices (attach mode),",
+            "per-engine busy time and VRAM usage over time, until the process exits.",
+            "Can be combined with \"-J\" for JSON output.",
+        ],
+        subcommands: Some(&["top", "json"]),
+    },
+    OptDef {
+        names: &["--exclude-pci"],
+        value: Some("<String>"),
+        help: &[
+            "Skip the device with the PCI path. (domain:bus:dev.func)",
+            "Multiple devices can be specified as a comma-separated list or by repeating the option.",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["--ip-hw"],
+        value: Some("<String>"),
+        help: &[
+            "Filter the IP blocks of \"--dump-ip-discovery\" by name. (e.g. GC,SDMA0,VCN)",
+        ],
+        subcommands: Some(&["dump"]),
+    },
+    OptDef {
+        names: &["-s"],
+        value: Some("<u64>[ms]"),
+        help: &[
+            "Refresh period (interval) in milliseconds for JSON mode. (default: 1000ms)",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["-n"],
+        value: Some("<u32>"),
+        help: &[
+            "Specifies the maximum number of iteration (samples) for all modes.",
+            "If 0 is specified, it will be an infinite loop. (default: 0)",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["--once"],
+        value: None,
+        help: &[
+            "Print one refresh of the TUI layout to stdout as plain text, and exit.",
+            "Only the selected device, without the terminal UI. (TUI mode)",
+        ],
+        subcommands: Some(&["top"]),
+    },
+    OptDef {
+        names: &["--duration"],
+        value: Some("<N>[s|m|h]"),
+        help: &[
+            "Exit after the duration in all modes. (e.g. 60s, 5m, 1h)",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["--idle-timeout"],
+        value: Some("<N>[s|m|h]"),
+        help: &[
+            "In JSON and SMI mode, when the GPU has been idle (no activity and no engine usage",
+            "of the processes) for the duration, stretch the polling interval by 5x and skip",
+            "the process scan until the GPU becomes active. (default: disabled)",
+        ],
+        subcommands: Some(&["smi", "json", "record"]),
+    },
+    OptDef {
+        names: &["--anomaly-threshold"],
+        value: Some("<f64>"),
+        help: &[
+            "Highlight the samples of the clocks, power, temperatures and fan that deviate from",
+            "the rolling mean by the z-score in GUI mode, and add them to \"Anomalies\" in JSON mode.",
+            "0 disables it. (default: 4)",
+        ],
+        subcommands: Some(&["gui", "json", "record"]),
+    },
+    OptDef {
+        names: &["--leak-detect"],
+        value: None,
+        help: &[
+            "Track the VRAM/GTT usage of each process, and list the processes whose usage",
+            "grows monotonically over a minute or longer (suspected leaks) with the growth rate.",
+            "(TUI, GUI and \"Leak Suspects\" in JSON mode)",
+        ],
+        subcommands: Some(&["top", "gui", "json", "record"]),
+    },
+    OptDef {
+        names: &["--leak-threshold"],
+        value: Some("<f64>"),
+        help: &[
+            "The growth rate in MiB/min of \"--leak-detect\", implies it. (default: 4)",
+        ],
+        subcommands: Some(&["top", "gui", "json", "record"]),
+    },
+    OptDef {
+        names: &["--io-uring"],
+        value: None,
+        help: &[
+            "Read the fdinfo and stat files of the processes in batches with io_uring.",
+            "Falls back to the normal reads if io_uring is not available.",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["--queue-priority"],
+        value: None,
+        help: &[
+            "Show the hardware queues (rings) used by each process and the priority of them,",
+            "to check that the high priority contexts (e.g. gamescope, VR compositors) are created.",
+            "Traces the command submissions with tracefs, requires root. (TUI and GUI mode)",
+        ],
+        subcommands: Some(&["top", "gui"]),
+    },
+    OptDef {
+        names: &["--sysfs-busy"],
+        value: None,
+        help: &[
+            "Show \"gpu_busy_percent\"/\"mem_busy_percent\" of sysfs next to the activity of GRBM",
+            "and gpu_metrics, to compare with the other monitors that read the sysfs values.",
+            "(TUI, GUI and JSON mode)",
+        ],
+        subcommands: Some(&["top", "gui", "json"]),
+    },
+    OptDef {
+        names: &["--source"],
+        value: Some("<grbm|pmu>"),
+        help: &[
+            "The source of the performance counters. (default: grbm)",
+            "\"pmu\" reads the counters of the amdgpu perf PMU (DF, XGMI) instead of polling",
+            "the GRBM/GRBM2 registers, with lower overhead. (Vega20, MI100 and later, TUI and JSON mode)",
+            "Requires \"kernel.perf_event_paranoid <= 0\" or CAP_PERFMON.",
+        ],
+        subcommands: Some(&["top", "json", "record"]),
+    },
+    OptDef {
+        names: &["--sqtt-trigger"],
+        value: Some("<file>"),
+        help: &[
+            "The trigger file of the SQTT capture of RADV (MESA_VK_TRACE_TRIGGER) for Radeon GPU Profiler.",
+            "(t) in TUI mode, or Alt+S in GUI mode creates the file, and saves the metrics of the last samples",
+            "to \"amdgpu_top_sqtt_<pci>_<time>.csv\" in the same directory.",
+            "The application must be started with \"MESA_VK_TRACE=rgp MESA_VK_TRACE_TRIGGER=<file>\".",
+            "(default: $MESA_VK_TRACE_TRIGGER, $RADV_THREAD_TRACE_TRIGGER)",
+        ],
+        subcommands: Some(&["top", "gui"]),
+    },
+    OptDef {
+        names: &["--summary"],
+        value: Some("<file>"),
+        help: &[
+            "Write the session summary (min/avg/max of temperature, power, SCLK, GFX and VRAM)",
+            "of each device to the file on exit. \"-\" is stderr.",
+        ],
+        subcommands: Some(LIVE),
+    },
+    OptDef {
+        names: &["--db"],
+        value: Some("<file>"),
+        help: &[
+            "Store the min/avg/max of the metrics sampled by the mode in the SQLite database.",
+            "(requires the \"sqlite\" feature)",
+        ],
+        subcommands: Some(&["top", "gui", "smi", "json", "replay", "record", "query"]),
+    },
+    OptDef {
+        names: &["--db-interval"],
+        value: Some("<N>[s|m|h]"),
+        help: &[
+            "Downsampling interval for \"--db\". (default: 60s)",
+        ],
+        subcommands: Some(MONITOR),
+    },
+    OptDef {
+        names: &["--db-retention"],
+        value: Some("<N>[s|m|h]"),
+        help: &[
+            "Delete the rows older than the duration from \"--db\". (default: 720h, 30 days)",
+        ],
+        subcommands: Some(MONITOR),
+    },
+    OptDef {
+        names: &["--query"],
+        value: Some("<from>[..<to>]"),
+        help: &[
+            "Print the min/avg/max of the metrics in \"--db\" over the time window and exit.",
+            "<from> and <to> are durations before now. (e.g. 1h, 2h..1h)",
+        ],
+        subcommands: Some(&[]),
+    },
+    OptDef {
+        names: &["--json-output-dir"],
+        value: Some("<dir>"),
+        help: &[
+            "Write JSON data to one NDJSON file per device in the directory, instead of stdout.",
+            "Old segments are rotated and compressed with gzip.",
+        ],
+        subcommands: Some(&["json"]),
+    },
+    OptDef {
+        names: &["--json-rotate-size"],
+        value: Some("<u64>"),
+        help: &[
+            "Rotate the NDJSON file when it exceeds the size in MiB. (default: 64 MiB)",
+        ],
+        subcommands: Some(&["json", "record"]),
+    },
+    OptDef {
+        names: &["--json-rotate-interval"],
+        value: Some("<u64>"),
+        help: &[
+            "Rotate the NDJSON file after the interval in hours. (default: 24 hours)",
+        ],
+        subcommands: Some(&["json", "record"]),
+    },
+    OptDef {
+        names: &["--otlp-endpoint"],
+        value: Some("<URL>"),
+        help: &[
+            "Push the device and process metrics to the OpenTelemetry collector",
+            "with OTLP/HTTP (JSON). (e.g. http://localhost:4318)",
+            "In TUI, GUI and SMI mode, the metrics sampled by the mode are exported.",
+        ],
+        subcommands: Some(MONITOR),
+    },
+    OptDef {
+        names: &["--otlp-interval"],
+        value: Some("<u64>"),
+        help: &[
+            "Export interval in seconds for \"--otlp-endpoint\". (default: 10s)",
+        ],
+        subcommands: Some(MONITOR),
+    },
+    OptDef {
+        names: &["--listen"],
+        value: Some("<address:port>"),
+        help: &[
+            "Serve the JSON data over HTTP. (e.g. 127.0.0.1:8080)",
+            "In TUI, GUI and SMI mode, the data sampled by the mode is served.",
+            "/devices, /devices/<pci>/metrics, /devices/<pci>/processes (\"?wait\" for long-poll),",
+            "/events, /devices/<pci>/events (Server-Sent Events), / (web dashboard)",
+            "Bearer token authentication is configured in \"~/.config/amdgpu_top/server.conf\".",
+        ],
+        subcommands: Some(MONITOR),
+    },
+    OptDef {
+        names: &["--serve"],
+        value: Some("<address:port>"),
+        help: &[
+            "Stream the JSON data to the clients of \"--connect\" over TCP. (e.g. 0.0.0.0:8081)",
+            "The token in \"~/.config/amdgpu_top/server.conf\" is required if configured.",
+            "TLS is used if \"cert\" and \"key\" are set in it (the \"tls\" feature).",
+        ],
+        subcommands: Some(LIVE),
+    },
+    OptDef {
+        names: &["--connect"],
+        value: Some("<host:port|tls://host:port|ssh://[user@]host[:port]>"),
+        help: &[
+            "Show the devices of the remote \"amdgpu_top --serve\" in a summary view of TUI mode,",
+            "without a local GPU. Only the values in the JSON output are shown, not the full TUI",
+            "(no GRBM/GRBM2, no toggles of the panels), and GUI mode is not supported.",
+            "The token is read from the \"AMDGPU_TOP_TOKEN\" environment variable.",
+            "With \"tls://\", connect with TLS, the server certificate is verified",
+            "with the CA certificates in \"AMDGPU_TOP_TLS_CA\" (or the system ones).",
+            "With \"ssh://\", run \"amdgpu_top -J\" on the remote host over ssh instead.",
+            "Multiple hosts can be specified as a comma-separated list or by repeating the option,",
+            "the devices of all the hosts are shown in a table.",
+        ],
+        subcommands: Some(&["top"]),
+    },
+    OptDef {
+        names: &["--mock"],
+        value: Some("<dir>"),
+        help: &[
+            "Serve the metrics from the fixture directory (fake sysfs, hwmon and fdinfo files)",
+            "instead of the device, without a local GPU. (TUI, GUI and JSON mode)",
+            "Multiple devices can be specified by repeating the option, GUI mode shows the first device.",
+        ],
+        subcommands: Some(&["top", "gui", "json", "replay"]),
+    },
+    OptDef {
+        names: &["--demo"],
+        value: None,
+        help: &[
+            "Show the synthetic metrics of a dGPU and an APU, without a local GPU. (TUI, GUI and JSON mode)",
+            "The values are the same on every run, for the previews and the screenshots of the docs.",
+        ],
+        subcommands: Some(&["top", "gui", "json"]),
+    },
+    OptDef {
+        names: &["--screenshot"],
+        value: Some("<png>"),
+        help: &[
+            "Render one frame of the GUI of \"--mock\" or \"--demo\" (the first device) to a PNG file",
+            "without a window or OpenGL, and exit. For the screenshots and the golden-image test of the layout.",
+        ],
+        subcommands: Some(&["gui"]),
+    },
+    OptDef {
+        names: &["-u", "--update-process-index"],
+        value: Some("<u64>"),
+        help: &[
+            "Update interval in seconds of the process index for fdinfo. (default: 5s)",
+        ],
+        subcommands: None,
+    },
+    OptDef {
+        names: &["--smoothing"],
+        value: Some("<instant|1s|5s>"),
+        help: &[
+            "Averaging window for GRBM, GRBM2 and fdinfo values. (default: instant)",
+            "\"1s\" and \"5s\" are exponential moving averages.",
+        ],
+        subcommands: Some(MONITOR),
+    },
+    OptDef {
+        names: &["--fdinfo-group-by"],
+        value: Some("<process|cgroup|user>"),
+        help: &[
+            "Add the fdinfo usage aggregated by the systemd slice/scope (cgroup)",
+            "or by the user to the JSON output as \"fdinfo groups\". (default: process, not grouped)",
+            "Press \"c\" in TUI mode or use \"Group by\" in GUI mode.",
+        ],
+        subcommands: Some(&["json", "record"]),
+    },
+    OptDef {
+        names: &["--gui-backend"],
+        value: Some("<glow|wgpu|software>"),
+        help: &[
+            "Rendering backend for GUI mode. (default: glow) \"wgpu\" renders with wgpu (Vulkan).",
+            "\"software\" renders on the CPU (Mesa llvmpipe), not on the monitored GPU.",
+        ],
+        subcommands: Some(&["gui"]),
+    },
+    OptDef {
+        names: &["--gui-render-pci"],
+        value: Some("<String>"),
+        help: &[
+            "Render the GUI on the GPU with the PCI path, independently from the monitored device.",
+            "(domain:bus:dev.func, Mesa only)",
+        ],
+        subcommands: Some(&["gui"]),
+    },
+    OptDef {
+        names: &["--history-tiers"],
+        value: Some("<resolution>:<span>,.."),
+        help: &[
+            "Resolutions and spans of the long-range history in GUI mode.",
+            "(default: 1s:10m,10s:2h,1m:24h)",
+        ],
+        subcommands: Some(&["gui"]),
+    },
+    OptDef {
+        names: &["--baseline"],
+        value: Some("<file>"),
+        help: &[
+            "Overlay a session recorded by JSON mode (\"-J\" or \"--json-output-dir\") on the plots of GUI mode.",
+        ],
+        subcommands: Some(&["gui"]),
+    },
+    OptDef {
+        names: &["--frame-source"],
+        value: Some("<vblank[:<crtc>]|pipe:<path>>"),
+        help: &[
+            "Plot the frame times with the GFX usage and the throttlers in GUI mode, to find the cause of stutters.",
+            "\"vblank\" follows the frames only with VRR (FreeSync), \"pipe\" reads a frame time (ms) per line.",
+        ],
+        subcommands: Some(&["gui"]),
+    },
+    OptDef {
+        names: &["--lang"],
+        value: Some("<String>"),
+        help: &[
+            "Language for GUI and TUI mode (e.g. \"en\", \"ja\"). (default: the desktop language)",
+            "\"pseudo\" is the pseudo-locale, to find the strings that are not localized.",
+        ],
+        subcommands: Some(&["top", "gui"]),
+    },
+    OptDef {
+        names: &["--temp-unit"],
+        value: Some("<C|F>"),
+        help: &[
+            "Display temperatures in Celsius or Fahrenheit. (default: C)",
+            "JSON mode keeps the values in Celsius, and adds the unit as \"display_unit\".",
+        ],
+        subcommands: Some(MONITOR),
+    },
+    OptDef {
+        names: &["--ascii"],
+        value: None,
+        help: &[
+            "Draw TUI and SMI mode with ASCII characters only (no box-drawing or block characters),",
+            "for serial consoles and minimal terminfo. Messages default to English.",
+        ],
+        subcommands: Some(&["top", "smi"]),
+    },
+    OptDef {
+        names: &["--apply-profile"],
+        value: Some("<String>"),
+        help: &[
+            "Apply the named tuning profile (power cap, fan curve, clock limits, power profile)",
+            "from \"~/.config/amdgpu_top/profiles.conf\" to the selected device after confirmation.",
+            "Requires root privileges, or \"amdgpu_top_helper\" installed setuid root.",
+        ],
+        subcommands: Some(LIVE),
+    },
+    OptDef {
+        names: &["--watchdog"],
+        value: Some("<temp>:<seconds>:<power_cap=W|max_fan>"),
+        help: &[
+            "When the junction temperature exceeds <temp> C for <seconds>,",
+            "lower the power cap to W or set the fan to max speed, log the action",
+            "to \"~/.config/amdgpu_top/watchdog.log\" and notify.",
+            "Requires root privileges, or \"amdgpu_top_helper\" installed setuid root.",
+        ],
+        subcommands: Some(LIVE),
+    },
+    OptDef {
+        names: &["--fail-on"],
+        value: Some("<metric><op><value>[%]"),
+        help: &[
+            "In SMI and JSON mode, exit with code 2 when the condition is breached",
+            "(e.g. \"temp>100\", \"vram>95%\", \"power>=300\", comma-separated or repeated).",
+            "metric: temp|vram|gtt|power|gfx|memory|media|sclk|fan, op: > >= < <=",
+        ],
+        subcommands: Some(&["smi", "json", "record"]),
+    },
+    OptDef {
+        names: &["--bell"],
+        value: None,
+        help: &[
+            "Ring the terminal bell when a \"--fail-on\" condition is breached.",
+        ],
+        subcommands: Some(&["smi", "json", "record"]),
+    },
+    OptDef {
+        names: &["--output"],
+        value: Some("<telegraf|zabbix|zabbix-discovery>"),
+        help: &[
+            "Print the metrics of the watched devices once and exit.",
+            "\"telegraf\": InfluxDB line protocol, for Telegraf \"inputs.exec\"",
+            "\"zabbix\": input for \"zabbix_sender -i -\"",
+            "\"zabbix-discovery\": Zabbix low-level discovery (LLD) JSON",
+        ],
+        subcommands: Some(&["dump"]),
+    },
+    OptDef {
+        names: &["--journald"],
+        value: None,
+        help: &[
+            "Log the metrics of the watched devices to journald as structured fields",
+            "(AMDGPU_TEMP, AMDGPU_POWER, ...). Falls back to syslog.",
+        ],
+        subcommands: Some(LIVE),
+    },
+    OptDef {
+        names: &["--journald-interval"],
+        value: Some("<u64>"),
+        help: &[
+            "Interval in seconds for \"--journald\". (default: 10s)",
+        ],
+        subcommands: Some(LIVE),
+    },
+    OptDef {
+        names: &["--journald-priority"],
+        value: Some("<emerg|alert|crit|err|warning|notice|info|debug>"),
+        help: &[
+            "Priority of the log entries for \"--journald\". (default: info)",
+        ],
+        subcommands: Some(LIVE),
+    },
+    OptDef {
+        names: &["--journald-fields"],
+        value: Some("<temp,power,gfx,vram,sclk,mclk,fan>"),
+        help: &[
+            "Comma-separated metrics for \"--journald\". (default: all)",
+        ],
+        subcommands: Some(LIVE),
+    },
+    OptDef {
+        names: &["--agentx"],
+        value: None,
+        help: &[
+            "Run as an AgentX subagent, exposing the metrics of the watched devices",
+            "to the SNMP master agent (e.g. Net-SNMP snmpd with \"master agentx\").",
+        ],
+        subcommands: Some(LIVE),
+    },
+    OptDef {
+        names: &["--agentx-socket"],
+        value: Some("<String>"),
+        help: &[
+            "Socket path of the AgentX master agent. (default: /var/agentx/master)",
+        ],
+        subcommands: Some(LIVE),
+    },
+    OptDef {
+        names: &["--agentx-oid"],
+        value: Some("<OID>"),
+        help: &[
+            "Base OID of the GPU subtree. (default: .1.3.6.1.4.1.8072.9999.4098)",
+        ],
+        subcommands: Some(LIVE),
+    },
+    OptDef {
+        names: &["--bug-report"],
+        value: Some("<out.tar.gz>"),
+        help: &[
+            "Collect dump info (with firmware versions and gpu_metrics), kernel/Mesa versions,",
+            "module parameters, dmesg and the metrics into an archive to attach to driver issues.",
+            "The metrics are taken from \"--json-output-dir\" if specified,",
+            "otherwise recorded for \"-n\" samples or \"--duration\". (default: 10 samples)",
+        ],
+        subcommands: Some(&[]),
+    },
+    OptDef {
+        names: &["--set-nickname"],
+        value: Some("<String>"),
+        help: &[
+            "Save a nickname for the selected device (keyed by PCI bus).",
+            "An empty string removes the nickname.",
+        ],
+        subcommands: Some(&[]),
+    },
+    OptDef {
+        names: &["--generate-completion"],
+        value: Some("<bash|zsh|fish>"),
+        help: &[
+            "Print the shell completion script generated from the options and the subcommands.",
+        ],
+        subcommands: Some(&[]),
+    },
+    OptDef {
+        names: &["--generate-man"],
+        value: None,
+        help: &[
+            "Print the man page (roff) generated from the options and the subcommands.",
+        ],
+        subcommands: Some(&[]),
+    },
+];
+
+fn help_msg(sub: Option<&SubcommandDef>) -> String {
+    use std::fmt::Write;
+
+    let name = env!("CARGO_PKG_NAME");
+    let mut buf = format!(
+        "{name} {}\n{}\n\nUSAGE:\n",
+        env!("CARGO_PKG_VERSION"),
+        env!("CARGO_PKG_HOMEPAGE"),
+    );
+
+    if let Some(sub) = sub {
+        let args = if sub.args.is_empty() { String::new() } else { format!("{} ", sub.args) };
+        let _ = writeln!(buf, "    <{name}> {} {args}[options ..]", sub.name);
+
+        for line in sub.about {
+            let _ = writeln!(buf, "        {line}");
+        }
+    } else {
+        let _ = writeln!(buf, "    cargo run -- [options ..] or <{name}> [options ..]");
+        let _ = writeln!(buf, "    <{name}> <subcommand> [options ..]");
+        let _ = writeln!(buf, "    <{name}> [options ..] -- <command> [args ..]");
+        buf.push_str(concat!(
+            "        Launch the command, monitor the GPU usage of it and its children until it exits,\n",
+            "        and print the summary of the engine busy time, VRAM usage and energy. (like time(1))\n",
+            "\n",
+            "SUBCOMMANDS:\n",
+        ));
+
+        for sub in SUBCOMMANDS {
+            let _ = writeln!(buf, "   {}", format!("{} {}", sub.name, sub.args).trim_end());
+
+            for line in sub.about {
+                let _ = writeln!(buf, "       {line}");
+            }
+        }
+    }
+
+    for (title, is_flag) in [("FLAGS", true), ("OPTIONS", false)] {
+        let _ = writeln!(buf, "\n{title}:");
+
+        for def in OPTIONS {
+            if def.value.is_none() != is_flag { continue }
+            if sub.is_some_and(|sub| !def.is_available(sub.name)) { continue }
+
+            let _ = write!(buf, "   {}", def.names.join(", "));
+
+            if let Some(value) = def.value {
+                let _ = write!(buf, " {value}");
+            }
+
+            buf.push('\n');
+
+            for line in def.help {
+                let _ = writeln!(buf, "       {line}");
+            }
+        }
+    }
+
+    buf
+}
 
 impl MainOpt {
+    /// Whether a single device is explicitly selected by the options.
+    pub fn has_device_selection(&self) -> bool {
+        self.instance != 0
+        || self.pci_path.is_some()
+        || self.device_name.is_some()
+        || self.select_apu
+    }
+
+    /// `-n`, `--duration` and `--summary`
+    pub fn session_options(&self) -> SessionOptions {
+        SessionOptions {
+            duration: self.duration,
+            samples: self.json_iterations,
+            summary_path: self.summary.clone(),
+        }
+    }
+
+    /// Converts the subcommand and its positional arguments to the flags of the mode,
+    /// followed by the options after checking that they are available for the subcommand.
+    fn subcommand_args(&mut self, sub: &SubcommandDef, args: &[String]) -> Vec<String> {
+        let options = args.iter().take_while(|arg| *arg != "--");
+
+        if options.clone().any(|arg| arg == "-h" || arg == "--help") {
+            println!("{}", help_msg(Some(sub)));
+            std::process::exit(0);
+        }
+
+        let missing = || -> ! {
+            eprintln!("missing argument: \"{} {}\"", sub.name, sub.args);
+            std::process::exit(1);
+        };
+        let positional = |i: usize| args.get(i).filter(|arg| !arg.starts_with('-')).cloned();
+
+        let (flags, pos): (Vec<String>, usize) = match sub.name {
+            "top" => (Vec::new(), 0),
+            "gui" => (vec!["--gui".to_string()], 0),
+            "smi" => (vec!["--smi".to_string()], 0),
+            "json" => (vec!["-J".to_string()], 0),
+            "dump" => (vec!["-d".to_string()], 0),
+            "list" => (vec!["--list".to_string()], 0),
+            "set" => {
+                let flag = match args.first().map(|s| s.as_str()) {
+                    Some("nickname") => "--set-nickname",
+                    Some("profile") => "--apply-profile",
+                    _ => missing(),
+                };
+                // an empty nickname removes the nickname
+                let Some(val) = args.get(1).cloned() else { missing() };
+
+                (vec![flag.to_string(), val], 2)
+            },
+            "record" => {
+                let Some(dir) = positional(0) else { missing() };
+
+                (vec!["-J".to_string(), "--json-output-dir".to_string(), dir], 1)
+            },
+            "replay" => {
+                let dirs: Vec<String> = (0..).map_while(positional).collect();
+
+                if dirs.is_empty() { missing() }
+
+                let flags = std::iter::once("-J".to_string())
+                    .chain(dirs.iter().flat_map(|dir| ["--mock".to_string(), dir.clone()]))
+                    .collect();
+
+                (flags, dirs.len())
+            },
+            "query" => {
+                let Some(window) = positional(0) else { missing() };
+
+                (vec!["--query".to_string(), window], 1)
+            },
+            "bug-report" => {
+                let Some(path) = positional(0) else { missing() };
+
+                (vec!["--bug-report".to_string(), path], 1)
+            },
+            "snapshot" => {
+                let output = positional(0);
+                let pos = if output.is_some() { 1 } else { 0 };
+
+                self.subcommand = Some(Subcommand::Snapshot { output });
+
+                (Vec::new(), pos)
+            },
+            "diff" => {
+                let (Some(a), Some(b)) = (positional(0), positional(1)) else { missing() };
+
+                self.subcommand = Some(Subcommand::Diff { a, b });
+
+                (Vec::new(), 2)
+            },
+            "doctor" => {
+                self.subcommand = Some(Subcommand::Doctor);
+
+                (Vec::new(), 0)
+            },
+            _ => unreachable!(),
+        };
+
+        let mut iter = args[pos..].iter().take_while(|arg| *arg != "--");
+
+        while let Some(arg) = iter.next() {
+            // the unknown options are reported by the parser
+            let Some(def) = OptDef::find(arg) else { continue };
+
+            if !def.is_available(sub.name) {
+                eprintln!("\"{arg}\" is not an option of \"{} {}\"", env!("CARGO_PKG_NAME"), sub.name);
+                std::process::exit(1);
+            }
+
+            if def.value.is_some() {
+                let _ = iter.next();
+            }
+        }
+
+        [flags.as_slice(), &args[pos..]].concat()
+    }
+
     #[allow(unused_assignments)]
     pub fn parse() -> Self {
         let mut opt = Self::default();
         let mut skip = false;
 
         let args = &std::env::args().collect::<Vec<String>>()[1..];
+        let args = match args.first().and_then(|name| SubcommandDef::find(name)) {
+            Some(sub) => opt.subcommand_args(sub, &args[1..]),
+            None => args.to_vec(),
+        };
 
         for (idx, arg) in args.iter().enumerate() {
             if skip {
@@ -113,9 +1249,36 @@ impl MainOpt {
                         std::process::exit(1);
                     }
                 },
+                "--pid" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.pid = Some(val_str.parse::<i32>().unwrap_or_else(|_| {
+                            eprintln!("invalid PID: {val_str:?}");
+                            std::process::exit(1);
+                        }));
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--pid <i32>\"");
+                        std::process::exit(1);
+                    }
+                },
                 "-d" | "--dump" => {
                     opt.dump = true;
                 },
+                "--dump-ip-discovery" => {
+                    opt.dump_ip_discovery = true;
+                },
+                "--ip-hw" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.ip_hw = IpHwFilter::parse(val_str).unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        });
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--ip-hw <String>\"");
+                        std::process::exit(1);
+                    }
+                },
                 "-J" | "--json" => {
                     #[cfg(feature = "json")]
                     {
@@ -186,12 +1349,382 @@ impl MainOpt {
                     opt.pci_path = args.get(idx+1).map(|v| v.to_string());
                     skip = true;
                 },
+                "--name" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.device_name = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--name <String>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--smoothing" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.smoothing = val_str.parse::<Smoothing>().unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        });
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--smoothing <instant|1s|5s>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--fdinfo-group-by" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.fdinfo_group = val_str.parse::<FdInfoGroupBy>().unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        });
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--fdinfo-group-by <process|cgroup|user>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--gui-backend" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.gui_backend = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--gui-backend <glow|wgpu|software>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--gui-render-pci" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.gui_render_pci = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--gui-render-pci <String>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--history-tiers" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.history_tiers = val_str.parse().unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        });
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--history-tiers <resolution>:<span>,..\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--baseline" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.baseline = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--baseline <file>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--frame-source" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.frame_source = Some(val_str.parse::<FrameSource>().unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        }));
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--frame-source <vblank[:<crtc>]|pipe:<path>>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--lang" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.lang = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--lang <String>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--ascii" => {
+                    opt.ascii = true;
+                },
+                "--temp-unit" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.temp_unit = Some(val_str.parse::<TempUnit>().unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        }));
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--temp-unit <C|F>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--set-nickname" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.set_nickname = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--set-nickname <String>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--apply-profile" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.apply_profile = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--apply-profile <String>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--json-output-dir" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.json_output_dir = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--json-output-dir <String>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--json-rotate-size" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        let tmp = val_str.parse::<u64>().unwrap();
+
+                        if tmp != 0 {
+                            opt.json_rotate_size = tmp;
+                        }
+
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--json-rotate-size <u64>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--json-rotate-interval" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        let tmp = val_str.parse::<u64>().unwrap();
+
+                        if tmp != 0 {
+                            opt.json_rotate_interval = tmp;
+                        }
+
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--json-rotate-interval <u64>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--otlp-endpoint" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.otlp_endpoint = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--otlp-endpoint <URL>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--otlp-interval" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        let tmp = val_str.parse::<u64>().unwrap();
+
+                        if tmp != 0 {
+                            opt.otlp_interval = tmp;
+                        }
+
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--otlp-interval <u64>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--listen" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.listen = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--listen <address:port>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--serve" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        #[cfg(feature = "json")]
+                        {
+                            opt.app_mode = AppMode::JSON;
+                        }
+                        opt.serve = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--serve <address:port>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--connect" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.connect.extend(
+                            val_str.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).map(|s| s.to_string())
+                        );
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--connect <host:port|tls://host:port|ssh://[user@]host[:port]>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--demo" => {
+                    opt.demo = true;
+                },
+                "--screenshot" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.screenshot = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--screenshot <png>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--mock" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.mock.push(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--mock <dir>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--watchdog" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.watchdog = Some(val_str.parse::<WatchdogRule>().unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        }));
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--watchdog <temp>:<seconds>:<power_cap=W|max_fan>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--fail-on" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.fail_on.extend(AlertCondition::parse_list(val_str).unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        }));
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--fail-on <metric><op><value>[%]\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--bell" => {
+                    opt.bell = true;
+                },
+                "--output" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.output_format = Some(val_str.parse::<OutputFormat>().unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        }));
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--output <telegraf|zabbix|zabbix-discovery>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--journald" => {
+                    opt.journald = true;
+                },
+                "--journald-interval" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        let tmp = val_str.parse::<u64>().unwrap();
+
+                        if tmp != 0 {
+                            opt.journald_interval = tmp;
+                        }
+
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--journald-interval <u64>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--journald-priority" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.journald_priority = val_str.parse::<JournalPriority>().unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        });
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--journald-priority <emerg|alert|crit|err|warning|notice|info|debug>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--journald-fields" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.journald_fields = SysfsMetric::parse_list(val_str).unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        });
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--journald-fields <temp,power,gfx,vram,sclk,mclk,fan>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--agentx" => {
+                    opt.agentx = true;
+                },
+                "--agentx-socket" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.agentx_socket = val_str.to_string();
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--agentx-socket <String>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--agentx-oid" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.agentx_oid = AgentX::parse_oid(val_str).unwrap_or_else(|| {
+                            eprintln!("invalid OID: {val_str:?}");
+                            std::process::exit(1);
+                        });
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--agentx-oid <OID>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--revert-on-exit" => {
+                    opt.revert_on_exit = true;
+                },
+                "-y" | "--yes" => {
+                    opt.yes = true;
+                },
+                "--read-only" => {
+                    opt.read_only = true;
+                },
+                "-q" | "--quiet" => {
+                    opt.quiet = true;
+                },
                 "-l" | "--list" => {
                     opt.list = true;
                 },
                 "--apu" | "--select-apu" => {
                     opt.select_apu = true;
                 },
+                "-a" | "--select-all" => {
+                    opt.select_all = true;
+                },
+                "--exclude-pci" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.exclude_pci.extend(
+                            val_str.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).map(|s| s.to_string())
+                        );
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--exclude-pci <String>\"");
+                        std::process::exit(1);
+                    }
+                },
                 "-n" => {
                     if let Some(val_str) = args.get(idx+1) {
                         opt.json_iterations = val_str.parse::<u32>().unwrap();
@@ -201,15 +1734,193 @@ impl MainOpt {
                         std::process::exit(1);
                     }
                 },
+                "--once" => {
+                    opt.once = true;
+                },
+                "--duration" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.duration = Some(parse_duration(val_str).unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        }));
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--duration <N>[s|m|h]\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--idle-timeout" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.idle_timeout = Some(parse_duration(val_str).unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        }));
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--idle-timeout <N>[s|m|h]\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--anomaly-threshold" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.anomaly_threshold = val_str.parse::<f64>().ok()
+                            .filter(|z| *z >= 0.0)
+                            .unwrap_or_else(|| {
+                                eprintln!("invalid z-score: {val_str:?}");
+                                std::process::exit(1);
+                            });
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--anomaly-threshold <f64>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--leak-detect" => {
+                    if opt.leak_threshold == 0.0 {
+                        opt.leak_threshold = LeakDetector::DEFAULT_THRESHOLD;
+                    }
+                },
+                "--leak-threshold" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.leak_threshold = val_str.parse::<f64>().ok()
+                            .filter(|rate| *rate > 0.0)
+                            .unwrap_or_else(|| {
+                                eprintln!("invalid growth rate: {val_str:?}");
+                                std::process::exit(1);
+                            });
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--leak-threshold <f64>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--io-uring" => {
+                    opt.io_uring = true;
+                },
+                "--queue-priority" => {
+                    opt.queue_priority = true;
+                },
+                "--sysfs-busy" => {
+                    opt.sysfs_busy = true;
+                },
+                "--source" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.pmu = match val_str.as_str() {
+                            "grbm" => false,
+                            "pmu" => true,
+                            _ => {
+                                eprintln!("invalid source: {val_str:?} (grbm|pmu)");
+                                std::process::exit(1);
+                            },
+                        };
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--source <grbm|pmu>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--sqtt-trigger" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.sqtt_trigger = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--sqtt-trigger <file>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--db" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.db = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--db <String>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--db-interval" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.db_interval = parse_duration(val_str).unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        });
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--db-interval <N>[s|m|h]\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--db-retention" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.db_retention = parse_duration(val_str).unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        });
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--db-retention <N>[s|m|h]\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--query" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.query = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--query <from>[..<to>]\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--summary" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.summary = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--summary <String>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--bug-report" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.bug_report = Some(val_str.to_string());
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--bug-report <out.tar.gz>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--generate-completion" => {
+                    if let Some(val_str) = args.get(idx+1) {
+                        opt.generate_completion = Some(val_str.parse::<Shell>().unwrap_or_else(|err| {
+                            eprintln!("{err}");
+                            std::process::exit(1);
+                        }));
+                        skip = true;
+                    } else {
+                        eprintln!("missing argument: \"--generate-completion <bash|zsh|fish>\"");
+                        std::process::exit(1);
+                    }
+                },
+                "--generate-man" => {
+                    opt.generate_man = true;
+                },
+                "--" => {
+                    if args.len() <= idx+1 {
+                        eprintln!("missing argument: \"-- <command> [args ..]\"");
+                        std::process::exit(1);
+                    }
+
+                    opt.launch = args[idx+1..].to_vec();
+                    break;
+                },
                 "-V" | "--version" => {
                     opt.version = true;
                 },
                 "-h" | "--help" => {
-                    println!("{HELP_MSG}");
+                    println!("{}", help_msg(None));
                     std::process::exit(0);
                 },
                 _ => {
-                    eprintln!("Unknown option: {arg}")
+                    eprintln!("Unknown option: {arg}");
                     std::process::exit(1);
                 },
             }
@@ -218,3 +1929,84 @@ impl MainOpt {
         opt
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn replay_to_mock() {
+        let sub = SubcommandDef::find("replay").unwrap();
+        let mut opt = MainOpt::default();
+
+        assert_eq!(
+            opt.subcommand_args(sub, &args("a b -n 3")),
+            args("-J --mock a --mock b -n 3"),
+        );
+        assert_eq!(opt.subcommand, None);
+    }
+
+    #[test]
+    fn record_to_json_output_dir() {
+        let sub = SubcommandDef::find("record").unwrap();
+        let mut opt = MainOpt::default();
+
+        assert_eq!(
+            opt.subcommand_args(sub, &args("dir -s 500")),
+            args("-J --json-output-dir dir -s 500"),
+        );
+    }
+
+    fn is_available(name: &str, subcommand: &str) -> bool {
+        OptDef::find(name).unwrap().is_available(subcommand)
+    }
+
+    #[test]
+    fn subcommands_are_defined() {
+        for def in OPTIONS {
+            for name in def.subcommands.unwrap_or_default() {
+                assert!(SubcommandDef::find(name).is_some(), "{:?}: {name}", def.names);
+            }
+        }
+    }
+
+    #[test]
+    fn screenshot_sources() {
+        for name in ["--screenshot", "--mock", "--demo"] {
+            assert!(is_available(name, "gui"), "{name}");
+        }
+    }
+
+    #[test]
+    fn replay_options() {
+        for name in ["-s", "-n", "--duration", "--listen", "--otlp-endpoint", "--db", "--mock"] {
+            assert!(is_available(name, "replay"), "{name}");
+        }
+
+        // ignored by the fixtures
+        for name in [
+            "--pid",
+            "--fail-on",
+            "--idle-timeout",
+            "--anomaly-threshold",
+            "--leak-detect",
+            "--fdinfo-group-by",
+            "--json-output-dir",
+            "--sysfs-busy",
+            "--source",
+            "--summary",
+            "--serve",
+            "--watchdog",
+            "--apply-profile",
+            "--journald",
+            "--agentx",
+        ] {
+            assert!(!is_available(name, "replay"), "{name}");
+            assert!(is_available(name, "json"), "{name}");
+        }
+    }
+}
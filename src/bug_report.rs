@@ -0,0 +1,198 @@
+// ref: https://www.gnu.org/software/tar/manual/html_node/Standard.html
+
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use flate2::{Compression, write::GzEncoder};
+use libamdgpu_top::stat::KmsgReader;
+use crate::args::MainOpt;
+
+const DIR_NAME: &str = "amdgpu_top_bug_report";
+const MODULE_PARAMETERS_PATH: &str = "/sys/module/amdgpu/parameters";
+/// The tail of each NDJSON file of "--json-output-dir"
+const MAX_NDJSON_SIZE: usize = 4 << 20;
+/// When "-n" and "--duration" are not specified
+const DEFAULT_SAMPLES: u32 = 10;
+
+/// ustar archive compressed with gzip
+struct TarGz {
+    encoder: GzEncoder<File>,
+    mtime: u64,
+}
+
+impl TarGz {
+    fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let encoder = GzEncoder::new(File::create(path)?, Compression::default());
+        let mtime = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+        Ok(Self { encoder, mtime })
+    }
+
+    fn header(&self, name: &str, size: usize) -> [u8; 512] {
+        let mut h = [0u8; 512];
+        let mut field = |offset: usize, len: usize, val: &[u8]| {
+            let len = val.len().min(len);
+            h[offset..offset+len].copy_from_slice(&val[..len]);
+        };
+
+        field(0, 100, name.as_bytes());
+        field(100, 8, b"0000644\0");
+        field(108, 8, b"0000000\0");
+        field(116, 8, b"0000000\0");
+        field(124, 12, format!("{size:011o}\0").as_bytes());
+        field(136, 12, format!("{:011o}\0", self.mtime).as_bytes());
+        field(148, 8, b"        "); // checksum is calculated with spaces
+        field(156, 1, b"0");
+        field(257, 6, b"ustar\0");
+        field(263, 2, b"00");
+
+        let checksum: u32 = h.iter().map(|b| *b as u32).sum();
+        h[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+
+        h
+    }
+
+    fn add(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        let header = self.header(&format!("{DIR_NAME}/{name}"), data.len());
+        let padding = (512 - data.len() % 512) % 512;
+
+        self.encoder.write_all(&header)?;
+        self.encoder.write_all(data)?;
+        self.encoder.write_all(&vec![0u8; padding])
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        // two zero blocks mark the end of the archive
+        self.encoder.write_all(&[0u8; 1024])?;
+        self.encoder.finish()?;
+
+        Ok(())
+    }
+}
+
+/// stdout of the command, `None` if it is not installed or fails
+fn command_output<S: AsRef<OsStr>>(cmd: S, args: &[&str]) -> Option<Vec<u8>> {
+    let output = Command::new(cmd).args(args).output().ok()?;
+
+    output.status.success().then_some(output.stdout)
+}
+
+fn module_parameters() -> io::Result<String> {
+    let mut params: Vec<String> = fs::read_dir(MODULE_PARAMETERS_PATH)?
+        .flatten()
+        .filter_map(|entry| {
+            let val = fs::read_to_string(entry.path()).ok()?;
+
+            Some(format!("{} = {}", entry.file_name().to_string_lossy(), val.trim_end()))
+        })
+        .collect();
+
+    params.sort();
+
+    Ok(params.join("\n") + "\n")
+}
+
+fn dmesg() -> io::Result<String> {
+    Ok(KmsgReader::read_all()?.iter().map(|record| format!("{record}\n")).collect())
+}
+
+fn tail(data: &[u8], max: usize) -> &[u8] {
+    let data = &data[data.len().saturating_sub(max)..];
+
+    // starts from a complete line
+    match data.iter().position(|b| *b == b'\n') {
+        Some(pos) if data.len() == max => &data[pos+1..],
+        _ => data,
+    }
+}
+
+/// "--bug-report <out.tar.gz>"
+/// Collects the information for the driver issues into a single archive.
+/// The metrics are taken from "--json-output-dir" if specified, otherwise recorded now in JSON mode.
+pub fn create<P: AsRef<Path>>(path: P, main_opt: &MainOpt, title: &str) -> io::Result<()> {
+    let exe = std::env::current_exe()?;
+    let mut tar = TarGz::create(&path)?;
+    let mut skipped: Vec<String> = Vec::new();
+
+    let version = format!(
+        "{title}\n{}",
+        fs::read_to_string("/proc/version").unwrap_or_default(),
+    );
+    tar.add("version.txt", version.as_bytes())?;
+
+    eprintln!("Collecting the device information...");
+
+    // includes the firmware versions and gpu_metrics
+    match command_output(&exe, &["--list", "--dump"]) {
+        Some(dump) => tar.add("dump_info.txt", &dump)?,
+        None => skipped.push("dump_info.txt".to_string()),
+    }
+
+    for (name, cmd, args) in [
+        ("glxinfo.txt", "glxinfo", &["-B"][..]),
+        ("vulkaninfo.txt", "vulkaninfo", &["--summary"][..]),
+    ] {
+        match command_output(cmd, args) {
+            Some(out) => tar.add(name, &out)?,
+            None => skipped.push(format!("{name}: {cmd} is not available")),
+        }
+    }
+
+    match module_parameters() {
+        Ok(params) => tar.add("module_parameters.txt", params.as_bytes())?,
+        Err(err) => skipped.push(format!("module_parameters.txt: {err}")),
+    }
+
+    match dmesg() {
+        Ok(dmesg) => tar.add("dmesg.txt", dmesg.as_bytes())?,
+        Err(err) => skipped.push(format!("dmesg.txt: {err} (requires CAP_SYSLOG if kernel.dmesg_restrict is 1)")),
+    }
+
+    let mut has_metrics = false;
+
+    if let Some(dir) = &main_opt.json_output_dir {
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            let Some("ndjson") = path.extension().and_then(|ext| ext.to_str()) else { continue };
+            let Ok(data) = fs::read(&path) else { continue };
+
+            tar.add(&format!("metrics/{}", entry.file_name().to_string_lossy()), tail(&data, MAX_NDJSON_SIZE))?;
+            has_metrics = true;
+        }
+    }
+
+    if !has_metrics {
+        let samples = if main_opt.json_iterations == 0 && main_opt.duration.is_none() {
+            DEFAULT_SAMPLES
+        } else {
+            main_opt.json_iterations
+        };
+        let mut args = vec!["-J".to_string(), "-n".to_string(), samples.to_string()];
+
+        if let Some(duration) = main_opt.duration {
+            args.extend(["--duration".to_string(), duration.as_secs().to_string()]);
+        }
+
+        eprintln!("Recording the metrics in JSON mode ({})...", args.join(" "));
+
+        let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+
+        match command_output(&exe, &args) {
+            Some(metrics) if !metrics.is_empty() => tar.add("metrics/metrics.ndjson", &metrics)?,
+            _ => skipped.push("metrics/metrics.ndjson: JSON mode is not available".to_string()),
+        }
+    }
+
+    if !skipped.is_empty() {
+        tar.add("skipped.txt", (skipped.join("\n") + "\n").as_bytes())?;
+
+        for s in &skipped {
+            eprintln!("Skipped {s}");
+        }
+    }
+
+    tar.finish()
+}
@@ -0,0 +1,296 @@
+use std::fmt::Write;
+use std::str::FromStr;
+use crate::args::{OptDef, SubcommandDef, OPTIONS, SUBCOMMANDS};
+
+const APP_NAME: &str = env!("CARGO_PKG_NAME");
+
+/// `--generate-completion <bash|zsh|fish>`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Self::Bash),
+            "zsh" => Ok(Self::Zsh),
+            "fish" => Ok(Self::Fish),
+            _ => Err(format!("invalid shell: {s:?} (bash|zsh|fish)")),
+        }
+    }
+}
+
+/// The completion of the value of an option, from [`OptDef::value`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ValueKind {
+    /// `<instant|1s|5s>`
+    Choices(Vec<&'static str>),
+    /// `<file>`, `<out.tar.gz>`
+    File,
+    /// `<dir>`
+    Dir,
+    Any,
+}
+
+impl ValueKind {
+    fn from_value(value: &'static str) -> Self {
+        let Some(inner) = value.strip_prefix('<').and_then(|v| v.strip_suffix('>')) else {
+            return Self::Any;
+        };
+
+        if inner == "dir" { return Self::Dir }
+        if inner == "file" || inner.contains('.') && !inner.contains(['<', ':', '|']) { return Self::File }
+
+        let choices: Vec<&str> = inner.split('|').collect();
+        let is_word = |c: &&str| !c.is_empty() && c.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+
+        if choices.len() > 1 && choices.iter().all(is_word) {
+            Self::Choices(choices)
+        } else {
+            Self::Any
+        }
+    }
+}
+
+/// The subcommands that take files as the positional arguments
+const FILE_ARGS: &[&str] = &["record", "replay", "snapshot", "diff", "bug-report"];
+
+fn subcommand_options(sub: Option<&SubcommandDef>) -> impl Iterator<Item = &'static OptDef> + '_ {
+    OPTIONS.iter().filter(move |def| sub.is_none_or(|sub| def.is_available(sub.name)))
+}
+
+fn names(defs: impl Iterator<Item = &'static OptDef>) -> String {
+    defs.flat_map(|def| def.names.iter().copied()).collect::<Vec<_>>().join(" ")
+}
+
+pub fn print(shell: Shell) {
+    let s = match shell {
+        Shell::Bash => bash(),
+        Shell::Zsh => zsh(),
+        Shell::Fish => fish(),
+    };
+
+    print!("{s}");
+}
+
+fn bash() -> String {
+    let mut buf = String::new();
+    let subcommands: Vec<&str> = SUBCOMMANDS.iter().map(|sub| sub.name).collect();
+
+    let _ = writeln!(buf, "# bash completion for {APP_NAME}, generated by \"{APP_NAME} --generate-completion bash\"");
+    let _ = writeln!(buf, "_{APP_NAME}() {{");
+    buf.push_str("    local cur prev sub opts\n");
+    buf.push_str("    COMPREPLY=()\n");
+    buf.push_str("    cur=\"${COMP_WORDS[COMP_CWORD]}\"\n");
+    buf.push_str("    prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n\n");
+
+    buf.push_str("    case \"$prev\" in\n");
+
+    for def in OPTIONS {
+        let Some(value) = def.value else { continue };
+        let reply = match ValueKind::from_value(value) {
+            ValueKind::Choices(choices) => format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", choices.join(" ")),
+            ValueKind::File => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string(),
+            ValueKind::Dir => "COMPREPLY=($(compgen -d -- \"$cur\"))".to_string(),
+            ValueKind::Any => String::new(),
+        };
+
+        let _ = writeln!(buf, "        {})\n            {reply}\n            return;;", def.names.join("|"));
+    }
+
+    buf.push_str("    esac\n\n");
+
+    buf.push_str("    sub=\"${COMP_WORDS[1]}\"\n\n");
+    buf.push_str("    case \"$sub\" in\n");
+
+    for sub in SUBCOMMANDS {
+        let _ = writeln!(buf, "        {})", sub.name);
+        let _ = writeln!(buf, "            opts=\"{}\"", names(subcommand_options(Some(sub))));
+
+        if sub.name == "set" {
+            buf.push_str("            if [ \"$COMP_CWORD\" -eq 2 ]; then\n");
+            buf.push_str("                COMPREPLY=($(compgen -W \"nickname profile\" -- \"$cur\"))\n");
+            buf.push_str("                return\n");
+            buf.push_str("            fi\n");
+        } else if FILE_ARGS.contains(&sub.name) {
+            buf.push_str("            if [[ \"$cur\" != -* ]]; then\n");
+            let dir = if matches!(sub.name, "record" | "replay") { "-d" } else { "-f" };
+            let _ = writeln!(buf, "                COMPREPLY=($(compgen {dir} -- \"$cur\"))");
+            buf.push_str("                return\n");
+            buf.push_str("            fi\n");
+        }
+
+        buf.push_str("            ;;\n");
+    }
+
+    buf.push_str("        *)\n");
+    let _ = writeln!(buf, "            opts=\"{}\"", names(subcommand_options(None)));
+    buf.push_str("            if [ \"$COMP_CWORD\" -eq 1 ]; then\n");
+    let _ = writeln!(buf, "                opts=\"{} $opts\"", subcommands.join(" "));
+    buf.push_str("            fi\n");
+    buf.push_str("            ;;\n");
+    buf.push_str("    esac\n\n");
+
+    buf.push_str("    COMPREPLY=($(compgen -W \"$opts\" -- \"$cur\"))\n");
+    buf.push_str("}\n\n");
+    let _ = writeln!(buf, "complete -F _{APP_NAME} {APP_NAME}");
+
+    buf
+}
+
+/// The first line of the help, for the description of zsh and fish
+fn summary(help: &[&str]) -> String {
+    help.first().copied().unwrap_or_default().trim_end_matches(',').to_string()
+}
+
+fn zsh_escape(s: &str) -> String {
+    s.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh_specs(sub: Option<&SubcommandDef>) -> Vec<String> {
+    let mut specs = Vec::new();
+
+    for def in subcommand_options(sub) {
+        let desc = zsh_escape(&summary(def.help));
+        let action = def.value.map(|value| match ValueKind::from_value(value) {
+            ValueKind::Choices(choices) => format!(":value:({})", choices.join(" ")),
+            ValueKind::File => ":file:_files".to_string(),
+            ValueKind::Dir => ":dir:_files -/".to_string(),
+            ValueKind::Any => format!(":{}: ", zsh_escape(value)),
+        }).unwrap_or_default();
+
+        for name in def.names {
+            specs.push(format!("'{name}[{desc}]{action}'"));
+        }
+    }
+
+    specs
+}
+
+fn zsh() -> String {
+    let mut buf = String::new();
+
+    let _ = writeln!(buf, "#compdef {APP_NAME}");
+    let _ = writeln!(buf, "# zsh completion for {APP_NAME}, generated by \"{APP_NAME} --generate-completion zsh\"\n");
+    let _ = writeln!(buf, "_{APP_NAME}() {{");
+    buf.push_str("    local -a subcommands\n");
+    buf.push_str("    subcommands=(\n");
+
+    for sub in SUBCOMMANDS {
+        let _ = writeln!(buf, "        '{}:{}'", sub.name, zsh_escape(&summary(sub.about)));
+    }
+
+    buf.push_str("    )\n\n");
+    buf.push_str("    case \"${words[2]}\" in\n");
+
+    for sub in SUBCOMMANDS {
+        let _ = writeln!(buf, "        {})", sub.name);
+        buf.push_str("            shift words\n");
+        buf.push_str("            (( CURRENT-- ))\n");
+        buf.push_str("            _arguments -s \\\n");
+
+        if sub.name == "set" {
+            buf.push_str("                '1:property:(nickname profile)' \\\n");
+            buf.push_str("                '2:value: ' \\\n");
+        } else if sub.name == "record" {
+            buf.push_str("                '1:dir:_files -/' \\\n");
+        } else if sub.name == "replay" {
+            buf.push_str("                '*:dir:_files -/' \\\n");
+        } else if sub.name == "diff" {
+            buf.push_str("                '1:file:_files' \\\n");
+            buf.push_str("                '2:file:_files' \\\n");
+        } else if FILE_ARGS.contains(&sub.name) {
+            buf.push_str("                '1:file:_files' \\\n");
+        }
+
+        for spec in zsh_specs(Some(sub)) {
+            let _ = writeln!(buf, "                {spec} \\");
+        }
+
+        buf.push_str("                && return\n");
+        buf.push_str("            ;;\n");
+    }
+
+    buf.push_str("        *)\n");
+    buf.push_str("            _arguments -s \\\n");
+    buf.push_str("                '1: :{_describe subcommand subcommands}' \\\n");
+
+    for spec in zsh_specs(None) {
+        let _ = writeln!(buf, "                {spec} \\");
+    }
+
+    buf.push_str("                && return\n");
+    buf.push_str("            ;;\n");
+    buf.push_str("    esac\n");
+    buf.push_str("}\n\n");
+    let _ = writeln!(buf, "_{APP_NAME} \"$@\"");
+
+    buf
+}
+
+fn fish_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn fish() -> String {
+    let mut buf = String::new();
+    let subcommands: Vec<&str> = SUBCOMMANDS.iter().map(|sub| sub.name).collect();
+    let subcommands = subcommands.join(" ");
+
+    let _ = writeln!(buf, "# fish completion for {APP_NAME}, generated by \"{APP_NAME} --generate-completion fish\"");
+    let _ = writeln!(buf, "complete -c {APP_NAME} -f\n");
+
+    for sub in SUBCOMMANDS {
+        let _ = writeln!(
+            buf,
+            "complete -c {APP_NAME} -n '__fish_use_subcommand' -a {} -d '{}'",
+            sub.name,
+            fish_escape(&summary(sub.about)),
+        );
+    }
+
+    let _ = writeln!(buf, "complete -c {APP_NAME} -n '__fish_seen_subcommand_from set; and __fish_is_nth_token 2' -a 'nickname profile'");
+    let _ = writeln!(buf, "complete -c {APP_NAME} -n '__fish_seen_subcommand_from {}' -F", FILE_ARGS.join(" "));
+    buf.push('\n');
+
+    for def in OPTIONS {
+        let condition = match def.subcommands {
+            None => String::new(),
+            Some([]) => format!(" -n 'not __fish_seen_subcommand_from {subcommands}'"),
+            Some(list) => format!(" -n '__fish_seen_subcommand_from {}'", list.join(" ")),
+        };
+        let mut names = String::new();
+
+        for name in def.names {
+            if let Some(long) = name.strip_prefix("--") {
+                let _ = write!(names, " -l {long}");
+            } else if let Some(short) = name.strip_prefix('-').filter(|s| s.len() == 1) {
+                let _ = write!(names, " -s {short}");
+            } else {
+                let _ = write!(names, " -o {}", name.trim_start_matches('-'));
+            }
+        }
+
+        let value = def.value.map(|value| match ValueKind::from_value(value) {
+            ValueKind::Choices(choices) => format!(" -x -a '{}'", choices.join(" ")),
+            ValueKind::File | ValueKind::Dir => " -r -F".to_string(),
+            ValueKind::Any => " -x".to_string(),
+        }).unwrap_or_default();
+
+        let _ = writeln!(
+            buf,
+            "complete -c {APP_NAME}{condition}{names}{value} -d '{}'",
+            fish_escape(&summary(def.help)),
+        );
+    }
+
+    buf
+}
@@ -0,0 +1,292 @@
+// `--db <file>`: the samples of the mode (`SAMPLE_BUS`) are downsampled to min/avg/max per
+// `--db-interval` and stored in an SQLite database, and the rows older than `--db-retention`
+// are deleted. `--query` prints min/avg/max over a time window from the database.
+// SQLite is bundled with `rusqlite` (the "sqlite" feature), no system libsqlite3 is required.
+
+// ## Reference
+//  * <https://www.sqlite.org/cintro.html>
+//  * <https://www.sqlite.org/lang_upsert.html>
+
+use std::collections::HashMap;
+use std::io;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use rusqlite::params;
+use libamdgpu_top::{DeviceSample, SAMPLE_BUS};
+use crate::args::MainOpt;
+
+/// The current bucket is written at this interval, so a short session is not lost
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+const SCHEMA: &str = "
+    PRAGMA journal_mode = WAL;
+    CREATE TABLE IF NOT EXISTS metrics (
+        time INTEGER NOT NULL, -- unix time in seconds, the start of the bucket
+        pci TEXT NOT NULL,
+        name TEXT NOT NULL,
+        min REAL NOT NULL,
+        avg REAL NOT NULL,
+        max REAL NOT NULL,
+        count INTEGER NOT NULL,
+        PRIMARY KEY (time, pci, name)
+    );
+";
+
+const UPSERT: &str = "
+    INSERT INTO metrics (time, pci, name, min, avg, max, count) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+    ON CONFLICT (time, pci, name) DO UPDATE SET
+        min = excluded.min, avg = excluded.avg, max = excluded.max, count = excluded.count;
+";
+
+const QUERY: &str = "
+    SELECT pci, name, MIN(min), SUM(avg * count) / SUM(count), MAX(max), SUM(count)
+    FROM metrics WHERE time >= ?1 AND time < ?2
+    GROUP BY pci, name ORDER BY pci, name;
+";
+
+/// (name, unit)
+const METRICS: &[(&str, &str)] = &[
+    ("gfx", "%"),
+    ("memory", "%"),
+    ("media", "%"),
+    ("vram", "MiB"),
+    ("gtt", "MiB"),
+    ("power", "W"),
+    ("edge_temp", "C"),
+    ("junction_temp", "C"),
+    ("memory_temp", "C"),
+    ("sclk", "MHz"),
+    ("mclk", "MHz"),
+    ("fan", "RPM"),
+];
+
+fn values(sample: &DeviceSample) -> [Option<f64>; 12] {
+    let s = &sample.sensors;
+    let temp = |t: Option<&libamdgpu_top::AMDGPU::HwmonTemp>| t.map(|t| t.current as f64);
+
+    [
+        sample.activity.gfx.map(|v| v as f64),
+        sample.activity.umc.map(|v| v as f64),
+        sample.activity.media.map(|v| v as f64),
+        Some((sample.vram_usage.0.vram.heap_usage >> 20) as f64),
+        Some((sample.vram_usage.0.gtt.heap_usage >> 20) as f64),
+        s.power.map(|v| v as f64),
+        temp(s.edge_temp.as_ref()),
+        temp(s.junction_temp.as_ref()),
+        temp(s.memory_temp.as_ref()),
+        s.sclk.map(|v| v as f64),
+        s.mclk.map(|v| v as f64),
+        s.fan_rpm.map(|v| v as f64),
+    ]
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct MinAvgMax {
+    min: f64,
+    max: f64,
+    sum: f64,
+    count: i64,
+}
+
+impl MinAvgMax {
+    fn add(&mut self, val: f64) {
+        if self.count == 0 || val < self.min { self.min = val }
+        if self.count == 0 || val > self.max { self.max = val }
+
+        self.sum += val;
+        self.count += 1;
+    }
+}
+
+/// `--db`, `--db-interval` and `--db-retention`
+pub fn spawn_db_writer(main_opt: &MainOpt) {
+    let Some(path) = &main_opt.db else { return };
+    let mut db = Connection::open(path).unwrap_or_else(|err| {
+        eprintln!("Failed to open the database {path:?}: {err}");
+        std::process::exit(1);
+    });
+    let interval = main_opt.db_interval.as_secs().max(1);
+    let retention = main_opt.db_retention.as_secs();
+    let rx = SAMPLE_BUS.subscribe();
+
+    std::thread::spawn(move || {
+        let mut bucket = 0;
+        let mut last_flush = Instant::now();
+        let mut map: HashMap<(String, &'static str), MinAvgMax> = HashMap::new();
+
+        for samples in rx {
+            let now = unix_time();
+            let cur = now - now % interval;
+
+            if cur != bucket {
+                if let Err(err) = db.write(bucket, &map).and_then(|_| db.delete_before(now.saturating_sub(retention))) {
+                    eprintln!("Failed to write to the database: {err}");
+                }
+
+                bucket = cur;
+                map.clear();
+                last_flush = Instant::now();
+            }
+
+            for sample in samples.iter() {
+                let Some(pci) = sample.device_path.pci else { continue };
+                let pci = pci.to_string();
+
+                for (&(name, _), val) in METRICS.iter().zip(values(sample)) {
+                    let Some(val) = val else { continue };
+
+                    map.entry((pci.clone(), name)).or_default().add(val);
+                }
+            }
+
+            if last_flush.elapsed() >= FLUSH_INTERVAL {
+                if let Err(err) = db.write(bucket, &map) {
+                    eprintln!("Failed to write to the database: {err}");
+                }
+
+                last_flush = Instant::now();
+            }
+        }
+    });
+}
+
+/// `--query <from>[..<to>]`, the durations before now (e.g. `1h`, `2h..1h`)
+pub fn query(path: &str, window: &str) -> io::Result<()> {
+    let (from, to) = parse_window(window).map_err(io::Error::other)?;
+    let now = unix_time();
+    let db = Connection::open(path)?;
+    let mut stmt = db.0.prepare(QUERY).map_err(io::Error::other)?;
+    let from = now.saturating_sub(from.as_secs()) as i64;
+    let to = now.saturating_sub(to.as_secs()) as i64 + 1;
+    let mut rows = stmt.query([from, to]).map_err(io::Error::other)?;
+
+    println!(
+        "{:<14} {:<14} {:>10} {:>10} {:>10} {:>8}",
+        "PCI", "Metric", "Min", "Avg", "Max", "Samples",
+    );
+
+    while let Some(row) = rows.next().map_err(io::Error::other)? {
+        let [pci, name] = [0, 1].map(|i| row.get::<_, String>(i).unwrap_or_default());
+        let [min, avg, max] = [2, 3, 4].map(|i| row.get::<_, f64>(i).unwrap_or(0.0));
+        let count: i64 = row.get(5).unwrap_or(0);
+        let unit = METRICS.iter().find(|(n, _)| *n == name).map_or("", |(_, unit)| unit);
+
+        println!(
+            "{:<14} {:<14} {:>10.1} {:>10.1} {:>10.1} {:>8}",
+            pci,
+            format!("{name} ({unit})"),
+            min,
+            avg,
+            max,
+            count,
+        );
+    }
+
+    Ok(())
+}
+
+fn parse_window(s: &str) -> Result<(Duration, Duration), String> {
+    let (from, to) = match s.split_once("..") {
+        Some((from, to)) => (libamdgpu_top::parse_duration(from)?, libamdgpu_top::parse_duration(to)?),
+        None => (libamdgpu_top::parse_duration(s)?, Duration::ZERO),
+    };
+
+    if from <= to {
+        return Err(format!("invalid window: {s:?} (<from>[..<to>], from > to)"));
+    }
+
+    Ok((from, to))
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+struct Connection(rusqlite::Connection);
+
+impl Connection {
+    fn open(path: &str) -> io::Result<Self> {
+        let conn = rusqlite::Connection::open(path).map_err(io::Error::other)?;
+
+        conn.busy_timeout(Duration::from_secs(1)).map_err(io::Error::other)?;
+        conn.execute_batch(SCHEMA).map_err(io::Error::other)?;
+
+        Ok(Self(conn))
+    }
+
+    fn write(&mut self, bucket: u64, map: &HashMap<(String, &'static str), MinAvgMax>) -> io::Result<()> {
+        if map.is_empty() { return Ok(()) }
+
+        // rolled back on drop if not committed
+        let tx = self.0.transaction().map_err(io::Error::other)?;
+
+        {
+            let mut stmt = tx.prepare_cached(UPSERT).map_err(io::Error::other)?;
+
+            for ((pci, name), v) in map {
+                stmt.execute(params![bucket as i64, pci, name, v.min, v.sum / v.count as f64, v.max, v.count])
+                    .map_err(io::Error::other)?;
+            }
+        }
+
+        tx.commit().map_err(io::Error::other)
+    }
+
+    fn delete_before(&self, time: u64) -> io::Result<()> {
+        self.0.execute("DELETE FROM metrics WHERE time < ?1", [time as i64]).map_err(io::Error::other)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_query_delete() {
+        let path = std::env::temp_dir().join(format!("amdgpu_top-db-{}.sqlite", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut db = Connection::open(path).unwrap();
+        let mut map: HashMap<(String, &'static str), MinAvgMax> = HashMap::new();
+        let key = ("0000:03:00.0".to_string(), "gfx");
+
+        for v in [10.0, 20.0, 60.0] {
+            map.entry(key.clone()).or_default().add(v);
+        }
+
+        db.write(100, &map).unwrap();
+        // the current bucket is written again at the flush
+        map.entry(key.clone()).or_default().add(70.0);
+        db.write(100, &map).unwrap();
+        map.clear();
+        map.entry(key.clone()).or_default().add(0.0);
+        db.write(110, &map).unwrap();
+
+        let mut stmt = db.0.prepare(QUERY).unwrap();
+        let row: (String, String, f64, f64, f64, i64) = stmt
+            .query_row([0, 200], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)))
+            .unwrap();
+
+        assert_eq!(row, (key.0.clone(), "gfx".to_string(), 0.0, 32.0, 70.0, 5));
+        drop(stmt);
+
+        db.delete_before(110).unwrap();
+
+        let count: i64 = db.0.query_row("SELECT COUNT(*) FROM metrics", [], |r| r.get(0)).unwrap();
+
+        assert_eq!(count, 1);
+
+        drop(db);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{path}{suffix}"));
+        }
+    }
+
+    #[test]
+    fn window() {
+        assert_eq!(parse_window("1h"), Ok((Duration::from_secs(3600), Duration::ZERO)));
+        assert_eq!(parse_window("2h..1h"), Ok((Duration::from_secs(7200), Duration::from_secs(3600))));
+        assert!(parse_window("1h..2h").is_err());
+        assert!(parse_window("x").is_err());
+    }
+}
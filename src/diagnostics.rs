@@ -0,0 +1,177 @@
+use std::fmt::Write;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+use libamdgpu_top::{DevicePath, exit_code};
+use libamdgpu_top::priv_helper;
+
+// The fatal errors with the hints to fix them, instead of `eprintln!` + `panic!()`.
+// In JSON mode, the error is also printed to stdout as a JSON object (`{"error": {..}}`),
+// so the consumers of the output do not have to parse stderr.
+// The process exits with the code of the kind (`ErrorKind::exit_code`).
+
+static JSON_MODE: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "json")]
+pub fn set_json_mode(json: bool) {
+    JSON_MODE.store(json, Ordering::Relaxed);
+}
+
+/// The escape sequences of colors, unless the output is redirected or `NO_COLOR` is set
+pub fn use_color<T: IsTerminal>(stream: T) -> bool {
+    stream.is_terminal() && std::env::var_os("NO_COLOR").is_none()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorKind {
+    DriverNotLoaded,
+    NoDevice,
+    PermissionDenied,
+    DeviceInit,
+    InvalidArgument,
+    FeatureDisabled,
+    Io,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DriverNotLoaded => "driver_not_loaded",
+            Self::NoDevice => "no_device",
+            Self::PermissionDenied => "permission_denied",
+            Self::DeviceInit => "device_init",
+            Self::InvalidArgument => "invalid_argument",
+            Self::FeatureDisabled => "feature_disabled",
+            Self::Io => "io",
+        }
+    }
+
+    /// See `libamdgpu_top::exit_code`
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::DriverNotLoaded | Self::NoDevice => exit_code::NO_DEVICE,
+            Self::PermissionDenied => exit_code::PERMISSION_DENIED,
+            Self::DeviceInit
+            | Self::InvalidArgument
+            | Self::FeatureDisabled
+            | Self::Io => exit_code::ERROR,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub hints: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new<S: Into<String>>(kind: ErrorKind, message: S) -> Self {
+        Self { kind, message: message.into(), hints: Vec::new() }
+    }
+
+    pub fn hint<S: Into<String>>(mut self, hint: S) -> Self {
+        self.hints.push(hint.into());
+        self
+    }
+
+    /// `"<message>: <err>"`, with the hints for the permission errors
+    pub fn io(message: &str, err: &io::Error) -> Self {
+        let diag = Self::new(ErrorKind::Io, format!("{message}: {err}"));
+
+        match err.kind() {
+            io::ErrorKind::PermissionDenied => Self { kind: ErrorKind::PermissionDenied, ..diag }
+                .hint("check the owner and the permission of the file or the directory"),
+            _ => diag,
+        }
+    }
+
+    pub fn device_init<E: std::fmt::Display>(err: E, device_path: &DevicePath, io_err: Option<&io::Error>) -> Self {
+        let render = device_path.render.display();
+        let pci = device_path.pci.map(|pci| format!(" ({pci})")).unwrap_or_default();
+        let diag = Self::new(ErrorKind::DeviceInit, format!("Failed to open {render}{pci}: {err}"));
+
+        match io_err.map(|err| err.kind()) {
+            Some(io::ErrorKind::PermissionDenied) => Self { kind: ErrorKind::PermissionDenied, ..diag }
+                .hint(format!("add your user to the group of {render} (usually \"render\" or \"video\"): sudo usermod -aG render $USER, then log in again"))
+                .hint("or run with sudo"),
+            Some(io::ErrorKind::NotFound) => diag
+                .hint("the device node is missing, the device may be removed or the driver is reloading"),
+            _ => diag
+                .hint("check the kernel log (dmesg) for the errors of amdgpu"),
+        }
+    }
+
+    pub fn feature_disabled(feature: &str) -> Self {
+        Self::new(ErrorKind::FeatureDisabled, format!("\"{feature}\" feature is not enabled for this build."))
+            .hint(format!("rebuild with \"cargo build --release --features {feature}\""))
+    }
+
+    pub fn invalid_pci(pci_path: &str) -> Self {
+        Self::new(ErrorKind::InvalidArgument, format!("Failed to parse from {pci_path:?} to `PCI::BUS_INFO`"))
+            .hint("the PCI path is \"domain:bus:dev.func\", e.g. \"0000:03:00.0\"")
+            .hint("\"amdgpu_top --list\" shows the PCI path of the devices")
+    }
+
+    pub fn read_only(option: &str) -> Self {
+        Self::new(ErrorKind::InvalidArgument, format!("\"{option}\" is not available in read-only mode."))
+            .hint("remove \"--read-only\" to enable the control features")
+    }
+
+    pub fn root_required(message: String) -> Self {
+        Self::new(ErrorKind::PermissionDenied, message)
+            .hint("run with sudo")
+            .hint(format!("or install \"{}\" setuid root (see PRIVILEGE SEPARATION in the man page)", priv_helper::HELPER_NAME))
+    }
+
+    pub fn print(&self) {
+        let color = use_color(io::stderr());
+        let [red, cyan, reset] = if color { ["\x1b[1;31m", "\x1b[1;36m", "\x1b[0m"] } else { [""; 3] };
+
+        eprintln!("{red}error{reset}: {}", self.message);
+
+        if !exit_code::is_quiet() {
+            for hint in &self.hints {
+                eprintln!("  {cyan}hint{reset}: {hint}");
+            }
+        }
+
+        if JSON_MODE.load(Ordering::Relaxed) {
+            println!("{}", self.json());
+        }
+    }
+
+    pub fn json(&self) -> String {
+        let hints: Vec<String> = self.hints.iter().map(|hint| json_str(hint)).collect();
+
+        format!(
+            "{{\"error\":{{\"kind\":{},\"message\":{},\"hints\":[{}]}}}}",
+            json_str(self.kind.as_str()),
+            json_str(&self.message),
+            hints.join(","),
+        )
+    }
+
+    pub fn exit(self) -> ! {
+        self.print();
+        std::process::exit(self.kind.exit_code());
+    }
+}
+
+fn json_str(s: &str) -> String {
+    let mut buf = String::with_capacity(s.len() + 2);
+    buf.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            c if c.is_control() => { let _ = write!(buf, "\\u{:04x}", c as u32); },
+            c => buf.push(c),
+        }
+    }
+
+    buf.push('"');
+    buf
+}
@@ -0,0 +1,296 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use libamdgpu_top::DevicePath;
+use libamdgpu_top::priv_helper::{self, ControlCapabilities};
+use crate::diagnostics;
+
+// `amdgpu_top doctor`: checks the access to the files that amdgpu_top reads,
+// and reports which features will be unavailable and why, instead of showing blanks.
+
+const DEBUGFS_DRI: &str = "/sys/kernel/debug/dri";
+const PPFEATUREMASK: &str = "/sys/module/amdgpu/parameters/ppfeaturemask";
+/// ref: drivers/gpu/drm/amd/include/amd_shared.h
+const PP_OVERDRIVE_MASK: u32 = 0x4000;
+const PP_GFXOFF_MASK: u32 = 0x8000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    Ok,
+    /// some features are unavailable
+    Warn,
+    /// amdgpu_top does not work
+    Fail,
+}
+
+struct Check {
+    status: Status,
+    name: String,
+    detail: String,
+    /// The features unavailable if not `Status::Ok`
+    unavailable: &'static str,
+    hint: Option<String>,
+}
+
+impl Check {
+    fn new<S: Into<String>>(name: S, status: Status, detail: String) -> Self {
+        Self { status, name: name.into(), detail, unavailable: "", hint: None }
+    }
+
+    fn unavailable(mut self, features: &'static str) -> Self {
+        if self.status != Status::Ok {
+            self.unavailable = features;
+        }
+        self
+    }
+
+    fn hint<S: Into<String>>(mut self, hint: S) -> Self {
+        if self.status != Status::Ok {
+            self.hint = Some(hint.into());
+        }
+        self
+    }
+
+    fn print(&self) {
+        let color = diagnostics::use_color(io::stdout());
+        let (tag, code) = match self.status {
+            Status::Ok => ("  OK  ", "\x1b[1;32m"),
+            Status::Warn => (" WARN ", "\x1b[1;33m"),
+            Status::Fail => (" FAIL ", "\x1b[1;31m"),
+        };
+        let [code, reset] = if color { [code, "\x1b[0m"] } else { [""; 2] };
+
+        println!("[{code}{tag}{reset}] {}: {}", self.name, self.detail);
+
+        if !self.unavailable.is_empty() {
+            println!("         unavailable: {}", self.unavailable);
+        }
+
+        if let Some(hint) = &self.hint {
+            println!("         hint: {hint}");
+        }
+    }
+}
+
+fn open_status(path: &Path, write: bool) -> (Status, String) {
+    let res = fs::OpenOptions::new().read(true).write(write).open(path);
+    let access = if write { "read/write" } else { "read" };
+
+    match res {
+        Ok(_) => (Status::Ok, access.to_string()),
+        Err(err) => (Status::Warn, format!("{} ({access})", err_str(&err))),
+    }
+}
+
+fn err_str(err: &io::Error) -> String {
+    match err.kind() {
+        io::ErrorKind::PermissionDenied => "permission denied".to_string(),
+        io::ErrorKind::NotFound => "not found".to_string(),
+        _ => err.to_string(),
+    }
+}
+
+fn is_root() -> bool {
+    fs::metadata("/proc/self").is_ok_and(|m| std::os::unix::fs::MetadataExt::uid(&m) == 0)
+}
+
+fn global_checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let driver = Path::new("/sys/bus/pci/drivers/amdgpu").exists();
+    checks.push(
+        Check::new(
+            "amdgpu driver",
+            if driver { Status::Ok } else { Status::Fail },
+            (if driver { "loaded" } else { "not loaded" }).to_string(),
+        )
+        .unavailable("all")
+        .hint("sudo modprobe amdgpu, \"lspci -k\" shows the kernel driver in use of the GPU"),
+    );
+
+    // the fdinfo of the processes of other users requires CAP_SYS_PTRACE
+    let (status, detail) = match fs::read_dir("/proc/1/fdinfo") {
+        Ok(_) => (Status::Ok, "readable".to_string()),
+        Err(err) => (Status::Warn, err_str(&err)),
+    };
+    checks.push(
+        Check::new("fdinfo of other users' processes", status, detail)
+            .unavailable("the usage of the processes of other users (e.g. the compositor, the other sessions)")
+            .hint("run with sudo to see all processes"),
+    );
+
+    let (status, detail) = open_status(Path::new("/dev/kmsg"), false);
+    checks.push(
+        Check::new("/dev/kmsg", status, detail)
+            .unavailable("kernel messages panel (TUI), dmesg of \"--bug-report\"")
+            .hint("kernel.dmesg_restrict is 1: run with sudo, or set \"sysctl kernel.dmesg_restrict=0\""),
+    );
+
+    let (status, detail, hint) = match fs::read_dir(DEBUGFS_DRI) {
+        Ok(_) => (Status::Ok, "readable".to_string(), ""),
+        // /sys/kernel/debug is not accessible except for root
+        Err(err) if err.kind() == io::ErrorKind::NotFound && is_root() =>
+            (Status::Warn, "not mounted".to_string(), "mount debugfs: sudo mount -t debugfs none /sys/kernel/debug"),
+        Err(err) => (Status::Warn, err_str(&err), "debugfs requires root: run with sudo"),
+    };
+    checks.push(
+        Check::new(DEBUGFS_DRI, status, detail)
+            .unavailable("GFXOFF%, VCN state, buffer objects (BO) breakdown and list, amdgpu_pm_info")
+            .hint(hint),
+    );
+
+    match fs::read_to_string(PPFEATUREMASK) {
+        Ok(s) => {
+            let mask = u32::from_str_radix(s.trim().trim_start_matches("0x"), 16).unwrap_or(0);
+            let od = mask & PP_OVERDRIVE_MASK != 0;
+            let gfxoff = mask & PP_GFXOFF_MASK != 0;
+
+            checks.push(
+                Check::new(
+                    "ppfeaturemask (overdrive)",
+                    if od { Status::Ok } else { Status::Warn },
+                    format!("{:#x}, overdrive {}", mask, if od { "enabled" } else { "disabled" }),
+                )
+                .unavailable("clock limits (pp_od_clk_voltage), fan curve of tuning profiles")
+                .hint(format!(
+                    "add \"amdgpu.ppfeaturemask={:#x}\" to the kernel parameters",
+                    mask | PP_OVERDRIVE_MASK,
+                )),
+            );
+
+            if !gfxoff {
+                checks.push(
+                    Check::new("ppfeaturemask (GFXOFF)", Status::Warn, format!("{mask:#x}, GFXOFF disabled"))
+                        .unavailable("GFXOFF (the power saving of the idle GFX), GFXOFF% is always 0"),
+                );
+            }
+        },
+        Err(err) => checks.push(
+            Check::new("ppfeaturemask", Status::Warn, err_str(&err))
+                .unavailable("the check of the overdrive and GFXOFF"),
+        ),
+    }
+
+    checks
+}
+
+fn device_checks(device_path: &DevicePath) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let (status, detail) = open_status(&device_path.render, true);
+    let status = if status == Status::Ok { status } else { Status::Fail };
+    checks.push(
+        Check::new(device_path.render.display().to_string(), status, detail)
+            .unavailable("the device (all the metrics)")
+            .hint(format!(
+                "add your user to the group of {} (usually \"render\"): sudo usermod -aG render $USER, then log in again",
+                device_path.render.display(),
+            )),
+    );
+
+    let Some(pci) = device_path.pci else { return checks };
+    let sysfs = pci.get_sysfs_path();
+
+    match pci.get_hwmon_path() {
+        Some(hwmon) => {
+            let (status, detail) = open_status(&hwmon.join("temp1_input"), false);
+            checks.push(
+                Check::new(hwmon.display().to_string(), status, detail)
+                    .unavailable("temperatures, power, fan speed, voltage")
+                    .hint("check the permission of the hwmon files"),
+            );
+        },
+        None => checks.push(
+            Check::new("hwmon", Status::Warn, "not found".to_string())
+                .unavailable("temperatures, power, fan speed, voltage")
+                .hint("the device may be in a low power state (BACO) or the driver failed to initialize SMU, check dmesg"),
+        ),
+    }
+
+    let (status, detail) = open_status(&sysfs.join("gpu_metrics"), false);
+    checks.push(
+        Check::new("gpu_metrics", status, detail)
+            .unavailable("throttle status, per-core clocks and the activity of gpu_metrics")
+            .hint("gpu_metrics is supported on Vega12 and later (SMU v11+)"),
+    );
+
+    if let Some(instance) = device_path.get_instance_number() {
+        let (status, detail) = open_status(Path::new(&format!("{DEBUGFS_DRI}/{instance}/amdgpu_gem_info")), false);
+        checks.push(
+            Check::new(format!("{DEBUGFS_DRI}/{instance}"), status, detail)
+                .unavailable("buffer objects (BO) breakdown and list, GFXOFF%, VCN state")
+                .hint("debugfs requires root: run with sudo"),
+        );
+    }
+
+    let caps = ControlCapabilities::detect(&pci);
+    let writable: Vec<&str> = [
+        (caps.power_cap, "power cap"),
+        (caps.fan, "fan"),
+        (caps.fan_curve, "fan curve"),
+        (caps.od_clk, "clock limits"),
+        (caps.power_profile, "power profile"),
+    ].iter().filter_map(|(b, name)| b.then_some(*name)).collect();
+    let (status, detail) = if priv_helper::is_read_only() {
+        (Status::Warn, "read-only mode".to_string())
+    } else if writable.is_empty() {
+        (Status::Warn, "nothing writable".to_string())
+    } else {
+        (Status::Ok, format!("writable: {}", writable.join(", ")))
+    };
+    checks.push(
+        Check::new("control", status, detail)
+            .unavailable("tuning profiles, watchdog actions, the controls of GUI mode")
+            .hint(format!("run with sudo, or install \"{}\" setuid root", priv_helper::HELPER_NAME)),
+    );
+
+    checks
+}
+
+/// `Err` if amdgpu_top does not work (e.g. the driver is not loaded, the device cannot be opened)
+pub fn run(list: &[DevicePath]) -> Result<(), ()> {
+    let mut all = Vec::new();
+
+    println!("System");
+
+    for check in global_checks() {
+        check.print();
+        all.push(check);
+    }
+
+    if list.is_empty() {
+        let check = Check::new("AMD GPU devices", Status::Fail, "not found".to_string())
+            .unavailable("all")
+            .hint("SI/CIK GPUs use the radeon driver by default, add \"amdgpu.si_support=1 radeon.si_support=0\" (or cik_support) to the kernel parameters");
+
+        check.print();
+        all.push(check);
+    }
+
+    for device_path in list {
+        let instance = device_path.get_instance_number().map(|i| format!("#{i}")).unwrap_or_default();
+        let pci = device_path.pci.map(|pci| pci.to_string()).unwrap_or_default();
+        let name = device_path.init().ok()
+            .map(|amdgpu_dev| amdgpu_dev.get_marketing_name_or_default())
+            .unwrap_or_default();
+
+        println!("\n{instance} {name} ({pci})");
+
+        for check in device_checks(device_path) {
+            check.print();
+            all.push(check);
+        }
+    }
+
+    let [warn, fail] = [Status::Warn, Status::Fail].map(|s| all.iter().filter(|c| c.status == s).count());
+
+    println!();
+
+    if warn == 0 && fail == 0 {
+        println!("All features are available.");
+    } else {
+        println!("{fail} failed, {warn} warnings.");
+    }
+
+    if fail == 0 { Ok(()) } else { Err(()) }
+}
@@ -1,14 +1,74 @@
 use libamdgpu_top::{
     AMDGPU::{
         VIDEO_CAPS::CODEC,
-        FW_VERSION::FW_TYPE,
         DeviceHandle,
         GPU_INFO,
     },
     AppDeviceInfo,
-    stat::Sensors,
+    Capability,
+    DevicePath,
+    get_firmware_versions,
+    is_harvested,
+    IpHwFilter,
+    P2pMatrix,
+    priv_helper::ControlCapabilities,
+    stat::{Sensors, XgmiStat},
 };
 
+/// `--dump-ip-discovery`
+pub fn dump_ip_discovery(device_path_list: &[DevicePath], filter: &IpHwFilter) {
+    for device_path in device_path_list {
+        let Ok(amdgpu_dev) = device_path.init() else { continue };
+        let Ok(ext_info) = amdgpu_dev.device_info() else { continue };
+        let Ok(memory_info) = amdgpu_dev.memory_info() else { continue };
+        let Ok(pci_bus) = amdgpu_dev.get_pci_bus_info() else { continue };
+        let sensors = Sensors::new(&amdgpu_dev, &pci_bus, &ext_info);
+        let info = AppDeviceInfo::new(&amdgpu_dev, &ext_info, &memory_info, &sensors);
+
+        println!("{pci_bus} [{}]", info.marketing_name);
+
+        if info.ip_die_entries.is_empty() {
+            println!("IP Discovery table is not available. (requires Linux 5.19 or later)\n");
+            continue;
+        }
+
+        info.ip_discovery_table(filter);
+        println!();
+    }
+}
+
+/// `--list -d` with multiple devices
+pub fn p2p_matrix(device_path_list: &[DevicePath]) {
+    let devices: Vec<_> = device_path_list.iter().filter_map(|device_path| device_path.pci).collect();
+    let matrix = P2pMatrix::new(&devices);
+
+    println!("P2P access (row: from, column: to the VRAM of):");
+    print!("{:12}", "");
+    for pci in &matrix.devices {
+        print!("  {pci}");
+    }
+    println!();
+
+    for (pci, row) in matrix.devices.iter().zip(matrix.access.iter()) {
+        print!("{pci}");
+        for access in row {
+            print!("  {:12}", access.map_or("", |access| access.label()));
+        }
+        println!();
+    }
+
+    println!();
+
+    for (pci, row) in matrix.devices.iter().zip(matrix.access.iter()) {
+        for (peer, access) in matrix.devices.iter().zip(row.iter()) {
+            let Some(access) = access else { continue };
+            println!("    {pci} -> {peer}: {access}");
+        }
+    }
+
+    println!();
+}
+
 pub fn dump(title: &str, amdgpu_dev: &DeviceHandle) {
     let ext_info = amdgpu_dev.device_info().unwrap();
     let memory_info = amdgpu_dev.memory_info().unwrap();
@@ -32,10 +92,14 @@ pub fn dump(title: &str, amdgpu_dev: &DeviceHandle) {
         println!("Supported Power Profiles: {profiles:?}");
     }
     info.cache_info();
+    capability_info(&info);
     if !info.ip_die_entries.is_empty() {
-        info.ip_discovery_table();
+        info.ip_discovery_table(&IpHwFilter::default());
     }
     fw_info(amdgpu_dev);
+    if let Some(xgmi) = XgmiStat::new(&pci_bus) {
+        xgmi_info(&xgmi);
+    }
     info.codec_info();
     info.vbios_info();
     if let Ok(metrics) = amdgpu_dev.get_gpu_metrics() {
@@ -65,6 +129,16 @@ fn sensors_info(sensors: &Sensors) {
         println!("Power Cap.          : {:3} W ({}-{} W)", cap.current, cap.min, cap.max);
         println!("Power Cap. (Default): {:3} W", cap.default);
     }
+    if let Some(limits) = &sensors.power_limits {
+        println!("APU Power Limits    :");
+        for limit in limits.iter() {
+            print!("    {:<15} : {:3} W", limit.type_.to_string(), limit.cap);
+            if let [Some(min), Some(max)] = [limit.min, limit.max] {
+                print!(" ({min}-{max} W)");
+            }
+            println!();
+        }
+    }
     if let Some(fan_max_rpm) = &sensors.fan_max_rpm {
         println!("Fan RPM (Max)       : {fan_max_rpm} RPM");
     }
@@ -96,45 +170,53 @@ fn sensors_info(sensors: &Sensors) {
     }
 }
 
-fn fw_info(amdgpu_dev: &DeviceHandle) {
-    const FW_LIST: &[FW_TYPE] = &[
-        FW_TYPE::VCE,
-        FW_TYPE::UVD,
-        FW_TYPE::GMC,
-        FW_TYPE::GFX_ME,
-        FW_TYPE::GFX_PFP,
-        FW_TYPE::GFX_CE,
-        FW_TYPE::GFX_RLC,
-        FW_TYPE::GFX_MEC,
-        FW_TYPE::SMC,
-        FW_TYPE::SDMA,
-        FW_TYPE::SOS,
-        FW_TYPE::ASD,
-        FW_TYPE::VCN,
-        FW_TYPE::GFX_RLC_RESTORE_LIST_CNTL,
-        FW_TYPE::GFX_RLC_RESTORE_LIST_GPM_MEM,
-        FW_TYPE::GFX_RLC_RESTORE_LIST_SRM_MEM,
-        FW_TYPE::DMCU,
-        FW_TYPE::TA,
-        FW_TYPE::DMCUB,
-        FW_TYPE::TOC,
-    ];
+fn capability_info(info: &AppDeviceInfo) {
+    let caps = ControlCapabilities::detect(&info.pci_bus);
+    let capabilities = Capability::detect_all(info, &caps);
+    let len = capabilities.iter().map(|cap| cap.name.len()).max().unwrap_or(0);
 
+    println!("\nCapabilities:");
+
+    for cap in &capabilities {
+        println!("    {:len$}: {}", cap.name, cap.status_str());
+    }
+}
+
+fn fw_info(amdgpu_dev: &DeviceHandle) {
     println!("\nFirmware info:");
 
-    for fw_type in FW_LIST {
-        let fw_info = match amdgpu_dev.query_firmware_version(*fw_type, 0, 0) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
+    for fw in get_firmware_versions(amdgpu_dev) {
+        println!(
+            "    {fw_type:<8} feature: {ftr:>3}, ver: {ver:>#10X}",
+            fw_type = fw.fw_type.to_string(),
+            ftr = fw.feature,
+            ver = fw.version,
+        );
+    }
+}
+
+fn xgmi_info(xgmi: &XgmiStat) {
+    println!("\nXGMI:");
+    println!("    Hive ID: {:#X}, Node ID: {:#X}, Physical ID: {}", xgmi.node.hive_id, xgmi.node.node_id, xgmi.node.physical_id);
+
+    if let [Some(width), Some(speed)] = [xgmi.link_width, xgmi.link_speed] {
+        println!("    Link: x{width} {speed} Gbps");
+    }
+
+    println!("    Errors: {}", xgmi.errors);
 
-        let (ver, ftr) = (fw_info.version, fw_info.feature);
+    if let [Some(ce), Some(ue)] = [xgmi.wafl_ce, xgmi.wafl_ue] {
+        println!("    WAFL Errors: CE {ce}, UE {ue}");
+    }
 
-        if ver == 0 { continue }
+    for peer in &xgmi.peers {
+        let pci = peer.pci_bus.map(|pci| pci.to_string()).unwrap_or_else(|| "-".to_string());
 
         println!(
-            "    {fw_type:<8} feature: {ftr:>3}, ver: {ver:>#10X}",
-            fw_type = fw_type.to_string(),
+            "    -> GPU{:<2} ({pci}) hops: {}, links: {}",
+            peer.physical_id,
+            peer.num_hops,
+            peer.num_links.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string()),
         );
     }
 }
@@ -146,7 +228,7 @@ trait DumpInfo {
     fn cache_info(&self);
     fn vbios_info(&self);
     fn codec_info(&self);
-    fn ip_discovery_table(&self);
+    fn ip_discovery_table(&self, filter: &IpHwFilter);
 }
 
 impl DumpInfo for AppDeviceInfo {
@@ -159,6 +241,22 @@ impl DumpInfo for AppDeviceInfo {
             self.ext_info.device_id(),
             self.ext_info.pci_rev_id()
         );
+
+        if let Some(numa_node) = self.placement.numa_node {
+            println!("NUMA Node                : {numa_node}");
+        }
+
+        if let Some(cpulist) = &self.placement.local_cpulist {
+            println!("Local CPUs               : {cpulist}");
+        }
+
+        if let Some(group) = &self.placement.iommu_group {
+            println!("IOMMU Group              : {}", group.id);
+
+            for dev in &group.devices {
+                println!("    {} ({})", dev.pci, dev.driver.as_deref().unwrap_or("-"));
+            }
+        }
     }
 
     fn gfx_info(&self) {
@@ -281,21 +379,31 @@ impl DumpInfo for AppDeviceInfo {
         }
     }
 
-    fn ip_discovery_table(&self) {
+    /// All instances, with the harvested (fused off) ones
+    fn ip_discovery_table(&self, filter: &IpHwFilter) {
         println!("\nIP Discovery table:");
         for die in &self.ip_die_entries {
             println!("    die_id: {:>2}", die.die_id);
 
-            for ip_hw in &die.ip_hw_ids {
+            for ip_hw in filter.filter(die) {
                 let hw_id = ip_hw.hw_id.to_string();
-                let Some(inst_info) = ip_hw.instances.first() else { continue };
-                println!(
-                    "        {hw_id:<10} num: {}, ver: {:>3}.{}.{}",
-                    ip_hw.instances.len(),
-                    inst_info.major,
-                    inst_info.minor,
-                    inst_info.revision,
-                );
+                println!("        {hw_id:<10} num: {}", ip_hw.instances.len());
+
+                for inst in &ip_hw.instances {
+                    let base_addr: Vec<String> = inst.base_address.iter().map(|addr| format!("{addr:#X}")).collect();
+                    print!(
+                        "            #{}: ver: {:>3}.{}.{}, harvest: {:#X}",
+                        inst.num_instance,
+                        inst.major,
+                        inst.minor,
+                        inst.revision,
+                        inst.harvest,
+                    );
+                    if is_harvested(inst) {
+                        print!(" (harvested)");
+                    }
+                    println!(", base_addr: [{}]", base_addr.join(", "));
+                }
             }
         }
     }
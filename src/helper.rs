@@ -0,0 +1,9 @@
+//! Privileged helper for the control features of amdgpu_top.
+//! Install it setuid root (or launch it with pkexec) so that amdgpu_top can run unprivileged.
+
+fn main() {
+    if let Err(err) = libamdgpu_top::priv_helper::run_helper() {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
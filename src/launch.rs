@@ -0,0 +1,134 @@
+use libamdgpu_top::{DevicePath, PCI};
+use libamdgpu_top::AMDGPU::DeviceHandle;
+use libamdgpu_top::stat::{PidMonitor, Sensors};
+use std::process::{Command, ExitStatus};
+use std::time::{Duration, Instant};
+use crate::MainOpt;
+
+// `amdgpu_top -- <command> [args ..]`
+// The driver does not report the power per process, so the energy is of the whole device
+// while the command is running.
+
+const POLL: Duration = Duration::from_millis(10);
+
+struct DeviceEnergy {
+    amdgpu_dev: DeviceHandle,
+    pci_bus: PCI::BUS_INFO,
+    sensors: Sensors,
+    /// J
+    energy: f64,
+}
+
+impl DeviceEnergy {
+    fn new(device_path: &DevicePath) -> Option<Self> {
+        let amdgpu_dev = device_path.init().ok()?;
+        let ext_info = amdgpu_dev.device_info().ok()?;
+        let pci_bus = amdgpu_dev.get_pci_bus_info().ok()?;
+        let sensors = Sensors::new(&amdgpu_dev, &pci_bus, &ext_info);
+
+        Some(Self { amdgpu_dev, pci_bus, sensors, energy: 0.0 })
+    }
+
+    fn update(&mut self, elapsed: Duration) {
+        self.sensors.update(&self.amdgpu_dev);
+
+        if let Some(power) = self.sensors.power {
+            self.energy += power as f64 * elapsed.as_secs_f64();
+        }
+    }
+}
+
+/// Launch the command and print the summary to stderr when it exits.
+/// Exits with the exit code of the command.
+pub fn run(main_opt: &MainOpt, command: &[String], device_path_list: &[DevicePath]) {
+    let start = Instant::now();
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .spawn()
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to launch {:?}: {err}", command[0]);
+            std::process::exit(127);
+        });
+
+    let mut monitor = PidMonitor::new(child.id() as i32, device_path_list, main_opt.smoothing);
+    let mut energy: Vec<DeviceEnergy> = device_path_list.iter().filter_map(DeviceEnergy::new).collect();
+    let period = Duration::from_millis(main_opt.refresh_period);
+
+    if let Some(monitor) = &mut monitor {
+        monitor.include_children = true;
+    }
+
+    let status = 'main: loop {
+        let sample_start = Instant::now();
+
+        while sample_start.elapsed() < period {
+            std::thread::sleep(POLL);
+
+            if let Ok(Some(status)) = child.try_wait() {
+                for device in energy.iter_mut() {
+                    device.update(sample_start.elapsed());
+                }
+
+                break 'main status;
+            }
+        }
+
+        if let Some(monitor) = &mut monitor {
+            monitor.update(period);
+        }
+
+        for device in energy.iter_mut() {
+            device.update(period);
+        }
+    };
+
+    print_summary(command, status, start.elapsed(), monitor.as_ref(), &energy);
+
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+fn print_summary(
+    command: &[String],
+    status: ExitStatus,
+    elapsed: Duration,
+    monitor: Option<&PidMonitor>,
+    energy: &[DeviceEnergy],
+) {
+    eprintln!();
+    eprintln!("Command: {}", command.join(" "));
+    eprintln!("Exit Status: {status}");
+    eprintln!("Elapsed: {:.2}s", elapsed.as_secs_f64());
+
+    for device in monitor.map(|m| m.devices.as_slice()).unwrap_or_default() {
+        // not used by the command
+        if device.peak_vram == 0 && device.peak_gtt == 0 { continue }
+
+        let t = &device.total;
+
+        eprintln!();
+        eprintln!("{} ({})", device.marketing_name, device.pci_bus);
+        eprintln!("    Engine Busy Time:");
+
+        for (label, ns) in [
+            ("GFX", t.gfx),
+            ("Compute", t.compute),
+            ("DMA", t.dma),
+            ("Decode", t.dec + t.vcn_jpeg),
+            ("Encode", t.enc + t.uvd_enc),
+            ("Media", t.media),
+        ] {
+            eprintln!(
+                "        {label:<8}: {:>10.3}s ({:>3}%)",
+                ns as f64 / 1_000_000_000.0,
+                (ns as f64 * 100.0 / elapsed.as_nanos() as f64).round() as i64,
+            );
+        }
+
+        eprintln!("    Peak VRAM : {:>6} MiB", device.peak_vram >> 10);
+        eprintln!("    Peak GTT  : {:>6} MiB", device.peak_gtt >> 10);
+
+        if let Some(e) = energy.iter().find(|e| e.pci_bus == device.pci_bus) {
+            eprintln!("    Energy    : {:>10.1} J (whole device)", e.energy);
+        }
+    }
+}
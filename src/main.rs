@@ -1,5 +1,8 @@
-use libamdgpu_top::{DevicePath, PCI};
+use std::sync::{Arc, Mutex};
+use libamdgpu_top::{AgentX, DeviceNicknames, DevicePath, JournalLogger, PCI, TuningBackup, TuningProfile, Watchdog};
 use libamdgpu_top::AMDGPU::DeviceHandle;
+use libamdgpu_top::exit_code;
+use libamdgpu_top::priv_helper::{self, ControlCapabilities};
 
 const APP_NAME: &str = env!("CARGO_PKG_NAME");
 #[cfg(feature = "git_version")]
@@ -8,21 +11,125 @@ const TITLE: &str = concat!(env!("CARGO_PKG_NAME"), " v", env!("CARGO_PKG_VERSIO
 const TITLE: &str = concat!(env!("CARGO_PKG_NAME"), " v", env!("CARGO_PKG_VERSION"));
 
 mod args;
-use args::{AppMode, MainOpt};
+use args::{AppMode, MainOpt, Subcommand};
 mod dump_info;
+mod bug_report;
+mod output_format;
+mod completion;
+mod man_page;
+mod launch;
+mod diagnostics;
+mod doctor;
+use diagnostics::{Diagnostic, ErrorKind};
+#[cfg(feature = "sqlite")]
+mod db;
 
 fn main() {
     let main_opt = MainOpt::parse();
-    let device_path_list = DevicePath::get_device_path_list();
+
+    // `--revert-on-exit`: Ctrl-C (JSON and GUI mode) and `kill` are received by the thread of
+    // `guard_profile` to revert the profile, before any other thread is spawned
+    if main_opt.revert_on_exit && main_opt.apply_profile.is_some() {
+        if let Err(err) = exit_code::block_termination_signals() {
+            Diagnostic::io("Failed to block the termination signals", &err).exit();
+        }
+    }
+
+    #[cfg(feature = "json")]
+    diagnostics::set_json_mode(main_opt.app_mode == AppMode::JSON);
+
+    priv_helper::set_read_only(main_opt.read_only);
+    libamdgpu_top::uring::set_enabled(main_opt.io_uring);
+    libamdgpu_top::pmu::set_enabled(main_opt.pmu);
+    libamdgpu_top::ring_trace::set_enabled(main_opt.queue_priority);
+    libamdgpu_top::exit_code::set_quiet(main_opt.quiet);
+    libamdgpu_top::sqtt::set_trigger_path(main_opt.sqtt_trigger.as_ref().map(std::path::PathBuf::from));
+
+    if let Some(shell) = main_opt.generate_completion {
+        completion::print(shell);
+        return;
+    }
+
+    if main_opt.generate_man {
+        man_page::print();
+        return;
+    }
+
+    if let Some(window) = &main_opt.query {
+        query_db(&main_opt, window);
+        return;
+    }
+
+    if let Some(Subcommand::Diff { a, b }) = &main_opt.subcommand {
+        diff_snapshots(a, b);
+        return;
+    }
+
+    // also checks the driver and the devices that are not found
+    if main_opt.subcommand == Some(Subcommand::Doctor) {
+        let list = DevicePath::try_get_device_path_list().unwrap_or_default();
+
+        if doctor::run(&exclude_devices(&main_opt, list)).is_err() {
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    // the client does not need the local GPU
+    if !main_opt.connect.is_empty() {
+        run_remote(&main_opt, &main_opt.connect);
+        return;
+    }
+
+    if !main_opt.mock.is_empty() {
+        run_mock(&main_opt);
+        return;
+    }
+
+    if main_opt.demo {
+        run_demo(&main_opt);
+        return;
+    }
+
+    if main_opt.screenshot.is_some() {
+        Diagnostic::new(ErrorKind::InvalidArgument, "\"--screenshot\" requires \"--mock\" or \"--demo\"")
+            .hint("e.g. amdgpu_top --demo --screenshot amdgpu_top.png")
+            .exit();
+    }
+
+    let device_path_list = DevicePath::try_get_device_path_list().unwrap_or_else(|_| {
+        Diagnostic::new(ErrorKind::DriverNotLoaded, "The AMDGPU driver is not loaded.")
+            .hint("load the driver: sudo modprobe amdgpu")
+            .hint("\"lspci -k\" shows the kernel driver in use of the GPU")
+            .exit()
+    });
+    let device_path_list = exclude_devices(&main_opt, device_path_list);
 
     if device_path_list.is_empty() {
-        eprintln!("There are no the AMD GPU devices found.");
-        panic!();
+        Diagnostic::new(ErrorKind::NoDevice, "There are no the AMD GPU devices found.")
+            .hint("\"lspci -k\" shows the kernel driver in use of the GPU")
+            .hint("SI/CIK GPUs use the radeon driver by default, add \"amdgpu.si_support=1 radeon.si_support=0\" (or cik_support) to the kernel parameters")
+            .hint("\"--exclude-pci\" may exclude all devices")
+            .exit();
+    }
+
+    if main_opt.dump_ip_discovery {
+        let list = watch_list(&main_opt, &device_path_list);
+
+        #[cfg(feature = "json")]
+        if main_opt.app_mode == AppMode::JSON {
+            amdgpu_top_json::dump_ip_discovery_json(&list, &main_opt.ip_hw);
+            return;
+        }
+
+        dump_info::dump_ip_discovery(&list, &main_opt.ip_hw);
+        return;
     }
 
     #[cfg(feature = "json")]
     if main_opt.app_mode == AppMode::JSON && main_opt.dump {
-        amdgpu_top_json::dump_json(&device_path_list);
+        amdgpu_top_json::dump_json(&watch_list(&main_opt, &device_path_list));
         return;
     } else if main_opt.app_mode == AppMode::JSON && main_opt.version {
         amdgpu_top_json::version_json(TITLE);
@@ -34,37 +141,156 @@ fn main() {
         return;
     }
 
+    #[cfg(feature = "json")]
+    if main_opt.app_mode == AppMode::JSON && main_opt.list {
+        amdgpu_top_json::list_json(&device_path_list);
+        return;
+    }
+
     if main_opt.list {
         device_list(main_opt.dump, &device_path_list);
         return;
     }
 
+    if let Some(Subcommand::Snapshot { output }) = &main_opt.subcommand {
+        save_snapshot(&main_opt, output.as_deref(), &watch_list(&main_opt, &device_path_list));
+        return;
+    }
+
+    if let Some(path) = &main_opt.bug_report {
+        match bug_report::create(path, &main_opt, TITLE) {
+            Ok(_) => if !exit_code::is_quiet() { eprintln!("Saved the bug report to {path:?}") },
+            Err(err) => Diagnostic::io(&format!("Failed to create the bug report {path:?}"), &err).exit(),
+        }
+
+        return;
+    }
+
+    if let Some(format) = main_opt.output_format {
+        output_format::print(format, &watch_list(&main_opt, &device_path_list));
+        return;
+    }
+
+    if !main_opt.launch.is_empty() {
+        launch::run(&main_opt, &main_opt.launch, &watch_list(&main_opt, &device_path_list));
+        return;
+    }
+
+    if let Some(pid) = main_opt.pid {
+        run_pid(&main_opt, pid, &watch_list(&main_opt, &device_path_list));
+        return;
+    }
+
     #[cfg(feature = "json")]
     if let AppMode::JSON = main_opt.app_mode {
+        let tuning_backup = main_opt.apply_profile.as_ref().map(|name| {
+            let (_, amdgpu_dev) = select_device(&main_opt, &device_path_list);
+
+            apply_profile(&amdgpu_dev, name, main_opt.yes)
+        });
+
+        if tuning_backup.is_some() && !main_opt.revert_on_exit {
+            return;
+        }
+
+        let tuning_backup = guard_profile(tuning_backup);
+
+        spawn_watchdog(&main_opt, &watch_list(&main_opt, &device_path_list));
+        spawn_journal_logger(&main_opt, &watch_list(&main_opt, &device_path_list));
+        spawn_agentx(&main_opt, &watch_list(&main_opt, &device_path_list));
+
         let mut j = amdgpu_top_json::JsonApp::new(
-            &device_path_list,
+            &watch_list(&main_opt, &device_path_list),
             main_opt.refresh_period,
             main_opt.update_process_index,
             main_opt.json_iterations,
+            main_opt.smoothing,
         );
 
-        j.run(TITLE);
+        if j.vec_device_info.is_empty() {
+            // the error of the first device, e.g. permission denied
+            if let Some(device_path) = watch_list(&main_opt, &device_path_list).first() {
+                let _ = init_device(device_path);
+            }
+
+            Diagnostic::new(ErrorKind::NoDevice, "Failed to open the AMD GPU devices.")
+                .hint("check the kernel log (dmesg) for the errors of amdgpu")
+                .exit();
+        }
+
+        if let Some(dir) = &main_opt.json_output_dir {
+            let output_dir = amdgpu_top_json::JsonOutputDir::new(
+                dir,
+                main_opt.json_rotate_size << 20,
+                std::time::Duration::from_secs(main_opt.json_rotate_interval * 60 * 60),
+            ).unwrap_or_else(|err| Diagnostic::io(&format!("Failed to create {dir:?}"), &err).exit());
+
+            j.output_dir = Some(output_dir);
+        }
+
+        j.otlp = otlp_exporter(&main_opt);
+        j.http_server = http_server(&main_opt);
+        j.remote = remote_server(&main_opt);
+        spawn_db_writer(&main_opt);
+
+        j.temp_unit = main_opt.temp_unit.unwrap_or_default();
+        j.fail_on = main_opt.fail_on.clone();
+        j.session = main_opt.session_options();
+        j.idle.timeout = main_opt.idle_timeout;
+
+        for device in j.vec_device_info.iter_mut() {
+            device.fdinfo_group = main_opt.fdinfo_group;
+            device.anomaly = libamdgpu_top::AnomalyDetector::new(main_opt.anomaly_threshold);
+            device.leak = libamdgpu_top::LeakDetector::new(main_opt.leak_threshold);
+            device.sysfs_busy = main_opt.sysfs_busy;
+        }
+
+        let exit = j.run(TITLE);
+
+        revert_profile(&tuning_backup);
+        exit.exit(main_opt.bell);
 
         return;
     }
 
-    let (device_path, amdgpu_dev) = if main_opt.select_apu {
-        select_apu(&device_path_list)
-    } else {
-        from_main_opt(&main_opt, &device_path_list)
-    };
+    let (device_path, amdgpu_dev) = select_device(&main_opt, &device_path_list);
+
+    if let Some(nickname) = &main_opt.set_nickname {
+        set_nickname(&amdgpu_dev, nickname);
+        return;
+    }
 
     if main_opt.dump {
         dump_info::dump(TITLE, &amdgpu_dev);
         return;
     }
 
-    match main_opt.app_mode {
+    let tuning_backup = main_opt.apply_profile.as_ref().map(|name| apply_profile(&amdgpu_dev, name, main_opt.yes));
+
+    if tuning_backup.is_some() && !main_opt.revert_on_exit {
+        return;
+    }
+
+    let tuning_backup = guard_profile(tuning_backup);
+
+    #[cfg(feature = "tui")]
+    let background_list = if main_opt.app_mode == AppMode::SMI {
+        watch_list(&main_opt, &device_path_list)
+    } else {
+        vec![device_path.clone()]
+    };
+    #[cfg(not(feature = "tui"))]
+    let background_list = vec![device_path.clone()];
+
+    spawn_watchdog(&main_opt, &background_list);
+    spawn_journal_logger(&main_opt, &background_list);
+    spawn_agentx(&main_opt, &background_list);
+    // fed by the sampling loop of the mode
+    #[cfg(feature = "json")]
+    amdgpu_top_json::spawn_sample_frontends(http_server(&main_opt), otlp_exporter(&main_opt));
+    spawn_db_writer(&main_opt);
+
+    let exit = match main_opt.app_mode {
         AppMode::TUI => {
             #[cfg(feature = "tui")]
             {
@@ -73,32 +299,94 @@ fn main() {
                     device_path,
                     amdgpu_dev,
                     &device_path_list,
-                    main_opt.update_process_index
+                    main_opt.update_process_index,
+                    main_opt.smoothing,
+                    main_opt.lang.as_deref(),
+                    main_opt.temp_unit.unwrap_or_default(),
+                    main_opt.ascii,
+                    &main_opt.session_options(),
+                    main_opt.leak_threshold,
+                    main_opt.sysfs_busy,
+                    main_opt.once,
                 )
             }
             #[cfg(not(feature = "tui"))]
             {
                 eprintln!("\"tui\" feature is not enabled for this build.");
                 dump_info::dump(TITLE, &amdgpu_dev);
+                exit_code::ExitReason::Done
             }
         },
         #[cfg(feature = "gui")]
-        AppMode::GUI => amdgpu_top_gui::run(
-            APP_NAME,
-            TITLE,
-            device_path,
-            amdgpu_dev,
-            &device_path_list,
-            main_opt.update_process_index,
-        ),
+        AppMode::GUI => {
+            amdgpu_top_gui::run(
+                APP_NAME,
+                TITLE,
+                device_path,
+                amdgpu_dev,
+                &device_path_list,
+                main_opt.update_process_index,
+                main_opt.smoothing,
+                gui_backend(&main_opt),
+                gui_render_pci(&main_opt),
+                main_opt.lang.as_deref(),
+                main_opt.temp_unit,
+                main_opt.session_options(),
+                main_opt.history_tiers.clone(),
+                main_opt.baseline.as_deref(),
+                main_opt.anomaly_threshold,
+                main_opt.frame_source.clone(),
+                main_opt.leak_threshold,
+                main_opt.sysfs_busy,
+            );
+
+            exit_code::ExitReason::Done
+        },
         #[cfg(feature = "json")]
         AppMode::JSON => unreachable!(),
         #[cfg(feature = "tui")]
         AppMode::SMI => amdgpu_top_tui::run_smi(
             TITLE,
-            &device_path_list,
+            &watch_list(&main_opt, &device_path_list),
             main_opt.update_process_index,
+            main_opt.smoothing,
+            main_opt.lang.as_deref(),
+            main_opt.temp_unit.unwrap_or_default(),
+            main_opt.ascii,
+            &main_opt.fail_on,
+            &main_opt.session_options(),
+            main_opt.idle_timeout,
         ),
+    };
+
+    // before exiting with the code of `--fail-on` or the lost device
+    revert_profile(&tuning_backup);
+    exit.exit(main_opt.bell);
+}
+
+/// The backup of `--apply-profile`, reverted by [`revert_profile`] when the mode returns,
+/// or on SIGINT, SIGTERM and SIGHUP if the mode is stopped by them.
+fn guard_profile(tuning_backup: Option<TuningBackup>) -> Arc<Mutex<Option<TuningBackup>>> {
+    let has_backup = tuning_backup.is_some();
+    let tuning_backup = Arc::new(Mutex::new(tuning_backup));
+
+    if has_backup {
+        let tuning_backup = tuning_backup.clone();
+
+        exit_code::on_termination_signal(move || revert_profile(&tuning_backup));
+    }
+
+    tuning_backup
+}
+
+fn revert_profile(tuning_backup: &Mutex<Option<TuningBackup>>) {
+    // the lock is held while reverting, a signal in the meantime waits for it and finds `None`
+    let mut lock = tuning_backup.lock().unwrap_or_else(|err| err.into_inner());
+    let Some(backup) = lock.take() else { return };
+
+    match backup.revert() {
+        Ok(_) => eprintln!("Reverted the tuning profile."),
+        Err(err) => eprintln!("Failed to revert the tuning profile: {err}"),
     }
 }
 
@@ -109,48 +397,527 @@ pub fn device_list(dump_info: bool, list: &[DevicePath]) {
 
         println!("#{instance}");
 
+        if let Some(nickname) = device_path.nickname() {
+            println!("Nickname = {nickname:?}");
+        }
+
         if dump_info {
             dump_info::dump(TITLE, &amdgpu_dev);
         } else {
             println!("Marketing Name = {:?}", amdgpu_dev.get_marketing_name_or_default());
         }
         println!("{device_path:?}");
+        print_device_nodes(device_path);
         println!();
     }
+
+    if dump_info && list.len() > 1 {
+        dump_info::p2p_matrix(list);
+    }
+}
+
+fn run_mock(main_opt: &MainOpt) {
+    let period = std::time::Duration::from_millis(main_opt.refresh_period);
+    let devices: Vec<_> = main_opt.mock.iter().map(|dir| {
+        libamdgpu_top::MockDevice::load(dir, period).unwrap_or_else(|err| {
+            Diagnostic::io(&format!("Failed to load the fixture {dir:?}"), &err).exit()
+        })
+    }).collect();
+
+    run_mock_devices(main_opt, devices);
+}
+
+fn run_demo(main_opt: &MainOpt) {
+    let period = std::time::Duration::from_millis(main_opt.refresh_period);
+
+    run_mock_devices(main_opt, libamdgpu_top::MockDevice::demo_all(period));
+}
+
+/// `--mock` and `--demo`, the samples are also published to `SAMPLE_BUS`
+/// for the HTTP server, the OTLP exporter and the database.
+#[allow(unused_mut)]
+fn run_mock_devices(main_opt: &MainOpt, mut devices: Vec<libamdgpu_top::MockDevice>) {
+    let period = std::time::Duration::from_millis(main_opt.refresh_period);
+
+    if let Some(png) = &main_opt.screenshot {
+        screenshot(main_opt, &mut devices[0], png);
+        return;
+    }
+
+    #[cfg(feature = "json")]
+    amdgpu_top_json::spawn_sample_frontends(http_server(main_opt), otlp_exporter(main_opt));
+    spawn_db_writer(main_opt);
+
+    match main_opt.app_mode {
+        #[cfg(feature = "json")]
+        AppMode::JSON => amdgpu_top_json::run_mock(
+            TITLE,
+            &mut devices,
+            main_opt.refresh_period,
+            main_opt.json_iterations,
+            &main_opt.session_options(),
+        ),
+        #[cfg(feature = "tui")]
+        AppMode::TUI => amdgpu_top_tui::run_mock(
+            TITLE,
+            devices,
+            period,
+            main_opt.smoothing,
+            main_opt.lang.as_deref(),
+            main_opt.temp_unit.unwrap_or_default(),
+            main_opt.ascii,
+            &main_opt.session_options(),
+        ),
+        #[cfg(feature = "gui")]
+        AppMode::GUI => amdgpu_top_gui::run_mock(
+            APP_NAME,
+            TITLE,
+            // one device per window
+            devices.into_iter().next().unwrap(),
+            period,
+            gui_backend(main_opt),
+            main_opt.lang.as_deref(),
+            main_opt.temp_unit,
+            main_opt.session_options(),
+        ),
+        _ => {
+            let _ = (devices, period);
+
+            Diagnostic::new(ErrorKind::InvalidArgument, "\"--mock\" and \"--demo\" are only supported in TUI, GUI and JSON mode")
+                .hint("e.g. amdgpu_top --demo")
+                .exit();
+        },
+    }
+}
+
+/// `--screenshot <png>`
+fn screenshot(main_opt: &MainOpt, device: &mut libamdgpu_top::MockDevice, png: &str) {
+    #[cfg(feature = "gui")]
+    {
+        amdgpu_top_gui::screenshot(device, main_opt.lang.as_deref(), main_opt.temp_unit, png)
+            .unwrap_or_else(|err| Diagnostic::io(&format!("Failed to save the screenshot {png:?}"), &err).exit());
+    }
+    #[cfg(not(feature = "gui"))]
+    {
+        let _ = (main_opt, device, png);
+        Diagnostic::feature_disabled("gui").exit();
+    }
+}
+
+fn run_pid(main_opt: &MainOpt, pid: i32, list: &[DevicePath]) {
+    #[cfg(feature = "json")]
+    if main_opt.app_mode == AppMode::JSON {
+        amdgpu_top_json::run_pid(
+            TITLE,
+            pid,
+            list,
+            main_opt.refresh_period,
+            main_opt.json_iterations,
+            &main_opt.session_options(),
+        );
+        return;
+    }
+
+    #[cfg(feature = "tui")]
+    {
+        amdgpu_top_tui::run_pid(
+            TITLE,
+            pid,
+            list,
+            main_opt.smoothing,
+            main_opt.lang.as_deref(),
+            main_opt.ascii,
+            &main_opt.session_options(),
+        );
+    }
+    #[cfg(not(feature = "tui"))]
+    {
+        let _ = (main_opt, pid, list);
+        Diagnostic::feature_disabled("tui").exit();
+    }
+}
+
+fn spawn_db_writer(main_opt: &MainOpt) {
+    #[cfg(feature = "sqlite")]
+    db::spawn_db_writer(main_opt);
+    #[cfg(not(feature = "sqlite"))]
+    if main_opt.db.is_some() {
+        Diagnostic::feature_disabled("sqlite").exit();
+    }
+}
+
+fn query_db(main_opt: &MainOpt, window: &str) {
+    let Some(path) = &main_opt.db else {
+        Diagnostic::new(ErrorKind::InvalidArgument, "\"--query\" requires \"--db <file>\"")
+            .hint("e.g. amdgpu_top query 1h --db ~/amdgpu_top.db")
+            .exit()
+    };
+
+    #[cfg(feature = "sqlite")]
+    if let Err(err) = db::query(path, window) {
+        eprintln!("Failed to query the database {path:?}: {err}");
+        std::process::exit(1);
+    }
+    #[cfg(not(feature = "sqlite"))]
+    {
+        let _ = (path, window);
+        Diagnostic::feature_disabled("sqlite").exit();
+    }
+}
+
+fn save_snapshot(main_opt: &MainOpt, output: Option<&str>, device_path_list: &[DevicePath]) {
+    #[cfg(feature = "json")]
+    {
+        let interval = std::time::Duration::from_millis(main_opt.refresh_period);
+        let snapshot = amdgpu_top_json::snapshot(TITLE, device_path_list, interval);
+        let s = format!("{snapshot:#}");
+
+        let Some(path) = output else {
+            println!("{s}");
+            return;
+        };
+
+        if let Err(err) = std::fs::write(path, s + "\n") {
+            Diagnostic::io(&format!("Failed to write the snapshot to {path:?}"), &err).exit();
+        }
+
+        if !exit_code::is_quiet() {
+            eprintln!("Saved the snapshot to {path:?}");
+        }
+    }
+    #[cfg(not(feature = "json"))]
+    {
+        let _ = (main_opt, output, device_path_list);
+        Diagnostic::feature_disabled("json").exit();
+    }
+}
+
+fn diff_snapshots(a: &str, b: &str) {
+    #[cfg(feature = "json")]
+    {
+        let [sa, sb] = [a, b].map(|path| amdgpu_top_json::load_snapshot(path).unwrap_or_else(|err| {
+            Diagnostic::io(&format!("Failed to load the snapshot {path:?}"), &err).exit()
+        }));
+
+        print!("{}", amdgpu_top_json::diff(&sa, &sb));
+    }
+    #[cfg(not(feature = "json"))]
+    {
+        let _ = (a, b);
+        Diagnostic::feature_disabled("json").exit();
+    }
+}
+
+fn run_remote(main_opt: &MainOpt, addrs: &[String]) {
+    #[cfg(feature = "tui")]
+    {
+        let token = std::env::var("AMDGPU_TOP_TOKEN").ok();
+
+        amdgpu_top_tui::run_remote(
+            TITLE,
+            addrs,
+            token.as_deref(),
+            main_opt.lang.as_deref(),
+            main_opt.ascii,
+        );
+    }
+    #[cfg(not(feature = "tui"))]
+    {
+        let _ = (main_opt, addrs);
+        Diagnostic::feature_disabled("tui").exit();
+    }
+}
+
+#[cfg(feature = "gui")]
+fn gui_backend(main_opt: &MainOpt) -> amdgpu_top_gui::GuiBackend {
+    let Some(backend) = &main_opt.gui_backend else { return Default::default() };
+
+    backend.parse().unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    })
+}
+
+#[cfg(feature = "gui")]
+fn gui_render_pci(main_opt: &MainOpt) -> Option<PCI::BUS_INFO> {
+    let pci_path = main_opt.gui_render_pci.as_ref()?;
+
+    let pci = pci_path.parse::<PCI::BUS_INFO>().unwrap_or_else(|_| Diagnostic::invalid_pci(pci_path).exit());
+
+    Some(pci)
+}
+
+fn print_device_nodes(device_path: &DevicePath) {
+    for (node, by_path) in [
+        (&device_path.card, device_path.by_path_card()),
+        (&device_path.render, device_path.by_path_render()),
+    ] {
+        let Some(by_path) = by_path else { continue };
+
+        println!("{} -> {}", by_path.display(), node.display());
+    }
+}
+
+fn set_nickname(amdgpu_dev: &DeviceHandle, nickname: &str) {
+    let pci = amdgpu_dev.get_pci_bus_info().unwrap_or_else(|err| {
+        eprintln!("Failed to get PCI bus info ({err})");
+        std::process::exit(1);
+    });
+    let mut nicknames = DeviceNicknames::load();
+
+    nicknames.set(&pci, nickname);
+
+    if let Err(err) = nicknames.save() {
+        Diagnostic::io("Failed to save nickname", &err).exit();
+    }
+
+    match nicknames.get(&pci) {
+        Some(name) => println!("{pci}: {name:?}"),
+        None => println!("{pci}: nickname removed"),
+    }
+}
+
+fn apply_profile(amdgpu_dev: &DeviceHandle, name: &str, yes: bool) -> TuningBackup {
+    if priv_helper::is_read_only() {
+        Diagnostic::read_only("--apply-profile").exit();
+    }
+
+    let Some(profile) = TuningProfile::find(name) else {
+        Diagnostic::new(ErrorKind::InvalidArgument, format!("Tuning profile {name:?} is not found in {:?}", TuningProfile::config_path()))
+            .hint("a profile is a \"[name]\" section of the file, see \"--apply-profile\" in the man page")
+            .exit()
+    };
+    let pci = amdgpu_dev.get_pci_bus_info().unwrap_or_else(|err| {
+        eprintln!("Failed to get PCI bus info ({err})");
+        std::process::exit(1);
+    });
+    let caps = ControlCapabilities::detect(&pci);
+    let not_writable: Vec<String> = profile.required_nodes()
+        .into_iter()
+        .filter(|node| !caps.is_writable(*node))
+        .map(|node| node.to_string())
+        .collect();
+
+    if !not_writable.is_empty() {
+        Diagnostic::root_required(format!(
+            "Cannot apply the tuning profile {name:?} to {pci}: {} not writable",
+            not_writable.join(", "),
+        )).exit();
+    }
+
+    // stderr, stdout is the data of the mode (e.g. JSON)
+    eprintln!("Apply the tuning profile {name:?} to {} ({pci}):", amdgpu_dev.get_marketing_name_or_default());
+    for change in profile.changes() {
+        eprintln!("    {change}");
+    }
+
+    if !yes {
+        use std::io::IsTerminal;
+
+        if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+            Diagnostic::new(ErrorKind::InvalidArgument, "\"--apply-profile\" cannot ask for the confirmation, stdin or stdout is not a terminal")
+                .hint("add \"--yes\" to apply the profile without the confirmation")
+                .exit();
+        }
+
+        eprint!("Continue? [y/N] ");
+
+        let mut answer = String::new();
+        let _ = std::io::stdin().read_line(&mut answer);
+
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            eprintln!("Canceled.");
+            std::process::exit(0);
+        }
+    }
+
+    profile.apply(&pci).unwrap_or_else(|err| Diagnostic::io("Failed to apply the tuning profile", &err).exit())
+}
+
+fn spawn_watchdog(main_opt: &MainOpt, list: &[DevicePath]) {
+    let Some(rule) = main_opt.watchdog else { return };
+
+    if priv_helper::is_read_only() {
+        Diagnostic::read_only("--watchdog").exit();
+    }
+
+    for device_path in list {
+        let Some(pci) = device_path.pci else { continue };
+
+        if !ControlCapabilities::detect(&pci).is_writable(rule.action.required_node()) {
+            eprintln!(
+                "Watchdog: {pci}: cannot {} ({} is not writable, requires root or {})",
+                rule.action,
+                rule.action.required_node(),
+                priv_helper::HELPER_NAME,
+            );
+            continue;
+        }
+
+        let Some(watchdog) = Watchdog::new(rule, pci) else { continue };
+
+        watchdog.spawn_thread(std::time::Duration::from_secs(1));
+    }
+}
+
+fn spawn_journal_logger(main_opt: &MainOpt, list: &[DevicePath]) {
+    if !main_opt.journald { return }
+
+    for device_path in list {
+        let Some(pci) = device_path.pci else { continue };
+        let Some(logger) = JournalLogger::new(
+            pci,
+            &main_opt.journald_fields,
+            main_opt.journald_priority,
+        ) else { continue };
+
+        logger.spawn_thread(std::time::Duration::from_secs(main_opt.journald_interval));
+    }
+}
+
+fn spawn_agentx(main_opt: &MainOpt, list: &[DevicePath]) {
+    if !main_opt.agentx { return }
+
+    let pci_list: Vec<_> = list.iter().filter_map(|device_path| device_path.pci).collect();
+
+    AgentX::new(&main_opt.agentx_socket, &main_opt.agentx_oid, &pci_list).spawn_thread();
+}
+
+#[cfg(feature = "json")]
+fn otlp_exporter(main_opt: &MainOpt) -> Option<amdgpu_top_json::OtlpExporter> {
+    let endpoint = main_opt.otlp_endpoint.as_ref()?;
+    let otlp = amdgpu_top_json::OtlpExporter::new(
+        endpoint,
+        std::time::Duration::from_secs(main_opt.otlp_interval),
+    ).unwrap_or_else(|err| {
+        eprintln!("{err}");
+        std::process::exit(1);
+    });
+
+    Some(otlp)
+}
+
+#[cfg(feature = "json")]
+fn http_server(main_opt: &MainOpt) -> Option<amdgpu_top_json::HttpServer> {
+    let addr = main_opt.listen.as_ref()?;
+    let http_server = amdgpu_top_json::HttpServer::bind(
+        addr,
+        amdgpu_top_json::HttpServerConfig::load(),
+    ).unwrap_or_else(|err| listen_error(addr, &err));
+
+    Some(http_server)
+}
+
+#[cfg(feature = "json")]
+fn listen_error(addr: &str, err: &std::io::Error) -> ! {
+    let message = format!("Failed to listen on {addr:?}: {err}");
+
+    match err.kind() {
+        std::io::ErrorKind::AddrInUse => Diagnostic::new(ErrorKind::Io, message)
+            .hint("another process (or amdgpu_top) uses the port, choose another port"),
+        std::io::ErrorKind::PermissionDenied => Diagnostic::new(ErrorKind::PermissionDenied, message)
+            .hint("the ports below 1024 require root, choose a port of 1024 or above"),
+        _ => Diagnostic::new(ErrorKind::Io, message),
+    }.exit()
+}
+
+#[cfg(feature = "json")]
+fn remote_server(main_opt: &MainOpt) -> Option<amdgpu_top_json::RemoteServer> {
+    let addr = main_opt.serve.as_ref()?;
+    let remote = amdgpu_top_json::RemoteServer::bind(
+        addr,
+        amdgpu_top_json::HttpServerConfig::load(),
+    ).unwrap_or_else(|err| listen_error(addr, &err));
+
+    Some(remote)
+}
+
+fn exclude_devices(main_opt: &MainOpt, list: Vec<DevicePath>) -> Vec<DevicePath> {
+    if main_opt.exclude_pci.is_empty() {
+        return list;
+    }
+
+    let exclude: Vec<PCI::BUS_INFO> = main_opt.exclude_pci.iter().map(|pci_path| {
+        pci_path.parse::<PCI::BUS_INFO>().unwrap_or_else(|_| Diagnostic::invalid_pci(pci_path).exit())
+    }).collect();
+
+    list
+        .into_iter()
+        .filter(|device_path| !device_path.pci.is_some_and(|pci| exclude.contains(&pci)))
+        .collect()
+}
+
+fn select_device(main_opt: &MainOpt, list: &[DevicePath]) -> (DevicePath, DeviceHandle) {
+    if main_opt.select_apu {
+        select_apu(list)
+    } else if let Some(name) = &main_opt.device_name {
+        select_by_name(name, list)
+    } else {
+        from_main_opt(main_opt, list)
+    }
+}
+
+/// Devices watched by JSON and SMI mode (and `--output`, `--pid`, ...).
+/// All devices unless a single device is selected without `--select-all`.
+fn watch_list(main_opt: &MainOpt, list: &[DevicePath]) -> Vec<DevicePath> {
+    if main_opt.select_all || !main_opt.has_device_selection() {
+        return list.to_vec();
+    }
+
+    let (device_path, _) = select_device(main_opt, list);
+
+    vec![device_path]
 }
 
 pub fn from_main_opt(main_opt: &MainOpt, list: &[DevicePath]) -> (DevicePath, DeviceHandle) {
     // default
     if main_opt.instance == 0 && main_opt.pci_path.is_none() {
-        return DevicePath::init_with_fallback(main_opt.instance, list);
+        if main_opt.exclude_pci.is_empty() {
+            return DevicePath::init_with_fallback(main_opt.instance, list);
+        }
+
+        // the default device may be excluded
+        let device_path = list[0].clone();
+        let amdgpu_dev = init_device(&device_path);
+
+        return (device_path, amdgpu_dev);
     }
 
     let device_path = if let Some(pci_path) = &main_opt.pci_path {
-        let pci = pci_path.parse::<PCI::BUS_INFO>().unwrap_or_else(|_| {
-            eprintln!("Failed to parse from {pci_path:?} to `PCI::BUS_INFO`");
-            panic!();
-        });
+        let pci = pci_path.parse::<PCI::BUS_INFO>().unwrap_or_else(|_| Diagnostic::invalid_pci(pci_path).exit());
 
         DevicePath::try_from(pci).unwrap_or_else(|err| {
-            eprintln!("{err}");
-            eprintln!("pci_path: {pci_path:?}");
-            eprintln!("Device list: {list:#?}");
-            panic!();
+            Diagnostic::new(ErrorKind::NoDevice, format!("No AMDGPU device at {pci_path:?}: {err}"))
+                .hint(format!("available devices: {}", device_names(list)))
+                .exit()
         })
     } else {
         DevicePath::new(main_opt.instance)
     };
 
-    let amdgpu_dev = device_path.init().unwrap_or_else(|err| {
-        eprintln!("{err}");
-        eprintln!("{:?}", device_path);
-        eprintln!("Device list: {list:#?}");
-        panic!();
-    });
+    let amdgpu_dev = init_device(&device_path);
 
     (device_path, amdgpu_dev)
 }
 
+fn init_device(device_path: &DevicePath) -> DeviceHandle {
+    device_path.init().unwrap_or_else(|err| {
+        Diagnostic::device_init(&err, device_path, err.downcast_ref::<std::io::Error>()).exit()
+    })
+}
+
+/// `#0 (0000:03:00.0), #1 (0000:0c:00.0)`, for the hints
+fn device_names(list: &[DevicePath]) -> String {
+    let names: Vec<String> = list.iter().map(|device_path| {
+        let instance = device_path.get_instance_number().map(|i| format!("#{i}")).unwrap_or_default();
+        let pci = device_path.pci.map(|pci| pci.to_string()).unwrap_or_default();
+
+        format!("{instance} ({pci})")
+    }).collect();
+
+    names.join(", ")
+}
+
 fn select_apu(list: &[DevicePath]) -> (DevicePath, DeviceHandle) {
     use libamdgpu_top::AMDGPU::GPU_INFO;
 
@@ -163,6 +930,29 @@ fn select_apu(list: &[DevicePath]) -> (DevicePath, DeviceHandle) {
         }
     }
 
-    eprintln!("The APU device is not installed or disabled.");
-    panic!();
+    Diagnostic::new(ErrorKind::NoDevice, "The APU device is not installed or disabled.")
+        .hint(format!("available devices: {}", device_names(list)))
+        .hint("some BIOSes disable the iGPU when a dGPU is installed (e.g. \"iGPU Multi-Monitor\" or \"UMA Frame Buffer Size\")")
+        .exit()
+}
+
+fn select_by_name(name: &str, list: &[DevicePath]) -> (DevicePath, DeviceHandle) {
+    use libamdgpu_top::AMDGPU::GPU_INFO;
+
+    let name = name.to_lowercase();
+
+    for device_path in list {
+        let Ok(amdgpu_dev) = device_path.init() else { continue };
+        let Ok(ext_info) = amdgpu_dev.device_info() else { continue };
+        let marketing_name = amdgpu_dev.get_marketing_name_or_default().to_lowercase();
+        let asic_name = ext_info.get_asic_name().to_string().to_lowercase();
+
+        if marketing_name.contains(&name) || asic_name.contains(&name) {
+            return (device_path.clone(), amdgpu_dev);
+        }
+    }
+
+    Diagnostic::new(ErrorKind::NoDevice, format!("No device matches the name {name:?}."))
+        .hint("the name is a substring of the marketing name or the ASIC name, \"amdgpu_top --list\" shows them")
+        .exit()
 }
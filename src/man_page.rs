@@ -0,0 +1,94 @@
+use std::fmt::Write;
+use crate::args::{OPTIONS, SUBCOMMANDS};
+
+// `--generate-man`: the man page of the subcommands and the options, generated from `args::OPTIONS`
+// and `args::SUBCOMMANDS`, so it does not go out of sync with `--help`.
+// `docs/amdgpu_top.1` is the hand-written one, with the key bindings of TUI mode.
+
+const APP_NAME: &str = env!("CARGO_PKG_NAME");
+
+fn escape(s: &str) -> String {
+    let s = s.replace('\\', "\\e").replace('-', "\\-");
+
+    // a line starting with "." or "'" is a request
+    if s.starts_with(['.', '\'']) { format!("\\&{s}") } else { s }
+}
+
+pub fn print() {
+    print!("{}", man_page());
+}
+
+fn man_page() -> String {
+    let mut buf = String::new();
+    let upper = APP_NAME.to_uppercase();
+    let mut description = env!("CARGO_PKG_DESCRIPTION").trim().lines();
+
+    let _ = writeln!(buf, ".TH {upper} 1 \"\" \"{APP_NAME} {}\" \"User Commands\"", env!("CARGO_PKG_VERSION"));
+
+    buf.push_str(".SH NAME\n");
+    let _ = writeln!(buf, "{APP_NAME} \\- {}", escape(description.next().unwrap_or_default()));
+
+    buf.push_str(".SH SYNOPSIS\n");
+    let _ = writeln!(buf, ".B {APP_NAME}\n[\\fIOPTIONS\\fR]\n.br");
+    let _ = writeln!(buf, ".B {APP_NAME}\n\\fISUBCOMMAND\\fR [\\fIARGS\\fR] [\\fIOPTIONS\\fR]\n.br");
+    let _ = writeln!(buf, ".B {APP_NAME}\n[\\fIOPTIONS\\fR] \\-\\- \\fICOMMAND\\fR [\\fIARGS\\fR]");
+
+    buf.push_str(".SH DESCRIPTION\n");
+
+    for line in description {
+        let _ = writeln!(buf, "{}", escape(line));
+    }
+
+    buf.push_str(".PP\n");
+    buf.push_str("\\-\\- \\fICOMMAND\\fR launches the command, monitors the GPU usage of it and its children until it exits,\n");
+    buf.push_str("and prints the summary of the engine busy time, VRAM usage and energy.\n");
+
+    buf.push_str(".SH SUBCOMMANDS\n");
+    buf.push_str("A subcommand selects the mode, and accepts only the common options and its own options.\n");
+
+    for sub in SUBCOMMANDS {
+        let _ = write!(buf, ".TP\n\\fB{}\\fR", escape(sub.name));
+
+        if !sub.args.is_empty() {
+            let _ = write!(buf, " \\fI{}\\fR", escape(sub.args));
+        }
+
+        buf.push('\n');
+
+        for line in sub.about {
+            let _ = writeln!(buf, "{}", escape(line));
+        }
+    }
+
+    for (title, is_flag) in [("FLAGS", true), ("OPTIONS", false)] {
+        let _ = writeln!(buf, ".SH {title}");
+
+        for def in OPTIONS {
+            if def.value.is_none() != is_flag { continue }
+
+            let names: Vec<String> = def.names.iter().map(|name| format!("\\fB{}\\fR", escape(name))).collect();
+            let _ = write!(buf, ".TP\n{}", names.join(", "));
+
+            if let Some(value) = def.value {
+                let _ = write!(buf, " \\fI{}\\fR", escape(value));
+            }
+
+            buf.push('\n');
+
+            for line in def.help {
+                let _ = writeln!(buf, "{}", escape(line));
+            }
+
+            match def.subcommands {
+                None => {},
+                Some([]) => buf.push_str(".br\nWithout a subcommand only.\n"),
+                Some(list) => { let _ = writeln!(buf, ".br\nSubcommands: {}", escape(&list.join(", "))); },
+            }
+        }
+    }
+
+    buf.push_str(".SH SEE ALSO\n");
+    let _ = writeln!(buf, "{}", escape(env!("CARGO_PKG_HOMEPAGE")));
+
+    buf
+}
@@ -0,0 +1,105 @@
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use libamdgpu_top::{DevicePath, SysfsMetric};
+
+/// One-shot output for external collectors (Telegraf `inputs.exec`, `zabbix_sender`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// InfluxDB line protocol
+    Telegraf,
+    /// `zabbix_sender -i -` input (`<host> <key> <value>`)
+    Zabbix,
+    /// Zabbix low-level discovery (LLD) JSON
+    ZabbixDiscovery,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "telegraf" | "influx" => Ok(Self::Telegraf),
+            "zabbix" => Ok(Self::Zabbix),
+            "zabbix-discovery" => Ok(Self::ZabbixDiscovery),
+            _ => Err(format!("invalid output format: {s:?} (telegraf|zabbix|zabbix-discovery)")),
+        }
+    }
+}
+
+struct OutputDevice {
+    instance: u32,
+    pci: String,
+    name: String,
+    metrics: Vec<(SysfsMetric, u64)>,
+}
+
+impl OutputDevice {
+    fn new(device_path: &DevicePath) -> Option<Self> {
+        let pci_bus = device_path.pci?;
+        let instance = device_path.get_instance_number()?;
+        let name = device_path.init()
+            .map(|amdgpu_dev| amdgpu_dev.get_marketing_name_or_default())
+            .unwrap_or_default();
+        let sysfs_path = pci_bus.get_sysfs_path();
+        let hwmon_path = pci_bus.get_hwmon_path()?;
+        let metrics = SysfsMetric::ALL
+            .iter()
+            .filter_map(|m| Some((*m, m.read(&sysfs_path, &hwmon_path)?)))
+            .collect();
+
+        Some(Self { instance, pci: pci_bus.to_string(), name, metrics })
+    }
+}
+
+pub fn print(format: OutputFormat, list: &[DevicePath]) {
+    let devices: Vec<_> = list.iter().filter_map(OutputDevice::new).collect();
+
+    match format {
+        OutputFormat::Telegraf => print_influx_line(&devices),
+        OutputFormat::Zabbix => print_zabbix_sender(&devices),
+        OutputFormat::ZabbixDiscovery => print_zabbix_discovery(&devices),
+    }
+}
+
+// ref: https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/
+fn print_influx_line(devices: &[OutputDevice]) {
+    let ns = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let escape = |s: &str| s.replace(',', "\\,").replace('=', "\\=").replace(' ', "\\ ");
+
+    for dev in devices {
+        if dev.metrics.is_empty() { continue }
+
+        let fields: Vec<String> = dev.metrics.iter().map(|(m, val)| format!("{m}={val}i")).collect();
+
+        println!(
+            "amdgpu,pci={},instance={},name={} {} {ns}",
+            escape(&dev.pci),
+            dev.instance,
+            escape(&dev.name),
+            fields.join(","),
+        );
+    }
+}
+
+// ref: https://www.zabbix.com/documentation/current/en/manpages/zabbix_sender
+// "-" is replaced with the hostname in the configuration file.
+fn print_zabbix_sender(devices: &[OutputDevice]) {
+    for dev in devices {
+        for (m, val) in &dev.metrics {
+            println!("- amdgpu.{m}[\"{}\"] {val}", dev.pci);
+        }
+    }
+}
+
+// ref: https://www.zabbix.com/documentation/current/en/manual/discovery/low_level_discovery
+fn print_zabbix_discovery(devices: &[OutputDevice]) {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let data: Vec<String> = devices.iter().map(|dev| format!(
+        "{{\"{{#GPU_PCI}}\":\"{}\",\"{{#GPU_INSTANCE}}\":\"{}\",\"{{#GPU_NAME}}\":\"{}\"}}",
+        escape(&dev.pci),
+        dev.instance,
+        escape(&dev.name),
+    )).collect();
+
+    println!("{{\"data\":[{}]}}", data.join(","));
+}